@@ -0,0 +1,209 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+#![allow(non_snake_case)]
+
+#[macro_use]
+extern crate criterion;
+extern crate aeonflux;
+extern crate curve25519_dalek;
+extern crate rand;
+
+use criterion::Criterion;
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand::thread_rng;
+
+use aeonflux::amacs;
+use aeonflux::batch::verify_presentations_batch;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::elgamal;
+use aeonflux::issuer::Issuer;
+use aeonflux::nonces::Ephemeral;
+use aeonflux::nonces::Nonces;
+use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::user::User;
+
+const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                      127,   4, 178,  70, 212, 141, 119, 112,
+                      153, 154, 135,  11, 227, 132, 247,  47,
+                       68, 192,  72, 200,  23,  88,  51,  82, ];
+
+/// The numbers of attributes to benchmark aMACs over.
+const MAC_ATTRIBUTE_COUNTS: [usize; 4] = [1, 2, 4, 8];
+
+/// The batch sizes to benchmark presentation verification over.
+const BATCH_SIZES: [usize; 3] = [1, 16, 64];
+
+fn attributes() -> Vec<RevealedAttribute> {
+    let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+    for _ in 0..NUMBER_OF_ATTRIBUTES {
+        attributes.push(Scalar::random(&mut thread_rng()));
+    }
+    attributes
+}
+
+/// An issuer, and a user holding one of its credentials.
+fn issuer_and_user() -> (Issuer, User) {
+    let mut rng = thread_rng();
+    let system_parameters = SystemParameters::from(H);
+    let issuer = Issuer::create(system_parameters, &mut rng);
+    let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+    let issuance = issuer.issue(&user.obtain(attributes()), &mut rng).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+
+    (issuer, user)
+}
+
+mod elgamal_benches {
+    use super::*;
+
+    fn encrypt(c: &mut Criterion) {
+        let mut rng = thread_rng();
+        let keypair = elgamal::Keypair::generate(&mut rng);
+        let message = elgamal::Message::from(&Scalar::random(&mut rng));
+        let nonce = Ephemeral::new(&mut rng);
+
+        c.bench_function("elgamal encrypt", move |b| {
+            b.iter(|| keypair.public.encrypt(&message, &nonce))
+        });
+    }
+
+    fn decrypt(c: &mut Criterion) {
+        let mut rng = thread_rng();
+        let keypair = elgamal::Keypair::generate(&mut rng);
+        let message = elgamal::Message::from(&Scalar::random(&mut rng));
+        let encryption = keypair.public.encrypt(&message, &Ephemeral::new(&mut rng));
+
+        c.bench_function("elgamal decrypt", move |b| {
+            b.iter(|| keypair.secret.decrypt(&encryption))
+        });
+    }
+
+    criterion_group!{
+        name = elgamal_benches;
+        config = Criterion::default();
+        targets =
+            encrypt,
+            decrypt,
+    }
+}
+
+mod amacs_benches {
+    use super::*;
+
+    fn mac(c: &mut Criterion) {
+        c.bench_function_over_inputs("amacs mac", |b, &&n| {
+            let mut rng = thread_rng();
+            let key = amacs::SecretKey::new(n, &mut rng);
+            let message: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+
+            b.iter(|| key.mac_scalars(&message, &mut rng))
+        }, MAC_ATTRIBUTE_COUNTS.iter());
+    }
+
+    fn verify(c: &mut Criterion) {
+        c.bench_function_over_inputs("amacs verify", |b, &&n| {
+            let mut rng = thread_rng();
+            let key = amacs::SecretKey::new(n, &mut rng);
+            let message: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+            let tag = key.mac_scalars(&message, &mut rng).unwrap();
+
+            b.iter(|| key.verify_scalars(&tag, &message))
+        }, MAC_ATTRIBUTE_COUNTS.iter());
+    }
+
+    criterion_group!{
+        name = amacs_benches;
+        config = Criterion::default();
+        targets =
+            mac,
+            verify,
+    }
+}
+
+mod credential_benches {
+    use super::*;
+
+    fn issue(c: &mut Criterion) {
+        let (issuer, user) = issuer_and_user();
+        let request: CredentialRequest = user.obtain(attributes());
+
+        c.bench_function("credential issuance", move |b| {
+            let mut rng = thread_rng();
+
+            b.iter(|| issuer.issue(&request, &mut rng))
+        });
+    }
+
+    fn obtain_finish(c: &mut Criterion) {
+        let (issuer, mut user) = issuer_and_user();
+        let issuance = issuer.issue(&user.obtain(attributes()), &mut thread_rng()).unwrap();
+
+        c.bench_function("credential issuance verification", move |b| {
+            b.iter(|| user.obtain_finish(Some(&issuance)))
+        });
+    }
+
+    fn show(c: &mut Criterion) {
+        let (_issuer, user) = issuer_and_user();
+
+        c.bench_function("credential presentation", move |b| {
+            let mut rng = thread_rng();
+            let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+
+            b.iter(|| user.show(&nonces, &mut rng))
+        });
+    }
+
+    fn verify(c: &mut Criterion) {
+        let (issuer, user) = issuer_and_user();
+        let mut rng = thread_rng();
+        let presentation = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        c.bench_function("credential verification", move |b| {
+            b.iter(|| issuer.verify(&presentation))
+        });
+    }
+
+    fn verify_batch(c: &mut Criterion) {
+        c.bench_function_over_inputs("credential batch verification", |b, &&n| {
+            let (issuer, user) = issuer_and_user();
+            let mut rng = thread_rng();
+            let presentations: Vec<CredentialPresentation> = (0..n)
+                .map(|_| user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap())
+                .collect();
+
+            b.iter(|| verify_presentations_batch(&issuer, &presentations))
+        }, BATCH_SIZES.iter());
+    }
+
+    criterion_group!{
+        name = credential_benches;
+        config = Criterion::default();
+        targets =
+            issue,
+            obtain_finish,
+            show,
+            verify,
+            verify_batch,
+    }
+}
+
+criterion_main!(
+    elgamal_benches::elgamal_benches,
+    amacs_benches::amacs_benches,
+    credential_benches::credential_benches,
+);
@@ -0,0 +1,40 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary bytes as credentials and presentations, in every
+//! encoding a verifier may be handed, checking that whatever decodes
+//! re-encodes to the same value.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+
+use aeonflux::credential::Credential;
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::credential::PresentationRef;
+use aeonflux::credential::VerifiedCredential;
+use aeonflux::heapless::HeaplessPresentation;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(presentation) = CredentialPresentation::from_bytes(data) {
+        let encoding = presentation.to_bytes();
+
+        assert!(CredentialPresentation::from_bytes(&encoding).unwrap() == presentation);
+    }
+    let _ = PresentationRef::from_bytes(data);
+    let _ = HeaplessPresentation::from_bytes(data);
+    let _ = Credential::from_bytes(data);
+    let _ = CredentialRequest::from_bytes(data);
+    let _ = CredentialIssuance::from_bytes(data);
+    let _ = VerifiedCredential::from_bytes(data);
+});
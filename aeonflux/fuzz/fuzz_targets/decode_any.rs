@@ -0,0 +1,30 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary payloads as every wire type, the first byte choosing
+//! which.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+
+use aeonflux::envelope::decode_any;
+use aeonflux::envelope::DEFAULT_SUITE;
+use aeonflux::envelope::WireKind;
+
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    if let Ok(kind) = WireKind::from_byte(data[0]) {
+        let _ = decode_any(kind, DEFAULT_SUITE, &data[1..]);
+    }
+});
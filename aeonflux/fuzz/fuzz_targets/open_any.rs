@@ -0,0 +1,22 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Open arbitrary envelopes, headers included.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+
+use aeonflux::envelope::open_any;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = open_any(data);
+});
@@ -0,0 +1,33 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary bytes as system and issuer parameters, and as the keys
+//! from which issuers and verifiers are loaded.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+
+use aeonflux::amacs;
+use aeonflux::elgamal;
+use aeonflux::heapless::HeaplessVerifier;
+use aeonflux::issuer::Issuer;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::parameters::SystemParameters;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SystemParameters::from_bytes(data);
+    let _ = IssuerParameters::from_bytes(data);
+    let _ = Issuer::from_bytes(data);
+    let _ = HeaplessVerifier::from_bytes(data);
+    let _ = amacs::SecretKey::from_bytes(data);
+    let _ = elgamal::PublicKey::from_bytes(data);
+});
@@ -0,0 +1,26 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary bytes as the proofs which have decoders of their own.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate aeonflux;
+
+use aeonflux::predicates::RangeProof;
+use aeonflux::proofs::valid_credential;
+use aeonflux::voprf;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = valid_credential::Proof::from_bytes(data);
+    let _ = voprf::Proof::from_bytes(data);
+    let _ = RangeProof::from_bytes(data);
+});
@@ -0,0 +1,420 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! An optional cache for verifiers which see the same credentials presented
+//! repeatedly, such as a regular member's at a daily gate.
+//!
+//! An `AcceleratedPresentation` carries, alongside a presentation of the
+//! holder's credential, a `ScopedPseudonym`, \\( T = m \cdot H_s \\), where
+//! \\( m \\) is the hidden attribute and \\( H_s \\) is derived from the
+//! verifier's scope, with a short proof that it was computed from the
+//! presented attribute.  An `AcceleratedVerifier` checks the presentation in
+//! full the first time it sees a pseudonym, and remembers when.  Until its
+//! `window` has passed, later presentations with the same pseudonym need only
+//! prove knowledge of the attribute behind it, and the slower proof that the
+//! credential is valid is skipped.
+//!
+//! # Privacy
+//!
+//! The pseudonym is deterministic per attribute and scope, so presentations
+//! to the same scope are linkable to one another, though not to the
+//! attribute or to presentations in any other scope.  The cache holds
+//! nothing but pseudonyms and the times they were last fully verified, and
+//! forgets each after its window.  Verifiers which wish to bound linkability
+//! may include the date, or some other epoch, in their scope.
+//!
+//! # Note
+//!
+//! Within the window, a credential which has since expired or been revoked
+//! is still accepted from a holder who knows its attribute.  The window
+//! should be no longer than the verifier is willing to accept that for.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeMap;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::SIZEOF_CREDENTIAL_PRESENTATION;
+use credential::Credential;
+use credential::CredentialPresentation;
+use credential::VerifiedCredential;
+use errors::CredentialError;
+use errors::Error;
+use issuer::Issuer;
+use nonces::Nonces;
+use nullifier::encode_scope;
+use nullifier::hash_scope_to_group;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use proofs::scoped_nullifier;
+use user::User;
+
+pub const SIZEOF_SCOPED_PSEUDONYM: usize = 32;
+
+/// The domain separator for the scopes of pseudonyms.
+const PSEUDONYM_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux scoped pseudonym v2";
+
+/// The encoding of a verifier's `scope`, whose basepoint \\( H_s \\) for
+/// pseudonyms is hashed from it as in `nullifier::scoped_nullifier()`.
+///
+/// # Errors
+///
+/// * `Error::Misuse` if the `scope` is longer than 65535 bytes.
+pub fn pseudonym_scope(scope: &[u8]) -> Result<Vec<u8>, Error> {
+    encode_scope(&[PSEUDONYM_DOMAIN_SEPARATOR, scope])
+}
+
+/// A tag which is unique per hidden attribute and scope.
+///
+/// This is kept in its compressed form, so that it may be ordered and stored
+/// cheaply.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct ScopedPseudonym(pub [u8; SIZEOF_SCOPED_PSEUDONYM]);
+
+impl ScopedPseudonym {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ScopedPseudonym, CredentialError> {
+        if bytes.len() != SIZEOF_SCOPED_PSEUDONYM {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; SIZEOF_SCOPED_PSEUDONYM];
+
+        tmp.copy_from_slice(bytes);
+
+        // Check that the pseudonym is a valid point before accepting it.
+        CompressedRistretto(tmp).decompress()?;
+
+        Ok(ScopedPseudonym(tmp))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ScopedPseudonym, "A valid byte sequence representing a ScopedPseudonym");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ScopedPseudonym);
+impl_try_from_bytes!(ScopedPseudonym, CredentialError);
+
+impl From<RistrettoPoint> for ScopedPseudonym {
+    fn from(source: RistrettoPoint) -> ScopedPseudonym {
+        ScopedPseudonym(source.compress().to_bytes())
+    }
+}
+
+/// A presentation of a credential along with its pseudonym in a scope.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AcceleratedPresentation {
+    /// A presentation of the holder's credential.
+    pub presentation: CredentialPresentation,
+    /// The pseudonym for the hidden attribute in the verifier's scope.
+    pub pseudonym: ScopedPseudonym,
+    /// A proof that the `pseudonym` was computed from the presented attribute.
+    pub proof: scoped_nullifier::Proof,
+}
+
+impl AcceleratedPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<AcceleratedPresentation, CredentialError> {
+        let offset: usize = SIZEOF_CREDENTIAL_PRESENTATION + SIZEOF_SCOPED_PSEUDONYM;
+
+        if bytes.len() < offset {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let presentation = CredentialPresentation::from_bytes(&bytes[..SIZEOF_CREDENTIAL_PRESENTATION])?;
+        let pseudonym = ScopedPseudonym::from_bytes(&bytes[SIZEOF_CREDENTIAL_PRESENTATION..offset])?;
+
+        let proof: scoped_nullifier::Proof = match deserialize(&bytes[offset..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(CredentialError::MissingData),
+        };
+
+        Ok(AcceleratedPresentation { presentation, pseudonym, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CREDENTIAL_PRESENTATION + SIZEOF_SCOPED_PSEUDONYM);
+
+        v.extend(self.presentation.to_bytes());
+        v.extend(self.pseudonym.to_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(AcceleratedPresentation,
+                                         "A valid byte sequence representing an AcceleratedPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(AcceleratedPresentation);
+impl_try_from_bytes!(AcceleratedPresentation, CredentialError);
+
+impl AcceleratedPresentation {
+    /// Present the `user`'s credential, with its pseudonym in a `scope`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if the `user` has no credential.
+    /// * `Error::Misuse` if the `scope` is longer than 65535 bytes.
+    pub fn create<R>(user: &User, scope: &[u8], rng: &mut R) -> Result<AcceleratedPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &Credential = match user.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+
+        let mut transcript = Transcript::new(b"AEONFLUX ACCELERATION");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+        let presentation: CredentialPresentation = user.show(&nonces, &mut csprng)?;
+
+        let Hs: RistrettoPoint = hash_scope_to_group(&pseudonym_scope(scope)?);
+        let T: RistrettoPoint = &Hs * &credential.attributes[0];
+
+        let proof = {
+            let secrets = scoped_nullifier::Secrets {
+                m0: &credential.attributes[0],
+                z0: nonces[0].as_witness(),
+            };
+            let publics = scoped_nullifier::Publics {
+                A: &user.system_parameters.h,
+                P: &presentation.rerandomized_nonce,
+                Hn: &Hs,
+                Cm0: &presentation.attributes_blinded[0].into(),
+                N: &T,
+            };
+
+            scoped_nullifier::Proof::create(&mut transcript, publics, secrets)
+        };
+
+        Ok(AcceleratedPresentation { presentation, pseudonym: ScopedPseudonym::from(T), proof })
+    }
+}
+
+/// The configuration of an `AcceleratedVerifier`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccelerationConfig {
+    /// A public string identifying the verifier, e.g. `b"north gate"`.
+    pub scope: Vec<u8>,
+    /// The number of seconds after a full verification for which a
+    /// pseudonym's presentations are accepted without one.
+    pub window: u64,
+    /// The most pseudonyms to remember at once.  When the cache is full,
+    /// new pseudonyms are verified in full but not remembered.
+    pub capacity: usize,
+}
+
+/// How an `AcceleratedVerifier` accepted a presentation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Acceleration {
+    /// The presentation was verified in full, and its pseudonym remembered
+    /// if there was room.
+    Verified(VerifiedCredential),
+    /// The pseudonym was fully verified at `verified_at`, within the window,
+    /// so only knowledge of its attribute was checked.
+    Cached { verified_at: u64 },
+}
+
+/// A verifier which remembers the pseudonyms it has recently verified in
+/// full.
+pub struct AcceleratedVerifier {
+    config: AccelerationConfig,
+    issuer: Issuer,
+    basepoint: RistrettoPoint,
+    cache: BTreeMap<ScopedPseudonym, u64>,
+}
+
+impl AcceleratedVerifier {
+    /// # Errors
+    ///
+    /// * `Error::Misuse` if the `config`'s scope is longer than 65535 bytes.
+    pub fn new(config: AccelerationConfig, issuer: Issuer) -> Result<AcceleratedVerifier, Error> {
+        let basepoint: RistrettoPoint = hash_scope_to_group(&pseudonym_scope(&config.scope)?);
+
+        Ok(AcceleratedVerifier { config, issuer, basepoint, cache: BTreeMap::new() })
+    }
+
+    pub fn config(&self) -> &AccelerationConfig {
+        &self.config
+    }
+
+    pub fn issuer(&self) -> &Issuer {
+        &self.issuer
+    }
+
+    /// The number of pseudonyms currently remembered.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Forget the pseudonyms whose windows have passed by `now`.
+    pub fn prune(&mut self, now: u64) {
+        let window: u64 = self.config.window;
+
+        self.cache.retain(|_, verified_at| now < verified_at.saturating_add(window));
+    }
+
+    /// Forget every pseudonym, e.g. because the issuer's key was rotated or
+    /// a credential was revoked.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Verify a `presentation` at time `now`, skipping the credential proof
+    /// if its pseudonym was fully verified within the window.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if the presentation does
+    ///   not blind exactly one attribute.
+    /// * `CredentialError::VerificationFailure` if the pseudonym proof did not
+    ///   verify.
+    /// * Any error from `Issuer::verify()`, when the presentation is verified
+    ///   in full.
+    pub fn verify(
+        &mut self,
+        presentation: &AcceleratedPresentation,
+        now: u64,
+    ) -> Result<Acceleration, Error>
+    {
+        self.prune(now);
+
+        if presentation.presentation.attributes_blinded.len() != 1 {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let T: RistrettoPoint = CompressedRistretto(presentation.pseudonym.0).decompress()?;
+
+        let mut transcript = Transcript::new(b"AEONFLUX ACCELERATION");
+        let publics = scoped_nullifier::Publics {
+            A: &self.issuer.system_parameters.h,
+            P: &presentation.presentation.rerandomized_nonce,
+            Hn: &self.basepoint,
+            Cm0: &presentation.presentation.attributes_blinded[0].into(),
+            N: &T,
+        };
+
+        if presentation.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::VerificationFailure.into());
+        }
+        if let Some(verified_at) = self.cache.get(&presentation.pseudonym) {
+            return Ok(Acceleration::Cached { verified_at: *verified_at });
+        }
+
+        let verified: VerifiedCredential = self.issuer.verify(&presentation.presentation)?;
+
+        if self.cache.len() < self.config.capacity {
+            self.cache.insert(presentation.pseudonym, now);
+        }
+
+        Ok(Acceleration::Verified(verified))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    const NOW: u64 = 1_538_000_000;
+    const DAY: u64 = 86400;
+
+    fn config() -> AccelerationConfig {
+        AccelerationConfig { scope: b"test gate".to_vec(), window: DAY, capacity: 16 }
+    }
+
+    fn setup() -> (AcceleratedVerifier, User) {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let verifier = AcceleratedVerifier::new(config(), issuer).unwrap();
+        let mut user: User = User::new(system_parameters, verifier.issuer().get_issuer_parameters(), None);
+        let issuance = verifier.issuer().issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        (verifier, user)
+    }
+
+    #[test]
+    fn repeated_presentations_are_cached_within_the_window() {
+        let mut rng = thread_rng();
+        let (mut verifier, user) = setup();
+
+        let first = AcceleratedPresentation::create(&user, b"test gate", &mut rng).unwrap();
+        let first = AcceleratedPresentation::from_bytes(&first.to_bytes()).unwrap();
+        let second = AcceleratedPresentation::create(&user, b"test gate", &mut rng).unwrap();
+
+        assert!(first.pseudonym == second.pseudonym);
+        assert!(first.presentation != second.presentation);
+
+        match verifier.verify(&first, NOW).unwrap() {
+            Acceleration::Verified(_) => (),
+            Acceleration::Cached { .. } => panic!(),
+        }
+        assert!(verifier.verify(&second, NOW + 60) == Ok(Acceleration::Cached { verified_at: NOW }));
+
+        // After the window, the presentation is verified in full again.
+        match verifier.verify(&second, NOW + DAY).unwrap() {
+            Acceleration::Verified(_) => (),
+            Acceleration::Cached { .. } => panic!(),
+        }
+        assert!(verifier.len() == 1);
+    }
+
+    #[test]
+    fn pseudonyms_are_scoped_and_proven() {
+        let mut rng = thread_rng();
+        let (mut verifier, user) = setup();
+
+        let here = AcceleratedPresentation::create(&user, b"test gate", &mut rng).unwrap();
+        let elsewhere = AcceleratedPresentation::create(&user, b"other gate", &mut rng).unwrap();
+
+        assert!(here.pseudonym != elsewhere.pseudonym);
+        assert!(verifier.verify(&elsewhere, NOW) == Err(CredentialError::VerificationFailure.into()));
+        assert!(verifier.verify(&here, NOW).is_ok());
+
+        // A cached pseudonym is only accepted with a proof of its attribute.
+        let mut forged = AcceleratedPresentation::create(&user, b"test gate", &mut rng).unwrap();
+
+        forged.proof = elsewhere.proof.clone();
+
+        assert!(verifier.verify(&forged, NOW) == Err(CredentialError::VerificationFailure.into()));
+
+        verifier.clear();
+
+        assert!(verifier.len() == 0);
+    }
+}
@@ -31,19 +31,22 @@ use std::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::string::String;
 
+#[cfg(feature = "std")]
+use std::fmt;
 #[cfg(feature = "std")]
 use std::ops::{Index, Mul};
 
+#[cfg(not(feature = "std"))]
+use core::fmt;
 #[cfg(not(feature = "std"))]
 use core::ops::{Index, Mul};
 
-use clear_on_drop::clear::Clear;
-
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::traits::Identity;
 
 use rand_core::RngCore;
 use rand_core::CryptoRng;
@@ -51,14 +54,20 @@ use rand_core::CryptoRng;
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
-use sha2::Sha512;
-
+use errors::Error;
 use errors::MacError;
+use hashing::Hasher;
 
 use parameters::NUMBER_OF_ATTRIBUTES;
+use secret::Clear;
+use secret::REDACTED;
+use secret::Secret;
 
 pub const SIZEOF_TAG: usize = 64;
 
+/// The domain separator used when hashing a string to a `Message`.
+const MESSAGE_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux message v1";
+
 /// A `Message` is a vector of `Scalar`s in \( \mathbb{Z}/\mathbb{Z}\ell \).
 #[derive(Clone, Debug)]
 #[repr(C)]
@@ -77,8 +86,10 @@ pub struct Message(pub Vec<Scalar>);
 impl From<String> for Message {
     fn from(source: String) -> Message {
         let mut v = Vec::new();
+        let mut hash = Hasher::new(MESSAGE_DOMAIN_SEPARATOR);
 
-        v.push(Scalar::hash_from_bytes::<Sha512>(source.as_bytes()));
+        hash.input(source.as_bytes());
+        v.push(hash.finalize_scalar());
 
         Message( v )
     }
@@ -124,8 +135,8 @@ pub struct Tag {
 
 impl Tag {
     pub fn from_bytes(bytes: &[u8]) -> Result<Tag, MacError> {
-        if bytes.len() != 64 {
-            return Err(MacError::PointDecompressionError);
+        if bytes.len() != SIZEOF_TAG {
+            return Err(MacError::MessageLengthError{ length: SIZEOF_TAG });
         }
 
         let mut P_bytes: [u8; 32] = [0u8; 32];
@@ -154,6 +165,9 @@ impl Tag {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(Tag, "A valid byte sequence representing an amacs::Tag");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Tag);
+impl_try_from_bytes!(Tag, MacError);
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(non_snake_case)]
@@ -200,15 +214,26 @@ impl PublicKey {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(PublicKey, "A valid byte sequence representing an amacs::PublicKey");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PublicKey);
+impl_try_from_bytes!(PublicKey, MacError);
 
 /// A secret key for authenticating and verifying `Tag`s.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 #[repr(C)]
 pub struct SecretKey {
     pub x0: Scalar,
     pub xn: Vec<Scalar>,
 }
 
+/// The scalars are redacted, since they're public fields for the sake of the
+/// proofs and so can't be kept in a `Secret`.
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "amacs::SecretKey {{ x0: {}, xn: {} }}", REDACTED, REDACTED)
+    }
+}
+
 /// Overwrite secret key material with null bytes when it goes out of scope.
 impl Drop for SecretKey {
     fn drop(&mut self) {
@@ -240,8 +265,8 @@ impl SecretKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, MacError> {
         let length: usize = bytes.len();
 
-        // The bytes must be a multiple of 32.
-        if length % 32 != 0 {
+        // The bytes must be a non-zero multiple of 32.
+        if length % 32 != 0 || length == 0 {
             return Err(MacError::MessageLengthError{ length });
         }
         let mut x0: Option<Scalar> = None;
@@ -301,45 +326,77 @@ impl SecretKey {
         PublicKey { Xn }
     }
 
-    pub fn mac<R>(&self, message: &Message, csprng: &mut R) -> Result<Tag, MacError>
+    pub fn mac<R>(&self, message: &Message, csprng: &mut R) -> Result<Tag, Error>
     where
         R: RngCore + CryptoRng,
     {
-        if self.xn.len() != message.0.len() {
-            return Err(MacError::MessageLengthError{ length: self.xn.len() });
+        self.mac_scalars(&message.0, csprng)
+    }
+
+    /// As `SecretKey::mac()`, but over a borrowed slice of scalars, so that
+    /// callers need not allocate a `Message`.
+    pub fn mac_scalars<R>(&self, message: &[Scalar], csprng: &mut R) -> Result<Tag, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if self.xn.len() != message.len() {
+            return Err(MacError::MessageLengthError{ length: self.xn.len() }.into());
         }
 
         let nonce: RistrettoPoint = &Scalar::random(csprng) * &RISTRETTO_BASEPOINT_TABLE;
         let mut exponent: Scalar = self.x0;
 
-        for (xi, mi) in self.xn.iter().zip(message.0.iter()) {
+        for (xi, mi) in self.xn.iter().zip(message.iter()) {
             exponent += xi * mi;
         }
         let mac = nonce * exponent;
 
+        exponent.clear();
+
         Ok(Tag { nonce: nonce, mac: mac })
     }
 
-    pub fn verify(&self, mac: &Tag, message: &Message) -> Result<(), MacError> {
-        if mac.nonce == RISTRETTO_BASEPOINT_POINT {
-            return Err(MacError::AuthenticationError);
+    pub fn verify(&self, mac: &Tag, message: &Message) -> Result<(), Error> {
+        self.verify_scalars(mac, &message.0)
+    }
+
+    /// As `SecretKey::verify()`, but over a borrowed slice of scalars.
+    ///
+    /// # Errors
+    ///
+    /// * `MacError::MessageLengthError` if the `message` is not as long as
+    ///   this key, since a shorter one would be checked against a prefix of
+    ///   it.
+    /// * `MacError::AuthenticationError` if the `mac` does not verify, or its
+    ///   nonce is the identity, to which every key gives a valid MAC.
+    pub fn verify_scalars(&self, mac: &Tag, message: &[Scalar]) -> Result<(), Error> {
+        if self.xn.len() != message.len() {
+            return Err(MacError::MessageLengthError{ length: self.xn.len() }.into());
+        }
+        if mac.nonce == RISTRETTO_BASEPOINT_POINT || mac.nonce == RistrettoPoint::identity() {
+            return Err(MacError::AuthenticationError.into());
         }
         let mut exponent = self.x0;
 
-        for (xi, mi) in self.xn.iter().zip(message.0.iter()) {
+        for (xi, mi) in self.xn.iter().zip(message.iter()) {
             exponent = (xi * mi) + exponent;
         }
         let check: RistrettoPoint = mac.nonce * exponent;
 
+        exponent.clear();
+
         if mac.mac == check {
             Ok(())
         } else {
-            Err(MacError::AuthenticationError)
+            Err(MacError::AuthenticationError.into())
         }
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(SecretKey, "A valid byte sequence representing an amacs::SecretKey");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SecretKey);
+impl_try_from_bytes!(SecretKey, MacError);
 
 #[derive(Clone, Debug)]
 #[repr(C)]
@@ -352,6 +409,10 @@ impl Keypair {
     pub fn from_bytes(bytes: &[u8]) -> Result<Keypair, MacError> {
         let length: usize = bytes.len();
 
+        if length < 32 {
+            return Err(MacError::KeypairDeserialisation);
+        }
+
         // The public key must always be 32 bytes shorter since the secret key has the extra x0 element.
         let public_key_length: usize = (length - 32) / 2;
         let secret_key_length: usize = length - public_key_length;
@@ -381,6 +442,9 @@ impl Keypair {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(Keypair, "A valid byte sequence representing an amacs::Keypair");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Keypair);
+impl_try_from_bytes!(Keypair, MacError);
 
 impl Keypair {
     pub fn new<R>(h: &RistrettoPoint, csprng: &mut R) -> Keypair
@@ -397,15 +461,15 @@ impl Keypair {
 /// A type for generating secret nonces for aMAC rerandomisation and then
 /// clearing them from memory.
 #[derive(Clone, Debug, Default)]
-pub struct Rerandomization(pub(crate) Scalar);
+pub struct Rerandomization(pub(crate) Secret<Scalar>);
 
 impl<'a, 'b> Mul<&'a Rerandomization> for &'b Tag {
     type Output = Tag;
 
     fn mul(self, other: &'a Rerandomization) -> Tag {
         Tag {
-            nonce: other.0 * self.nonce,
-            mac:   other.0 * self.mac,
+            nonce: other.0.expose_secret() * self.nonce,
+            mac:   other.0.expose_secret() * self.mac,
         }
     }
 }
@@ -415,8 +479,8 @@ impl<'a, 'b> Mul<&'b Tag> for &'a Rerandomization {
 
     fn mul(self, other: &'b Tag) -> Tag {
         Tag {
-            nonce: self.0 * other.nonce,
-            mac:   self.0 * other.mac,
+            nonce: self.0.expose_secret() * other.nonce,
+            mac:   self.0.expose_secret() * other.mac,
         }
     }
 }
@@ -426,7 +490,7 @@ impl Rerandomization {
     where
         R: RngCore + CryptoRng,
     {
-        Rerandomization(Scalar::random(csprng))
+        Rerandomization(Secret::new(Scalar::random(csprng)))
     }
 
     pub fn apply_to_tag(&self, tag: &Tag) -> Tag {
@@ -485,4 +549,34 @@ mod test {
         assert!(key.verify(&rerandomised, &m1).is_ok());
         assert!(key.verify(&rerandomised, &m2).is_err());
     }
+
+    #[test]
+    fn identity_nonces_are_rejected() {
+        let mut csprng = thread_rng();
+        let key = SecretKey::new(2, &mut csprng);
+        let message = [Scalar::random(&mut csprng), Scalar::random(&mut csprng)];
+        let identity = RistrettoPoint::identity();
+
+        // Every key gives the identity a valid MAC over every message.
+        assert!(key.verify_scalars(&Tag { nonce: identity, mac: identity }, &message) ==
+                Err(MacError::AuthenticationError.into()));
+    }
+
+    #[test]
+    fn messages_must_match_the_key_length() {
+        let mut csprng = thread_rng();
+        let key = SecretKey::new(2, &mut csprng);
+        let message = [Scalar::random(&mut csprng), Scalar::random(&mut csprng)];
+        let tag = key.mac_scalars(&message, &mut csprng).unwrap();
+
+        // A tag over a prefix of the key, i.e. with the last attribute zero,
+        // must not verify for the shorter message.
+        let prefix = key.mac_scalars(&[message[0], Scalar::zero()], &mut csprng).unwrap();
+
+        assert!(key.verify_scalars(&tag, &message).is_ok());
+        assert!(key.verify_scalars(&prefix, &message[..1]) ==
+                Err(MacError::MessageLengthError{ length: 2 }.into()));
+        assert!(key.verify_scalars(&tag, &[message[0], message[1], message[0]]) ==
+                Err(MacError::MessageLengthError{ length: 2 }.into()));
+    }
 }
@@ -0,0 +1,356 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Asynchronous issuance and presentation.
+//!
+//! The state machines in `gate` neither block nor perform any I/O, and so
+//! remain the way to drive a session by hand.  This module adds two traits
+//! for the parts of a session which may need to wait on the network:
+//!
+//! * an `AsyncIssuer` issues and verifies credentials, e.g. by forwarding
+//!   requests to a remote service or a key held elsewhere.  An `Issuer` is
+//!   trivially one, and `respond` answers a client's `GateMessage` with any
+//!   of them.
+//! * an `AsyncGateClient` carries a `GateClient`'s messages to a gate and
+//!   awaits its answers.  `GateClient::obtain_async`, `blind_obtain_async`,
+//!   and `present_async` drive a whole session over one, returning a future
+//!   which resolves to the client's new state.
+//!
+//! This module is only available with the `async` feature.
+
+use std::boxed::Box;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec::Vec;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use credential::RevealedAttribute;
+use credential::VerifiedCredential;
+use errors::Error;
+use errors::GateError;
+use gate::ClientState;
+use gate::GateClient;
+use gate::GateMessage;
+use issuer::Issuer;
+use rng::default_rng;
+
+/// An issuer whose operations complete asynchronously.
+pub trait AsyncIssuer {
+    type Issue: Future<Output = Result<CredentialIssuance, Error>>;
+    type BlindIssue: Future<Output = Result<CredentialBlindIssuance, Error>>;
+    type Verify: Future<Output = Result<VerifiedCredential, Error>>;
+
+    /// Issue a credential for a `request`, as `Issuer::issue` does.
+    fn issue(&self, request: &CredentialRequest) -> Self::Issue;
+
+    /// Issue a credential for a blinded `request`, as `Issuer::blind_issue`
+    /// does.
+    fn blind_issue(&self, request: &CredentialBlindRequest) -> Self::BlindIssue;
+
+    /// Verify a `presentation`, as `Issuer::verify` does.
+    fn verify(&self, presentation: &CredentialPresentation) -> Self::Verify;
+}
+
+/// A local `Issuer` completes every operation immediately, using the
+/// default generator.
+impl AsyncIssuer for Issuer {
+    type Issue = Ready<Result<CredentialIssuance, Error>>;
+    type BlindIssue = Ready<Result<CredentialBlindIssuance, Error>>;
+    type Verify = Ready<Result<VerifiedCredential, Error>>;
+
+    fn issue(&self, request: &CredentialRequest) -> Self::Issue {
+        ready(Issuer::issue(self, request, &mut default_rng()))
+    }
+
+    fn blind_issue(&self, request: &CredentialBlindRequest) -> Self::BlindIssue {
+        ready(Issuer::blind_issue(self, request, &mut default_rng()))
+    }
+
+    fn verify(&self, presentation: &CredentialPresentation) -> Self::Verify {
+        ready(Issuer::verify(self, presentation))
+    }
+}
+
+/// The future returned by `respond`.
+pub enum Respond<I: AsyncIssuer> {
+    Issue(Pin<Box<I::Issue>>),
+    BlindIssue(Pin<Box<I::BlindIssue>>),
+    Verify(Pin<Box<I::Verify>>),
+    Done(Option<GateError>),
+}
+
+impl<I: AsyncIssuer> Future for Respond<I> {
+    type Output = Result<GateMessage, Error>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let response: Result<GateMessage, Error> = match *self.get_mut() {
+            Respond::Issue(ref mut x) => match x.as_mut().poll(context) {
+                Poll::Pending      => return Poll::Pending,
+                Poll::Ready(Ok(y)) => Ok(GateMessage::Issuance(y)),
+                Poll::Ready(Err(y)) => Err(y),
+            },
+            Respond::BlindIssue(ref mut x) => match x.as_mut().poll(context) {
+                Poll::Pending      => return Poll::Pending,
+                Poll::Ready(Ok(y)) => Ok(GateMessage::BlindIssuance(y)),
+                Poll::Ready(Err(y)) => Err(y),
+            },
+            // As with `GateServer`, a rejected presentation is answered
+            // rather than treated as an error.
+            Respond::Verify(ref mut x) => match x.as_mut().poll(context) {
+                Poll::Pending      => return Poll::Pending,
+                Poll::Ready(Ok(_)) => Ok(GateMessage::Verdict(true)),
+                Poll::Ready(Err(_)) => Ok(GateMessage::Verdict(false)),
+            },
+            Respond::Done(ref mut error) => match error.take() {
+                Some(x) => Err(x.into()),
+                None    => panic!("Respond polled after completion"),
+            },
+        };
+
+        Poll::Ready(response)
+    }
+}
+
+/// Answer a client's request `message` with an `issuer`.
+///
+/// This is the asynchronous counterpart of `GateServer::handle`, without the
+/// session state: servers needing timeouts should keep their own.
+///
+/// # Errors
+///
+/// * `GateError::UnexpectedMessage` if the `message` was not a client request.
+/// * Any error from the `issuer` if a request could not be fulfilled.
+pub fn respond<I: AsyncIssuer>(issuer: &I, message: &GateMessage) -> Respond<I> {
+    match *message {
+        GateMessage::IssuanceRequest(ref x)      => Respond::Issue(Box::pin(issuer.issue(x))),
+        GateMessage::BlindIssuanceRequest(ref x) => Respond::BlindIssue(Box::pin(issuer.blind_issue(x))),
+        GateMessage::Presentation(ref x)         => Respond::Verify(Box::pin(issuer.verify(x))),
+        _                                        => Respond::Done(Some(GateError::UnexpectedMessage)),
+    }
+}
+
+/// A transport carrying a `GateClient`'s messages to a gate.
+pub trait AsyncGateClient {
+    type Exchange: Future<Output = Result<GateMessage, GateError>>;
+
+    /// Send a `message` to the gate and await its answer.
+    fn exchange(&mut self, message: GateMessage) -> Self::Exchange;
+
+    /// The current time, in the same units as the `GateClient`'s timeout.
+    fn now(&self) -> u64;
+}
+
+/// A session driven over an `AsyncGateClient`, resolving to the client's
+/// new state.
+pub struct Session<'a, T: AsyncGateClient + 'a> {
+    client: &'a mut GateClient,
+    transport: &'a mut T,
+    exchange: Result<Pin<Box<T::Exchange>>, Option<Error>>,
+}
+
+impl<'a, T: AsyncGateClient + 'a> Session<'a, T> {
+    fn start(
+        client: &'a mut GateClient,
+        transport: &'a mut T,
+        message: Result<GateMessage, Error>,
+    ) -> Session<'a, T>
+    {
+        let exchange = match message {
+            Ok(x)  => Ok(Box::pin(transport.exchange(x))),
+            Err(x) => Err(Some(x)),
+        };
+
+        Session { client, transport, exchange }
+    }
+}
+
+impl<'a, T: AsyncGateClient + 'a> Future for Session<'a, T> {
+    type Output = Result<ClientState, Error>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let answer: Result<GateMessage, GateError> = match this.exchange {
+            Ok(ref mut x) => match x.as_mut().poll(context) {
+                Poll::Pending  => return Poll::Pending,
+                Poll::Ready(y) => y,
+            },
+            Err(ref mut error) => match error.take() {
+                Some(x) => return Poll::Ready(Err(x)),
+                None    => panic!("Session polled after completion"),
+            },
+        };
+
+        this.exchange = Err(None);
+
+        Poll::Ready(match answer {
+            Ok(message) => this.client.handle(&message, this.transport.now()),
+            Err(x)      => Err(this.client.fail(x).into()),
+        })
+    }
+}
+
+impl GateClient {
+    /// Obtain a credential over revealed `attributes` through a `transport`.
+    ///
+    /// # Errors
+    ///
+    /// Any error from `start_issuance`, from the `transport`, or from
+    /// `handle`.  Upon an error from the `transport`, the client moves to
+    /// `ClientState::Failed`.
+    pub fn obtain_async<'a, T>(
+        &'a mut self,
+        transport: &'a mut T,
+        attributes: Vec<RevealedAttribute>,
+    ) -> Session<'a, T>
+    where
+        T: AsyncGateClient,
+    {
+        let message = self.start_issuance(attributes, transport.now());
+
+        Session::start(self, transport, message)
+    }
+
+    /// Obtain a credential over encrypted `attributes` through a `transport`.
+    ///
+    /// # Errors
+    ///
+    /// As for `obtain_async`, with `start_blind_issuance`.
+    pub fn blind_obtain_async<'a, T, R>(
+        &'a mut self,
+        transport: &'a mut T,
+        attributes: Vec<RevealedAttribute>,
+        rng: &mut R,
+    ) -> Session<'a, T>
+    where
+        T: AsyncGateClient,
+        R: RngCore + CryptoRng,
+    {
+        let message = self.start_blind_issuance(attributes, transport.now(), rng);
+
+        Session::start(self, transport, message)
+    }
+
+    /// Present our credential through a `transport`.
+    ///
+    /// # Errors
+    ///
+    /// As for `obtain_async`, with `start_presentation`.
+    pub fn present_async<'a, T, R>(
+        &'a mut self,
+        transport: &'a mut T,
+        rng: &mut R,
+    ) -> Session<'a, T>
+    where
+        T: AsyncGateClient,
+        R: RngCore + CryptoRng,
+    {
+        let message = self.start_presentation(transport.now(), rng);
+
+        Session::start(self, transport, message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use gate::GateConfig;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use user::User;
+
+    use rand::thread_rng;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        fn noop(_: *const ()) { }
+        fn clone(_: *const ()) -> RawWaker { RawWaker::new(0 as *const (), &VTABLE) }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let waker = unsafe { Waker::from_raw(RawWaker::new(0 as *const (), &VTABLE)) };
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+
+        loop {
+            if let Poll::Ready(x) = future.as_mut().poll(&mut context) {
+                return x;
+            }
+        }
+    }
+
+    /// A transport which hands every message straight to an issuer, taking
+    /// `latency` seconds to do so.
+    struct Loopback<'a> {
+        issuer: &'a Issuer,
+        now: u64,
+        latency: u64,
+    }
+
+    impl<'a> AsyncGateClient for Loopback<'a> {
+        type Exchange = Ready<Result<GateMessage, GateError>>;
+
+        fn exchange(&mut self, message: GateMessage) -> Self::Exchange {
+            self.now += self.latency;
+
+            ready(block_on(respond(self.issuer, &message)).map_err(GateError::Credential))
+        }
+
+        fn now(&self) -> u64 {
+            self.now
+        }
+    }
+
+    #[test]
+    fn obtain_and_present_async() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut client = GateClient::new(user, GateConfig::default());
+        let mut transport = Loopback { issuer: &issuer, now: 0, latency: 1 };
+
+        let state = block_on(client.obtain_async(&mut transport, vec![Scalar::random(&mut rng)]));
+
+        assert!(state == Ok(ClientState::Holding));
+
+        let state = block_on(client.present_async(&mut transport, &mut rng));
+
+        assert!(state == Ok(ClientState::Admitted));
+
+        // The synchronous state machine is untouched by the asynchronous one.
+        assert!(client.start_issuance(vec![Scalar::random(&mut rng)], 0).err() == Some(GateError::WrongState.into()));
+    }
+
+    #[test]
+    fn slow_transports_time_out() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut client = GateClient::new(user, GateConfig::default());
+        let mut transport = Loopback { issuer: &issuer, now: 0, latency: 2 * GateConfig::default().timeout };
+
+        let state = block_on(client.obtain_async(&mut transport, vec![Scalar::random(&mut rng)]));
+
+        assert!(state == Err(GateError::Timeout.into()));
+        assert!(client.state() == ClientState::Failed(GateError::Timeout));
+        assert!(block_on(respond(&issuer, &GateMessage::Verdict(true))) == Err(GateError::UnexpectedMessage.into()));
+    }
+}
@@ -0,0 +1,262 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Injective encodings of common attribute types as scalars.
+//!
+//! Every attribute of a credential is a `Scalar`, and a credential is only as
+//! sound as the map from an integrator's values into scalars: if two values
+//! encode to the same scalar, e.g. because a string was truncated or reduced
+//! modulo the group order, a holder of one may present it as the other.  The
+//! encoders here are injective, reject values they cannot represent rather
+//! than wrapping or truncating them, and each has a decoder which rejects
+//! any scalar it did not produce:
+//!
+//! * integers, as themselves, so that `predicates` may prove ranges over
+//!   them;
+//! * timestamps, as seconds since the UNIX epoch, up to the end of the year
+//!   9999, and so also comparable with `predicates`;
+//! * UUIDs, as their sixteen bytes;
+//! * short strings, as up to `MAXIMUM_SHORT_STRING` bytes of UTF-8 together
+//!   with their length, so that no string collides with another ending in
+//!   null bytes; and
+//! * ISO 3166-1 alpha-2 country codes, case-insensitively, as small
+//!   integers.
+//!
+//! An encoding says nothing of its type, so a verifier must know from the
+//! credential's schema which encoding each attribute uses.
+
+#[cfg(not(feature = "std"))]
+use core::str;
+#[cfg(feature = "std")]
+use std::str;
+
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+#[cfg(feature = "std")]
+use std::time::UNIX_EPOCH;
+
+use curve25519_dalek::scalar::Scalar;
+
+use errors::AttributeError;
+use errors::Error;
+
+/// The latest encodable timestamp, `9999-12-31T23:59:59Z`.
+pub const MAXIMUM_TIMESTAMP: u64 = 253_402_300_799;
+
+/// The longest string, in bytes, which may be encoded.
+pub const MAXIMUM_SHORT_STRING: usize = 30;
+
+/// The scalar whose little-endian encoding is `bytes`, which must be below
+/// the group order.
+fn scalar_from_low_bytes(bytes: &[u8]) -> Scalar {
+    let mut encoded = [0u8; 32];
+
+    encoded[..bytes.len()].copy_from_slice(bytes);
+    Scalar::from_bits(encoded)
+}
+
+/// The low `length` bytes of a `scalar`, if all of its other bytes are zero.
+fn low_bytes(scalar: &Scalar, length: usize) -> Result<&[u8], AttributeError> {
+    let bytes: &[u8; 32] = scalar.as_bytes();
+
+    if bytes[length..].iter().any(|b| *b != 0) {
+        return Err(AttributeError::NotAnEncoding);
+    }
+    Ok(&bytes[..length])
+}
+
+/// Encode an integer.
+pub fn encode_u64(x: u64) -> Scalar {
+    Scalar::from(x)
+}
+
+/// Decode an integer encoded with `encode_u64`.
+pub fn decode_u64(scalar: &Scalar) -> Result<u64, Error> {
+    let bytes = low_bytes(scalar, 8)?;
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    Ok(x)
+}
+
+/// Encode a timestamp, in `seconds` since the UNIX epoch.
+///
+/// # Errors
+///
+/// * `AttributeError::OutOfRange` if the timestamp is after
+///   `MAXIMUM_TIMESTAMP`.
+pub fn encode_timestamp(seconds: u64) -> Result<Scalar, Error> {
+    if seconds > MAXIMUM_TIMESTAMP {
+        return Err(AttributeError::OutOfRange.into());
+    }
+    Ok(encode_u64(seconds))
+}
+
+/// Decode a timestamp encoded with `encode_timestamp`, in seconds since the
+/// UNIX epoch.
+pub fn decode_timestamp(scalar: &Scalar) -> Result<u64, Error> {
+    let seconds = decode_u64(scalar)?;
+
+    if seconds > MAXIMUM_TIMESTAMP {
+        return Err(AttributeError::NotAnEncoding.into());
+    }
+    Ok(seconds)
+}
+
+/// Encode a `time`, to the second, as by `encode_timestamp`.
+///
+/// # Errors
+///
+/// * `AttributeError::OutOfRange` if the `time` is before the UNIX epoch or
+///   after `MAXIMUM_TIMESTAMP`.
+#[cfg(feature = "std")]
+pub fn encode_system_time(time: SystemTime) -> Result<Scalar, Error> {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => encode_timestamp(since.as_secs()),
+        Err(_)    => Err(AttributeError::OutOfRange.into()),
+    }
+}
+
+/// Encode a UUID, given as its sixteen bytes in the order they are written.
+pub fn encode_uuid(uuid: &[u8; 16]) -> Scalar {
+    scalar_from_low_bytes(&uuid[..])
+}
+
+/// Decode a UUID encoded with `encode_uuid`.
+pub fn decode_uuid(scalar: &Scalar) -> Result<[u8; 16], Error> {
+    let mut uuid = [0u8; 16];
+
+    uuid.copy_from_slice(low_bytes(scalar, 16)?);
+    Ok(uuid)
+}
+
+/// Encode a string of at most `MAXIMUM_SHORT_STRING` bytes.
+///
+/// The string's bytes are followed by its length, so that e.g. `"a"` and
+/// `"a\0"` are encoded differently.
+///
+/// # Errors
+///
+/// * `AttributeError::TooLong` if the string is longer than
+///   `MAXIMUM_SHORT_STRING` bytes.
+pub fn encode_short_string(string: &str) -> Result<Scalar, Error> {
+    let bytes: &[u8] = string.as_bytes();
+
+    if bytes.len() > MAXIMUM_SHORT_STRING {
+        return Err(AttributeError::TooLong.into());
+    }
+
+    let mut encoded = [0u8; MAXIMUM_SHORT_STRING + 1];
+
+    encoded[..bytes.len()].copy_from_slice(bytes);
+    encoded[MAXIMUM_SHORT_STRING] = bytes.len() as u8;
+
+    Ok(scalar_from_low_bytes(&encoded[..]))
+}
+
+/// A string decoded with `decode_short_string`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ShortString {
+    bytes: [u8; MAXIMUM_SHORT_STRING],
+    length: usize,
+}
+
+impl ShortString {
+    pub fn as_str(&self) -> &str {
+        // The bytes were checked to be UTF-8 when decoded.
+        str::from_utf8(&self.bytes[..self.length]).unwrap()
+    }
+}
+
+/// Decode a string encoded with `encode_short_string`.
+pub fn decode_short_string(scalar: &Scalar) -> Result<ShortString, Error> {
+    let encoded = low_bytes(scalar, MAXIMUM_SHORT_STRING + 1)?;
+    let length: usize = encoded[MAXIMUM_SHORT_STRING] as usize;
+
+    if length > MAXIMUM_SHORT_STRING || encoded[length..MAXIMUM_SHORT_STRING].iter().any(|b| *b != 0) {
+        return Err(AttributeError::NotAnEncoding.into());
+    }
+    if str::from_utf8(&encoded[..length]).is_err() {
+        return Err(AttributeError::NotAnEncoding.into());
+    }
+
+    let mut bytes = [0u8; MAXIMUM_SHORT_STRING];
+
+    bytes.copy_from_slice(&encoded[..MAXIMUM_SHORT_STRING]);
+
+    Ok(ShortString { bytes, length })
+}
+
+/// Encode an ISO 3166-1 alpha-2 country code, e.g. `"NZ"`, in either case,
+/// as an integer below 676.
+///
+/// Whether the code is assigned is not checked.
+///
+/// # Errors
+///
+/// * `AttributeError::InvalidCountryCode` if the code is not two ASCII
+///   letters.
+pub fn encode_country_code(code: &str) -> Result<Scalar, Error> {
+    let bytes: &[u8] = code.as_bytes();
+
+    if bytes.len() != 2 || !bytes.iter().all(|b| b.is_ascii_alphabetic()) {
+        return Err(AttributeError::InvalidCountryCode.into());
+    }
+
+    let first: u64 = (bytes[0].to_ascii_uppercase() - b'A') as u64;
+    let second: u64 = (bytes[1].to_ascii_uppercase() - b'A') as u64;
+
+    Ok(encode_u64(26 * first + second))
+}
+
+/// Decode a country code encoded with `encode_country_code`, as two
+/// uppercase ASCII letters.
+pub fn decode_country_code(scalar: &Scalar) -> Result<[u8; 2], Error> {
+    let x = decode_u64(scalar)?;
+
+    if x >= 26 * 26 {
+        return Err(AttributeError::NotAnEncoding.into());
+    }
+    Ok([b'A' + (x / 26) as u8, b'A' + (x % 26) as u8])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encodings_round_trip() {
+        assert!(decode_u64(&encode_u64(u64::max_value())) == Ok(u64::max_value()));
+        assert!(decode_timestamp(&encode_timestamp(1546300800).unwrap()) == Ok(1546300800));
+        assert!(decode_uuid(&encode_uuid(&[0xff; 16])) == Ok([0xff; 16]));
+        assert!(decode_short_string(&encode_short_string("κόσμε").unwrap()).unwrap().as_str() == "κόσμε");
+        assert!(decode_short_string(&encode_short_string("").unwrap()).unwrap().as_str() == "");
+        assert!(decode_country_code(&encode_country_code("nz").unwrap()) == Ok(*b"NZ"));
+        assert!(encode_country_code("NZ") == encode_country_code("nZ"));
+    }
+
+    #[test]
+    fn unrepresentable_values_are_rejected() {
+        assert!(encode_timestamp(MAXIMUM_TIMESTAMP + 1) == Err(AttributeError::OutOfRange.into()));
+        assert!(encode_short_string("this string is thirty-one bytes") == Err(AttributeError::TooLong.into()));
+        assert!(encode_country_code("NZL") == Err(AttributeError::InvalidCountryCode.into()));
+        assert!(encode_country_code("N1") == Err(AttributeError::InvalidCountryCode.into()));
+
+        // Strings differing only in trailing null bytes are distinct.
+        assert!(encode_short_string("a") != encode_short_string("a\0"));
+
+        // Scalars which no encoder produced do not decode.
+        assert!(decode_u64(&(Scalar::from(u64::max_value()) + Scalar::one())) == Err(AttributeError::NotAnEncoding.into()));
+        assert!(decode_country_code(&encode_u64(676)) == Err(AttributeError::NotAnEncoding.into()));
+        assert!(decode_short_string(&encode_uuid(&[0x61; 16])) == Err(AttributeError::NotAnEncoding.into()));
+        assert!(decode_timestamp(&encode_u64(MAXIMUM_TIMESTAMP + 1)) == Err(AttributeError::NotAnEncoding.into()));
+    }
+}
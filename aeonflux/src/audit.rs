@@ -0,0 +1,366 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! An optional, hash-chained log of a gate's verification decisions.
+//!
+//! Each `AuditEntry` records a digest of the presentation which was shown, a
+//! digest of the policy it was checked against, the `AuditOutcome`, and the
+//! coarse time bucket in which the decision was made.  Every entry is hashed
+//! together with the hash of the entry before it, so that publishing the
+//! latest `AuditLog::head()` commits the gate to its entire history, and an
+//! auditor given an exported log can check that nothing was removed,
+//! reordered, or altered.
+//!
+//! Since presentations are rerandomised on every showing, their digests do
+//! not link a user's entries to one another, and times are rounded down to a
+//! bucket so that entries cannot be correlated with other logs by their
+//! precise timing.  Nothing else about the user is recorded.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use errors::AuditError;
+use errors::Error;
+use hashing::Hasher;
+use hashing::SIZEOF_HASHED_KEY;
+
+pub const SIZEOF_AUDIT_DIGEST: usize = SIZEOF_HASHED_KEY;
+
+/// The size of an exported `AuditEntry`.
+pub const SIZEOF_AUDIT_ENTRY: usize = 2 * SIZEOF_AUDIT_DIGEST + 1 + 8;
+
+/// The size of the header of an exported `AuditLog`.
+const SIZEOF_AUDIT_LOG_HEADER: usize = 8 + 8;
+
+/// A digest, used for the presentations, policies, and chain hashes in an
+/// audit log.
+pub type AuditDigest = [u8; SIZEOF_AUDIT_DIGEST];
+
+/// The chain hash which precedes the first entry in a log.
+pub const GENESIS: AuditDigest = [0u8; SIZEOF_AUDIT_DIGEST];
+
+/// Hash some `input` under a `domain` separator to produce an `AuditDigest`.
+fn digest(domain: &'static [u8], input: &[u8]) -> AuditDigest {
+    let mut hash = Hasher::new(domain);
+
+    hash.input(input);
+    hash.finalize_key()
+}
+
+/// Compute the digest of an encoded presentation, for recording in a log.
+pub fn presentation_digest(presentation: &[u8]) -> AuditDigest {
+    digest(b"aeonflux audit presentation v1", presentation)
+}
+
+/// Compute the digest of a policy, such as the encoding of a `TrustPolicy`
+/// or `PassPredicate`, for recording in a log.
+pub fn policy_digest(policy: &[u8]) -> AuditDigest {
+    digest(b"aeonflux audit policy v1", policy)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+fn push_u64(v: &mut Vec<u8>, x: u64) {
+    for i in 0..8 {
+        v.push((x >> (8 * i)) as u8);
+    }
+}
+
+/// The outcome of a gate's decision.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum AuditOutcome {
+    Accepted = 0,
+    Rejected = 1,
+}
+
+impl AuditOutcome {
+    pub fn from_byte(byte: u8) -> Result<AuditOutcome, AuditError> {
+        match byte {
+            0 => Ok(AuditOutcome::Accepted),
+            1 => Ok(AuditOutcome::Rejected),
+            _ => Err(AuditError::UnknownOutcome),
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl<'a, T, E> From<&'a Result<T, E>> for AuditOutcome {
+    fn from(source: &'a Result<T, E>) -> AuditOutcome {
+        match *source {
+            Ok(_)  => AuditOutcome::Accepted,
+            Err(_) => AuditOutcome::Rejected,
+        }
+    }
+}
+
+/// A single decision in an `AuditLog`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct AuditEntry {
+    pub presentation: AuditDigest,
+    pub policy: AuditDigest,
+    pub outcome: AuditOutcome,
+    /// The time of the decision, divided by the log's bucket size.
+    pub time_bucket: u64,
+}
+
+impl AuditEntry {
+    pub fn from_bytes(bytes: &[u8]) -> Result<AuditEntry, AuditError> {
+        if bytes.len() != SIZEOF_AUDIT_ENTRY {
+            return Err(AuditError::WrongNumberOfBytes);
+        }
+
+        let mut presentation: AuditDigest = [0u8; SIZEOF_AUDIT_DIGEST];
+        let mut policy: AuditDigest = [0u8; SIZEOF_AUDIT_DIGEST];
+
+        presentation.copy_from_slice(&bytes[00..32]);
+        policy.copy_from_slice(&bytes[32..64]);
+
+        let outcome = AuditOutcome::from_byte(bytes[64])?;
+        let time_bucket = read_u64(&bytes[65..73]);
+
+        Ok(AuditEntry { presentation, policy, outcome, time_bucket })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_AUDIT_ENTRY);
+
+        v.extend(self.presentation.iter());
+        v.extend(self.policy.iter());
+        v.push(self.outcome.to_byte());
+        push_u64(&mut v, self.time_bucket);
+        v
+    }
+
+    /// Compute the chain hash of this entry, given that of the `previous` one.
+    pub fn chain(&self, previous: &AuditDigest) -> AuditDigest {
+        let mut input: Vec<u8> = Vec::with_capacity(SIZEOF_AUDIT_DIGEST + SIZEOF_AUDIT_ENTRY);
+
+        input.extend(previous.iter());
+        input.extend(self.to_bytes());
+
+        digest(b"aeonflux audit chain v1", &input)
+    }
+}
+
+impl_try_from_bytes!(AuditEntry, AuditError);
+
+/// A verifier's hash-chained log of decisions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditLog {
+    /// The number of seconds in each time bucket.
+    bucket_size: u64,
+    entries: Vec<AuditEntry>,
+    head: AuditDigest,
+}
+
+impl AuditLog {
+    /// Create a new, empty log, rounding times down to multiples of
+    /// `bucket_size` seconds.  A `bucket_size` of zero is treated as one.
+    pub fn new(bucket_size: u64) -> AuditLog {
+        AuditLog {
+            bucket_size: if bucket_size == 0 { 1 } else { bucket_size },
+            entries: Vec::new(),
+            head: GENESIS,
+        }
+    }
+
+    /// The chain hash of the latest entry, or `GENESIS` if the log is empty.
+    ///
+    /// Operators should periodically publish this, so that the log cannot
+    /// later be rewritten without detection.
+    pub fn head(&self) -> AuditDigest {
+        self.head
+    }
+
+    pub fn bucket_size(&self) -> u64 {
+        self.bucket_size
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Record a decision about an encoded `presentation`, checked against a
+    /// `policy` at time `now`.
+    ///
+    /// # Errors
+    ///
+    /// * `AuditError::NonMonotonicTime` if `now` is in an earlier time bucket
+    ///   than the latest entry.
+    ///
+    /// # Returns
+    ///
+    /// The new `head()` of the log.
+    pub fn record(
+        &mut self,
+        presentation: &[u8],
+        policy: &[u8],
+        outcome: AuditOutcome,
+        now: u64,
+    ) -> Result<AuditDigest, Error>
+    {
+        let entry = AuditEntry {
+            presentation: presentation_digest(presentation),
+            policy: policy_digest(policy),
+            outcome: outcome,
+            time_bucket: now / self.bucket_size,
+        };
+
+        self.push(entry)?;
+
+        Ok(self.head)
+    }
+
+    fn push(&mut self, entry: AuditEntry) -> Result<(), AuditError> {
+        if let Some(latest) = self.entries.last() {
+            if entry.time_bucket < latest.time_bucket {
+                return Err(AuditError::NonMonotonicTime);
+            }
+        }
+        self.head = entry.chain(&self.head);
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Export this log for an auditor.
+    pub fn export(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_AUDIT_LOG_HEADER + self.len() * SIZEOF_AUDIT_ENTRY);
+
+        push_u64(&mut v, self.bucket_size);
+        push_u64(&mut v, self.entries.len() as u64);
+
+        for entry in self.entries.iter() {
+            v.extend(entry.to_bytes());
+        }
+        v
+    }
+
+    /// Rebuild, and check the consistency of, an exported log.
+    ///
+    /// # Inputs
+    ///
+    /// * `bytes` is a log produced by `AuditLog::export()`.
+    /// * `head` is, optionally, a previously published `head()`.  If given,
+    ///   the rebuilt log must end with it.
+    ///
+    /// # Errors
+    ///
+    /// * `AuditError::WrongNumberOfBytes` if the export is truncated.
+    /// * `AuditError::NonMonotonicTime` if the entries are out of order.
+    /// * `AuditError::HeadMismatch` if the log does not end in `head`.
+    pub fn verify_export(bytes: &[u8], head: Option<&AuditDigest>) -> Result<AuditLog, Error> {
+        if bytes.len() < SIZEOF_AUDIT_LOG_HEADER {
+            return Err(AuditError::WrongNumberOfBytes.into());
+        }
+
+        let bucket_size: u64 = read_u64(&bytes[0..8]);
+        let count: u64 = read_u64(&bytes[8..16]);
+        let body: &[u8] = &bytes[SIZEOF_AUDIT_LOG_HEADER..];
+
+        if bucket_size == 0 || body.len() % SIZEOF_AUDIT_ENTRY != 0 ||
+           (body.len() / SIZEOF_AUDIT_ENTRY) as u64 != count
+        {
+            return Err(AuditError::WrongNumberOfBytes.into());
+        }
+
+        let mut log = AuditLog::new(bucket_size);
+
+        for chunk in body.chunks(SIZEOF_AUDIT_ENTRY) {
+            log.push(AuditEntry::from_bytes(chunk)?)?;
+        }
+
+        if let Some(expected) = head {
+            if log.head != *expected {
+                return Err(AuditError::HeadMismatch.into());
+            }
+        }
+        Ok(log)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn log() -> AuditLog {
+        let mut log = AuditLog::new(3600);
+
+        log.record(b"presentation one", b"tier >= 2", AuditOutcome::Accepted, 1546300800).unwrap();
+        log.record(b"presentation two", b"tier >= 2", AuditOutcome::Rejected, 1546300900).unwrap();
+        log.record(b"presentation three", b"tier >= 3", AuditOutcome::Accepted, 1546308000).unwrap();
+        log
+    }
+
+    #[test]
+    fn export_and_verify() {
+        let log = log();
+        let head = log.head();
+        let verified = AuditLog::verify_export(&log.export(), Some(&head)).unwrap();
+
+        assert!(verified == log);
+        assert!(verified.entries()[0].time_bucket == verified.entries()[1].time_bucket);
+        assert!(head != GENESIS);
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let log = log();
+        let head = log.head();
+        let mut exported = log.export();
+
+        // Flip an entry's outcome.
+        exported[SIZEOF_AUDIT_LOG_HEADER + SIZEOF_AUDIT_ENTRY + 64] = AuditOutcome::Accepted.to_byte();
+
+        assert!(AuditLog::verify_export(&exported, Some(&head)) == Err(AuditError::HeadMismatch.into()));
+
+        // Drop the last entry.
+        let mut truncated = log.export();
+        let length = truncated.len() - SIZEOF_AUDIT_ENTRY;
+
+        truncated.truncate(length);
+
+        assert!(AuditLog::verify_export(&truncated, Some(&head)) == Err(AuditError::WrongNumberOfBytes.into()));
+    }
+
+    #[test]
+    fn time_must_not_go_backwards() {
+        let mut log = log();
+
+        assert!(log.record(b"presentation four", b"tier >= 2", AuditOutcome::Accepted, 1546300800) ==
+                Err(AuditError::NonMonotonicTime.into()));
+        assert!(log.len() == 3);
+    }
+
+    #[test]
+    fn outcome_from_result() {
+        let accepted: Result<(), ()> = Ok(());
+        let rejected: Result<(), ()> = Err(());
+
+        assert!(AuditOutcome::from(&accepted) == AuditOutcome::Accepted);
+        assert!(AuditOutcome::from(&rejected) == AuditOutcome::Rejected);
+    }
+}
@@ -0,0 +1,390 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Threshold backups of an issuer's secrets, held by several custodians.
+//!
+//! `Issuer::backup()` seals the issuer's key material, together with its
+//! `revocation::RevocationRegistry` and `non_revocation::Accumulator`, if it
+//! keeps them, under a fresh key, and shares that key to \\( n \\)
+//! custodians' elGamal keys exactly as a wallet's `recovery::RecoverySecret`
+//! is shared to its guardians.  The resulting `Backup` is not secret, and
+//! may be copied to each custodian and to offline storage.
+//!
+//! To restore, any \\( t \\) custodians decrypt their shares with
+//! `recovery::Sharing::decrypt_share()`, and `Backup::restore()` combines
+//! them, rederives the sealing key, and authenticates and opens the sealed
+//! state.  The threshold must be at least two, so that no single custodian
+//! can restore the issuer alone, and a custodian who lies about their share
+//! is caught by its decryption proof and skipped.
+//!
+//! # Warning
+//!
+//! The state is sealed with ChaCha20-Poly1305, under a nonce chosen afresh
+//! for each backup, and with the sharing as associated data.  A backup
+//! reveals the length of the registry state, and a registry's rollback
+//! history is not kept.
+//!
+//! This module is only available with the `backup` feature.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use chacha20poly1305::Key;
+use chacha20poly1305::KeyInit;
+use chacha20poly1305::Nonce;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::aead::Payload;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use derivation::DerivationKey;
+use derivation::Purpose;
+use elgamal;
+use encoding::u64_to_bytes;
+use errors::BackupError;
+use errors::Error;
+use hashing::Hasher;
+use hashing::SIZEOF_HASHED_KEY;
+use issuer::Issuer;
+use non_revocation::Accumulator;
+use recovery::DecryptedShare;
+use recovery::RecoverySecret;
+use recovery::Sharing;
+use revocation::RevocationRegistry;
+use secret::Clear;
+use secret::Secret;
+
+/// The length of a backup's ChaCha20-Poly1305 nonce.
+pub const SIZEOF_BACKUP_NONCE: usize = 12;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// The key sealing a backup, derived from the root its custodians recover.
+fn sealing_key(root: &DerivationKey) -> Secret<[u8; SIZEOF_HASHED_KEY]> {
+    let ephemeral = root.purpose(Purpose::Backup).ephemeral(0);
+    let mut h = Hasher::new(b"aeonflux backup key v1");
+
+    h.input(ephemeral.as_scalar().as_bytes());
+
+    Secret::new(h.finalize_key())
+}
+
+/// Append a length-prefixed `item`, or a zero length for none.
+fn push_item(v: &mut Vec<u8>, item: Option<&[u8]>) {
+    match item {
+        Some(x) => {
+            v.push(1);
+            v.extend(u64_to_bytes(x.len() as u64).iter());
+            v.extend(x.iter());
+        },
+        None => v.push(0),
+    }
+}
+
+/// Read an item written by `push_item()`, returning it and the rest.
+fn read_item(bytes: &[u8]) -> Result<(Option<&[u8]>, &[u8]), BackupError> {
+    match bytes.first() {
+        Some(&0) => Ok((None, &bytes[1..])),
+        Some(&1) if bytes.len() >= 9 => {
+            let length: usize = read_u64(&bytes[1..9]) as usize;
+
+            if bytes.len() - 9 < length {
+                return Err(BackupError::WrongNumberOfBytes);
+            }
+            Ok((Some(&bytes[9..9 + length]), &bytes[9 + length..]))
+        },
+        _ => Err(BackupError::WrongNumberOfBytes),
+    }
+}
+
+/// An issuer's secrets and registry state, sealed under a key shared to
+/// some custodians.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Backup {
+    /// The sharing of the sealing key, from which each custodian decrypts
+    /// their share.
+    pub sharing: Sharing,
+    pub nonce: [u8; SIZEOF_BACKUP_NONCE],
+    /// The sealed state, followed by its Poly1305 tag.
+    pub sealed: Vec<u8>,
+}
+
+impl Backup {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Backup, BackupError> {
+        if bytes.len() < 8 {
+            return Err(BackupError::WrongNumberOfBytes);
+        }
+
+        let length: usize = read_u64(&bytes[00..08]) as usize;
+
+        if bytes.len() - 8 < length || bytes.len() - 8 - length < SIZEOF_BACKUP_NONCE {
+            return Err(BackupError::WrongNumberOfBytes);
+        }
+
+        let (sharing_bytes, rest) = bytes[8..].split_at(length);
+        let sharing = Sharing::from_bytes(sharing_bytes)?;
+        let mut nonce = [0u8; SIZEOF_BACKUP_NONCE];
+
+        nonce.copy_from_slice(&rest[..SIZEOF_BACKUP_NONCE]);
+
+        Ok(Backup { sharing, nonce, sealed: rest[SIZEOF_BACKUP_NONCE..].to_vec() })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let sharing = self.sharing.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(8 + sharing.len() + SIZEOF_BACKUP_NONCE + self.sealed.len());
+
+        v.extend(u64_to_bytes(sharing.len() as u64).iter());
+        v.extend(sharing);
+        v.extend(self.nonce.iter());
+        v.extend(self.sealed.iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Backup, "A valid byte sequence representing a Backup");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Backup);
+impl_try_from_bytes!(Backup, BackupError);
+
+/// The state restored from a `Backup`.
+pub struct Restored {
+    pub issuer: Issuer,
+    /// The issuer's revocation registry, if one was backed up.
+    pub revocation: Option<RevocationRegistry>,
+    /// The issuer's non-revocation accumulator, if one was backed up.
+    pub accumulator: Option<Accumulator>,
+}
+
+impl Issuer {
+    /// Back up this issuer's secrets, with its `revocation` registry and
+    /// `accumulator`, if it keeps them, to some `custodians`, any `threshold`
+    /// of whom may later restore them.
+    ///
+    /// # Errors
+    ///
+    /// * `BackupError::InvalidThreshold` if `threshold` is less than two or
+    ///   greater than the number of custodians, or if there are more than
+    ///   `recovery::MAXIMUM_GUARDIANS`.
+    pub fn backup<R>(
+        &self,
+        revocation: Option<&RevocationRegistry>,
+        accumulator: Option<&Accumulator>,
+        custodians: &[elgamal::PublicKey],
+        threshold: usize,
+        csprng: &mut R,
+    ) -> Result<Backup, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if threshold < 2 {
+            return Err(BackupError::InvalidThreshold.into());
+        }
+
+        let secret = RecoverySecret::generate(csprng);
+        let sharing = match secret.share(&self.system_parameters, custodians, threshold, csprng) {
+            Ok(x)  => x,
+            Err(_) => return Err(BackupError::InvalidThreshold.into()),
+        };
+        let mut key = sealing_key(&secret.derivation_key());
+        let mut nonce = [0u8; SIZEOF_BACKUP_NONCE];
+
+        csprng.fill_bytes(&mut nonce);
+
+        let mut issuer = Secret::new(self.to_bytes());
+        let mut accumulator: Option<Secret<Vec<u8>>> = accumulator.map(|x| Secret::new(x.to_bytes()));
+        let revocation: Option<Vec<u8>> = revocation.map(|x| x.to_bytes());
+        let mut plaintext: Secret<Vec<u8>> = Secret::new(Vec::new());
+
+        push_item(plaintext.expose_secret_mut(), Some(issuer.expose_secret()));
+        push_item(plaintext.expose_secret_mut(), revocation.as_ref().map(|x| &x[..]));
+        push_item(plaintext.expose_secret_mut(), accumulator.as_ref().map(|x| &x.expose_secret()[..]));
+
+        let sharing_bytes: Vec<u8> = sharing.to_bytes();
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key.expose_secret()));
+        let sealed = cipher.encrypt(Nonce::from_slice(&nonce),
+                                    Payload { msg: plaintext.expose_secret(), aad: &sharing_bytes });
+
+        key.clear();
+        issuer.expose_secret_mut()[..].clear();
+        plaintext.expose_secret_mut()[..].clear();
+
+        if let Some(ref mut x) = accumulator {
+            x.expose_secret_mut()[..].clear();
+        }
+
+        match sealed {
+            Ok(sealed) => Ok(Backup { sharing, nonce, sealed }),
+            Err(_)     => Err(BackupError::WrongNumberOfBytes.into()),
+        }
+    }
+}
+
+impl Backup {
+    /// Restore the issuer and its registry state from some custodians'
+    /// `decrypted` shares of this backup's sealing key.
+    ///
+    /// As in `recovery::Sharing::recover()`, shares which do not verify are
+    /// skipped.
+    ///
+    /// # Errors
+    ///
+    /// * `BackupError::Recovery(RecoveryError::NotEnoughShares)` if fewer
+    ///   than the threshold of distinct, valid shares were given.
+    /// * `BackupError::Corrupt` if the sealed state did not authenticate.
+    pub fn restore(&self, decrypted: &[DecryptedShare]) -> Result<Restored, Error> {
+        let root = self.sharing.recover(decrypted)?;
+        let mut key = sealing_key(&root);
+
+        let sharing_bytes: Vec<u8> = self.sharing.to_bytes();
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key.expose_secret()));
+        let opened = cipher.decrypt(Nonce::from_slice(&self.nonce),
+                                    Payload { msg: &self.sealed, aad: &sharing_bytes });
+
+        key.clear();
+
+        let mut opened: Secret<Vec<u8>> = match opened {
+            Ok(x)  => Secret::new(x),
+            Err(_) => return Err(BackupError::Corrupt.into()),
+        };
+
+        let restored = Restored::from_plaintext(opened.expose_secret());
+
+        opened.expose_secret_mut()[..].clear();
+
+        Ok(restored?)
+    }
+}
+
+impl Restored {
+    fn from_plaintext(plaintext: &[u8]) -> Result<Restored, BackupError> {
+        let (issuer, rest) = read_item(plaintext)?;
+        let (revocation, rest) = read_item(rest)?;
+        let (accumulator, rest) = read_item(rest)?;
+
+        if !rest.is_empty() {
+            return Err(BackupError::WrongNumberOfBytes);
+        }
+
+        let issuer = Issuer::from_bytes(issuer.ok_or(BackupError::WrongNumberOfBytes)?)?;
+        let revocation = match revocation {
+            Some(x) => Some(RevocationRegistry::from_bytes(x)?),
+            None    => None,
+        };
+        let accumulator = match accumulator {
+            Some(x) => Some(Accumulator::from_bytes(x)?),
+            None    => None,
+        };
+
+        Ok(Restored { issuer, revocation, accumulator })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use errors::RecoveryError;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    #[test]
+    fn a_threshold_of_custodians_restore_the_issuer() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let keypairs: Vec<elgamal::Keypair> = (0..3).map(|_| elgamal::Keypair::generate(&mut rng)).collect();
+        let custodians: Vec<elgamal::PublicKey> = keypairs.iter().map(|k| k.public).collect();
+        let mut revocation = RevocationRegistry::new(b"members", 7).unwrap();
+        let mut accumulator = Accumulator::create(&mut rng);
+
+        revocation.revoke(b"1234").unwrap();
+        accumulator.revoke(&[Scalar::random(&mut rng)]);
+
+        let backup = issuer.backup(Some(&revocation), Some(&accumulator), &custodians, 2, &mut rng).unwrap();
+        let backup = Backup::from_bytes(&backup.to_bytes()).unwrap();
+
+        assert!(backup.sharing.verify(&system_parameters).is_ok());
+
+        let decrypted: Vec<DecryptedShare> = keypairs.iter()
+            .map(|k| backup.sharing.decrypt_share(k).unwrap())
+            .collect();
+        let restored = backup.restore(&decrypted[1..]).unwrap();
+
+        assert!(restored.issuer.to_bytes() == issuer.to_bytes());
+        assert!(restored.revocation == Some(revocation));
+        assert!(restored.accumulator.unwrap().to_bytes() == accumulator.to_bytes());
+
+        // One custodian alone cannot restore.
+        assert!(backup.restore(&decrypted[..1]).err() == Some(BackupError::Recovery(RecoveryError::NotEnoughShares).into()));
+        assert!(issuer.backup(None, None, &custodians, 1, &mut rng).err() == Some(BackupError::InvalidThreshold.into()));
+
+        // Registries are optional, and each backup has its own nonce.
+        let bare = issuer.backup(None, None, &custodians, 2, &mut rng).unwrap();
+        let decrypted: Vec<DecryptedShare> = keypairs.iter()
+            .map(|k| bare.sharing.decrypt_share(k).unwrap())
+            .collect();
+        let restored = bare.restore(&decrypted).unwrap();
+
+        assert!(restored.revocation.is_none());
+        assert!(restored.accumulator.is_none());
+        assert!(bare.nonce != backup.nonce);
+    }
+
+    #[test]
+    fn modified_backups_do_not_restore() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let keypairs: Vec<elgamal::Keypair> = (0..2).map(|_| elgamal::Keypair::generate(&mut rng)).collect();
+        let custodians: Vec<elgamal::PublicKey> = keypairs.iter().map(|k| k.public).collect();
+
+        let backup = issuer.backup(None, None, &custodians, 2, &mut rng).unwrap();
+        let decrypted: Vec<DecryptedShare> = keypairs.iter()
+            .map(|k| backup.sharing.decrypt_share(k).unwrap())
+            .collect();
+
+        let mut modified = backup.clone();
+
+        modified.sealed[8] ^= 1;
+
+        assert!(modified.restore(&decrypted).err() == Some(BackupError::Corrupt.into()));
+
+        let mut modified = backup.clone();
+
+        modified.nonce[0] ^= 1;
+
+        assert!(modified.restore(&decrypted).err() == Some(BackupError::Corrupt.into()));
+
+        let mut modified = backup.clone();
+        let last = modified.sealed.len() - 1;
+
+        modified.sealed[last] ^= 1;
+
+        assert!(modified.restore(&decrypted).err() == Some(BackupError::Corrupt.into()));
+        assert!(backup.restore(&decrypted).is_ok());
+    }
+}
@@ -0,0 +1,277 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Batch operations, which run across all cores with the `parallel` feature.
+//!
+//! Every function here returns its results in the same order as its inputs,
+//! and, given the same inputs and RNG, the same results whether or not the
+//! `parallel` feature is enabled.  Randomness is always drawn from the
+//! caller's RNG sequentially, before any work is split across threads.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use amacs;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use credential::VerifiedCredential;
+use elgamal;
+use errors::CredentialError;
+use errors::Error;
+use issuer::Issuer;
+use metrics::Metrics;
+use metrics::Operation;
+use metrics::report;
+use nonces::Nonces;
+
+/// Map `$f` over `$items`, in parallel if the `parallel` feature is enabled,
+/// collecting the results in order.
+macro_rules! batch_map {
+    ($items:expr, $f:expr) => {{
+        #[cfg(feature = "parallel")]
+        let results = $items.par_iter().map($f).collect();
+        #[cfg(not(feature = "parallel"))]
+        let results = $items.iter().map($f).collect();
+
+        results
+    }}
+}
+
+/// Encrypt each of `messages` to `key`, using the corresponding one of
+/// `nonces`.
+///
+/// # Errors
+///
+/// * `CredentialError::MissingData` if there are fewer `nonces` than
+///   `messages`.
+pub fn encrypt_batch(
+    key: &elgamal::PublicKey,
+    messages: &[elgamal::Message],
+    nonces: &Nonces,
+) -> Result<Vec<elgamal::Encryption>, Error>
+{
+    if nonces.0.len() < messages.len() {
+        return Err(CredentialError::MissingData.into());
+    }
+
+    let pairs: Vec<(&elgamal::Message, &::nonces::Ephemeral)> = messages.iter().zip(nonces.0.iter()).collect();
+
+    Ok(batch_map!(pairs, |&(message, nonce)| key.encrypt(message, nonce)))
+}
+
+/// Verify each of the `(tag, message)` pairs in `macs` under `key`.
+pub fn verify_macs_batch(
+    key: &amacs::SecretKey,
+    macs: &[(amacs::Tag, amacs::Message)],
+) -> Vec<Result<(), Error>>
+{
+    batch_map!(macs, |&(ref tag, ref message)| key.verify(tag, message))
+}
+
+/// Verify each of `presentations`, as `Issuer::verify()`.
+pub fn verify_presentations_batch(
+    issuer: &Issuer,
+    presentations: &[CredentialPresentation],
+) -> Vec<Result<VerifiedCredential, Error>>
+{
+    batch_map!(presentations, |presentation| issuer.verify(presentation))
+}
+
+/// Issue a credential for each of `requests`, as `Issuer::issue()`.
+///
+/// Each issuance is made with its own CSPRNG, seeded in turn from `csprng`.
+pub fn issue_batch<R>(
+    issuer: &Issuer,
+    requests: &[CredentialRequest],
+    csprng: &mut R,
+) -> Vec<Result<CredentialIssuance, Error>>
+where
+    R: RngCore + CryptoRng,
+{
+    let mut seeded: Vec<(&CredentialRequest, [u8; 32])> = Vec::with_capacity(requests.len());
+
+    for request in requests.iter() {
+        let mut seed = [0u8; 32];
+
+        csprng.fill_bytes(&mut seed);
+        seeded.push((request, seed));
+    }
+
+    batch_map!(seeded, |&(request, seed)| issuer.issue(request, &mut ChaChaRng::from_seed(seed)))
+}
+
+/// Verify each of `presentations`, as `verify_presentations_batch()`,
+/// reporting the batch and each of its outcomes to `metrics`.
+pub fn verify_presentations_batch_with_metrics<M>(
+    issuer: &Issuer,
+    presentations: &[CredentialPresentation],
+    metrics: &M,
+) -> Vec<Result<VerifiedCredential, Error>>
+where
+    M: Metrics + ?Sized,
+{
+    observe_batch(metrics, Operation::Verification, presentations.len(), || {
+        verify_presentations_batch(issuer, presentations)
+    })
+}
+
+/// Issue a credential for each of `requests`, as `issue_batch()`, reporting
+/// the batch and each of its outcomes to `metrics`.
+pub fn issue_batch_with_metrics<R, M>(
+    issuer: &Issuer,
+    requests: &[CredentialRequest],
+    csprng: &mut R,
+    metrics: &M,
+) -> Vec<Result<CredentialIssuance, Error>>
+where
+    R: RngCore + CryptoRng,
+    M: Metrics + ?Sized,
+{
+    observe_batch(metrics, Operation::Issuance, requests.len(), || issue_batch(issuer, requests, csprng))
+}
+
+/// Run a batch of `size` `operation`s, reporting its size, how long it took
+/// as a whole, and then each outcome, in order.
+///
+/// Outcomes are reported after the batch completes, on the calling thread,
+/// so that `metrics` need not be shared between threads.
+fn observe_batch<M, T, F>(
+    metrics: &M,
+    operation: Operation,
+    size: usize,
+    f: F,
+) -> Vec<Result<T, Error>>
+where
+    M: Metrics + ?Sized,
+    F: FnOnce() -> Vec<Result<T, Error>>,
+{
+    metrics.batch(operation, size);
+
+    #[cfg(feature = "std")]
+    let start = ::std::time::Instant::now();
+
+    let results = f();
+
+    #[cfg(feature = "std")]
+    metrics.timed(operation, start.elapsed());
+
+    for result in results.iter() {
+        report(metrics, operation, result);
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::traits::Identity;
+
+    use credential::RevealedAttribute;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use test_utils::identity_forgery;
+    use user::User;
+
+    #[test]
+    fn issue_and_verify_batch() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut users: Vec<User> = Vec::new();
+        let mut requests: Vec<CredentialRequest> = Vec::new();
+
+        for _ in 0..8 {
+            let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+            let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+            attributes.push(Scalar::random(&mut rng));
+            requests.push(user.obtain(attributes));
+            users.push(user);
+        }
+
+        let issuances = issue_batch(&issuer, &requests, &mut rng);
+        let mut presentations: Vec<CredentialPresentation> = Vec::new();
+
+        for (user, issuance) in users.iter_mut().zip(issuances.iter()) {
+            user.obtain_finish(Some(issuance.as_ref().unwrap())).unwrap();
+            presentations.push(user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap());
+        }
+
+        // Break one presentation, and forge another, and check that only
+        // they fail, in order.
+        presentations[3].rerandomized_nonce = presentations[2].rerandomized_nonce;
+        presentations[5] = identity_forgery(&issuer, &mut rng);
+
+        let results = verify_presentations_batch(&issuer, &presentations);
+
+        for (i, result) in results.iter().enumerate() {
+            assert!(result.is_ok() == (i != 3 && i != 5));
+        }
+        assert!(results[5] == Err(CredentialError::VerificationFailure.into()));
+    }
+
+    #[test]
+    fn encrypt_batch_is_ordered() {
+        let mut rng = thread_rng();
+        let keypair = elgamal::Keypair::generate(&mut rng);
+        let scalars: Vec<Scalar> = (0..8).map(|_| Scalar::random(&mut rng)).collect();
+        let messages: Vec<elgamal::Message> = scalars.iter().map(elgamal::Message::from).collect();
+        let nonces = Nonces::new(&mut rng, messages.len());
+        let encryptions = encrypt_batch(&keypair.public, &messages, &nonces).unwrap();
+
+        for ((message, nonce), encryption) in messages.iter().zip(nonces.0.iter()).zip(encryptions.iter()) {
+            assert!(keypair.public.encrypt(message, nonce) == *encryption);
+        }
+
+        assert!(encrypt_batch(&keypair.public, &messages, &Nonces::new(&mut rng, 7)).is_err());
+    }
+
+    #[test]
+    fn verify_macs_batch_is_ordered() {
+        let mut rng = thread_rng();
+        let key = amacs::SecretKey::new(1, &mut rng);
+        let mut macs: Vec<(amacs::Tag, amacs::Message)> = Vec::new();
+
+        for _ in 0..8 {
+            let message = amacs::Message::from(Scalar::random(&mut rng));
+
+            macs.push((key.mac(&message, &mut rng).unwrap(), message));
+        }
+        macs[5].1 = amacs::Message::from(Scalar::random(&mut rng));
+
+        // Nor may the inputs be malformed: a tag with the identity for its
+        // nonce, or a message shorter than the key.
+        macs[2].0 = amacs::Tag { nonce: RistrettoPoint::identity(), mac: RistrettoPoint::identity() };
+        macs[6].1 = amacs::Message(Vec::new());
+
+        let results = verify_macs_batch(&key, &macs);
+
+        for (i, result) in results.iter().enumerate() {
+            assert!(result.is_ok() == (i != 2 && i != 5 && i != 6));
+        }
+    }
+}
@@ -0,0 +1,799 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Publicly verifiable credentials from BBS signatures, with selective
+//! disclosure.
+//!
+//! Presentations of the credentials in `issuer` may only be verified by the
+//! issuer, since verifying them requires its aMAC key.  For relying parties
+//! which refuse keyed verification, an issuer may instead sign the same
+//! attributes, under the same `Schema`s, with a `BbsKeypair` over the
+//! BLS12-381 pairing curve.  A holder presents a `BbsCredential` by proving
+//! knowledge of the signature while disclosing only the attributes it
+//! chooses, as the schema's reveal rules allow, and anyone with the issuer's
+//! `BbsPublicKey` may verify the `BbsPresentation`.
+//!
+//! The signatures and proofs follow the construction in the IRTF's BBS
+//! signatures draft:
+//!
+//! * A signature on messages \\( m_1, \ldots, m_L \\) is \\( (A, e) \\) with
+//!   \\( A = (x + e)^{-1} \cdot B \\) and
+//!   \\( B = P_1 + \mathrm{domain} \cdot Q_1 + \sum_i m_i \cdot H_i \\), and
+//!   verifies when \\( e(A, W + e \cdot P_2) = e(B, P_2) \\).
+//! * A presentation rerandomises \\( A \\) into \\( \bar{A} \\), and proves
+//!   knowledge of \\( e \\) and of the undisclosed messages in a Schnorr
+//!   proof; it verifies when \\( e(\bar{A}, W) = e(\bar{B}, P_2) \\) and the
+//!   proof does.
+//!
+//! The generators are hashed to the curve from this crate's own labels, and
+//! attributes, being scalars modulo the Ristretto group order, are hashed to
+//! BBS messages, so signatures are not interchangeable with those of other
+//! implementations.  The schema's identifier is the signature's header,
+//! binding every signature to the schema it was issued under.
+//!
+//! This module is only available with the `bbs` feature.
+
+use std::vec::Vec;
+
+use bbs_sha2::Sha256;
+
+use bls12_381::G1Affine;
+use bls12_381::G1Projective;
+use bls12_381::G2Affine;
+use bls12_381::G2Projective;
+use bls12_381::Scalar as BlsScalar;
+use bls12_381::hash_to_curve::ExpandMsgXmd;
+use bls12_381::hash_to_curve::HashToCurve;
+use bls12_381::pairing;
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::RevealedAttribute;
+use encoding::u64_to_bytes;
+use errors::BbsError;
+use errors::Error;
+use errors::SchemaError;
+use hashing::Hasher;
+use schema::SIZEOF_SCHEMA_ID;
+use schema::Schema;
+use schema::SchemaId;
+use secret::Clear;
+use secret::Secret;
+
+pub const SIZEOF_BBS_PUBLIC_KEY: usize = 96;
+pub const SIZEOF_BBS_KEYPAIR: usize = 32 + SIZEOF_BBS_PUBLIC_KEY;
+pub const SIZEOF_BBS_SIGNATURE: usize = 48 + 32;
+
+/// The size of a `BbsProof` without its responses for hidden attributes.
+const SIZEOF_BBS_PROOF_HEADER: usize = 3 * 48 + 4 * 32;
+
+/// The domain separation tag for hashing generators to the curve.
+const GENERATOR_DST: &'static [u8] = b"AEONFLUX-BBS-V1_XMD:SHA-256_SSWU_RO_";
+
+fn read_g1(bytes: &[u8]) -> Result<G1Affine, BbsError> {
+    let mut tmp = [0u8; 48];
+
+    tmp.copy_from_slice(bytes);
+
+    Option::from(G1Affine::from_compressed(&tmp)).ok_or(BbsError::PointDecompressionError)
+}
+
+fn read_g2(bytes: &[u8]) -> Result<G2Affine, BbsError> {
+    let mut tmp = [0u8; 96];
+
+    tmp.copy_from_slice(bytes);
+
+    Option::from(G2Affine::from_compressed(&tmp)).ok_or(BbsError::PointDecompressionError)
+}
+
+fn read_bls_scalar(bytes: &[u8]) -> Result<BlsScalar, BbsError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    Option::from(BlsScalar::from_bytes(&tmp)).ok_or(BbsError::ScalarFormatError)
+}
+
+fn read_attribute(bytes: &[u8]) -> Result<RevealedAttribute, BbsError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    Scalar::from_canonical_bytes(tmp).ok_or(BbsError::ScalarFormatError)
+}
+
+/// The `length` bytes of `bytes` beginning at `offset`.
+fn take(bytes: &[u8], offset: usize, length: usize) -> Result<&[u8], BbsError> {
+    bytes.get(offset..offset + length).ok_or(BbsError::WrongNumberOfBytes)
+}
+
+fn random_scalar<R>(csprng: &mut R) -> BlsScalar
+where
+    R: RngCore + CryptoRng,
+{
+    let mut bytes = [0u8; 64];
+
+    csprng.fill_bytes(&mut bytes);
+
+    let scalar = BlsScalar::from_bytes_wide(&bytes);
+
+    bytes.clear();
+    scalar
+}
+
+fn finalize_bls_scalar(h: Hasher) -> BlsScalar {
+    BlsScalar::from_bytes_wide(&h.finalize_digest())
+}
+
+/// Map an attribute, which is a scalar modulo the Ristretto group order, to
+/// a BBS message modulo the BLS12-381 group order.
+fn message(attribute: &RevealedAttribute) -> BlsScalar {
+    let mut h = Hasher::new(b"aeonflux bbs message v1");
+
+    h.input(attribute.as_bytes());
+    finalize_bls_scalar(h)
+}
+
+/// The fixed generators, \\( P_1 \\) and \\( Q_1 \\), and one generator
+/// \\( H_i \\) for each attribute, all with unknown discrete logarithms
+/// relative to one another.
+struct Generators {
+    P1: G1Projective,
+    Q1: G1Projective,
+    H: Vec<G1Projective>,
+}
+
+fn hash_to_g1(label: &[u8], index: u64) -> G1Projective {
+    let mut input: Vec<u8> = Vec::with_capacity(label.len() + 8);
+
+    input.extend(label);
+    input.extend(u64_to_bytes(index).iter());
+
+    <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(&input, GENERATOR_DST)
+}
+
+impl Generators {
+    fn new(count: usize) -> Generators {
+        Generators {
+            P1: hash_to_g1(b"P1", 0),
+            Q1: hash_to_g1(b"Q1", 0),
+            H: (0..count as u64).map(|i| hash_to_g1(b"H", i)).collect(),
+        }
+    }
+
+    /// \\( P_1 + \mathrm{domain} \cdot Q_1 + \sum_i m_i \cdot H_i \\), over
+    /// the messages at the given indices.
+    fn commit<'a, I>(&self, domain: &BlsScalar, messages: I) -> G1Projective
+    where
+        I: Iterator<Item = (usize, &'a BlsScalar)>,
+    {
+        let mut B: G1Projective = self.P1 + self.Q1 * domain;
+
+        for (i, m) in messages {
+            B += self.H[i] * m;
+        }
+        B
+    }
+}
+
+/// Bind a signature to the issuer's key, the generators, and the schema.
+fn domain(public: &BbsPublicKey, generators: &Generators, schema: &SchemaId) -> BlsScalar {
+    let mut h = Hasher::new(b"aeonflux bbs domain v1");
+
+    h.input(&public.0.to_compressed()).input_u64(generators.H.len() as u64);
+    h.input(&G1Affine::from(generators.Q1).to_compressed());
+
+    for H in generators.H.iter() {
+        h.input(&G1Affine::from(H).to_compressed());
+    }
+    h.input(&schema.0[..]);
+
+    finalize_bls_scalar(h)
+}
+
+/// Whether disclosing the attributes at the sorted `indices` follows the
+/// `schema`'s reveal rules.
+fn check_disclosure(schema: &Schema, indices: &[usize]) -> Result<(), BbsError> {
+    for (i, attribute) in schema.attributes.iter().enumerate() {
+        if !attribute.reveal.allows(indices.binary_search(&i).is_ok()) {
+            return Err(SchemaError::RevealRule.into());
+        }
+    }
+    Ok(())
+}
+
+/// An issuer's public key, \\( W = x \cdot P_2 \\).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BbsPublicKey(pub G2Affine);
+
+impl BbsPublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<BbsPublicKey, BbsError> {
+        if bytes.len() != SIZEOF_BBS_PUBLIC_KEY {
+            return Err(BbsError::WrongNumberOfBytes);
+        }
+        Ok(BbsPublicKey(read_g2(bytes)?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_compressed().to_vec()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(BbsPublicKey, "A valid byte sequence representing a BbsPublicKey");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(BbsPublicKey);
+impl_try_from_bytes!(BbsPublicKey, BbsError);
+
+/// An issuer's BBS signing key.
+#[derive(Clone, Debug)]
+pub struct BbsKeypair {
+    secret: Secret<BlsScalar>,
+    pub public: BbsPublicKey,
+}
+
+/// Overwrite secret key material with zeroes when it goes out of scope.
+impl Drop for BbsKeypair {
+    fn drop(&mut self) {
+        *self.secret.expose_secret_mut() = BlsScalar::zero();
+    }
+}
+
+impl BbsKeypair {
+    pub fn from_bytes(bytes: &[u8]) -> Result<BbsKeypair, BbsError> {
+        if bytes.len() != SIZEOF_BBS_KEYPAIR {
+            return Err(BbsError::WrongNumberOfBytes);
+        }
+
+        let secret: BlsScalar = read_bls_scalar(&bytes[..32])?;
+        let public = BbsPublicKey::from_bytes(&bytes[32..])?;
+
+        Ok(BbsKeypair { secret: Secret::new(secret), public })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_BBS_KEYPAIR);
+
+        v.extend(self.secret.expose_secret().to_bytes().iter());
+        v.extend(self.public.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(BbsKeypair, "A valid byte sequence representing a BbsKeypair");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(BbsKeypair);
+impl_try_from_bytes!(BbsKeypair, BbsError);
+
+impl BbsKeypair {
+    /// Generate a new signing key.
+    pub fn generate<R>(csprng: &mut R) -> BbsKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret: BlsScalar = random_scalar(csprng);
+        let public = BbsPublicKey(G2Affine::from(G2Affine::generator() * secret));
+
+        BbsKeypair { secret: Secret::new(secret), public }
+    }
+
+    /// Issue a credential over some `attributes`, one for each attribute of
+    /// the `schema`, in order.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::WrongNumberOfAttributes` if the number of `attributes`
+    ///   differs from the `schema`'s.
+    pub fn issue(&self, schema: &Schema, attributes: Vec<RevealedAttribute>) -> Result<BbsCredential, Error> {
+        if attributes.len() == 0 || attributes.len() != schema.attributes.len() {
+            return Err(SchemaError::WrongNumberOfAttributes.into());
+        }
+
+        let id: SchemaId = schema.id();
+        let messages: Vec<BlsScalar> = attributes.iter().map(message).collect();
+        let generators = Generators::new(messages.len());
+        let domain: BlsScalar = domain(&self.public, &generators, &id);
+
+        // The signature's e is derived from the key and the messages, so that
+        // it never repeats for different messages.
+        let mut h = Hasher::new(b"aeonflux bbs signature v1");
+
+        h.input(&self.secret.expose_secret().to_bytes());
+
+        for m in messages.iter() {
+            h.input(&m.to_bytes());
+        }
+        h.input(&domain.to_bytes());
+
+        let e: BlsScalar = finalize_bls_scalar(h);
+        let inverse: BlsScalar = Option::from((self.secret.expose_secret() + &e).invert())
+            .ok_or(BbsError::InvalidSignature)?;
+        let B: G1Projective = generators.commit(&domain, messages.iter().enumerate());
+        let signature = BbsSignature { A: G1Affine::from(B * inverse), e };
+
+        Ok(BbsCredential { schema: id, attributes, signature })
+    }
+}
+
+/// A BBS signature, \\( (A, e) \\).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BbsSignature {
+    pub A: G1Affine,
+    pub e: BlsScalar,
+}
+
+impl BbsSignature {
+    pub fn from_bytes(bytes: &[u8]) -> Result<BbsSignature, BbsError> {
+        if bytes.len() != SIZEOF_BBS_SIGNATURE {
+            return Err(BbsError::WrongNumberOfBytes);
+        }
+        Ok(BbsSignature { A: read_g1(&bytes[..48])?, e: read_bls_scalar(&bytes[48..80])? })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_BBS_SIGNATURE);
+
+        v.extend(self.A.to_compressed().iter());
+        v.extend(self.e.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(BbsSignature, "A valid byte sequence representing a BbsSignature");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(BbsSignature);
+impl_try_from_bytes!(BbsSignature, BbsError);
+
+/// A holder's credential: its attributes, the schema they were issued under,
+/// and the issuer's signature on them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BbsCredential {
+    pub schema: SchemaId,
+    pub attributes: Vec<RevealedAttribute>,
+    pub signature: BbsSignature,
+}
+
+impl BbsCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<BbsCredential, BbsError> {
+        let mut schema = SchemaId([0u8; SIZEOF_SCHEMA_ID]);
+
+        schema.0.copy_from_slice(take(bytes, 0, SIZEOF_SCHEMA_ID)?);
+
+        let signature = BbsSignature::from_bytes(take(bytes, SIZEOF_SCHEMA_ID, SIZEOF_BBS_SIGNATURE)?)?;
+        let mut offset: usize = SIZEOF_SCHEMA_ID + SIZEOF_BBS_SIGNATURE;
+        let count: usize = take(bytes, offset, 1)?[0] as usize;
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(count);
+
+        offset += 1;
+
+        for _ in 0..count {
+            attributes.push(read_attribute(take(bytes, offset, 32)?)?);
+            offset += 32;
+        }
+        if offset != bytes.len() {
+            return Err(BbsError::WrongNumberOfBytes);
+        }
+
+        Ok(BbsCredential { schema, attributes, signature })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SCHEMA_ID + SIZEOF_BBS_SIGNATURE + 1 + 32 * self.attributes.len());
+
+        v.extend(self.schema.0.iter());
+        v.extend(self.signature.to_bytes());
+        v.push(self.attributes.len() as u8);
+
+        for attribute in self.attributes.iter() {
+            v.extend(attribute.as_bytes().iter());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(BbsCredential, "A valid byte sequence representing a BbsCredential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(BbsCredential);
+impl_try_from_bytes!(BbsCredential, BbsError);
+
+/// The challenge of a `BbsProof`.
+fn challenge(
+    Abar: &G1Affine,
+    Bbar: &G1Affine,
+    D: &G1Affine,
+    T1: &G1Projective,
+    T2: &G1Projective,
+    disclosed: &[(usize, RevealedAttribute)],
+    domain: &BlsScalar,
+    header: &[u8],
+) -> BlsScalar
+{
+    let mut h = Hasher::new(b"aeonflux bbs challenge v1");
+
+    h.input(&Abar.to_compressed()).input(&Bbar.to_compressed()).input(&D.to_compressed());
+    h.input(&G1Affine::from(T1).to_compressed()).input(&G1Affine::from(T2).to_compressed());
+    h.input_u64(disclosed.len() as u64);
+
+    for &(i, ref attribute) in disclosed.iter() {
+        h.input_u64(i as u64).input(attribute.as_bytes());
+    }
+    h.input(&domain.to_bytes()).input_u64(header.len() as u64).input(header);
+
+    finalize_bls_scalar(h)
+}
+
+impl BbsCredential {
+    /// Check the issuer's signature on this credential, under its `public`
+    /// key and the `schema` it was issued under.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::UnknownSchema` if this credential was issued under
+    ///   another schema.
+    /// * `BbsError::InvalidSignature` if the signature does not verify.
+    pub fn verify(&self, public: &BbsPublicKey, schema: &Schema) -> Result<(), Error> {
+        if schema.id() != self.schema {
+            return Err(SchemaError::UnknownSchema.into());
+        }
+        if self.attributes.len() != schema.attributes.len() {
+            return Err(SchemaError::WrongNumberOfAttributes.into());
+        }
+
+        let messages: Vec<BlsScalar> = self.attributes.iter().map(message).collect();
+        let generators = Generators::new(messages.len());
+        let domain: BlsScalar = domain(public, &generators, &self.schema);
+        let B: G1Projective = generators.commit(&domain, messages.iter().enumerate());
+        let W: G2Projective = G2Projective::from(public.0) + G2Affine::generator() * self.signature.e;
+
+        if bool::from(self.signature.A.is_identity()) ||
+           pairing(&self.signature.A, &G2Affine::from(W)) != pairing(&G1Affine::from(B), &G2Affine::generator())
+        {
+            return Err(BbsError::InvalidSignature.into());
+        }
+        Ok(())
+    }
+
+    /// Present this credential, disclosing the attributes named in
+    /// `disclose` and hiding the rest.
+    ///
+    /// # Inputs
+    ///
+    /// * `public` is the issuer's public key.
+    /// * `schema` is the schema this credential was issued under.
+    /// * `header` is bound into the presentation, e.g. a nonce chosen by the
+    ///   relying party, so that it cannot be replayed elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// * `BbsError::UnknownAttribute` if a name is not in the `schema`.
+    /// * `SchemaError::RevealRule` if the disclosure breaks the `schema`'s
+    ///   reveal rules.
+    /// * `SchemaError::UnknownSchema` if this credential was issued under
+    ///   another schema.
+    pub fn present<R>(
+        &self,
+        public: &BbsPublicKey,
+        schema: &Schema,
+        disclose: &[&str],
+        header: &[u8],
+        csprng: &mut R,
+    ) -> Result<BbsPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if schema.id() != self.schema {
+            return Err(SchemaError::UnknownSchema.into());
+        }
+        if self.attributes.len() != schema.attributes.len() {
+            return Err(SchemaError::WrongNumberOfAttributes.into());
+        }
+
+        let mut indices: Vec<usize> = Vec::with_capacity(disclose.len());
+
+        for name in disclose.iter() {
+            indices.push(schema.index_of(name).ok_or(BbsError::UnknownAttribute)?);
+        }
+        indices.sort();
+        indices.dedup();
+        check_disclosure(schema, &indices)?;
+
+        let disclosed: Vec<(usize, RevealedAttribute)> = indices.iter().map(|&i| (i, self.attributes[i])).collect();
+        let hidden: Vec<usize> = (0..self.attributes.len()).filter(|i| indices.binary_search(i).is_err()).collect();
+        let messages: Vec<BlsScalar> = self.attributes.iter().map(message).collect();
+        let generators = Generators::new(messages.len());
+        let domain: BlsScalar = domain(public, &generators, &self.schema);
+
+        let r1: BlsScalar = random_scalar(csprng);
+        let r2: BlsScalar = random_scalar(csprng);
+        let e_tilde: BlsScalar = random_scalar(csprng);
+        let r1_tilde: BlsScalar = random_scalar(csprng);
+        let r3_tilde: BlsScalar = random_scalar(csprng);
+        let m_tilde: Vec<BlsScalar> = hidden.iter().map(|_| random_scalar(csprng)).collect();
+
+        let B: G1Projective = generators.commit(&domain, messages.iter().enumerate());
+        let D: G1Projective = B * r2;
+        let Abar: G1Projective = self.signature.A * (r1 * r2);
+        let Bbar: G1Projective = D * r1 - Abar * self.signature.e;
+        let T1: G1Projective = Abar * e_tilde + D * r1_tilde;
+        let mut T2: G1Projective = D * r3_tilde;
+
+        for (&j, m) in hidden.iter().zip(m_tilde.iter()) {
+            T2 += generators.H[j] * m;
+        }
+
+        let Abar = G1Affine::from(Abar);
+        let Bbar = G1Affine::from(Bbar);
+        let D = G1Affine::from(D);
+        let c: BlsScalar = challenge(&Abar, &Bbar, &D, &T1, &T2, &disclosed, &domain, header);
+        let r3: BlsScalar = Option::from(r2.invert()).ok_or(BbsError::InvalidProof)?;
+
+        let proof = BbsProof {
+            Abar, Bbar, D,
+            e_hat: e_tilde + self.signature.e * c,
+            r1_hat: r1_tilde - r1 * c,
+            r3_hat: r3_tilde - r3 * c,
+            m_hat: hidden.iter().zip(m_tilde.iter()).map(|(&j, m)| m + &(messages[j] * c)).collect(),
+            c,
+        };
+
+        Ok(BbsPresentation { schema: self.schema, disclosed, proof })
+    }
+}
+
+/// A proof of knowledge of a BBS signature on some hidden and some disclosed
+/// messages.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BbsProof {
+    pub Abar: G1Affine,
+    pub Bbar: G1Affine,
+    pub D: G1Affine,
+    pub e_hat: BlsScalar,
+    pub r1_hat: BlsScalar,
+    pub r3_hat: BlsScalar,
+    /// The responses for the hidden messages, in order.
+    pub m_hat: Vec<BlsScalar>,
+    pub c: BlsScalar,
+}
+
+/// A presentation of a `BbsCredential`, which anyone with the issuer's
+/// `BbsPublicKey` may verify.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BbsPresentation {
+    pub schema: SchemaId,
+    /// The disclosed attributes and their positions in the schema, in order.
+    pub disclosed: Vec<(usize, RevealedAttribute)>,
+    pub proof: BbsProof,
+}
+
+impl BbsPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<BbsPresentation, BbsError> {
+        let mut schema = SchemaId([0u8; SIZEOF_SCHEMA_ID]);
+
+        schema.0.copy_from_slice(take(bytes, 0, SIZEOF_SCHEMA_ID)?);
+
+        let proof: &[u8] = take(bytes, SIZEOF_SCHEMA_ID, SIZEOF_BBS_PROOF_HEADER)?;
+        let Abar: G1Affine = read_g1(&proof[..48])?;
+        let Bbar: G1Affine = read_g1(&proof[48..96])?;
+        let D: G1Affine = read_g1(&proof[96..144])?;
+        let e_hat: BlsScalar = read_bls_scalar(&proof[144..176])?;
+        let r1_hat: BlsScalar = read_bls_scalar(&proof[176..208])?;
+        let r3_hat: BlsScalar = read_bls_scalar(&proof[208..240])?;
+        let c: BlsScalar = read_bls_scalar(&proof[240..272])?;
+        let mut offset: usize = SIZEOF_SCHEMA_ID + SIZEOF_BBS_PROOF_HEADER;
+
+        let count: usize = take(bytes, offset, 1)?[0] as usize;
+        let mut disclosed: Vec<(usize, RevealedAttribute)> = Vec::with_capacity(count);
+
+        offset += 1;
+
+        for _ in 0..count {
+            let entry: &[u8] = take(bytes, offset, 33)?;
+
+            disclosed.push((entry[0] as usize, read_attribute(&entry[1..])?));
+            offset += 33;
+        }
+
+        let count: usize = take(bytes, offset, 1)?[0] as usize;
+        let mut m_hat: Vec<BlsScalar> = Vec::with_capacity(count);
+
+        offset += 1;
+
+        for _ in 0..count {
+            m_hat.push(read_bls_scalar(take(bytes, offset, 32)?)?);
+            offset += 32;
+        }
+        if offset != bytes.len() {
+            return Err(BbsError::WrongNumberOfBytes);
+        }
+
+        Ok(BbsPresentation {
+            schema, disclosed,
+            proof: BbsProof { Abar, Bbar, D, e_hat, r1_hat, r3_hat, m_hat, c },
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SCHEMA_ID + SIZEOF_BBS_PROOF_HEADER + 2 +
+                                                33 * self.disclosed.len() + 32 * self.proof.m_hat.len());
+
+        v.extend(self.schema.0.iter());
+        v.extend(self.proof.Abar.to_compressed().iter());
+        v.extend(self.proof.Bbar.to_compressed().iter());
+        v.extend(self.proof.D.to_compressed().iter());
+        v.extend(self.proof.e_hat.to_bytes().iter());
+        v.extend(self.proof.r1_hat.to_bytes().iter());
+        v.extend(self.proof.r3_hat.to_bytes().iter());
+        v.extend(self.proof.c.to_bytes().iter());
+        v.push(self.disclosed.len() as u8);
+
+        for &(i, ref attribute) in self.disclosed.iter() {
+            v.push(i as u8);
+            v.extend(attribute.as_bytes().iter());
+        }
+        v.push(self.proof.m_hat.len() as u8);
+
+        for m in self.proof.m_hat.iter() {
+            v.extend(m.to_bytes().iter());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(BbsPresentation, "A valid byte sequence representing a BbsPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(BbsPresentation);
+impl_try_from_bytes!(BbsPresentation, BbsError);
+
+impl BbsPresentation {
+    /// The disclosed attribute called `name` in the `schema`, if it was
+    /// disclosed.
+    pub fn disclosed(&self, schema: &Schema, name: &str) -> Option<&RevealedAttribute> {
+        let index: usize = schema.index_of(name)?;
+
+        self.disclosed.iter().find(|&&(i, _)| i == index).map(|&(_, ref attribute)| attribute)
+    }
+
+    /// Verify this presentation under an issuer's `public` key, for the
+    /// `schema` it claims and the `header` the relying party expects.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::UnknownSchema` if this presentation was made under
+    ///   another schema.
+    /// * `SchemaError::RevealRule` if its disclosures break the `schema`'s
+    ///   reveal rules.
+    /// * `BbsError::InvalidProof` if the proof does not verify.
+    pub fn verify(&self, public: &BbsPublicKey, schema: &Schema, header: &[u8]) -> Result<(), Error> {
+        if schema.id() != self.schema {
+            return Err(SchemaError::UnknownSchema.into());
+        }
+
+        let count: usize = schema.attributes.len();
+        let indices: Vec<usize> = self.disclosed.iter().map(|&(i, _)| i).collect();
+
+        // The disclosed indices must be distinct, in order, and within the
+        // schema, and every other attribute must have a response.
+        if indices.windows(2).any(|w| w[0] >= w[1]) ||
+           indices.iter().any(|&i| i >= count) ||
+           indices.len() + self.proof.m_hat.len() != count
+        {
+            return Err(BbsError::InvalidProof.into());
+        }
+        check_disclosure(schema, &indices)?;
+
+        let proof: &BbsProof = &self.proof;
+
+        if bool::from(proof.Abar.is_identity()) {
+            return Err(BbsError::InvalidProof.into());
+        }
+
+        let hidden: Vec<usize> = (0..count).filter(|i| indices.binary_search(i).is_err()).collect();
+        let messages: Vec<(usize, BlsScalar)> = self.disclosed.iter().map(|&(i, ref a)| (i, message(a))).collect();
+        let generators = Generators::new(count);
+        let domain: BlsScalar = domain(public, &generators, &self.schema);
+
+        let T1: G1Projective = proof.Bbar * proof.c + proof.Abar * proof.e_hat + proof.D * proof.r1_hat;
+        let Bv: G1Projective = generators.commit(&domain, messages.iter().map(|&(i, ref m)| (i, m)));
+        let mut T2: G1Projective = Bv * proof.c + proof.D * proof.r3_hat;
+
+        for (&j, m) in hidden.iter().zip(proof.m_hat.iter()) {
+            T2 += generators.H[j] * m;
+        }
+
+        if challenge(&proof.Abar, &proof.Bbar, &proof.D, &T1, &T2, &self.disclosed, &domain, header) != proof.c {
+            return Err(BbsError::InvalidProof.into());
+        }
+        if pairing(&proof.Abar, &public.0) != pairing(&proof.Bbar, &G2Affine::generator()) {
+            return Err(BbsError::InvalidProof.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use schema::AttributeType;
+    use schema::Reveal;
+
+    fn membership() -> Schema {
+        Schema::new("membership")
+            .attribute("venue", AttributeType::Identifier, Reveal::Always)
+            .attribute("tier", AttributeType::Integer, Reveal::Optional)
+            .attribute("member", AttributeType::Identifier, Reveal::Never)
+    }
+
+    fn credential(keypair: &BbsKeypair) -> BbsCredential {
+        let mut rng = thread_rng();
+        let attributes = vec![Scalar::random(&mut rng), Scalar::from(3u64), Scalar::random(&mut rng)];
+
+        keypair.issue(&membership(), attributes).unwrap()
+    }
+
+    #[test]
+    fn issue_and_verify_signatures() {
+        let mut rng = thread_rng();
+        let keypair = BbsKeypair::generate(&mut rng);
+        let other = BbsKeypair::generate(&mut rng);
+        let credential = BbsCredential::from_bytes(&credential(&keypair).to_bytes()).unwrap();
+        let schema = membership();
+
+        assert!(BbsKeypair::from_bytes(&keypair.to_bytes()).unwrap().public == keypair.public);
+        assert!(credential.verify(&keypair.public, &schema).is_ok());
+        assert!(credential.verify(&other.public, &schema) == Err(BbsError::InvalidSignature.into()));
+
+        let mut altered = credential.clone();
+
+        altered.attributes[1] = Scalar::from(4u64);
+
+        assert!(altered.verify(&keypair.public, &schema) == Err(BbsError::InvalidSignature.into()));
+        assert!(keypair.issue(&schema, vec![Scalar::one()]).err() ==
+                Some(SchemaError::WrongNumberOfAttributes.into()));
+    }
+
+    #[test]
+    fn selective_disclosure() {
+        let mut rng = thread_rng();
+        let keypair = BbsKeypair::generate(&mut rng);
+        let credential = credential(&keypair);
+        let schema = membership();
+
+        for disclose in [&["venue"][..], &["venue", "tier"][..]].iter() {
+            let presentation = credential.present(&keypair.public, &schema, disclose, b"nonce", &mut rng).unwrap();
+            let decoded = BbsPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+            assert!(decoded == presentation);
+            assert!(decoded.verify(&keypair.public, &schema, b"nonce").is_ok());
+            assert!(decoded.verify(&keypair.public, &schema, b"other").err() == Some(BbsError::InvalidProof.into()));
+            assert!(decoded.disclosed(&schema, "venue") == Some(&credential.attributes[0]));
+            assert!(decoded.disclosed(&schema, "member") == None);
+        }
+
+        // The schema's reveal rules hold for both parties.
+        assert!(credential.present(&keypair.public, &schema, &["tier"], b"nonce", &mut rng).err() ==
+                Some(SchemaError::RevealRule.into()));
+        assert!(credential.present(&keypair.public, &schema, &["venue", "member"], b"nonce", &mut rng).err() ==
+                Some(SchemaError::RevealRule.into()));
+
+        // A disclosed attribute cannot be changed.
+        let mut presentation = credential.present(&keypair.public, &schema, &["venue"], b"nonce", &mut rng).unwrap();
+
+        presentation.disclosed[0].1 = Scalar::random(&mut rng);
+
+        assert!(presentation.verify(&keypair.public, &schema, b"nonce").err() == Some(BbsError::InvalidProof.into()));
+    }
+}
@@ -0,0 +1,53 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! `test-vectors`: print known-answer test vectors as JSON.
+//!
+//! This is only built with the `test-vectors` feature.  Run
+//! `test-vectors [COUNT]` to print COUNT vectors (by default, 8), or
+//! `test-vectors check FILE` to check a file of them.
+
+extern crate aeonflux;
+extern crate serde_json;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use aeonflux::test_vectors::check_all;
+use aeonflux::test_vectors::generate_all;
+
+const DEFAULT_COUNT: u64 = 8;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.iter().map(|x| x.as_str()).collect::<Vec<&str>>().as_slice() {
+        [] => println!("{}", serde_json::to_string_pretty(&generate_all(DEFAULT_COUNT)).unwrap()),
+        ["check", path] => {
+            let text: String = match fs::read_to_string(path) {
+                Ok(x)  => x,
+                Err(e) => { eprintln!("error: {}: {}", path, e); process::exit(1) },
+            };
+            let vectors = match serde_json::from_str(&text) {
+                Ok(x)  => x,
+                Err(e) => { eprintln!("error: {}: {}", path, e); process::exit(1) },
+            };
+            if let Err(e) = check_all(&vectors) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        },
+        [count] => match count.parse::<u64>() {
+            Ok(n)  => println!("{}", serde_json::to_string_pretty(&generate_all(n)).unwrap()),
+            Err(_) => { eprintln!("usage: test-vectors [COUNT | check FILE]"); process::exit(2) },
+        },
+        _ => { eprintln!("usage: test-vectors [COUNT | check FILE]"); process::exit(2) },
+    }
+}
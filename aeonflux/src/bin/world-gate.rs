@@ -0,0 +1,413 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! `world-gate`: an operator's command-line tool for aeonflux issuers.
+//!
+//! This is only built with the `cli` feature.  Run `world-gate help` for
+//! usage.
+//!
+//! All keys, parameters, requests, issuances, and presentations are read and
+//! written as raw files in the encoding of their `to_bytes()` methods.  A
+//! revocation list is a text file with one lowercase hex identifier per
+//! line.
+
+extern crate aeonflux;
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+
+use aeonflux::clock::Clock;
+use aeonflux::clock::SystemClock;
+use aeonflux::credential::CredentialBlindRequest;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::errors::CredentialError;
+use aeonflux::errors::Error;
+use aeonflux::issuer::Issuer;
+use aeonflux::parameters::SIZEOF_SYSTEM_PARAMETERS;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::rng::default_rng;
+
+const USAGE: &'static str = "\
+Usage: world-gate <command> [options]
+
+Key ceremony:
+  params generate --out FILE              Generate new random system parameters.
+  key generate --params FILE --out FILE   Generate a new issuer key.
+  key rotate --key FILE                   Replace an issuer key with a new one under the same
+                                          system parameters, keeping the old key as FILE.<time>.
+  key export --key FILE --out FILE        Export an issuer's public parameters.
+
+Publication:
+  params publish --key FILE --out FILE    Write the system parameters followed by the issuer's
+                                          public parameters, for distribution to users.
+
+Issuance:
+  issue --key FILE --request FILE --out FILE [--blind]
+                                          Issue a credential for a (blind) request.
+
+Revocation:
+  revocation add --list FILE HEX          Add an identifier to a revocation list.
+  revocation remove --list FILE HEX       Remove an identifier from a revocation list.
+  revocation list --list FILE             Print a revocation list.
+
+Verification:
+  verify --key FILE --presentation FILE   Verify a credential presentation.  Exits with
+                                          status 1 if it does not verify.
+";
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Io(String, std::io::Error),
+    Credential(Error),
+    InvalidHex(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Usage(ref x)        => write!(f, "{}\n\n{}", x, USAGE),
+            CliError::Io(ref path, ref e) => write!(f, "{}: {}", path, e),
+            CliError::Credential(ref e)   => write!(f, "{}", e),
+            CliError::InvalidHex(ref x)   => write!(f, "Not a hex identifier: {}", x),
+        }
+    }
+}
+
+impl From<CredentialError> for CliError {
+    fn from(source: CredentialError) -> CliError {
+        CliError::Credential(source.into())
+    }
+}
+
+impl From<Error> for CliError {
+    fn from(source: Error) -> CliError {
+        CliError::Credential(source)
+    }
+}
+
+/// Parsed command-line arguments: positional words, and `--flag [value]` options.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+/// Options which take no value.
+const SWITCHES: &'static [&'static str] = &["--blind"];
+
+impl Arguments {
+    fn parse<I: Iterator<Item = String>>(args: I) -> Result<Arguments, CliError> {
+        let mut parsed = Arguments::default();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                if SWITCHES.contains(&arg.as_str()) {
+                    parsed.options.push((arg, None));
+                } else {
+                    match args.next() {
+                        Some(value) => parsed.options.push((arg, Some(value))),
+                        None        => return Err(CliError::Usage(format!("{} requires a value", arg))),
+                    }
+                }
+            } else {
+                parsed.positional.push(arg);
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|&(ref k, _)| k == name)
+    }
+
+    fn option(&self, name: &str) -> Result<&str, CliError> {
+        for &(ref k, ref v) in self.options.iter() {
+            if k == name {
+                if let Some(ref value) = *v {
+                    return Ok(value);
+                }
+            }
+        }
+        Err(CliError::Usage(format!("missing required option {}", name)))
+    }
+
+    fn command(&self) -> Vec<&str> {
+        self.positional.iter().map(|x| x.as_str()).collect()
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+fn write(path: &str, bytes: &[u8]) -> Result<(), CliError> {
+    fs::write(path, bytes).map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+/// Write secret key material, readable only by the current user where supported.
+fn write_secret(path: &str, bytes: &[u8]) -> Result<(), CliError> {
+    let mut options = OpenOptions::new();
+
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    options.open(path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+fn read_issuer(path: &str) -> Result<Issuer, CliError> {
+    Ok(Issuer::from_bytes(&read(path)?)?)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(s: &str) -> Result<Vec<u8>, CliError> {
+    let s = s.trim().to_lowercase();
+
+    if s.len() == 0 || s.len() % 2 != 0 || !s.is_ascii() {
+        return Err(CliError::InvalidHex(s));
+    }
+
+    let mut v: Vec<u8> = Vec::with_capacity(s.len() / 2);
+
+    for i in (0..s.len()).step_by(2) {
+        match u8::from_str_radix(&s[i..i + 2], 16) {
+            Ok(b)  => v.push(b),
+            Err(_) => return Err(CliError::InvalidHex(s.clone())),
+        }
+    }
+    Ok(v)
+}
+
+fn read_revocation_list(path: &str) -> Result<BTreeSet<Vec<u8>>, CliError> {
+    if !Path::new(path).exists() {
+        return Ok(BTreeSet::new());
+    }
+
+    let text = fs::read_to_string(path).map_err(|e| CliError::Io(path.to_string(), e))?;
+    let mut list = BTreeSet::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line.len() > 0 && !line.starts_with('#') {
+            list.insert(unhex(line)?);
+        }
+    }
+    Ok(list)
+}
+
+fn write_revocation_list(path: &str, list: &BTreeSet<Vec<u8>>) -> Result<(), CliError> {
+    let mut text = String::new();
+
+    for id in list.iter() {
+        text.push_str(&hex(id));
+        text.push('\n');
+    }
+    write(path, text.as_bytes())
+}
+
+/// Run a command, returning the process exit status.
+fn run(args: &Arguments) -> Result<i32, CliError> {
+    let mut rng = default_rng();
+    let words = args.command();
+
+    // The revocation commands take an identifier after the command words.
+    let (command, operand): (String, Option<&str>) = if words.len() == 3 && words[0] == "revocation" {
+        (words[..2].join(" "), Some(words[2]))
+    } else {
+        (words.join(" "), None)
+    };
+
+    match (command.as_str(), operand) {
+        ("params generate", None) => {
+            let system_parameters = SystemParameters::hunt_and_peck(&mut rng);
+
+            write(args.option("--out")?, &system_parameters.to_bytes())?;
+        },
+        ("params publish", None) => {
+            let issuer = read_issuer(args.option("--key")?)?;
+            let mut bundle: Vec<u8> = issuer.system_parameters.to_bytes();
+
+            bundle.extend(issuer.get_issuer_parameters().to_bytes());
+            write(args.option("--out")?, &bundle)?;
+        },
+        ("key generate", None) => {
+            let system_parameters = SystemParameters::from_bytes(&read(args.option("--params")?)?)?;
+            let issuer = Issuer::create(system_parameters, &mut rng);
+
+            write_secret(args.option("--out")?, &issuer.to_bytes())?;
+        },
+        ("key rotate", None) => {
+            let path = args.option("--key")?;
+            let old = read_issuer(path)?;
+            let new = Issuer::create(old.system_parameters, &mut rng);
+            let archived = format!("{}.{}", path, SystemClock.now());
+
+            write_secret(&archived, &old.to_bytes())?;
+            write_secret(path, &new.to_bytes())?;
+
+            println!("Archived the previous key to {}", archived);
+        },
+        ("key export", None) => {
+            let issuer = read_issuer(args.option("--key")?)?;
+
+            write(args.option("--out")?, &issuer.get_issuer_parameters().to_bytes())?;
+        },
+        ("issue", None) => {
+            let issuer = read_issuer(args.option("--key")?)?;
+            let request = read(args.option("--request")?)?;
+            let issuance: Vec<u8> = if args.flag("--blind") {
+                issuer.blind_issue(&CredentialBlindRequest::from_bytes(&request)?, &mut rng)?.to_bytes()
+            } else {
+                issuer.issue(&CredentialRequest::from_bytes(&request)?, &mut rng)?.to_bytes()
+            };
+
+            write(args.option("--out")?, &issuance)?;
+        },
+        ("revocation add", Some(id)) => {
+            let path = args.option("--list")?;
+            let mut list = read_revocation_list(path)?;
+
+            list.insert(unhex(id)?);
+            write_revocation_list(path, &list)?;
+        },
+        ("revocation remove", Some(id)) => {
+            let path = args.option("--list")?;
+            let mut list = read_revocation_list(path)?;
+
+            if !list.remove(&unhex(id)?) {
+                eprintln!("{} was not revoked", id);
+                return Ok(1);
+            }
+            write_revocation_list(path, &list)?;
+        },
+        ("revocation list", None) => {
+            for id in read_revocation_list(args.option("--list")?)?.iter() {
+                println!("{}", hex(id));
+            }
+        },
+        ("verify", None) => {
+            let issuer = read_issuer(args.option("--key")?)?;
+            let presentation = CredentialPresentation::from_bytes(&read(args.option("--presentation")?)?)?;
+
+            match issuer.verify(&presentation) {
+                Ok(_)  => println!("valid"),
+                Err(e) => {
+                    println!("invalid: {}", e);
+                    return Ok(1);
+                },
+            }
+        },
+        ("help", None) | ("", None) => print!("{}", USAGE),
+        _ => return Err(CliError::Usage("unknown command".to_string())),
+    }
+    Ok(0)
+}
+
+fn main() {
+    let status = match Arguments::parse(env::args().skip(1)).and_then(|args| run(&args)) {
+        Ok(x) => x,
+        Err(e @ CliError::Usage(_)) => {
+            eprintln!("{}", e);
+            2
+        },
+        Err(e) => {
+            eprintln!("world-gate: {}", e);
+            1
+        },
+    };
+
+    process::exit(status);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(s: &str) -> Arguments {
+        Arguments::parse(s.split_whitespace().map(|x| x.to_string())).unwrap()
+    }
+
+    fn temp(name: &str) -> String {
+        let mut path = env::temp_dir();
+
+        path.push(format!("world-gate-test-{}-{}", process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_arguments() {
+        let parsed = args("issue --key k --blind --request r --out o");
+
+        assert!(parsed.command() == vec!["issue"]);
+        assert!(parsed.option("--key").unwrap() == "k");
+        assert!(parsed.option("--out").unwrap() == "o");
+        assert!(parsed.flag("--blind"));
+        assert!(parsed.option("--params").is_err());
+        assert!(Arguments::parse(vec!["--key".to_string()].into_iter()).is_err());
+    }
+
+    #[test]
+    fn key_ceremony_and_publication() {
+        let params = temp("params");
+        let key = temp("key");
+        let bundle = temp("bundle");
+
+        assert!(run(&args(&format!("params generate --out {}", params))).unwrap() == 0);
+        assert!(run(&args(&format!("key generate --params {} --out {}", params, key))).unwrap() == 0);
+
+        let first = read_issuer(&key).unwrap();
+
+        assert!(run(&args(&format!("key rotate --key {}", key))).unwrap() == 0);
+
+        let second = read_issuer(&key).unwrap();
+
+        assert!(first.system_parameters == second.system_parameters);
+        assert!(first.get_issuer_parameters() != second.get_issuer_parameters());
+        assert!(run(&args(&format!("params publish --key {} --out {}", key, bundle))).unwrap() == 0);
+        assert!(read(&bundle).unwrap()[..SIZEOF_SYSTEM_PARAMETERS] == second.system_parameters.to_bytes()[..]);
+
+        for path in [params, key, bundle].iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn revocation_list_management() {
+        let list = temp("revoked");
+
+        assert!(run(&args(&format!("revocation add --list {} 00ff", list))).unwrap() == 0);
+        assert!(run(&args(&format!("revocation add --list {} ABCD", list))).unwrap() == 0);
+        assert!(run(&args(&format!("revocation remove --list {} 00ff", list))).unwrap() == 0);
+        assert!(run(&args(&format!("revocation remove --list {} 00ff", list))).unwrap() == 1);
+        assert!(read_revocation_list(&list).unwrap().into_iter().collect::<Vec<_>>() == vec![vec![0xab, 0xcd]]);
+        assert!(run(&args(&format!("revocation add --list {} xyz", list))).is_err());
+
+        let _ = fs::remove_file(&list);
+    }
+}
@@ -0,0 +1,451 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A deterministic CBOR profile for wire types, and COSE-style signed
+//! envelopes for issuer parameters.
+//!
+//! Every wire type implements `Cbor`, encoding as a single CBOR byte string
+//! holding its canonical `to_bytes` encoding.  Encoders follow the core
+//! deterministic encoding requirements of RFC 8949 §4.2: every head uses its
+//! shortest form and every length is definite.  Decoders reject anything
+//! else, so that each value has exactly one CBOR encoding, and that encoding
+//! costs only a few bytes more than the raw one.
+//!
+//! A `SignedIssuerParameters` is a `COSE_Sign1` structure (RFC 9052 §4.2)
+//! whose payload is an issuer's parameters, signed by a federation's
+//! `RegistryKeypair`.  Since COSE registers no algorithm for Schnorr
+//! signatures over ristretto255, the algorithm is identified by a value from
+//! the private use range, `COSE_ALGORITHM_RISTRETTO_SCHNORR`.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+pub use errors::CborError;
+use issuer::IssuerParameters;
+use trust::RegistryKeypair;
+use trust::RegistrySignature;
+
+/// The CBOR major type of an unsigned integer.
+const MAJOR_UNSIGNED: u8 = 0;
+/// The CBOR major type of a negative integer.
+const MAJOR_NEGATIVE: u8 = 1;
+/// The CBOR major type of a byte string.
+const MAJOR_BYTES: u8 = 2;
+/// The CBOR major type of a text string.
+const MAJOR_TEXT: u8 = 3;
+/// The CBOR major type of an array.
+const MAJOR_ARRAY: u8 = 4;
+/// The CBOR major type of a map.
+const MAJOR_MAP: u8 = 5;
+/// The CBOR major type of a tag.
+const MAJOR_TAG: u8 = 6;
+
+/// The tag of a `COSE_Sign1` structure.
+pub const COSE_SIGN1_TAG: u64 = 18;
+
+/// The COSE header parameter holding the signature algorithm.
+pub const COSE_HEADER_ALGORITHM: i64 = 1;
+
+/// The COSE header parameter holding the signer's key identifier.
+pub const COSE_HEADER_KEY_ID: i64 = 4;
+
+/// Schnorr signatures over ristretto255 with a merlin transcript, as made by a
+/// `RegistryKeypair`.
+pub const COSE_ALGORITHM_RISTRETTO_SCHNORR: i64 = -65537;
+
+/// The transcript label for signatures over issuer parameters.
+const COSE_SIGNATURE_LABEL: &'static [u8] = b"AEONFLUX COSE SIGN1";
+
+/// A type with a deterministic CBOR encoding.
+pub trait Cbor: Sized {
+    /// Encode this value as deterministic CBOR.
+    fn to_cbor(&self) -> Vec<u8>;
+
+    /// Decode a value from deterministic CBOR.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose error is a `CborError` if `bytes` was not the
+    /// deterministic encoding of a valid value.
+    fn from_cbor(bytes: &[u8]) -> Result<Self, CborError>;
+}
+
+/// Append a data item head with the given `major` type and argument `n`,
+/// encoded in its shortest form.
+fn push_head(v: &mut Vec<u8>, major: u8, n: u64) {
+    let major: u8 = major << 5;
+
+    if n < 24 {
+        v.push(major | n as u8);
+    } else if n <= 0xff {
+        v.push(major | 24);
+        v.push(n as u8);
+    } else if n <= 0xffff {
+        v.push(major | 25);
+        v.extend((0..2).rev().map(|i| (n >> (8 * i)) as u8));
+    } else if n <= 0xffff_ffff {
+        v.push(major | 26);
+        v.extend((0..4).rev().map(|i| (n >> (8 * i)) as u8));
+    } else {
+        v.push(major | 27);
+        v.extend((0..8).rev().map(|i| (n >> (8 * i)) as u8));
+    }
+}
+
+/// Append an integer.
+pub fn push_int(v: &mut Vec<u8>, x: i64) {
+    if x < 0 {
+        push_head(v, MAJOR_NEGATIVE, !(x as u64));
+    } else {
+        push_head(v, MAJOR_UNSIGNED, x as u64);
+    }
+}
+
+/// Append a byte string.
+pub fn push_bytes(v: &mut Vec<u8>, bytes: &[u8]) {
+    push_head(v, MAJOR_BYTES, bytes.len() as u64);
+    v.extend(bytes.iter());
+}
+
+/// Append a text string.
+pub fn push_text(v: &mut Vec<u8>, text: &str) {
+    push_head(v, MAJOR_TEXT, text.len() as u64);
+    v.extend(text.as_bytes().iter());
+}
+
+/// Append the head of an array of `length` items, which must follow it.
+pub fn push_array(v: &mut Vec<u8>, length: usize) {
+    push_head(v, MAJOR_ARRAY, length as u64);
+}
+
+/// Append the head of a map of `length` pairs, which must follow it with
+/// their keys in deterministic order.
+pub fn push_map(v: &mut Vec<u8>, length: usize) {
+    push_head(v, MAJOR_MAP, length as u64);
+}
+
+/// Append a `tag` for the item which must follow it.
+pub fn push_tag(v: &mut Vec<u8>, tag: u64) {
+    push_head(v, MAJOR_TAG, tag);
+}
+
+/// A reader of deterministically encoded CBOR data items.
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Decoder<'a> {
+        Decoder { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], CborError> {
+        if self.bytes.len() - self.offset < length {
+            return Err(CborError::Truncated);
+        }
+
+        let taken: &'a [u8] = &self.bytes[self.offset..self.offset + length];
+
+        self.offset += length;
+        Ok(taken)
+    }
+
+    /// Read the head of the next data item, which must have the `expected`
+    /// major type.
+    fn head(&mut self, expected: u8) -> Result<u64, CborError> {
+        let initial: u8 = self.take(1)?[0];
+
+        if initial >> 5 != expected {
+            return Err(CborError::UnexpectedType);
+        }
+
+        let length: usize = match initial & 0x1f {
+            n @ 0...23 => return Ok(n as u64),
+            24         => 1,
+            25         => 2,
+            26         => 4,
+            27         => 8,
+            _          => return Err(CborError::NonDeterministicEncoding),
+        };
+        let n: u64 = self.take(length)?.iter().fold(0u64, |n, b| (n << 8) | *b as u64);
+
+        // Anything which would have fit in a shorter head is rejected.
+        let minimum: u64 = match length {
+            1 => 24,
+            2 => 0x100,
+            4 => 0x1_0000,
+            _ => 0x1_0000_0000,
+        };
+        if n < minimum {
+            return Err(CborError::NonDeterministicEncoding);
+        }
+        Ok(n)
+    }
+
+    fn length(&mut self, expected: u8) -> Result<usize, CborError> {
+        let n: u64 = self.head(expected)?;
+
+        if n > (self.bytes.len() - self.offset) as u64 {
+            return Err(CborError::Truncated);
+        }
+        Ok(n as usize)
+    }
+
+    /// Read an integer.
+    pub fn int(&mut self) -> Result<i64, CborError> {
+        let initial: u8 = match self.bytes.get(self.offset) {
+            Some(x) => *x,
+            None    => return Err(CborError::Truncated),
+        };
+        let negative: bool = initial >> 5 == MAJOR_NEGATIVE;
+        let n: u64 = self.head(if negative { MAJOR_NEGATIVE } else { MAJOR_UNSIGNED })?;
+
+        if n > i64::max_value() as u64 {
+            return Err(CborError::InvalidValue);
+        }
+        if negative {
+            Ok(!(n as i64))
+        } else {
+            Ok(n as i64)
+        }
+    }
+
+    /// Read a byte string.
+    pub fn bytes(&mut self) -> Result<&'a [u8], CborError> {
+        let length: usize = self.length(MAJOR_BYTES)?;
+
+        self.take(length)
+    }
+
+    /// Read a text string.
+    pub fn text(&mut self) -> Result<&'a str, CborError> {
+        let length: usize = self.length(MAJOR_TEXT)?;
+
+        ::core::str::from_utf8(self.take(length)?).or(Err(CborError::InvalidValue))
+    }
+
+    /// Read the head of an array, returning its number of items.
+    pub fn array(&mut self) -> Result<usize, CborError> {
+        self.length(MAJOR_ARRAY)
+    }
+
+    /// Read the head of a map, returning its number of pairs.
+    pub fn map(&mut self) -> Result<usize, CborError> {
+        self.length(MAJOR_MAP)
+    }
+
+    /// Read a tag.
+    pub fn tag(&mut self) -> Result<u64, CborError> {
+        self.head(MAJOR_TAG)
+    }
+
+    /// Check that every byte has been read.
+    pub fn finish(self) -> Result<(), CborError> {
+        if self.offset != self.bytes.len() {
+            return Err(CborError::TrailingBytes);
+        }
+        Ok(())
+    }
+}
+
+/// The protected header of every `SignedIssuerParameters`.
+fn protected_header() -> Vec<u8> {
+    let mut v: Vec<u8> = Vec::with_capacity(8);
+
+    push_map(&mut v, 1);
+    push_int(&mut v, COSE_HEADER_ALGORITHM);
+    push_int(&mut v, COSE_ALGORITHM_RISTRETTO_SCHNORR);
+    v
+}
+
+/// The `Sig_structure` which is signed for a `COSE_Sign1` with no external
+/// authenticated data.
+fn signature_structure(protected: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut v: Vec<u8> = Vec::with_capacity(24 + protected.len() + payload.len());
+
+    push_array(&mut v, 4);
+    push_text(&mut v, "Signature1");
+    push_bytes(&mut v, protected);
+    push_bytes(&mut v, &[]);
+    push_bytes(&mut v, payload);
+    v
+}
+
+/// An issuer's parameters, signed as a `COSE_Sign1` by a federation's
+/// `RegistryKeypair`.
+#[derive(Clone, Debug)]
+pub struct SignedIssuerParameters {
+    parameters: IssuerParameters,
+    /// An identifier for the key which signed the parameters, e.g. the
+    /// federation's name.
+    pub key_id: Vec<u8>,
+    pub signature: RegistrySignature,
+}
+
+impl SignedIssuerParameters {
+    /// Sign some issuer `parameters` with the federation's `keypair`, which
+    /// is identified to verifiers by `key_id`.
+    pub fn sign<R>(
+        parameters: IssuerParameters,
+        key_id: &[u8],
+        keypair: &RegistryKeypair,
+        csprng: &mut R,
+    ) -> SignedIssuerParameters
+    where
+        R: RngCore + CryptoRng,
+    {
+        let message: Vec<u8> = signature_structure(&protected_header(), &parameters.to_bytes());
+        let signature: RegistrySignature = keypair.sign(COSE_SIGNATURE_LABEL, &message, csprng);
+
+        SignedIssuerParameters { parameters, key_id: key_id.to_vec(), signature }
+    }
+
+    /// Verify the signature with the federation's `public` key.
+    ///
+    /// # Returns
+    ///
+    /// The signed `IssuerParameters`.
+    pub fn verify(&self, public: &RistrettoPoint) -> Result<&IssuerParameters, CborError> {
+        let message: Vec<u8> = signature_structure(&protected_header(), &self.parameters.to_bytes());
+
+        if self.signature.verify(COSE_SIGNATURE_LABEL, public, &message) {
+            Ok(&self.parameters)
+        } else {
+            Err(CborError::VerificationFailure)
+        }
+    }
+}
+
+impl Cbor for SignedIssuerParameters {
+    fn to_cbor(&self) -> Vec<u8> {
+        let payload: Vec<u8> = self.parameters.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(96 + self.key_id.len() + payload.len());
+
+        push_tag(&mut v, COSE_SIGN1_TAG);
+        push_array(&mut v, 4);
+        push_bytes(&mut v, &protected_header());
+        push_map(&mut v, 1);
+        push_int(&mut v, COSE_HEADER_KEY_ID);
+        push_bytes(&mut v, &self.key_id);
+        push_bytes(&mut v, &payload);
+        push_bytes(&mut v, &self.signature.to_bytes());
+        v
+    }
+
+    fn from_cbor(bytes: &[u8]) -> Result<SignedIssuerParameters, CborError> {
+        let mut decoder: Decoder = Decoder::new(bytes);
+
+        if decoder.tag()? != COSE_SIGN1_TAG || decoder.array()? != 4 {
+            return Err(CborError::UnexpectedType);
+        }
+        // There is only one algorithm, and so only one valid protected header.
+        if decoder.bytes()? != &protected_header()[..] {
+            return Err(CborError::UnknownAlgorithm);
+        }
+        if decoder.map()? != 1 || decoder.int()? != COSE_HEADER_KEY_ID {
+            return Err(CborError::UnexpectedType);
+        }
+
+        let key_id: Vec<u8> = decoder.bytes()?.to_vec();
+        let parameters = match IssuerParameters::from_bytes(decoder.bytes()?) {
+            Ok(x)  => x,
+            Err(_) => return Err(CborError::InvalidValue),
+        };
+        let signature = match RegistrySignature::from_bytes(decoder.bytes()?) {
+            Ok(x)  => x,
+            Err(_) => return Err(CborError::InvalidValue),
+        };
+
+        decoder.finish()?;
+
+        Ok(SignedIssuerParameters { parameters, key_id, signature })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use credential::CredentialPresentation;
+    use issuer::Issuer;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    #[test]
+    fn heads_are_deterministic() {
+        let mut v: Vec<u8> = Vec::new();
+
+        push_int(&mut v, 23);
+        push_int(&mut v, 24);
+        push_int(&mut v, -1);
+        push_int(&mut v, 0x1_0000);
+        assert!(v == [0x17, 0x18, 0x18, 0x20, 0x1a, 0x00, 0x01, 0x00, 0x00]);
+
+        let mut decoder: Decoder = Decoder::new(&v);
+
+        assert!(decoder.int() == Ok(23));
+        assert!(decoder.int() == Ok(24));
+        assert!(decoder.int() == Ok(-1));
+        assert!(decoder.int() == Ok(0x1_0000));
+        assert!(decoder.finish().is_ok());
+
+        // 23 in a one-byte argument, and an indefinite-length byte string.
+        assert!(Decoder::new(&[0x18, 0x17]).int() == Err(CborError::NonDeterministicEncoding));
+        assert!(Decoder::new(&[0x5f]).bytes() == Err(CborError::NonDeterministicEncoding));
+        assert!(Decoder::new(&[0x43, 0x00]).bytes() == Err(CborError::Truncated));
+    }
+
+    #[test]
+    fn wire_types_round_trip() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let encoded: Vec<u8> = system_parameters.to_cbor();
+
+        assert!(SystemParameters::from_cbor(&encoded).unwrap().to_bytes() == system_parameters.to_bytes());
+        assert!(encoded.len() == system_parameters.to_bytes().len() + 2);
+        assert!(CredentialPresentation::from_cbor(&encoded).is_err());
+
+        let mut trailing: Vec<u8> = encoded.clone();
+
+        trailing.push(0);
+        assert!(SystemParameters::from_cbor(&trailing).err() == Some(CborError::TrailingBytes));
+
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        assert!(Issuer::from_cbor(&issuer.to_cbor()).is_ok());
+    }
+
+    #[test]
+    fn signed_issuer_parameters() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let federation = RegistryKeypair::generate(&mut rng);
+        let other = RegistryKeypair::generate(&mut rng);
+        let signed = SignedIssuerParameters::sign(issuer.get_issuer_parameters(), b"federation",
+                                                  &federation, &mut rng);
+        let encoded: Vec<u8> = signed.to_cbor();
+
+        assert!(encoded[0] == 0xd2);
+
+        let decoded = SignedIssuerParameters::from_cbor(&encoded).unwrap();
+
+        assert!(&decoded.key_id[..] == &b"federation"[..]);
+        assert!(decoded.verify(&federation.public).is_ok());
+        assert!(decoded.verify(&other.public).err() == Some(CborError::VerificationFailure));
+    }
+}
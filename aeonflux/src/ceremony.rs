@@ -0,0 +1,629 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Key ceremonies, in which several operators contribute entropy to the
+//! generation of `SystemParameters`.
+//!
+//! The basepoint `h` must be chosen so that nobody knows `log_g(h)`.
+//! `SystemParameters::hunt_and_peck()` does so honestly, but whoever runs it
+//! could instead pick `h = x·g` for some `x` they keep, and so forge
+//! credentials.  In a ceremony, no single operator chooses `h`:
+//!
+//! 1. Each operator draws some entropy as a `Contributor`, and publishes a
+//!    `CeremonyCommitment` to it, signed with their `RegistryKeypair`.
+//! 2. Once every commitment is in, each operator reveals their entropy in a
+//!    `CeremonyReveal`.
+//! 3. The entropy is hashed, in the order the commitments were made, into a
+//!    chain of states, and `h` is hashed to the group from the last state.
+//!
+//! Because every operator is bound to their entropy before seeing anyone
+//! else's, `h` is uniformly random so long as any one operator is honest.
+//! The last operator to reveal may still refuse to, and so force the
+//! ceremony to be run again, but cannot choose the result.
+//!
+//! The ceremony produces a `CeremonyTranscript`, recording each step and the
+//! state after it, which anyone may `verify()` to recompute the parameters
+//! and to check which operators took part.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use errors::CeremonyError;
+use errors::Error;
+use errors::TrustError;
+use hashing::Hasher;
+use hashing::SIZEOF_HASHED_KEY;
+use parameters::SystemParameters;
+use secret::Secret;
+use trust::SIZEOF_REGISTRY_SIGNATURE;
+use trust::RegistryKeypair;
+use trust::RegistrySignature;
+
+/// The maximum length of a ceremony's name.
+pub const MAXIMUM_SIZEOF_CEREMONY_NAME: usize = 255;
+
+/// The size of each operator's entropy.
+pub const SIZEOF_CEREMONY_ENTROPY: usize = 32;
+
+/// The size of a `CeremonyCommitment`.
+pub const SIZEOF_CEREMONY_COMMITMENT: usize = 32 + SIZEOF_HASHED_KEY + SIZEOF_REGISTRY_SIGNATURE;
+
+/// The size of a `CeremonyReveal`.
+pub const SIZEOF_CEREMONY_REVEAL: usize = 32 + SIZEOF_CEREMONY_ENTROPY;
+
+/// The size of a `CeremonyStep`.
+pub const SIZEOF_CEREMONY_STEP: usize = SIZEOF_CEREMONY_COMMITMENT + SIZEOF_CEREMONY_ENTROPY + SIZEOF_HASHED_KEY;
+
+/// The transcript label for signatures over a `CeremonyCommitment`.
+const COMMITMENT_SIGNATURE_LABEL: &'static [u8] = b"AEONFLUX CEREMONY COMMITMENT";
+
+fn check_name(name: &[u8]) -> Result<(), CeremonyError> {
+    if name.is_empty() || name.len() > MAXIMUM_SIZEOF_CEREMONY_NAME {
+        return Err(CeremonyError::InvalidName);
+    }
+    Ok(())
+}
+
+fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, CeremonyError> {
+    match CompressedRistretto::from_slice(bytes).decompress() {
+        Some(x) => Ok(x),
+        None    => Err(CeremonyError::PointDecompressionError),
+    }
+}
+
+/// Hash an operator's `entropy` into a commitment within the ceremony `name`.
+fn commitment_digest(name: &[u8], operator: &RistrettoPoint, entropy: &[u8]) -> [u8; SIZEOF_HASHED_KEY] {
+    let mut h = Hasher::new(b"aeonflux ceremony commitment v1");
+
+    h.input_u8_prefixed(name).input(operator.compress().as_bytes()).input(entropy);
+    h.finalize_key()
+}
+
+/// The message an operator signs to publish their commitment.
+fn commitment_message(name: &[u8], digest: &[u8; SIZEOF_HASHED_KEY]) -> Vec<u8> {
+    let mut v: Vec<u8> = Vec::with_capacity(1 + name.len() + SIZEOF_HASHED_KEY);
+
+    v.push(name.len() as u8);
+    v.extend(name);
+    v.extend(digest.iter());
+    v
+}
+
+/// One operator's entropy, kept secret until every commitment is in.
+#[derive(Clone, Debug)]
+pub struct Contributor {
+    operator: RistrettoPoint,
+    entropy: Secret<[u8; SIZEOF_CEREMONY_ENTROPY]>,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for Contributor {
+    fn drop(&mut self) {
+        self.entropy.clear();
+    }
+}
+
+impl Contributor {
+    /// Draw fresh entropy for the operator whose key is `keypair`.
+    pub fn new<R>(keypair: &RegistryKeypair, csprng: &mut R) -> Contributor
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut entropy = [0u8; SIZEOF_CEREMONY_ENTROPY];
+
+        csprng.fill_bytes(&mut entropy);
+
+        Contributor { operator: keypair.public, entropy: Secret::new(entropy) }
+    }
+
+    /// Commit to this entropy within the ceremony `name`.
+    ///
+    /// # Errors
+    ///
+    /// * `CeremonyError::InvalidName` if `name` is empty or longer than
+    ///   `MAXIMUM_SIZEOF_CEREMONY_NAME`.
+    /// * `CeremonyError::UnknownOperator` if `keypair` is not the one this
+    ///   entropy was drawn for.
+    pub fn commit<R>(
+        &self,
+        name: &[u8],
+        keypair: &RegistryKeypair,
+        csprng: &mut R,
+    ) -> Result<CeremonyCommitment, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        check_name(name)?;
+
+        if keypair.public != self.operator {
+            return Err(CeremonyError::UnknownOperator.into());
+        }
+
+        let digest = commitment_digest(name, &self.operator, self.entropy.expose_secret());
+        let signature = keypair.sign(COMMITMENT_SIGNATURE_LABEL, &commitment_message(name, &digest), csprng);
+
+        Ok(CeremonyCommitment { operator: self.operator, digest, signature })
+    }
+
+    /// Reveal this entropy, once every operator has committed.
+    pub fn reveal(&self) -> CeremonyReveal {
+        CeremonyReveal { operator: self.operator, entropy: *self.entropy.expose_secret() }
+    }
+}
+
+/// An operator's signed commitment to their entropy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CeremonyCommitment {
+    /// The operator's public key.
+    pub operator: RistrettoPoint,
+    pub digest: [u8; SIZEOF_HASHED_KEY],
+    pub signature: RegistrySignature,
+}
+
+impl CeremonyCommitment {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CeremonyCommitment, CeremonyError> {
+        if bytes.len() != SIZEOF_CEREMONY_COMMITMENT {
+            return Err(CeremonyError::WrongNumberOfBytes);
+        }
+
+        let operator: RistrettoPoint = read_point(&bytes[..32])?;
+        let mut digest = [0u8; SIZEOF_HASHED_KEY];
+
+        digest.copy_from_slice(&bytes[32..32 + SIZEOF_HASHED_KEY]);
+
+        let signature = match RegistrySignature::from_bytes(&bytes[32 + SIZEOF_HASHED_KEY..]) {
+            Ok(x)                              => x,
+            Err(TrustError::ScalarFormatError) => return Err(CeremonyError::ScalarFormatError),
+            Err(_)                             => return Err(CeremonyError::WrongNumberOfBytes),
+        };
+
+        Ok(CeremonyCommitment { operator, digest, signature })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CEREMONY_COMMITMENT);
+
+        v.extend(self.operator.compress().as_bytes());
+        v.extend(self.digest.iter());
+        v.extend(self.signature.to_bytes());
+        v
+    }
+
+    /// Whether the operator signed this commitment within the ceremony `name`.
+    pub fn verify(&self, name: &[u8]) -> bool {
+        self.signature.verify(COMMITMENT_SIGNATURE_LABEL, &self.operator, &commitment_message(name, &self.digest))
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CeremonyCommitment, "A valid byte sequence representing a CeremonyCommitment");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CeremonyCommitment);
+impl_try_from_bytes!(CeremonyCommitment, CeremonyError);
+
+/// An operator's entropy, revealed after every commitment is in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CeremonyReveal {
+    /// The operator's public key.
+    pub operator: RistrettoPoint,
+    pub entropy: [u8; SIZEOF_CEREMONY_ENTROPY],
+}
+
+impl CeremonyReveal {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CeremonyReveal, CeremonyError> {
+        if bytes.len() != SIZEOF_CEREMONY_REVEAL {
+            return Err(CeremonyError::WrongNumberOfBytes);
+        }
+
+        let operator: RistrettoPoint = read_point(&bytes[..32])?;
+        let mut entropy = [0u8; SIZEOF_CEREMONY_ENTROPY];
+
+        entropy.copy_from_slice(&bytes[32..]);
+
+        Ok(CeremonyReveal { operator, entropy })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CEREMONY_REVEAL);
+
+        v.extend(self.operator.compress().as_bytes());
+        v.extend(self.entropy.iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CeremonyReveal, "A valid byte sequence representing a CeremonyReveal");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CeremonyReveal);
+impl_try_from_bytes!(CeremonyReveal, CeremonyError);
+
+/// One operator's step in a `CeremonyTranscript`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CeremonyStep {
+    pub commitment: CeremonyCommitment,
+    /// The entropy which opens the commitment.
+    pub entropy: [u8; SIZEOF_CEREMONY_ENTROPY],
+    /// The state of the ceremony after this step.
+    pub state: [u8; SIZEOF_HASHED_KEY],
+}
+
+impl CeremonyStep {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CeremonyStep, CeremonyError> {
+        if bytes.len() != SIZEOF_CEREMONY_STEP {
+            return Err(CeremonyError::WrongNumberOfBytes);
+        }
+
+        let split: usize = SIZEOF_CEREMONY_COMMITMENT + SIZEOF_CEREMONY_ENTROPY;
+        let commitment = CeremonyCommitment::from_bytes(&bytes[..SIZEOF_CEREMONY_COMMITMENT])?;
+        let mut entropy = [0u8; SIZEOF_CEREMONY_ENTROPY];
+        let mut state = [0u8; SIZEOF_HASHED_KEY];
+
+        entropy.copy_from_slice(&bytes[SIZEOF_CEREMONY_COMMITMENT..split]);
+        state.copy_from_slice(&bytes[split..]);
+
+        Ok(CeremonyStep { commitment, entropy, state })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CEREMONY_STEP);
+
+        v.extend(self.commitment.to_bytes());
+        v.extend(self.entropy.iter());
+        v.extend(self.state.iter());
+        v
+    }
+}
+
+/// The state of a ceremony once every commitment is in, before any entropy.
+fn initial_state(name: &[u8], commitments: &[&CeremonyCommitment]) -> [u8; SIZEOF_HASHED_KEY] {
+    let mut h = Hasher::new(b"aeonflux ceremony v1");
+
+    h.input_u8_prefixed(name);
+
+    for commitment in commitments.iter() {
+        h.input(commitment.operator.compress().as_bytes()).input(&commitment.digest);
+    }
+    h.finalize_key()
+}
+
+/// The state of a ceremony after an `operator` contributes their `entropy`.
+fn next_state(
+    state: &[u8; SIZEOF_HASHED_KEY],
+    operator: &RistrettoPoint,
+    entropy: &[u8; SIZEOF_CEREMONY_ENTROPY],
+) -> [u8; SIZEOF_HASHED_KEY]
+{
+    let mut h = Hasher::new(b"aeonflux ceremony step v1");
+
+    h.input(state).input(operator.compress().as_bytes()).input(entropy);
+    h.finalize_key()
+}
+
+/// A verifiable record of a finished ceremony.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CeremonyTranscript {
+    /// The name of the ceremony, e.g. the deployment and year.
+    pub name: Vec<u8>,
+    /// Each operator's step, in the order they committed.
+    pub steps: Vec<CeremonyStep>,
+}
+
+impl CeremonyTranscript {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CeremonyTranscript, CeremonyError> {
+        if bytes.is_empty() || bytes.len() < 1 + bytes[0] as usize {
+            return Err(CeremonyError::WrongNumberOfBytes);
+        }
+
+        let length: usize = bytes[0] as usize;
+        let name: Vec<u8> = bytes[1..1 + length].to_vec();
+        let rest: &[u8] = &bytes[1 + length..];
+
+        if rest.len() % SIZEOF_CEREMONY_STEP != 0 {
+            return Err(CeremonyError::WrongNumberOfBytes);
+        }
+
+        let mut steps: Vec<CeremonyStep> = Vec::with_capacity(rest.len() / SIZEOF_CEREMONY_STEP);
+
+        for chunk in rest.chunks(SIZEOF_CEREMONY_STEP) {
+            steps.push(CeremonyStep::from_bytes(chunk)?);
+        }
+
+        Ok(CeremonyTranscript { name, steps })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(1 + self.name.len() + self.steps.len() * SIZEOF_CEREMONY_STEP);
+
+        v.push(self.name.len() as u8);
+        v.extend(self.name.iter());
+
+        for step in self.steps.iter() {
+            v.extend(step.to_bytes());
+        }
+        v
+    }
+
+    /// The public keys of the operators who took part, in order.
+    pub fn operators(&self) -> Vec<RistrettoPoint> {
+        self.steps.iter().map(|step| step.commitment.operator).collect()
+    }
+
+    /// Check every step of this transcript, and recompute the parameters it
+    /// produced.
+    ///
+    /// # Errors
+    ///
+    /// * `CeremonyError::InvalidName` if the name is empty or too long.
+    /// * `CeremonyError::Incomplete` if no operator took part.
+    /// * `CeremonyError::Duplicate` if an operator took part twice.
+    /// * `CeremonyError::VerificationFailure` if a commitment was not signed
+    ///   by its operator.
+    /// * `CeremonyError::Mismatch` if some entropy does not open its
+    ///   commitment, or a recorded state was not computed from the steps
+    ///   before it.
+    pub fn verify(&self) -> Result<SystemParameters, Error> {
+        check_name(&self.name)?;
+
+        if self.steps.is_empty() {
+            return Err(CeremonyError::Incomplete.into());
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            if self.steps[..i].iter().any(|x| x.commitment.operator == step.commitment.operator) {
+                return Err(CeremonyError::Duplicate.into());
+            }
+            if !step.commitment.verify(&self.name) {
+                return Err(CeremonyError::VerificationFailure.into());
+            }
+            if commitment_digest(&self.name, &step.commitment.operator, &step.entropy) != step.commitment.digest {
+                return Err(CeremonyError::Mismatch.into());
+            }
+        }
+
+        let commitments: Vec<&CeremonyCommitment> = self.steps.iter().map(|step| &step.commitment).collect();
+        let mut state = initial_state(&self.name, &commitments);
+
+        for step in self.steps.iter() {
+            state = next_state(&state, &step.commitment.operator, &step.entropy);
+
+            if state != step.state {
+                return Err(CeremonyError::Mismatch.into());
+            }
+        }
+
+        let mut h = Hasher::new(b"aeonflux ceremony generator v1");
+
+        h.input(&state);
+
+        let H: RistrettoPoint = h.finalize_point();
+
+        // Only as likely as guessing the discrete log of h, but still.
+        if H == RISTRETTO_BASEPOINT_POINT {
+            return Err(CeremonyError::Mismatch.into());
+        }
+
+        Ok(SystemParameters::from(H))
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CeremonyTranscript, "A valid byte sequence representing a CeremonyTranscript");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CeremonyTranscript);
+impl_try_from_bytes!(CeremonyTranscript, CeremonyError);
+
+/// A coordinator's view of a ceremony in progress.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ceremony {
+    name: Vec<u8>,
+    commitments: Vec<CeremonyCommitment>,
+    reveals: Vec<Option<[u8; SIZEOF_CEREMONY_ENTROPY]>>,
+    revealing: bool,
+}
+
+impl Ceremony {
+    /// Begin a ceremony called `name`.
+    ///
+    /// # Errors
+    ///
+    /// * `CeremonyError::InvalidName` if `name` is empty or longer than
+    ///   `MAXIMUM_SIZEOF_CEREMONY_NAME`.
+    pub fn new(name: &[u8]) -> Result<Ceremony, Error> {
+        check_name(name)?;
+
+        Ok(Ceremony { name: name.to_vec(), commitments: Vec::new(), reveals: Vec::new(), revealing: false })
+    }
+
+    /// The name of this ceremony.
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// The commitments made so far, in order.
+    pub fn commitments(&self) -> &[CeremonyCommitment] {
+        &self.commitments
+    }
+
+    /// Accept an operator's commitment.
+    ///
+    /// # Errors
+    ///
+    /// * `CeremonyError::WrongPhase` if some entropy has already been
+    ///   revealed.
+    /// * `CeremonyError::Duplicate` if the operator has already committed.
+    /// * `CeremonyError::VerificationFailure` if the commitment was not
+    ///   signed by its operator within this ceremony.
+    pub fn commit(&mut self, commitment: &CeremonyCommitment) -> Result<(), Error> {
+        if self.revealing {
+            return Err(CeremonyError::WrongPhase.into());
+        }
+        if self.commitments.iter().any(|x| x.operator == commitment.operator) {
+            return Err(CeremonyError::Duplicate.into());
+        }
+        if !commitment.verify(&self.name) {
+            return Err(CeremonyError::VerificationFailure.into());
+        }
+
+        self.commitments.push(*commitment);
+        self.reveals.push(None);
+
+        Ok(())
+    }
+
+    /// Accept an operator's revealed entropy, which closes the ceremony to
+    /// further commitments.
+    ///
+    /// # Errors
+    ///
+    /// * `CeremonyError::UnknownOperator` if the operator did not commit.
+    /// * `CeremonyError::Duplicate` if the operator has already revealed.
+    /// * `CeremonyError::Mismatch` if the entropy does not open the
+    ///   operator's commitment.
+    pub fn reveal(&mut self, reveal: &CeremonyReveal) -> Result<(), Error> {
+        let index: usize = match self.commitments.iter().position(|x| x.operator == reveal.operator) {
+            Some(x) => x,
+            None    => return Err(CeremonyError::UnknownOperator.into()),
+        };
+
+        if self.reveals[index].is_some() {
+            return Err(CeremonyError::Duplicate.into());
+        }
+        if commitment_digest(&self.name, &reveal.operator, &reveal.entropy) != self.commitments[index].digest {
+            return Err(CeremonyError::Mismatch.into());
+        }
+
+        self.revealing = true;
+        self.reveals[index] = Some(reveal.entropy);
+
+        Ok(())
+    }
+
+    /// Finish the ceremony, once every operator has revealed.
+    ///
+    /// # Returns
+    ///
+    /// The `SystemParameters`, and the transcript from which anyone may
+    /// recompute them.
+    ///
+    /// # Errors
+    ///
+    /// * `CeremonyError::Incomplete` if no operator committed, or some
+    ///   operator has not revealed.
+    pub fn finish(&self) -> Result<(SystemParameters, CeremonyTranscript), Error> {
+        if self.commitments.is_empty() || self.reveals.iter().any(|x| x.is_none()) {
+            return Err(CeremonyError::Incomplete.into());
+        }
+
+        let commitments: Vec<&CeremonyCommitment> = self.commitments.iter().collect();
+        let mut state = initial_state(&self.name, &commitments);
+        let mut steps: Vec<CeremonyStep> = Vec::with_capacity(self.commitments.len());
+
+        for (commitment, entropy) in self.commitments.iter().zip(self.reveals.iter()) {
+            let entropy = entropy.unwrap();
+
+            state = next_state(&state, &commitment.operator, &entropy);
+            steps.push(CeremonyStep { commitment: *commitment, entropy, state });
+        }
+
+        let transcript = CeremonyTranscript { name: self.name.clone(), steps };
+
+        Ok((transcript.verify()?, transcript))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn ceremonies_produce_verifiable_transcripts() {
+        let mut rng = thread_rng();
+        let keypairs: Vec<RegistryKeypair> = (0..3).map(|_| RegistryKeypair::generate(&mut rng)).collect();
+        let contributors: Vec<Contributor> = keypairs.iter().map(|k| Contributor::new(k, &mut rng)).collect();
+        let mut ceremony = Ceremony::new(b"aeonflux test ceremony").unwrap();
+
+        for (contributor, keypair) in contributors.iter().zip(keypairs.iter()) {
+            let commitment = contributor.commit(ceremony.name(), keypair, &mut rng).unwrap();
+
+            ceremony.commit(&CeremonyCommitment::from_bytes(&commitment.to_bytes()).unwrap()).unwrap();
+        }
+        assert!(ceremony.finish().err() == Some(CeremonyError::Incomplete.into()));
+
+        // Operators may reveal in any order, but not commit afterwards.
+        for contributor in contributors.iter().rev() {
+            ceremony.reveal(&CeremonyReveal::from_bytes(&contributor.reveal().to_bytes()).unwrap()).unwrap();
+        }
+
+        let late = RegistryKeypair::generate(&mut rng);
+        let commitment = Contributor::new(&late, &mut rng).commit(b"aeonflux test ceremony", &late, &mut rng).unwrap();
+
+        assert!(ceremony.commit(&commitment).err() == Some(CeremonyError::WrongPhase.into()));
+
+        let (system_parameters, transcript) = ceremony.finish().unwrap();
+        let transcript = CeremonyTranscript::from_bytes(&transcript.to_bytes()).unwrap();
+
+        assert!(transcript.verify() == Ok(system_parameters));
+        assert!(transcript.operators() == keypairs.iter().map(|k| k.public).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn tampered_ceremonies_are_refused() {
+        let mut rng = thread_rng();
+        let alice = RegistryKeypair::generate(&mut rng);
+        let mallory = RegistryKeypair::generate(&mut rng);
+        let a = Contributor::new(&alice, &mut rng);
+        let m = Contributor::new(&mallory, &mut rng);
+        let mut ceremony = Ceremony::new(b"aeonflux test ceremony").unwrap();
+
+        // A commitment made within another ceremony is refused.
+        let elsewhere = a.commit(b"another ceremony", &alice, &mut rng).unwrap();
+
+        assert!(ceremony.commit(&elsewhere).err() == Some(CeremonyError::VerificationFailure.into()));
+
+        ceremony.commit(&a.commit(ceremony.name(), &alice, &mut rng).unwrap()).unwrap();
+        ceremony.commit(&m.commit(ceremony.name(), &mallory, &mut rng).unwrap()).unwrap();
+
+        // Mallory cannot change their entropy after seeing Alice's.
+        let mut changed = m.reveal();
+
+        ceremony.reveal(&a.reveal()).unwrap();
+        changed.entropy[0] ^= 1;
+
+        assert!(ceremony.reveal(&changed).err() == Some(CeremonyError::Mismatch.into()));
+
+        ceremony.reveal(&m.reveal()).unwrap();
+
+        let (_, transcript) = ceremony.finish().unwrap();
+        let mut tampered = transcript.clone();
+
+        tampered.steps[1].entropy[0] ^= 1;
+        assert!(tampered.verify().err() == Some(CeremonyError::Mismatch.into()));
+
+        let mut tampered = transcript.clone();
+
+        tampered.steps.swap(0, 1);
+        assert!(tampered.verify().err() == Some(CeremonyError::Mismatch.into()));
+
+        let mut tampered = transcript;
+
+        tampered.steps[1] = tampered.steps[0];
+        assert!(tampered.verify().err() == Some(CeremonyError::Duplicate.into()));
+    }
+}
@@ -0,0 +1,136 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Presentations bound to the connection they were made for.
+//!
+//! An attacker in the middle of a connection, or a verifier passing on what
+//! it was shown, could otherwise replay a presentation over a connection of
+//! their own.  Both ends of a secure channel can compute a value unique to
+//! it, such as a TLS exporter value or a Noise handshake hash, which nobody
+//! in the middle can choose.  `User::show_bound()` binds a presentation's
+//! proof, through its transcript, to such a `ChannelBinding`, and
+//! `Issuer::verify_bound()` accepts it only with the binding of the
+//! connection it arrived over, so that a stolen presentation is worthless
+//! on any other.
+//!
+//! For TLS, the binding is the 32-byte exporter value with the label
+//! `TLS_EXPORTER_LABEL` and an empty context, as in RFC 9266.  For Noise, it
+//! is the handshake hash once the handshake is complete.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use merlin::Transcript;
+
+/// The exporter label for TLS channel bindings, from RFC 9266.
+pub const TLS_EXPORTER_LABEL: &'static str = "EXPORTER-Channel-Binding";
+
+/// The length of a TLS exporter channel binding, from RFC 9266.
+pub const SIZEOF_TLS_EXPORTER: usize = 32;
+
+/// A value which both ends of a secure channel, and no one else, agree on.
+///
+/// Each kind of binding is bound under its own label, so that a value taken
+/// from one kind of channel never matches a binding of another kind.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ChannelBinding {
+    kind: &'static [u8],
+    value: Vec<u8>,
+}
+
+impl ChannelBinding {
+    /// A TLS connection's exporter value for `TLS_EXPORTER_LABEL`.
+    pub fn tls_exporter(value: [u8; SIZEOF_TLS_EXPORTER]) -> ChannelBinding {
+        ChannelBinding { kind: b"tls-exporter", value: value.to_vec() }
+    }
+
+    /// A Noise session's handshake `hash`, once the handshake is complete.
+    pub fn noise_handshake_hash(hash: &[u8]) -> ChannelBinding {
+        ChannelBinding { kind: b"noise-handshake-hash", value: hash.to_vec() }
+    }
+
+    /// A `value` from some other `kind` of channel, which must differ from
+    /// the kinds above.
+    pub fn other(kind: &'static [u8], value: &[u8]) -> ChannelBinding {
+        ChannelBinding { kind, value: value.to_vec() }
+    }
+
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+}
+
+/// Bind a `channel`, if any, into a presentation's `transcript`, so that a
+/// presentation verifies only over the connection it was made for.
+/// Presentations without a binding leave their transcripts as they were.
+pub(crate) fn bind_channel(transcript: &mut Transcript, channel: Option<&ChannelBinding>) {
+    if let Some(channel) = channel {
+        transcript.commit_bytes(b"channel-binding-kind", channel.kind);
+        transcript.commit_bytes(b"channel-binding", &channel.value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use errors::CredentialError;
+    use issuer::Issuer;
+    use nonces::Nonces;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use test_utils::holder;
+
+    #[test]
+    fn presentations_verify_only_over_their_own_connection() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let user = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+        let ours = ChannelBinding::tls_exporter([7u8; SIZEOF_TLS_EXPORTER]);
+        let theirs = ChannelBinding::tls_exporter([8u8; SIZEOF_TLS_EXPORTER]);
+
+        let shown = user.show_bound(&ours, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(issuer.verify_bound(&shown, &ours).is_ok());
+
+        // Replayed over another connection, or stripped of its binding.
+        assert!(issuer.verify_bound(&shown, &theirs) == Err(CredentialError::MacVerification.into()));
+        assert!(issuer.verify(&shown) == Err(CredentialError::MacVerification.into()));
+
+        // An unbound presentation is not accepted as a bound one.
+        let unbound = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(issuer.verify_bound(&unbound, &ours) == Err(CredentialError::MacVerification.into()));
+    }
+
+    #[test]
+    fn kinds_of_binding_are_separated() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let user = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+        let value = [3u8; SIZEOF_TLS_EXPORTER];
+        let tls = ChannelBinding::tls_exporter(value);
+        let noise = ChannelBinding::noise_handshake_hash(&value);
+
+        assert!(tls.value() == noise.value());
+        assert!(tls != noise);
+
+        let shown = user.show_bound(&noise, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(issuer.verify_bound(&shown, &noise).is_ok());
+        assert!(issuer.verify_bound(&shown, &tls).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! The core shared by `qr` and `fragment`: splitting a message into numbered
+//! pieces, and collecting them, in any order, until it is whole.
+//!
+//! Each transport frames pieces in its own way, with its own message
+//! identifier, checksum, and check of the reassembled message, and encodes
+//! their index and total with `push_position()`.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+/// The length of an encoded index and total in bytes.
+pub const SIZEOF_POSITION: usize = 2 + 2;
+
+/// The maximum number of pieces a message may be split into.
+pub const MAXIMUM_PIECES: usize = 0xffff;
+
+/// Why a piece could not be added to a `Reassembler`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Rejection {
+    /// The piece's index was not below the total number of pieces.
+    InvalidIndex,
+    /// The piece belonged to a different message than the others.
+    MismatchedMessage,
+}
+
+/// Append a piece's `index` and the `total` number of pieces to `v`.
+pub fn push_position(v: &mut Vec<u8>, index: u16, total: u16) {
+    v.push(index as u8);
+    v.push((index >> 8) as u8);
+    v.push(total as u8);
+    v.push((total >> 8) as u8);
+}
+
+/// Read a piece's index and total from the first `SIZEOF_POSITION` `bytes`.
+///
+/// # Returns
+///
+/// The index and total, or `Rejection::InvalidIndex` unless the index is
+/// below the total.
+pub fn read_position(bytes: &[u8]) -> Result<(u16, u16), Rejection> {
+    let index: u16 = (bytes[0] as u16) | ((bytes[1] as u16) << 8);
+    let total: u16 = (bytes[2] as u16) | ((bytes[3] as u16) << 8);
+
+    if index >= total {
+        return Err(Rejection::InvalidIndex);
+    }
+    Ok((index, total))
+}
+
+/// Split a `message` into pieces of at most `data_size` bytes each.  Even an
+/// empty message has one piece.
+///
+/// # Returns
+///
+/// The message's pieces, in order, or `None` if it would need more than
+/// `MAXIMUM_PIECES` pieces, or if `data_size` is zero.
+pub fn split(message: &[u8], data_size: usize) -> Option<Vec<&[u8]>> {
+    if data_size == 0 {
+        return None;
+    }
+
+    let total: usize = if message.len() == 0 { 1 } else { (message.len() + data_size - 1) / data_size };
+
+    if total > MAXIMUM_PIECES {
+        return None;
+    }
+
+    Some((0..total).map(|index| {
+        let start: usize = index * data_size;
+        let end: usize = if start + data_size > message.len() { message.len() } else { start + data_size };
+
+        &message[start..end]
+    }).collect())
+}
+
+/// Collects the pieces of a message, identified by an `Id`, in any order,
+/// until it is complete.
+#[derive(Clone, Debug)]
+pub struct Reassembler<Id> {
+    message_id: Option<Id>,
+    pieces: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+impl<Id> Default for Reassembler<Id> {
+    fn default() -> Reassembler<Id> {
+        Reassembler { message_id: None, pieces: Vec::new(), received: 0 }
+    }
+}
+
+impl<Id: Copy + Eq> Reassembler<Id> {
+    pub fn new() -> Reassembler<Id> {
+        Reassembler::default()
+    }
+
+    /// The identifier of the message being reassembled, once any piece has
+    /// been received.
+    pub fn message_id(&self) -> Option<Id> {
+        self.message_id
+    }
+
+    /// The number of distinct pieces received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// The total number of pieces in the message, once any has been received.
+    pub fn total(&self) -> Option<usize> {
+        match self.message_id {
+            Some(_) => Some(self.pieces.len()),
+            None    => None,
+        }
+    }
+
+    /// The indices of the pieces not yet received, once any has been.
+    pub fn missing(&self) -> Vec<u16> {
+        self.pieces.iter().enumerate()
+            .filter(|&(_, piece)| piece.is_none())
+            .map(|(index, _)| index as u16)
+            .collect()
+    }
+
+    /// Forget all pieces received so far, e.g. to receive a different message.
+    pub fn reset(&mut self) {
+        self.message_id = None;
+        self.pieces.clear();
+        self.received = 0;
+    }
+
+    /// Add the piece at `index` of `total` of the message `message_id`.
+    /// Pieces which were already received are ignored.
+    ///
+    /// # Errors
+    ///
+    /// * `Rejection::MismatchedMessage` if the piece belongs to a different
+    ///   message than those already received.
+    /// * `Rejection::InvalidIndex` if the piece's index is not below its total.
+    ///
+    /// # Returns
+    ///
+    /// The reassembled message, if this was its last missing piece, in which
+    /// case the reassembler is reset.  The caller should check the message
+    /// against its identifier or digest.
+    pub fn push(
+        &mut self,
+        message_id: Id,
+        index: u16,
+        total: u16,
+        data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, Rejection>
+    {
+        match self.message_id {
+            Some(id) => {
+                if id != message_id || self.pieces.len() != total as usize {
+                    return Err(Rejection::MismatchedMessage);
+                }
+            },
+            None => {
+                if index >= total {
+                    return Err(Rejection::InvalidIndex);
+                }
+                self.message_id = Some(message_id);
+                self.pieces.clear();
+                self.pieces.resize(total as usize, None);
+            },
+        }
+
+        let index: usize = index as usize;
+
+        if index >= self.pieces.len() {
+            return Err(Rejection::InvalidIndex);
+        }
+        if self.pieces[index].is_some() {
+            return Ok(None);
+        }
+
+        self.pieces[index] = Some(data);
+        self.received += 1;
+
+        if self.received < self.pieces.len() {
+            return Ok(None);
+        }
+
+        let mut message: Vec<u8> = Vec::new();
+
+        for data in self.pieces.iter() {
+            if let Some(ref x) = *data {
+                message.extend(x.iter());
+            }
+        }
+
+        self.reset();
+
+        Ok(Some(message))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    #[test]
+    fn split_and_reassemble_in_any_order() {
+        let m = message(100);
+        let pieces = split(&m, 16).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        assert!(pieces.len() == 7);
+        assert!(pieces[6].len() == 4);
+
+        for i in [6, 0, 3, 3, 1, 5, 2].iter() {
+            assert!(reassembler.push(1u8, *i, 7, pieces[*i as usize].to_vec()).unwrap().is_none());
+        }
+        assert!(reassembler.missing() == vec![4]);
+        assert!(reassembler.push(1u8, 4, 7, pieces[4].to_vec()).unwrap() == Some(m.clone()));
+        assert!(reassembler.total().is_none());
+
+        assert!(split(&[], 16).unwrap().len() == 1);
+        assert!(split(&m, 0).is_none());
+        assert!(split(&message(MAXIMUM_PIECES + 1), 1).is_none());
+    }
+
+    #[test]
+    fn bad_pieces_are_rejected() {
+        let m = message(100);
+        let pieces = split(&m, 64).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        assert!(reassembler.push(1u8, 2, 2, pieces[0].to_vec()) == Err(Rejection::InvalidIndex));
+        assert!(reassembler.total().is_none());
+
+        reassembler.push(1u8, 0, 2, pieces[0].to_vec()).unwrap();
+
+        assert!(reassembler.push(2u8, 1, 2, pieces[1].to_vec()) == Err(Rejection::MismatchedMessage));
+        assert!(reassembler.push(1u8, 1, 3, pieces[1].to_vec()) == Err(Rejection::MismatchedMessage));
+        assert!(reassembler.message_id() == Some(1u8));
+
+        let mut v = Vec::new();
+
+        push_position(&mut v, 3, 3);
+
+        assert!(read_position(&v) == Err(Rejection::InvalidIndex));
+    }
+}
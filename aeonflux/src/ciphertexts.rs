@@ -0,0 +1,233 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Aggregated proofs that many attribute encryptions are well-formed.
+//!
+//! A user requesting a credential over encrypted attributes must prove that
+//! each ciphertext \\( (E\_{i,0}, E\_{i,1}) = (e\_i B, m\_i B + e\_i D) \\) was
+//! made with their key \\( D \\) from an attribute \\( m\_i \\) they know.
+//! Rather than one `proofs::attributes_blinded` proof per ciphertext, which
+//! would add 128 bytes to a request for every attribute, the ciphertexts are
+//! combined with weights \\( w\_0 = 1, w\_1, \ldots, w\_{n-1} \\) derived by
+//! hashing them all, and a single proof with one shared challenge is made
+//! over
+//!
+//! \\[
+//!     \left(\sum\_i w\_i E\_{i,0}, \sum\_i w\_i E\_{i,1}\right)
+//!         = \left(\Big(\sum\_i w\_i e\_i\Big) B,
+//!                 \Big(\sum\_i w\_i m\_i\Big) B + \Big(\sum\_i w\_i e\_i\Big) D\right),
+//! \\]
+//!
+//! whose responses are the correspondingly combined nonces and attributes.
+//! Since the weights are fixed only once every ciphertext is, a user who did
+//! not know the attribute in any one of them could not answer for the
+//! combination, and the proof is the same size however many ciphertexts it
+//! covers.  With a single ciphertext, its weight is one, and the proof is
+//! exactly the `attributes_blinded` proof over that ciphertext.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use merlin::Transcript;
+
+use credential::EncryptedAttribute;
+use elgamal;
+use elgamal::Plaintexts;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use nonces::Ephemeral;
+use proofs::attributes_blinded;
+
+/// The weights with which the `encryptions` to `D` are combined.
+fn weights(D: &RistrettoPoint, encryptions: &[EncryptedAttribute]) -> Vec<Scalar> {
+    let mut transcript = Transcript::new(b"AEONFLUX CIPHERTEXT WEIGHTS");
+
+    transcript.commit_bytes(b"D", D.compress().as_bytes());
+    transcript.commit_bytes(b"n", &u64_to_bytes(encryptions.len() as u64));
+
+    for encryption in encryptions.iter() {
+        transcript.commit_bytes(b"E", &encryption.to_bytes());
+    }
+
+    let mut weights: Vec<Scalar> = Vec::with_capacity(encryptions.len());
+
+    weights.push(Scalar::one());
+
+    for _ in 1..encryptions.len() {
+        let mut bytes = [0u8; 64];
+
+        transcript.challenge_bytes(b"w", &mut bytes);
+        weights.push(Scalar::from_bytes_mod_order_wide(&bytes));
+    }
+    weights
+}
+
+/// The combination of the `encryptions` under the `weights`.
+fn combine(encryptions: &[EncryptedAttribute], weights: &[Scalar]) -> EncryptedAttribute {
+    elgamal::Encryption {
+        commitment: RistrettoPoint::vartime_multiscalar_mul(weights, encryptions.iter().map(|e| e.commitment)),
+        encryption: RistrettoPoint::vartime_multiscalar_mul(weights, encryptions.iter().map(|e| e.encryption)),
+    }
+}
+
+/// Prove, under a `transcript`, that every one of the `encryptions` was made
+/// with the `key` from the corresponding attribute and nonce in its
+/// `plaintexts`, where `B` is the basepoint of the system parameters.
+///
+/// # Errors
+///
+/// * `CredentialError::WrongNumberOfAttributes` if there are no
+///   encryptions, or not one plaintext and nonce for each.
+pub fn prove(
+    transcript: &mut Transcript,
+    B: &RistrettoPoint,
+    key: &elgamal::Keypair,
+    encryptions: &[EncryptedAttribute],
+    plaintexts: &Plaintexts,
+) -> Result<attributes_blinded::Proof, Error>
+{
+    if encryptions.len() == 0 ||
+       plaintexts.attributes.len() != encryptions.len() ||
+       plaintexts.nonces.iter().count() != encryptions.len()
+    {
+        return Err(CredentialError::WrongNumberOfAttributes.into());
+    }
+
+    let D: RistrettoPoint = key.public.into();
+    let weights: Vec<Scalar> = weights(&D, encryptions);
+    let E: EncryptedAttribute = combine(encryptions, &weights);
+
+    // The combined secrets are cleared with the ephemerals holding them.
+    let e: Ephemeral = Ephemeral::from(weights.iter().zip(plaintexts.nonces.iter())
+                                       .fold(Scalar::zero(), |acc, (w, e)| acc + w * e.as_scalar()));
+    let m: Ephemeral = Ephemeral::from(weights.iter().zip(plaintexts.attributes.iter())
+                                       .fold(Scalar::zero(), |acc, (w, m)| acc + w * m));
+
+    let secrets = attributes_blinded::Secrets {
+        d: key.secret.as_witness(),
+        e0: e.as_witness(),
+        m0: m.as_scalar(),
+    };
+    let publics = attributes_blinded::Publics {
+        B: B,
+        D: &D,
+        encrypted_attribute_0_0: &E.commitment,
+        encrypted_attribute_0_1: &E.encryption,
+    };
+
+    Ok(attributes_blinded::Proof::create(transcript, publics, secrets))
+}
+
+/// Verify, under a `transcript`, a `proof` made with `prove()` that every
+/// one of the `encryptions` was made with the `public_key`.
+///
+/// # Errors
+///
+/// * `CredentialError::WrongNumberOfAttributes` if there are no
+///   encryptions.
+/// * `CredentialError::VerificationFailure` if the proof did not verify.
+pub fn verify(
+    transcript: &mut Transcript,
+    B: &RistrettoPoint,
+    public_key: &elgamal::PublicKey,
+    encryptions: &[EncryptedAttribute],
+    proof: &attributes_blinded::Proof,
+) -> Result<(), Error>
+{
+    if encryptions.len() == 0 {
+        return Err(CredentialError::WrongNumberOfAttributes.into());
+    }
+
+    let D: RistrettoPoint = (*public_key).into();
+    let E: EncryptedAttribute = combine(encryptions, &weights(&D, encryptions));
+
+    let publics = attributes_blinded::Publics {
+        B: B,
+        D: &D,
+        encrypted_attribute_0_0: &E.commitment,
+        encrypted_attribute_0_1: &E.encryption,
+    };
+
+    proof.verify(transcript, publics).or(Err(CredentialError::VerificationFailure.into()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    use bincode::serialize;
+
+    #[test]
+    fn one_proof_covers_many_ciphertexts() {
+        let mut rng = thread_rng();
+        let B = RISTRETTO_BASEPOINT_POINT;
+        let key = elgamal::Keypair::generate(&mut rng);
+        let attributes: Vec<Scalar> = (0..16).map(|_| Scalar::random(&mut rng)).collect();
+        let (encryptions, plaintexts) = key.public.encrypt_attributes(&attributes, &mut rng);
+
+        let proof = prove(&mut Transcript::new(b"test"), &B, &key, &encryptions, &plaintexts).unwrap();
+
+        assert!(verify(&mut Transcript::new(b"test"), &B, &key.public, &encryptions, &proof).is_ok());
+
+        // The proof is no larger than one over a single ciphertext.
+        let (one, one_plaintext) = key.public.encrypt_attributes(&attributes[..1], &mut rng);
+        let single = prove(&mut Transcript::new(b"test"), &B, &key, &one, &one_plaintext).unwrap();
+
+        assert!(serialize(&proof).unwrap().len() == serialize(&single).unwrap().len());
+
+        // A single ciphertext's proof is the ordinary one.
+        let publics = attributes_blinded::Publics {
+            B: &B,
+            D: &key.public.into(),
+            encrypted_attribute_0_0: &one[0].commitment,
+            encrypted_attribute_0_1: &one[0].encryption,
+        };
+
+        assert!(single.verify(&mut Transcript::new(b"test"), publics).is_ok());
+    }
+
+    #[test]
+    fn every_ciphertext_is_covered() {
+        let mut rng = thread_rng();
+        let B = RISTRETTO_BASEPOINT_POINT;
+        let key = elgamal::Keypair::generate(&mut rng);
+        let attributes: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let (mut encryptions, plaintexts) = key.public.encrypt_attributes(&attributes, &mut rng);
+
+        let proof = prove(&mut Transcript::new(b"test"), &B, &key, &encryptions, &plaintexts).unwrap();
+
+        // Substituting, reordering, or dropping any ciphertext is caught.
+        let mut substituted = encryptions.clone();
+
+        substituted[3].encryption += B;
+
+        assert!(verify(&mut Transcript::new(b"test"), &B, &key.public, &substituted, &proof).is_err());
+
+        encryptions.swap(1, 2);
+
+        assert!(verify(&mut Transcript::new(b"test"), &B, &key.public, &encryptions, &proof).is_err());
+        assert!(verify(&mut Transcript::new(b"test"), &B, &key.public, &encryptions[..3], &proof).is_err());
+        assert!(verify(&mut Transcript::new(b"test"), &B, &key.public, &[], &proof) ==
+                Err(CredentialError::WrongNumberOfAttributes.into()));
+    }
+}
@@ -0,0 +1,162 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Sources of the current time, for expiry and epoch checks.
+//!
+//! Everything in this crate which depends upon the time takes it either as
+//! `now`, in seconds since the Unix epoch, or from a `Clock`, so that the
+//! logic is the same whether the time comes from:
+//!
+//! * a `SystemClock`, which reads the platform's clock (with the `std`
+//!   feature),
+//! * a `FixedClock`, which only moves when told to, e.g. in tests, or
+//! * an `ExternalClock`, which asks a closure, e.g. on an embedded target
+//!   without a realtime clock which is told the time by its host.
+//!
+//! Any of these may be wrapped in a `MonotonicClock`, so that a clock which
+//! is stepped backwards, e.g. by an NTP correction, does not move the time
+//! it reports backwards with it.
+
+#[cfg(not(feature = "std"))]
+use core::cell::Cell;
+#[cfg(feature = "std")]
+use std::cell::Cell;
+
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the current time.
+pub trait Clock {
+    /// The current time, in seconds since the Unix epoch.
+    fn now(&self) -> u64;
+}
+
+impl<'a, C: Clock + ?Sized> Clock for &'a C {
+    fn now(&self) -> u64 {
+        (**self).now()
+    }
+}
+
+/// The platform's clock.
+///
+/// A platform clock set before the Unix epoch reads as the epoch itself.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(x)  => x.as_secs(),
+            Err(_) => 0,
+        }
+    }
+}
+
+/// A clock which only moves when it is `set` or `advance`d.
+#[derive(Clone, Debug, Default)]
+pub struct FixedClock(Cell<u64>);
+
+impl FixedClock {
+    /// Create a clock stopped at `now`.
+    pub fn new(now: u64) -> FixedClock {
+        FixedClock(Cell::new(now))
+    }
+
+    /// Set this clock to `now`.
+    pub fn set(&self, now: u64) {
+        self.0.set(now);
+    }
+
+    /// Move this clock forward by some `seconds`.
+    pub fn advance(&self, seconds: u64) {
+        self.0.set(self.0.get().saturating_add(seconds));
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+/// A clock which asks an external time source, such as a host or a radio
+/// time signal, for the time.
+#[derive(Clone, Copy, Debug)]
+pub struct ExternalClock<F>(pub F);
+
+impl<F> Clock for ExternalClock<F>
+where
+    F: Fn() -> u64,
+{
+    fn now(&self) -> u64 {
+        (self.0)()
+    }
+}
+
+/// A clock which never reports an earlier time than it has before.
+///
+/// Verifiers checking expiry or freshness should read the time through one,
+/// so that stepping their clock back cannot make a stale credential or
+/// presentation acceptable again.  The time stands still, rather than
+/// running backwards, until the underlying clock catches up.
+#[derive(Clone, Debug, Default)]
+pub struct MonotonicClock<C> {
+    clock: C,
+    latest: Cell<u64>,
+}
+
+impl<C: Clock> MonotonicClock<C> {
+    pub fn new(clock: C) -> MonotonicClock<C> {
+        MonotonicClock { clock, latest: Cell::new(0) }
+    }
+}
+
+impl<C: Clock> Clock for MonotonicClock<C> {
+    fn now(&self) -> u64 {
+        let now = self.clock.now();
+
+        if now > self.latest.get() {
+            self.latest.set(now);
+        }
+        self.latest.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clocks() {
+        let fixed = FixedClock::new(10);
+
+        fixed.advance(5);
+        assert!(fixed.now() == 15);
+        fixed.set(3);
+        assert!((&fixed).now() == 3);
+
+        let external = ExternalClock(|| 42);
+
+        assert!(external.now() == 42);
+    }
+
+    #[test]
+    fn monotonic_clocks_never_run_backwards() {
+        let fixed = FixedClock::new(100);
+        let monotonic = MonotonicClock::new(&fixed);
+
+        assert!(monotonic.now() == 100);
+        fixed.set(40);
+        assert!(monotonic.now() == 100);
+        fixed.set(101);
+        assert!(monotonic.now() == 101);
+    }
+}
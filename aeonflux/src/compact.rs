@@ -0,0 +1,354 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Compact presentations, for NFC taps and other transports where every
+//! byte costs latency.
+//!
+//! The minimal policy asks only to see a single attribute, with no schema,
+//! scope, timestamp, or channel binding.  Since nothing is hidden, a
+//! `CompactPresentation` reveals the attribute and proves only that the
+//! holder knows a valid MAC over it: the issuer recomputes the MAC from the
+//! rerandomised nonce, \\( P \\), and the attribute, and the holder proves
+//! knowledge of the blinding factor between it and their commitment to the
+//! MAC, \\( C\_Q \\).  This is `proofs::attribute_opening`, a single
+//! Schnorr proof, made by `prove_opening()`, which tickets use as well.  It is
+//! packed as
+//!
+//! ```text
+//! version (1) || C_Q (32) || P (32) || attribute (32) || proof (64)
+//! ```
+//!
+//! i.e. `SIZEOF_COMPACT_PRESENTATION`, 161 bytes, well under the bound of
+//! `MAXIMUM_COMPACT_PRESENTATION_SIZE`, so that a presentation fits in a
+//! single short APDU.
+//!
+//! A compact presentation reveals the attribute, so presentations of the
+//! same credential may be linked by it.  The MAC is still rerandomised, so
+//! presentations of different credentials over equal attributes may not.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use amacs;
+use credential::Credential;
+use credential::RevealedAttribute;
+use errors::CredentialError;
+use errors::Error;
+use issuer::Issuer;
+use nonces::Ephemeral;
+use pedersen;
+use proofs::attribute_opening;
+use secret::Clear;
+use user::User;
+
+/// The current version of the compact presentation format.
+pub const COMPACT_PRESENTATION_VERSION: u8 = 1;
+
+/// The length of an encoded `CompactPresentation`.
+pub const SIZEOF_COMPACT_PRESENTATION: usize = 1 + 32 + 32 + 32 + attribute_opening::SIZEOF_PROOF;
+
+/// The size which a `CompactPresentation` must never exceed, so that it
+/// remains answerable within an NFC tap.
+pub const MAXIMUM_COMPACT_PRESENTATION_SIZE: usize = 300;
+
+/// A presentation of a credential's single attribute, revealed, with a proof
+/// of a valid MAC over it and nothing else.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompactPresentation {
+    /// A Pedersen commitment to the rerandomised MAC.
+    pub rerandomized_mac_commitment: pedersen::Commitment,
+    /// The rerandomised nonce of the MAC.
+    pub rerandomized_nonce: RistrettoPoint,
+    /// The credential's attribute.
+    pub attribute: RevealedAttribute,
+    /// A proof that the commitment opens to the MAC over the attribute.
+    pub proof: attribute_opening::Proof,
+}
+
+impl CompactPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CompactPresentation, CredentialError> {
+        if bytes.len() != SIZEOF_COMPACT_PRESENTATION {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+        if bytes[0] != COMPACT_PRESENTATION_VERSION {
+            return Err(CredentialError::MissingData);
+        }
+
+        let rerandomized_mac_commitment = pedersen::Commitment::from_bytes(&bytes[1..33])?;
+
+        let mut tmp: [u8; 32] = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[33..65]);
+
+        let rerandomized_nonce = CompressedRistretto(tmp).decompress().ok_or(CredentialError::PointDecompressionError)?;
+
+        tmp.copy_from_slice(&bytes[65..97]);
+
+        let attribute = Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)?;
+        let proof = attribute_opening::Proof::from_bytes(&bytes[97..])
+            .map_err(|_| CredentialError::ScalarFormatError)?;
+
+        Ok(CompactPresentation { rerandomized_mac_commitment, rerandomized_nonce, attribute, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_COMPACT_PRESENTATION);
+
+        v.push(COMPACT_PRESENTATION_VERSION);
+        v.extend(self.rerandomized_mac_commitment.to_bytes());
+        v.extend(self.rerandomized_nonce.compress().as_bytes());
+        v.extend(self.attribute.as_bytes());
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CompactPresentation,
+                                         "A valid byte sequence representing a CompactPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CompactPresentation);
+impl_try_from_bytes!(CompactPresentation, CredentialError);
+
+/// Commit to a rerandomised MAC, `(P, CQ)`, and the revealed `attribute` it
+/// is over.
+fn commit_opening(
+    transcript: &mut Transcript,
+    P: &RistrettoPoint,
+    CQ: &pedersen::Commitment,
+    attribute: &RevealedAttribute,
+) {
+    transcript.commit_bytes(b"P", P.compress().as_bytes());
+    transcript.commit_bytes(b"CQ", &CQ.to_bytes());
+    transcript.commit_bytes(b"attribute", attribute.as_bytes());
+}
+
+/// Rerandomise a `mac` over a revealed `attribute`, commit to it, and prove
+/// knowledge of the commitment's opening over the `transcript`.
+///
+/// This is the whole of a compact presentation, and also of a ticket, whose
+/// `transcript` additionally commits to its scope.
+///
+/// # Returns
+///
+/// The commitment to the rerandomised MAC, its nonce, and the proof.
+pub(crate) fn prove_opening<R>(
+    transcript: &mut Transcript,
+    A: &RistrettoPoint,
+    mac: &amacs::Tag,
+    attribute: &RevealedAttribute,
+    rng: &mut R,
+) -> (pedersen::Commitment, RistrettoPoint, attribute_opening::Proof)
+where
+    R: RngCore + CryptoRng,
+{
+    let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+    // Rerandomise the MAC, and commit to it, so that it cannot be linked
+    // to the issuance.
+    let rerandomized_mac: amacs::Tag = amacs::Rerandomization::new(&mut csprng).apply_to_tag(mac);
+    let P = rerandomized_mac.nonce;
+    let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+    let CQ = pedersen::Commitment::to(&rerandomized_mac.mac, &zQ, A);
+
+    // The issuer recomputes the MAC and subtracts the commitment, leaving
+    // -zQ·A, whose discrete log we prove we know.
+    let minus_zQ: Ephemeral = -zQ;
+    let V: RistrettoPoint = &minus_zQ * A;
+
+    commit_opening(transcript, &P, &CQ, attribute);
+
+    let proof = attribute_opening::Proof::create(
+        transcript,
+        attribute_opening::Publics { A: A, W: &V },
+        attribute_opening::Secrets { z0: minus_zQ.as_witness() },
+    );
+
+    (CQ, P, proof)
+}
+
+/// Verify, over the `transcript`, a `proof` that `CQ` commits to a MAC with
+/// nonce `P` over the revealed `attribute`, under the aMAC key whose secret
+/// scalars combine, for this attribute, to \( x = x\_0 + x\_1 m \).
+///
+/// The caller should clear `x` afterwards.
+pub(crate) fn verify_opening(
+    transcript: &mut Transcript,
+    A: &RistrettoPoint,
+    x: &Scalar,
+    P: &RistrettoPoint,
+    CQ: &pedersen::Commitment,
+    attribute: &RevealedAttribute,
+    proof: &attribute_opening::Proof,
+) -> Result<(), ()>
+{
+    // Every key gives the identity a valid MAC over every attribute.
+    if *P == RistrettoPoint::identity() {
+        return Err(());
+    }
+
+    // Recompute the MAC in constant time, since this uses the secret key.
+    let mut V: RistrettoPoint = x * P;
+
+    V -= *CQ;
+
+    commit_opening(transcript, P, CQ, attribute);
+
+    proof.verify(transcript, attribute_opening::Publics { A: A, W: &V })
+}
+
+impl User {
+    /// Present our credential compactly, revealing its attribute.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if we have no credential.
+    /// * `CredentialError::WrongNumberOfAttributes` unless the credential has
+    ///   exactly one attribute.
+    pub fn show_compact<R>(&self, rng: &mut R) -> Result<CompactPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &Credential = match self.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+
+        if credential.attributes.len() != 1 {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let attribute: RevealedAttribute = credential.attributes[0];
+        let mut transcript = Transcript::new(b"AEONFLUX COMPACT SHOW");
+        let (CQ, P, proof) = prove_opening(&mut transcript, &self.system_parameters.h,
+                                           &credential.mac, &attribute, rng);
+
+        Ok(CompactPresentation {
+            rerandomized_mac_commitment: CQ,
+            rerandomized_nonce: P,
+            attribute,
+            proof,
+        })
+    }
+}
+
+impl Issuer {
+    /// Verify a compact `presentation`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerKey` if this issuer has no key for the
+    ///   attribute.
+    /// * `CredentialError::MacVerification` if the presentation is not of a
+    ///   credential we issued over its attribute.
+    ///
+    /// # Returns
+    ///
+    /// The presented attribute.
+    pub fn verify_compact(&self, presentation: &CompactPresentation) -> Result<RevealedAttribute, Error> {
+        if self.keypair.secret.xn.len() == 0 {
+            return Err(CredentialError::NoIssuerKey.into());
+        }
+
+        let mut x: Scalar = &self.keypair.secret.x0 + &(&self.keypair.secret.xn[0] * &presentation.attribute);
+        let mut transcript = Transcript::new(b"AEONFLUX COMPACT SHOW");
+        let verified = verify_opening(&mut transcript, &self.system_parameters.h, &x,
+                                      &presentation.rerandomized_nonce,
+                                      &presentation.rerandomized_mac_commitment,
+                                      &presentation.attribute, &presentation.proof);
+
+        x.clear();
+
+        match verified {
+            Ok(())  => Ok(presentation.attribute),
+            Err(()) => Err(CredentialError::MacVerification.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use nonces::Nonces;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use test_utils::holder;
+
+    #[test]
+    fn compact_presentations_stay_under_the_bound() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let attribute = Scalar::random(&mut rng);
+        let user = holder(&issuer, vec![attribute], &mut rng);
+
+        assert!(SIZEOF_COMPACT_PRESENTATION == 161);
+        assert!(SIZEOF_COMPACT_PRESENTATION <= MAXIMUM_COMPACT_PRESENTATION_SIZE);
+
+        for _ in 0..8 {
+            let bytes = user.show_compact(&mut rng).unwrap().to_bytes();
+
+            assert!(bytes.len() == SIZEOF_COMPACT_PRESENTATION);
+            assert!(issuer.verify_compact(&CompactPresentation::from_bytes(&bytes).unwrap()) == Ok(attribute));
+        }
+
+        // Smaller than a full presentation, which hides the attribute.
+        let full = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(SIZEOF_COMPACT_PRESENTATION < full.to_bytes().len());
+    }
+
+    #[test]
+    fn compact_presentations_are_bound_to_their_attribute_and_issuer() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let other = Issuer::create(SystemParameters::from(H), &mut rng);
+        let user = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+
+        let mut presentation = user.show_compact(&mut rng).unwrap();
+
+        assert!(other.verify_compact(&presentation) == Err(CredentialError::MacVerification.into()));
+
+        presentation.attribute += Scalar::one();
+
+        assert!(issuer.verify_compact(&presentation) == Err(CredentialError::MacVerification.into()));
+
+        presentation.rerandomized_nonce = RistrettoPoint::identity();
+
+        assert!(issuer.verify_compact(&presentation) == Err(CredentialError::MacVerification.into()));
+
+        let mut bytes = user.show_compact(&mut rng).unwrap().to_bytes();
+
+        bytes[0] = COMPACT_PRESENTATION_VERSION + 1;
+
+        assert!(CompactPresentation::from_bytes(&bytes).is_err());
+        assert!(CompactPresentation::from_bytes(&bytes[..SIZEOF_COMPACT_PRESENTATION - 1])
+                == Err(CredentialError::WrongNumberOfBytes));
+    }
+}
@@ -0,0 +1,372 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Asking the holder before answering a `PresentationRequest`, and keeping
+//! a record of what they agreed to show.
+//!
+//! A wallet answers requests with `respond_with_consent()`, which checks the
+//! request against its schema, and then asks a `ConsentHandler`, e.g. a
+//! prompt built from `PresentationRequest::describe()`, whether to go on.
+//! No proof is made unless the handler agrees.  Each response which is made
+//! is written to the holder's `DisclosureLog`, recording when, to which
+//! verifier, under which schema, and what was shown, so that the holder can
+//! later review everything they have disclosed.
+//!
+//! The log never leaves the holder's device, and may be kept in any
+//! `storage::RecordStore`.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use clock::Clock;
+use credential::RevealedAttribute;
+use encoding::u64_to_bytes;
+use errors::Error;
+use errors::RequestError;
+use presentation_request::Disclosure;
+use presentation_request::PresentationRequest;
+use presentation_request::PresentationResponse;
+use presentation_request::Requirement;
+use presentation_request::read_string;
+use presentation_request::write_string;
+use schema::Schema;
+use schema::SchemaId;
+use schema::SIZEOF_SCHEMA_ID;
+use user::User;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// A wallet's hook for asking the holder whether to answer a request.
+pub trait ConsentHandler {
+    /// Whether the holder agrees to answer the `request`.
+    ///
+    /// This is only called for requests which the credential's schema
+    /// allows, and before any proof is made.
+    fn consent(&mut self, request: &PresentationRequest) -> bool;
+}
+
+impl<F: FnMut(&PresentationRequest) -> bool> ConsentHandler for F {
+    fn consent(&mut self, request: &PresentationRequest) -> bool {
+        self(request)
+    }
+}
+
+/// One attribute which was shown to a verifier.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisclosedAttribute {
+    pub label: String,
+    pub attribute: String,
+    pub requirement: Requirement,
+    /// The value shown, if the attribute was revealed.
+    pub value: Option<RevealedAttribute>,
+}
+
+/// The record of one response to a `PresentationRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisclosureRecord {
+    /// When the response was made, in seconds since the Unix epoch.
+    pub time: u64,
+    /// The verifier which the response was made to.
+    pub verifier: String,
+    pub schema: SchemaId,
+    pub attributes: Vec<DisclosedAttribute>,
+}
+
+impl DisclosureRecord {
+    fn new(time: u64, request: &PresentationRequest, response: &PresentationResponse) -> DisclosureRecord {
+        let attributes = request.attributes.iter().zip(response.disclosures.iter()).map(|(requested, disclosure)| {
+            DisclosedAttribute {
+                label: requested.label.clone(),
+                attribute: requested.attribute.clone(),
+                requirement: requested.requirement,
+                value: match *disclosure {
+                    Disclosure::Revealed(value, _) => Some(value),
+                    Disclosure::Satisfied(_)       => None,
+                },
+            }
+        }).collect();
+
+        DisclosureRecord {
+            time: time,
+            verifier: request.verifier.clone(),
+            schema: request.schema,
+            attributes: attributes,
+        }
+    }
+
+    /// Read a record from the start of `bytes`, returning it and the number
+    /// of bytes read.
+    fn read(bytes: &[u8]) -> Result<(DisclosureRecord, usize), RequestError> {
+        let time: u64 = read_u64(bytes.get(..8).ok_or(RequestError::WrongNumberOfBytes)?);
+        let (verifier, length) = read_string(&bytes[8..])?;
+        let mut offset: usize = 8 + length;
+
+        let header: &[u8] = bytes.get(offset..offset + SIZEOF_SCHEMA_ID + 1).ok_or(RequestError::WrongNumberOfBytes)?;
+        let mut schema = [0u8; SIZEOF_SCHEMA_ID];
+
+        schema.copy_from_slice(&header[..SIZEOF_SCHEMA_ID]);
+
+        let count: usize = header[SIZEOF_SCHEMA_ID] as usize;
+        let mut attributes: Vec<DisclosedAttribute> = Vec::with_capacity(count);
+
+        offset += header.len();
+
+        for _ in 0..count {
+            let (label, length) = read_string(&bytes[offset..])?;
+            offset += length;
+            let (attribute, length) = read_string(&bytes[offset..])?;
+            offset += length;
+            let (requirement, length) = Requirement::read(&bytes[offset..])?;
+            offset += length;
+
+            let value: Option<RevealedAttribute> = match requirement {
+                Requirement::Reveal => {
+                    let mut tmp = [0u8; 32];
+
+                    tmp.copy_from_slice(bytes.get(offset..offset + 32).ok_or(RequestError::WrongNumberOfBytes)?);
+                    offset += 32;
+
+                    Some(Scalar::from_canonical_bytes(tmp).ok_or(RequestError::ScalarFormatError)?)
+                },
+                Requirement::Predicate(..) => None,
+            };
+
+            attributes.push(DisclosedAttribute { label, attribute, requirement, value });
+        }
+
+        Ok((DisclosureRecord { time, verifier, schema: SchemaId(schema), attributes }, offset))
+    }
+
+    fn write(&self, v: &mut Vec<u8>) {
+        v.extend(u64_to_bytes(self.time).iter());
+        write_string(v, &self.verifier);
+        v.extend(self.schema.0.iter());
+        v.push(self.attributes.len() as u8);
+
+        for disclosed in self.attributes.iter() {
+            write_string(v, &disclosed.label);
+            write_string(v, &disclosed.attribute);
+            disclosed.requirement.write(v);
+
+            if let Some(ref value) = disclosed.value {
+                v.extend(value.as_bytes());
+            }
+        }
+    }
+}
+
+/// A holder's record of every response they have made, oldest first.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DisclosureLog(Vec<DisclosureRecord>);
+
+impl DisclosureLog {
+    pub fn new() -> DisclosureLog {
+        DisclosureLog(Vec::new())
+    }
+
+    pub fn records(&self) -> &[DisclosureRecord] {
+        &self.0
+    }
+
+    /// The records of the responses made to the given `verifier`.
+    pub fn to_verifier<'a>(&'a self, verifier: &'a str) -> impl Iterator<Item = &'a DisclosureRecord> + 'a {
+        self.0.iter().filter(move |record| record.verifier == verifier)
+    }
+
+    /// Forget every record made before `time`.
+    pub fn prune(&mut self, time: u64) {
+        self.0.retain(|record| record.time >= time);
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<DisclosureLog, RequestError> {
+        let mut records: Vec<DisclosureRecord> = Vec::new();
+        let mut offset: usize = 0;
+
+        while offset < bytes.len() {
+            let (record, length) = DisclosureRecord::read(&bytes[offset..])?;
+
+            records.push(record);
+            offset += length;
+        }
+        Ok(DisclosureLog(records))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        for record in self.0.iter() {
+            record.write(&mut v);
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(DisclosureLog, "A valid byte sequence representing a DisclosureLog");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(DisclosureLog);
+impl_try_from_bytes!(DisclosureLog, RequestError);
+
+/// Answer a `request` with the `user`'s credential under `schema`, only if
+/// the `handler` consents to it, and record the response in the `log`.
+///
+/// # Errors
+///
+/// * Any error from `PresentationRequest::check()`, before the `handler` is
+///   asked.
+/// * `RequestError::Refused` if the `handler` does not consent.
+/// * Any error from `PresentationRequest::respond()`.
+pub fn respond_with_consent<H, C, R>(
+    request: &PresentationRequest,
+    user: &User,
+    schema: &Schema,
+    handler: &mut H,
+    log: &mut DisclosureLog,
+    clock: &C,
+    rng: &mut R,
+) -> Result<PresentationResponse, Error>
+where
+    H: ConsentHandler + ?Sized,
+    C: Clock,
+    R: RngCore + CryptoRng,
+{
+    request.check(schema)?;
+
+    if !handler.consent(request) {
+        return Err(RequestError::Refused.into());
+    }
+
+    let response: PresentationResponse = request.respond(user, schema, rng)?;
+
+    log.0.push(DisclosureRecord::new(clock.now(), request, &response));
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use clock::FixedClock;
+    use issuer::Issuer;
+    use parameters::SystemParameters;
+    use predicates::Predicate;
+    use schema::AttributeType;
+    use schema::Reveal;
+    use storage::MemoryStore;
+    use storage::RecordStore;
+    use test_utils::H;
+
+    fn age() -> Schema {
+        Schema::new("age").attribute("age", AttributeType::Integer, Reveal::Optional)
+    }
+
+    fn setup() -> (Issuer, User) {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue_with_schema(&user.obtain(vec![Scalar::from(34u64)]), &age().id(), &mut rng).unwrap();
+
+        user.obtain_finish_with_schema(Some(&issuance), &age().id()).unwrap();
+
+        (issuer, user)
+    }
+
+    #[test]
+    fn consented_responses_are_logged() {
+        let mut rng = thread_rng();
+        let (issuer, user) = setup();
+        let clock = FixedClock::new(1_600_000_000);
+        let mut log = DisclosureLog::new();
+        let mut asked: Vec<String> = Vec::new();
+
+        let request = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .predicate("Age check", "age", Predicate::AtLeast(18), 8, "to sell you alcohol");
+        let mut handler = |request: &PresentationRequest| { asked.extend(request.describe()); true };
+        let response = respond_with_consent(&request, &user, &age(), &mut handler, &mut log, &clock, &mut rng).unwrap();
+
+        assert!(request.verify(&issuer, &age(), &response).is_ok());
+
+        let census = PresentationRequest::new("Registry", &age(), &mut rng)
+            .reveal("Age", "age", "for the census");
+
+        clock.advance(60);
+        respond_with_consent(&census, &user, &age(), &mut |_: &PresentationRequest| true, &mut log, &clock, &mut rng).unwrap();
+
+        assert!(asked.len() == 1);
+        assert!(log.records().len() == 2);
+        assert!(log.to_verifier("Corner Shop").count() == 1);
+        assert!(log.to_verifier("Registry").next().unwrap().attributes[0].value == Some(Scalar::from(34u64)));
+        assert!(log.records()[0].attributes[0].value.is_none());
+
+        let mut store = MemoryStore::new();
+
+        store.put_record(b"alice", &log).unwrap();
+
+        let mut stored: DisclosureLog = store.get_record(b"alice").unwrap().unwrap();
+
+        assert!(stored == log);
+
+        stored.prune(1_600_000_030);
+
+        assert!(stored.records().len() == 1);
+    }
+
+    #[test]
+    fn refused_requests_are_not_answered() {
+        let mut rng = thread_rng();
+        let (_, user) = setup();
+        let clock = FixedClock::new(1_600_000_000);
+        let mut log = DisclosureLog::new();
+        let mut asked: usize = 0;
+
+        let request = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .reveal("Age", "age", "to sell you alcohol");
+        let result = respond_with_consent(&request, &user, &age(),
+                                          &mut |_: &PresentationRequest| { asked += 1; false },
+                                          &mut log, &clock, &mut rng);
+
+        assert!(result.err() == Some(RequestError::Refused.into()));
+
+        // Requests which the schema does not allow never reach the holder.
+        let unknown = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .reveal("Name", "name", "to greet you");
+        let result = respond_with_consent(&unknown, &user, &age(),
+                                          &mut |_: &PresentationRequest| { asked += 1; true },
+                                          &mut log, &clock, &mut rng);
+
+        assert!(result.err() == Some(RequestError::UnknownAttribute.into()));
+        assert!(asked == 1);
+        assert!(log.records().is_empty());
+    }
+}
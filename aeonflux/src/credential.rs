@@ -14,24 +14,36 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
 use amacs::SIZEOF_TAG;
 use amacs::Tag;
 
-use bincode::{deserialize, serialize};
+use bincode::deserialize;
 
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::traits::Identity;
 
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
+use channel_binding::ChannelBinding;
+
 use elgamal;
 
 use parameters::NUMBER_OF_ATTRIBUTES;
 
 use pedersen;
 
+use schema::SchemaId;
+
+use secret::REDACTED;
+
 use errors::CredentialError;
 
 use proofs::attributes_blinded;
@@ -48,11 +60,11 @@ pub const ISSUANCE_NUMBER_OF_REVEALED_ATTRIBUTES: usize = 1;
 /// The number of encrypted attributes on a `Credential` during issuance.
 pub const ISSUANCE_NUMBER_OF_HIDDEN_ATTRIBUTES: usize = 0;
 
-/// The number of revealed attributes on a `Credential` during presentation.
-pub const PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES: usize = 0;
+/// The number of encrypted attributes on a `Credential` during blind issuance.
+pub const BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES: usize = 1;
 
-/// The number of encrypted attributes on a `Credential` during presentation.
-pub const PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES: usize = 1;
+pub use parameters::PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+pub use parameters::PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES;
 
 /// A plaintext attribute that is revealed to the issuer when requesting a
 /// credential.
@@ -64,7 +76,7 @@ pub type EncryptedAttribute = elgamal::Encryption;
 
 /// An anonymous credential belonging to a user and issued and verified
 /// by an issuer.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 #[repr(C)]
 pub struct Credential {
     /// The non-interactive zero knowledge proof that this credential is
@@ -75,6 +87,14 @@ pub struct Credential {
     pub attributes: Vec<RevealedAttribute>,
 }
 
+/// A credential's tag and attributes are the user's secrets, since either
+/// would link their presentations.
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Credential {{ mac: {}, attributes: {} }}", REDACTED, REDACTED)
+    }
+}
+
 impl Credential {
     pub fn from_bytes(bytes: &[u8]) -> Result<Credential, CredentialError> {
         let length: usize = bytes.len();
@@ -115,6 +135,9 @@ impl Credential {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(Credential, "A valid byte sequence representing a Credential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Credential);
+impl_try_from_bytes!(Credential, CredentialError);
 
 /// A request from a user for a `Credential`, optionally
 /// containing revealed and encrypted attributes.  If there are encrypted
@@ -133,26 +156,240 @@ pub struct CredentialBlindRequest {
     /// 1. the `encrypted_attributes` were created with the user's public key,
     /// 2. the user knows the corresponding secret key, and
     ///
+    /// A single proof covers every encrypted attribute, as aggregated by the
+    /// `ciphertexts` module.
+    ///
     /// The `attributes_blinded_proof` is required if there are `encrypted_attributes`.
     pub attributes_blinded_proof: Option<attributes_blinded::Proof>,
     /// The user's elGamal public key.
     pub public_key: elgamal::PublicKey,
 }
 
+/// Read a one-byte count of `size`-byte items at `offset` in `bytes`, returning
+/// the count and the offset of the first item.
+fn read_count(bytes: &[u8], offset: usize, size: usize) -> Result<(usize, usize), CredentialError> {
+    if bytes.len() <= offset {
+        return Err(CredentialError::WrongNumberOfBytes);
+    }
+    let count: usize = bytes[offset] as usize;
+
+    if bytes.len() < offset + 1 + count * size {
+        return Err(CredentialError::WrongNumberOfBytes);
+    }
+
+    Ok((count, offset + 1))
+}
+
+impl CredentialBlindRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CredentialBlindRequest, CredentialError> {
+        if bytes.len() < elgamal::SIZEOF_PUBLIC_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+        let public_key = elgamal::PublicKey::from_bytes(&bytes[00..32])?;
+
+        let (revealed, mut offset) = read_count(bytes, 32, 32)?;
+        let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(revealed);
+
+        for chunk in bytes[offset..offset + 32 * revealed].chunks(32) {
+            let mut tmp: [u8; 32] = [0u8; 32];
+
+            tmp.copy_from_slice(chunk);
+
+            match Scalar::from_canonical_bytes(tmp) {
+                Some(x) => attributes_revealed.push(x),
+                None    => return Err(CredentialError::ScalarFormatError),
+            }
+        }
+        offset += 32 * revealed;
+
+        let (blinded, offset) = read_count(bytes, offset, elgamal::SIZEOF_ENCRYPTION)?;
+        let mut attributes_blinded: Vec<EncryptedAttribute> = Vec::with_capacity(blinded);
+
+        for chunk in bytes[offset..offset + elgamal::SIZEOF_ENCRYPTION * blinded].chunks(elgamal::SIZEOF_ENCRYPTION) {
+            attributes_blinded.push(elgamal::Encryption::from_bytes(chunk)?);
+        }
+        let offset = offset + elgamal::SIZEOF_ENCRYPTION * blinded;
+
+        let attributes_blinded_proof: Option<attributes_blinded::Proof> = if offset == bytes.len() {
+            None
+        } else {
+            match deserialize(&bytes[offset..]) {
+                Ok(x)   => Some(x),
+                Err(_x) => return Err(CredentialError::MissingData),
+            }
+        };
+
+        Ok(CredentialBlindRequest {
+            attributes_revealed: if revealed == 0 { None } else { Some(attributes_revealed) },
+            attributes_blinded: if blinded == 0 { None } else { Some(attributes_blinded) },
+            attributes_blinded_proof,
+            public_key,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(512);
+
+        v.extend(self.public_key.to_bytes());
+
+        match self.attributes_revealed {
+            None        => v.push(0),
+            Some(ref x) => {
+                v.push(x.len() as u8);
+
+                for attribute in x.iter() {
+                    v.extend(attribute.to_bytes().iter());
+                }
+            },
+        }
+
+        match self.attributes_blinded {
+            None        => v.push(0),
+            Some(ref x) => {
+                v.push(x.len() as u8);
+
+                for attribute in x.iter() {
+                    v.extend(attribute.to_bytes());
+                }
+            },
+        }
+
+        if let Some(ref proof) = self.attributes_blinded_proof {
+            v.extend(proof.to_bytes().iter());
+        }
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CredentialBlindRequest,
+                                         "A valid byte sequence representing a CredentialBlindRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CredentialBlindRequest);
+impl_try_from_bytes!(CredentialBlindRequest, CredentialError);
+
 /// An blinded issuance of a `Credential`.
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct CredentialBlindIssuance {
     pub proof: issuance_blinded::Proof,
+    /// A Pedersen commitment to the issuer's secret key, `x0`.
+    pub secret_key_commitment: pedersen::Commitment,
+    /// The aMAC nonce, `P`.
     pub blinding_commitment: RistrettoPoint,
+    /// The auxiliary commitments `Ti = b * Xi` for each blinded attribute.
     pub auxiliary_commitments: Vec<RistrettoPoint>,
+    /// The aMAC, `Q`, encrypted to the user's elGamal public key.
     pub encrypted_mac: elgamal::Encryption,
-    /// DOCDOC
+    /// The attributes which were revealed to the issuer, if any.
     pub attributes_revealed: Vec<RevealedAttribute>,
-    /// DOCDOC
+    /// The user's encrypted attributes which the aMAC was computed over.
     pub encrypted_attributes: Vec<EncryptedAttribute>,
 }
 
+impl CredentialBlindIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CredentialBlindIssuance, CredentialError> {
+        if bytes.len() < 64 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+        let secret_key_commitment = pedersen::Commitment::from_bytes(&bytes[00..32])?;
+
+        let mut tmp: [u8; 32] = [0u8; 32];
+        tmp.copy_from_slice(&bytes[32..64]);
+        let blinding_commitment = CompressedRistretto(tmp).decompress().ok_or(CredentialError::PointDecompressionError)?;
+
+        let (auxiliary, offset) = read_count(bytes, 64, 32)?;
+        let mut auxiliary_commitments: Vec<RistrettoPoint> = Vec::with_capacity(auxiliary);
+
+        for chunk in bytes[offset..offset + 32 * auxiliary].chunks(32) {
+            auxiliary_commitments.push(CompressedRistretto::from_slice(chunk).decompress().ok_or(CredentialError::PointDecompressionError)?);
+        }
+        let offset = offset + 32 * auxiliary;
+
+        if bytes.len() < offset + elgamal::SIZEOF_ENCRYPTION {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+        let encrypted_mac = elgamal::Encryption::from_bytes(&bytes[offset..offset + elgamal::SIZEOF_ENCRYPTION])?;
+        let offset = offset + elgamal::SIZEOF_ENCRYPTION;
+
+        let (revealed, offset) = read_count(bytes, offset, 32)?;
+        let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(revealed);
+
+        for chunk in bytes[offset..offset + 32 * revealed].chunks(32) {
+            let mut tmp: [u8; 32] = [0u8; 32];
+
+            tmp.copy_from_slice(chunk);
+
+            match Scalar::from_canonical_bytes(tmp) {
+                Some(x) => attributes_revealed.push(x),
+                None    => return Err(CredentialError::ScalarFormatError),
+            }
+        }
+        let offset = offset + 32 * revealed;
+
+        let (encrypted, offset) = read_count(bytes, offset, elgamal::SIZEOF_ENCRYPTION)?;
+        let mut encrypted_attributes: Vec<EncryptedAttribute> = Vec::with_capacity(encrypted);
+
+        for chunk in bytes[offset..offset + elgamal::SIZEOF_ENCRYPTION * encrypted].chunks(elgamal::SIZEOF_ENCRYPTION) {
+            encrypted_attributes.push(elgamal::Encryption::from_bytes(chunk)?);
+        }
+        let offset = offset + elgamal::SIZEOF_ENCRYPTION * encrypted;
+
+        let proof: issuance_blinded::Proof = match deserialize(&bytes[offset..]) {
+            Ok(x)   => x,
+            Err(_x) => {
+                // println!("Error while deserializing CredentialBlindIssuance: {}", _x);
+                return Err(CredentialError::MissingData);
+            },
+        };
+
+        Ok(CredentialBlindIssuance {
+            proof,
+            secret_key_commitment,
+            blinding_commitment,
+            auxiliary_commitments,
+            encrypted_mac,
+            attributes_revealed,
+            encrypted_attributes,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(1000);
+
+        v.extend(self.secret_key_commitment.to_bytes());
+        v.extend(self.blinding_commitment.compress().to_bytes().iter());
+
+        v.push(self.auxiliary_commitments.len() as u8);
+
+        for commitment in self.auxiliary_commitments.iter() {
+            v.extend(commitment.compress().to_bytes().iter());
+        }
+
+        v.extend(self.encrypted_mac.to_bytes());
+        v.push(self.attributes_revealed.len() as u8);
+
+        for attribute in self.attributes_revealed.iter() {
+            v.extend(attribute.to_bytes().iter());
+        }
+
+        v.push(self.encrypted_attributes.len() as u8);
+
+        for attribute in self.encrypted_attributes.iter() {
+            v.extend(attribute.to_bytes());
+        }
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CredentialBlindIssuance,
+                                         "A valid byte sequence representing a CredentialBlindIssuance");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CredentialBlindIssuance);
+impl_try_from_bytes!(CredentialBlindIssuance, CredentialError);
+
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
 pub struct CredentialRequest {
@@ -198,6 +435,9 @@ impl CredentialRequest {
 
 impl_serde_with_to_bytes_and_from_bytes!(CredentialRequest,
                                          "A valid byte sequence representing a CredentialRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CredentialRequest);
+impl_try_from_bytes!(CredentialRequest, CredentialError);
 
 #[derive(Debug, Eq, PartialEq)]
 #[repr(C)]
@@ -209,6 +449,10 @@ pub struct CredentialIssuance {
 
 impl CredentialIssuance {
     pub fn from_bytes(bytes: &[u8]) -> Result<CredentialIssuance, CredentialError> {
+        if bytes.len() < 32 + SIZEOF_CREDENTIAL {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
         let secret_key_commitment = pedersen::Commitment::from_bytes(&bytes[00..32])?;
         let credential = Credential::from_bytes(&bytes[32..32+SIZEOF_CREDENTIAL])?;
         
@@ -229,21 +473,16 @@ impl CredentialIssuance {
         v.extend(self.secret_key_commitment.to_bytes());
         v.extend(self.credential.to_bytes());
 
-        let serialized = match serialize(&self.proof) {
-            Ok(x)   => x,
-            Err(_x) => {
-                // println!("Error while serializing CredentialIssuance: {}", _x);
-                panic!();  // XXX clean this up
-            },
-        };
-
-        v.extend(serialized);
+        v.extend(self.proof.to_bytes().iter());
         v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(CredentialIssuance,
                                          "A valid byte sequence representing a CredentialIssuance");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CredentialIssuance);
+impl_try_from_bytes!(CredentialIssuance, CredentialError);
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
@@ -267,8 +506,11 @@ impl CredentialPresentation {
     pub fn from_bytes(bytes: &[u8]) -> Result<CredentialPresentation, CredentialError> {
         let length: usize = bytes.len();
 
-        // The bytes must be a multiple of 32.
-        if length % 32 != 0 {
+        let attributes_offset: usize = 32 * (PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES + PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
+
+        // The bytes must be a multiple of 32, and long enough to hold every
+        // field before the proof.
+        if length % 32 != 0 || length < 64 + attributes_offset {
             return Err(CredentialError::WrongNumberOfBytes);
         }
 
@@ -276,9 +518,13 @@ impl CredentialPresentation {
 
         let mut tmp: [u8; 32] = [0u8; 32];
         tmp.copy_from_slice(&bytes[32..64]);
-        let rerandomized_nonce = CompressedRistretto(tmp).decompress()?;
+        let rerandomized_nonce = CompressedRistretto(tmp).decompress().ok_or(CredentialError::PointDecompressionError)?;
+
+        // Every key gives the identity a valid MAC.
+        if rerandomized_nonce == RistrettoPoint::identity() {
+            return Err(CredentialError::VerificationFailure);
+        }
 
-        let attributes_offset: usize = 32 * (PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES + PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
         let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES);
         let mut attributes_blinded: Vec<pedersen::Commitment> = Vec::with_capacity(PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
         let mut attributes_processed: usize = 0;
@@ -333,21 +579,16 @@ impl CredentialPresentation {
             v.extend(attribute.to_bytes());
         }
 
-        let serialized = match serialize(&self.proof) {
-            Ok(x)   => x,
-            Err(_x) => {
-                // println!("Error while serializing CredentialPresentation: {}", _x);
-                panic!();  // XXX clean this up
-            },
-        };
-
-        v.extend(serialized);
+        v.extend(self.proof.to_bytes().iter());
         v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(CredentialPresentation,
                                          "A valid byte sequence representing a CredentialPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CredentialPresentation);
+impl_try_from_bytes!(CredentialPresentation, CredentialError);
 
 /// A `Credential` which has already been verified.
 ///
@@ -372,24 +613,190 @@ impl VerifiedCredential {
 
 impl_serde_with_to_bytes_and_from_bytes!(VerifiedCredential,
                                          "A valid byte sequence representing a VerifiedCredential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(VerifiedCredential);
+impl_try_from_bytes!(VerifiedCredential, CredentialError);
+
+/// What a presentation is bound to besides the credential, for
+/// `User::show_with()` and `Issuer::verify_with()`, which must be given the
+/// same options.  Any of them may be combined, e.g. a presentation under a
+/// schema, at a timestamp, over a bound channel:
+///
+/// ```rust,ignore
+/// let options = PresentationOptions::new().schema(&schema).at(now).bound(&channel);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PresentationOptions<'a> {
+    pub(crate) schema: Option<&'a SchemaId>,
+    pub(crate) scope: Option<&'a RistrettoPoint>,
+    pub(crate) timestamp: Option<u64>,
+    pub(crate) channel: Option<&'a ChannelBinding>,
+}
+
+impl<'a> PresentationOptions<'a> {
+    /// No options, as for `User::show()` and `Issuer::verify()`.
+    pub fn new() -> PresentationOptions<'a> {
+        PresentationOptions::default()
+    }
+
+    /// For a credential issued with `Issuer::issue_with_schema()` under a
+    /// `schema`.
+    pub fn schema(mut self, schema: &'a SchemaId) -> PresentationOptions<'a> {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Also prove a tag, \\( N = m_0 \cdot H \\), on the hidden attribute for
+    /// some scoped `basepoint` \\( H \\), e.g. a nullifier, under the same
+    /// challenge as the credential.
+    pub fn scoped(mut self, basepoint: &'a RistrettoPoint) -> PresentationOptions<'a> {
+        self.scope = Some(basepoint);
+        self
+    }
+
+    /// Bind the presentation to a `timestamp`, in seconds since the Unix
+    /// epoch.  Whether it is recent enough is up to the verifier, e.g. with
+    /// a `freshness::FreshnessPolicy`.
+    pub fn at(mut self, timestamp: u64) -> PresentationOptions<'a> {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Bind the presentation to the connection it is sent over, by way of
+    /// its `channel` binding, so that it cannot be replayed over another.
+    pub fn bound(mut self, channel: &'a ChannelBinding) -> PresentationOptions<'a> {
+        self.channel = Some(channel);
+        self
+    }
+}
+
+/// A `CredentialPresentation` borrowed from an encoded buffer.
+///
+/// Only the layout of the buffer is checked upon construction.  Each field is
+/// decoded, and each point decompressed, only when it is asked for, so that a
+/// verifier whose policy looks at only some fields pays for only those.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PresentationRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PresentationRef<'a> {
+    const ATTRIBUTES_OFFSET: usize = 64;
+    const BLINDED_OFFSET: usize = 64 + 32 * PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+    const PROOF_OFFSET: usize = 64 + 32 * (PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES +
+                                           PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
+
+    /// Check the layout of an encoded `CredentialPresentation`, without
+    /// copying or decoding any of it.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfBytes` if `bytes` are not a multiple
+    ///   of 32 bytes long, or are too short to contain every field.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<PresentationRef<'a>, CredentialError> {
+        if bytes.len() % 32 != 0 || bytes.len() < PresentationRef::PROOF_OFFSET {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        Ok(PresentationRef { bytes })
+    }
+
+    /// The underlying encoded bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decompress the commitment to the rerandomised aMAC.
+    pub fn rerandomized_mac_commitment(&self) -> Result<pedersen::Commitment, CredentialError> {
+        pedersen::Commitment::from_bytes(&self.bytes[00..32])
+    }
+
+    /// Decompress the rerandomised aMAC nonce, which may not be the
+    /// identity.
+    pub fn rerandomized_nonce(&self) -> Result<RistrettoPoint, CredentialError> {
+        let P = CompressedRistretto::from_slice(&self.bytes[32..64]).decompress().ok_or(CredentialError::PointDecompressionError)?;
+
+        if P == RistrettoPoint::identity() {
+            return Err(CredentialError::VerificationFailure);
+        }
+        Ok(P)
+    }
+
+    /// Decode the revealed attribute at `index`, or `None` if there is no
+    /// such attribute.
+    pub fn attribute_revealed(&self, index: usize) -> Option<Result<RevealedAttribute, CredentialError>> {
+        if index >= PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES {
+            return None;
+        }
+        let start: usize = PresentationRef::ATTRIBUTES_OFFSET + 32 * index;
+        let mut tmp: [u8; 32] = [0u8; 32];
+
+        tmp.copy_from_slice(&self.bytes[start..start + 32]);
+
+        Some(Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError))
+    }
+
+    /// Decompress the commitment to the hidden attribute at `index`, or `None`
+    /// if there is no such attribute.
+    pub fn attribute_blinded(&self, index: usize) -> Option<Result<pedersen::Commitment, CredentialError>> {
+        if index >= PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES {
+            return None;
+        }
+        let start: usize = PresentationRef::BLINDED_OFFSET + 32 * index;
+
+        Some(pedersen::Commitment::from_bytes(&self.bytes[start..start + 32])
+             .or(Err(CredentialError::PointDecompressionError)))
+    }
+
+    /// Decode the proof of knowledge of a valid credential.
+    pub fn proof(&self) -> Result<valid_credential::Proof, CredentialError> {
+        deserialize(&self.bytes[PresentationRef::PROOF_OFFSET..]).or(Err(CredentialError::MissingData))
+    }
+
+    /// Decode every field into an owned `CredentialPresentation`.
+    pub fn decode(&self) -> Result<CredentialPresentation, CredentialError> {
+        let mut attributes_revealed: Vec<RevealedAttribute> = Vec::with_capacity(PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES);
+        let mut attributes_blinded: Vec<pedersen::Commitment> = Vec::with_capacity(PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
+
+        for index in 0..PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES {
+            attributes_revealed.push(self.attribute_revealed(index)??);
+        }
+        for index in 0..PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES {
+            attributes_blinded.push(self.attribute_blinded(index)??);
+        }
+
+        Ok(CredentialPresentation {
+            rerandomized_mac_commitment: self.rerandomized_mac_commitment()?,
+            rerandomized_nonce: self.rerandomized_nonce()?,
+            attributes_revealed,
+            attributes_blinded,
+            proof: self.proof()?,
+        })
+    }
+}
+
+impl<'a> ::core::convert::TryFrom<&'a [u8]> for PresentationRef<'a> {
+    type Error = CredentialError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<PresentationRef<'a>, CredentialError> {
+        PresentationRef::from_bytes(bytes)
+    }
+}
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use amacs;
     use issuer::Issuer;
     use issuer::IssuerParameters;
     use nonces::Nonces;
     use parameters::SystemParameters;
+    use test_utils::H;
     use user::User;
 
     use rand::thread_rng;
 
-    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
-                          127,   4, 178,  70, 212, 141, 119, 112,
-                          153, 154, 135,  11, 227, 132, 247,  47,
-                           68, 192,  72, 200,  23,  88,  51,  82, ];
-
     #[test]
     fn credential_presentation_serialize_deserialize() {
         let mut issuer_rng = thread_rng();
@@ -418,6 +825,46 @@ mod test {
         assert!(deserialized.unwrap() == alice_presentation);
     }
 
+    #[test]
+    fn presentation_ref_decodes_lazily() {
+        let mut rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut alice_attributes: Vec<RevealedAttribute> = Vec::new();
+        alice_attributes.push(Scalar::random(&mut rng));
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+        let alice_issuance: CredentialIssuance = issuer.issue(&alice_request, &mut rng).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut rng).unwrap();
+        let mut serialized = alice_presentation.to_bytes();
+
+        {
+            let borrowed = PresentationRef::from_bytes(&serialized).unwrap();
+
+            assert!(borrowed.as_bytes().as_ptr() == serialized.as_ptr());
+            assert!(borrowed.rerandomized_nonce().unwrap() == alice_presentation.rerandomized_nonce);
+            assert!(borrowed.attribute_blinded(PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES).is_none());
+            assert!(borrowed.decode().unwrap() == alice_presentation);
+        }
+
+        // Corrupting the mac commitment is only noticed when it's decoded.
+        for byte in serialized[00..32].iter_mut() {
+            *byte = 0xff;
+        }
+
+        let borrowed = PresentationRef::from_bytes(&serialized).unwrap();
+
+        assert!(borrowed.rerandomized_nonce().is_ok());
+        assert!(borrowed.rerandomized_mac_commitment().is_err());
+        assert!(borrowed.decode().is_err());
+        assert!(PresentationRef::from_bytes(&serialized[..63]).is_err());
+    }
+
     #[test]
     fn verified_credential_serialize_deserialize() {
         let mut issuer_rng = thread_rng();
@@ -447,4 +894,86 @@ mod test {
         assert!(deserialized.is_ok());
         assert!(deserialized.unwrap() == verified);
     }
+
+    #[test]
+    fn malformed_encodings_are_rejected_without_panicking() {
+        let mut rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut alice_attributes: Vec<RevealedAttribute> = Vec::new();
+        alice_attributes.push(Scalar::random(&mut rng));
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+        let alice_issuance: CredentialIssuance = issuer.issue(&alice_request, &mut rng).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut rng).unwrap();
+        let presentation: Vec<u8> = alice_presentation.to_bytes();
+        let issuance: Vec<u8> = alice_issuance.to_bytes();
+        let keypair: Vec<u8> = issuer.keypair.to_bytes();
+
+        // Every truncation of a valid encoding must be rejected, or at least
+        // decoded, without panicking.
+        for length in 0..presentation.len() {
+            assert!(CredentialPresentation::from_bytes(&presentation[..length]).is_err());
+            let _ = PresentationRef::from_bytes(&presentation[..length]);
+            let _ = CredentialBlindRequest::from_bytes(&presentation[..length]);
+            let _ = CredentialBlindIssuance::from_bytes(&presentation[..length]);
+        }
+        for length in 0..issuance.len() {
+            assert!(CredentialIssuance::from_bytes(&issuance[..length]).is_err());
+        }
+        for length in 0..keypair.len() {
+            let _ = amacs::Keypair::from_bytes(&keypair[..length]);
+            let _ = amacs::SecretKey::from_bytes(&keypair[..length]);
+            let _ = elgamal::Keypair::from_bytes(&keypair[..length]);
+        }
+
+        // A presentation constructed with the wrong number of attributes is
+        // rejected rather than indexed.
+        let mut forged: CredentialPresentation = alice_presentation.clone();
+
+        forged.attributes_blinded.clear();
+        assert!(issuer.verify(&forged).err() == Some(CredentialError::WrongNumberOfAttributes.into()));
+
+        // As is a request to show with the wrong number of nonces.
+        let no_nonces: Nonces = Nonces::new(&mut rng, 0);
+
+        assert!(alice.show(&no_nonces, &mut rng).err() == Some(CredentialError::WrongNumberOfAttributes.into()));
+    }
+
+    #[test]
+    fn try_from_bytes_matches_from_bytes() {
+        use std::convert::TryFrom;
+
+        let mut rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut alice_attributes: Vec<RevealedAttribute> = Vec::new();
+        alice_attributes.push(Scalar::random(&mut rng));
+        let alice_request: CredentialRequest = alice.obtain(alice_attributes);
+        let alice_issuance: CredentialIssuance = issuer.issue(&alice_request, &mut rng).unwrap();
+
+        alice.obtain_finish(Some(&alice_issuance)).unwrap();
+
+        let alice_nonces: Nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let presentation: Vec<u8> = alice.show(&alice_nonces, &mut rng).unwrap().to_bytes();
+        let decoded: CredentialPresentation = CredentialPresentation::try_from(&presentation[..]).unwrap();
+
+        assert!(decoded.to_bytes() == presentation);
+        assert!(issuer.verify(&decoded).is_ok());
+        assert!(PresentationRef::try_from(&presentation[..]).unwrap().as_bytes() == &presentation[..]);
+        assert!(CredentialIssuance::try_from(&alice_issuance.to_bytes()[..]).unwrap() == alice_issuance);
+
+        // The errors are exactly those of from_bytes.
+        assert!(CredentialPresentation::try_from(&presentation[1..]).err() ==
+                CredentialPresentation::from_bytes(&presentation[1..]).err());
+        assert!(SystemParameters::try_from(&[0u8; 31][..]).err() ==
+                SystemParameters::from_bytes(&[0u8; 31][..]).err());
+    }
 }
@@ -0,0 +1,175 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Hierarchical deterministic derivation of nonces, blinding factors, and
+//! user keys.
+//!
+//! A `DerivationKey` is the root of a tree, derived from a seed, e.g. one
+//! which a user has written down for recovering their wallet.  Beneath it is
+//! one `PurposeKey` for each `Purpose`, and beneath each of those, a scalar
+//! for every index:
+//!
+//! ```text
+//! seed ─── root ──┬── Nonce ─────── 0, 1, 2, …
+//!                 ├── Blinding ──── 0, 1, 2, …
+//!                 └── UserKey ───── 0, 1, 2, …
+//! ```
+//!
+//! Every step is hardened, i.e. a hash of the parent's secret key, so that
+//! neither a scalar nor a `PurposeKey` reveals anything about its parent or
+//! about any of its siblings: the keys derived for one purpose may be handed
+//! to a component which needs them, e.g. a test harness which replays
+//! nonces, without exposing the user's keys.
+
+use curve25519_dalek::scalar::Scalar;
+
+use elgamal;
+use hashing::Hasher;
+use issuer::IssuerParameters;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use parameters::SystemParameters;
+use secret::Clear;
+use secret::Secret;
+use user::User;
+
+/// The branches of a derivation tree.
+///
+/// These values are part of every derivation, and must never be reused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum Purpose {
+    /// Nonces for encryptions and presentations.
+    Nonce = 1,
+    /// Blinding factors for Pedersen commitments.
+    Blinding = 2,
+    /// A user's elGamal keys.
+    UserKey = 3,
+    /// Keys sealing an issuer's backups.
+    Backup = 4,
+}
+
+/// The root of a derivation tree.
+#[derive(Clone, Debug)]
+pub struct DerivationKey(Secret<[u8; 32]>);
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for DerivationKey {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl DerivationKey {
+    /// Derive the root of a tree from a `seed`, which should have at least
+    /// 256 bits of entropy.
+    pub fn from_seed(seed: &[u8]) -> DerivationKey {
+        let mut h = Hasher::new(b"aeonflux derivation root v1");
+
+        h.input(seed);
+
+        DerivationKey(Secret::new(h.finalize_key()))
+    }
+
+    /// Derive the branch of this tree for a `purpose`.
+    pub fn purpose(&self, purpose: Purpose) -> PurposeKey {
+        let mut h = Hasher::new(b"aeonflux derivation purpose v1");
+
+        h.input(self.0.expose_secret()).input(&[purpose as u8][..]);
+
+        PurposeKey { purpose, key: Secret::new(h.finalize_key()) }
+    }
+
+    /// Recover the `index`th user derived from this tree, with the same
+    /// elGamal key it had before, e.g. so that the credentials in a restored
+    /// backup may be presented again.
+    pub fn recover_user(
+        &self,
+        system_parameters: SystemParameters,
+        issuer_parameters: IssuerParameters,
+        index: u64,
+    ) -> User
+    {
+        let key = self.purpose(Purpose::UserKey).elgamal_keypair(index);
+
+        User::new(system_parameters, issuer_parameters, Some(key))
+    }
+}
+
+/// One branch of a derivation tree.
+#[derive(Clone, Debug)]
+pub struct PurposeKey {
+    purpose: Purpose,
+    key: Secret<[u8; 32]>,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for PurposeKey {
+    fn drop(&mut self) {
+        self.key.clear();
+    }
+}
+
+impl PurposeKey {
+    /// The purpose this branch was derived for.
+    pub fn purpose(&self) -> Purpose {
+        self.purpose
+    }
+
+    fn scalar(&self, index: u64) -> Scalar {
+        let mut h = Hasher::new(b"aeonflux derivation index v1");
+
+        h.input(self.key.expose_secret()).input_u64(index);
+        h.finalize_scalar()
+    }
+
+    /// The `index`th nonce or blinding factor.
+    pub fn ephemeral(&self, index: u64) -> Ephemeral {
+        Ephemeral::from(self.scalar(index))
+    }
+
+    /// The `size` consecutive nonces beginning at index `first`, e.g. for
+    /// `User::show()`.
+    pub fn nonces(&self, first: u64, size: usize) -> Nonces {
+        Nonces((0..size as u64).map(|i| self.ephemeral(first + i)).collect())
+    }
+
+    /// The `index`th elGamal keypair.
+    pub fn elgamal_keypair(&self, index: u64) -> elgamal::Keypair {
+        let secret = elgamal::SecretKey(Secret::new(self.scalar(index)));
+        let public = elgamal::PublicKey::from(&secret);
+
+        elgamal::Keypair { secret, public }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn derivations_are_reproducible_and_separated() {
+        let root = DerivationKey::from_seed(&[7u8; 32]);
+        let nonces = root.purpose(Purpose::Nonce);
+        let blindings = root.purpose(Purpose::Blinding);
+
+        assert!(nonces.ephemeral(0).to_bytes() == DerivationKey::from_seed(&[7u8; 32])
+                .purpose(Purpose::Nonce).ephemeral(0).to_bytes());
+        assert!(nonces.ephemeral(0).to_bytes() != nonces.ephemeral(1).to_bytes());
+        assert!(nonces.ephemeral(0).to_bytes() != blindings.ephemeral(0).to_bytes());
+        assert!(nonces.ephemeral(0).to_bytes() != DerivationKey::from_seed(&[8u8; 32])
+                .purpose(Purpose::Nonce).ephemeral(0).to_bytes());
+        assert!(nonces.nonces(1, 2)[0].to_bytes() == nonces.ephemeral(1).to_bytes());
+
+        let keypair = root.purpose(Purpose::UserKey).elgamal_keypair(0);
+
+        assert!(keypair == root.purpose(Purpose::UserKey).elgamal_keypair(0));
+        assert!(keypair.public == elgamal::PublicKey::from(&keypair.secret));
+    }
+}
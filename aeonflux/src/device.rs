@@ -0,0 +1,781 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Device credentials, sharing one anonymous identity across a holder's
+//! devices.
+//!
+//! A device credential is a credential over a fixed schema of two hidden
+//! attributes: the holder's identity, i.e. the hidden attribute of their
+//! ordinary credential, and a device key which is generated on the device
+//! and never leaves it.  To enroll a new device, the holder copies their
+//! identity credential to it, and the device
+//!
+//! 1. presents the identity credential,
+//! 2. commits to the identity and to a fresh device key, and
+//! 3. proves, with `proofs::device_enrollment`, that the first commitment
+//!    holds the same identity as the presentation.
+//!
+//! The `DeviceIssuer` checks both, and issues a MAC over the committed
+//! attributes without learning either of them.
+//!
+//! A device credential is shown with a nullifier in some scope, which is the
+//! identity's `nullifier::scoped_nullifier()` there.  So every device of a
+//! holder, and their identity credential, is the same holder to a verifier
+//! counting nullifiers, while each device's credential is useless without
+//! its own device key.  Losing a phone loses only that device's key: the
+//! identity lives on in the holder's other devices, and in any copy of the
+//! identity credential, from which a replacement may be enrolled.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use amacs;
+use credential::Credential;
+use credential::CredentialPresentation;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::DeviceError;
+use errors::Error;
+use issuer::Issuer;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use nullifier::hash_scope_to_group;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
+use pedersen;
+use proofs::device_enrollment;
+use proofs::device_issuance;
+use proofs::valid_device;
+use rate_limit::Nullifier;
+use secret::REDACTED;
+use secret::Secret;
+use user::User;
+
+/// The number of attributes in a device credential: the holder's identity,
+/// and the device's key.
+pub const DEVICE_NUMBER_OF_ATTRIBUTES: usize = 2;
+
+pub const SIZEOF_DEVICE_CREDENTIAL: usize = 64 + amacs::SIZEOF_TAG;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, DeviceError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(&bytes[..32]);
+
+    Ok(CompressedRistretto(tmp).decompress()?)
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<Scalar, DeviceError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(&bytes[..32]);
+
+    Scalar::from_canonical_bytes(tmp).ok_or(DeviceError::Credential(CredentialError::ScalarFormatError))
+}
+
+/// The issuer's parameters for device credentials.
+pub type DeviceIssuerParameters = amacs::PublicKey;
+
+/// A new device's request for a device credential.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnrollmentRequest {
+    /// A presentation of the holder's identity credential.
+    pub presentation: CredentialPresentation,
+    /// A commitment to the identity, \\( B \cdot m_1 + A \cdot r_1 \\).
+    pub C1: RistrettoPoint,
+    /// A commitment to the device key, \\( B \cdot m_2 + A \cdot r_2 \\).
+    pub C2: RistrettoPoint,
+    pub proof: device_enrollment::Proof,
+}
+
+impl EnrollmentRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<EnrollmentRequest, DeviceError> {
+        if bytes.len() < 72 {
+            return Err(DeviceError::WrongNumberOfBytes);
+        }
+
+        let C1 = read_point(&bytes[00..32])?;
+        let C2 = read_point(&bytes[32..64])?;
+        let length: usize = read_u64(&bytes[64..72]) as usize;
+
+        if bytes.len() - 72 < length {
+            return Err(DeviceError::WrongNumberOfBytes);
+        }
+
+        let presentation = CredentialPresentation::from_bytes(&bytes[72..72 + length])?;
+        let proof: device_enrollment::Proof = match deserialize(&bytes[72 + length..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(DeviceError::WrongNumberOfBytes),
+        };
+
+        Ok(EnrollmentRequest { presentation, C1, C2, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let presentation: Vec<u8> = self.presentation.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(72 + presentation.len());
+
+        v.extend(self.C1.compress().as_bytes());
+        v.extend(self.C2.compress().as_bytes());
+        v.extend(u64_to_bytes(presentation.len() as u64).iter());
+        v.extend(presentation);
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EnrollmentRequest, "A valid byte sequence representing an EnrollmentRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(EnrollmentRequest);
+impl_try_from_bytes!(EnrollmentRequest, DeviceError);
+
+/// The state a new device keeps between sending its `EnrollmentRequest` and
+/// receiving the `DeviceIssuance` for it.
+#[derive(Debug)]
+pub struct DeviceEnrollment {
+    identity: Secret<Scalar>,
+    device_key: Secret<Scalar>,
+    r1: Ephemeral,
+    r2: Ephemeral,
+    C1: RistrettoPoint,
+    C2: RistrettoPoint,
+}
+
+impl Drop for DeviceEnrollment {
+    fn drop(&mut self) {
+        self.identity.clear();
+        self.device_key.clear();
+    }
+}
+
+impl DeviceEnrollment {
+    /// Begin enrolling this device, with a fresh device key, as a device of
+    /// the holder of the `identity` credential.
+    ///
+    /// # Errors
+    ///
+    /// * `DeviceError::Credential` if the `identity` holds no credential, or
+    ///   it could not be presented.
+    pub fn new<R>(identity: &User, rng: &mut R) -> Result<(DeviceEnrollment, EnrollmentRequest), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &Credential = identity.credential.as_ref().ok_or(CredentialError::MissingData)?;
+
+        let mut transcript = Transcript::new(b"AEONFLUX DEVICE ENROLLMENT");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        // The identity credential is presented with nonces we keep, so that
+        // its commitment to the identity may be opened in our own proof.
+        let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+        let presentation: CredentialPresentation = identity.show(&nonces, &mut csprng)?;
+
+        let A: RistrettoPoint = identity.system_parameters.h;
+        let B: RistrettoPoint = identity.system_parameters.g;
+        let P: RistrettoPoint = presentation.rerandomized_nonce;
+        let Cm0: RistrettoPoint = presentation.attributes_blinded[0].into();
+
+        let identity: Secret<Scalar> = Secret::new(credential.attributes[0]);
+        let device_key: Secret<Scalar> = Secret::new(Scalar::random(&mut csprng));
+        let r1: Ephemeral = Ephemeral::new(&mut csprng);
+        let r2: Ephemeral = Ephemeral::new(&mut csprng);
+        let C1: RistrettoPoint = pedersen::Commitment::to(&(&B * identity.expose_secret()), &r1, &A).into();
+        let C2: RistrettoPoint = pedersen::Commitment::to(&(&B * device_key.expose_secret()), &r2, &A).into();
+
+        let secrets = device_enrollment::Secrets {
+            m1: identity.expose_secret(),
+            m2: device_key.expose_secret(),
+            z0: nonces[0].as_witness(),
+            r1: r1.as_witness(),
+            r2: r2.as_witness(),
+        };
+        let publics = device_enrollment::Publics {
+            B: &B,
+            A: &A,
+            P: &P,
+            Cm0: &Cm0,
+            C1: &C1,
+            C2: &C2,
+        };
+        let proof = device_enrollment::Proof::create(&mut transcript, publics, secrets);
+
+        Ok((DeviceEnrollment { identity, device_key, r1, r2, C1, C2 },
+            EnrollmentRequest { presentation, C1, C2, proof }))
+    }
+
+    /// Verify the `issuance` for our request and, if valid, obtain the
+    /// device's credential.
+    ///
+    /// # Errors
+    ///
+    /// * `DeviceError::Issuance` if the issuer's proof did not verify.
+    pub fn finish(
+        self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &DeviceIssuerParameters,
+        issuance: &DeviceIssuance,
+    ) -> Result<DeviceCredential, Error>
+    {
+        if issuer_parameters.Xn.len() != DEVICE_NUMBER_OF_ATTRIBUTES ||
+           issuance.P == RistrettoPoint::identity()
+        {
+            return Err(DeviceError::Issuance.into());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX DEVICE ISSUANCE");
+        let publics = device_issuance::Publics {
+            B: &system_parameters.g,
+            A: &system_parameters.h,
+            X1: &issuer_parameters.Xn[0],
+            X2: &issuer_parameters.Xn[1],
+            P: &issuance.P,
+            T1_0: &issuance.T1,
+            T1_1: &issuance.T1,
+            T2_0: &issuance.T2,
+            T2_1: &issuance.T2,
+            Cx0: &issuance.secret_key_commitment,
+            C1: &self.C1,
+            C2: &self.C2,
+            Q: &issuance.Q,
+        };
+
+        if issuance.proof.verify(&mut transcript, publics).is_err() {
+            return Err(DeviceError::Issuance.into());
+        }
+
+        // Since Q = (x0 + x1 * m1 + x2 * m2) * P + r1 * T1 + r2 * T2, removing
+        // the commitments' blinding leaves the MAC over our attributes.
+        let mac: RistrettoPoint = issuance.Q - (&issuance.T1 * self.r1.as_scalar()) - (&issuance.T2 * self.r2.as_scalar());
+
+        Ok(DeviceCredential {
+            identity: *self.identity.expose_secret(),
+            device_key: *self.device_key.expose_secret(),
+            tag: amacs::Tag { nonce: issuance.P, mac },
+        })
+    }
+}
+
+/// The issuer's response to an `EnrollmentRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeviceIssuance {
+    pub P: RistrettoPoint,
+    /// The MAC over the committed attributes, still blinded by the requested
+    /// commitments' nonces.
+    pub Q: RistrettoPoint,
+    /// \\( X_1 \cdot b \\), for removing the identity commitment's blinding.
+    pub T1: RistrettoPoint,
+    /// \\( X_2 \cdot b \\), for removing the device key commitment's blinding.
+    pub T2: RistrettoPoint,
+    pub secret_key_commitment: RistrettoPoint,
+    pub proof: device_issuance::Proof,
+}
+
+impl DeviceIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<DeviceIssuance, DeviceError> {
+        if bytes.len() < 160 {
+            return Err(DeviceError::WrongNumberOfBytes);
+        }
+
+        let P = read_point(&bytes[00..32])?;
+        let Q = read_point(&bytes[32..64])?;
+        let T1 = read_point(&bytes[64..96])?;
+        let T2 = read_point(&bytes[96..128])?;
+        let secret_key_commitment = read_point(&bytes[128..160])?;
+        let proof: device_issuance::Proof = match deserialize(&bytes[160..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(DeviceError::WrongNumberOfBytes),
+        };
+
+        Ok(DeviceIssuance { P, Q, T1, T2, secret_key_commitment, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(160);
+
+        v.extend(self.P.compress().as_bytes());
+        v.extend(self.Q.compress().as_bytes());
+        v.extend(self.T1.compress().as_bytes());
+        v.extend(self.T2.compress().as_bytes());
+        v.extend(self.secret_key_commitment.compress().as_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(DeviceIssuance, "A valid byte sequence representing a DeviceIssuance");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(DeviceIssuance);
+impl_try_from_bytes!(DeviceIssuance, DeviceError);
+
+/// An issuer and verifier of device credentials.
+#[derive(Clone, Debug)]
+pub struct DeviceIssuer {
+    pub system_parameters: SystemParameters,
+    pub keypair: amacs::Keypair,
+}
+
+impl DeviceIssuer {
+    /// Create a new `DeviceIssuer` with a fresh key.
+    pub fn create<R>(system_parameters: SystemParameters, csprng: &mut R) -> DeviceIssuer
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = amacs::SecretKey::new(DEVICE_NUMBER_OF_ATTRIBUTES, csprng);
+        let public = secret.get_public_key(&system_parameters.h);
+
+        DeviceIssuer { system_parameters, keypair: amacs::Keypair { public, secret } }
+    }
+
+    /// Get this `DeviceIssuer`'s parameters for publishing to users.
+    pub fn get_issuer_parameters(&self) -> DeviceIssuerParameters {
+        self.keypair.public.clone()
+    }
+
+    fn check_keypair(&self) -> Result<(), DeviceError> {
+        if self.keypair.secret.xn.len() != DEVICE_NUMBER_OF_ATTRIBUTES ||
+           self.keypair.public.Xn.len() != DEVICE_NUMBER_OF_ATTRIBUTES
+        {
+            return Err(DeviceError::Credential(CredentialError::NoIssuerKey));
+        }
+        Ok(())
+    }
+
+    /// Enroll a device of a holder of a credential from the `identity`
+    /// issuer, for the `request` the device made.
+    ///
+    /// # Errors
+    ///
+    /// * `DeviceError::Enrollment` if the identity credential's presentation
+    ///   or the enrollment proof did not verify.
+    /// * `DeviceError::Credential` if this issuer's key is not for device
+    ///   credentials.
+    pub fn enroll<R>(
+        &self,
+        identity: &Issuer,
+        request: &EnrollmentRequest,
+        rng: &mut R,
+    ) -> Result<DeviceIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.check_keypair()?;
+
+        if identity.verify(&request.presentation).is_err() || request.presentation.attributes_blinded.len() != 1 {
+            return Err(DeviceError::Enrollment.into());
+        }
+
+        let A: RistrettoPoint = self.system_parameters.h;
+        let B: RistrettoPoint = self.system_parameters.g;
+        let Cm0: RistrettoPoint = request.presentation.attributes_blinded[0].into();
+
+        let mut transcript = Transcript::new(b"AEONFLUX DEVICE ENROLLMENT");
+        let publics = device_enrollment::Publics {
+            B: &B,
+            A: &A,
+            P: &request.presentation.rerandomized_nonce,
+            Cm0: &Cm0,
+            C1: &request.C1,
+            C2: &request.C2,
+        };
+
+        if request.proof.verify(&mut transcript, publics).is_err() {
+            return Err(DeviceError::Enrollment.into());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX DEVICE ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let x0: &Scalar = &self.keypair.secret.x0;
+        let x1: &Scalar = &self.keypair.secret.xn[0];
+        let x2: &Scalar = &self.keypair.secret.xn[1];
+        let X1: &RistrettoPoint = &self.keypair.public.Xn[0];
+        let X2: &RistrettoPoint = &self.keypair.public.Xn[1];
+
+        // Commit to the issuer's secret x0.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let Cx0: RistrettoPoint = pedersen::Commitment::to(&(&B * x0), &x0_tilde, &A).into();
+
+        // Form the nonce P = b * B, and the auxiliary commitments
+        // Ti = b * Xi = ti * A, which let the holder remove the blinding of
+        // their commitment Ci from the MAC.
+        let b: Ephemeral = Ephemeral::new(&mut csprng);
+        let P: RistrettoPoint = &B * b.as_scalar();
+        let t1: Ephemeral = (b.as_scalar() * x1).into();
+        let t2: Ephemeral = (b.as_scalar() * x2).into();
+        let T1: RistrettoPoint = X1 * b.as_scalar();
+        let T2: RistrettoPoint = X2 * b.as_scalar();
+        let Q: RistrettoPoint = (&P * x0) + (&request.C1 * t1.as_scalar()) + (&request.C2 * t2.as_scalar());
+
+        let secrets = device_issuance::Secrets {
+            x0: x0,
+            x1: x1,
+            x2: x2,
+            x0_tilde: x0_tilde.as_witness(),
+            b: b.as_witness(),
+            t1: t1.as_witness(),
+            t2: t2.as_witness(),
+        };
+        let publics = device_issuance::Publics {
+            B: &B,
+            A: &A,
+            X1: X1,
+            X2: X2,
+            P: &P,
+            T1_0: &T1,
+            T1_1: &T1,
+            T2_0: &T2,
+            T2_1: &T2,
+            Cx0: &Cx0,
+            C1: &request.C1,
+            C2: &request.C2,
+            Q: &Q,
+        };
+        let proof = device_issuance::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(DeviceIssuance { P, Q, T1, T2, secret_key_commitment: Cx0, proof })
+    }
+
+    /// Verify a `presentation` of a device credential, and that its
+    /// nullifier was derived within `scope` from the same identity.
+    ///
+    /// As with `nullifier::verify()`, whether the nullifier was seen before
+    /// is up to the caller.
+    ///
+    /// # Errors
+    ///
+    /// * `DeviceError::VerificationFailure` if the device credential was not
+    ///   issued by us, or the nullifier is not for this `scope`.
+    pub fn verify(&self, scope: &[u8], presentation: &DevicePresentation) -> Result<(), Error> {
+        self.check_keypair()?;
+
+        if presentation.P == RistrettoPoint::identity() {
+            return Err(DeviceError::VerificationFailure.into());
+        }
+
+        let N: RistrettoPoint = CompressedRistretto(presentation.nullifier.0).decompress()?;
+        let H: RistrettoPoint = hash_scope_to_group(scope);
+
+        // The MAC is recomputed under the secret key, so this must remain
+        // constant-time.
+        let V: RistrettoPoint = (&presentation.P * &self.keypair.secret.x0) +
+                                (&presentation.Cm1 * &self.keypair.secret.xn[0]) +
+                                (&presentation.Cm2 * &self.keypair.secret.xn[1]) -
+                                presentation.CQ;
+
+        let mut transcript = presentation_transcript(scope);
+        let publics = valid_device::Publics {
+            A: &self.system_parameters.h,
+            P: &presentation.P,
+            X1: &self.keypair.public.Xn[0],
+            X2: &self.keypair.public.Xn[1],
+            V: &V,
+            Cm1: &presentation.Cm1,
+            Cm2: &presentation.Cm2,
+            H: &H,
+            N: &N,
+        };
+
+        if presentation.proof.verify(&mut transcript, publics).is_err() {
+            return Err(DeviceError::VerificationFailure.into());
+        }
+        Ok(())
+    }
+}
+
+fn presentation_transcript(scope: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"AEONFLUX DEVICE PRESENTATION");
+
+    transcript.commit_bytes(b"scope", scope);
+    transcript
+}
+
+/// A device credential, held by one of the holder's devices.
+#[derive(Clone, Eq, PartialEq)]
+pub struct DeviceCredential {
+    identity: Scalar,
+    device_key: Scalar,
+    tag: amacs::Tag,
+}
+
+/// As with a `Credential`, tag and attributes are the holder's secrets.
+impl fmt::Debug for DeviceCredential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DeviceCredential {{ identity: {}, device_key: {}, tag: {} }}", REDACTED, REDACTED, REDACTED)
+    }
+}
+
+impl DeviceCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<DeviceCredential, DeviceError> {
+        if bytes.len() != SIZEOF_DEVICE_CREDENTIAL {
+            return Err(DeviceError::WrongNumberOfBytes);
+        }
+
+        let identity = read_scalar(&bytes[00..32])?;
+        let device_key = read_scalar(&bytes[32..64])?;
+        let tag = amacs::Tag::from_bytes(&bytes[64..])?;
+
+        Ok(DeviceCredential { identity, device_key, tag })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_DEVICE_CREDENTIAL);
+
+        v.extend(self.identity.as_bytes());
+        v.extend(self.device_key.as_bytes());
+        v.extend(self.tag.to_bytes());
+        v
+    }
+
+    /// Present this device credential with the identity's nullifier in a
+    /// `scope`, hiding both the identity and the device key.
+    ///
+    /// # Errors
+    ///
+    /// * `DeviceError::Credential` if the `issuer_parameters` are not for
+    ///   device credentials.
+    pub fn present<R>(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &DeviceIssuerParameters,
+        scope: &[u8],
+        rng: &mut R,
+    ) -> Result<DevicePresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if issuer_parameters.Xn.len() != DEVICE_NUMBER_OF_ATTRIBUTES {
+            return Err(DeviceError::Credential(CredentialError::NoIssuerParameters).into());
+        }
+
+        let mut transcript = presentation_transcript(scope);
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let A: RistrettoPoint = system_parameters.h;
+        let H: RistrettoPoint = hash_scope_to_group(scope);
+        let tag: amacs::Tag = amacs::Rerandomization::new(&mut csprng).apply_to_tag(&self.tag);
+        let P: RistrettoPoint = tag.nonce;
+
+        // Commit to the rerandomised aMAC and to each attribute.
+        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+        let z1: Ephemeral = Ephemeral::new(&mut csprng);
+        let z2: Ephemeral = Ephemeral::new(&mut csprng);
+        let CQ: RistrettoPoint = pedersen::Commitment::to(&tag.mac, &zQ, &A).into();
+        let Cm1: RistrettoPoint = pedersen::Commitment::to(&(&P * &self.identity), &z1, &A).into();
+        let Cm2: RistrettoPoint = pedersen::Commitment::to(&(&P * &self.device_key), &z2, &A).into();
+        let V: RistrettoPoint = (&issuer_parameters.Xn[0] * z1.as_scalar()) +
+                                (&issuer_parameters.Xn[1] * z2.as_scalar()) -
+                                (&A * zQ.as_scalar());
+        let N: RistrettoPoint = &H * &self.identity;
+
+        let minus_zQ: Scalar = -zQ.as_scalar();
+        let secrets = valid_device::Secrets {
+            m1: &self.identity,
+            m2: &self.device_key,
+            z1: z1.as_witness(),
+            z2: z2.as_witness(),
+            minus_zQ: &minus_zQ,
+        };
+        let publics = valid_device::Publics {
+            A: &A,
+            P: &P,
+            X1: &issuer_parameters.Xn[0],
+            X2: &issuer_parameters.Xn[1],
+            V: &V,
+            Cm1: &Cm1,
+            Cm2: &Cm2,
+            H: &H,
+            N: &N,
+        };
+        let proof = valid_device::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(DevicePresentation { P, CQ, Cm1, Cm2, nullifier: Nullifier::from(N), proof })
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(DeviceCredential, "A valid byte sequence representing a DeviceCredential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(DeviceCredential);
+impl_try_from_bytes!(DeviceCredential, DeviceError);
+
+/// A presentation of a `DeviceCredential`, hiding its attributes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DevicePresentation {
+    /// The rerandomised aMAC nonce.
+    pub P: RistrettoPoint,
+    /// A commitment to the rerandomised aMAC.
+    pub CQ: RistrettoPoint,
+    /// A commitment to the identity.
+    pub Cm1: RistrettoPoint,
+    /// A commitment to the device key.
+    pub Cm2: RistrettoPoint,
+    /// The identity's nullifier in the scope of the presentation.
+    pub nullifier: Nullifier,
+    pub proof: valid_device::Proof,
+}
+
+impl DevicePresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<DevicePresentation, DeviceError> {
+        if bytes.len() < 160 {
+            return Err(DeviceError::WrongNumberOfBytes);
+        }
+
+        let P = read_point(&bytes[00..32])?;
+        let CQ = read_point(&bytes[32..64])?;
+        let Cm1 = read_point(&bytes[64..96])?;
+        let Cm2 = read_point(&bytes[96..128])?;
+        let nullifier = Nullifier::from_bytes(&bytes[128..160])?;
+        let proof: valid_device::Proof = match deserialize(&bytes[160..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(DeviceError::WrongNumberOfBytes),
+        };
+
+        Ok(DevicePresentation { P, CQ, Cm1, Cm2, nullifier, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(160);
+
+        v.extend(self.P.compress().as_bytes());
+        v.extend(self.CQ.compress().as_bytes());
+        v.extend(self.Cm1.compress().as_bytes());
+        v.extend(self.Cm2.compress().as_bytes());
+        v.extend(self.nullifier.0.iter());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(DevicePresentation, "A valid byte sequence representing a DevicePresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(DevicePresentation);
+impl_try_from_bytes!(DevicePresentation, DeviceError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use nullifier;
+    use test_utils::H;
+    use test_utils::holder;
+
+    fn enroll(devices: &DeviceIssuer, issuer: &Issuer, identity: &User) -> DeviceCredential {
+        let mut rng = thread_rng();
+        let (enrollment, request) = DeviceEnrollment::new(identity, &mut rng).unwrap();
+        let request = EnrollmentRequest::from_bytes(&request.to_bytes()).unwrap();
+        let issuance = devices.enroll(issuer, &request, &mut rng).unwrap();
+        let issuance = DeviceIssuance::from_bytes(&issuance.to_bytes()).unwrap();
+
+        enrollment.finish(&devices.system_parameters, &devices.get_issuer_parameters(), &issuance).unwrap()
+    }
+
+    #[test]
+    fn devices_share_their_holders_nullifiers() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let devices = DeviceIssuer::create(system_parameters, &mut rng);
+        let parameters = devices.get_issuer_parameters();
+        let key = Scalar::random(&mut rng);
+        let identity = holder(&issuer, vec![key], &mut rng);
+
+        let phone = enroll(&devices, &issuer, &identity);
+        let laptop = enroll(&devices, &issuer, &identity);
+
+        assert!(DeviceCredential::from_bytes(&phone.to_bytes()).unwrap() == phone);
+        assert!(phone != laptop);
+
+        let from_phone = phone.present(&system_parameters, &parameters, b"poll 1", &mut rng).unwrap();
+        let from_phone = DevicePresentation::from_bytes(&from_phone.to_bytes()).unwrap();
+        let from_laptop = laptop.present(&system_parameters, &parameters, b"poll 1", &mut rng).unwrap();
+
+        assert!(devices.verify(b"poll 1", &from_phone).is_ok());
+        assert!(devices.verify(b"poll 1", &from_laptop).is_ok());
+
+        // Every device, and the identity credential itself, is the same
+        // holder within a scope.
+        assert!(from_phone.nullifier == from_laptop.nullifier);
+        assert!(from_phone.nullifier == nullifier::scoped_nullifier(b"poll 1", &key));
+
+        // A presentation verifies only in its own scope.
+        assert!(devices.verify(b"poll 2", &from_phone) == Err(DeviceError::VerificationFailure.into()));
+    }
+
+    #[test]
+    fn enrollment_requires_the_identity_credential() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let stranger = Issuer::create(system_parameters, &mut rng);
+        let devices = DeviceIssuer::create(system_parameters, &mut rng);
+        let alice = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+        let bob = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+
+        let (_, request) = DeviceEnrollment::new(&alice, &mut rng).unwrap();
+        let (_, other) = DeviceEnrollment::new(&bob, &mut rng).unwrap();
+
+        // The identity credential must be from the identity issuer, and the
+        // committed identity must be the presented one.
+        assert!(devices.enroll(&stranger, &request, &mut rng).err() == Some(DeviceError::Enrollment.into()));
+
+        let mut swapped = request.clone();
+
+        swapped.C1 = other.C1;
+
+        assert!(devices.enroll(&issuer, &swapped, &mut rng).err() == Some(DeviceError::Enrollment.into()));
+        assert!(devices.enroll(&issuer, &request, &mut rng).is_ok());
+
+        // A device credential only verifies with the issuer which enrolled
+        // it.
+        let phone = enroll(&devices, &issuer, &alice);
+        let elsewhere = DeviceIssuer::create(system_parameters, &mut rng);
+        let presentation = phone.present(&system_parameters, &devices.get_issuer_parameters(), b"poll", &mut rng).unwrap();
+
+        assert!(elsewhere.verify(b"poll", &presentation) == Err(DeviceError::VerificationFailure.into()));
+        assert!(DeviceEnrollment::new(&User::new(system_parameters, issuer.get_issuer_parameters(), None), &mut rng).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Credentials bound to a signing key the holder already has.
+//!
+//! A holder with an existing Ristretto, or with the `ed25519` feature an
+//! Ed25519, signing key may obtain a credential whose hidden attribute is
+//! the key's secret scalar \\( x \\).  The credential is issued blindly, so
+//! the issuer never learns which key it is bound to, and is presented as
+//! usual with `User::show()`, which reveals nothing about the key either.
+//! Since the attribute is the secret key itself, a holder cannot lend their
+//! credential to anyone without also lending them their key, and the
+//! credential must be stored as carefully as the key.
+//!
+//! Where a holder wants to authenticate a channel, they may instead `show()`
+//! it here.  This reveals neither the key nor its public half, but only a
+//! commitment \\( K = x \cdot B\_{\mathrm{chan}} \\) to it, where
+//! \\( B\_{\mathrm{chan}} \\) is hashed from the `ChannelBinding` of the
+//! connection, e.g. its TLS exporter value, and proves, under the credential
+//! proof's challenge, that the holder knows the \\( x \\) behind both the
+//! commitment and the credential's hidden attribute.  The verifier learns
+//! that whoever made the presentation on this connection holds the key a
+//! valid credential is bound to.  Since every connection has its own
+//! basepoint, presentations over different connections cannot be linked to
+//! each other, or by the issuer to the issuance.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+#[cfg(feature = "ed25519")]
+use ed25519_dalek;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+#[cfg(feature = "ed25519")]
+use zeroize::Zeroize;
+
+use channel_binding::ChannelBinding;
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::CredentialPresentation;
+use credential::PresentationOptions;
+use credential::VerifiedCredential;
+use errors::CredentialError;
+use errors::DualKeyError;
+use errors::Error;
+use hashing::Hasher;
+use issuer::Issuer;
+use nonces::Nonces;
+use trust::RegistryKeypair;
+use user::User;
+
+/// The domain separator for hashing a channel binding to the basepoint of
+/// committed keys.
+const CHANNEL_BASEPOINT_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux dual-key channel basepoint v1";
+
+/// The size of an encoded committed key.
+pub const SIZEOF_COMMITTED_KEY: usize = 32;
+
+/// The basepoint \\( B\_{\mathrm{chan}} \\) of keys committed to on the
+/// connection with this `channel` binding.
+fn channel_basepoint(channel: &ChannelBinding) -> RistrettoPoint {
+    let mut h = Hasher::new(CHANNEL_BASEPOINT_DOMAIN_SEPARATOR);
+
+    h.input(channel.value());
+    h.finalize_point()
+}
+
+/// A holder's signing key, which their credential is bound to.
+pub enum SigningKey {
+    /// A Schnorr key over Ristretto, as made with `RegistryKeypair`.
+    Ristretto(RegistryKeypair),
+    #[cfg(feature = "ed25519")]
+    Ed25519(ed25519_dalek::Keypair),
+}
+
+impl SigningKey {
+    /// The hidden attribute of a credential bound to this key, its secret
+    /// scalar.
+    fn attribute(&self) -> Scalar {
+        match *self {
+            SigningKey::Ristretto(ref keypair) => *keypair.secret(),
+            // The secret scalar of an Ed25519 key is the first half of its
+            // expansion, which is clamped but otherwise taken as it is.
+            #[cfg(feature = "ed25519")]
+            SigningKey::Ed25519(ref keypair) => {
+                let mut expanded = ed25519_dalek::ExpandedSecretKey::from(&keypair.secret).to_bytes();
+                let mut bytes = [0u8; 32];
+
+                bytes.copy_from_slice(&expanded[..32]);
+                let x = Scalar::from_bytes_mod_order(bytes);
+
+                expanded.zeroize();
+                bytes.zeroize();
+                x
+            },
+        }
+    }
+}
+
+/// Request a credential bound to the `key`, without revealing it, as with
+/// `User::blind_obtain()`.
+pub fn request<R>(user: &mut User, key: &SigningKey, rng: &mut R) -> Result<CredentialBlindRequest, Error>
+where
+    R: RngCore + CryptoRng,
+{
+    Ok(user.blind_obtain(&[key.attribute()], rng)?)
+}
+
+/// Finish obtaining a credential bound to the `key`, as with
+/// `User::blind_obtain_finish()`.
+pub fn obtain_finish(user: &mut User, issuance: Option<&CredentialBlindIssuance>, key: &SigningKey)
+    -> Result<(), Error>
+{
+    Ok(user.blind_obtain_finish(issuance, &[key.attribute()])?)
+}
+
+/// A presentation of a credential bound to a key, proving possession of it
+/// on one connection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DualKeyPresentation {
+    /// The commitment \\( K = x \cdot B\_{\mathrm{chan}} \\) to the key.
+    pub key: RistrettoPoint,
+    pub presentation: CredentialPresentation,
+}
+
+impl DualKeyPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<DualKeyPresentation, DualKeyError> {
+        if bytes.len() < SIZEOF_COMMITTED_KEY {
+            return Err(DualKeyError::WrongNumberOfBytes);
+        }
+
+        let key = CompressedRistretto::from_slice(&bytes[..SIZEOF_COMMITTED_KEY]).decompress()?;
+        let presentation = CredentialPresentation::from_bytes(&bytes[SIZEOF_COMMITTED_KEY..])?;
+
+        Ok(DualKeyPresentation { key, presentation })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.key.compress().as_bytes());
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(DualKeyPresentation, "A valid byte sequence representing a DualKeyPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(DualKeyPresentation);
+impl_try_from_bytes!(DualKeyPresentation, DualKeyError);
+
+/// Present the `user`'s credential, committing to the `key` it is bound to
+/// under the `channel` binding of the connection it is sent over, and
+/// proving possession of it.
+///
+/// # Errors
+///
+/// * `DualKeyError::WrongKey` if the credential is not bound to the `key`.
+/// * `DualKeyError::Credential` if the credential could not be presented.
+pub fn show<R>(
+    user: &User,
+    key: &SigningKey,
+    channel: &ChannelBinding,
+    nonces: &Nonces,
+    rng: &mut R,
+) -> Result<DualKeyPresentation, Error>
+where
+    R: RngCore + CryptoRng,
+{
+    match user.credential {
+        Some(ref credential) if credential.attributes.first() == Some(&key.attribute()) => (),
+        Some(_) => return Err(DualKeyError::WrongKey.into()),
+        None    => return Err(DualKeyError::Credential(CredentialError::MissingData).into()),
+    }
+
+    let basepoint: RistrettoPoint = channel_basepoint(channel);
+    let options = PresentationOptions::new().scoped(&basepoint).bound(channel);
+
+    match user.show_with(&options, nonces, rng)? {
+        (presentation, Some(key)) => Ok(DualKeyPresentation { key, presentation }),
+        (_, None)                 => Err(DualKeyError::Credential(CredentialError::MissingData).into()),
+    }
+}
+
+/// Verify a `presentation` made over the connection with this `channel`
+/// binding, that its credential is bound to the key it commits to, and that
+/// the holder possesses that key.
+///
+/// # Errors
+///
+/// * `DualKeyError::Credential` if the presentation did not verify, its
+///   commitment is to another key, or it was made on another connection.
+pub fn verify(
+    issuer: &Issuer,
+    channel: &ChannelBinding,
+    presentation: &DualKeyPresentation,
+) -> Result<VerifiedCredential, Error>
+{
+    let basepoint: RistrettoPoint = channel_basepoint(channel);
+    let options = PresentationOptions::new().scoped(&basepoint).bound(channel);
+
+    Ok(issuer.verify_with(&presentation.presentation, &options, Some(&presentation.key))?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[cfg(feature = "ed25519")]
+    use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    fn bound(issuer: &Issuer, key: &SigningKey) -> User {
+        let mut rng = thread_rng();
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let blind = request(&mut user, key, &mut rng).unwrap();
+        let issuance = issuer.blind_issue(&blind, &mut rng).unwrap();
+
+        obtain_finish(&mut user, Some(&issuance), key).unwrap();
+        user
+    }
+
+    #[test]
+    fn keys_are_proven_on_their_channel() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let keypair = RegistryKeypair::generate(&mut rng);
+        let public = keypair.public;
+        let key = SigningKey::Ristretto(keypair);
+        let user = bound(&issuer, &key);
+        let exporter = ChannelBinding::tls_exporter([1u8; 32]);
+
+        let shown = show(&user, &key, &exporter, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let shown = DualKeyPresentation::from_bytes(&shown.to_bytes()).unwrap();
+
+        assert!(verify(&issuer, &exporter, &shown).is_ok());
+
+        // The presentation is only good on the channel it was made for.
+        assert!(verify(&issuer, &ChannelBinding::tls_exporter([2u8; 32]), &shown) ==
+                Err(DualKeyError::Credential(CredentialError::MacVerification).into()));
+
+        // Neither the key nor its commitment on one channel is revealed on
+        // another.
+        let other = ChannelBinding::tls_exporter([2u8; 32]);
+        let elsewhere = show(&user, &key, &other, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(verify(&issuer, &other, &elsewhere).is_ok());
+        assert!(shown.key != public);
+        assert!(elsewhere.key != public);
+        assert!(elsewhere.key != shown.key);
+
+        // An ordinary presentation of the same credential reveals no key.
+        assert!(issuer.verify(&user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn credentials_are_bound_to_one_key() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let key = SigningKey::Ristretto(RegistryKeypair::generate(&mut rng));
+        let other = SigningKey::Ristretto(RegistryKeypair::generate(&mut rng));
+        let user = bound(&issuer, &key);
+
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let channel = ChannelBinding::noise_handshake_hash(&[0u8; 64]);
+
+        assert!(show(&user, &other, &channel, &nonces, &mut rng).err() == Some(DualKeyError::WrongKey.into()));
+
+        // Committing to another key fails the credential proof.
+        let shown = show(&user, &key, &channel, &nonces, &mut rng).unwrap();
+        let substituted = DualKeyPresentation {
+            key: &channel_basepoint(&channel) * &other.attribute(),
+            presentation: shown.presentation,
+        };
+
+        assert!(verify(&issuer, &channel, &substituted) ==
+                Err(DualKeyError::Credential(CredentialError::MacVerification).into()));
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    #[allow(non_snake_case)]
+    fn ed25519_keys_are_proven_on_their_channel() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let key = SigningKey::Ed25519(ed25519_dalek::Keypair { secret, public });
+        let user = bound(&issuer, &key);
+        let channel = ChannelBinding::tls_exporter([3u8; 32]);
+
+        let shown = show(&user, &key, &channel, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(verify(&issuer, &channel, &shown).is_ok());
+
+        // The credential is bound to the secret scalar behind the public key.
+        let A = &ED25519_BASEPOINT_TABLE * &key.attribute();
+
+        assert!(A.compress().as_bytes() == public.as_bytes());
+    }
+}
@@ -18,8 +18,6 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
-use clear_on_drop::clear::Clear;
-
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -32,8 +30,11 @@ use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
 use errors::CredentialError;
+use secret::Secret;
 
 pub use nonces::Ephemeral;
+use nonces::Nonces;
+use nonces::Witness;
 
 pub const SIZEOF_PUBLIC_KEY: usize = 32;
 pub const SIZEOF_SECRET_KEY: usize = 32;
@@ -46,7 +47,7 @@ pub struct PublicKey(pub(crate) RistrettoPoint);
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[repr(C)]
-pub struct SecretKey(pub(crate) Scalar);
+pub struct SecretKey(pub(crate) Secret<Scalar>);
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
@@ -94,15 +95,97 @@ impl<'a, 'b> Add<&'b Encryption> for &'a Encryption {
     }
 }
 
+impl Encryption {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Encryption, CredentialError> {
+        if bytes.len() != SIZEOF_ENCRYPTION {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut commitment_bytes = [0u8; 32];
+        let mut encryption_bytes = [0u8; 32];
+
+        commitment_bytes.copy_from_slice(&bytes[00..32]);
+        encryption_bytes.copy_from_slice(&bytes[32..64]);
+
+        let commitment = CompressedRistretto(commitment_bytes).decompress().ok_or(CredentialError::PointDecompressionError)?;
+        let encryption = CompressedRistretto(encryption_bytes).decompress().ok_or(CredentialError::PointDecompressionError)?;
+
+        Ok(Encryption{ commitment, encryption })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENCRYPTION);
+
+        v.extend(self.commitment.compress().to_bytes().iter());
+        v.extend(self.encryption.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Encryption, "A valid byte sequence representing an elgamal::Encryption");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Encryption);
+impl_try_from_bytes!(Encryption, CredentialError);
+
+/// An `Encryption` borrowed from an encoded buffer, whose points are only
+/// decompressed when they are asked for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncryptionRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> EncryptionRef<'a> {
+    /// Check that `bytes` are the right length for an `Encryption`, without
+    /// copying or decompressing them.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<EncryptionRef<'a>, CredentialError> {
+        if bytes.len() != SIZEOF_ENCRYPTION {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        Ok(EncryptionRef { bytes })
+    }
+
+    /// The underlying encoded bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decompress the commitment part of this encryption.
+    pub fn commitment(&self) -> Result<RistrettoPoint, CredentialError> {
+        Ok(CompressedRistretto::from_slice(&self.bytes[00..32]).decompress().ok_or(CredentialError::PointDecompressionError)?)
+    }
+
+    /// Decompress the encryption part of this encryption.
+    pub fn encryption(&self) -> Result<RistrettoPoint, CredentialError> {
+        Ok(CompressedRistretto::from_slice(&self.bytes[32..64]).decompress().ok_or(CredentialError::PointDecompressionError)?)
+    }
+
+    /// Decompress both points into an owned `Encryption`.
+    pub fn decode(&self) -> Result<Encryption, CredentialError> {
+        Ok(Encryption { commitment: self.commitment()?, encryption: self.encryption()? })
+    }
+}
+
+impl<'a> ::core::convert::TryFrom<&'a [u8]> for EncryptionRef<'a> {
+    type Error = CredentialError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<EncryptionRef<'a>, CredentialError> {
+        EncryptionRef::from_bytes(bytes)
+    }
+}
+
 impl PublicKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<PublicKey, CredentialError> {
-        assert!(bytes.len() == 32);
+        if bytes.len() != SIZEOF_PUBLIC_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
 
         let mut tmp = [0u8; 32];
 
         tmp.copy_from_slice(bytes);
 
-        let point = CompressedRistretto(tmp).decompress()?;
+        let point = CompressedRistretto(tmp).decompress().ok_or(CredentialError::PointDecompressionError)?;
 
         Ok(PublicKey(point))
     }
@@ -117,6 +200,9 @@ impl PublicKey {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(PublicKey, "A valid byte sequence representing an elgamal::PublicKey");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PublicKey);
+impl_try_from_bytes!(PublicKey, CredentialError);
 
 impl PublicKey {
     pub fn encrypt(&self, message: &Message, nonce: &Ephemeral)
@@ -128,6 +214,45 @@ impl PublicKey {
 
         Encryption{ commitment, encryption }
     }
+
+    /// Encrypt each of a vector of `attributes` to this key, generating all
+    /// of their nonces in one pass.
+    ///
+    /// Every attribute is encrypted under its own nonce, as a separate pair.
+    /// Sharing a single commitment across the vector is only sound when each
+    /// attribute is encrypted to a different key: under one key, the
+    /// difference of any two encryptions would be the difference of their
+    /// attributes.
+    ///
+    /// # Returns
+    ///
+    /// The encryptions, in the order of `attributes`, and the `Plaintexts`
+    /// which the caller must retain for proving statements about them.
+    pub fn encrypt_attributes<R>(
+        &self,
+        attributes: &[Scalar],
+        csprng: &mut R,
+    ) -> (Vec<Encryption>, Plaintexts)
+    where
+        R: CryptoRng + RngCore,
+    {
+        let nonces: Nonces = Nonces::new(csprng, attributes.len());
+        let encryptions: Vec<Encryption> = attributes.iter().zip(nonces.iter())
+            .map(|(attribute, nonce)| self.encrypt(&Message::from(attribute), nonce))
+            .collect();
+
+        (encryptions, Plaintexts { attributes: attributes.to_vec(), nonces })
+    }
+}
+
+/// The attributes and nonces behind a vector of encryptions made with
+/// `PublicKey::encrypt_attributes`.
+#[derive(Clone, Debug)]
+pub struct Plaintexts {
+    pub attributes: Vec<Scalar>,
+    /// The nonce each of `attributes` was encrypted under, which are cleared
+    /// when dropped.
+    pub nonces: Nonces,
 }
 
 impl From<PublicKey> for RistrettoPoint {
@@ -138,52 +263,57 @@ impl From<PublicKey> for RistrettoPoint {
 
 impl<'a> From<&'a SecretKey> for PublicKey {
     fn from(secret: &'a SecretKey) -> PublicKey {
-        PublicKey(&RISTRETTO_BASEPOINT_TABLE * &secret.0)
+        PublicKey(&RISTRETTO_BASEPOINT_TABLE * secret.0.expose_secret())
     }
 }
 
 impl SecretKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, CredentialError> {
-        assert!(bytes.len() == 32);
+        if bytes.len() != SIZEOF_SECRET_KEY {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
 
         let mut tmp = [0u8; 32];
 
         tmp.copy_from_slice(bytes);
 
-        let s = Scalar::from_canonical_bytes(tmp)?;
+        let s = Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)?;
 
-        Ok(SecretKey(s))
+        Ok(SecretKey(Secret::new(s)))
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Vec::with_capacity(32);
 
-        v.extend(self.0.to_bytes().iter());
+        v.extend(self.0.expose_secret().to_bytes().iter());
 
         v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(SecretKey, "A valid byte sequence representing an elamal::SecretKey");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SecretKey);
+impl_try_from_bytes!(SecretKey, CredentialError);
 
 impl SecretKey {
     pub fn generate<C>(csprng: &mut C) -> SecretKey
     where
         C: CryptoRng + RngCore,
     {
-        SecretKey(Scalar::random(csprng))
+        SecretKey(Secret::new(Scalar::random(csprng)))
     }
 
     pub fn decrypt(&self, encryption: &Encryption) -> RistrettoPoint {
-        let secret: RistrettoPoint = &encryption.commitment * &self.0;
+        let secret: RistrettoPoint = &encryption.commitment * self.0.expose_secret();
 
         &encryption.encryption - &secret
     }
-}
 
-impl From<SecretKey> for Scalar {
-    fn from(secret: SecretKey) -> Scalar {
-        secret.0
+    /// Borrow this key as the witness to a proof, leaving the scalar where it
+    /// is, to be overwritten when the key is dropped.
+    pub fn as_witness(&self) -> Witness {
+        Witness::from_secret(&self.0)
     }
 }
 
@@ -196,7 +326,9 @@ impl Drop for SecretKey {
 
 impl Keypair {
     pub fn from_bytes(bytes: &[u8]) -> Result<Keypair, CredentialError> {
-        assert!(bytes.len() == 64);
+        if bytes.len() != 64 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
 
         let secret = SecretKey::from_bytes(&bytes[00..32])?;
         let public = PublicKey::from_bytes(&bytes[32..64])?;
@@ -207,7 +339,9 @@ impl Keypair {
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Vec::with_capacity(64);
 
-        v.extend(self.secret.to_bytes());
+        // Written directly, rather than through `SecretKey::to_bytes()`, so
+        // that no intermediate copy of the secret key is left on the heap.
+        v.extend(self.secret.0.expose_secret().as_bytes().iter());
         v.extend(self.public.to_bytes());
 
         v
@@ -215,6 +349,9 @@ impl Keypair {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(Keypair, "A valid byte sequence representing an elgamal::Keypair");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Keypair);
+impl_try_from_bytes!(Keypair, CredentialError);
 
 impl Keypair {
     pub fn generate<C>(csprng: &mut C) -> Keypair
@@ -237,6 +374,8 @@ impl Keypair {
 mod test {
     use super::*;
 
+    use curve25519_dalek::constants::BASEPOINT_ORDER;
+
     use rand::thread_rng;
 
     #[test]
@@ -249,4 +388,95 @@ mod test {
 
         assert!(keypair.secret.decrypt(&enc) == msg.0);
     }
+
+    #[test]
+    fn secret_keys_are_borrowed_as_witnesses() {
+        let keypair = Keypair::generate(&mut thread_rng());
+
+        // The witness is the key's own scalar, not a copy of it.
+        assert!(::core::ptr::eq(keypair.secret.as_witness().scalar(), keypair.secret.0.expose_secret()));
+        assert!(&RISTRETTO_BASEPOINT_TABLE * keypair.secret.as_witness().scalar() == keypair.public.0);
+    }
+
+    #[test]
+    fn encryption_ref_is_lazy() {
+        let mut csprng = thread_rng();
+        let nonce = Ephemeral::new(&mut csprng);
+        let msg = Message(&RISTRETTO_BASEPOINT_TABLE * &nonce);
+        let keypair = Keypair::generate(&mut csprng);
+        let enc = keypair.public.encrypt(&msg, &nonce);
+        let mut bytes = enc.to_bytes();
+
+        assert!(EncryptionRef::from_bytes(&bytes).unwrap().decode().unwrap() == enc);
+        assert!(EncryptionRef::from_bytes(&bytes[1..]).is_err());
+
+        // An invalid encryption part is only noticed when it is used.
+        for byte in bytes[32..64].iter_mut() {
+            *byte = 0xff;
+        }
+
+        let borrowed = EncryptionRef::from_bytes(&bytes).unwrap();
+
+        assert!(borrowed.commitment().unwrap() == enc.commitment);
+        assert!(borrowed.encryption().is_err());
+    }
+
+    #[test]
+    fn encrypt_attributes_roundtrip() {
+        let mut csprng = thread_rng();
+        let keypair = Keypair::generate(&mut csprng);
+        let attributes: Vec<Scalar> = (0..3).map(|_| Scalar::random(&mut csprng)).collect();
+        let (encryptions, plaintexts) = keypair.public.encrypt_attributes(&attributes, &mut csprng);
+
+        assert!(encryptions.len() == 3);
+        assert!(plaintexts.attributes == attributes);
+
+        for (i, encryption) in encryptions.iter().enumerate() {
+            assert!(keypair.secret.decrypt(encryption) == Message::from(&attributes[i]).0);
+            assert!(keypair.public.encrypt(&Message::from(&attributes[i]), &plaintexts.nonces[i]) == *encryption);
+        }
+        assert!(encryptions[0].commitment != encryptions[1].commitment);
+    }
+
+    /// Add the group order to a little-endian scalar encoding, which a
+    /// decoder that reduced modulo the order would silently accept.
+    fn add_basepoint_order(bytes: &mut [u8; 32]) {
+        let order: &[u8; 32] = BASEPOINT_ORDER.as_bytes();
+        let mut carry: u16 = 0;
+
+        for i in 0..32 {
+            let sum: u16 = bytes[i] as u16 + order[i] as u16 + carry;
+
+            bytes[i] = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+
+    #[test]
+    fn decoders_reject_malleated_inputs() {
+        let mut csprng = thread_rng();
+        let keypair = Keypair::generate(&mut csprng);
+        let message = Message::from(&Scalar::random(&mut csprng));
+        let encryption = keypair.public.encrypt(&message, &Ephemeral::new(&mut csprng));
+
+        // s + l encodes the same scalar as s, but not canonically.
+        let mut secret: [u8; 32] = [0u8; 32];
+
+        secret.copy_from_slice(&keypair.secret.to_bytes());
+        add_basepoint_order(&mut secret);
+
+        assert!(SecretKey::from_bytes(&secret) == Err(CredentialError::ScalarFormatError));
+        assert!(SecretKey::from_bytes(&secret[..31]) == Err(CredentialError::WrongNumberOfBytes));
+
+        // Ristretto encodings with the low bit set are never valid.
+        let mut bytes = encryption.to_bytes();
+
+        bytes[32] |= 1;
+
+        assert!(Encryption::from_bytes(&bytes) == Err(CredentialError::PointDecompressionError));
+        assert!(EncryptionRef::from_bytes(&bytes).unwrap().encryption() ==
+                Err(CredentialError::PointDecompressionError));
+        assert!(PublicKey::from_bytes(&[0xffu8; 32]) == Err(CredentialError::PointDecompressionError));
+        assert!(PublicKey::from_bytes(&[0u8; 33]) == Err(CredentialError::WrongNumberOfBytes));
+    }
 }
@@ -0,0 +1,381 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Proofs of eligibility attached to blind credential requests.
+//!
+//! An issuer may only wish to issue credentials whose hidden attribute is on
+//! some allow-list, e.g. a hash of a phone number which belongs to one of its
+//! members, without learning which attribute it is.  The keeper of the list
+//! runs a keyed-verification `Accumulator`, as in `non_revocation`, and only
+//! hands out witnesses for elements on the list, so that removing an element
+//! from the list is revoking it from the accumulator.
+//!
+//! The user attaches to their `CredentialBlindRequest` an `EligibilityProof`
+//! that the attribute they encrypted in it is the one their witness is for,
+//! rerandomising the witness as for a `NonRevocationPresentation`.  The
+//! issuer checks the proof with any `EligibilityCheck` before issuing, the
+//! `Accumulator` itself being one, and learns nothing more about the
+//! attribute than that it is on the list.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::EncryptedAttribute;
+use credential::RevealedAttribute;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::EligibilityError;
+use errors::Error;
+use issuer::Issuer;
+use non_revocation::Accumulator;
+use non_revocation::NonRevocationWitness;
+use nonces::Nonces;
+use parameters::SystemParameters;
+use proofs::eligibility;
+use secret::Clear;
+use user::User;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, EligibilityError> {
+    match CompressedRistretto::from_slice(&bytes[..32]).decompress() {
+        Some(x) => Ok(x),
+        None    => Err(EligibilityError::Credential(CredentialError::PointDecompressionError)),
+    }
+}
+
+/// The blinded attribute of a `request`, and the user's public key it was
+/// encrypted to.
+fn blinded(request: &CredentialBlindRequest) -> Result<(EncryptedAttribute, RistrettoPoint), EligibilityError> {
+    match request.attributes_blinded {
+        Some(ref x) if x.len() == 1 => Ok((x[0], request.public_key.into())),
+        _                           => Err(EligibilityError::Credential(CredentialError::WrongNumberOfAttributes)),
+    }
+}
+
+/// A proof that the blinded attribute of a request is in an accumulator.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EligibilityProof {
+    /// The accumulator epoch the proof was made against.
+    pub epoch: u64,
+    pub Vbar: RistrettoPoint,
+    pub Wbar: RistrettoPoint,
+    pub proof: eligibility::Proof,
+}
+
+impl EligibilityProof {
+    pub fn from_bytes(bytes: &[u8]) -> Result<EligibilityProof, EligibilityError> {
+        if bytes.len() < 8 + 64 {
+            return Err(EligibilityError::WrongNumberOfBytes);
+        }
+
+        let epoch: u64 = read_u64(&bytes[..8]);
+        let Vbar: RistrettoPoint = read_point(&bytes[8..40])?;
+        let Wbar: RistrettoPoint = read_point(&bytes[40..72])?;
+        let proof: eligibility::Proof = match deserialize(&bytes[72..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(EligibilityError::WrongNumberOfBytes),
+        };
+
+        Ok(EligibilityProof { epoch, Vbar, Wbar, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(8 + 64);
+
+        v.extend(u64_to_bytes(self.epoch).iter());
+        v.extend(self.Vbar.compress().as_bytes());
+        v.extend(self.Wbar.compress().as_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EligibilityProof, "A valid byte sequence representing an EligibilityProof");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(EligibilityProof);
+impl_try_from_bytes!(EligibilityProof, EligibilityError);
+
+/// A blind credential request with a proof that its hidden attribute is
+/// eligible.
+#[derive(Debug, Eq, PartialEq)]
+pub struct EligibleRequest {
+    pub request: CredentialBlindRequest,
+    pub eligibility: EligibilityProof,
+}
+
+impl EligibleRequest {
+    /// Decode the proof's length, as four bytes, the proof, and then the
+    /// request.
+    pub fn from_bytes(bytes: &[u8]) -> Result<EligibleRequest, EligibilityError> {
+        if bytes.len() < 4 {
+            return Err(EligibilityError::WrongNumberOfBytes);
+        }
+
+        let length: usize = read_u64(&bytes[..4]) as usize;
+
+        if bytes.len() < 4 + length {
+            return Err(EligibilityError::WrongNumberOfBytes);
+        }
+
+        let eligibility = EligibilityProof::from_bytes(&bytes[4..4 + length])?;
+        let request = CredentialBlindRequest::from_bytes(&bytes[4 + length..])?;
+
+        Ok(EligibleRequest { request, eligibility })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let eligibility: Vec<u8> = self.eligibility.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(4 + eligibility.len());
+
+        v.extend(u64_to_bytes(eligibility.len() as u64)[..4].iter());
+        v.extend(eligibility);
+        v.extend(self.request.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EligibleRequest, "A valid byte sequence representing an EligibleRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(EligibleRequest);
+impl_try_from_bytes!(EligibleRequest, EligibilityError);
+
+impl EligibleRequest {
+    /// As `User::blind_obtain()`, but with a proof from the user's `witness`
+    /// that the hidden attribute is in the witness's accumulator.
+    ///
+    /// # Errors
+    ///
+    /// * `EligibilityError::WrongHandle` if the `witness` is for another
+    ///   attribute than `attributes[0]`.
+    /// * `EligibilityError::Credential` if the blind request could not be
+    ///   made, as for `User::blind_obtain()`.
+    pub fn create<R>(
+        user: &mut User,
+        attributes: &[RevealedAttribute],
+        witness: &NonRevocationWitness,
+        rng: &mut R,
+    ) -> Result<EligibleRequest, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if attributes.first() != Some(witness.handle.expose_secret()) {
+            return Err(EligibilityError::WrongHandle.into());
+        }
+
+        let (request, nonces): (CredentialBlindRequest, Nonces) = user.blind_obtain_with_ephemerals(attributes, rng)?;
+        let (E0, D) = blinded(&request)?;
+
+        let mut transcript = Transcript::new(b"AEONFLUX ELIGIBILITY");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let mut r: Scalar = Scalar::random(&mut csprng);
+        let Vbar: RistrettoPoint = &r * &witness.value.value;
+        let Wbar: RistrettoPoint = &r * &witness.W;
+        let Q: RistrettoPoint = &attributes[0] * &Wbar;
+
+        let proof = {
+            let secrets = eligibility::Secrets {
+                m0: &attributes[0],
+                e0: nonces[0].as_witness(),
+                r: &r,
+            };
+            let publics = eligibility::Publics {
+                B: &user.system_parameters.g,
+                D: &D,
+                E0_0: &E0.commitment,
+                E0_1: &E0.encryption,
+                V: &witness.value.value,
+                Vbar: &Vbar,
+                Wbar: &Wbar,
+                Q: &Q,
+            };
+
+            eligibility::Proof::create(&mut transcript, publics, secrets)
+        };
+
+        r.clear();
+
+        let eligibility = EligibilityProof { epoch: witness.value.epoch, Vbar, Wbar, proof };
+
+        Ok(EligibleRequest { request, eligibility })
+    }
+}
+
+/// An issuer-side check that the hidden attribute of a blind request is
+/// eligible for a credential.
+pub trait EligibilityCheck {
+    /// Check the `proof` attached to a `request` made under the
+    /// `system_parameters`.
+    fn check(
+        &self,
+        system_parameters: &SystemParameters,
+        request: &CredentialBlindRequest,
+        proof: &EligibilityProof,
+    ) -> Result<(), EligibilityError>;
+}
+
+/// An accumulator checks that the hidden attribute is one it has witnessed,
+/// and not since removed.
+impl EligibilityCheck for Accumulator {
+    fn check(
+        &self,
+        system_parameters: &SystemParameters,
+        request: &CredentialBlindRequest,
+        proof: &EligibilityProof,
+    ) -> Result<(), EligibilityError>
+    {
+        if proof.epoch != self.value().epoch {
+            return Err(EligibilityError::WrongEpoch);
+        }
+
+        // A zero r would make the proof trivial.
+        if proof.Vbar == RistrettoPoint::identity() || proof.Wbar == RistrettoPoint::identity() {
+            return Err(EligibilityError::InvalidProof);
+        }
+
+        let (E0, D) = blinded(request)?;
+        let Q: RistrettoPoint = self.unblind(&proof.Vbar, &proof.Wbar);
+
+        let mut transcript = Transcript::new(b"AEONFLUX ELIGIBILITY");
+        let publics = eligibility::Publics {
+            B: &system_parameters.g,
+            D: &D,
+            E0_0: &E0.commitment,
+            E0_1: &E0.encryption,
+            V: &self.value().value,
+            Vbar: &proof.Vbar,
+            Wbar: &proof.Wbar,
+            Q: &Q,
+        };
+
+        proof.proof.verify(&mut transcript, publics).or(Err(EligibilityError::InvalidProof))
+    }
+}
+
+/// Issue a credential for an eligible `request`, as `Issuer::blind_issue()`,
+/// once its proof passes the issuer's `check`.
+///
+/// # Errors
+///
+/// * Any error from the `check`, in which case nothing is issued.
+/// * `EligibilityError::Credential` if the blind issuance failed.
+pub fn issue_if_eligible<E, R>(
+    issuer: &Issuer,
+    request: &EligibleRequest,
+    check: &E,
+    rng: &mut R,
+) -> Result<CredentialBlindIssuance, Error>
+where
+    E: EligibilityCheck + ?Sized,
+    R: RngCore + CryptoRng,
+{
+    check.check(&issuer.system_parameters, &request.request, &request.eligibility)?;
+
+    Ok(issuer.blind_issue(&request.request, rng)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use hashing::Hasher;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use test_utils::H;
+
+    fn phone_number(number: &[u8]) -> Scalar {
+        let mut h = Hasher::new(b"aeonflux example phone number");
+
+        h.input(number);
+        h.finalize_scalar()
+    }
+
+    #[test]
+    fn eligible_requests_are_issued() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let allowed = Accumulator::create(&mut rng);
+        let attribute = phone_number(b"+1 555 0100");
+        let witness = allowed.witness(&attribute).unwrap();
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+
+        let request = EligibleRequest::create(&mut user, &[attribute], &witness, &mut rng).unwrap();
+        let request = EligibleRequest::from_bytes(&request.to_bytes()).unwrap();
+
+        let issuance = issue_if_eligible(&issuer, &request, &allowed, &mut rng).unwrap();
+
+        user.blind_obtain_finish(Some(&issuance), &[attribute]).unwrap();
+
+        assert!(issuer.verify(&user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn ineligible_requests_are_refused() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let mut allowed = Accumulator::create(&mut rng);
+        let other = Accumulator::create(&mut rng);
+        let alice = phone_number(b"+1 555 0100");
+        let mallory = phone_number(b"+1 555 0199");
+        let witness = allowed.witness(&alice).unwrap();
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+
+        // A witness only proves the attribute it was made for.
+        assert!(EligibleRequest::create(&mut user, &[mallory], &witness, &mut rng).unwrap_err() ==
+                EligibilityError::WrongHandle);
+
+        let request = EligibleRequest::create(&mut user, &[alice], &witness, &mut rng).unwrap();
+
+        // Another list's keeper refuses the proof, and so does this one's once
+        // the attribute is removed from the list.
+        assert!(issue_if_eligible(&issuer, &request, &other, &mut rng).unwrap_err() ==
+                EligibilityError::InvalidProof);
+
+        let mut forged = EligibleRequest::from_bytes(&request.to_bytes()).unwrap();
+
+        forged.eligibility.Wbar = forged.eligibility.Vbar;
+
+        assert!(allowed.check(&issuer.system_parameters, &forged.request, &forged.eligibility) ==
+                Err(EligibilityError::InvalidProof));
+
+        allowed.revoke(&[alice]);
+
+        assert!(issue_if_eligible(&issuer, &request, &allowed, &mut rng).unwrap_err() ==
+                EligibilityError::WrongEpoch);
+    }
+}
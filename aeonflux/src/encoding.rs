@@ -0,0 +1,124 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Constant-time encoders for secret values.
+//!
+//! Hidden attributes, blinding factors, and the values behind range proofs
+//! must never decide which branch is taken, which memory is touched, or how
+//! much data is hashed, since an attacker sharing a machine with a prover may
+//! observe any of these.  The helpers here encode and select between such
+//! values using only masking arithmetic.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use subtle::Choice;
+
+/// Identifiers are zero-padded to a multiple of this many bytes before being
+/// hashed, so that the time spent hashing one reveals at most which multiple
+/// of `SIZEOF_PADDING_BLOCK` its length falls below.
+pub const SIZEOF_PADDING_BLOCK: usize = 256;
+
+/// Encode `x` as eight little-endian bytes.
+pub fn u64_to_bytes(x: u64) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+
+    for i in 0..8 {
+        bytes[i] = (x >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+/// The `i`th bit of `x`, for `i` less than 64.
+pub fn bit(x: u64, i: usize) -> Choice {
+    Choice::from(((x >> i) & 1) as u8)
+}
+
+/// Return `a` if `choice` is zero, or `b` if it is one, without branching.
+pub fn conditional_select_scalar(a: &Scalar, b: &Scalar, choice: Choice) -> Scalar {
+    let mask: u8 = 0u8.wrapping_sub(choice.unwrap_u8());
+    let a_bytes: &[u8; 32] = a.as_bytes();
+    let b_bytes: &[u8; 32] = b.as_bytes();
+    let mut selected: [u8; 32] = [0u8; 32];
+
+    for i in 0..32 {
+        selected[i] = a_bytes[i] ^ (mask & (a_bytes[i] ^ b_bytes[i]));
+    }
+    // Both inputs were canonical, so the selection is as well.
+    Scalar::from_bits(selected)
+}
+
+/// Zero if `choice` is zero, or one if it is one.
+pub fn choice_to_scalar(choice: Choice) -> Scalar {
+    Scalar::from(choice.unwrap_u8() as u64)
+}
+
+/// Append `input` to `v`, preceded by its length as eight little-endian
+/// bytes and followed by enough zeroes to fill a whole number of
+/// `SIZEOF_PADDING_BLOCK`s.
+///
+/// The length prefix keeps the encoding injective, and the padding keeps the
+/// amount of data later hashed independent of the exact length of `input`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn push_padded(v: &mut Vec<u8>, input: &[u8]) {
+    let blocks: usize = (input.len() + SIZEOF_PADDING_BLOCK - 1) / SIZEOF_PADDING_BLOCK;
+    let padding: usize = blocks * SIZEOF_PADDING_BLOCK - input.len();
+
+    v.extend(u64_to_bytes(input.len() as u64).iter());
+    v.extend(input.iter());
+    v.extend((0..padding).map(|_| 0u8));
+}
+
+/// The length of `input` once encoded by `push_padded`.
+pub fn sizeof_padded(input: &[u8]) -> usize {
+    8 + ((input.len() + SIZEOF_PADDING_BLOCK - 1) / SIZEOF_PADDING_BLOCK) * SIZEOF_PADDING_BLOCK
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn select_and_bits() {
+        let mut rng = thread_rng();
+        let a: Scalar = Scalar::random(&mut rng);
+        let b: Scalar = Scalar::random(&mut rng);
+
+        assert!(conditional_select_scalar(&a, &b, Choice::from(0)) == a);
+        assert!(conditional_select_scalar(&a, &b, Choice::from(1)) == b);
+        assert!(choice_to_scalar(bit(0b10, 0)) == Scalar::zero());
+        assert!(choice_to_scalar(bit(0b10, 1)) == Scalar::one());
+        assert!(u64_to_bytes(0x0102) == [2, 1, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn padding_hides_length_within_a_block() {
+        let mut short: Vec<u8> = Vec::new();
+        let mut long: Vec<u8> = Vec::new();
+
+        push_padded(&mut short, b"a@b.c");
+        push_padded(&mut long, b"alice.with.a.long.name@example.com");
+
+        assert!(short.len() == long.len());
+        assert!(short.len() == sizeof_padded(b"a@b.c"));
+        assert!(short != long);
+
+        // Zero-padding must not collide with an input ending in zeroes.
+        let mut zeroes: Vec<u8> = Vec::new();
+
+        push_padded(&mut zeroes, b"a@b.c\x00");
+        assert!(zeroes != short);
+    }
+}
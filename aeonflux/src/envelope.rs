@@ -0,0 +1,551 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A versioned envelope for public wire types.
+//!
+//! Every public wire type, as well as the issuer keys, user state, and
+//! credentials which are kept at rest, may be sealed in an envelope, a three
+//! byte header followed by its encoding:
+//!
+//! ```text
+//! +---------+-------+------+---------------------+
+//! | version | suite | kind | payload             |
+//! +---------+-------+------+---------------------+
+//!   1 byte   1 byte  1 byte  the rest
+//! ```
+//!
+//! The `Suite` names the curve, hash, and MAC scheme which the payload was
+//! made with, and the `WireKind` names which type it is, so that a verifier
+//! may be handed an envelope without knowing either beforehand, and so that
+//! a new suite, e.g. one using MAC_DH or another hash, may be added later
+//! without any deployed verifier mistaking its values for those of a suite it
+//! already knows.  Opening an envelope routes its payload to the decoder for
+//! its suite, and fails with `EnvelopeError::UnknownSuite` for any suite this
+//! version of the crate does not implement.
+//!
+//! An envelope adds no confidentiality: one holding secret key material must
+//! be stored as carefully as the key itself.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use merlin::Transcript;
+
+use zeroize::Zeroize;
+
+use credential::Credential;
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use elgamal;
+use errors::EnvelopeError;
+use errors::Error;
+use gate::GateMessage;
+use hashed_identifier::HashedIdentifierRequest;
+use hashed_identifier::HashedIdentifierReveal;
+use issuer::Issuer;
+use issuer::IssuerParameters;
+use nullifier::ScopedPresentation;
+use nullifier_sync::SyncMessage;
+use parameters::SystemParameters;
+use parameter_chain::SignedParameterBundle;
+use pass::PassIssuance;
+use pass::PassPresentation;
+use predicates::RangeProof;
+use presentation_request::PresentationRequest;
+use presentation_request::PresentationResponse;
+use public_tokens::PublicToken;
+use public_tokens::TokenChallenge;
+use public_tokens::TokenCommitment;
+use public_tokens::TokenSignatureResponse;
+use rate_limit::Nullifier;
+use rate_limit::RateLimitPresentation;
+use revocation::SignedRevocationDelta;
+use schema::SchemaPresentation;
+use sybil::LinkedCredentialRequest;
+use sybil::SybilTag;
+use ticket::CompactTicket;
+use ticket::TicketRequest;
+use tokens::TokenRedemption;
+use tokens::TokenRequest;
+use tokens::TokenResponse;
+use transcript::HashTranscript;
+use transcript::SuiteTranscript;
+use trust::SignedTrustRegistry;
+use user::User;
+
+/// The version of the envelope format itself.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// The size of an envelope's header.
+pub const SIZEOF_ENVELOPE_HEADER: usize = 3;
+
+/// The algorithms a wire type's encoding was made with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum Suite {
+    /// ristretto255, SHA-512 and merlin transcripts, and MAC_GGM, as
+    /// implemented throughout this crate.
+    Ristretto255Sha512MacGgm = 1,
+    /// As `Ristretto255Sha512MacGgm`, but with proof transcripts over
+    /// SHA-512 rather than merlin, for deployments which may only use FIPS
+    /// approved hash functions.
+    Ristretto255Sha512HashTranscriptMacGgm = 2,
+    /// As `Ristretto255Sha512MacGgm`, but with proof transcripts over BLAKE3.
+    #[cfg(feature = "blake3-transcripts")]
+    Ristretto255Blake3MacGgm = 3,
+}
+
+/// The suite which new envelopes are sealed with.
+pub const DEFAULT_SUITE: Suite = Suite::Ristretto255Sha512MacGgm;
+
+impl Suite {
+    pub fn from_byte(byte: u8) -> Result<Suite, EnvelopeError> {
+        match byte {
+            1 => Ok(Suite::Ristretto255Sha512MacGgm),
+            2 => Ok(Suite::Ristretto255Sha512HashTranscriptMacGgm),
+            #[cfg(feature = "blake3-transcripts")]
+            3 => Ok(Suite::Ristretto255Blake3MacGgm),
+            _ => Err(EnvelopeError::UnknownSuite),
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Begin a proof transcript, within the domain named by `label`, over the
+    /// hash function this suite names.
+    pub fn transcript(&self, label: &'static [u8]) -> SuiteTranscript {
+        match *self {
+            Suite::Ristretto255Sha512MacGgm
+                => SuiteTranscript::Merlin(Transcript::new(label)),
+            Suite::Ristretto255Sha512HashTranscriptMacGgm
+                => SuiteTranscript::Sha512(HashTranscript::new(label)),
+            #[cfg(feature = "blake3-transcripts")]
+            Suite::Ristretto255Blake3MacGgm
+                => SuiteTranscript::Blake3(HashTranscript::new(label)),
+        }
+    }
+}
+
+/// Which wire type an envelope holds.
+///
+/// These values are part of the wire format, and must never be reused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum WireKind {
+    SystemParameters = 1,
+    IssuerParameters = 2,
+    ElgamalPublicKey = 3,
+    Encryption = 4,
+    CredentialRequest = 5,
+    CredentialIssuance = 6,
+    CredentialBlindRequest = 7,
+    CredentialBlindIssuance = 8,
+    CredentialPresentation = 9,
+    HashedIdentifierRequest = 10,
+    HashedIdentifierReveal = 11,
+    SybilTag = 12,
+    LinkedCredentialRequest = 13,
+    GateMessage = 14,
+    TokenRequest = 15,
+    TokenResponse = 16,
+    TokenRedemption = 17,
+    Nullifier = 18,
+    RateLimitPresentation = 19,
+    TicketRequest = 20,
+    CompactTicket = 21,
+    SignedTrustRegistry = 22,
+    RangeProof = 23,
+    PassIssuance = 24,
+    PassPresentation = 25,
+    SyncMessage = 26,
+    SchemaPresentation = 27,
+    TokenCommitment = 28,
+    TokenChallenge = 29,
+    TokenSignatureResponse = 30,
+    PublicToken = 31,
+    SignedRevocationDelta = 32,
+    SignedParameterBundle = 33,
+    PresentationRequest = 34,
+    PresentationResponse = 35,
+    ScopedPresentation = 36,
+    Issuer = 37,
+    User = 38,
+    Credential = 39,
+    ElgamalKeypair = 40,
+}
+
+impl WireKind {
+    pub fn from_byte(byte: u8) -> Result<WireKind, EnvelopeError> {
+        match byte {
+             1 => Ok(WireKind::SystemParameters),
+             2 => Ok(WireKind::IssuerParameters),
+             3 => Ok(WireKind::ElgamalPublicKey),
+             4 => Ok(WireKind::Encryption),
+             5 => Ok(WireKind::CredentialRequest),
+             6 => Ok(WireKind::CredentialIssuance),
+             7 => Ok(WireKind::CredentialBlindRequest),
+             8 => Ok(WireKind::CredentialBlindIssuance),
+             9 => Ok(WireKind::CredentialPresentation),
+            10 => Ok(WireKind::HashedIdentifierRequest),
+            11 => Ok(WireKind::HashedIdentifierReveal),
+            12 => Ok(WireKind::SybilTag),
+            13 => Ok(WireKind::LinkedCredentialRequest),
+            14 => Ok(WireKind::GateMessage),
+            15 => Ok(WireKind::TokenRequest),
+            16 => Ok(WireKind::TokenResponse),
+            17 => Ok(WireKind::TokenRedemption),
+            18 => Ok(WireKind::Nullifier),
+            19 => Ok(WireKind::RateLimitPresentation),
+            20 => Ok(WireKind::TicketRequest),
+            21 => Ok(WireKind::CompactTicket),
+            22 => Ok(WireKind::SignedTrustRegistry),
+            23 => Ok(WireKind::RangeProof),
+            24 => Ok(WireKind::PassIssuance),
+            25 => Ok(WireKind::PassPresentation),
+            26 => Ok(WireKind::SyncMessage),
+            27 => Ok(WireKind::SchemaPresentation),
+            28 => Ok(WireKind::TokenCommitment),
+            29 => Ok(WireKind::TokenChallenge),
+            30 => Ok(WireKind::TokenSignatureResponse),
+            31 => Ok(WireKind::PublicToken),
+            32 => Ok(WireKind::SignedRevocationDelta),
+            33 => Ok(WireKind::SignedParameterBundle),
+            34 => Ok(WireKind::PresentationRequest),
+            35 => Ok(WireKind::PresentationResponse),
+            36 => Ok(WireKind::ScopedPresentation),
+            37 => Ok(WireKind::Issuer),
+            38 => Ok(WireKind::User),
+            39 => Ok(WireKind::Credential),
+            40 => Ok(WireKind::ElgamalKeypair),
+            _  => Err(EnvelopeError::WrongKind),
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A wire type which may be sealed in an envelope.
+pub trait Enveloped: Sized {
+    /// The kind recorded in this type's envelopes.
+    const KIND: WireKind;
+
+    /// Encode this value under a `suite`.
+    fn to_payload(&self, suite: Suite) -> Vec<u8>;
+
+    /// Decode a value from a `payload` encoded under a `suite`.
+    fn from_payload(suite: Suite, payload: &[u8]) -> Result<Self, EnvelopeError>;
+}
+
+/// The header of an envelope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct EnvelopeHeader {
+    pub version: u8,
+    pub suite: Suite,
+    /// The raw kind byte, which may be one this version of the crate does not
+    /// know.
+    pub kind: u8,
+}
+
+impl EnvelopeHeader {
+    /// Read the header of an envelope, without decoding its payload.
+    ///
+    /// # Errors
+    ///
+    /// * `EnvelopeError::WrongNumberOfBytes` if there is no header.
+    /// * `EnvelopeError::UnknownVersion` or `EnvelopeError::UnknownSuite` if
+    ///   the envelope was made by a later version of this crate.
+    pub fn from_bytes(bytes: &[u8]) -> Result<EnvelopeHeader, EnvelopeError> {
+        if bytes.len() < SIZEOF_ENVELOPE_HEADER {
+            return Err(EnvelopeError::WrongNumberOfBytes);
+        }
+        if bytes[0] != ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnknownVersion);
+        }
+
+        Ok(EnvelopeHeader { version: bytes[0], suite: Suite::from_byte(bytes[1])?, kind: bytes[2] })
+    }
+}
+
+impl_try_from_bytes!(EnvelopeHeader, EnvelopeError);
+
+/// Seal a `value` in an envelope under the `DEFAULT_SUITE`.
+pub fn seal<T: Enveloped>(value: &T) -> Vec<u8> {
+    seal_with_suite(value, DEFAULT_SUITE)
+}
+
+/// Seal a `value` in an envelope under some `suite`.
+pub fn seal_with_suite<T: Enveloped>(value: &T, suite: Suite) -> Vec<u8> {
+    let mut payload: Vec<u8> = value.to_payload(suite);
+    let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENVELOPE_HEADER + payload.len());
+
+    v.push(ENVELOPE_VERSION);
+    v.push(suite.to_byte());
+    v.push(T::KIND.to_byte());
+    v.extend(payload.iter());
+
+    // The payload may be secret key material, of which the envelope should
+    // be the only copy.
+    payload.zeroize();
+    v
+}
+
+/// Open an envelope holding a `T`.
+///
+/// # Returns
+///
+/// The value, along with the suite it was made with.
+///
+/// # Errors
+///
+/// * Any error from `EnvelopeHeader::from_bytes`.
+/// * `EnvelopeError::WrongKind` if the envelope holds some other type.
+/// * `EnvelopeError::InvalidPayload` if the payload could not be decoded.
+pub fn open<T: Enveloped>(bytes: &[u8]) -> Result<(T, Suite), Error> {
+    let header: EnvelopeHeader = EnvelopeHeader::from_bytes(bytes)?;
+
+    if header.kind != T::KIND.to_byte() {
+        return Err(EnvelopeError::WrongKind.into());
+    }
+
+    Ok((T::from_payload(header.suite, &bytes[SIZEOF_ENVELOPE_HEADER..])?, header.suite))
+}
+
+/// Decode a `payload` of some `kind` under a `suite`, discarding the value.
+///
+/// This is a single entry point into the decoder of every wire type, for
+/// fuzzers and for services which only need to check that a payload is
+/// well-formed before passing it on.
+///
+/// # Errors
+///
+/// * `EnvelopeError::InvalidPayload` if the payload could not be decoded.
+pub fn decode_any(kind: WireKind, suite: Suite, payload: &[u8]) -> Result<(), Error> {
+    fn decode<T: Enveloped>(suite: Suite, payload: &[u8]) -> Result<(), Error> {
+        T::from_payload(suite, payload)?;
+
+        Ok(())
+    }
+
+    match kind {
+        WireKind::SystemParameters        => decode::<SystemParameters>(suite, payload),
+        WireKind::IssuerParameters        => decode::<IssuerParameters>(suite, payload),
+        WireKind::ElgamalPublicKey        => decode::<elgamal::PublicKey>(suite, payload),
+        WireKind::Encryption              => decode::<elgamal::Encryption>(suite, payload),
+        WireKind::CredentialRequest       => decode::<CredentialRequest>(suite, payload),
+        WireKind::CredentialIssuance      => decode::<CredentialIssuance>(suite, payload),
+        WireKind::CredentialBlindRequest  => decode::<CredentialBlindRequest>(suite, payload),
+        WireKind::CredentialBlindIssuance => decode::<CredentialBlindIssuance>(suite, payload),
+        WireKind::CredentialPresentation  => decode::<CredentialPresentation>(suite, payload),
+        WireKind::HashedIdentifierRequest => decode::<HashedIdentifierRequest>(suite, payload),
+        WireKind::HashedIdentifierReveal  => decode::<HashedIdentifierReveal>(suite, payload),
+        WireKind::SybilTag                => decode::<SybilTag>(suite, payload),
+        WireKind::LinkedCredentialRequest => decode::<LinkedCredentialRequest>(suite, payload),
+        WireKind::GateMessage             => decode::<GateMessage>(suite, payload),
+        WireKind::TokenRequest            => decode::<TokenRequest>(suite, payload),
+        WireKind::TokenResponse           => decode::<TokenResponse>(suite, payload),
+        WireKind::TokenRedemption         => decode::<TokenRedemption>(suite, payload),
+        WireKind::Nullifier               => decode::<Nullifier>(suite, payload),
+        WireKind::RateLimitPresentation   => decode::<RateLimitPresentation>(suite, payload),
+        WireKind::TicketRequest           => decode::<TicketRequest>(suite, payload),
+        WireKind::CompactTicket           => decode::<CompactTicket>(suite, payload),
+        WireKind::SignedTrustRegistry     => decode::<SignedTrustRegistry>(suite, payload),
+        WireKind::RangeProof              => decode::<RangeProof>(suite, payload),
+        WireKind::PassIssuance            => decode::<PassIssuance>(suite, payload),
+        WireKind::PassPresentation        => decode::<PassPresentation>(suite, payload),
+        WireKind::SyncMessage             => decode::<SyncMessage>(suite, payload),
+        WireKind::SchemaPresentation      => decode::<SchemaPresentation>(suite, payload),
+        WireKind::TokenCommitment         => decode::<TokenCommitment>(suite, payload),
+        WireKind::TokenChallenge          => decode::<TokenChallenge>(suite, payload),
+        WireKind::TokenSignatureResponse  => decode::<TokenSignatureResponse>(suite, payload),
+        WireKind::PublicToken             => decode::<PublicToken>(suite, payload),
+        WireKind::SignedRevocationDelta   => decode::<SignedRevocationDelta>(suite, payload),
+        WireKind::SignedParameterBundle   => decode::<SignedParameterBundle>(suite, payload),
+        WireKind::PresentationRequest     => decode::<PresentationRequest>(suite, payload),
+        WireKind::PresentationResponse    => decode::<PresentationResponse>(suite, payload),
+        WireKind::ScopedPresentation      => decode::<ScopedPresentation>(suite, payload),
+        WireKind::Issuer                  => decode::<Issuer>(suite, payload),
+        WireKind::User                    => decode::<User>(suite, payload),
+        WireKind::Credential              => decode::<Credential>(suite, payload),
+        WireKind::ElgamalKeypair          => decode::<elgamal::Keypair>(suite, payload),
+    }
+}
+
+/// Open an envelope holding any wire type, discarding the value.
+///
+/// # Returns
+///
+/// The kind of the value, along with the suite it was made with.
+///
+/// # Errors
+///
+/// * Any error from `EnvelopeHeader::from_bytes`.
+/// * `EnvelopeError::WrongKind` if the kind is one this version of the crate
+///   does not know.
+/// * `EnvelopeError::InvalidPayload` if the payload could not be decoded.
+pub fn open_any(bytes: &[u8]) -> Result<(WireKind, Suite), Error> {
+    let header: EnvelopeHeader = EnvelopeHeader::from_bytes(bytes)?;
+    let kind: WireKind = WireKind::from_byte(header.kind)?;
+
+    decode_any(kind, header.suite, &bytes[SIZEOF_ENVELOPE_HEADER..])?;
+
+    Ok((kind, header.suite))
+}
+
+macro_rules! impl_enveloped_with_to_bytes_and_from_bytes {
+    ($t:ty, $kind:expr) => {
+        impl Enveloped for $t {
+            const KIND: WireKind = $kind;
+
+            // The suites differ only in how proof challenges are derived,
+            // and so share every encoding.
+            fn to_payload(&self, _suite: Suite) -> Vec<u8> {
+                self.to_bytes()[..].to_vec()
+            }
+
+            fn from_payload(_suite: Suite, payload: &[u8]) -> Result<$t, EnvelopeError> {
+                match <$t>::from_bytes(payload) {
+                    Ok(x)  => Ok(x),
+                    Err(_) => Err(EnvelopeError::InvalidPayload),
+                }
+            }
+        }
+    }
+}
+
+/// As `impl_enveloped_with_to_bytes_and_from_bytes`, for types holding
+/// secrets, whose encodings are not copied on their way into an envelope.
+macro_rules! impl_enveloped_secret_with_to_bytes_and_from_bytes {
+    ($t:ty, $kind:expr) => {
+        impl Enveloped for $t {
+            const KIND: WireKind = $kind;
+
+            fn to_payload(&self, _suite: Suite) -> Vec<u8> {
+                self.to_bytes()
+            }
+
+            fn from_payload(_suite: Suite, payload: &[u8]) -> Result<$t, EnvelopeError> {
+                match <$t>::from_bytes(payload) {
+                    Ok(x)  => Ok(x),
+                    Err(_) => Err(EnvelopeError::InvalidPayload),
+                }
+            }
+        }
+    }
+}
+
+impl_enveloped_with_to_bytes_and_from_bytes!(SystemParameters, WireKind::SystemParameters);
+impl_enveloped_with_to_bytes_and_from_bytes!(IssuerParameters, WireKind::IssuerParameters);
+impl_enveloped_with_to_bytes_and_from_bytes!(elgamal::PublicKey, WireKind::ElgamalPublicKey);
+impl_enveloped_with_to_bytes_and_from_bytes!(elgamal::Encryption, WireKind::Encryption);
+impl_enveloped_with_to_bytes_and_from_bytes!(CredentialRequest, WireKind::CredentialRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(CredentialIssuance, WireKind::CredentialIssuance);
+impl_enveloped_with_to_bytes_and_from_bytes!(CredentialBlindRequest, WireKind::CredentialBlindRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(CredentialBlindIssuance, WireKind::CredentialBlindIssuance);
+impl_enveloped_with_to_bytes_and_from_bytes!(CredentialPresentation, WireKind::CredentialPresentation);
+impl_enveloped_with_to_bytes_and_from_bytes!(HashedIdentifierRequest, WireKind::HashedIdentifierRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(HashedIdentifierReveal, WireKind::HashedIdentifierReveal);
+impl_enveloped_with_to_bytes_and_from_bytes!(SybilTag, WireKind::SybilTag);
+impl_enveloped_with_to_bytes_and_from_bytes!(LinkedCredentialRequest, WireKind::LinkedCredentialRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(GateMessage, WireKind::GateMessage);
+impl_enveloped_with_to_bytes_and_from_bytes!(TokenRequest, WireKind::TokenRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(TokenResponse, WireKind::TokenResponse);
+impl_enveloped_with_to_bytes_and_from_bytes!(TokenRedemption, WireKind::TokenRedemption);
+impl_enveloped_with_to_bytes_and_from_bytes!(Nullifier, WireKind::Nullifier);
+impl_enveloped_with_to_bytes_and_from_bytes!(RateLimitPresentation, WireKind::RateLimitPresentation);
+impl_enveloped_with_to_bytes_and_from_bytes!(TicketRequest, WireKind::TicketRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(CompactTicket, WireKind::CompactTicket);
+impl_enveloped_with_to_bytes_and_from_bytes!(SignedTrustRegistry, WireKind::SignedTrustRegistry);
+impl_enveloped_with_to_bytes_and_from_bytes!(RangeProof, WireKind::RangeProof);
+impl_enveloped_with_to_bytes_and_from_bytes!(PassIssuance, WireKind::PassIssuance);
+impl_enveloped_with_to_bytes_and_from_bytes!(PassPresentation, WireKind::PassPresentation);
+impl_enveloped_with_to_bytes_and_from_bytes!(SyncMessage, WireKind::SyncMessage);
+impl_enveloped_with_to_bytes_and_from_bytes!(SchemaPresentation, WireKind::SchemaPresentation);
+impl_enveloped_with_to_bytes_and_from_bytes!(TokenCommitment, WireKind::TokenCommitment);
+impl_enveloped_with_to_bytes_and_from_bytes!(TokenChallenge, WireKind::TokenChallenge);
+impl_enveloped_with_to_bytes_and_from_bytes!(TokenSignatureResponse, WireKind::TokenSignatureResponse);
+impl_enveloped_with_to_bytes_and_from_bytes!(PublicToken, WireKind::PublicToken);
+impl_enveloped_with_to_bytes_and_from_bytes!(SignedRevocationDelta, WireKind::SignedRevocationDelta);
+impl_enveloped_with_to_bytes_and_from_bytes!(SignedParameterBundle, WireKind::SignedParameterBundle);
+impl_enveloped_with_to_bytes_and_from_bytes!(PresentationRequest, WireKind::PresentationRequest);
+impl_enveloped_with_to_bytes_and_from_bytes!(PresentationResponse, WireKind::PresentationResponse);
+impl_enveloped_with_to_bytes_and_from_bytes!(ScopedPresentation, WireKind::ScopedPresentation);
+impl_enveloped_secret_with_to_bytes_and_from_bytes!(Issuer, WireKind::Issuer);
+impl_enveloped_secret_with_to_bytes_and_from_bytes!(User, WireKind::User);
+impl_enveloped_secret_with_to_bytes_and_from_bytes!(Credential, WireKind::Credential);
+impl_enveloped_secret_with_to_bytes_and_from_bytes!(elgamal::Keypair, WireKind::ElgamalKeypair);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use test_utils::H;
+
+    #[test]
+    fn envelopes_route_by_suite_and_kind() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let sealed: Vec<u8> = seal(&system_parameters);
+
+        assert!(sealed[..SIZEOF_ENVELOPE_HEADER] == [ENVELOPE_VERSION, 1, WireKind::SystemParameters.to_byte()]);
+        assert!(&sealed[SIZEOF_ENVELOPE_HEADER..] == &system_parameters.to_bytes()[..]);
+
+        let (opened, suite): (SystemParameters, Suite) = open(&sealed).unwrap();
+
+        assert!(opened == system_parameters);
+        assert!(suite == DEFAULT_SUITE);
+        assert!(open::<elgamal::PublicKey>(&sealed).err() == Some(EnvelopeError::WrongKind.into()));
+
+        let mut future: Vec<u8> = sealed.clone();
+
+        future[1] = 0xff;
+        assert!(open::<SystemParameters>(&future).err() == Some(EnvelopeError::UnknownSuite.into()));
+        future[1] = Suite::Ristretto255Sha512HashTranscriptMacGgm.to_byte();
+        assert!(open::<SystemParameters>(&future).unwrap().1 == Suite::Ristretto255Sha512HashTranscriptMacGgm);
+        future[0] = 0xff;
+        assert!(EnvelopeHeader::from_bytes(&future).err() == Some(EnvelopeError::UnknownVersion));
+
+        let keypair = elgamal::Keypair::generate(&mut rng);
+        let mut truncated: Vec<u8> = seal(&keypair.public);
+
+        truncated.pop();
+        assert!(open::<elgamal::PublicKey>(&truncated).err() == Some(EnvelopeError::InvalidPayload.into()));
+    }
+
+    #[test]
+    fn open_any_routes_every_kind() {
+        let system_parameters = SystemParameters::from(H);
+        let sealed: Vec<u8> = seal(&system_parameters);
+
+        assert!(open_any(&sealed) == Ok((WireKind::SystemParameters, DEFAULT_SUITE)));
+
+        for byte in 1..41 {
+            assert!(WireKind::from_byte(byte).unwrap().to_byte() == byte);
+        }
+        assert!(WireKind::from_byte(0).err() == Some(EnvelopeError::WrongKind));
+        assert!(WireKind::from_byte(41).err() == Some(EnvelopeError::WrongKind));
+
+        let mut relabelled: Vec<u8> = sealed.clone();
+
+        relabelled[2] = WireKind::CredentialPresentation.to_byte();
+        assert!(open_any(&relabelled).err() == Some(EnvelopeError::InvalidPayload.into()));
+        relabelled[2] = 0xff;
+        assert!(open_any(&relabelled).err() == Some(EnvelopeError::WrongKind.into()));
+    }
+}
@@ -59,10 +59,53 @@ impl From<NoneError> for MacError {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CborError {
+    /// A data item used a longer head than necessary, an indefinite length,
+    /// or a reserved encoding.
+    NonDeterministicEncoding,
+    /// Bytes were left over after the last data item.
+    TrailingBytes,
+    /// The data ended in the middle of a data item.
+    Truncated,
+    /// A data item had a different type than expected.
+    UnexpectedType,
+    /// The value inside a data item could not be decoded.
+    InvalidValue,
+    /// A COSE structure named an algorithm other than ours.
+    UnknownAlgorithm,
+    /// A COSE signature was not made by the given key.
+    VerificationFailure,
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CborError::NonDeterministicEncoding
+                => write!(f, "The CBOR data was not deterministically encoded"),
+            CborError::TrailingBytes
+                => write!(f, "The CBOR data had trailing bytes"),
+            CborError::Truncated
+                => write!(f, "The CBOR data was truncated"),
+            CborError::UnexpectedType
+                => write!(f, "A CBOR data item had an unexpected type"),
+            CborError::InvalidValue
+                => write!(f, "A CBOR data item held an invalid value"),
+            CborError::UnknownAlgorithm
+                => write!(f, "The COSE signature algorithm was not recognised"),
+            CborError::VerificationFailure
+                => write!(f, "The COSE signature could not be verified"),
+        }
+    }
+}
+
+impl ::failure::Fail for CborError { }
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum CredentialError {
     BadAttribute,
     CredentialIssuance,
+    Expired,
     MacCreation,
     MacVerification,
     MissingData,
@@ -83,6 +126,8 @@ impl fmt::Display for CredentialError {
                 => write!(f, "An attribute was unacceptable"),
             CredentialError::CredentialIssuance
                 => write!(f, "Failed to get a credential issued"),
+            CredentialError::Expired
+                => write!(f, "The issuer parameters have expired or are not yet valid"),
             CredentialError::MacCreation
                 => write!(f, "Could not create a MAC"),
             CredentialError::MacVerification
@@ -133,3 +178,2588 @@ impl From<MacError> for CredentialError {
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum EnvelopeError {
+    /// An envelope's payload could not be decoded as its kind.
+    InvalidPayload,
+    /// An envelope was made with a suite this version does not implement.
+    UnknownSuite,
+    /// An envelope was in a format this version does not implement.
+    UnknownVersion,
+    /// An envelope held a different type than expected.
+    WrongKind,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EnvelopeError::InvalidPayload
+                => write!(f, "The envelope's payload could not be decoded"),
+            EnvelopeError::UnknownSuite
+                => write!(f, "The envelope's algorithm suite is not supported"),
+            EnvelopeError::UnknownVersion
+                => write!(f, "The envelope's format version is not supported"),
+            EnvelopeError::WrongKind
+                => write!(f, "The envelope holds a different type"),
+            EnvelopeError::WrongNumberOfBytes
+                => write!(f, "The envelope was too short"),
+        }
+    }
+}
+
+impl ::failure::Fail for EnvelopeError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MigrationError {
+    /// The bytes were neither an envelope nor a legacy encoding of the type.
+    Malformed,
+    /// The bytes were an envelope holding a different type.
+    WrongKind,
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MigrationError::Malformed
+                => write!(f, "The bytes are not an encoding of the expected type"),
+            MigrationError::WrongKind
+                => write!(f, "The envelope holds a different type"),
+        }
+    }
+}
+
+impl ::failure::Fail for MigrationError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GateError {
+    /// An error in the underlying credential protocol.
+    Credential(Error),
+    /// The credential was presented but the verifier rejected it.
+    Rejected,
+    /// A report token could not be requested or unblinded.
+    Report(Error),
+    /// The peer did not respond within the configured timeout.
+    Timeout,
+    /// A message was received which is not allowed in the current state.
+    UnexpectedMessage,
+    /// A message could not be deserialised because its type was unknown.
+    UnknownMessageType,
+    /// An action was attempted which is not allowed in the current state.
+    WrongState,
+}
+
+impl fmt::Display for GateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GateError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            GateError::Rejected
+                => write!(f, "The credential presentation was rejected"),
+            GateError::Report(ref e)
+                => write!(f, "Report token error: {}", e),
+            GateError::Timeout
+                => write!(f, "The gate session timed out"),
+            GateError::UnexpectedMessage
+                => write!(f, "A message was received which is not allowed in the current state"),
+            GateError::UnknownMessageType
+                => write!(f, "The message type was unknown"),
+            GateError::WrongState
+                => write!(f, "The action is not allowed in the current state"),
+        }
+    }
+}
+
+impl ::failure::Fail for GateError { }
+
+impl From<CredentialError> for GateError {
+    fn from(source: CredentialError) -> GateError {
+        GateError::Credential(source.into())
+    }
+}
+
+impl From<ReportError> for GateError {
+    fn from(source: ReportError) -> GateError {
+        GateError::Report(source.into())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum TokenError {
+    /// The token was already redeemed.
+    DoubleSpend,
+    /// The token's redemption MAC did not verify.
+    InvalidMac,
+    PointDecompressionError,
+    /// The issuer's batch proof of correct signing did not verify.
+    ProofFailure,
+    ScalarFormatError,
+    /// More tokens were requested than `MAXIMUM_TOKENS_PER_REQUEST`, or a
+    /// response contained a different number of tokens than were requested.
+    WrongNumberOfTokens,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TokenError::DoubleSpend
+                => write!(f, "The token was already redeemed"),
+            TokenError::InvalidMac
+                => write!(f, "The token redemption could not be authenticated"),
+            TokenError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            TokenError::ProofFailure
+                => write!(f, "The proof of correct token signing could not be verified"),
+            TokenError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            TokenError::WrongNumberOfTokens
+                => write!(f, "The wrong number of tokens was requested or returned"),
+            TokenError::WrongNumberOfBytes
+                => write!(f, "The token data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for TokenError { }
+
+impl From<NoneError> for TokenError {
+    fn from(_source: NoneError) -> TokenError {
+        TokenError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum VoprfError {
+    /// `DeriveKeyPair` failed to find a non-zero scalar.
+    DeriveKeyPairError,
+    /// The client's input mapped to the identity element.
+    InvalidInput,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// The server's proof of correct evaluation did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+    /// The numbers of inputs, blinds, and elements did not match.
+    WrongNumberOfElements,
+}
+
+impl fmt::Display for VoprfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VoprfError::DeriveKeyPairError
+                => write!(f, "Could not derive a key pair from the seed"),
+            VoprfError::InvalidInput
+                => write!(f, "The input mapped to the identity element"),
+            VoprfError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point, or it was the identity"),
+            VoprfError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            VoprfError::VerificationFailure
+                => write!(f, "The proof of correct evaluation could not be verified"),
+            VoprfError::WrongNumberOfBytes
+                => write!(f, "The data was not the correct length"),
+            VoprfError::WrongNumberOfElements
+                => write!(f, "The numbers of inputs, blinds, and elements did not match"),
+        }
+    }
+}
+
+impl ::failure::Fail for VoprfError { }
+
+impl From<NoneError> for VoprfError {
+    fn from(_source: NoneError) -> VoprfError {
+        VoprfError::PointDecompressionError
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RateLimitError {
+    /// An error in the underlying credential protocol.
+    Credential(CredentialError),
+    /// The presentation was made for an epoch other than the current one.
+    WrongEpoch,
+    /// The presentation's action counter was not below the per-epoch limit.
+    LimitExceeded,
+    /// The nullifier was already seen in this epoch.
+    NullifierReused,
+    /// The proof that the nullifier was computed from the presented
+    /// credential did not verify.
+    VerificationFailure,
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RateLimitError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            RateLimitError::WrongEpoch
+                => write!(f, "The presentation was not made for the current epoch"),
+            RateLimitError::LimitExceeded
+                => write!(f, "The number of actions per epoch was exceeded"),
+            RateLimitError::NullifierReused
+                => write!(f, "The nullifier was already used in this epoch"),
+            RateLimitError::VerificationFailure
+                => write!(f, "The nullifier proof could not be verified"),
+        }
+    }
+}
+
+impl ::failure::Fail for RateLimitError { }
+
+impl From<CredentialError> for RateLimitError {
+    fn from(source: CredentialError) -> RateLimitError {
+        RateLimitError::Credential(source)
+    }
+}
+
+impl From<NoneError> for RateLimitError {
+    fn from(_source: NoneError) -> RateLimitError {
+        RateLimitError::Credential(CredentialError::PointDecompressionError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum TicketError {
+    /// The ticket was already presented.
+    AlreadyUsed,
+    /// An error in the underlying credential protocol.
+    Credential(CredentialError),
+    /// The venue ID was empty or too long, or the validity window was empty.
+    InvalidScope,
+    /// The ticket was presented outside of its validity window.
+    OutsideWindow,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// The ticket's proof of a valid credential did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+    /// The ticket was presented at a venue other than the one it was minted for.
+    WrongVenue,
+}
+
+impl fmt::Display for TicketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TicketError::AlreadyUsed
+                => write!(f, "The ticket was already used"),
+            TicketError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            TicketError::InvalidScope
+                => write!(f, "The venue ID or validity window was invalid"),
+            TicketError::OutsideWindow
+                => write!(f, "The ticket is not valid at this time"),
+            TicketError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            TicketError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            TicketError::VerificationFailure
+                => write!(f, "The ticket could not be verified"),
+            TicketError::WrongNumberOfBytes
+                => write!(f, "The ticket data was not the correct length"),
+            TicketError::WrongVenue
+                => write!(f, "The ticket is for a different venue"),
+        }
+    }
+}
+
+impl ::failure::Fail for TicketError { }
+
+impl From<CredentialError> for TicketError {
+    fn from(source: CredentialError) -> TicketError {
+        TicketError::Credential(source)
+    }
+}
+
+impl From<NoneError> for TicketError {
+    fn from(_source: NoneError) -> TicketError {
+        TicketError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FragmentError {
+    /// A fragment's checksum did not match its contents.
+    ChecksumMismatch,
+    /// A fragment's index was not below the total number of fragments.
+    InvalidIndex,
+    /// The reassembled message did not match its digest.
+    MessageDigestMismatch,
+    /// A fragment belonged to a different message than the others.
+    MismatchedMessage,
+    /// The message would need more than `MAXIMUM_FRAGMENTS` fragments.
+    TooManyFragments,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FragmentError::ChecksumMismatch
+                => write!(f, "The fragment was corrupted"),
+            FragmentError::InvalidIndex
+                => write!(f, "The fragment's index was out of range"),
+            FragmentError::MessageDigestMismatch
+                => write!(f, "The reassembled message was corrupted"),
+            FragmentError::MismatchedMessage
+                => write!(f, "The fragment belongs to a different message"),
+            FragmentError::TooManyFragments
+                => write!(f, "The message is too large to split into fragments"),
+            FragmentError::WrongNumberOfBytes
+                => write!(f, "The fragment data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for FragmentError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum QrError {
+    /// A chunk's checksum did not match its contents.
+    ChecksumMismatch,
+    /// A chunk's text encoding was malformed.
+    InvalidEncoding,
+    /// A chunk's index was not below the total number of chunks.
+    InvalidIndex,
+    /// The reassembled message did not match its digest.
+    MessageDigestMismatch,
+    /// A chunk belonged to a different message than the others.
+    MismatchedMessage,
+    /// The message would need more than `MAXIMUM_CHUNKS` chunks.
+    TooManyChunks,
+    /// The chunk had an unknown version.
+    UnknownVersion,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QrError::ChecksumMismatch
+                => write!(f, "The chunk was corrupted"),
+            QrError::InvalidEncoding
+                => write!(f, "The chunk text was not validly encoded"),
+            QrError::InvalidIndex
+                => write!(f, "The chunk's index was out of range"),
+            QrError::MessageDigestMismatch
+                => write!(f, "The reassembled message was corrupted"),
+            QrError::MismatchedMessage
+                => write!(f, "The chunk belongs to a different message"),
+            QrError::TooManyChunks
+                => write!(f, "The message is too large to split into chunks"),
+            QrError::UnknownVersion
+                => write!(f, "The chunk version was unknown"),
+            QrError::WrongNumberOfBytes
+                => write!(f, "The chunk data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for QrError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ServiceError {
+    /// An error in the underlying credential protocol.
+    Credential(Error),
+    /// The request body could not be decoded.
+    MalformedRequest,
+    /// The response body could not be encoded.
+    MalformedResponse,
+    /// The endpoint does not accept the request method.
+    MethodNotAllowed,
+    /// There is no endpoint at the request path.
+    NotFound,
+    /// The request body was neither JSON nor CBOR.
+    UnsupportedMediaType,
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ServiceError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            ServiceError::MalformedRequest
+                => write!(f, "The request body could not be decoded"),
+            ServiceError::MalformedResponse
+                => write!(f, "The response body could not be encoded"),
+            ServiceError::MethodNotAllowed
+                => write!(f, "The endpoint does not accept this request method"),
+            ServiceError::NotFound
+                => write!(f, "There is no endpoint at this path"),
+            ServiceError::UnsupportedMediaType
+                => write!(f, "The request content type must be application/json or application/cbor"),
+        }
+    }
+}
+
+impl ::failure::Fail for ServiceError { }
+
+impl From<CredentialError> for ServiceError {
+    fn from(source: CredentialError) -> ServiceError {
+        ServiceError::Credential(source.into())
+    }
+}
+
+impl From<Error> for ServiceError {
+    fn from(source: Error) -> ServiceError {
+        ServiceError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum TrustError {
+    /// An error decoding the parameters of a trusted issuer.
+    Credential(CredentialError),
+    /// The issuer is trusted, but not for the given epoch.
+    EpochNotAccepted,
+    /// The issuer ID was empty or longer than `MAXIMUM_SIZEOF_ISSUER_ID`.
+    InvalidIssuerId,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// The issuer is trusted, but not for the given scope.
+    ScopeNotAccepted,
+    /// The issuer is not in the trust registry.
+    UnknownIssuer,
+    /// The trust registry's signature did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for TrustError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrustError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            TrustError::EpochNotAccepted
+                => write!(f, "The issuer is not trusted in this epoch"),
+            TrustError::InvalidIssuerId
+                => write!(f, "The issuer ID was empty or too long"),
+            TrustError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            TrustError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            TrustError::ScopeNotAccepted
+                => write!(f, "The issuer is not trusted for this scope"),
+            TrustError::UnknownIssuer
+                => write!(f, "The issuer is not in the trust registry"),
+            TrustError::VerificationFailure
+                => write!(f, "The trust registry's signature could not be verified"),
+            TrustError::WrongNumberOfBytes
+                => write!(f, "The trust registry data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for TrustError { }
+
+impl From<CredentialError> for TrustError {
+    fn from(source: CredentialError) -> TrustError {
+        TrustError::Credential(source)
+    }
+}
+
+impl From<MacError> for TrustError {
+    fn from(source: MacError) -> TrustError {
+        TrustError::Credential(CredentialError::from(source))
+    }
+}
+
+impl From<NoneError> for TrustError {
+    fn from(_source: NoneError) -> TrustError {
+        TrustError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PredicateError {
+    /// The prover's value does not satisfy the predicate.
+    OutOfRange,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// The predicate proof did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for PredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PredicateError::OutOfRange
+                => write!(f, "The committed value does not satisfy the predicate"),
+            PredicateError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            PredicateError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            PredicateError::VerificationFailure
+                => write!(f, "The predicate proof could not be verified"),
+            PredicateError::WrongNumberOfBytes
+                => write!(f, "The predicate proof was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for PredicateError { }
+
+impl From<NoneError> for PredicateError {
+    fn from(_source: NoneError) -> PredicateError {
+        PredicateError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PassError {
+    Credential(CredentialError),
+    /// The validity window ended before it started, or was too long.
+    InvalidWindow,
+    /// The issuer's proof of correct issuance did not verify.
+    Issuance,
+    PointDecompressionError,
+    Predicate(PredicateError),
+    /// The pass's MAC or its proof of validity did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for PassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PassError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            PassError::InvalidWindow
+                => write!(f, "The validity window was empty or too long"),
+            PassError::Issuance
+                => write!(f, "The pass issuance could not be verified"),
+            PassError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            PassError::Predicate(ref e)
+                => write!(f, "Pass predicate error: {}", e),
+            PassError::VerificationFailure
+                => write!(f, "The pass presentation could not be verified"),
+            PassError::WrongNumberOfBytes
+                => write!(f, "The pass data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for PassError { }
+
+impl From<CredentialError> for PassError {
+    fn from(source: CredentialError) -> PassError {
+        PassError::Credential(source)
+    }
+}
+
+impl From<MacError> for PassError {
+    fn from(source: MacError) -> PassError {
+        PassError::Credential(CredentialError::from(source))
+    }
+}
+
+impl From<NoneError> for PassError {
+    fn from(_source: NoneError) -> PassError {
+        PassError::PointDecompressionError
+    }
+}
+
+impl From<PredicateError> for PassError {
+    fn from(source: PredicateError) -> PassError {
+        PassError::Predicate(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AuditError {
+    /// The audit log did not end in the expected chain hash.
+    HeadMismatch,
+    /// An entry was recorded in an earlier time bucket than its predecessor.
+    NonMonotonicTime,
+    /// An entry's outcome byte was not a known `AuditOutcome`.
+    UnknownOutcome,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AuditError::HeadMismatch
+                => write!(f, "The audit log does not match its published head"),
+            AuditError::NonMonotonicTime
+                => write!(f, "The audit log's entries are not in time order"),
+            AuditError::UnknownOutcome
+                => write!(f, "The audit log entry had an unknown outcome"),
+            AuditError::WrongNumberOfBytes
+                => write!(f, "The audit log data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for AuditError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum VcError {
+    /// An error decoding the credential or presentation in an envelope.
+    Credential(CredentialError),
+    /// A `proofValue` was not multibase-encoded hexadecimal.
+    InvalidProofValue,
+    /// An envelope lacked its `type`, `proof`, or `proofValue`.
+    MissingField,
+    /// An envelope did not hold the kind of credential expected.
+    UnexpectedType,
+    /// An envelope's proof was not one of ours.
+    UnknownProofType,
+}
+
+impl fmt::Display for VcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VcError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            VcError::InvalidProofValue
+                => write!(f, "The proof value was not validly encoded"),
+            VcError::MissingField
+                => write!(f, "The envelope is missing a required field"),
+            VcError::UnexpectedType
+                => write!(f, "The envelope holds a different kind of credential"),
+            VcError::UnknownProofType
+                => write!(f, "The envelope's proof type was not recognised"),
+        }
+    }
+}
+
+impl ::failure::Fail for VcError { }
+
+impl From<CredentialError> for VcError {
+    fn from(source: CredentialError) -> VcError {
+        VcError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SyncError {
+    /// An error decoding a nullifier.
+    Credential(CredentialError),
+    /// The epoch has been closed, and accepts no further spends.
+    EpochClosed,
+    /// The nullifier was already spent in this epoch.
+    NullifierReused,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SyncError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            SyncError::EpochClosed
+                => write!(f, "The epoch has been closed"),
+            SyncError::NullifierReused
+                => write!(f, "The nullifier was already spent in this epoch"),
+            SyncError::WrongNumberOfBytes
+                => write!(f, "The sync message was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for SyncError { }
+
+impl From<CredentialError> for SyncError {
+    fn from(source: CredentialError) -> SyncError {
+        SyncError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StorageError {
+    /// The backing store failed, e.g. to read from or write to disk.
+    Backend,
+    /// A stored record could not be decoded.
+    Corrupt,
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StorageError::Backend
+                => write!(f, "The backing store failed"),
+            StorageError::Corrupt
+                => write!(f, "A stored record could not be decoded"),
+        }
+    }
+}
+
+impl ::failure::Fail for StorageError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SchemaError {
+    /// An error decoding or verifying the underlying credential or
+    /// presentation.
+    Credential(CredentialError),
+    /// An upgrade's schemas disagreed on an attribute they share, or an
+    /// attribute added by the upgrade had no default.
+    Incompatible,
+    /// A schema's encoding was malformed, e.g. a name was not UTF-8 or an
+    /// attribute type was unknown.
+    Malformed,
+    /// An attribute which must be revealed was hidden, or one which must be
+    /// hidden was revealed.
+    RevealRule,
+    /// A presentation named a schema which the verifier does not know.
+    UnknownSchema,
+    /// A schema described a different number of attributes than the
+    /// credential holds.
+    WrongNumberOfAttributes,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            SchemaError::Incompatible
+                => write!(f, "The schemas of the upgrade are incompatible"),
+            SchemaError::Malformed
+                => write!(f, "The schema was malformed"),
+            SchemaError::RevealRule
+                => write!(f, "An attribute was revealed or hidden against the schema's rules"),
+            SchemaError::UnknownSchema
+                => write!(f, "The schema is not known"),
+            SchemaError::WrongNumberOfAttributes
+                => write!(f, "The schema describes a different number of attributes"),
+            SchemaError::WrongNumberOfBytes
+                => write!(f, "The schema data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for SchemaError { }
+
+impl From<CredentialError> for SchemaError {
+    fn from(source: CredentialError) -> SchemaError {
+        SchemaError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BbsError {
+    /// A presentation's proof of knowledge of a signature did not verify.
+    InvalidProof,
+    /// A credential's signature did not verify.
+    InvalidSignature,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// The credential or presentation did not match its schema.
+    Schema(SchemaError),
+    /// An attribute to disclose was not named in the schema.
+    UnknownAttribute,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for BbsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BbsError::InvalidProof
+                => write!(f, "The BBS presentation did not verify"),
+            BbsError::InvalidSignature
+                => write!(f, "The BBS signature did not verify"),
+            BbsError::PointDecompressionError
+                => write!(f, "Cannot decompress BLS12-381 point"),
+            BbsError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            BbsError::Schema(ref e)
+                => write!(f, "Schema error: {}", e),
+            BbsError::UnknownAttribute
+                => write!(f, "The attribute is not named in the schema"),
+            BbsError::WrongNumberOfBytes
+                => write!(f, "The BBS data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for BbsError { }
+
+impl From<SchemaError> for BbsError {
+    fn from(source: SchemaError) -> BbsError {
+        BbsError::Schema(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RevocationError {
+    /// An error decoding or verifying the underlying credential presentation.
+    Credential(CredentialError),
+    /// A delta did not apply cleanly to the registry, or did not produce the
+    /// registry it was signed for, and the whole registry should be fetched
+    /// again.
+    Diverged,
+    /// A request for a revocable credential, or the presentation of the
+    /// credential it was made with, did not verify.
+    Enrollment,
+    /// The issuer's proof of correct issuance of a revocable credential did
+    /// not verify.
+    Issuance,
+    /// A registry name or a revoked identifier was empty or longer than
+    /// `MAXIMUM_SIZEOF_REVOCATION_ID`.
+    InvalidIdentifier,
+    /// A non-revocation proof did not verify.
+    InvalidProof,
+    /// A suspension ended before it began.
+    InvalidSuspension,
+    /// A delta listed an identifier more than once, or out of order.
+    Malformed,
+    /// No applied delta remains to be rolled back.
+    NothingToRollBack,
+    PointDecompressionError,
+    /// The credential's handle has been revoked.
+    Revoked,
+    ScalarFormatError,
+    /// A delta's signature did not verify.
+    VerificationFailure,
+    /// A delta did not start from the registry's epoch, or did not move to a
+    /// later one.
+    WrongEpoch,
+    /// A non-revocation witness was for another credential.
+    WrongHandle,
+    /// A delta was for another registry.
+    WrongRegistry,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for RevocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RevocationError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            RevocationError::Diverged
+                => write!(f, "The revocation delta does not match this registry"),
+            RevocationError::Enrollment
+                => write!(f, "The revocable credential request could not be verified"),
+            RevocationError::Issuance
+                => write!(f, "The revocable credential issuance could not be verified"),
+            RevocationError::InvalidIdentifier
+                => write!(f, "The revocation identifier was empty or too long"),
+            RevocationError::InvalidProof
+                => write!(f, "The non-revocation proof could not be verified"),
+            RevocationError::InvalidSuspension
+                => write!(f, "The suspension ended before it began"),
+            RevocationError::Malformed
+                => write!(f, "The revocation delta was malformed"),
+            RevocationError::NothingToRollBack
+                => write!(f, "There is no revocation delta to roll back"),
+            RevocationError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            RevocationError::Revoked
+                => write!(f, "The credential has been revoked"),
+            RevocationError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            RevocationError::VerificationFailure
+                => write!(f, "The revocation delta's signature could not be verified"),
+            RevocationError::WrongEpoch
+                => write!(f, "The revocation delta is not for this registry's epoch"),
+            RevocationError::WrongHandle
+                => write!(f, "The non-revocation witness is for another credential"),
+            RevocationError::WrongRegistry
+                => write!(f, "The revocation delta is for another registry"),
+            RevocationError::WrongNumberOfBytes
+                => write!(f, "The revocation data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for RevocationError { }
+
+impl From<CredentialError> for RevocationError {
+    fn from(source: CredentialError) -> RevocationError {
+        RevocationError::Credential(source)
+    }
+}
+
+impl From<MacError> for RevocationError {
+    fn from(source: MacError) -> RevocationError {
+        RevocationError::Credential(CredentialError::from(source))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ChainError {
+    /// An error decoding or checking the issuer parameters within a bundle.
+    Credential(CredentialError),
+    /// No parameter bundle has been accepted into the chain.
+    Empty,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// A bundle was not for a later epoch than the head of the chain.
+    StaleEpoch,
+    /// A bundle was signed by neither the current key nor the root key.
+    VerificationFailure,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChainError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            ChainError::Empty
+                => write!(f, "The parameter chain has no bundles"),
+            ChainError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            ChainError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            ChainError::StaleEpoch
+                => write!(f, "The parameter bundle is not for a later epoch"),
+            ChainError::VerificationFailure
+                => write!(f, "The parameter bundle's signature could not be verified"),
+            ChainError::WrongNumberOfBytes
+                => write!(f, "The parameter bundle was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for ChainError { }
+
+impl From<CredentialError> for ChainError {
+    fn from(source: CredentialError) -> ChainError {
+        ChainError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CeremonyError {
+    /// An operator committed or revealed twice, or took part twice in a
+    /// transcript.
+    Duplicate,
+    /// No operator committed, or some operator has not revealed.
+    Incomplete,
+    /// A ceremony's name was empty or longer than
+    /// `MAXIMUM_SIZEOF_CEREMONY_NAME`.
+    InvalidName,
+    /// Some entropy did not open its commitment, or a transcript's state was
+    /// not computed from the steps before it.
+    Mismatch,
+    PointDecompressionError,
+    ScalarFormatError,
+    /// An operator revealed without having committed, or signed with a key
+    /// other than their own.
+    UnknownOperator,
+    /// A commitment was not signed by its operator within the ceremony.
+    VerificationFailure,
+    /// A commitment was made after some entropy was revealed.
+    WrongPhase,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for CeremonyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CeremonyError::Duplicate
+                => write!(f, "The operator has already taken part in the ceremony"),
+            CeremonyError::Incomplete
+                => write!(f, "Not every operator has contributed to the ceremony"),
+            CeremonyError::InvalidName
+                => write!(f, "The ceremony name was empty or too long"),
+            CeremonyError::Mismatch
+                => write!(f, "The ceremony's entropy does not match its commitments"),
+            CeremonyError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            CeremonyError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            CeremonyError::UnknownOperator
+                => write!(f, "The operator has not committed to the ceremony"),
+            CeremonyError::VerificationFailure
+                => write!(f, "The ceremony commitment's signature could not be verified"),
+            CeremonyError::WrongPhase
+                => write!(f, "The ceremony is no longer accepting commitments"),
+            CeremonyError::WrongNumberOfBytes
+                => write!(f, "The ceremony data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for CeremonyError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RequestError {
+    /// An error making or verifying the underlying presentation.
+    Credential(CredentialError),
+    /// A revealed attribute's opening, or a predicate's range proof, did not
+    /// verify.
+    InvalidProof,
+    /// A request or response's encoding was malformed, e.g. a string was not
+    /// UTF-8 or a requirement was unknown.
+    Malformed,
+    /// A predicate was requested over an attribute which is not an integer or
+    /// a timestamp, or over an unsupported number of bits.
+    NotAnInteger,
+    /// An error making, verifying, or decoding a predicate's range proof.
+    Predicate(PredicateError),
+    /// The holder did not consent to the request.
+    Refused,
+    ScalarFormatError,
+    /// The request does not fit the schema's rules.
+    Schema(SchemaError),
+    /// A requested attribute is not in the schema.
+    UnknownAttribute,
+    /// The credential does not satisfy the request.
+    Unsatisfied,
+    WrongNumberOfBytes,
+    /// A response did not answer each requested attribute, in order, with a
+    /// disclosure of the requested kind.
+    WrongNumberOfDisclosures,
+    /// The request or response is for a different schema.
+    WrongSchema,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RequestError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            RequestError::InvalidProof
+                => write!(f, "A requested attribute's proof could not be verified"),
+            RequestError::Malformed
+                => write!(f, "The presentation request was malformed"),
+            RequestError::NotAnInteger
+                => write!(f, "A predicate was requested over an attribute which is not an integer"),
+            RequestError::Predicate(ref e)
+                => write!(f, "Predicate error: {}", e),
+            RequestError::Refused
+                => write!(f, "The holder did not consent to the presentation request"),
+            RequestError::ScalarFormatError
+                => write!(f, "Cannot use scalar with high-bit set"),
+            RequestError::Schema(ref e)
+                => write!(f, "Schema error: {}", e),
+            RequestError::UnknownAttribute
+                => write!(f, "A requested attribute is not in the schema"),
+            RequestError::Unsatisfied
+                => write!(f, "The credential does not satisfy the request"),
+            RequestError::WrongNumberOfBytes
+                => write!(f, "The presentation request data was not the correct length"),
+            RequestError::WrongNumberOfDisclosures
+                => write!(f, "The response does not answer each requested attribute"),
+            RequestError::WrongSchema
+                => write!(f, "The presentation request is for a different schema"),
+        }
+    }
+}
+
+impl ::failure::Fail for RequestError { }
+
+impl From<CredentialError> for RequestError {
+    fn from(source: CredentialError) -> RequestError {
+        RequestError::Credential(source)
+    }
+}
+
+impl From<PredicateError> for RequestError {
+    fn from(source: PredicateError) -> RequestError {
+        RequestError::Predicate(source)
+    }
+}
+
+impl From<SchemaError> for RequestError {
+    fn from(source: SchemaError) -> RequestError {
+        RequestError::Schema(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ReportError {
+    /// A holder with the same nullifier was already admitted at the entry.
+    AlreadyAdmitted,
+    /// An error making or verifying the presentation or its nullifier.
+    Credential(CredentialError),
+    /// An error signing, unblinding, or redeeming the report token.
+    Token(TokenError),
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReportError::AlreadyAdmitted
+                => write!(f, "The holder was already admitted at this entry"),
+            ReportError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            ReportError::Token(ref e)
+                => write!(f, "Report token error: {}", e),
+            ReportError::WrongNumberOfBytes
+                => write!(f, "The report data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for ReportError { }
+
+impl From<CredentialError> for ReportError {
+    fn from(source: CredentialError) -> ReportError {
+        ReportError::Credential(source)
+    }
+}
+
+impl From<TokenError> for ReportError {
+    fn from(source: TokenError) -> ReportError {
+        ReportError::Token(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum VaultError {
+    /// An error decoding a stored ciphertext.
+    Credential(CredentialError),
+    /// A decryption proof did not verify.
+    InvalidProof,
+    /// A record had no attribute at the requested index.
+    NoSuchAttribute,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for VaultError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VaultError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            VaultError::InvalidProof
+                => write!(f, "The decryption proof could not be verified"),
+            VaultError::NoSuchAttribute
+                => write!(f, "The sealed record has no attribute at that index"),
+            VaultError::WrongNumberOfBytes
+                => write!(f, "The sealed attribute data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for VaultError { }
+
+impl From<CredentialError> for VaultError {
+    fn from(source: CredentialError) -> VaultError {
+        VaultError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum EligibilityError {
+    /// An error making or decoding the underlying blind request, or issuing
+    /// a credential for it.
+    Credential(CredentialError),
+    /// The eligibility proof did not verify.
+    InvalidProof,
+    /// The eligibility proof was made against another value of the
+    /// accumulator, e.g. because the holder has not refreshed their witness.
+    WrongEpoch,
+    /// The witness was for another attribute than the one requested.
+    WrongHandle,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for EligibilityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EligibilityError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            EligibilityError::InvalidProof
+                => write!(f, "The eligibility proof could not be verified"),
+            EligibilityError::WrongEpoch
+                => write!(f, "The eligibility proof was for another accumulator value"),
+            EligibilityError::WrongHandle
+                => write!(f, "The eligibility witness was for another attribute"),
+            EligibilityError::WrongNumberOfBytes
+                => write!(f, "The eligible request was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for EligibilityError { }
+
+impl From<CredentialError> for EligibilityError {
+    fn from(source: CredentialError) -> EligibilityError {
+        EligibilityError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DeviceError {
+    Credential(CredentialError),
+    /// The enrollment proof, or the presentation of the credential it was
+    /// made with, did not verify.
+    Enrollment,
+    /// The issuer's proof of correct issuance did not verify.
+    Issuance,
+    PointDecompressionError,
+    /// The device credential's MAC or its proof of validity did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DeviceError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            DeviceError::Enrollment
+                => write!(f, "The device enrollment could not be verified"),
+            DeviceError::Issuance
+                => write!(f, "The device credential issuance could not be verified"),
+            DeviceError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            DeviceError::VerificationFailure
+                => write!(f, "The device presentation could not be verified"),
+            DeviceError::WrongNumberOfBytes
+                => write!(f, "The device data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for DeviceError { }
+
+impl From<CredentialError> for DeviceError {
+    fn from(source: CredentialError) -> DeviceError {
+        DeviceError::Credential(source)
+    }
+}
+
+impl From<MacError> for DeviceError {
+    fn from(source: MacError) -> DeviceError {
+        DeviceError::Credential(CredentialError::from(source))
+    }
+}
+
+impl From<NoneError> for DeviceError {
+    fn from(_source: NoneError) -> DeviceError {
+        DeviceError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RecoveryError {
+    Credential(CredentialError),
+    /// A guardian's proof that it decrypted its share correctly did not
+    /// verify.
+    InvalidDecryption,
+    /// A share's proof that it was encrypted from the sharing's polynomial
+    /// did not verify.
+    InvalidSharing,
+    /// The threshold was zero, or greater than the number of guardians.
+    InvalidThreshold,
+    /// The key was not one of the sharing's guardians.
+    NotAGuardian,
+    /// Fewer valid shares than the threshold were given.
+    NotEnoughShares,
+    PointDecompressionError,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for RecoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecoveryError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            RecoveryError::InvalidDecryption
+                => write!(f, "The guardian's decryption could not be verified"),
+            RecoveryError::InvalidSharing
+                => write!(f, "The encrypted share could not be verified"),
+            RecoveryError::InvalidThreshold
+                => write!(f, "The threshold must be between one and the number of guardians"),
+            RecoveryError::NotAGuardian
+                => write!(f, "The key is not a guardian of this sharing"),
+            RecoveryError::NotEnoughShares
+                => write!(f, "Fewer valid shares than the threshold were given"),
+            RecoveryError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            RecoveryError::WrongNumberOfBytes
+                => write!(f, "The recovery data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for RecoveryError { }
+
+impl From<CredentialError> for RecoveryError {
+    fn from(source: CredentialError) -> RecoveryError {
+        RecoveryError::Credential(source)
+    }
+}
+
+impl From<NoneError> for RecoveryError {
+    fn from(_source: NoneError) -> RecoveryError {
+        RecoveryError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ReplayError {
+    /// An error decoding the parameters or secrets in a bundle.
+    Credential(CredentialError),
+    /// A recorded step was sent by neither the client nor the server.
+    UnknownSender,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReplayError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            ReplayError::UnknownSender
+                => write!(f, "A recorded step had an unknown sender"),
+            ReplayError::WrongNumberOfBytes
+                => write!(f, "The replay bundle was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for ReplayError { }
+
+impl From<CredentialError> for ReplayError {
+    fn from(source: CredentialError) -> ReplayError {
+        ReplayError::Credential(source)
+    }
+}
+
+impl From<MacError> for ReplayError {
+    fn from(source: MacError) -> ReplayError {
+        ReplayError::Credential(CredentialError::from(source))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BackupError {
+    /// The sealed issuer state did not authenticate under the recovered key,
+    /// e.g. because it was modified, or shares of another backup were given.
+    Corrupt,
+    Credential(CredentialError),
+    /// The threshold was less than two, so that a single custodian could
+    /// restore the backup alone, or greater than the number of custodians.
+    InvalidThreshold,
+    Recovery(RecoveryError),
+    /// A restored revocation registry or accumulator could not be decoded.
+    Revocation(RevocationError),
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BackupError::Corrupt
+                => write!(f, "The backup could not be authenticated"),
+            BackupError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            BackupError::InvalidThreshold
+                => write!(f, "The threshold must be between two and the number of custodians"),
+            BackupError::Recovery(ref e)
+                => write!(f, "Recovery error: {}", e),
+            BackupError::Revocation(ref e)
+                => write!(f, "Revocation error: {}", e),
+            BackupError::WrongNumberOfBytes
+                => write!(f, "The backup was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for BackupError { }
+
+impl From<CredentialError> for BackupError {
+    fn from(source: CredentialError) -> BackupError {
+        BackupError::Credential(source)
+    }
+}
+
+impl From<RecoveryError> for BackupError {
+    fn from(source: RecoveryError) -> BackupError {
+        BackupError::Recovery(source)
+    }
+}
+
+impl From<RevocationError> for BackupError {
+    fn from(source: RevocationError) -> BackupError {
+        BackupError::Revocation(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AttributeError {
+    /// A country code was not two ASCII letters.
+    InvalidCountryCode,
+    /// A scalar was not produced by the encoder for the expected type.
+    NotAnEncoding,
+    /// A value was outside of the range its encoding can represent.
+    OutOfRange,
+    /// A string was longer than `attributes::MAXIMUM_SHORT_STRING` bytes.
+    TooLong,
+}
+
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AttributeError::InvalidCountryCode
+                => write!(f, "The country code was not two ASCII letters"),
+            AttributeError::NotAnEncoding
+                => write!(f, "The scalar is not an encoding of an attribute of this type"),
+            AttributeError::OutOfRange
+                => write!(f, "The value is outside of the encodable range"),
+            AttributeError::TooLong
+                => write!(f, "The string is too long to encode as an attribute"),
+        }
+    }
+}
+
+impl ::failure::Fail for AttributeError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ExportError {
+    Credential(CredentialError),
+    /// A generator was the identity, or both generators were the same.
+    InvalidGenerators,
+    PointDecompressionError,
+    /// The proof linking the commitment to the presentation did not verify.
+    VerificationFailure,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            ExportError::InvalidGenerators
+                => write!(f, "The generators must be distinct and not the identity"),
+            ExportError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            ExportError::VerificationFailure
+                => write!(f, "The exported commitment could not be verified"),
+            ExportError::WrongNumberOfBytes
+                => write!(f, "The exported commitment was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for ExportError { }
+
+impl From<CredentialError> for ExportError {
+    fn from(source: CredentialError) -> ExportError {
+        ExportError::Credential(source)
+    }
+}
+
+impl From<NoneError> for ExportError {
+    fn from(_source: NoneError) -> ExportError {
+        ExportError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FreshnessError {
+    Credential(CredentialError),
+    /// The presentation was dated further ahead of our clock than the
+    /// policy's permitted skew.
+    FromTheFuture,
+    /// The presentation was older than the policy's maximum age.
+    Stale,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for FreshnessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FreshnessError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            FreshnessError::FromTheFuture
+                => write!(f, "The presentation was dated in the future"),
+            FreshnessError::Stale
+                => write!(f, "The presentation is too old"),
+            FreshnessError::WrongNumberOfBytes
+                => write!(f, "The fresh presentation was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for FreshnessError { }
+
+impl From<CredentialError> for FreshnessError {
+    fn from(source: CredentialError) -> FreshnessError {
+        FreshnessError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StreamError {
+    /// A frame's issuance could not be decoded or did not verify.
+    Credential(CredentialError),
+    /// A frame was longer than `streaming::MAXIMUM_FRAME_SIZE`.
+    FrameTooLarge,
+    /// A frame was of an unknown kind, out of order, or did not follow the
+    /// stream's header.
+    Malformed,
+    /// The issuer refused the request a frame answered.
+    Refused,
+    /// The stream ended before every frame it announced had arrived.
+    Truncated,
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StreamError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            StreamError::FrameTooLarge
+                => write!(f, "A frame of the stream was too large"),
+            StreamError::Malformed
+                => write!(f, "The stream was malformed"),
+            StreamError::Refused
+                => write!(f, "The issuer refused the request"),
+            StreamError::Truncated
+                => write!(f, "The stream ended early"),
+        }
+    }
+}
+
+impl ::failure::Fail for StreamError { }
+
+impl From<CredentialError> for StreamError {
+    fn from(source: CredentialError) -> StreamError {
+        StreamError::Credential(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PolicyError {
+    /// A range admitted no value, or was unbounded on both sides.
+    InvalidRange,
+    /// Instructions or a response were malformed.
+    Malformed,
+    /// A roster proof was required and not given, or given and not required.
+    MissingRosterProof,
+    PointDecompressionError,
+    /// The policy does not fit the schema, or a response to it did not
+    /// verify.
+    Request(RequestError),
+    /// An error making or verifying a roster proof.
+    Revocation(RevocationError),
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PolicyError::InvalidRange
+                => write!(f, "A range in the policy admits no value"),
+            PolicyError::Malformed
+                => write!(f, "The presentation policy was malformed"),
+            PolicyError::MissingRosterProof
+                => write!(f, "A roster proof was missing or unexpected"),
+            PolicyError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            PolicyError::Request(ref e)
+                => write!(f, "Presentation request error: {}", e),
+            PolicyError::Revocation(ref e)
+                => write!(f, "Roster error: {}", e),
+            PolicyError::WrongNumberOfBytes
+                => write!(f, "The presentation policy data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for PolicyError { }
+
+impl From<NoneError> for PolicyError {
+    fn from(_source: NoneError) -> PolicyError {
+        PolicyError::PointDecompressionError
+    }
+}
+
+impl From<RequestError> for PolicyError {
+    fn from(source: RequestError) -> PolicyError {
+        PolicyError::Request(source)
+    }
+}
+
+impl From<RevocationError> for PolicyError {
+    fn from(source: RevocationError) -> PolicyError {
+        PolicyError::Revocation(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SelfCheckError {
+    /// A presentation which was altered after it was made was accepted.
+    AcceptedForgery,
+    /// The published parameters are not those of the loaded issuer.
+    EpochMismatch,
+    /// The published parameters are not valid at the time of the check.
+    Expired,
+    /// The issuer could not issue a credential.
+    Issuance(Error),
+    /// The issuer's public parameters are not those of its secret key.
+    KeyMismatch,
+    /// A holder could not verify an issuance, or present its credential.
+    Presentation(Error),
+    /// A presentation of a credential just issued was not accepted.
+    Verification(Error),
+}
+
+impl fmt::Display for SelfCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SelfCheckError::AcceptedForgery
+                => write!(f, "An altered presentation was accepted"),
+            SelfCheckError::EpochMismatch
+                => write!(f, "The published parameters are not the issuer's"),
+            SelfCheckError::Expired
+                => write!(f, "The published parameters are not currently valid"),
+            SelfCheckError::Issuance(ref e)
+                => write!(f, "Could not issue a credential: {}", e),
+            SelfCheckError::KeyMismatch
+                => write!(f, "The issuer parameters do not match its secret key"),
+            SelfCheckError::Presentation(ref e)
+                => write!(f, "Could not obtain or present a credential: {}", e),
+            SelfCheckError::Verification(ref e)
+                => write!(f, "Could not verify a presentation: {}", e),
+        }
+    }
+}
+
+impl ::failure::Fail for SelfCheckError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DualKeyError {
+    /// The credential could not be presented, or did not verify.
+    Credential(CredentialError),
+    PointDecompressionError,
+    /// The credential is not bound to the signing key presented with it.
+    WrongKey,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for DualKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DualKeyError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            DualKeyError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            DualKeyError::WrongKey
+                => write!(f, "The credential is not bound to the signing key"),
+            DualKeyError::WrongNumberOfBytes
+                => write!(f, "The dual-key presentation data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for DualKeyError { }
+
+impl From<CredentialError> for DualKeyError {
+    fn from(source: CredentialError) -> DualKeyError {
+        DualKeyError::Credential(source)
+    }
+}
+
+impl From<NoneError> for DualKeyError {
+    fn from(_source: NoneError) -> DualKeyError {
+        DualKeyError::PointDecompressionError
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SecureChannelError {
+    /// A transport frame did not authenticate under the session's keys.
+    Decryption,
+    /// A gate message carried by the channel could not be decoded.
+    Gate(GateError),
+    /// The handshake failed, e.g. because a handshake message was altered.
+    Handshake,
+    /// A frame's length prefix did not match its length.
+    Malformed,
+    /// An IK handshake was begun without the responder's static key.
+    MissingKey,
+    /// A message was too long to be sealed into a single frame.
+    TooLarge,
+    /// The peer's static key was not the one expected, or not trusted.
+    UntrustedPeer,
+    /// A handshake message was written or read out of turn, or the session
+    /// was used before the handshake finished.
+    WrongState,
+}
+
+impl fmt::Display for SecureChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SecureChannelError::Decryption
+                => write!(f, "A frame could not be decrypted"),
+            SecureChannelError::Gate(ref e)
+                => write!(f, "Gate protocol error: {}", e),
+            SecureChannelError::Handshake
+                => write!(f, "The Noise handshake failed"),
+            SecureChannelError::Malformed
+                => write!(f, "The frame was malformed"),
+            SecureChannelError::MissingKey
+                => write!(f, "The responder's static key is required"),
+            SecureChannelError::TooLarge
+                => write!(f, "The message was too large for a frame"),
+            SecureChannelError::UntrustedPeer
+                => write!(f, "The peer's static key is not trusted"),
+            SecureChannelError::WrongState
+                => write!(f, "The secure channel is not in the right state"),
+        }
+    }
+}
+
+impl ::failure::Fail for SecureChannelError { }
+
+impl From<GateError> for SecureChannelError {
+    fn from(source: GateError) -> SecureChannelError {
+        SecureChannelError::Gate(source)
+    }
+}
+
+/// What could not be decoded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DecodeError {
+    /// Some data, such as a key, a proof, or a field, was missing.
+    MissingData,
+    /// The structure of the encoding was malformed, or a checksum within it
+    /// did not match.
+    Malformed,
+    PointDecompression,
+    ScalarFormat,
+    /// A list held a different number of attributes, tokens, or elements than
+    /// expected.
+    WrongNumberOfElements,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::MissingData
+                => write!(f, "some data was missing"),
+            DecodeError::Malformed
+                => write!(f, "the encoding was malformed"),
+            DecodeError::PointDecompression
+                => write!(f, "cannot decompress Ristretto point"),
+            DecodeError::ScalarFormat
+                => write!(f, "cannot use scalar with high-bit set"),
+            DecodeError::WrongNumberOfElements
+                => write!(f, "the wrong number of elements"),
+            DecodeError::WrongNumberOfBytes
+                => write!(f, "the wrong number of bytes"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for DecodeError {
+    fn description(&self) -> &str {
+        "decode error"
+    }
+}
+
+/// The statement whose proof, MAC, or signature did not verify.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Statement {
+    /// That a tag is an algebraic MAC on a message.
+    Mac,
+    /// That a presentation was made from a credential we issued, as in
+    /// `proofs::valid_credential`.
+    ValidCredential,
+    /// A proof accompanying a credential request, or a statement about the
+    /// attributes of a presentation, such as a pseudonym or hashed identifier.
+    CredentialAttributes,
+    /// That an issuance was a correctly computed MAC on the requested
+    /// attributes.
+    Issuance,
+    /// That a nullifier was computed from the presented credential, as in
+    /// `proofs::scoped_nullifier`.
+    ScopedNullifier,
+    /// That a batch of tokens was signed with the issuer's key, as in
+    /// `proofs::batch_dleq`.
+    BatchDleq,
+    /// That a VOPRF was evaluated with the server's key.
+    Voprf,
+    /// That an attribute satisfies a predicate.
+    Predicate,
+    /// That a pass was correctly issued, as in `proofs::pass_issuance`.
+    PassIssuance,
+    /// That a pass presentation was made from a pass we issued, as in
+    /// `proofs::valid_pass`.
+    ValidPass,
+    /// A signature by a trust registry or federation key.
+    Signature,
+    /// That an audit log ends in the expected chain hash.
+    AuditChain,
+    /// A BBS signature, or a presentation of one.
+    Bbs,
+    /// That a credential's handle is in the issuer's accumulator, as in
+    /// `proofs::valid_revocable`, and is for the presented credential, as in
+    /// `proofs::roster_link`.
+    NonRevocation,
+    /// That a stored ciphertext decrypts to a claimed plaintext, as in
+    /// `vault::Opening`.
+    Decryption,
+    /// That a requested attribute is in an allow-list accumulator, as in
+    /// `proofs::eligibility`.
+    Eligibility,
+    /// That a device's credential commits to the same identity as the
+    /// credential it was enrolled with, as in `proofs::device_enrollment`.
+    DeviceEnrollment,
+    /// That a device credential was correctly issued, as in
+    /// `proofs::device_issuance`.
+    DeviceIssuance,
+    /// That a device presentation was made from a device credential we
+    /// issued, as in `proofs::valid_device`.
+    ValidDevice,
+    /// That an encrypted share was dealt from a sharing's polynomial, as in
+    /// `proofs::correct_share`.
+    CorrectShare,
+    /// That an exported commitment opens to a credential's hidden attribute,
+    /// as in `proofs::commitment_export`.
+    CommitmentExport,
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Statement::Mac                  => write!(f, "MAC"),
+            Statement::ValidCredential      => write!(f, "valid credential"),
+            Statement::CredentialAttributes => write!(f, "credential attributes"),
+            Statement::Issuance             => write!(f, "correct issuance"),
+            Statement::ScopedNullifier      => write!(f, "scoped nullifier"),
+            Statement::BatchDleq            => write!(f, "batch DLEQ"),
+            Statement::Voprf                => write!(f, "VOPRF evaluation"),
+            Statement::Predicate            => write!(f, "predicate"),
+            Statement::PassIssuance         => write!(f, "pass issuance"),
+            Statement::ValidPass            => write!(f, "valid pass"),
+            Statement::Signature            => write!(f, "signature"),
+            Statement::AuditChain           => write!(f, "audit chain"),
+            Statement::Bbs                  => write!(f, "BBS signature"),
+            Statement::NonRevocation        => write!(f, "non-revocation"),
+            Statement::Decryption           => write!(f, "verifiable decryption"),
+            Statement::Eligibility          => write!(f, "eligibility"),
+            Statement::DeviceEnrollment     => write!(f, "device enrollment"),
+            Statement::DeviceIssuance       => write!(f, "device issuance"),
+            Statement::ValidDevice          => write!(f, "valid device credential"),
+            Statement::CorrectShare         => write!(f, "correct secret sharing"),
+            Statement::CommitmentExport     => write!(f, "commitment export"),
+        }
+    }
+}
+
+/// Which policy a valid credential or presentation was rejected by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PolicyViolation {
+    /// An attribute was unacceptable.
+    BadAttribute,
+    /// The per-epoch limit on presentations was reached.
+    LimitExceeded,
+    /// An attribute did not satisfy the required predicate.
+    OutOfRange,
+    /// The verifier rejected the presentation.
+    Rejected,
+    /// The issuer is trusted, but not for the given scope.
+    ScopeNotAccepted,
+    /// The issuer is not trusted.
+    UnknownIssuer,
+    /// A ticket was presented at a venue other than the one it was minted for.
+    WrongVenue,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PolicyViolation::BadAttribute
+                => write!(f, "an attribute was unacceptable"),
+            PolicyViolation::LimitExceeded
+                => write!(f, "the rate limit was exceeded"),
+            PolicyViolation::OutOfRange
+                => write!(f, "an attribute did not satisfy the predicate"),
+            PolicyViolation::Rejected
+                => write!(f, "the verifier rejected the presentation"),
+            PolicyViolation::ScopeNotAccepted
+                => write!(f, "the issuer is not trusted for this scope"),
+            PolicyViolation::UnknownIssuer
+                => write!(f, "the issuer is not trusted"),
+            PolicyViolation::WrongVenue
+                => write!(f, "the ticket is for another venue"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for PolicyViolation {
+    fn description(&self) -> &str {
+        "policy violation"
+    }
+}
+
+/// Every error in this crate, arranged by why it happened, for callers which
+/// must decide what to do about a failure rather than only report it.
+///
+/// Every other error type in this crate converts into an `Error`, so any of
+/// them may be propagated into it with `?`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Error {
+    /// Some input could not be decoded.
+    Decode(DecodeError),
+    /// A proof of the given statement did not verify.
+    Proof(Statement),
+    /// A valid credential or presentation was not accepted.
+    Policy(PolicyViolation),
+    /// The credential, or the key which issued it, has been revoked.
+    Revoked,
+    /// A credential, ticket, or presentation was used outside of its
+    /// validity window or epoch.
+    Expired,
+    /// A token, nullifier, or ticket was already spent.
+    Replayed,
+    /// A message was unexpected, or did not arrive in time.
+    Protocol,
+    /// A version, suite, algorithm, or format is not implemented.
+    Unsupported,
+    /// An argument was invalid, or an object was used before being properly
+    /// initialised.
+    Misuse,
+    /// Protocol state could not be loaded or saved.
+    Storage,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Decode(ref e)
+                => write!(f, "Could not decode: {}", e),
+            Error::Proof(ref s)
+                => write!(f, "The {} proof did not verify", s),
+            Error::Policy(ref p)
+                => write!(f, "Policy violation: {}", p),
+            Error::Revoked
+                => write!(f, "The credential has been revoked"),
+            Error::Expired
+                => write!(f, "The credential was used outside of its validity period"),
+            Error::Replayed
+                => write!(f, "The credential was already spent"),
+            Error::Protocol
+                => write!(f, "A protocol message was unexpected or late"),
+            Error::Unsupported
+                => write!(f, "The format or algorithm is not supported"),
+            Error::Misuse
+                => write!(f, "An argument was invalid or an object was not initialised"),
+            Error::Storage
+                => write!(f, "Protocol state could not be loaded or saved"),
+        }
+    }
+}
+
+// Under std, `failure` implements `Fail` for every `std::error::Error`.
+#[cfg(not(feature = "std"))]
+impl ::failure::Fail for Error { }
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Decode(_)   => "decode error",
+            Error::Proof(_)    => "proof verification failure",
+            Error::Policy(_)   => "policy violation",
+            Error::Revoked     => "revoked",
+            Error::Expired     => "expired",
+            Error::Replayed    => "replayed",
+            Error::Protocol    => "protocol error",
+            Error::Unsupported => "unsupported",
+            Error::Misuse      => "misuse",
+            Error::Storage     => "storage error",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match *self {
+            Error::Decode(ref e) => Some(e),
+            Error::Policy(ref p) => Some(p),
+            _                    => None,
+        }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(source: DecodeError) -> Error {
+        Error::Decode(source)
+    }
+}
+
+impl From<NoneError> for Error {
+    fn from(_source: NoneError) -> Error {
+        Error::Decode(DecodeError::MissingData)
+    }
+}
+
+impl From<MacError> for Error {
+    fn from(source: MacError) -> Error {
+        match source {
+            MacError::KeypairDeserialisation          => Error::Decode(DecodeError::Malformed),
+            MacError::PointDecompressionError         => Error::Decode(DecodeError::PointDecompression),
+            MacError::ScalarFormatError               => Error::Decode(DecodeError::ScalarFormat),
+            MacError::MessageLengthError{ length: _ } => Error::Misuse,
+            MacError::AuthenticationError             => Error::Proof(Statement::Mac),
+        }
+    }
+}
+
+impl From<CborError> for Error {
+    fn from(source: CborError) -> Error {
+        match source {
+            CborError::NonDeterministicEncoding |
+            CborError::TrailingBytes            |
+            CborError::UnexpectedType           |
+            CborError::InvalidValue             => Error::Decode(DecodeError::Malformed),
+            CborError::Truncated                => Error::Decode(DecodeError::WrongNumberOfBytes),
+            CborError::UnknownAlgorithm         => Error::Unsupported,
+            CborError::VerificationFailure      => Error::Proof(Statement::Signature),
+        }
+    }
+}
+
+impl From<CredentialError> for Error {
+    fn from(source: CredentialError) -> Error {
+        match source {
+            CredentialError::BadAttribute            => Error::Policy(PolicyViolation::BadAttribute),
+            CredentialError::CredentialIssuance      => Error::Proof(Statement::Issuance),
+            CredentialError::Expired                 => Error::Expired,
+            CredentialError::MacCreation             => Error::Misuse,
+            CredentialError::MacVerification         => Error::Proof(Statement::ValidCredential),
+            CredentialError::MissingData             => Error::Decode(DecodeError::MissingData),
+            CredentialError::NoIssuerKey             |
+            CredentialError::NoIssuerParameters      |
+            CredentialError::NoSystemParameters      => Error::Misuse,
+            CredentialError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            CredentialError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            CredentialError::WrongNumberOfAttributes => Error::Decode(DecodeError::WrongNumberOfElements),
+            CredentialError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+            CredentialError::VerificationFailure     => Error::Proof(Statement::CredentialAttributes),
+        }
+    }
+}
+
+impl From<EnvelopeError> for Error {
+    fn from(source: EnvelopeError) -> Error {
+        match source {
+            EnvelopeError::InvalidPayload     => Error::Decode(DecodeError::Malformed),
+            EnvelopeError::UnknownSuite       |
+            EnvelopeError::UnknownVersion     => Error::Unsupported,
+            EnvelopeError::WrongKind          => Error::Protocol,
+            EnvelopeError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<MigrationError> for Error {
+    fn from(source: MigrationError) -> Error {
+        match source {
+            MigrationError::Malformed => Error::Decode(DecodeError::Malformed),
+            MigrationError::WrongKind => Error::Protocol,
+        }
+    }
+}
+
+impl From<GateError> for Error {
+    fn from(source: GateError) -> Error {
+        match source {
+            GateError::Credential(e)      => e,
+            GateError::Rejected           => Error::Policy(PolicyViolation::Rejected),
+            GateError::Report(e)          => e,
+            GateError::Timeout            |
+            GateError::UnexpectedMessage  |
+            GateError::WrongState         => Error::Protocol,
+            GateError::UnknownMessageType => Error::Unsupported,
+        }
+    }
+}
+
+impl From<TokenError> for Error {
+    fn from(source: TokenError) -> Error {
+        match source {
+            TokenError::DoubleSpend             => Error::Replayed,
+            TokenError::InvalidMac              => Error::Proof(Statement::Mac),
+            TokenError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            TokenError::ProofFailure            => Error::Proof(Statement::BatchDleq),
+            TokenError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            TokenError::WrongNumberOfTokens     => Error::Decode(DecodeError::WrongNumberOfElements),
+            TokenError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<VoprfError> for Error {
+    fn from(source: VoprfError) -> Error {
+        match source {
+            VoprfError::DeriveKeyPairError      |
+            VoprfError::InvalidInput            => Error::Misuse,
+            VoprfError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            VoprfError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            VoprfError::VerificationFailure     => Error::Proof(Statement::Voprf),
+            VoprfError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+            VoprfError::WrongNumberOfElements   => Error::Decode(DecodeError::WrongNumberOfElements),
+        }
+    }
+}
+
+impl From<RateLimitError> for Error {
+    fn from(source: RateLimitError) -> Error {
+        match source {
+            RateLimitError::Credential(e)       => e.into(),
+            RateLimitError::WrongEpoch          => Error::Expired,
+            RateLimitError::LimitExceeded       => Error::Policy(PolicyViolation::LimitExceeded),
+            RateLimitError::NullifierReused     => Error::Replayed,
+            RateLimitError::VerificationFailure => Error::Proof(Statement::ScopedNullifier),
+        }
+    }
+}
+
+impl From<TicketError> for Error {
+    fn from(source: TicketError) -> Error {
+        match source {
+            TicketError::AlreadyUsed             => Error::Replayed,
+            TicketError::Credential(e)           => e.into(),
+            TicketError::InvalidScope            => Error::Misuse,
+            TicketError::OutsideWindow           => Error::Expired,
+            TicketError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            TicketError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            TicketError::VerificationFailure     => Error::Proof(Statement::ValidCredential),
+            TicketError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+            TicketError::WrongVenue              => Error::Policy(PolicyViolation::WrongVenue),
+        }
+    }
+}
+
+impl From<FragmentError> for Error {
+    fn from(source: FragmentError) -> Error {
+        match source {
+            FragmentError::ChecksumMismatch      |
+            FragmentError::InvalidIndex          |
+            FragmentError::MessageDigestMismatch |
+            FragmentError::MismatchedMessage     => Error::Decode(DecodeError::Malformed),
+            FragmentError::TooManyFragments      => Error::Misuse,
+            FragmentError::WrongNumberOfBytes    => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<QrError> for Error {
+    fn from(source: QrError) -> Error {
+        match source {
+            QrError::ChecksumMismatch      |
+            QrError::InvalidEncoding       |
+            QrError::InvalidIndex          |
+            QrError::MessageDigestMismatch |
+            QrError::MismatchedMessage     => Error::Decode(DecodeError::Malformed),
+            QrError::TooManyChunks         => Error::Misuse,
+            QrError::UnknownVersion        => Error::Unsupported,
+            QrError::WrongNumberOfBytes    => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<ServiceError> for Error {
+    fn from(source: ServiceError) -> Error {
+        match source {
+            ServiceError::Credential(e)        => e.into(),
+            ServiceError::MalformedRequest     => Error::Decode(DecodeError::Malformed),
+            ServiceError::MethodNotAllowed     |
+            ServiceError::NotFound             |
+            ServiceError::UnsupportedMediaType => Error::Unsupported,
+        }
+    }
+}
+
+impl From<TrustError> for Error {
+    fn from(source: TrustError) -> Error {
+        match source {
+            TrustError::Credential(e)           => e.into(),
+            TrustError::EpochNotAccepted        => Error::Expired,
+            TrustError::InvalidIssuerId         => Error::Misuse,
+            TrustError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            TrustError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            TrustError::ScopeNotAccepted        => Error::Policy(PolicyViolation::ScopeNotAccepted),
+            TrustError::UnknownIssuer           => Error::Policy(PolicyViolation::UnknownIssuer),
+            TrustError::VerificationFailure     => Error::Proof(Statement::Signature),
+            TrustError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<PredicateError> for Error {
+    fn from(source: PredicateError) -> Error {
+        match source {
+            PredicateError::OutOfRange              => Error::Policy(PolicyViolation::OutOfRange),
+            PredicateError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            PredicateError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            PredicateError::VerificationFailure     => Error::Proof(Statement::Predicate),
+            PredicateError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<PassError> for Error {
+    fn from(source: PassError) -> Error {
+        match source {
+            PassError::Credential(e)           => e.into(),
+            PassError::InvalidWindow           => Error::Misuse,
+            PassError::Issuance                => Error::Proof(Statement::PassIssuance),
+            PassError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            PassError::Predicate(e)            => e.into(),
+            PassError::VerificationFailure     => Error::Proof(Statement::ValidPass),
+            PassError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<AuditError> for Error {
+    fn from(source: AuditError) -> Error {
+        match source {
+            AuditError::HeadMismatch       => Error::Proof(Statement::AuditChain),
+            AuditError::NonMonotonicTime   |
+            AuditError::UnknownOutcome     => Error::Decode(DecodeError::Malformed),
+            AuditError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<VcError> for Error {
+    fn from(source: VcError) -> Error {
+        match source {
+            VcError::Credential(e)     => e.into(),
+            VcError::InvalidProofValue |
+            VcError::UnexpectedType    => Error::Decode(DecodeError::Malformed),
+            VcError::MissingField      => Error::Decode(DecodeError::MissingData),
+            VcError::UnknownProofType  => Error::Unsupported,
+        }
+    }
+}
+
+impl From<SyncError> for Error {
+    fn from(source: SyncError) -> Error {
+        match source {
+            SyncError::Credential(e)      => e.into(),
+            SyncError::EpochClosed        => Error::Expired,
+            SyncError::NullifierReused    => Error::Replayed,
+            SyncError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<StorageError> for Error {
+    fn from(source: StorageError) -> Error {
+        match source {
+            StorageError::Backend => Error::Storage,
+            StorageError::Corrupt => Error::Decode(DecodeError::Malformed),
+        }
+    }
+}
+
+impl From<SchemaError> for Error {
+    fn from(source: SchemaError) -> Error {
+        match source {
+            SchemaError::Credential(e)           => e.into(),
+            SchemaError::Incompatible            => Error::Misuse,
+            SchemaError::Malformed               => Error::Decode(DecodeError::Malformed),
+            SchemaError::RevealRule              => Error::Policy(PolicyViolation::BadAttribute),
+            SchemaError::UnknownSchema           => Error::Unsupported,
+            SchemaError::WrongNumberOfAttributes => Error::Decode(DecodeError::WrongNumberOfElements),
+            SchemaError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<BbsError> for Error {
+    fn from(source: BbsError) -> Error {
+        match source {
+            BbsError::InvalidProof            => Error::Proof(Statement::Bbs),
+            BbsError::InvalidSignature        => Error::Proof(Statement::Bbs),
+            BbsError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            BbsError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            BbsError::Schema(e)               => e.into(),
+            BbsError::UnknownAttribute        => Error::Misuse,
+            BbsError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<RevocationError> for Error {
+    fn from(source: RevocationError) -> Error {
+        match source {
+            RevocationError::Credential(e)           => e.into(),
+            RevocationError::Diverged                => Error::Protocol,
+            RevocationError::Enrollment              => Error::Proof(Statement::CredentialAttributes),
+            RevocationError::Issuance                => Error::Proof(Statement::Issuance),
+            RevocationError::InvalidIdentifier       => Error::Misuse,
+            RevocationError::InvalidProof            => Error::Proof(Statement::NonRevocation),
+            RevocationError::InvalidSuspension       => Error::Misuse,
+            RevocationError::Malformed               => Error::Decode(DecodeError::Malformed),
+            RevocationError::NothingToRollBack       => Error::Misuse,
+            RevocationError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            RevocationError::Revoked                 => Error::Revoked,
+            RevocationError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            RevocationError::VerificationFailure     => Error::Proof(Statement::Signature),
+            RevocationError::WrongEpoch              => Error::Expired,
+            RevocationError::WrongHandle             => Error::Misuse,
+            RevocationError::WrongRegistry           => Error::Misuse,
+            RevocationError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<ChainError> for Error {
+    fn from(source: ChainError) -> Error {
+        match source {
+            ChainError::Credential(e)           => e.into(),
+            ChainError::Empty                   => Error::Misuse,
+            ChainError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            ChainError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            ChainError::StaleEpoch              => Error::Replayed,
+            ChainError::VerificationFailure     => Error::Proof(Statement::Signature),
+            ChainError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<CeremonyError> for Error {
+    fn from(source: CeremonyError) -> Error {
+        match source {
+            CeremonyError::Duplicate               => Error::Misuse,
+            CeremonyError::Incomplete              => Error::Misuse,
+            CeremonyError::InvalidName             => Error::Misuse,
+            CeremonyError::Mismatch                => Error::Protocol,
+            CeremonyError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            CeremonyError::ScalarFormatError       => Error::Decode(DecodeError::ScalarFormat),
+            CeremonyError::UnknownOperator         => Error::Misuse,
+            CeremonyError::VerificationFailure     => Error::Proof(Statement::Signature),
+            CeremonyError::WrongPhase              => Error::Misuse,
+            CeremonyError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<RequestError> for Error {
+    fn from(source: RequestError) -> Error {
+        match source {
+            RequestError::Credential(e)            => e.into(),
+            RequestError::InvalidProof             => Error::Proof(Statement::CredentialAttributes),
+            RequestError::Malformed                => Error::Decode(DecodeError::Malformed),
+            RequestError::NotAnInteger             => Error::Misuse,
+            RequestError::Predicate(e)             => e.into(),
+            RequestError::Refused                  => Error::Policy(PolicyViolation::Rejected),
+            RequestError::ScalarFormatError        => Error::Decode(DecodeError::ScalarFormat),
+            RequestError::Schema(e)                => e.into(),
+            RequestError::UnknownAttribute         => Error::Misuse,
+            RequestError::Unsatisfied              => Error::Policy(PolicyViolation::OutOfRange),
+            RequestError::WrongNumberOfBytes       => Error::Decode(DecodeError::WrongNumberOfBytes),
+            RequestError::WrongNumberOfDisclosures => Error::Protocol,
+            RequestError::WrongSchema              => Error::Misuse,
+        }
+    }
+}
+
+impl From<ReportError> for Error {
+    fn from(source: ReportError) -> Error {
+        match source {
+            ReportError::AlreadyAdmitted    => Error::Replayed,
+            ReportError::Credential(e)      => e.into(),
+            ReportError::Token(e)           => e.into(),
+            ReportError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<VaultError> for Error {
+    fn from(source: VaultError) -> Error {
+        match source {
+            VaultError::Credential(e)      => e.into(),
+            VaultError::InvalidProof       => Error::Proof(Statement::Decryption),
+            VaultError::NoSuchAttribute    => Error::Misuse,
+            VaultError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<EligibilityError> for Error {
+    fn from(source: EligibilityError) -> Error {
+        match source {
+            EligibilityError::Credential(e)      => e.into(),
+            EligibilityError::InvalidProof       => Error::Proof(Statement::Eligibility),
+            EligibilityError::WrongEpoch         => Error::Expired,
+            EligibilityError::WrongHandle        => Error::Misuse,
+            EligibilityError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<DeviceError> for Error {
+    fn from(source: DeviceError) -> Error {
+        match source {
+            DeviceError::Credential(e)           => e.into(),
+            DeviceError::Enrollment              => Error::Proof(Statement::DeviceEnrollment),
+            DeviceError::Issuance                => Error::Proof(Statement::DeviceIssuance),
+            DeviceError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            DeviceError::VerificationFailure     => Error::Proof(Statement::ValidDevice),
+            DeviceError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<RecoveryError> for Error {
+    fn from(source: RecoveryError) -> Error {
+        match source {
+            RecoveryError::Credential(e)           => e.into(),
+            RecoveryError::InvalidDecryption       => Error::Proof(Statement::Decryption),
+            RecoveryError::InvalidSharing          => Error::Proof(Statement::CorrectShare),
+            RecoveryError::InvalidThreshold        => Error::Misuse,
+            RecoveryError::NotAGuardian            => Error::Misuse,
+            RecoveryError::NotEnoughShares         => Error::Protocol,
+            RecoveryError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            RecoveryError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<ReplayError> for Error {
+    fn from(source: ReplayError) -> Error {
+        match source {
+            ReplayError::Credential(e)      => e.into(),
+            ReplayError::UnknownSender      => Error::Decode(DecodeError::Malformed),
+            ReplayError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<BackupError> for Error {
+    fn from(source: BackupError) -> Error {
+        match source {
+            BackupError::Corrupt            => Error::Decode(DecodeError::Malformed),
+            BackupError::Credential(e)      => e.into(),
+            BackupError::InvalidThreshold   => Error::Misuse,
+            BackupError::Recovery(e)        => e.into(),
+            BackupError::Revocation(e)      => e.into(),
+            BackupError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<AttributeError> for Error {
+    fn from(source: AttributeError) -> Error {
+        match source {
+            AttributeError::InvalidCountryCode => Error::Misuse,
+            AttributeError::NotAnEncoding      => Error::Decode(DecodeError::ScalarFormat),
+            AttributeError::OutOfRange         => Error::Misuse,
+            AttributeError::TooLong            => Error::Misuse,
+        }
+    }
+}
+
+impl From<ExportError> for Error {
+    fn from(source: ExportError) -> Error {
+        match source {
+            ExportError::Credential(e)           => e.into(),
+            ExportError::InvalidGenerators       => Error::Misuse,
+            ExportError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            ExportError::VerificationFailure     => Error::Proof(Statement::CommitmentExport),
+            ExportError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<FreshnessError> for Error {
+    fn from(source: FreshnessError) -> Error {
+        match source {
+            FreshnessError::Credential(e)      => e.into(),
+            FreshnessError::FromTheFuture      => Error::Expired,
+            FreshnessError::Stale              => Error::Expired,
+            FreshnessError::WrongNumberOfBytes => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<StreamError> for Error {
+    fn from(source: StreamError) -> Error {
+        match source {
+            StreamError::Credential(e)  => e.into(),
+            StreamError::FrameTooLarge  => Error::Decode(DecodeError::WrongNumberOfBytes),
+            StreamError::Malformed      => Error::Decode(DecodeError::Malformed),
+            StreamError::Refused        => Error::Protocol,
+            StreamError::Truncated      => Error::Decode(DecodeError::MissingData),
+        }
+    }
+}
+
+impl From<PolicyError> for Error {
+    fn from(source: PolicyError) -> Error {
+        match source {
+            PolicyError::InvalidRange            => Error::Misuse,
+            PolicyError::Malformed               => Error::Decode(DecodeError::Malformed),
+            PolicyError::MissingRosterProof      => Error::Protocol,
+            PolicyError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            PolicyError::Request(e)              => e.into(),
+            PolicyError::Revocation(e)           => e.into(),
+            PolicyError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<SelfCheckError> for Error {
+    fn from(source: SelfCheckError) -> Error {
+        match source {
+            SelfCheckError::AcceptedForgery => Error::Storage,
+            SelfCheckError::EpochMismatch   => Error::Storage,
+            SelfCheckError::Expired         => Error::Expired,
+            SelfCheckError::Issuance(e)     => e,
+            SelfCheckError::KeyMismatch     => Error::Storage,
+            SelfCheckError::Presentation(e) => e,
+            SelfCheckError::Verification(e) => e,
+        }
+    }
+}
+
+impl From<DualKeyError> for Error {
+    fn from(source: DualKeyError) -> Error {
+        match source {
+            DualKeyError::Credential(e)           => e.into(),
+            DualKeyError::PointDecompressionError => Error::Decode(DecodeError::PointDecompression),
+            DualKeyError::WrongKey                => Error::Misuse,
+            DualKeyError::WrongNumberOfBytes      => Error::Decode(DecodeError::WrongNumberOfBytes),
+        }
+    }
+}
+
+impl From<SecureChannelError> for Error {
+    fn from(source: SecureChannelError) -> Error {
+        match source {
+            SecureChannelError::Decryption    => Error::Protocol,
+            SecureChannelError::Gate(e)       => e.into(),
+            SecureChannelError::Handshake     => Error::Protocol,
+            SecureChannelError::Malformed     => Error::Decode(DecodeError::Malformed),
+            SecureChannelError::MissingKey    => Error::Misuse,
+            SecureChannelError::TooLarge      => Error::Decode(DecodeError::WrongNumberOfBytes),
+            SecureChannelError::UntrustedPeer => Error::Policy(PolicyViolation::UnknownIssuer),
+            SecureChannelError::WrongState    => Error::Misuse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn verify_ticket(result: Result<(), TicketError>) -> Result<(), Error> {
+        Ok(result?)
+    }
+
+    #[test]
+    fn errors_convert_by_cause() {
+        assert!(verify_ticket(Err(TicketError::OutsideWindow)) == Err(Error::Expired));
+        assert!(verify_ticket(Err(TicketError::AlreadyUsed)) == Err(Error::Replayed));
+        assert!(verify_ticket(Err(TicketError::Credential(CredentialError::MacVerification))) ==
+                Err(Error::Proof(Statement::ValidCredential)));
+        assert!(Error::from(PassError::Predicate(PredicateError::OutOfRange)) ==
+                Error::Policy(PolicyViolation::OutOfRange));
+        assert!(format!("{}", Error::Proof(Statement::ScopedNullifier)) ==
+                "The scoped nullifier proof did not verify");
+    }
+}
@@ -0,0 +1,331 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Export of a credential's hidden attribute as a standard Pedersen
+//! commitment, for use in external proof systems.
+//!
+//! A holder who must prove something this crate cannot express, e.g. a
+//! statement in a Bulletproofs circuit, or a link to a credential from
+//! another system, may export their hidden attribute \\( m \\) as
+//!
+//! \\[
+//!     C = m G + r H
+//! \\]
+//!
+//! under generators \\( G, H \\) of the external system's choosing, along
+//! with a presentation of their credential and a `proofs::commitment_export`
+//! proof that \\( C \\) commits to the same \\( m \\) as the presentation
+//! does.  The holder keeps the `Opening` \\( (m, r) \\) to build the external
+//! statement about \\( C \\), and the verifier, having checked the
+//! presentation and the link, hands \\( C \\) to the external verifier.
+//!
+//! The generators must be ones whose relative discrete log nobody knows,
+//! e.g. from `Generators::hashed()`, or the commitment will bind nothing.
+//! The proof is bound to a caller-chosen `context`, which should name the
+//! external statement, so that an export cannot be replayed to another.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::Credential;
+use credential::CredentialPresentation;
+use credential::SIZEOF_CREDENTIAL_PRESENTATION;
+use credential::VerifiedCredential;
+use errors::CredentialError;
+use errors::Error;
+use errors::ExportError;
+use hashing::Hasher;
+use issuer::Issuer;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use proofs::commitment_export;
+use secret::Secret;
+use user::User;
+
+/// The generators of an external system's Pedersen commitments.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Generators {
+    pub G: RistrettoPoint,
+    pub H: RistrettoPoint,
+}
+
+impl Generators {
+    /// Use the generators `G` and `H` of an external system.
+    ///
+    /// # Errors
+    ///
+    /// * `ExportError::InvalidGenerators` if either generator is the
+    ///   identity, or if they are the same.
+    pub fn new(G: RistrettoPoint, H: RistrettoPoint) -> Result<Generators, Error> {
+        if G == RistrettoPoint::identity() || H == RistrettoPoint::identity() || G == H {
+            return Err(ExportError::InvalidGenerators.into());
+        }
+        Ok(Generators { G, H })
+    }
+
+    /// Derive a pair of generators, of which nobody knows the relative
+    /// discrete log, for a `domain`.
+    pub fn hashed(domain: &[u8]) -> Generators {
+        let mut g = Hasher::new(b"aeonflux export generator G v1");
+        let mut h = Hasher::new(b"aeonflux export generator H v1");
+
+        g.input(domain);
+        h.input(domain);
+
+        Generators { G: g.finalize_point(), H: h.finalize_point() }
+    }
+}
+
+/// The transcript under which an export to some `generators` is proven.
+fn export_transcript(generators: &Generators, context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"AEONFLUX COMMITMENT EXPORT");
+
+    transcript.commit_bytes(b"G", generators.G.compress().as_bytes());
+    transcript.commit_bytes(b"H", generators.H.compress().as_bytes());
+    transcript.commit_bytes(b"context", context);
+    transcript
+}
+
+/// The value and blinding factor of an exported commitment, kept by the
+/// holder to prove statements about it.
+#[derive(Clone, Debug)]
+pub struct Opening {
+    value: Secret<Scalar>,
+    blinding: Secret<Scalar>,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for Opening {
+    fn drop(&mut self) {
+        self.value.clear();
+        self.blinding.clear();
+    }
+}
+
+impl Opening {
+    /// The committed attribute, \\( m \\).
+    pub fn value(&self) -> &Scalar {
+        self.value.expose_secret()
+    }
+
+    /// The commitment's blinding factor, \\( r \\).
+    pub fn blinding(&self) -> &Scalar {
+        self.blinding.expose_secret()
+    }
+}
+
+/// A presentation of a credential, with its hidden attribute exported as a
+/// Pedersen commitment under an external system's generators.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExportedCommitment {
+    pub presentation: CredentialPresentation,
+    /// The commitment \\( C = m G + r H \\).
+    pub commitment: RistrettoPoint,
+    pub proof: commitment_export::Proof,
+}
+
+impl ExportedCommitment {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ExportedCommitment, ExportError> {
+        if bytes.len() < SIZEOF_CREDENTIAL_PRESENTATION + 32 {
+            return Err(ExportError::WrongNumberOfBytes);
+        }
+
+        let presentation = CredentialPresentation::from_bytes(&bytes[..SIZEOF_CREDENTIAL_PRESENTATION])?;
+        let commitment = CompressedRistretto::from_slice(
+            &bytes[SIZEOF_CREDENTIAL_PRESENTATION..SIZEOF_CREDENTIAL_PRESENTATION + 32]).decompress()?;
+        let proof: commitment_export::Proof = match deserialize(&bytes[SIZEOF_CREDENTIAL_PRESENTATION + 32..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(ExportError::WrongNumberOfBytes),
+        };
+
+        Ok(ExportedCommitment { presentation, commitment, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CREDENTIAL_PRESENTATION + 32);
+
+        v.extend(self.presentation.to_bytes());
+        v.extend(self.commitment.compress().as_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ExportedCommitment, "A valid byte sequence representing an ExportedCommitment");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ExportedCommitment);
+impl_try_from_bytes!(ExportedCommitment, ExportError);
+
+impl ExportedCommitment {
+    /// Present the `user`'s credential and export its hidden attribute as a
+    /// commitment under the given `generators`, for the external statement
+    /// named by `context`.
+    ///
+    /// # Errors
+    ///
+    /// * `ExportError::Credential(CredentialError::MissingData)` if the
+    ///   `user` has no credential.
+    ///
+    /// # Returns
+    ///
+    /// The export, for the verifier, and its `Opening`, for the holder.
+    pub fn create<R>(
+        user: &User,
+        generators: &Generators,
+        context: &[u8],
+        rng: &mut R,
+    ) -> Result<(ExportedCommitment, Opening), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &Credential = match user.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+
+        let mut transcript = export_transcript(generators, context);
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+        let presentation: CredentialPresentation = user.show(&nonces, &mut csprng)?;
+
+        let m0: &Scalar = &credential.attributes[0];
+        let r: Ephemeral = Ephemeral::new(&mut csprng);
+        let C: RistrettoPoint = generators.G * m0 + generators.H * r.as_scalar();
+
+        let proof = {
+            let secrets = commitment_export::Secrets {
+                m0: m0,
+                z0: nonces[0].as_witness(),
+                r: r.as_witness(),
+            };
+            let publics = commitment_export::Publics {
+                A: &user.system_parameters.h,
+                P: &presentation.rerandomized_nonce,
+                G: &generators.G,
+                H: &generators.H,
+                Cm0: &presentation.attributes_blinded[0].into(),
+                C: &C,
+            };
+
+            commitment_export::Proof::create(&mut transcript, publics, secrets)
+        };
+        let opening = Opening {
+            value: Secret::new(*m0),
+            blinding: Secret::new(*r.as_scalar()),
+        };
+
+        Ok((ExportedCommitment { presentation, commitment: C, proof }, opening))
+    }
+
+    /// Verify the presentation, and that the exported commitment is to its
+    /// hidden attribute under the given `generators` and `context`.
+    ///
+    /// # Errors
+    ///
+    /// * `ExportError::Credential` if the presentation did not verify.
+    /// * `ExportError::VerificationFailure` if the linking proof did not
+    ///   verify.
+    ///
+    /// # Returns
+    ///
+    /// The verified credential, whose hidden attribute is committed to in
+    /// `self.commitment`.
+    pub fn verify(
+        &self,
+        issuer: &Issuer,
+        generators: &Generators,
+        context: &[u8],
+    ) -> Result<VerifiedCredential, Error>
+    {
+        let verified = issuer.verify(&self.presentation)?;
+
+        if self.presentation.attributes_blinded.len() != 1 {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let mut transcript = export_transcript(generators, context);
+        let publics = commitment_export::Publics {
+            A: &issuer.system_parameters.h,
+            P: &self.presentation.rerandomized_nonce,
+            G: &generators.G,
+            H: &generators.H,
+            Cm0: &self.presentation.attributes_blinded[0].into(),
+            C: &self.commitment,
+        };
+
+        match self.proof.verify(&mut transcript, publics) {
+            Ok(_)  => Ok(verified),
+            Err(_) => Err(ExportError::VerificationFailure.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use test_utils::holder;
+
+    #[test]
+    fn exported_commitments_open_to_the_hidden_attribute() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let attribute = Scalar::random(&mut rng);
+        let user = holder(&issuer, vec![attribute], &mut rng);
+        let generators = Generators::hashed(b"bulletproofs");
+
+        let (export, opening) = ExportedCommitment::create(&user, &generators, b"age over 18", &mut rng).unwrap();
+        let export = ExportedCommitment::from_bytes(&export.to_bytes()).unwrap();
+
+        assert!(export.verify(&issuer, &generators, b"age over 18").is_ok());
+        assert!(*opening.value() == attribute);
+        assert!(export.commitment == generators.G * opening.value() + generators.H * opening.blinding());
+    }
+
+    #[test]
+    fn exports_are_bound_to_their_generators_and_context() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let user = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+        let generators = Generators::hashed(b"bulletproofs");
+
+        let (export, _) = ExportedCommitment::create(&user, &generators, b"age over 18", &mut rng).unwrap();
+
+        assert!(export.verify(&issuer, &generators, b"resident of NZ").err() == Some(ExportError::VerificationFailure.into()));
+        assert!(export.verify(&issuer, &Generators::hashed(b"other"), b"age over 18").is_err());
+        assert!(Generators::new(generators.G, generators.G).err() == Some(ExportError::InvalidGenerators.into()));
+    }
+}
@@ -0,0 +1,450 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Splitting messages into fragments for constrained transports, such as
+//! BLE characteristics and NFC APDUs, and reassembling them.
+//!
+//! A presentation is several times larger than the 20 bytes a BLE write
+//! carries by default, or the 255 bytes of a short APDU, so a message (e.g.
+//! a `GateMessage`, or a `secure_channel` frame) is split into `Fragment`s
+//! of at most the transport's unit.  Each fragment carries:
+//!
+//! * a 2-byte message identifier, which is a truncated SHA-512 digest of the
+//!   whole message, and tells fragments of different messages apart,
+//! * its index and the total number of fragments, and
+//! * a CRC-16 over the fragment, to detect corruption.
+//!
+//! The header and checksum are kept to 8 bytes, so that even a 20-byte unit
+//! carries 12 bytes of message.  Since two messages share an identifier one
+//! time in 65536, the message is followed, once only, by a 16-byte SHA-512
+//! digest of it, against which the reassembled message is checked.
+//!
+//! Messages are split and reassembled by `chunking`, as for `qr`, whose
+//! 17 bytes of framing would leave only 3 of a 20-byte unit.  Fragments may
+//! arrive in any order and any number of times.  A transfer
+//! which was interrupted, e.g. because the holder's phone moved out of
+//! range, may be resumed: the receiver keeps its `Reassembler`, and sends
+//! the `Resume` it gives, which is small enough for any unit, and the sender
+//! sends again only the fragments from the first one missing.
+//!
+//! # Wire format
+//!
+//! ```text
+//! message:  message || digest (16)
+//! fragment: message id (2) || index (2, LE) || total (2, LE) || data || crc16 (2, LE)
+//! resume:   message id (2) || from (2, LE) || crc16 (2, LE)
+//! ```
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use chunking;
+use chunking::Rejection;
+use chunking::SIZEOF_POSITION;
+use errors::Error;
+use errors::FragmentError;
+
+/// The length of a fragment's header in bytes.
+pub const SIZEOF_FRAGMENT_HEADER: usize = 2 + SIZEOF_POSITION;
+
+/// The length of a fragment's trailing checksum in bytes.
+pub const SIZEOF_FRAGMENT_CHECKSUM: usize = 2;
+
+/// The length of the digest which follows a message, in bytes.
+pub const SIZEOF_MESSAGE_DIGEST: usize = 16;
+
+/// The length of an encoded `Resume` in bytes.
+pub const SIZEOF_RESUME: usize = 2 + 2 + SIZEOF_FRAGMENT_CHECKSUM;
+
+/// The maximum number of fragments a message may be split into.
+pub const MAXIMUM_FRAGMENTS: usize = chunking::MAXIMUM_PIECES;
+
+/// The payload of a BLE write or notification at the default ATT MTU of 23
+/// bytes, less the 3-byte ATT header.
+pub const BLE_DEFAULT_UNIT: usize = 20;
+
+/// The longest data field of a short NFC command APDU.
+pub const NFC_SHORT_APDU_UNIT: usize = 255;
+
+/// Compute the CRC-16/CCITT-FALSE of some `bytes`.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+
+    for byte in bytes.iter() {
+        crc ^= (*byte as u16) << 8;
+
+        for _ in 0..8 {
+            let mask: u16 = (!(crc >> 15)).wrapping_add(1);
+
+            crc = (crc << 1) ^ (0x1021 & mask);
+        }
+    }
+    crc
+}
+
+/// Compute the digest which follows a `message`.
+fn message_digest(message: &[u8]) -> [u8; SIZEOF_MESSAGE_DIGEST] {
+    let mut digest = [0u8; SIZEOF_MESSAGE_DIGEST];
+
+    digest.copy_from_slice(&Sha512::digest(message)[..SIZEOF_MESSAGE_DIGEST]);
+    digest
+}
+
+/// Compute the 2-byte identifier of a `message`.
+fn message_id(message: &[u8]) -> [u8; 2] {
+    let digest = message_digest(message);
+
+    [digest[0], digest[1]]
+}
+
+impl From<Rejection> for FragmentError {
+    fn from(source: Rejection) -> FragmentError {
+        match source {
+            Rejection::InvalidIndex      => FragmentError::InvalidIndex,
+            Rejection::MismatchedMessage => FragmentError::MismatchedMessage,
+        }
+    }
+}
+
+/// Append the CRC-16 of `v` to it.
+fn push_checksum(v: &mut Vec<u8>) {
+    let checksum: u16 = crc16(v);
+
+    v.push(checksum as u8);
+    v.push((checksum >> 8) as u8);
+}
+
+/// Check and strip the trailing CRC-16 of some `bytes` at least `minimum`
+/// bytes long, including the checksum.
+fn check_checksum(bytes: &[u8], minimum: usize) -> Result<&[u8], FragmentError> {
+    if bytes.len() < minimum {
+        return Err(FragmentError::WrongNumberOfBytes);
+    }
+
+    let length: usize = bytes.len() - SIZEOF_FRAGMENT_CHECKSUM;
+    let checksum: u16 = (bytes[length] as u16) | ((bytes[length + 1] as u16) << 8);
+
+    if crc16(&bytes[..length]) != checksum {
+        return Err(FragmentError::ChecksumMismatch);
+    }
+    Ok(&bytes[..length])
+}
+
+/// One transport-unit-sized piece of a message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fragment {
+    pub message_id: [u8; 2],
+    pub index: u16,
+    pub total: u16,
+    pub data: Vec<u8>,
+}
+
+impl Fragment {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Fragment, FragmentError> {
+        let bytes: &[u8] = check_checksum(bytes, SIZEOF_FRAGMENT_HEADER + SIZEOF_FRAGMENT_CHECKSUM)?;
+
+        let (index, total) = chunking::read_position(&bytes[2..])?;
+
+        Ok(Fragment {
+            message_id: [bytes[0], bytes[1]],
+            index,
+            total,
+            data: bytes[SIZEOF_FRAGMENT_HEADER..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_FRAGMENT_HEADER + self.data.len() + SIZEOF_FRAGMENT_CHECKSUM);
+
+        v.extend(self.message_id.iter());
+        chunking::push_position(&mut v, self.index, self.total);
+        v.extend(self.data.iter());
+
+        push_checksum(&mut v);
+        v
+    }
+}
+
+impl_try_from_bytes!(Fragment, FragmentError);
+
+/// A receiver's request to continue an interrupted transfer from the
+/// fragment at index `from`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Resume {
+    pub message_id: [u8; 2],
+    pub from: u16,
+}
+
+impl Resume {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Resume, FragmentError> {
+        if bytes.len() != SIZEOF_RESUME {
+            return Err(FragmentError::WrongNumberOfBytes);
+        }
+
+        let bytes: &[u8] = check_checksum(bytes, SIZEOF_RESUME)?;
+
+        Ok(Resume {
+            message_id: [bytes[0], bytes[1]],
+            from: (bytes[2] as u16) | ((bytes[3] as u16) << 8),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_RESUME);
+
+        v.extend(self.message_id.iter());
+        v.push(self.from as u8);
+        v.push((self.from >> 8) as u8);
+
+        push_checksum(&mut v);
+        v
+    }
+}
+
+impl_try_from_bytes!(Resume, FragmentError);
+
+/// Split a `message` into fragments of at most `unit` bytes each, once
+/// encoded with `Fragment::to_bytes()`, e.g. `BLE_DEFAULT_UNIT`, or the
+/// negotiated ATT MTU less 3.
+///
+/// # Errors
+///
+/// * `FragmentError::WrongNumberOfBytes` if the `unit` leaves no room for
+///   any data after the fragment header and checksum.
+/// * `FragmentError::TooManyFragments` if the message would need more than
+///   `MAXIMUM_FRAGMENTS` fragments.
+pub fn split(message: &[u8], unit: usize) -> Result<Vec<Fragment>, Error> {
+    if unit <= SIZEOF_FRAGMENT_HEADER + SIZEOF_FRAGMENT_CHECKSUM {
+        return Err(FragmentError::WrongNumberOfBytes.into());
+    }
+
+    let data_size: usize = unit - SIZEOF_FRAGMENT_HEADER - SIZEOF_FRAGMENT_CHECKSUM;
+    let mut framed: Vec<u8> = Vec::with_capacity(message.len() + SIZEOF_MESSAGE_DIGEST);
+
+    framed.extend(message.iter());
+    framed.extend(message_digest(message).iter());
+
+    let pieces: Vec<&[u8]> = chunking::split(&framed, data_size).ok_or(FragmentError::TooManyFragments)?;
+    let total: u16 = pieces.len() as u16;
+    let id: [u8; 2] = message_id(message);
+
+    Ok(pieces.iter().enumerate().map(|(index, data)| {
+        Fragment { message_id: id, index: index as u16, total: total, data: data.to_vec() }
+    }).collect())
+}
+
+/// The `fragments` of a message which remain to be sent after a receiver
+/// asked to `resume` its transfer.
+///
+/// # Errors
+///
+/// * `FragmentError::MismatchedMessage` if the receiver was receiving some
+///   other message, or asked for a fragment this message does not have.
+pub fn resume<'a>(fragments: &'a [Fragment], resume: &Resume) -> Result<&'a [Fragment], Error> {
+    let from: usize = resume.from as usize;
+
+    match fragments.first() {
+        Some(first) if first.message_id == resume.message_id && from < fragments.len() => Ok(&fragments[from..]),
+        _ => Err(FragmentError::MismatchedMessage.into()),
+    }
+}
+
+/// Collects received fragments, in any order, until a message is complete.
+#[derive(Clone, Debug, Default)]
+pub struct Reassembler(chunking::Reassembler<[u8; 2]>);
+
+impl Reassembler {
+    pub fn new() -> Reassembler {
+        Reassembler::default()
+    }
+
+    /// The number of distinct fragments received so far.
+    pub fn received(&self) -> usize {
+        self.0.received()
+    }
+
+    /// The total number of fragments in the message, once any has been
+    /// received.
+    pub fn total(&self) -> Option<usize> {
+        self.0.total()
+    }
+
+    /// The indices of the fragments not yet received, once any has been.
+    pub fn missing(&self) -> Vec<u16> {
+        self.0.missing()
+    }
+
+    /// Where the sender should resume an interrupted transfer, once any
+    /// fragment has been received.
+    pub fn resume(&self) -> Option<Resume> {
+        let id: [u8; 2] = self.0.message_id()?;
+        let from: u16 = *self.0.missing().first()?;
+
+        Some(Resume { message_id: id, from: from })
+    }
+
+    /// Forget all fragments received so far, e.g. to receive a different
+    /// message.
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    /// Add a received `fragment`.  Fragments which were already received
+    /// are ignored.
+    ///
+    /// # Errors
+    ///
+    /// * `FragmentError::MismatchedMessage` if the fragment belongs to a
+    ///   different message than those already received.
+    /// * `FragmentError::InvalidIndex` if the fragment's index is not below
+    ///   its total.
+    /// * `FragmentError::MessageDigestMismatch` if every fragment was
+    ///   received but the reassembled message was corrupt, in which case the
+    ///   reassembler is reset.
+    ///
+    /// # Returns
+    ///
+    /// The reassembled message, if this was its last missing fragment.
+    pub fn push(&mut self, fragment: Fragment) -> Result<Option<Vec<u8>>, Error> {
+        let mut message: Vec<u8> = match self.0.push(fragment.message_id, fragment.index,
+                                                     fragment.total, fragment.data) {
+            Ok(Some(x)) => x,
+            Ok(None)    => return Ok(None),
+            Err(e)      => return Err(FragmentError::from(e).into()),
+        };
+
+        if message.len() < SIZEOF_MESSAGE_DIGEST {
+            return Err(FragmentError::MessageDigestMismatch.into());
+        }
+
+        let length: usize = message.len() - SIZEOF_MESSAGE_DIGEST;
+
+        if message_digest(&message[..length])[..] != message[length..] {
+            return Err(FragmentError::MessageDigestMismatch.into());
+        }
+        message.truncate(length);
+
+        Ok(Some(message))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    #[test]
+    fn crc16_check_value() {
+        assert!(crc16(b"123456789") == 0x29b1);
+    }
+
+    #[test]
+    fn interrupted_ble_transfers_resume() {
+        let m = message(700);
+        let fragments = split(&m, BLE_DEFAULT_UNIT).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        assert!(fragments.len() == 60);
+
+        for fragment in fragments.iter() {
+            assert!(fragment.to_bytes().len() <= BLE_DEFAULT_UNIT);
+        }
+
+        // The connection drops after the first twenty fragments, with the
+        // twelfth corrupted in flight.
+        for (i, fragment) in fragments[..20].iter().enumerate() {
+            let mut bytes = fragment.to_bytes();
+
+            if i == 12 {
+                bytes[7] ^= 0x01;
+
+                assert!(Fragment::from_bytes(&bytes) == Err(FragmentError::ChecksumMismatch));
+            } else {
+                assert!(reassembler.push(Fragment::from_bytes(&bytes).unwrap()).unwrap().is_none());
+            }
+        }
+
+        assert!(reassembler.missing().len() == 41);
+
+        let request = Resume::from_bytes(&reassembler.resume().unwrap().to_bytes()).unwrap();
+
+        assert!(request.from == 12);
+
+        let remaining = resume(&fragments, &request).unwrap();
+        let mut result = None;
+
+        for fragment in remaining.iter() {
+            result = reassembler.push(Fragment::from_bytes(&fragment.to_bytes()).unwrap()).unwrap();
+        }
+
+        assert!(result == Some(m));
+        assert!(reassembler.total().is_none());
+    }
+
+    #[test]
+    fn fragments_of_other_messages_are_refused() {
+        let first = split(&message(300), NFC_SHORT_APDU_UNIT).unwrap();
+        let second = split(&message(301), NFC_SHORT_APDU_UNIT).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        assert!(first.len() == 2);
+
+        reassembler.push(first[0].clone()).unwrap();
+
+        assert!(reassembler.push(second[1].clone()) == Err(FragmentError::MismatchedMessage.into()));
+        assert!(resume(&second, &reassembler.resume().unwrap()) == Err(FragmentError::MismatchedMessage.into()));
+        assert!(split(&message(10), SIZEOF_FRAGMENT_HEADER + SIZEOF_FRAGMENT_CHECKSUM)
+                == Err(FragmentError::WrongNumberOfBytes.into()));
+    }
+
+    #[test]
+    fn out_of_range_indices_are_refused() {
+        let mut fragment = split(&message(10), BLE_DEFAULT_UNIT).unwrap()[0].clone();
+
+        fragment.index = fragment.total;
+
+        assert!(Fragment::from_bytes(&fragment.to_bytes()) == Err(FragmentError::InvalidIndex));
+        assert!(Reassembler::new().push(fragment) == Err(FragmentError::InvalidIndex.into()));
+    }
+
+    #[test]
+    fn corrupted_messages_do_not_reassemble() {
+        let m = message(100);
+        let mut fragments = split(&m, NFC_SHORT_APDU_UNIT).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        // A fragment altered before its checksum was computed, or taken from
+        // another message with the same identifier, is only caught by the
+        // message's digest.
+        fragments[0].data[0] ^= 0x01;
+
+        let bytes = fragments[0].to_bytes();
+
+        assert!(reassembler.push(Fragment::from_bytes(&bytes).unwrap()) ==
+                Err(FragmentError::MessageDigestMismatch.into()));
+        assert!(reassembler.total().is_none());
+
+        // Empty messages still carry their digest.
+        let empty = split(&[], BLE_DEFAULT_UNIT).unwrap();
+
+        assert!(empty.len() == 2);
+
+        reassembler.push(empty[1].clone()).unwrap();
+
+        assert!(reassembler.push(empty[0].clone()) == Ok(Some(Vec::new())));
+    }
+}
@@ -0,0 +1,231 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Presentations which rot after a verifier-chosen age.
+//!
+//! A presentation made with `show()` is bound, through its proof's
+//! transcript, to the time at which the holder made it, so that its
+//! timestamp cannot be changed without invalidating it.  A verifier then
+//! accepts it only within its `FreshnessPolicy`: no older than `max_age`,
+//! and no further in the future than `max_skew`, which tolerates holders
+//! whose clocks run fast.  A presentation which is stolen but never
+//! replayed in time therefore becomes useless, even to a verifier which
+//! keeps no nullifiers.
+//!
+//! A holder whose clock runs fast gains at most `max_skew` of lifetime.
+//! Skew the other way, on the verifier's side, is clawed back by reading
+//! the time through a `clock::MonotonicClock`: if the verifier's clock is
+//! stepped backwards, e.g. by an NTP correction, the time it reports stands
+//! still instead, so that presentations which have already rotted are not
+//! revived.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use clock::Clock;
+use credential::CredentialPresentation;
+use credential::VerifiedCredential;
+use encoding::u64_to_bytes;
+use errors::Error;
+use errors::FreshnessError;
+use issuer::Issuer;
+use nonces::Nonces;
+use user::User;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// Bind a `timestamp`, if any, into a presentation's `transcript`, so that
+/// a presentation verifies only at the time it was dated with.
+/// Presentations without a timestamp leave their transcripts as they were.
+pub(crate) fn bind_timestamp(transcript: &mut Transcript, timestamp: Option<u64>) {
+    if let Some(t) = timestamp {
+        transcript.commit_bytes(b"timestamp", &u64_to_bytes(t));
+    }
+}
+
+/// How old, and how far in the future, a verifier accepts presentations.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct FreshnessPolicy {
+    /// The greatest age, in seconds, of an acceptable presentation.
+    pub max_age: u64,
+    /// How far, in seconds, a holder's clock may run ahead of ours.
+    pub max_skew: u64,
+}
+
+impl FreshnessPolicy {
+    pub fn new(max_age: u64, max_skew: u64) -> FreshnessPolicy {
+        FreshnessPolicy { max_age, max_skew }
+    }
+
+    /// Check that a presentation dated `timestamp` is acceptable at `now`.
+    ///
+    /// # Errors
+    ///
+    /// * `FreshnessError::FromTheFuture` if the `timestamp` is more than
+    ///   `max_skew` ahead of `now`.
+    /// * `FreshnessError::Stale` if it is more than `max_age` behind.
+    pub fn check(&self, timestamp: u64, now: u64) -> Result<(), Error> {
+        if timestamp > now.saturating_add(self.max_skew) {
+            return Err(FreshnessError::FromTheFuture.into());
+        }
+        if now > timestamp.saturating_add(self.max_age) {
+            return Err(FreshnessError::Stale.into());
+        }
+        Ok(())
+    }
+}
+
+/// A presentation of a credential, bound to the time it was made.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FreshPresentation {
+    /// When the presentation was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub presentation: CredentialPresentation,
+}
+
+impl FreshPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<FreshPresentation, FreshnessError> {
+        if bytes.len() < 8 {
+            return Err(FreshnessError::WrongNumberOfBytes);
+        }
+
+        let timestamp: u64 = read_u64(&bytes[..8]);
+        let presentation = CredentialPresentation::from_bytes(&bytes[8..])?;
+
+        Ok(FreshPresentation { timestamp, presentation })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(u64_to_bytes(self.timestamp).iter());
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(FreshPresentation, "A valid byte sequence representing a FreshPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(FreshPresentation);
+impl_try_from_bytes!(FreshPresentation, FreshnessError);
+
+/// Present the `user`'s credential, dated with the time on their `clock`.
+pub fn show<C, R>(
+    user: &User,
+    clock: &C,
+    nonces: &Nonces,
+    rng: &mut R,
+) -> Result<FreshPresentation, Error>
+where
+    C: Clock,
+    R: RngCore + CryptoRng,
+{
+    let timestamp: u64 = clock.now();
+    let presentation = user.show_at(timestamp, nonces, rng)?;
+
+    Ok(FreshPresentation { timestamp, presentation })
+}
+
+/// Verify a `presentation`, and that it is fresh under a `policy` at the
+/// time on our `clock`.
+///
+/// The age is checked before the proof, so that stale presentations are
+/// rejected cheaply.
+pub fn verify<C>(
+    issuer: &Issuer,
+    policy: &FreshnessPolicy,
+    clock: &C,
+    presentation: &FreshPresentation,
+) -> Result<VerifiedCredential, Error>
+where
+    C: Clock,
+{
+    policy.check(presentation.timestamp, clock.now())?;
+
+    Ok(issuer.verify_at(&presentation.presentation, presentation.timestamp)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use clock::FixedClock;
+    use clock::MonotonicClock;
+    use errors::CredentialError;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    #[test]
+    fn presentations_rot_after_their_maximum_age() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let policy = FreshnessPolicy::new(60, 5);
+        let holder = FixedClock::new(1000);
+        let verifier = FixedClock::new(1030);
+        let monotonic = MonotonicClock::new(&verifier);
+
+        let shown = show(&user, &holder, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let shown = FreshPresentation::from_bytes(&shown.to_bytes()).unwrap();
+
+        assert!(verify(&issuer, &policy, &monotonic, &shown).is_ok());
+
+        // Stepping the verifier's clock back does not revive it once stale.
+        verifier.set(1061);
+        assert!(verify(&issuer, &policy, &monotonic, &shown).err() == Some(FreshnessError::Stale.into()));
+        verifier.set(1030);
+        assert!(verify(&issuer, &policy, &monotonic, &shown).err() == Some(FreshnessError::Stale.into()));
+
+        // The timestamp is bound to the proof.
+        let mut redated = shown.clone();
+
+        redated.timestamp = 1100;
+        verifier.set(1100);
+
+        assert!(verify(&issuer, &policy, &verifier, &redated).err() ==
+                Some(FreshnessError::Credential(CredentialError::MacVerification)));
+    }
+
+    #[test]
+    fn presentations_are_accepted_within_the_window() {
+        let policy = FreshnessPolicy::new(60, 5);
+
+        assert!(policy.check(1005, 1000).is_ok());
+        assert!(policy.check(1006, 1000) == Err(FreshnessError::FromTheFuture.into()));
+        assert!(policy.check(1000, 1060).is_ok());
+        assert!(policy.check(1000, 1061) == Err(FreshnessError::Stale.into()));
+        assert!(policy.check(u64::max_value(), u64::max_value()).is_ok());
+    }
+}
@@ -0,0 +1,922 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Session state machines for both sides of credential issuance and
+//! presentation.
+//!
+//! A `GateClient` wraps a `User` and a `GateServer` wraps an `Issuer`.  Each
+//! produces and consumes typed `GateMessage`s, and refuses any message or
+//! action which is not allowed in its current state.  All messages have a
+//! canonical byte encoding, so integrators need only move bytes between the
+//! two.
+//!
+//! A server opened `with_reports()` also accepts `ReportablePresentation`s,
+//! and answers those it admits with a `ReportToken` rather than a
+//! `Verdict(true)`, as described in `report`.  A server opened
+//! `with_metrics()` reports the outcome and duration of its request, as
+//! described in `metrics`.
+//!
+//! Neither side reads a clock.  Instead, every method which may start,
+//! advance, or expire a session takes the current time, `now`, in seconds
+//! since some epoch agreed upon by the caller.
+//!
+//! With the `secure-channel` feature, `secure_channel` carries these
+//! messages over a Noise session, for gates without TLS.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "secure-channel")]
+pub mod secure_channel;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use credential::RevealedAttribute;
+use credential::VerifiedCredential;
+use errors::Error;
+use errors::GateError;
+use errors::ReportError;
+use issuer::Issuer;
+use metrics::Metrics;
+use metrics::NoMetrics;
+use metrics::Operation;
+use metrics::observe;
+use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use rate_limit::NullifierStore;
+use report::PendingReport;
+use report::ReportDesk;
+use report::ReportRequest;
+use report::ReportToken;
+use tokens::TokenResponse;
+use user::User;
+
+/// The default number of seconds to wait for a response from the peer.
+pub const DEFAULT_GATE_TIMEOUT: u64 = 60;
+
+/// Configuration shared by `GateClient`s and `GateServer`s.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GateConfig {
+    /// The number of seconds after which a session awaiting a message expires.
+    pub timeout: u64,
+}
+
+impl Default for GateConfig {
+    fn default() -> GateConfig {
+        GateConfig { timeout: DEFAULT_GATE_TIMEOUT }
+    }
+}
+
+/// The type byte which prefixes every serialised `GateMessage`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum GateMessageType {
+    IssuanceRequest = 1,
+    Issuance = 2,
+    BlindIssuanceRequest = 3,
+    BlindIssuance = 4,
+    Presentation = 5,
+    Verdict = 6,
+    ReportablePresentation = 7,
+    ReportToken = 8,
+}
+
+impl GateMessageType {
+    pub fn from_byte(byte: u8) -> Result<GateMessageType, GateError> {
+        match byte {
+            1 => Ok(GateMessageType::IssuanceRequest),
+            2 => Ok(GateMessageType::Issuance),
+            3 => Ok(GateMessageType::BlindIssuanceRequest),
+            4 => Ok(GateMessageType::BlindIssuance),
+            5 => Ok(GateMessageType::Presentation),
+            6 => Ok(GateMessageType::Verdict),
+            7 => Ok(GateMessageType::ReportablePresentation),
+            8 => Ok(GateMessageType::ReportToken),
+            _ => Err(GateError::UnknownMessageType),
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A message sent between a `GateClient` and a `GateServer`.
+#[derive(Debug, Eq, PartialEq)]
+pub enum GateMessage {
+    /// Client to server: a request for a credential over revealed attributes.
+    IssuanceRequest(CredentialRequest),
+    /// Server to client: a credential over revealed attributes.
+    Issuance(CredentialIssuance),
+    /// Client to server: a request for a credential over encrypted attributes.
+    BlindIssuanceRequest(CredentialBlindRequest),
+    /// Server to client: a credential over encrypted attributes.
+    BlindIssuance(CredentialBlindIssuance),
+    /// Client to server: a presentation of a credential.
+    Presentation(CredentialPresentation),
+    /// Server to client: whether the presentation was accepted.
+    Verdict(bool),
+    /// Client to server: a presentation at an entry, with a request for a
+    /// report token.
+    ReportablePresentation(ReportRequest),
+    /// Server to client: the presentation was accepted, and a report token
+    /// was signed.
+    ReportToken(TokenResponse),
+}
+
+impl GateMessage {
+    pub fn from_bytes(bytes: &[u8]) -> Result<GateMessage, GateError> {
+        if bytes.len() < 1 {
+            return Err(GateError::UnknownMessageType);
+        }
+
+        let payload: &[u8] = &bytes[1..];
+
+        Ok(match GateMessageType::from_byte(bytes[0])? {
+            GateMessageType::IssuanceRequest
+                => GateMessage::IssuanceRequest(CredentialRequest::from_bytes(payload)?),
+            GateMessageType::Issuance
+                => GateMessage::Issuance(CredentialIssuance::from_bytes(payload)?),
+            GateMessageType::BlindIssuanceRequest
+                => GateMessage::BlindIssuanceRequest(CredentialBlindRequest::from_bytes(payload)?),
+            GateMessageType::BlindIssuance
+                => GateMessage::BlindIssuance(CredentialBlindIssuance::from_bytes(payload)?),
+            GateMessageType::Presentation
+                => GateMessage::Presentation(CredentialPresentation::from_bytes(payload)?),
+            GateMessageType::Verdict => {
+                if payload.len() != 1 || payload[0] > 1 {
+                    return Err(GateError::UnexpectedMessage);
+                }
+                GateMessage::Verdict(payload[0] == 1)
+            },
+            GateMessageType::ReportablePresentation
+                => GateMessage::ReportablePresentation(ReportRequest::from_bytes(payload)?),
+            GateMessageType::ReportToken
+                => GateMessage::ReportToken(TokenResponse::from_bytes(payload).map_err(ReportError::from)?),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.push(self.message_type().to_byte());
+
+        match *self {
+            GateMessage::IssuanceRequest(ref x)        => v.extend(x.to_bytes()),
+            GateMessage::Issuance(ref x)               => v.extend(x.to_bytes()),
+            GateMessage::BlindIssuanceRequest(ref x)   => v.extend(x.to_bytes()),
+            GateMessage::BlindIssuance(ref x)          => v.extend(x.to_bytes()),
+            GateMessage::Presentation(ref x)           => v.extend(x.to_bytes()),
+            GateMessage::Verdict(x)                    => v.push(x as u8),
+            GateMessage::ReportablePresentation(ref x) => v.extend(x.to_bytes()),
+            GateMessage::ReportToken(ref x)            => v.extend(x.to_bytes()),
+        }
+
+        v
+    }
+
+    /// The `GateMessageType` of this message.
+    pub fn message_type(&self) -> GateMessageType {
+        match *self {
+            GateMessage::IssuanceRequest(_)        => GateMessageType::IssuanceRequest,
+            GateMessage::Issuance(_)               => GateMessageType::Issuance,
+            GateMessage::BlindIssuanceRequest(_)   => GateMessageType::BlindIssuanceRequest,
+            GateMessage::BlindIssuance(_)          => GateMessageType::BlindIssuance,
+            GateMessage::Presentation(_)           => GateMessageType::Presentation,
+            GateMessage::Verdict(_)                => GateMessageType::Verdict,
+            GateMessage::ReportablePresentation(_) => GateMessageType::ReportablePresentation,
+            GateMessage::ReportToken(_)            => GateMessageType::ReportToken,
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(GateMessage, "A valid byte sequence representing a GateMessage");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(GateMessage);
+impl_try_from_bytes!(GateMessage, GateError);
+
+/// The states of a `GateClient`.
+///
+/// The allowed transitions are:
+///
+/// * `Idle` to `AwaitingIssuance`, by `start_issuance()`,
+/// * `Idle` to `AwaitingBlindIssuance`, by `start_blind_issuance()`,
+/// * `AwaitingIssuance` to `Holding`, upon receiving an `Issuance`,
+/// * `AwaitingBlindIssuance` to `Holding`, upon receiving a `BlindIssuance`,
+/// * `Holding` or `Admitted` to `AwaitingVerdict`, by `start_presentation()`
+///   or `start_reportable_presentation()`, and
+/// * `AwaitingVerdict` to `Admitted`, upon receiving a `Verdict(true)`, or a
+///   `ReportToken` after `start_reportable_presentation()`.
+///
+/// Any `Awaiting*` state moves to `Failed` upon an error, a rejection, or a
+/// timeout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClientState {
+    /// The client holds no credential and has no session in progress.
+    Idle,
+    /// The client sent an `IssuanceRequest` at time `since`.
+    AwaitingIssuance { since: u64 },
+    /// The client sent a `BlindIssuanceRequest` at time `since`.
+    AwaitingBlindIssuance { since: u64 },
+    /// The client holds a credential and has no session in progress.
+    Holding,
+    /// The client sent a `Presentation` at time `since`.
+    AwaitingVerdict { since: u64 },
+    /// The client's last presentation was accepted.
+    Admitted,
+    /// The client's last session failed.
+    Failed(GateError),
+}
+
+/// The client side of a gate: a `User` obtaining and presenting credentials.
+pub struct GateClient {
+    pub config: GateConfig,
+    user: User,
+    state: ClientState,
+    /// The plaintext attributes of a pending blind issuance.
+    pending_attributes: Vec<RevealedAttribute>,
+    /// A pending report token, and the report key it will be signed under.
+    pending_report: Option<(PendingReport, RistrettoPoint)>,
+    /// Report tokens received, and not yet taken.
+    report_tokens: Vec<ReportToken>,
+}
+
+impl GateClient {
+    /// Create a new `GateClient` for a `user`.
+    ///
+    /// The client starts in `ClientState::Holding` if the `user` already has a
+    /// credential, and in `ClientState::Idle` otherwise.
+    pub fn new(user: User, config: GateConfig) -> GateClient {
+        let state = match user.credential {
+            Some(_) => ClientState::Holding,
+            None    => ClientState::Idle,
+        };
+
+        GateClient {
+            config,
+            user,
+            state,
+            pending_attributes: Vec::new(),
+            pending_report: None,
+            report_tokens: Vec::new(),
+        }
+    }
+
+    /// The current state of this client.
+    pub fn state(&self) -> ClientState {
+        self.state
+    }
+
+    /// The `User` wrapped by this client.
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// Take the report tokens received since this was last called.
+    pub fn take_report_tokens(&mut self) -> Vec<ReportToken> {
+        ::core::mem::replace(&mut self.report_tokens, Vec::new())
+    }
+
+    /// Consume this client, returning the wrapped `User`.
+    pub fn into_user(self) -> User {
+        self.user
+    }
+
+    /// Fail the session if we have been awaiting a response for too long.
+    ///
+    /// # Returns
+    ///
+    /// The state of this client after checking for expiry.
+    pub fn poll_timeout(&mut self, now: u64) -> ClientState {
+        let since: Option<u64> = match self.state {
+            ClientState::AwaitingIssuance { since }      => Some(since),
+            ClientState::AwaitingBlindIssuance { since } => Some(since),
+            ClientState::AwaitingVerdict { since }       => Some(since),
+            _                                            => None,
+        };
+
+        if let Some(since) = since {
+            if now.saturating_sub(since) > self.config.timeout {
+                self.fail(GateError::Timeout);
+            }
+        }
+        self.state
+    }
+
+    pub(crate) fn fail(&mut self, error: GateError) -> GateError {
+        self.state = ClientState::Failed(error);
+        self.pending_attributes.clear();
+        self.pending_report = None;
+
+        error
+    }
+
+    /// Whether a new issuance session may be started from the current state.
+    fn may_start_issuance(&self) -> bool {
+        match self.state {
+            ClientState::Idle | ClientState::Failed(_) => self.user.credential.is_none(),
+            _                                          => false,
+        }
+    }
+
+    /// Request a credential over revealed `attributes`.
+    ///
+    /// # Errors
+    ///
+    /// * `GateError::WrongState` unless the client is `Idle`, or `Failed`
+    ///   without holding a credential.
+    pub fn start_issuance(
+        &mut self,
+        attributes: Vec<RevealedAttribute>,
+        now: u64,
+    ) -> Result<GateMessage, Error>
+    {
+        if !self.may_start_issuance() {
+            return Err(GateError::WrongState.into());
+        }
+
+        let request: CredentialRequest = self.user.obtain(attributes);
+
+        self.state = ClientState::AwaitingIssuance { since: now };
+
+        Ok(GateMessage::IssuanceRequest(request))
+    }
+
+    /// Request a credential over encrypted `attributes`.
+    ///
+    /// # Errors
+    ///
+    /// * `GateError::WrongState` unless the client is `Idle`, or `Failed`
+    ///   without holding a credential.
+    /// * Any error from `User::blind_obtain()`.
+    pub fn start_blind_issuance<R>(
+        &mut self,
+        attributes: Vec<RevealedAttribute>,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<GateMessage, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if !self.may_start_issuance() {
+            return Err(GateError::WrongState.into());
+        }
+
+        let request: CredentialBlindRequest = self.user.blind_obtain(&attributes, rng)?;
+
+        self.pending_attributes = attributes;
+        self.state = ClientState::AwaitingBlindIssuance { since: now };
+
+        Ok(GateMessage::BlindIssuanceRequest(request))
+    }
+
+    /// Present our credential.
+    ///
+    /// # Errors
+    ///
+    /// * `GateError::WrongState` unless the client is `Holding` or `Admitted`,
+    ///   or `Failed` while holding a credential.
+    /// * Any error from `User::show()`.
+    pub fn start_presentation<R>(
+        &mut self,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<GateMessage, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        match self.state {
+            ClientState::Holding | ClientState::Admitted => (),
+            ClientState::Failed(_) if self.user.credential.is_some() => (),
+            _ => return Err(GateError::WrongState.into()),
+        }
+
+        let nonces: Nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+        let presentation: CredentialPresentation = self.user.show(&nonces, rng)?;
+
+        self.state = ClientState::AwaitingVerdict { since: now };
+
+        Ok(GateMessage::Presentation(presentation))
+    }
+
+    /// Present our credential at an `entry`, and ask for a report token to be
+    /// signed under the entry's report key, `public`.
+    ///
+    /// # Errors
+    ///
+    /// As for `start_presentation()`, and any error from
+    /// `ReportRequest::create()`.
+    pub fn start_reportable_presentation<R>(
+        &mut self,
+        entry: &[u8],
+        public: &RistrettoPoint,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<GateMessage, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        match self.state {
+            ClientState::Holding | ClientState::Admitted => (),
+            ClientState::Failed(_) if self.user.credential.is_some() => (),
+            _ => return Err(GateError::WrongState.into()),
+        }
+
+        let (request, pending) = ReportRequest::create(&self.user, entry, rng)?;
+
+        self.pending_report = Some((pending, *public));
+        self.state = ClientState::AwaitingVerdict { since: now };
+
+        Ok(GateMessage::ReportablePresentation(request))
+    }
+
+    /// Process a `message` from the server.
+    ///
+    /// # Errors
+    ///
+    /// * `GateError::Timeout` if the session expired before `now`.
+    /// * `GateError::UnexpectedMessage` if the `message` is not allowed in the
+    ///   current state.
+    /// * `GateError::Rejected` if the server rejected our presentation.
+    /// * `GateError::Credential` if an issuance could not be verified.
+    ///
+    /// Upon any error other than `UnexpectedMessage`, the client moves to
+    /// `ClientState::Failed`.
+    ///
+    /// # Returns
+    ///
+    /// The new state of this client.
+    pub fn handle(
+        &mut self,
+        message: &GateMessage,
+        now: u64,
+    ) -> Result<ClientState, Error>
+    {
+        trace_span!("gate_client", state = ?self.state, message = ?message.message_type());
+
+        if let ClientState::Failed(GateError::Timeout) = self.poll_timeout(now) {
+            return Err(GateError::Timeout.into());
+        }
+
+        let result: Result<ClientState, GateError> = match (self.state, message) {
+            (ClientState::AwaitingIssuance { .. }, &GateMessage::Issuance(ref issuance)) => {
+                match self.user.obtain_finish(Some(issuance)) {
+                    Ok(_)  => Ok(ClientState::Holding),
+                    Err(x) => Err(GateError::Credential(x)),
+                }
+            },
+            (ClientState::AwaitingBlindIssuance { .. }, &GateMessage::BlindIssuance(ref issuance)) => {
+                match self.user.blind_obtain_finish(Some(issuance), &self.pending_attributes) {
+                    Ok(_)  => Ok(ClientState::Holding),
+                    Err(x) => Err(GateError::Credential(x)),
+                }
+            },
+            (ClientState::AwaitingVerdict { .. }, &GateMessage::Verdict(true))  => Ok(ClientState::Admitted),
+            (ClientState::AwaitingVerdict { .. }, &GateMessage::Verdict(false)) => Err(GateError::Rejected),
+            (ClientState::AwaitingVerdict { .. }, &GateMessage::ReportToken(ref response)) => {
+                let finished = match self.pending_report {
+                    Some((ref pending, ref public)) => pending.finish(response, public),
+                    None                            => return Err(GateError::UnexpectedMessage.into()),
+                };
+
+                match finished {
+                    Ok(token) => {
+                        self.report_tokens.push(token);
+
+                        Ok(ClientState::Admitted)
+                    },
+                    Err(x) => Err(GateError::Report(x)),
+                }
+            },
+            _ => return Err(GateError::UnexpectedMessage.into()),
+        };
+
+        match result {
+            Ok(state) => {
+                self.state = state;
+                self.pending_attributes.clear();
+                self.pending_report = None;
+
+                Ok(state)
+            },
+            Err(x) => Err(self.fail(x).into()),
+        }
+    }
+}
+
+/// The states of a `GateServer` session.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerState {
+    /// The session was opened at time `since` and awaits a client request.
+    AwaitingRequest { since: u64 },
+    /// A credential was issued in this session.
+    Issued,
+    /// A presentation was verified in this session.
+    Verified(VerifiedCredential),
+    /// A presentation was rejected in this session.
+    Rejected,
+    /// The session failed.
+    Failed(GateError),
+}
+
+/// The server side of a gate: an `Issuer` handling one client session.
+///
+/// Each session handles exactly one request.  A new `GateServer` should be
+/// created, with `GateServer::open()`, for each client session; the `Issuer`
+/// is borrowed so that it may be shared between sessions.
+pub struct GateServer<'a> {
+    pub config: GateConfig,
+    issuer: &'a Issuer,
+    state: ServerState,
+    /// The desk signing report tokens, and the nullifiers of those it has
+    /// already admitted, if report tokens are offered.
+    reports: Option<(&'a ReportDesk, &'a mut dyn NullifierStore)>,
+    /// Where the outcome and duration of each request is reported.
+    metrics: &'a dyn Metrics,
+    /// An identifier for this session, recorded in tracing spans.
+    session: u64,
+}
+
+impl<'a> GateServer<'a> {
+    /// Open a new session at time `now`.
+    pub fn open(issuer: &'a Issuer, config: GateConfig, now: u64) -> GateServer<'a> {
+        GateServer {
+            config,
+            issuer,
+            state: ServerState::AwaitingRequest { since: now },
+            reports: None,
+            metrics: &NoMetrics,
+            session: 0,
+        }
+    }
+
+    /// Offer report tokens from a `desk` in this session, refusing holders
+    /// whose nullifiers are already in `entries`.
+    pub fn with_reports(mut self, desk: &'a ReportDesk, entries: &'a mut dyn NullifierStore) -> GateServer<'a> {
+        self.reports = Some((desk, entries));
+        self
+    }
+
+    /// Report the outcome and duration of this session's request to
+    /// `metrics`.
+    pub fn with_metrics(mut self, metrics: &'a dyn Metrics) -> GateServer<'a> {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Identify this session as `session` in tracing spans, e.g. to follow
+    /// it across a load balancer's logs.  The identifier is never sent to
+    /// the client.
+    pub fn with_session_id(mut self, session: u64) -> GateServer<'a> {
+        self.session = session;
+        self
+    }
+
+    /// The identifier of this session, or zero if none was given.
+    pub fn session_id(&self) -> u64 {
+        self.session
+    }
+
+    /// The current state of this session.
+    pub fn state(&self) -> &ServerState {
+        &self.state
+    }
+
+    /// Fail the session if the client did not send a request in time.
+    pub fn poll_timeout(&mut self, now: u64) -> &ServerState {
+        let expired: bool = match self.state {
+            ServerState::AwaitingRequest { since } => now.saturating_sub(since) > self.config.timeout,
+            _                                      => false,
+        };
+
+        if expired {
+            self.state = ServerState::Failed(GateError::Timeout);
+        }
+        &self.state
+    }
+
+    /// Process a `message` from the client.
+    ///
+    /// # Errors
+    ///
+    /// * `GateError::Timeout` if the session expired before `now`.
+    /// * `GateError::UnexpectedMessage` if the session already handled a
+    ///   request, or the `message` was not a client request.
+    /// * `GateError::Credential` if the request could not be fulfilled.
+    ///
+    /// A rejected presentation is not an error: the session moves to
+    /// `ServerState::Rejected` and `GateMessage::Verdict(false)` is returned
+    /// for the client.  This includes a `ReportablePresentation` from a holder
+    /// who was already admitted at the entry.  A `ReportablePresentation` is
+    /// unexpected unless the session was opened `with_reports()`.
+    ///
+    /// # Returns
+    ///
+    /// The `GateMessage` to send back to the client.
+    pub fn handle<R>(
+        &mut self,
+        message: &GateMessage,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<GateMessage, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        trace_span!("gate_server", session = self.session, message = ?message.message_type());
+
+        match *self.poll_timeout(now) {
+            ServerState::AwaitingRequest { .. } => (),
+            ServerState::Failed(GateError::Timeout) => return Err(GateError::Timeout.into()),
+            _ => return Err(GateError::UnexpectedMessage.into()),
+        }
+
+        let issuer: &Issuer = self.issuer;
+        let metrics: &dyn Metrics = self.metrics;
+
+        let result: Result<(ServerState, GateMessage), GateError> = match *message {
+            GateMessage::IssuanceRequest(ref request) => {
+                match observe(metrics, Operation::Issuance, || issuer.issue(request, rng)) {
+                    Ok(x)  => Ok((ServerState::Issued, GateMessage::Issuance(x))),
+                    Err(x) => Err(GateError::Credential(x)),
+                }
+            },
+            GateMessage::BlindIssuanceRequest(ref request) => {
+                match observe(metrics, Operation::BlindIssuance, || issuer.blind_issue(request, rng)) {
+                    Ok(x)  => Ok((ServerState::Issued, GateMessage::BlindIssuance(x))),
+                    Err(x) => Err(GateError::Credential(x)),
+                }
+            },
+            GateMessage::Presentation(ref presentation) => {
+                match observe(metrics, Operation::Verification, || issuer.verify(presentation)) {
+                    Ok(x)  => Ok((ServerState::Verified(x), GateMessage::Verdict(true))),
+                    Err(_) => Ok((ServerState::Rejected, GateMessage::Verdict(false))),
+                }
+            },
+            GateMessage::ReportablePresentation(ref request) => {
+                let (desk, entries) = match self.reports {
+                    Some((desk, ref mut entries)) => (desk, entries),
+                    None                          => return Err(GateError::UnexpectedMessage.into()),
+                };
+
+                match observe(metrics, Operation::ReportablePresentation, || desk.admit(issuer, request, &mut **entries)) {
+                    Ok((x, response)) => Ok((ServerState::Verified(x), GateMessage::ReportToken(response))),
+                    Err(_)            => Ok((ServerState::Rejected, GateMessage::Verdict(false))),
+                }
+            },
+            _ => return Err(GateError::UnexpectedMessage.into()),
+        };
+
+        trace_result!(result);
+
+        match result {
+            Ok((state, response)) => {
+                self.state = state;
+
+                Ok(response)
+            },
+            Err(x) => {
+                self.state = ServerState::Failed(x);
+
+                Err(x.into())
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use std::collections::HashSet;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use parameters::SystemParameters;
+    use rate_limit::Nullifier;
+    use test_utils::H;
+    use tokens::TokenPreimage;
+
+    fn setup() -> (Issuer, GateClient) {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let user: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+        (issuer, GateClient::new(user, GateConfig::default()))
+    }
+
+    /// Send a message through its byte encoding, as over a network.
+    fn wire(message: GateMessage) -> GateMessage {
+        GateMessage::from_bytes(&message.to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn issuance_and_presentation() {
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 1);
+        let response = session.handle(&wire(request), 2, &mut rng).unwrap();
+
+        assert!(*session.state() == ServerState::Issued);
+        assert!(client.handle(&wire(response), 3).unwrap() == ClientState::Holding);
+
+        let presentation = client.start_presentation(4, &mut rng).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 4);
+        let verdict = session.handle(&wire(presentation), 5, &mut rng).unwrap();
+
+        assert!(verdict == GateMessage::Verdict(true));
+        assert!(client.handle(&wire(verdict), 6).unwrap() == ClientState::Admitted);
+    }
+
+    #[test]
+    fn blind_issuance() {
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+
+        let request = client.start_blind_issuance(vec![Scalar::random(&mut rng)], 0, &mut rng).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 0);
+        let response = session.handle(&wire(request), 1, &mut rng).unwrap();
+
+        assert!(client.handle(&wire(response), 2).unwrap() == ClientState::Holding);
+        assert!(client.user().credential.is_some());
+    }
+
+    #[test]
+    fn presentation_to_wrong_issuer_is_rejected() {
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+        let other_issuer: Issuer = Issuer::create(issuer.system_parameters, &mut rng);
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let response = GateServer::open(&issuer, GateConfig::default(), 0).handle(&request, 0, &mut rng).unwrap();
+
+        client.handle(&response, 0).unwrap();
+
+        let presentation = client.start_presentation(0, &mut rng).unwrap();
+        let mut session = GateServer::open(&other_issuer, GateConfig::default(), 0);
+        let verdict = session.handle(&presentation, 0, &mut rng).unwrap();
+
+        assert!(*session.state() == ServerState::Rejected);
+        assert!(client.handle(&verdict, 0) == Err(GateError::Rejected.into()));
+        assert!(client.state() == ClientState::Failed(GateError::Rejected));
+    }
+
+    #[test]
+    fn sessions_report_to_their_metrics() {
+        use std::cell::RefCell;
+
+        use errors::Error;
+
+        #[derive(Default)]
+        struct Outcomes(RefCell<Vec<(Operation, Option<Error>)>>);
+
+        impl Metrics for Outcomes {
+            fn succeeded(&self, operation: Operation) {
+                self.0.borrow_mut().push((operation, None));
+            }
+
+            fn failed(&self, operation: Operation, reason: Error) {
+                self.0.borrow_mut().push((operation, Some(reason)));
+            }
+        }
+
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+        let other_issuer: Issuer = Issuer::create(issuer.system_parameters, &mut rng);
+        let outcomes = Outcomes::default();
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let response = GateServer::open(&issuer, GateConfig::default(), 0)
+            .with_metrics(&outcomes)
+            .handle(&request, 0, &mut rng)
+            .unwrap();
+
+        client.handle(&response, 0).unwrap();
+
+        let presentation = client.start_presentation(0, &mut rng).unwrap();
+
+        GateServer::open(&other_issuer, GateConfig::default(), 0)
+            .with_metrics(&outcomes)
+            .handle(&presentation, 0, &mut rng)
+            .unwrap();
+
+        // A rejected presentation is not an error for the session, but is a
+        // failure for the verifier's metrics.
+        let outcomes = outcomes.0.borrow();
+
+        assert!(outcomes[0] == (Operation::Issuance, None));
+        assert!(outcomes[1].0 == Operation::Verification);
+        assert!(outcomes[1].1.is_some());
+    }
+
+    #[test]
+    fn unexpected_messages() {
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+
+        // A client with no credential can't present one.
+        assert!(client.start_presentation(0, &mut rng).is_err());
+        // A client not awaiting a verdict ignores one.
+        assert!(client.handle(&GateMessage::Verdict(true), 0) == Err(GateError::UnexpectedMessage.into()));
+        assert!(client.state() == ClientState::Idle);
+
+        // A server only handles one request per session.
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 0);
+
+        session.handle(&request, 0, &mut rng).unwrap();
+
+        assert!(session.handle(&request, 0, &mut rng) == Err(GateError::UnexpectedMessage.into()));
+        assert!(session.handle(&GateMessage::Verdict(true), 0, &mut rng) == Err(GateError::UnexpectedMessage.into()));
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+        let config = GateConfig { timeout: 10 };
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let mut session = GateServer::open(&issuer, config, 0);
+
+        assert!(session.handle(&request, 11, &mut rng) == Err(GateError::Timeout.into()));
+
+        let response = GateServer::open(&issuer, config, 0).handle(&request, 1, &mut rng).unwrap();
+
+        assert!(client.handle(&response, 61) == Err(GateError::Timeout.into()));
+        assert!(client.state() == ClientState::Failed(GateError::Timeout));
+
+        // The client may start over after a failure.
+        assert!(client.start_issuance(vec![Scalar::random(&mut rng)], 62).is_ok());
+    }
+
+    #[test]
+    fn reportable_presentation() {
+        let mut rng = thread_rng();
+        let (issuer, mut client) = setup();
+        let desk = ReportDesk::new(b"concert", &mut rng);
+        let mut entries: HashSet<Nullifier> = HashSet::new();
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let response = GateServer::open(&issuer, GateConfig::default(), 0).handle(&request, 0, &mut rng).unwrap();
+
+        client.handle(&response, 0).unwrap();
+
+        // A server which offers no report tokens refuses to sign one.
+        let presentation = client.start_reportable_presentation(&desk.entry, &desk.public(), 1, &mut rng).unwrap();
+
+        assert!(GateServer::open(&issuer, GateConfig::default(), 1).handle(&presentation, 1, &mut rng) ==
+                Err(GateError::UnexpectedMessage.into()));
+
+        let answer = GateServer::open(&issuer, GateConfig::default(), 1)
+            .with_reports(&desk, &mut entries)
+            .handle(&wire(presentation), 2, &mut rng)
+            .unwrap();
+
+        assert!(answer.message_type() == GateMessageType::ReportToken);
+        assert!(client.handle(&wire(answer), 3).unwrap() == ClientState::Admitted);
+
+        let tokens = client.take_report_tokens();
+
+        assert!(tokens.len() == 1);
+        assert!(client.take_report_tokens().is_empty());
+        assert!(desk.redeem(&tokens[0].redeem(b"great show"), b"great show", &mut HashSet::<TokenPreimage>::new()).is_ok());
+
+        // The same holder is not admitted at the entry again.
+        let again = client.start_reportable_presentation(&desk.entry, &desk.public(), 4, &mut rng).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 4).with_reports(&desk, &mut entries);
+
+        assert!(session.handle(&again, 4, &mut rng).unwrap() == GateMessage::Verdict(false));
+        assert!(*session.state() == ServerState::Rejected);
+    }
+
+    #[test]
+    fn unknown_message_type() {
+        assert!(GateMessage::from_bytes(&[0u8, 1u8]) == Err(GateError::UnknownMessageType));
+        assert!(GateMessage::from_bytes(&[]) == Err(GateError::UnknownMessageType));
+        assert!(GateMessage::from_bytes(&[6u8, 1u8]).unwrap() == GateMessage::Verdict(true));
+    }
+}
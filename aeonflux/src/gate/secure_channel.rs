@@ -0,0 +1,484 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! An authenticated, encrypted channel for gate messages, for gates which
+//! have no TLS, such as those reached over BLE or NFC.
+//!
+//! A `Handshake` runs the Noise XX or IK pattern, over X25519, ChaCha20-
+//! Poly1305, and SHA-256, between the static keys of a holder and of a gate.
+//! The holder initiates, and the gate responds.
+//!
+//! * Under `Pattern::XX`, each side learns the other's static key during the
+//!   handshake.  A holder may pin the gate's key, e.g. as published by its
+//!   issuer, and should, since otherwise it learns only that *some* gate is
+//!   at the other end.
+//! * Under `Pattern::IK`, the holder must already know the gate's static key,
+//!   and the handshake takes one round trip rather than three messages.
+//!
+//! Either way, a gate may restrict itself to the holders' keys it trusts.
+//! Holders who wish to stay unlinkable between sessions should use a fresh
+//! `StaticKeypair` for each, and gates open to such holders should trust any
+//! key, since the credential presented, not the channel, is what admits them.
+//!
+//! Once the handshake is finished, the `SecureChannel` it becomes seals and
+//! opens `GateMessage`s, and gives the `ChannelBinding` of the session, so
+//! that presentations may be bound to it with `User::show_bound()`.
+//!
+//! Every handshake message and sealed message is a frame: its length, as two
+//! big-endian bytes, then that many bytes of Noise message.  Integrators
+//! reading from a stream should read the two bytes, then `frame_length()`
+//! more.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use snow::Builder;
+use snow::HandshakeState;
+use snow::TransportState;
+
+use channel_binding::ChannelBinding;
+use errors::Error;
+use errors::SecureChannelError;
+use gate::GateMessage;
+use secret::Secret;
+
+/// The length of a static key, public or private.
+pub const SIZEOF_STATIC_KEY: usize = 32;
+
+/// The length of a frame's length prefix.
+pub const SIZEOF_FRAME_HEADER: usize = 2;
+
+/// The longest Noise message which fits in a frame, as Noise allows.
+pub const MAX_MESSAGE_LENGTH: usize = 65535;
+
+/// The length of the authentication tag on every sealed message.
+const SIZEOF_TAG: usize = 16;
+
+/// The Noise handshake pattern to run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Pattern {
+    /// Both static keys are sent during the handshake.
+    XX,
+    /// The responder's static key is known to the initiator beforehand.
+    IK,
+}
+
+impl Pattern {
+    fn protocol_name(&self) -> &'static str {
+        match *self {
+            Pattern::XX => "Noise_XX_25519_ChaChaPoly_SHA256",
+            Pattern::IK => "Noise_IK_25519_ChaChaPoly_SHA256",
+        }
+    }
+}
+
+/// An X25519 static keypair, for either end of a `Handshake`.
+#[derive(Clone, Debug)]
+pub struct StaticKeypair {
+    private: Secret<[u8; SIZEOF_STATIC_KEY]>,
+    pub public: [u8; SIZEOF_STATIC_KEY],
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for StaticKeypair {
+    fn drop(&mut self) {
+        self.private.clear();
+    }
+}
+
+impl StaticKeypair {
+    /// Generate a keypair from a `csprng`.
+    pub fn generate<R>(csprng: &mut R) -> StaticKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut private = [0u8; SIZEOF_STATIC_KEY];
+
+        csprng.fill_bytes(&mut private);
+
+        StaticKeypair::from_bytes(private)
+    }
+
+    /// The keypair with the `private` key given.
+    pub fn from_bytes(private: [u8; SIZEOF_STATIC_KEY]) -> StaticKeypair {
+        let mut bits = private;
+
+        bits[0]  &= 248;
+        bits[31] &= 127;
+        bits[31] |= 64;
+
+        let public = (&ED25519_BASEPOINT_TABLE * &Scalar::from_bits(bits)).to_montgomery().to_bytes();
+
+        StaticKeypair { private: Secret::new(private), public }
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIZEOF_STATIC_KEY] {
+        *self.private.expose_secret()
+    }
+}
+
+/// Which static keys a `Handshake` will accept from its peer.
+#[derive(Clone, Debug)]
+enum Expected {
+    Any,
+    Pinned([u8; SIZEOF_STATIC_KEY]),
+    Trusted(Vec<[u8; SIZEOF_STATIC_KEY]>),
+}
+
+impl Expected {
+    fn accepts(&self, key: &[u8]) -> bool {
+        match *self {
+            Expected::Any => true,
+            Expected::Pinned(ref pinned) => &pinned[..] == key,
+            Expected::Trusted(ref trusted) => trusted.iter().any(|k| &k[..] == key),
+        }
+    }
+}
+
+/// One side of a Noise handshake in progress.
+pub struct Handshake {
+    state: HandshakeState,
+    expected: Expected,
+}
+
+impl Handshake {
+    /// Begin a handshake as the holder, with our `local` static key.
+    ///
+    /// The gate's static key, `remote`, is pinned if given: a handshake with
+    /// any other gate fails.
+    ///
+    /// # Errors
+    ///
+    /// * `SecureChannelError::MissingKey` if the `pattern` is `IK` and no
+    ///   `remote` key was given.
+    pub fn initiate(
+        pattern: Pattern,
+        local: &StaticKeypair,
+        remote: Option<&[u8; SIZEOF_STATIC_KEY]>,
+    ) -> Result<Handshake, Error>
+    {
+        let builder = Builder::new(pattern.protocol_name().parse().map_err(|_| SecureChannelError::Handshake)?)
+            .local_private_key(local.private.expose_secret());
+
+        let (builder, expected) = match (pattern, remote) {
+            (Pattern::IK, None) => return Err(SecureChannelError::MissingKey.into()),
+            (Pattern::IK, Some(key)) => (builder.remote_public_key(key), Expected::Pinned(*key)),
+            (Pattern::XX, Some(key)) => (builder, Expected::Pinned(*key)),
+            (Pattern::XX, None) => (builder, Expected::Any),
+        };
+
+        Ok(Handshake {
+            state: builder.build_initiator().map_err(|_| SecureChannelError::Handshake)?,
+            expected,
+        })
+    }
+
+    /// Respond to a handshake as the gate, with our `local` static key.
+    ///
+    /// If some `trusted` keys are given, a handshake with a holder whose
+    /// static key is not among them fails.
+    pub fn respond(
+        pattern: Pattern,
+        local: &StaticKeypair,
+        trusted: Option<&[[u8; SIZEOF_STATIC_KEY]]>,
+    ) -> Result<Handshake, Error>
+    {
+        let state = Builder::new(pattern.protocol_name().parse().map_err(|_| SecureChannelError::Handshake)?)
+            .local_private_key(local.private.expose_secret())
+            .build_responder()
+            .map_err(|_| SecureChannelError::Handshake)?;
+
+        let expected = match trusted {
+            Some(keys) => Expected::Trusted(keys.to_vec()),
+            None => Expected::Any,
+        };
+
+        Ok(Handshake { state, expected })
+    }
+
+    /// Whether every message of the handshake has been written or read.
+    pub fn is_finished(&self) -> bool {
+        self.state.is_handshake_finished()
+    }
+
+    /// Write the next handshake message, as a frame for the peer.
+    ///
+    /// # Errors
+    ///
+    /// * `SecureChannelError::WrongState` if it is the peer's turn to write,
+    ///   or the handshake is finished.
+    pub fn write_message(&mut self) -> Result<Vec<u8>, Error> {
+        if self.is_finished() || !self.state.is_my_turn() {
+            return Err(SecureChannelError::WrongState.into());
+        }
+
+        let mut message = [0u8; MAX_MESSAGE_LENGTH];
+        let length = self.state.write_message(&[], &mut message).map_err(|_| SecureChannelError::Handshake)?;
+
+        Ok(frame(&message[..length])?)
+    }
+
+    /// Read the peer's next handshake message from a `frame`.
+    ///
+    /// # Errors
+    ///
+    /// * `SecureChannelError::WrongState` if it is our turn to write, or the
+    ///   handshake is finished.
+    /// * `SecureChannelError::Malformed` if the frame's length is wrong.
+    /// * `SecureChannelError::Handshake` if the message was not the one
+    ///   expected, including when an `IK` initiator used the wrong key for us.
+    /// * `SecureChannelError::UntrustedPeer` if the peer's static key is not
+    ///   pinned or trusted.
+    pub fn read_message(&mut self, frame: &[u8]) -> Result<(), Error> {
+        if self.is_finished() || self.state.is_my_turn() {
+            return Err(SecureChannelError::WrongState.into());
+        }
+
+        let mut payload = [0u8; MAX_MESSAGE_LENGTH];
+
+        self.state.read_message(unframe(frame)?, &mut payload).map_err(|_| SecureChannelError::Handshake)?;
+
+        // Check the peer's key as soon as we learn it, so that an XX holder
+        // never sends its own static key to an unexpected gate.
+        match self.state.get_remote_static() {
+            Some(key) if !self.expected.accepts(key) => Err(SecureChannelError::UntrustedPeer.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Finish the handshake, and begin sealing and opening messages.
+    ///
+    /// # Errors
+    ///
+    /// * `SecureChannelError::WrongState` if the handshake is not finished.
+    pub fn finish(self) -> Result<SecureChannel, Error> {
+        if !self.is_finished() {
+            return Err(SecureChannelError::WrongState.into());
+        }
+
+        let mut remote = [0u8; SIZEOF_STATIC_KEY];
+
+        match self.state.get_remote_static() {
+            Some(key) if key.len() == SIZEOF_STATIC_KEY && self.expected.accepts(key) => remote.copy_from_slice(key),
+            _ => return Err(SecureChannelError::UntrustedPeer.into()),
+        }
+
+        let binding = ChannelBinding::noise_handshake_hash(self.state.get_handshake_hash());
+        let transport = self.state.into_transport_mode().map_err(|_| SecureChannelError::Handshake)?;
+
+        Ok(SecureChannel { transport, binding, remote })
+    }
+}
+
+/// An authenticated, encrypted channel carrying `GateMessage`s.
+///
+/// Frames must be opened in the order in which they were sealed: a frame
+/// which is replayed, reordered, or dropped causes the next to fail.
+pub struct SecureChannel {
+    transport: TransportState,
+    binding: ChannelBinding,
+    remote: [u8; SIZEOF_STATIC_KEY],
+}
+
+impl SecureChannel {
+    /// The peer's static key.
+    pub fn remote_static(&self) -> &[u8; SIZEOF_STATIC_KEY] {
+        &self.remote
+    }
+
+    /// The binding of this channel, for `User::show_bound()` and
+    /// `Issuer::verify_bound()`.
+    pub fn channel_binding(&self) -> &ChannelBinding {
+        &self.binding
+    }
+
+    /// Encrypt a `message`, as a frame for the peer.
+    ///
+    /// # Errors
+    ///
+    /// * `SecureChannelError::TooLarge` if the encoded message does not fit.
+    pub fn seal(&mut self, message: &GateMessage) -> Result<Vec<u8>, Error> {
+        let plaintext: Vec<u8> = message.to_bytes();
+
+        if plaintext.len() + SIZEOF_TAG > MAX_MESSAGE_LENGTH {
+            return Err(SecureChannelError::TooLarge.into());
+        }
+
+        let mut ciphertext = [0u8; MAX_MESSAGE_LENGTH];
+        let length = self.transport.write_message(&plaintext, &mut ciphertext)
+            .map_err(|_| SecureChannelError::TooLarge)?;
+
+        Ok(frame(&ciphertext[..length])?)
+    }
+
+    /// Decrypt and decode a message from a `frame` sealed by the peer.
+    ///
+    /// # Errors
+    ///
+    /// * `SecureChannelError::Malformed` if the frame's length is wrong.
+    /// * `SecureChannelError::Decryption` if the frame was altered, or is out
+    ///   of order.
+    /// * `SecureChannelError::Gate` if the decrypted message could not be
+    ///   decoded.
+    pub fn open(&mut self, frame: &[u8]) -> Result<GateMessage, Error> {
+        let mut plaintext = [0u8; MAX_MESSAGE_LENGTH];
+        let length = self.transport.read_message(unframe(frame)?, &mut plaintext)
+            .map_err(|_| SecureChannelError::Decryption)?;
+
+        Ok(GateMessage::from_bytes(&plaintext[..length])?)
+    }
+}
+
+/// The number of bytes which follow a frame's `header`.
+pub fn frame_length(header: [u8; SIZEOF_FRAME_HEADER]) -> usize {
+    ((header[0] as usize) << 8) | header[1] as usize
+}
+
+fn frame(message: &[u8]) -> Result<Vec<u8>, SecureChannelError> {
+    if message.len() > MAX_MESSAGE_LENGTH {
+        return Err(SecureChannelError::TooLarge);
+    }
+
+    let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_FRAME_HEADER + message.len());
+
+    v.push((message.len() >> 8) as u8);
+    v.push(message.len() as u8);
+    v.extend_from_slice(message);
+
+    Ok(v)
+}
+
+fn unframe(frame: &[u8]) -> Result<&[u8], SecureChannelError> {
+    if frame.len() < SIZEOF_FRAME_HEADER {
+        return Err(SecureChannelError::Malformed);
+    }
+
+    let length = frame_length([frame[0], frame[1]]);
+
+    if frame.len() != SIZEOF_FRAME_HEADER + length {
+        return Err(SecureChannelError::Malformed);
+    }
+
+    Ok(&frame[SIZEOF_FRAME_HEADER..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use gate::ClientState;
+    use gate::GateClient;
+    use gate::GateConfig;
+    use gate::GateServer;
+    use issuer::Issuer;
+    use parameters::SystemParameters;
+    use user::User;
+
+    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+    /// Run a handshake to completion, passing each frame to the other side.
+    fn handshake(mut holder: Handshake, mut gate: Handshake) -> (SecureChannel, SecureChannel) {
+        loop {
+            gate.read_message(&holder.write_message().unwrap()).unwrap();
+
+            if gate.is_finished() {
+                break;
+            }
+            holder.read_message(&gate.write_message().unwrap()).unwrap();
+
+            if holder.is_finished() {
+                break;
+            }
+        }
+        (holder.finish().unwrap(), gate.finish().unwrap())
+    }
+
+    #[test]
+    fn gate_sessions_run_over_an_xx_channel() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let mut client = GateClient::new(user, GateConfig::default());
+
+        let gate_key = StaticKeypair::generate(&mut rng);
+        let holder_key = StaticKeypair::generate(&mut rng);
+
+        let (mut holder, mut gate) = handshake(
+            Handshake::initiate(Pattern::XX, &holder_key, Some(&gate_key.public)).unwrap(),
+            Handshake::respond(Pattern::XX, &gate_key, None).unwrap(),
+        );
+
+        assert!(holder.remote_static() == &gate_key.public);
+        assert!(gate.remote_static() == &holder_key.public);
+        assert!(holder.channel_binding() == gate.channel_binding());
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 1);
+        let response = session.handle(&gate.open(&holder.seal(&request).unwrap()).unwrap(), 2, &mut rng).unwrap();
+
+        assert!(client.handle(&holder.open(&gate.seal(&response).unwrap()).unwrap(), 3).unwrap() == ClientState::Holding);
+
+        let presentation = client.start_presentation(4, &mut rng).unwrap();
+        let mut session = GateServer::open(&issuer, GateConfig::default(), 4);
+        let verdict = session.handle(&gate.open(&holder.seal(&presentation).unwrap()).unwrap(), 5, &mut rng).unwrap();
+
+        assert!(verdict == GateMessage::Verdict(true));
+        assert!(client.handle(&holder.open(&gate.seal(&verdict).unwrap()).unwrap(), 6).unwrap() == ClientState::Admitted);
+    }
+
+    #[test]
+    fn wrong_keys_and_altered_frames_are_refused() {
+        let mut rng = thread_rng();
+        let gate_key = StaticKeypair::generate(&mut rng);
+        let other_key = StaticKeypair::generate(&mut rng);
+        let holder_key = StaticKeypair::generate(&mut rng);
+
+        assert!(Handshake::initiate(Pattern::IK, &holder_key, None).err() == Some(SecureChannelError::MissingKey.into()));
+
+        // A holder who pinned another gate's key.
+        let mut holder = Handshake::initiate(Pattern::IK, &holder_key, Some(&other_key.public)).unwrap();
+        let mut gate = Handshake::respond(Pattern::IK, &gate_key, None).unwrap();
+
+        assert!(gate.read_message(&holder.write_message().unwrap()) == Err(SecureChannelError::Handshake.into()));
+
+        // A gate which does not trust the holder's key.
+        let mut holder = Handshake::initiate(Pattern::IK, &holder_key, Some(&gate_key.public)).unwrap();
+        let mut gate = Handshake::respond(Pattern::IK, &gate_key, Some(&[other_key.public])).unwrap();
+
+        assert!(gate.read_message(&holder.write_message().unwrap()) == Err(SecureChannelError::UntrustedPeer.into()));
+
+        let (mut holder, mut gate) = handshake(
+            Handshake::initiate(Pattern::IK, &holder_key, Some(&gate_key.public)).unwrap(),
+            Handshake::respond(Pattern::IK, &gate_key, Some(&[holder_key.public])).unwrap(),
+        );
+        let mut sealed = holder.seal(&GateMessage::Verdict(true)).unwrap();
+        let last = sealed.len() - 1;
+
+        assert!(gate.open(&sealed[..last]).err() == Some(SecureChannelError::Malformed.into()));
+
+        sealed[last] ^= 1;
+
+        assert!(gate.open(&sealed).err() == Some(SecureChannelError::Decryption.into()));
+    }
+}
@@ -0,0 +1,578 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Credential attributes which are salted hashes of external identifiers.
+//!
+//! Some deployments must never store plaintext identifiers, such as email
+//! addresses or usernames, anywhere.  This module maps an identifier to an
+//! attribute \\( m = H(\mathrm{identifier} || \mathrm{salt}) \\), where the
+//! salt is known only to the user.  The issuer keeps only the attribute and a
+//! Pedersen commitment to the salt.
+//!
+//! # Note
+//!
+//! Correct hashing cannot be proven in zero-knowledge with the sigma
+//! protocols in this crate.  Instead, at issuance the user sends the
+//! identifier and the opening of their salt commitment to the issuer over a
+//! private channel, and the issuer recomputes the hash before discarding both.
+//!
+//! Later, the user may optionally reveal their salt to a verifier who already
+//! knows the identifier, and prove that a hidden attribute in a presentation
+//! is the hash of that identifier.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::CredentialPresentation;
+use credential::RevealedAttribute;
+use encoding;
+use errors::CredentialError;
+use errors::Error;
+use hashing::Hasher;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use parameters::SystemParameters;
+use pedersen;
+use proofs::attribute_opening;
+use secret::Secret;
+
+pub const SIZEOF_SALT: usize = 32;
+pub const SIZEOF_SALT_OPENING: usize = 64;
+pub const SIZEOF_HASHED_IDENTIFIER: usize = 64;
+
+/// The domain separator which is prepended to all hashed identifiers.
+const HASHED_IDENTIFIER_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux hashed identifier v1";
+
+/// A secret salt, known only to the user.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Salt(pub(crate) Secret<Scalar>);
+
+impl Salt {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Salt, CredentialError> {
+        if bytes.len() != SIZEOF_SALT {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 32];
+
+        tmp.copy_from_slice(bytes);
+
+        Ok(Salt(Secret::new(Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)?)))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.expose_secret().to_bytes().to_vec()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Salt, "A valid byte sequence representing a hashed_identifier::Salt");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Salt);
+impl_try_from_bytes!(Salt, CredentialError);
+
+impl Salt {
+    /// Generate a new random `Salt`.
+    pub fn random<R>(csprng: &mut R) -> Salt
+    where
+        R: RngCore + CryptoRng,
+    {
+        Salt(Secret::new(Scalar::random(csprng)))
+    }
+}
+
+/// Overwrite the salt with null bytes when it goes out of scope.
+impl Drop for Salt {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Hash an external `identifier` with a `salt` to produce a credential attribute.
+///
+/// The identifier is length-prefixed so that no two `(identifier, salt)`
+/// pairs produce the same hash input, and zero-padded to a whole number of
+/// `encoding::SIZEOF_PADDING_BLOCK`s so that hashing it takes the same time
+/// for every identifier of up to that many bytes.
+pub fn hash_identifier(identifier: &[u8], salt: &Salt) -> RevealedAttribute {
+    let mut padded: Vec<u8> = Vec::with_capacity(encoding::sizeof_padded(identifier));
+    let mut hash = Hasher::new(HASHED_IDENTIFIER_DOMAIN_SEPARATOR);
+
+    encoding::push_padded(&mut padded, identifier);
+
+    hash.input(&padded).input(salt.0.expose_secret().as_bytes());
+    hash.finalize_scalar()
+}
+
+/// A user's secret salt, along with the blinding factor for their commitment to it.
+#[derive(Clone, Debug, Default)]
+pub struct SaltOpening {
+    pub salt: Salt,
+    pub blinding: Ephemeral,
+}
+
+impl SaltOpening {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaltOpening, CredentialError> {
+        if bytes.len() != SIZEOF_SALT_OPENING {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let salt = Salt::from_bytes(&bytes[00..32])?;
+
+        let mut tmp = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[32..64]);
+
+        let blinding = Ephemeral::from(Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)?);
+
+        Ok(SaltOpening { salt, blinding })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SALT_OPENING);
+
+        v.extend(self.salt.to_bytes());
+        v.extend(self.blinding.to_bytes());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SaltOpening,
+                                         "A valid byte sequence representing a hashed_identifier::SaltOpening");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SaltOpening);
+impl_try_from_bytes!(SaltOpening, CredentialError);
+
+impl SaltOpening {
+    /// Choose a new random salt and blinding factor.
+    pub fn new<R>(csprng: &mut R) -> SaltOpening
+    where
+        R: RngCore + CryptoRng,
+    {
+        SaltOpening {
+            salt: Salt::random(csprng),
+            blinding: Ephemeral::new(csprng),
+        }
+    }
+
+    /// Compute the Pedersen commitment, `Cs = salt * G + blinding * H`, to the salt.
+    pub fn commit(&self, system_parameters: &SystemParameters) -> pedersen::Commitment {
+        pedersen::Commitment::to(&(&system_parameters.g * self.salt.0.expose_secret()),
+                                 &self.blinding, &system_parameters.h)
+    }
+
+    /// Compute the `HashedIdentifier` for an `identifier` salted with this salt.
+    pub fn hashed_identifier(
+        &self,
+        identifier: &[u8],
+        system_parameters: &SystemParameters,
+    ) -> HashedIdentifier
+    {
+        HashedIdentifier {
+            attribute: hash_identifier(identifier, &self.salt),
+            salt_commitment: self.commit(system_parameters),
+        }
+    }
+
+    /// Selectively reveal the salt, and prove that the `index`th hidden
+    /// attribute within a `presentation` is the hash of an identifier with it.
+    ///
+    /// # Inputs
+    ///
+    /// * `identifier` is the plaintext identifier which the verifier knows.
+    /// * `presentation` is a `CredentialPresentation` made with `nonces`.
+    /// * `nonces` are the same `Nonces` passed to `User::show()`.
+    /// * `index` is the index of the hashed identifier attribute.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if the `presentation` or
+    ///   the `nonces` had no `index`th attribute.
+    pub fn reveal(
+        &self,
+        identifier: &[u8],
+        presentation: &CredentialPresentation,
+        nonces: &Nonces,
+        index: usize,
+        system_parameters: &SystemParameters,
+    ) -> Result<HashedIdentifierReveal, Error>
+    {
+        if index >= presentation.attributes_blinded.len() || index >= nonces.0.len() {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let attribute: RevealedAttribute = hash_identifier(identifier, &self.salt);
+        let W: RistrettoPoint = opened_attribute_commitment(&attribute, presentation, index);
+
+        let mut transcript = Transcript::new(b"AEONFLUX HASHED IDENTIFIER REVEAL");
+        let secrets = attribute_opening::Secrets {
+            z0: nonces[index].as_witness(),
+        };
+        let publics = attribute_opening::Publics {
+            A: &system_parameters.h,
+            W: &W,
+        };
+
+        transcript.commit_bytes(b"salt", self.salt.0.expose_secret().as_bytes());
+
+        let proof = attribute_opening::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(HashedIdentifierReveal {
+            salt: self.salt.clone(),
+            proof: proof,
+        })
+    }
+}
+
+/// Compute `W = Cm - m * P`, which is `z * A` iff `Cm` is a commitment to `m`.
+fn opened_attribute_commitment(
+    attribute: &RevealedAttribute,
+    presentation: &CredentialPresentation,
+    index: usize,
+) -> RistrettoPoint
+{
+    let Cm: RistrettoPoint = presentation.attributes_blinded[index].into();
+
+    Cm - (attribute * presentation.rerandomized_nonce)
+}
+
+/// The record of a hashed identifier which the issuer stores.
+///
+/// This contains neither the identifier nor the salt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HashedIdentifier {
+    /// The salted hash of the identifier, used as a credential attribute.
+    pub attribute: RevealedAttribute,
+    /// A Pedersen commitment to the user's salt.
+    pub salt_commitment: pedersen::Commitment,
+}
+
+impl HashedIdentifier {
+    pub fn from_bytes(bytes: &[u8]) -> Result<HashedIdentifier, CredentialError> {
+        if bytes.len() != SIZEOF_HASHED_IDENTIFIER {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[00..32]);
+
+        let attribute = Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)?;
+        let salt_commitment = pedersen::Commitment::from_bytes(&bytes[32..64])?;
+
+        Ok(HashedIdentifier { attribute, salt_commitment })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_HASHED_IDENTIFIER);
+
+        v.extend(self.attribute.to_bytes().iter());
+        v.extend(self.salt_commitment.to_bytes());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(HashedIdentifier,
+                                         "A valid byte sequence representing a HashedIdentifier");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(HashedIdentifier);
+impl_try_from_bytes!(HashedIdentifier, CredentialError);
+
+impl HashedIdentifier {
+    /// Check that a user's `opening` opens this record's salt commitment, e.g.
+    /// when auditing a disputed record.
+    pub fn open(
+        &self,
+        opening: &SaltOpening,
+        system_parameters: &SystemParameters,
+    ) -> Result<(), Error>
+    {
+        match self.salt_commitment.open(&(&system_parameters.g * opening.salt.0.expose_secret()),
+                                        &opening.blinding, &system_parameters.h) {
+            Ok(_)  => Ok(()),
+            Err(_) => Err(CredentialError::VerificationFailure.into()),
+        }
+    }
+}
+
+/// The message which a user sends to the issuer, over a private channel, in
+/// order to show that a hashed identifier attribute was computed correctly.
+#[derive(Clone, Debug)]
+pub struct HashedIdentifierRequest {
+    /// The opening of the user's salt commitment.
+    pub opening: SaltOpening,
+    /// The plaintext external identifier.
+    pub identifier: Vec<u8>,
+}
+
+impl HashedIdentifierRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<HashedIdentifierRequest, CredentialError> {
+        if bytes.len() < SIZEOF_SALT_OPENING {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let opening = SaltOpening::from_bytes(&bytes[..SIZEOF_SALT_OPENING])?;
+        let identifier = bytes[SIZEOF_SALT_OPENING..].to_vec();
+
+        Ok(HashedIdentifierRequest { opening, identifier })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_SALT_OPENING + self.identifier.len());
+
+        v.extend(self.opening.to_bytes());
+        v.extend(self.identifier.iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(HashedIdentifierRequest,
+                                         "A valid byte sequence representing a HashedIdentifierRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(HashedIdentifierRequest);
+impl_try_from_bytes!(HashedIdentifierRequest, CredentialError);
+
+impl HashedIdentifierRequest {
+    /// Create a request for a hashed `identifier` with a new random salt.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the `HashedIdentifierRequest` to give to the issuer, and
+    /// the `SaltOpening` which the user must keep.
+    pub fn create<R>(identifier: &[u8], csprng: &mut R) -> (HashedIdentifierRequest, SaltOpening)
+    where
+        R: RngCore + CryptoRng,
+    {
+        let opening = SaltOpening::new(csprng);
+
+        (HashedIdentifierRequest { opening: opening.clone(), identifier: identifier.to_vec() }, opening)
+    }
+
+    /// Called by the issuer to check that a requested `attribute` is the
+    /// salted hash of the identifier.
+    ///
+    /// This consumes the request, so that the identifier and salt are
+    /// dropped once the check is complete.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::BadAttribute` if the `attribute` was not the hash of
+    ///   the identifier with the salt.
+    ///
+    /// # Returns
+    ///
+    /// The `HashedIdentifier` for the issuer to store.
+    pub fn verify(
+        self,
+        attribute: &RevealedAttribute,
+        system_parameters: &SystemParameters,
+    ) -> Result<HashedIdentifier, Error>
+    {
+        let hashed: HashedIdentifier = self.opening.hashed_identifier(&self.identifier, system_parameters);
+
+        if hashed.attribute != *attribute {
+            return Err(CredentialError::BadAttribute.into());
+        }
+
+        Ok(hashed)
+    }
+}
+
+/// Clear the plaintext identifier when the request goes out of scope.
+impl Drop for HashedIdentifierRequest {
+    fn drop(&mut self) {
+        for byte in self.identifier.iter_mut() {
+            *byte = 0;
+        }
+    }
+}
+
+/// A selectively revealed salt, with a proof that a hidden attribute in a
+/// presentation is the hash of a known identifier with that salt.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HashedIdentifierReveal {
+    pub salt: Salt,
+    pub proof: attribute_opening::Proof,
+}
+
+impl HashedIdentifierReveal {
+    pub fn from_bytes(bytes: &[u8]) -> Result<HashedIdentifierReveal, CredentialError> {
+        if bytes.len() < SIZEOF_SALT {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let salt = Salt::from_bytes(&bytes[..SIZEOF_SALT])?;
+        let proof: attribute_opening::Proof = match deserialize(&bytes[SIZEOF_SALT..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(CredentialError::MissingData),
+        };
+
+        Ok(HashedIdentifierReveal { salt, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.salt.to_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(HashedIdentifierReveal,
+                                         "A valid byte sequence representing a HashedIdentifierReveal");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(HashedIdentifierReveal);
+impl_try_from_bytes!(HashedIdentifierReveal, CredentialError);
+
+impl HashedIdentifierReveal {
+    /// Verify that the `index`th hidden attribute in a `presentation` is the
+    /// hash of `identifier` with the revealed salt.
+    ///
+    /// # Note
+    ///
+    /// This only relates the attribute to the presentation.  The verifier
+    /// must still verify the presentation itself, e.g. with `Issuer::verify()`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if the `presentation` had
+    ///   no `index`th attribute.
+    /// * `CredentialError::VerificationFailure` if the proof did not verify.
+    pub fn verify(
+        &self,
+        identifier: &[u8],
+        presentation: &CredentialPresentation,
+        index: usize,
+        system_parameters: &SystemParameters,
+    ) -> Result<(), Error>
+    {
+        if index >= presentation.attributes_blinded.len() {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let attribute: RevealedAttribute = hash_identifier(identifier, &self.salt);
+        let W: RistrettoPoint = opened_attribute_commitment(&attribute, presentation, index);
+
+        let mut transcript = Transcript::new(b"AEONFLUX HASHED IDENTIFIER REVEAL");
+        let publics = attribute_opening::Publics {
+            A: &system_parameters.h,
+            W: &W,
+        };
+
+        transcript.commit_bytes(b"salt", self.salt.0.expose_secret().as_bytes());
+
+        match self.proof.verify(&mut transcript, publics) {
+            Ok(_)  => Ok(()),
+            Err(_) => Err(CredentialError::VerificationFailure.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use credential::CredentialRequest;
+    use issuer::Issuer;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use test_utils::H;
+    use user::User;
+
+    const EMAIL: &'static [u8] = b"alice@example.com";
+
+    #[test]
+    fn hash_identifier_is_salted() {
+        let mut csprng = thread_rng();
+        let salt1 = Salt::random(&mut csprng);
+        let salt2 = Salt::random(&mut csprng);
+
+        assert!(hash_identifier(EMAIL, &salt1) == hash_identifier(EMAIL, &salt1));
+        assert!(hash_identifier(EMAIL, &salt1) != hash_identifier(EMAIL, &salt2));
+        assert!(hash_identifier(EMAIL, &salt1) != hash_identifier(b"bob@example.com", &salt1));
+    }
+
+    #[test]
+    fn hashed_identifier_request_serialize_deserialize() {
+        let mut csprng = thread_rng();
+        let (request, _opening) = HashedIdentifierRequest::create(EMAIL, &mut csprng);
+        let deserialized = HashedIdentifierRequest::from_bytes(&request.to_bytes()).unwrap();
+
+        assert!(deserialized.identifier == request.identifier);
+        assert!(deserialized.opening.salt == request.opening.salt);
+    }
+
+    #[test]
+    fn hashed_identifier_request_bad_attribute() {
+        let mut csprng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let (request, _opening) = HashedIdentifierRequest::create(EMAIL, &mut csprng);
+
+        assert!(request.verify(&Scalar::random(&mut csprng), &system_parameters).is_err());
+    }
+
+    #[test]
+    fn hashed_identifier_issuance_and_reveal() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+        // Alice hashes her email with a secret salt.
+        let (hashed_request, opening) = HashedIdentifierRequest::create(EMAIL, &mut alice_rng);
+        let attribute: RevealedAttribute = hash_identifier(EMAIL, &opening.salt);
+        let request: CredentialRequest = alice.obtain(vec![attribute]);
+
+        // The issuer checks the hashing, and stores only the HashedIdentifier.
+        let record: HashedIdentifier = hashed_request.verify(&request.attributes_revealed[0],
+                                                             &system_parameters).unwrap();
+        let issuance = issuer.issue(&request, &mut issuer_rng).unwrap();
+
+        assert!(record.open(&opening, &system_parameters).is_ok());
+        assert!(HashedIdentifier::from_bytes(&record.to_bytes()).unwrap() == record);
+
+        alice.obtain_finish(Some(&issuance)).unwrap();
+
+        let nonces: Nonces = Nonces::new(&mut alice_rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = alice.show(&nonces, &mut alice_rng).unwrap();
+
+        issuer.verify(&presentation).unwrap();
+
+        // Alice reveals her salt to a verifier who knows her email.
+        let reveal = opening.reveal(EMAIL, &presentation, &nonces, 0, &system_parameters).unwrap();
+        let reveal = HashedIdentifierReveal::from_bytes(&reveal.to_bytes()).unwrap();
+
+        assert!(reveal.verify(EMAIL, &presentation, 0, &system_parameters).is_ok());
+        assert!(reveal.verify(b"bob@example.com", &presentation, 0, &system_parameters).is_err());
+        assert!(reveal.verify(EMAIL, &presentation, 1, &system_parameters).is_err());
+    }
+}
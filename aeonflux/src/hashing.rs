@@ -0,0 +1,152 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Domain-separated hashing.
+//!
+//! Every hash this crate computes outside of a proof transcript, whether to
+//! derive a basepoint for pseudonyms or nullifiers, to map an identifier to
+//! an attribute, or to derive a key, goes through a `Hasher`, which cannot
+//! be constructed without a domain separation label and which produces a
+//! typed output.  This keeps one use of SHA-512 from ever being mistaken for
+//! another.
+//!
+//! The label is hashed first, verbatim, so no label may be a prefix of
+//! another.  Every label in this crate begins with `aeonflux` and ends with
+//! a version, e.g. `b"aeonflux sybil scope v1"`.
+//!
+//! The hash-to-group and hash-to-scalar functions of the VOPRF follow RFC
+//! 9497 to the byte, and so are not computed here.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use encoding::u64_to_bytes;
+
+/// The size of the output of `Hasher::finalize_key`.
+pub const SIZEOF_HASHED_KEY: usize = 32;
+
+/// The size of the output of `Hasher::finalize_digest`.
+pub const SIZEOF_DIGEST: usize = 64;
+
+/// A SHA-512 hash, which is bound to a domain separation label on creation.
+#[derive(Clone)]
+pub struct Hasher(Sha512);
+
+impl Hasher {
+    /// Begin a hash within the domain named by `label`.
+    pub fn new(label: &'static [u8]) -> Hasher {
+        let mut hash = Sha512::default();
+
+        hash.input(label);
+
+        Hasher(hash)
+    }
+
+    /// Append some `bytes` to the hash input.
+    pub fn input(&mut self, bytes: &[u8]) -> &mut Hasher {
+        self.0.input(bytes);
+        self
+    }
+
+    /// Append `bytes`, preceded by their length as a single byte.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is longer than 255 bytes.
+    pub fn input_u8_prefixed(&mut self, bytes: &[u8]) -> &mut Hasher {
+        assert!(bytes.len() <= 255);
+
+        self.0.input(&[bytes.len() as u8][..]);
+        self.0.input(bytes);
+        self
+    }
+
+    /// Append `x` as eight little-endian bytes.
+    pub fn input_u64(&mut self, x: u64) -> &mut Hasher {
+        self.0.input(&u64_to_bytes(x)[..]);
+        self
+    }
+
+    /// Append `x` as four little-endian bytes.
+    pub fn input_u32(&mut self, x: u32) -> &mut Hasher {
+        let mut bytes = [0u8; 4];
+
+        for i in 0..4 {
+            bytes[i] = (x >> (8 * i)) as u8;
+        }
+        self.0.input(&bytes[..]);
+        self
+    }
+
+    /// Reduce the hash to a uniformly distributed scalar.
+    pub fn finalize_scalar(self) -> Scalar {
+        Scalar::from_hash(self.0)
+    }
+
+    /// Map the hash to a point, whose discrete log relative to any other is
+    /// unknown.
+    pub fn finalize_point(self) -> RistrettoPoint {
+        RistrettoPoint::from_hash(self.0)
+    }
+
+    /// Truncate the hash to a 32-byte key or identifier.
+    pub fn finalize_key(self) -> [u8; SIZEOF_HASHED_KEY] {
+        let mut key = [0u8; SIZEOF_HASHED_KEY];
+
+        key.copy_from_slice(&self.0.result()[..SIZEOF_HASHED_KEY]);
+        key
+    }
+
+    /// The full 64-byte hash.
+    pub fn finalize_digest(self) -> [u8; SIZEOF_DIGEST] {
+        let mut digest = [0u8; SIZEOF_DIGEST];
+
+        digest.copy_from_slice(&self.0.result()[..]);
+        digest
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::vec::Vec;
+
+    #[test]
+    fn outputs_match_prefixed_sha512() {
+        let mut input: Vec<u8> = Vec::new();
+
+        input.extend(b"aeonflux test v1".iter());
+        input.extend(b"hello".iter());
+        input.extend(u64_to_bytes(7).iter());
+
+        let mut h = Hasher::new(b"aeonflux test v1");
+
+        h.input(b"hello").input_u64(7);
+
+        assert!(h.clone().finalize_scalar() == Scalar::hash_from_bytes::<Sha512>(&input));
+        assert!(h.clone().finalize_point() == RistrettoPoint::hash_from_bytes::<Sha512>(&input));
+        assert!(&h.clone().finalize_digest()[..] == &Sha512::digest(&input)[..]);
+        assert!(&h.finalize_key()[..] == &Sha512::digest(&input)[..SIZEOF_HASHED_KEY]);
+    }
+
+    #[test]
+    fn labels_separate_domains() {
+        let mut a = Hasher::new(b"aeonflux test a v1");
+        let mut b = Hasher::new(b"aeonflux test b v1");
+
+        a.input(b"same");
+        b.input(b"same");
+
+        assert!(a.finalize_point() != b.finalize_point());
+    }
+}
@@ -0,0 +1,475 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Presentation verification without an allocator.
+//!
+//! A verifier with no heap, such as the firmware of a badge reader, is
+//! provisioned with a `HeaplessVerifier`, which is the part of an `Issuer`
+//! needed to check presentations, and decodes each encoded
+//! `CredentialPresentation` it receives into a `HeaplessPresentation`, whose
+//! attributes are held in arrays sized by the fixed numbers of attributes.
+//! Everything here builds with neither the `std` nor the `alloc` feature,
+//! e.g. with
+//!
+//! ```text
+//! cargo build --no-default-features --features "nightly u32_backend"
+//! ```
+//!
+//! A `HeaplessVerifier` holds the issuer's secret key, and so the reader
+//! holding one must be provisioned and protected as carefully as the issuer.
+//!
+//! Only verification is heapless.  The holder's side, from `user` and
+//! `credential` down to `nonces`, `amacs`, `elgamal`, and `pedersen`, and the
+//! `issuer`, still need the `alloc` feature.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use amacs;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use credential::CredentialPresentation;
+use errors::CredentialError;
+use errors::Error;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use issuer::Issuer;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES;
+use parameters::PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+use parameters::SIZEOF_SYSTEM_PARAMETERS;
+use parameters::SystemParameters;
+use proofs::valid_credential;
+use secret::Clear;
+use secret::REDACTED;
+
+/// The size of an encoded `CredentialPresentation`, which is also that of a
+/// `HeaplessPresentation`.
+pub const SIZEOF_HEAPLESS_PRESENTATION: usize = 64 + 32 * (PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES +
+                                                           PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES) +
+                                                valid_credential::SIZEOF_PROOF;
+
+/// The size of an encoded `HeaplessVerifier`.
+pub const SIZEOF_HEAPLESS_VERIFIER: usize = SIZEOF_SYSTEM_PARAMETERS + 32 + 32 * NUMBER_OF_ATTRIBUTES + 32;
+
+pub(crate) fn decompress(bytes: &[u8]) -> Result<RistrettoPoint, CredentialError> {
+    CompressedRistretto::from_slice(bytes).decompress().ok_or(CredentialError::PointDecompressionError)
+}
+
+pub(crate) fn canonical_scalar(bytes: &[u8]) -> Result<Scalar, CredentialError> {
+    let mut tmp: [u8; 32] = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)
+}
+
+/// A `CredentialPresentation` whose attributes are held in fixed-size arrays.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HeaplessPresentation {
+    pub rerandomized_mac_commitment: RistrettoPoint,
+    pub rerandomized_nonce: RistrettoPoint,
+    pub attributes_revealed: [Scalar; PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES],
+    pub attributes_blinded: [RistrettoPoint; PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES],
+    pub proof: valid_credential::Proof,
+}
+
+impl HeaplessPresentation {
+    /// Decode the encoding of a `CredentialPresentation`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfBytes` if `bytes` are not
+    ///   `SIZEOF_HEAPLESS_PRESENTATION` bytes long.
+    /// * `CredentialError::PointDecompressionError` or
+    ///   `CredentialError::ScalarFormatError` if a field could not be decoded.
+    /// * `CredentialError::VerificationFailure` if the nonce is the identity.
+    /// * `CredentialError::MissingData` if the proof could not be decoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<HeaplessPresentation, CredentialError> {
+        if bytes.len() != SIZEOF_HEAPLESS_PRESENTATION {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let rerandomized_mac_commitment: RistrettoPoint = decompress(&bytes[00..32])?;
+        let rerandomized_nonce: RistrettoPoint = decompress(&bytes[32..64])?;
+
+        if rerandomized_nonce == RistrettoPoint::identity() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let mut attributes_revealed = [Scalar::zero(); PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES];
+        let mut attributes_blinded = [rerandomized_nonce; PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES];
+        let mut offset: usize = 64;
+
+        for attribute in attributes_revealed.iter_mut() {
+            *attribute = canonical_scalar(&bytes[offset..offset + 32])?;
+            offset += 32;
+        }
+        for attribute in attributes_blinded.iter_mut() {
+            *attribute = decompress(&bytes[offset..offset + 32])?;
+            offset += 32;
+        }
+
+        let proof = valid_credential::Proof::from_bytes(&bytes[offset..])
+            .or(Err(CredentialError::MissingData))?;
+
+        Ok(HeaplessPresentation {
+            rerandomized_mac_commitment,
+            rerandomized_nonce,
+            attributes_revealed,
+            attributes_blinded,
+            proof,
+        })
+    }
+
+    /// Encode this presentation as a `CredentialPresentation` would be.
+    pub fn to_bytes(&self) -> [u8; SIZEOF_HEAPLESS_PRESENTATION] {
+        let mut bytes: [u8; SIZEOF_HEAPLESS_PRESENTATION] = [0u8; SIZEOF_HEAPLESS_PRESENTATION];
+        let mut offset: usize = 64;
+
+        bytes[00..32].copy_from_slice(self.rerandomized_mac_commitment.compress().as_bytes());
+        bytes[32..64].copy_from_slice(self.rerandomized_nonce.compress().as_bytes());
+
+        for attribute in self.attributes_revealed.iter() {
+            bytes[offset..offset + 32].copy_from_slice(attribute.as_bytes());
+            offset += 32;
+        }
+        for attribute in self.attributes_blinded.iter() {
+            bytes[offset..offset + 32].copy_from_slice(attribute.compress().as_bytes());
+            offset += 32;
+        }
+        bytes[offset..].copy_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+}
+
+impl_try_from_bytes!(HeaplessPresentation, CredentialError);
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> ::core::convert::TryFrom<&'a CredentialPresentation> for HeaplessPresentation {
+    type Error = CredentialError;
+
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if the `presentation`
+    ///   does not have the fixed numbers of revealed and blinded attributes.
+    fn try_from(presentation: &'a CredentialPresentation) -> Result<HeaplessPresentation, CredentialError> {
+        if presentation.attributes_revealed.len() != PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES ||
+           presentation.attributes_blinded.len() != PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut attributes_revealed = [Scalar::zero(); PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES];
+        let mut attributes_blinded = [RistrettoPoint::identity(); PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES];
+
+        attributes_revealed.copy_from_slice(&presentation.attributes_revealed[..]);
+
+        for (index, attribute) in presentation.attributes_blinded.iter().enumerate() {
+            attributes_blinded[index] = (*attribute).into();
+        }
+
+        Ok(HeaplessPresentation {
+            rerandomized_mac_commitment: presentation.rerandomized_mac_commitment.into(),
+            rerandomized_nonce: presentation.rerandomized_nonce,
+            attributes_revealed,
+            attributes_blinded,
+            proof: presentation.proof.clone(),
+        })
+    }
+}
+
+/// Everything an `Issuer` uses to verify a presentation, in fixed-size
+/// arrays.
+#[derive(Clone)]
+pub struct HeaplessVerifier {
+    system_parameters: SystemParameters,
+    x0: Scalar,
+    xn: [Scalar; NUMBER_OF_ATTRIBUTES],
+    X0: RistrettoPoint,
+}
+
+/// The secret scalars are redacted.
+impl fmt::Debug for HeaplessVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HeaplessVerifier {{ system_parameters: {:?}, x0: {}, xn: {}, X0: {:?} }}",
+               self.system_parameters, REDACTED, REDACTED, self.X0)
+    }
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for HeaplessVerifier {
+    fn drop(&mut self) {
+        self.x0.clear();
+
+        for scalar in self.xn.iter_mut() {
+            scalar.clear();
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> ::core::convert::TryFrom<&'a Issuer> for HeaplessVerifier {
+    type Error = CredentialError;
+
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if the `issuer`'s key is
+    ///   not for `NUMBER_OF_ATTRIBUTES` attributes.
+    fn try_from(issuer: &'a Issuer) -> Result<HeaplessVerifier, CredentialError> {
+        if issuer.keypair.secret.xn.len() != NUMBER_OF_ATTRIBUTES ||
+           issuer.keypair.public.Xn.is_empty() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut xn = [Scalar::zero(); NUMBER_OF_ATTRIBUTES];
+
+        xn.copy_from_slice(&issuer.keypair.secret.xn[..]);
+
+        Ok(HeaplessVerifier {
+            system_parameters: issuer.system_parameters,
+            x0: issuer.keypair.secret.x0,
+            xn,
+            X0: issuer.keypair.public.Xn[0],
+        })
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl HeaplessVerifier {
+    /// The `Issuer` whose key this verifier holds, e.g. to issue the
+    /// credential of `HeaplessVerifier::self_check()`.
+    ///
+    /// Its public key is the one this verifier was provisioned with, rather
+    /// than one recomputed from the secret key, so that a mismatch between
+    /// them is caught by the issuer's own key check.
+    pub(crate) fn to_issuer(&self) -> Issuer {
+        let secret = amacs::SecretKey { x0: self.x0, xn: self.xn.to_vec() };
+        let mut public = secret.get_public_key(&self.system_parameters.h);
+
+        public.Xn[0] = self.X0;
+
+        Issuer::new(self.system_parameters, amacs::Keypair { public, secret })
+    }
+}
+
+impl HeaplessVerifier {
+    /// Decode a `HeaplessVerifier`, e.g. as provisioned into a reader's flash.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfBytes` if `bytes` are not
+    ///   `SIZEOF_HEAPLESS_VERIFIER` bytes long.
+    /// * `CredentialError::PointDecompressionError` or
+    ///   `CredentialError::ScalarFormatError` if a field could not be decoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<HeaplessVerifier, CredentialError> {
+        if bytes.len() != SIZEOF_HEAPLESS_VERIFIER {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let system_parameters = SystemParameters::from_bytes(&bytes[..SIZEOF_SYSTEM_PARAMETERS])?;
+        let mut offset: usize = SIZEOF_SYSTEM_PARAMETERS;
+        let x0: Scalar = canonical_scalar(&bytes[offset..offset + 32])?;
+        let mut xn = [Scalar::zero(); NUMBER_OF_ATTRIBUTES];
+
+        offset += 32;
+
+        for scalar in xn.iter_mut() {
+            *scalar = canonical_scalar(&bytes[offset..offset + 32])?;
+            offset += 32;
+        }
+
+        let X0: RistrettoPoint = decompress(&bytes[offset..offset + 32])?;
+
+        Ok(HeaplessVerifier { system_parameters, x0, xn, X0 })
+    }
+
+    /// Encode this verifier.  The result holds the issuer's secret key.
+    pub fn to_bytes(&self) -> [u8; SIZEOF_HEAPLESS_VERIFIER] {
+        let mut bytes: [u8; SIZEOF_HEAPLESS_VERIFIER] = [0u8; SIZEOF_HEAPLESS_VERIFIER];
+        let mut offset: usize = 64;
+
+        bytes[00..32].copy_from_slice(self.system_parameters.g.compress().as_bytes());
+        bytes[32..64].copy_from_slice(self.system_parameters.h.compress().as_bytes());
+        bytes[offset..offset + 32].copy_from_slice(self.x0.as_bytes());
+        offset += 32;
+
+        for scalar in self.xn.iter() {
+            bytes[offset..offset + 32].copy_from_slice(scalar.as_bytes());
+            offset += 32;
+        }
+        bytes[offset..offset + 32].copy_from_slice(self.X0.compress().as_bytes());
+        bytes
+    }
+
+    /// Verify a `presentation`, exactly as `Issuer::verify` would.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::VerificationFailure` if its nonce is the identity.
+    /// * `CredentialError::MacVerification` if the presentation is invalid.
+    pub fn verify(&self, presentation: &HeaplessPresentation) -> Result<(), Error> {
+        let mut x: Scalar = Scalar::zero();
+        let mut V_prime: RistrettoPoint = RistrettoPoint::identity();
+
+        self.verify_parts(&presentation.rerandomized_mac_commitment,
+                          &presentation.rerandomized_nonce,
+                          &presentation.attributes_revealed,
+                          &presentation.attributes_blinded,
+                          &presentation.proof,
+                          &mut x, &mut V_prime)
+    }
+
+    /// Verify the decoded fields of a presentation, using `x` and `V_prime`
+    /// as space for the recomputed MAC.  `x` is cleared afterwards.
+    pub(crate) fn verify_parts(
+        &self,
+        rerandomized_mac_commitment: &RistrettoPoint,
+        P: &RistrettoPoint,
+        attributes_revealed: &[Scalar],
+        attributes_blinded: &[RistrettoPoint],
+        proof: &valid_credential::Proof,
+        x: &mut Scalar,
+        V_prime: &mut RistrettoPoint,
+    ) -> Result<(), Error>
+    {
+        // Every key gives the identity a valid MAC.
+        if *P == RistrettoPoint::identity() {
+            return Err(CredentialError::VerificationFailure.into());
+        }
+
+        // As in `Issuer::verify`, the MAC is recomputed in constant time.
+        *x = self.x0;
+
+        for (index, attribute) in attributes_revealed.iter().enumerate() {
+            *x += &self.xn[index] * attribute;
+        }
+
+        *V_prime = &*x * P;
+
+        for (index, attribute) in attributes_blinded.iter().enumerate() {
+            *V_prime += &self.xn[index] * attribute;
+        }
+        *V_prime -= rerandomized_mac_commitment;
+
+        x.clear();
+
+        let mut transcript = Transcript::new(b"AEONFLUX SHOW");
+        let publics = valid_credential::Publics {
+            B: &self.system_parameters.g,
+            A: &self.system_parameters.h,
+            X0: &self.X0,
+            P: P,
+            V: &*V_prime,
+            Cm0: &attributes_blinded[0],
+        };
+
+        proof.verify(&mut transcript, publics).or(Err(CredentialError::MacVerification.into()))
+    }
+}
+
+impl_try_from_bytes!(HeaplessVerifier, CredentialError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::convert::TryFrom;
+    use std::vec::Vec;
+
+    use bincode::serialize;
+
+    use rand::thread_rng;
+
+    use credential::RevealedAttribute;
+    use nonces::Nonces;
+    use test_utils::H;
+    use test_utils::identity_forgery;
+    use user::User;
+
+    #[test]
+    fn heapless_verification_matches_issuer() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+        attributes.push(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(&user.obtain(attributes), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let presentation = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let encoded: Vec<u8> = presentation.to_bytes();
+        let heapless = HeaplessPresentation::from_bytes(&encoded).unwrap();
+
+        assert!(heapless == HeaplessPresentation::try_from(&presentation).unwrap());
+        assert!(&heapless.to_bytes()[..] == &encoded[..]);
+        assert!(&heapless.proof.to_bytes()[..] == &serialize(&presentation.proof).unwrap()[..]);
+
+        let verifier = HeaplessVerifier::from_bytes(&HeaplessVerifier::try_from(&issuer).unwrap().to_bytes()).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert!(verifier.verify(&heapless).is_ok());
+
+        let other = HeaplessVerifier::try_from(&Issuer::create(system_parameters, &mut rng)).unwrap();
+
+        assert!(other.verify(&heapless) == Err(CredentialError::MacVerification.into()));
+    }
+
+    #[test]
+    fn wrong_numbers_of_attributes_are_rejected() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let mut issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let presentation = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let mut too_many = presentation.clone();
+        let mut too_few = presentation.clone();
+
+        too_many.attributes_revealed.push(Scalar::random(&mut rng));
+        too_few.attributes_blinded.clear();
+
+        assert!(HeaplessPresentation::try_from(&too_many) == Err(CredentialError::WrongNumberOfAttributes));
+        assert!(HeaplessPresentation::try_from(&too_few) == Err(CredentialError::WrongNumberOfAttributes));
+
+        issuer.keypair.secret.xn.push(Scalar::random(&mut rng));
+
+        assert!(HeaplessVerifier::try_from(&issuer).err() == Some(CredentialError::WrongNumberOfAttributes));
+
+        issuer.keypair.secret.xn.clear();
+
+        assert!(HeaplessVerifier::try_from(&issuer).err() == Some(CredentialError::WrongNumberOfAttributes));
+    }
+
+    #[test]
+    fn identity_nonces_are_rejected() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let forged = identity_forgery(&issuer, &mut rng);
+        let verifier = HeaplessVerifier::try_from(&issuer).unwrap();
+        let heapless = HeaplessPresentation::try_from(&forged).unwrap();
+
+        assert!(verifier.verify(&heapless) == Err(CredentialError::VerificationFailure.into()));
+        assert!(HeaplessPresentation::from_bytes(&forged.to_bytes()) == Err(CredentialError::VerificationFailure));
+    }
+}
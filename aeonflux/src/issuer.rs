@@ -13,11 +13,15 @@ use alloc::vec::Vec;
 use std::vec::Vec;
 
 use amacs;
+use channel_binding::ChannelBinding;
+use channel_binding::bind_channel;
+use ciphertexts;
 pub use amacs::PublicKey as IssuerParameters;
 pub use amacs::SecretKey as IssuerSecretKey;
 
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
 
 use merlin::Transcript;
 
@@ -27,17 +31,58 @@ use rand_core::CryptoRng;
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
+use clock::Clock;
+use credential::BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES;
 use credential::Credential;
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
 use credential::CredentialIssuance;
 use credential::CredentialRequest;
 use credential::CredentialPresentation;
+use credential::PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES;
+use credential::PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+use credential::EncryptedAttribute;
+use credential::PresentationOptions;
 use credential::VerifiedCredential;
+use elgamal;
+use encoding::u64_to_bytes;
 use errors::CredentialError;
+use errors::Error;
+use freshness::bind_timestamp;
+use hashing::Hasher;
 use nonces::Ephemeral;
 use parameters::SystemParameters;
 use pedersen::{self};
+use precomputed::PrecomputedIssuerParameters;
+use proofs::attributes_blinded;
+use proofs::issuance_blinded;
 use proofs::issuance_revealed;
 use proofs::valid_credential;
+use schema::bind_schema;
+use schema::SchemaId;
+use secret::Clear;
+
+/// An issuer's key material for issuing under some schema, with its
+/// commitment to it, made once for any number of issuances.
+struct IssuanceKey {
+    X1: RistrettoPoint,
+    x1: Scalar,
+    /// The issuer's `x0`, moved under the schema.
+    x0: Scalar,
+    tweak: Scalar,
+    x0_tilde: Ephemeral,
+    Cx0: pedersen::Commitment,
+}
+
+/// Overwrite the secret key material with null bytes once the issuances are
+/// made.
+impl Drop for IssuanceKey {
+    fn drop(&mut self) {
+        self.x0.clear();
+        self.x1.clear();
+        self.tweak.clear();
+    }
+}
 
 /// An issuer and honest verifier of `Credential`s.
 #[repr(C)]
@@ -46,6 +91,8 @@ pub struct Issuer {
     pub system_parameters: SystemParameters,
     /// The issuer's aMAC key material.
     pub keypair: amacs::Keypair,
+    /// Basepoint tables for the system parameters and our public key.
+    pub precomputed: PrecomputedIssuerParameters,
 }
 
 impl Issuer {
@@ -57,7 +104,7 @@ impl Issuer {
         let system_parameters = SystemParameters::from_bytes(&bytes[..64])?;
         let keypair = amacs::Keypair::from_bytes(&bytes[64..])?;
 
-        Ok(Issuer{ system_parameters, keypair })
+        Ok(Issuer::new(system_parameters, keypair))
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -71,6 +118,9 @@ impl Issuer {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(Issuer, "A valid byte sequence representing an Issuer");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Issuer);
+impl_try_from_bytes!(Issuer, CredentialError);
 
 impl Issuer {
     /// Create a new `Issuer` from some agreed upon `system_parameters`.
@@ -92,16 +142,19 @@ impl Issuer {
     {
         let keypair = amacs::Keypair::new(&system_parameters.h, csprng);
 
-        Issuer { system_parameters, keypair }
+        Issuer::new(system_parameters, keypair)
     }
 
-    /// Initialize an `Issuer`.
+    /// Initialize an `Issuer`, precomputing basepoint tables for its
+    /// parameters.
     pub fn new(
         system_parameters: SystemParameters,
         keypair: amacs::Keypair,
     ) -> Self
     {
-        Issuer { system_parameters, keypair }
+        let precomputed = PrecomputedIssuerParameters::new(&system_parameters, &keypair.public);
+
+        Issuer { system_parameters, keypair, precomputed }
     }
 
     /// Get this `Issuer`s parameters for publishing to users.
@@ -134,7 +187,91 @@ impl Issuer {
     ///
     /// A `CredentialIssuance` upon successful issuance.
     pub fn issue<R>(&self, request: &CredentialRequest, rng: &mut R)
-        -> Result<CredentialIssuance, CredentialError>
+        -> Result<CredentialIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.issue_under(request, None, rng)
+    }
+
+    /// As `Issuer::issue()`, but for a credential under a `schema`.
+    ///
+    /// The schema is bound into the issuance proof, and the aMAC is made
+    /// with a secret key for that schema alone, so that the credential
+    /// verifies with `Issuer::verify_with_schema()` for the same schema, and
+    /// never for another, even one with the same number of attributes.
+    pub fn issue_with_schema<R>(&self, request: &CredentialRequest, schema: &SchemaId, rng: &mut R)
+        -> Result<CredentialIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.issue_under(request, Some(schema), rng)
+    }
+
+    /// The part of the aMAC key which differs between schemas: zero for
+    /// credentials without one, and otherwise a hash of `x0` and the schema,
+    /// so that no one without `x0` can move a credential between them.
+    fn schema_tweak(&self, schema: Option<&SchemaId>) -> Scalar {
+        match schema {
+            None     => Scalar::zero(),
+            Some(id) => {
+                let mut h = Hasher::new(b"aeonflux schema key v1");
+
+                h.input(self.keypair.secret.x0.as_bytes()).input(&id.0[..]);
+                h.finalize_scalar()
+            },
+        }
+    }
+
+    /// Issue a credential for each of `requests`, as `Issuer::issue()`, for
+    /// enrolling many members at once.
+    ///
+    /// The issuer's key material is looked up, moved under the schema, and
+    /// committed to only once for the whole batch, and every issuance shares
+    /// the same `secret_key_commitment`.  Each issuance still carries its own
+    /// proof, since a single proof over the batch could only be checked by
+    /// someone holding every member's credential.  For spreading a batch
+    /// across cores instead, see `batch::issue_batch()`.
+    ///
+    /// # Errors
+    ///
+    /// As `Issuer::issue()`.  No credentials are issued if any request
+    /// fails.
+    pub fn issue_batch<R>(&self, requests: &[CredentialRequest], rng: &mut R)
+        -> Result<Vec<CredentialIssuance>, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.issue_batch_under(requests, None, rng)
+    }
+
+    /// As `Issuer::issue_batch()`, but for credentials under a `schema`.
+    pub fn issue_batch_with_schema<R>(&self, requests: &[CredentialRequest], schema: &SchemaId, rng: &mut R)
+        -> Result<Vec<CredentialIssuance>, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.issue_batch_under(requests, Some(schema), rng)
+    }
+
+    fn issue_batch_under<R>(&self, requests: &[CredentialRequest], schema: Option<&SchemaId>, rng: &mut R)
+        -> Result<Vec<CredentialIssuance>, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        trace_span!("issue_batch", batch = requests.len(), schema = schema.is_some());
+
+        let key: IssuanceKey = self.issuance_key(schema, rng)?;
+        let mut issuances: Vec<CredentialIssuance> = Vec::with_capacity(requests.len());
+
+        for request in requests.iter() {
+            issuances.push(self.issue_with_key(request, schema, &key, rng)?);
+        }
+        Ok(issuances)
+    }
+
+    /// Look up and commit to our key material for issuing under `schema`.
+    fn issuance_key<R>(&self, schema: Option<&SchemaId>, rng: &mut R) -> Result<IssuanceKey, CredentialError>
     where
         R: RngCore + CryptoRng,
     {
@@ -147,41 +284,78 @@ impl Issuer {
             Some(x) => *x,
             None => return Err(CredentialError::NoIssuerKey),
         };
+        let tweak: Scalar = self.schema_tweak(schema);
+        let x0: Scalar = &self.keypair.secret.x0 + &tweak;
+
+        // Choose a blinding factor, x~0
+        let x0_tilde: Ephemeral = Ephemeral::new(rng);
+
+        // Construct a commitment to the issuer secret key
+        let Cx0 = pedersen::Commitment::to_precomputed(
+            &self.precomputed.mul(&self.system_parameters.g, &x0),
+            &x0_tilde, &self.system_parameters.h, &self.precomputed);
+
+        Ok(IssuanceKey { X1, x1, x0, tweak, x0_tilde, Cx0 })
+    }
 
+    fn issue_under<R>(&self, request: &CredentialRequest, schema: Option<&SchemaId>, rng: &mut R)
+        -> Result<CredentialIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        trace_span!("issue", revealed = request.attributes_revealed.len(), schema = schema.is_some());
+
+        let key: IssuanceKey = self.issuance_key(schema, rng)?;
+        let issuance = self.issue_with_key(request, schema, &key, rng);
+
+        trace_result!(issuance, encoded);
+
+        issuance
+    }
+
+    fn issue_with_key<R>(
+        &self,
+        request: &CredentialRequest,
+        schema: Option<&SchemaId>,
+        key: &IssuanceKey,
+        rng: &mut R,
+    ) -> Result<CredentialIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
         let attributes = &request.attributes_revealed;
 
         // Create a transcript and feed the context into it
         let mut transcript = Transcript::new(b"AEONFLUX ISSUANCE");
+
+        bind_schema(&mut transcript, schema);
+
         let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
 
-        // Calculate (u, u'), i.e. (nonce, mac)
-        let tag: amacs::Tag = self.keypair.secret.mac(&attributes.clone().into(), &mut csprng)
+        // Calculate (u, u'), i.e. (nonce, mac), and move the mac under the
+        // schema's key, i.e. from (x0 + ...) * u to (x0 + k) * u.
+        let mut tag: amacs::Tag = self.keypair.secret.mac_scalars(attributes, &mut csprng)
             .or(Err(CredentialError::MacCreation))?;
 
-        // Choose a blinding factor, x~0
-        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
-
-        // Construct a commitment to the issuer secret key
-        let Cx0 = pedersen::Commitment::to(&(&self.system_parameters.g * &self.keypair.secret.x0),
-                                           &x0_tilde, &self.system_parameters.h);
-        // XXX Could speed up the above by multiscalar_mul and generating a basepoint table
+        tag.mac += &key.tweak * &tag.nonce;
 
         // Construct the NIZK proof of correct issuance
         let secrets = issuance_revealed::Secrets {
-            x0: &self.keypair.secret.x0,
-            x1: &x1,
-            x0_tilde: (&x0_tilde).into(),
-            m1x1: &(&attributes[0] * &x1),
+            x0: &key.x0,
+            x1: &key.x1,
+            x0_tilde: key.x0_tilde.as_witness(),
+            m1x1: &(&attributes[0] * &key.x1),
         };
         let publics = issuance_revealed::Publics {
             P: &tag.nonce,
             Q: &tag.mac,
-            Cx0: &Cx0.into(),
+            Cx0: &key.Cx0.into(),
             B: &self.system_parameters.g,
             A: &self.system_parameters.h,
-            X1: &X1,
+            X1: &key.X1,
         };
         let proof = issuance_revealed::Proof::create(&mut transcript, publics, secrets);
+
         let cred = Credential {
             mac: tag.clone(),
             attributes: attributes.clone(),
@@ -190,28 +364,298 @@ impl Issuer {
         Ok(CredentialIssuance{
             proof: proof,
             credential: cred,
+            secret_key_commitment: key.Cx0,
+        })
+    }
+
+    /// Blinded credential issuance.
+    ///
+    /// The issuer never learns the plaintext of the user's blinded
+    /// attributes.  Instead, it computes the aMAC homomorphically over the
+    /// user's elGamal encryptions of them, and returns the aMAC encrypted to
+    /// the user's public key along with a proof that it did so correctly.
+    ///
+    /// # Inputs
+    ///
+    /// * `request` is a `CredentialBlindRequest` containing exactly
+    ///   `BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES` encrypted attributes and
+    ///   a proof that they are well-formed.
+    ///
+    /// # Errors
+    ///
+    /// This method may return the following errors:
+    ///
+    /// * `CredentialError::NoIssuerParameters` or
+    ///   `CredentialError::NoIssuerKey` if this `Issuer`'s key material was
+    ///   not the correct length.
+    /// * `CredentialError::WrongNumberOfAttributes` if the `request` had any
+    ///   revealed attributes or the wrong number of blinded ones.
+    /// * `CredentialError::MissingData` if the `request` had no
+    ///   `attributes_blinded_proof`.
+    /// * `CredentialError::VerificationFailure` if the `request`'s
+    ///   `attributes_blinded_proof` couldn't be verified.
+    ///
+    /// # Returns
+    ///
+    /// A `CredentialBlindIssuance` upon successful issuance.
+    pub fn blind_issue<R>(&self, request: &CredentialBlindRequest, rng: &mut R)
+        -> Result<CredentialBlindIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        trace_span!("blind_issue", encrypted = request.attributes_blinded.as_ref().map_or(0, |x| x.len()));
+
+        let issuance = self.issue_blinded(request, rng);
+
+        trace_result!(issuance, encoded);
+
+        issuance
+    }
+
+    fn issue_blinded<R>(&self, request: &CredentialBlindRequest, rng: &mut R)
+        -> Result<CredentialBlindIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        // Obtain our needed public and secret key material.
+        let X1: RistrettoPoint = match self.keypair.public.Xn.get(0) {
+            Some(x) => *x,
+            None => return Err(CredentialError::NoIssuerParameters.into()),
+        };
+        let x1: Scalar = match self.keypair.secret.xn.get(0) {
+            Some(x) => *x,
+            None => return Err(CredentialError::NoIssuerKey.into()),
+        };
+
+        if let Some(ref revealed) = request.attributes_revealed {
+            if revealed.len() != 0 {
+                return Err(CredentialError::WrongNumberOfAttributes.into());
+            }
+        }
+        let encrypted_attributes: &Vec<EncryptedAttribute> = match request.attributes_blinded {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::WrongNumberOfAttributes.into()),
+        };
+        if encrypted_attributes.len() != BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+        let attributes_blinded_proof: &attributes_blinded::Proof = match request.attributes_blinded_proof {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+        let D: RistrettoPoint = request.public_key.into();
+        let E0: &elgamal::Encryption = &encrypted_attributes[0];
+
+        // Check that the ciphertexts were correctly formed and made with the
+        // user's public key.
+        let mut request_transcript = Transcript::new(b"AEONFLUX BLIND REQUEST");
+
+        ciphertexts::verify(&mut request_transcript, &self.system_parameters.g, &request.public_key,
+                            encrypted_attributes, attributes_blinded_proof)?;
+
+        // Create a transcript and feed the context into it
+        let mut transcript = Transcript::new(b"AEONFLUX BLIND ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        // Choose the aMAC nonce, P = b * B, and the randomness for encrypting
+        // the aMAC to the user.
+        let b: Ephemeral = Ephemeral::new(&mut csprng);
+        let s: Ephemeral = Ephemeral::new(&mut csprng);
+        let P: RistrettoPoint = self.precomputed.mul(&self.system_parameters.g, b.as_scalar());
+
+        // Form the auxiliary commitment T1 = b * X1 = t1 * A, which links the
+        // aMAC nonce to the issuer's public key.
+        let t1: Ephemeral = (b.as_scalar() * &x1).into();
+        let T1: RistrettoPoint = self.precomputed.mul(&X1, b.as_scalar());
+
+        // Use the additive homomorphism in elGamal encryption to compute the
+        // aMAC over the encrypted attribute:
+        //
+        //     Enc_D(Q) = (s * B + t1 * E0_0, s * D + t1 * E0_1 + x0 * P)
+        //
+        // which decrypts to Q = (x0 + x1 * m0) * P.
+        let encrypted_mac = elgamal::Encryption {
+            commitment: self.precomputed.mul(&self.system_parameters.g, s.as_scalar()) + (&E0.commitment * &t1),
+            encryption: (&D * &s) + (&E0.encryption * &t1) + (&P * &self.keypair.secret.x0),
+        };
+
+        // Choose a blinding factor, x~0, and commit to the issuer secret key
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let Cx0 = pedersen::Commitment::to_precomputed(
+            &self.precomputed.mul(&self.system_parameters.g, &self.keypair.secret.x0),
+            &x0_tilde, &self.system_parameters.h, &self.precomputed);
+
+        // Construct the NIZK proof of correct blind issuance
+        let secrets = issuance_blinded::Secrets {
+            x0: &self.keypair.secret.x0,
+            x1: &x1,
+            x0_tilde: x0_tilde.as_witness(),
+            b: b.as_witness(),
+            s: s.as_witness(),
+            t1: t1.as_witness(),
+        };
+        let publics = issuance_blinded::Publics {
+            B: &self.system_parameters.g,
+            A: &self.system_parameters.h,
+            X1: &X1,
+            D: &D,
+            P: &P,
+            T1_0: &T1,
+            T1_1: &T1,
+            Cx0: &Cx0.into(),
+            EQ_commitment: &encrypted_mac.commitment,
+            EQ_encryption: &encrypted_mac.encryption,
+            encrypted_attribute_0_0: &E0.commitment,
+            encrypted_attribute_0_1: &E0.encryption,
+        };
+        let proof = issuance_blinded::Proof::create(&mut transcript, publics, secrets);
+        let mut auxiliary_commitments: Vec<RistrettoPoint> = Vec::with_capacity(1);
+
+        auxiliary_commitments.push(T1);
+
+        Ok(CredentialBlindIssuance {
+            proof: proof,
             secret_key_commitment: Cx0,
+            blinding_commitment: P,
+            auxiliary_commitments: auxiliary_commitments,
+            encrypted_mac: encrypted_mac,
+            attributes_revealed: Vec::with_capacity(0),
+            encrypted_attributes: encrypted_attributes.clone(),
         })
     }
 
     pub fn verify(&self, presentation: &CredentialPresentation)
-        -> Result<VerifiedCredential, CredentialError>
+        -> Result<VerifiedCredential, Error>
+    {
+        self.verify_with(presentation, &PresentationOptions::new(), None)
+    }
+
+    /// As `Issuer::verify()`, but for a credential issued with
+    /// `Issuer::issue_with_schema()` under a `schema`.
+    pub fn verify_with_schema(&self, presentation: &CredentialPresentation, schema: &SchemaId)
+        -> Result<VerifiedCredential, Error>
+    {
+        self.verify_with(presentation, &PresentationOptions::new().schema(schema), None)
+    }
+
+    /// As `Issuer::verify()`, for a presentation made with
+    /// `User::show_scoped()`, which also shows that `tag` was computed from
+    /// the hidden attribute and the scoped `basepoint`.
+    pub fn verify_scoped(&self, presentation: &CredentialPresentation, basepoint: &RistrettoPoint, tag: &RistrettoPoint)
+        -> Result<VerifiedCredential, Error>
     {
+        self.verify_with(presentation, &PresentationOptions::new().scoped(basepoint), Some(tag))
+    }
+
+    /// As `Issuer::verify()`, for a presentation made with `User::show_at()`
+    /// at the given `timestamp`.
+    ///
+    /// Whether the timestamp is recent enough is up to the caller, e.g. with
+    /// a `freshness::FreshnessPolicy`.
+    pub fn verify_at(&self, presentation: &CredentialPresentation, timestamp: u64)
+        -> Result<VerifiedCredential, Error>
+    {
+        self.verify_with(presentation, &PresentationOptions::new().at(timestamp), None)
+    }
+
+    /// As `Issuer::verify()`, for a presentation made with
+    /// `User::show_bound()` over the connection with this `channel` binding.
+    pub fn verify_bound(&self, presentation: &CredentialPresentation, channel: &ChannelBinding)
+        -> Result<VerifiedCredential, Error>
+    {
+        self.verify_with(presentation, &PresentationOptions::new().bound(channel), None)
+    }
+
+    /// As `Issuer::verify()`, for a presentation made with
+    /// `User::show_with()` under the same `options`, and, if they are
+    /// `scoped()`, the `tag` it returned.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if a `tag` is given for options which
+    ///   are not scoped, or none for options which are.
+    /// * `CredentialError::MacVerification` if the presentation was not made
+    ///   from a credential we issued, under these options.
+    pub fn verify_with(
+        &self,
+        presentation: &CredentialPresentation,
+        options: &PresentationOptions,
+        tag: Option<&RistrettoPoint>,
+    ) -> Result<VerifiedCredential, Error>
+    {
+        trace_span!("verify",
+                    size = presentation.to_bytes().len(),
+                    schema = options.schema.is_some(),
+                    scoped = options.scope.is_some(),
+                    fresh = options.timestamp.is_some(),
+                    bound = options.channel.is_some());
+
+        let scoped: Option<valid_credential::ScopedPublics> = match (options.scope, tag) {
+            (Some(basepoint), Some(N)) => Some(valid_credential::ScopedPublics { Hn: basepoint, N: N }),
+            (None, None)               => None,
+            _                          => return Err(CredentialError::MissingData.into()),
+        };
+        let verified = self.verify_presentation(presentation, options, scoped);
+
+        trace_result!(verified);
+
+        verified
+    }
+
+    fn verify_presentation(
+        &self,
+        presentation: &CredentialPresentation,
+        options: &PresentationOptions,
+        scoped: Option<valid_credential::ScopedPublics>,
+    ) -> Result<VerifiedCredential, Error>
+    {
+        // The presentation may have been constructed by anyone, so its
+        // attributes must be counted before they are indexed.
+        if presentation.attributes_revealed.len() != PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES ||
+           presentation.attributes_blinded.len() != PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES
+        {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+        if self.keypair.secret.xn.len() < PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES +
+                                          PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES ||
+           self.keypair.public.Xn.len() == 0
+        {
+            return Err(CredentialError::NoIssuerKey.into());
+        }
+
         let P = presentation.rerandomized_nonce;
 
-        // Recompute the MAC
-        let mut V_prime: RistrettoPoint = &self.keypair.secret.x0 * &P;
+        // Every key gives the identity a valid MAC, so with it the proof
+        // would hold without any credential.
+        if P == RistrettoPoint::identity() {
+            return Err(CredentialError::VerificationFailure.into());
+        }
+
+        // Recompute the MAC.  This uses the secret key, so unlike the proof
+        // verification it must remain constant-time.  With so few attributes,
+        // separate multiplications are cheaper than a multiscalar
+        // multiplication, which would allocate its lookup tables.
+        let mut x: Scalar = &self.keypair.secret.x0 + &self.schema_tweak(options.schema);
 
         for (index, attribute) in presentation.attributes_revealed.iter().enumerate() {
-            V_prime += (&self.keypair.secret.xn[index] * attribute) * &P;
+            x += &self.keypair.secret.xn[index] * attribute;
         }
 
+        let mut V_prime: RistrettoPoint = &x * &P;
+
         for (index, attribute) in presentation.attributes_blinded.iter().enumerate() {
             V_prime += &self.keypair.secret.xn[index] * attribute;
         }
         V_prime -= presentation.rerandomized_mac_commitment;
 
+        x.clear();
+
         let mut transcript = Transcript::new(b"AEONFLUX SHOW");
+
+        bind_schema(&mut transcript, options.schema);
+        bind_timestamp(&mut transcript, options.timestamp);
+        bind_channel(&mut transcript, options.channel);
+
         let publics = valid_credential::Publics {
             B: &self.system_parameters.g,
             A: &self.system_parameters.h,
@@ -221,31 +665,133 @@ impl Issuer {
             Cm0: &presentation.attributes_blinded[0].into(),
         };
 
-        if presentation.proof.verify(&mut transcript, publics).is_err() {
-            return Err(CredentialError::MacVerification);
+        let verified = match scoped {
+            Some(scoped) => presentation.proof.verify_scoped(&mut transcript, publics, scoped),
+            None         => presentation.proof.verify(&mut transcript, publics),
+        };
+
+        if verified.is_err() {
+            return Err(CredentialError::MacVerification.into());
         }
 
         Ok(VerifiedCredential(presentation.clone()))
     }
+
+    /// Get this `Issuer`s parameters for publishing to users, along with the
+    /// period in which users and verifiers should accept them.
+    pub fn get_expiring_issuer_parameters(&self, not_before: u64, not_after: u64) -> ExpiringIssuerParameters {
+        ExpiringIssuerParameters { parameters: self.get_issuer_parameters(), not_before, not_after }
+    }
+
+    /// As `Issuer::verify()`, but only while this issuer's `expiring`
+    /// parameters are valid according to a `clock`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::NoIssuerParameters` if `expiring` are not this
+    ///   issuer's parameters.
+    /// * `CredentialError::Expired` if they are not valid at the current time.
+    /// * Any error from `Issuer::verify()`.
+    pub fn verify_unexpired<C>(
+        &self,
+        presentation: &CredentialPresentation,
+        expiring: &ExpiringIssuerParameters,
+        clock: &C,
+    ) -> Result<VerifiedCredential, Error>
+    where
+        C: Clock,
+    {
+        if expiring.check(clock)? != &self.keypair.public {
+            return Err(CredentialError::NoIssuerParameters.into());
+        }
+        self.verify(presentation)
+    }
+}
+
+/// An issuer's parameters, along with the period in which they are valid,
+/// after which the issuer is expected to have rotated its key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpiringIssuerParameters {
+    pub parameters: IssuerParameters,
+    /// The first second, since the Unix epoch, at which the parameters are
+    /// valid.
+    pub not_before: u64,
+    /// The first second at which the parameters are no longer valid.
+    pub not_after: u64,
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+impl ExpiringIssuerParameters {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ExpiringIssuerParameters, CredentialError> {
+        if bytes.len() < 16 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let not_before: u64 = read_u64(&bytes[..8]);
+        let not_after: u64 = read_u64(&bytes[8..16]);
+        let parameters = IssuerParameters::from_bytes(&bytes[16..])?;
+
+        Ok(ExpiringIssuerParameters { parameters, not_before, not_after })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(u64_to_bytes(self.not_before).iter());
+        v.extend(u64_to_bytes(self.not_after).iter());
+        v.extend(self.parameters.to_bytes());
+        v
+    }
+
+    /// Whether the parameters are valid at the time `now`.
+    pub fn is_valid_at(&self, now: u64) -> bool {
+        self.not_before <= now && now < self.not_after
+    }
+
+    /// The parameters, if they are valid according to a `clock`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::Expired` if they are not.
+    pub fn check<C>(&self, clock: &C) -> Result<&IssuerParameters, Error>
+    where
+        C: Clock,
+    {
+        if !self.is_valid_at(clock.now()) {
+            return Err(CredentialError::Expired.into());
+        }
+        Ok(&self.parameters)
+    }
 }
 
+impl_serde_with_to_bytes_and_from_bytes!(ExpiringIssuerParameters, "A valid byte sequence representing ExpiringIssuerParameters");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ExpiringIssuerParameters);
+impl_try_from_bytes!(ExpiringIssuerParameters, CredentialError);
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     use rand::thread_rng;
 
+    use clock::FixedClock;
     use credential::RevealedAttribute;
     use parameters::NUMBER_OF_ATTRIBUTES;
     use parameters::SystemParameters;
     use nonces::Nonces;
+    use test_utils::H;
+    use test_utils::identity_forgery;
     use user::User;
 
-    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
-                          127,   4, 178,  70, 212, 141, 119, 112,
-                          153, 154, 135,  11, 227, 132, 247,  47,
-                           68, 192,  72, 200,  23,  88,  51,  82, ];
-
     #[test]
     fn credential_issuance_and_presentation() {
         // Create RNGs for each party.
@@ -281,4 +827,236 @@ mod test {
         let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
         let _verified_credential: VerifiedCredential = issuer.verify(&alice_presentation).unwrap();
     }
+
+    #[test]
+    fn credential_blind_issuance_and_presentation() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+        let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+
+        // Alice needs an elGamal key for blind issuance, but blind_obtain()
+        // will make one for her.
+        let mut alice: User = User::new(system_parameters, issuer_parameters.clone(), None);
+
+        let mut alice_attributes: Vec<RevealedAttribute> = Vec::new();
+
+        alice_attributes.push(Scalar::random(&mut alice_rng));
+
+        let alice_request: CredentialBlindRequest = alice.blind_obtain(&alice_attributes, &mut alice_rng).unwrap();
+
+        assert!(alice.key.is_some());
+
+        // Serialise the request as if sending it over the wire.
+        let alice_request_bytes: Vec<u8> = alice_request.to_bytes();
+        let issuer_request: CredentialBlindRequest = CredentialBlindRequest::from_bytes(&alice_request_bytes).unwrap();
+
+        let issuance: CredentialBlindIssuance = issuer.blind_issue(&issuer_request, &mut issuer_rng).unwrap();
+        let issuance_bytes: Vec<u8> = issuance.to_bytes();
+        let alice_issuance: CredentialBlindIssuance = CredentialBlindIssuance::from_bytes(&issuance_bytes).unwrap();
+
+        alice.blind_obtain_finish(Some(&alice_issuance), &alice_attributes).unwrap();
+
+        assert!(alice.credential.as_ref().unwrap().attributes == alice_attributes);
+
+        let alice_nonces: Nonces = Nonces::new(&mut alice_rng, NUMBER_OF_ATTRIBUTES);
+        let alice_presentation: CredentialPresentation = alice.show(&alice_nonces, &mut alice_rng).unwrap();
+        let _verified_credential: VerifiedCredential = issuer.verify(&alice_presentation).unwrap();
+    }
+
+    #[test]
+    fn credential_blind_issuance_wrong_attributes() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+        let alice_attributes: Vec<RevealedAttribute> = vec![Scalar::random(&mut alice_rng)];
+        let other_attributes: Vec<RevealedAttribute> = vec![Scalar::random(&mut alice_rng)];
+
+        let alice_request = alice.blind_obtain(&alice_attributes, &mut alice_rng).unwrap();
+        let issuance = issuer.blind_issue(&alice_request, &mut issuer_rng).unwrap();
+
+        assert!(alice.blind_obtain_finish(Some(&issuance), &other_attributes).is_err());
+        assert!(alice.credential.is_none());
+    }
+
+    #[test]
+    fn credential_blind_request_without_proof() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut issuer_rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+        let alice_attributes: Vec<RevealedAttribute> = vec![Scalar::random(&mut alice_rng)];
+        let mut alice_request = alice.blind_obtain(&alice_attributes, &mut alice_rng).unwrap();
+
+        alice_request.attributes_blinded_proof = None;
+
+        assert!(issuer.blind_issue(&alice_request, &mut issuer_rng) == Err(CredentialError::MissingData.into()));
+    }
+
+    #[test]
+    fn expiring_issuer_parameters() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let other: Issuer = Issuer::create(system_parameters, &mut rng);
+        let expiring: ExpiringIssuerParameters = issuer.get_expiring_issuer_parameters(100, 200);
+        let clock = FixedClock::new(150);
+
+        assert!(ExpiringIssuerParameters::from_bytes(&expiring.to_bytes()).unwrap() == expiring);
+
+        let mut alice: User = User::new(system_parameters, expiring.check(&clock).unwrap().clone(), None);
+        let issuance = issuer.issue(&alice.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        alice.obtain_finish(Some(&issuance)).unwrap();
+
+        let presentation = alice.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(issuer.verify_unexpired(&presentation, &expiring, &clock).is_ok());
+        assert!(other.verify_unexpired(&presentation, &expiring, &clock) == Err(CredentialError::NoIssuerParameters.into()));
+
+        clock.advance(50);
+
+        assert!(expiring.check(&clock) == Err(CredentialError::Expired.into()));
+        assert!(issuer.verify_unexpired(&presentation, &expiring, &clock) == Err(CredentialError::Expired.into()));
+    }
+
+    #[test]
+    fn scoped_presentations_share_one_proof() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let mut alice: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue(&alice.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        alice.obtain_finish(Some(&issuance)).unwrap();
+
+        let basepoint: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let (presentation, tag) = alice.show_scoped(&basepoint, &nonces, &mut rng).unwrap();
+        let plain = alice.show(&nonces, &mut rng).unwrap();
+
+        // The tag costs nothing beyond its own encoding.
+        assert!(presentation.to_bytes().len() == plain.to_bytes().len());
+        assert!(issuer.verify_scoped(&presentation, &basepoint, &tag).is_ok());
+
+        // The proof is bound to both the basepoint and the tag.
+        let other: RistrettoPoint = RistrettoPoint::random(&mut rng);
+
+        assert!(issuer.verify_scoped(&presentation, &other, &tag) == Err(CredentialError::MacVerification.into()));
+        assert!(issuer.verify_scoped(&presentation, &basepoint, &other) == Err(CredentialError::MacVerification.into()));
+        assert!(issuer.verify(&presentation) == Err(CredentialError::MacVerification.into()));
+        assert!(issuer.verify_scoped(&plain, &basepoint, &tag) == Err(CredentialError::MacVerification.into()));
+    }
+
+    #[test]
+    fn every_combination_of_options_verifies() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let schema: SchemaId = SchemaId([5u8; 32]);
+        let mut plain: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut schematic: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let attribute: Scalar = Scalar::random(&mut rng);
+
+        let issuance = issuer.issue(&plain.obtain(vec![attribute]), &mut rng).unwrap();
+        plain.obtain_finish(Some(&issuance)).unwrap();
+        let issuance = issuer.issue_with_schema(&schematic.obtain(vec![attribute]), &schema, &mut rng).unwrap();
+        schematic.obtain_finish(Some(&issuance)).unwrap();
+
+        let basepoint: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let channel: ChannelBinding = ChannelBinding::tls_exporter([7u8; 32]);
+        let other: ChannelBinding = ChannelBinding::tls_exporter([8u8; 32]);
+
+        for combination in 0..16u8 {
+            let mut options = PresentationOptions::new();
+
+            if combination & 1 != 0 { options = options.schema(&schema); }
+            if combination & 2 != 0 { options = options.scoped(&basepoint); }
+            if combination & 4 != 0 { options = options.at(1_700_000_000); }
+            if combination & 8 != 0 { options = options.bound(&channel); }
+
+            let user: &User = if combination & 1 != 0 { &schematic } else { &plain };
+            let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+            let (presentation, tag) = user.show_with(&options, &nonces, &mut rng).unwrap();
+
+            assert!(tag.is_some() == (combination & 2 != 0));
+            assert!(issuer.verify_with(&presentation, &options, tag.as_ref()).is_ok());
+
+            // Dropping or changing any one option fails.
+            let mut wrong = options;
+            wrong.schema = if options.schema.is_some() { None } else { Some(&schema) };
+            assert!(issuer.verify_with(&presentation, &wrong, tag.as_ref()).is_err());
+
+            let mut wrong = options;
+            wrong.timestamp = Some(options.timestamp.unwrap_or(0) + 1);
+            assert!(issuer.verify_with(&presentation, &wrong, tag.as_ref()).is_err());
+
+            let mut wrong = options;
+            wrong.channel = if options.channel.is_some() { Some(&other) } else { Some(&channel) };
+            assert!(issuer.verify_with(&presentation, &wrong, tag.as_ref()).is_err());
+
+            match tag {
+                Some(ref N) => {
+                    let mut wrong = options;
+                    wrong.scope = None;
+                    assert!(issuer.verify_with(&presentation, &wrong, None).is_err());
+                    assert!(issuer.verify_with(&presentation, &options, Some(&basepoint)).is_err());
+                    assert!(issuer.verify_with(&presentation, &options, None) == Err(CredentialError::MissingData.into()));
+                    assert!(&basepoint * &attribute == *N);
+                },
+                None => {
+                    assert!(issuer.verify_with(&presentation, &options.scoped(&basepoint),
+                                               Some(&(&basepoint * &attribute))).is_err());
+                    assert!(issuer.verify_with(&presentation, &options, Some(&basepoint)) ==
+                            Err(CredentialError::MissingData.into()));
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn identity_nonces_are_rejected() {
+        let mut rng = thread_rng();
+        let issuer: Issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let forged: CredentialPresentation = identity_forgery(&issuer, &mut rng);
+
+        assert!(issuer.verify(&forged) == Err(CredentialError::VerificationFailure.into()));
+        assert!(issuer.verify_with(&forged, &PresentationOptions::new(), None) ==
+                Err(CredentialError::VerificationFailure.into()));
+        assert!(CredentialPresentation::from_bytes(&forged.to_bytes()).err() ==
+                Some(CredentialError::VerificationFailure));
+    }
+
+    #[test]
+    fn batch_issuance_shares_one_commitment() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let mut users: Vec<User> = (0..4).map(|_| User::new(system_parameters, issuer.get_issuer_parameters(), None)).collect();
+        let requests: Vec<CredentialRequest> = users.iter().map(|u| u.obtain(vec![Scalar::random(&mut rng)])).collect();
+
+        let issuances = issuer.issue_batch(&requests, &mut rng).unwrap();
+
+        assert!(issuances.len() == 4);
+
+        for (user, issuance) in users.iter_mut().zip(issuances.iter()) {
+            assert!(issuance.secret_key_commitment == issuances[0].secret_key_commitment);
+
+            user.obtain_finish(Some(issuance)).unwrap();
+
+            let presentation = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+            assert!(issuer.verify(&presentation).is_ok());
+        }
+        assert!(issuer.issue_batch(&[], &mut rng).unwrap().is_empty());
+    }
 }
@@ -14,39 +14,205 @@
 // We denote group elements with capital and scalars with lowercased names.
 #![allow(non_snake_case)]
 
-#![cfg_attr(any(not(feature = "std"), feature = "alloc"), feature(alloc))]
+#![cfg_attr(feature = "alloc", feature(alloc))]
+
+#[cfg(not(any(feature = "u32_backend", feature = "u64_backend", feature = "avx2_backend")))]
+compile_error!("aeonflux requires a curve25519-dalek backend: enable one of the \
+                u32_backend, u64_backend, avx2_backend, or simd_backend features");
 
 #[cfg(feature = "std")]
 #[macro_use]
 extern crate std;
-#[cfg(any(not(feature = "std"), feature = "alloc"))]
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "axum-service")]
+extern crate axum;
+#[cfg(any(feature = "std", feature = "alloc"))]
 extern crate bincode;
-extern crate clear_on_drop;
+#[cfg(feature = "bbs")]
+extern crate bbs_sha2;
+#[cfg(feature = "blake3-transcripts")]
+extern crate blake3;
+#[cfg(feature = "bbs")]
+extern crate bls12_381;
+#[cfg(feature = "backup")]
+extern crate chacha20poly1305;
 extern crate curve25519_dalek;
+#[cfg(feature = "ed25519")]
+extern crate ed25519_dalek;
 extern crate failure;
+#[cfg(feature = "getrandom")]
+extern crate getrandom;
 extern crate merlin;
+#[cfg(feature = "test-utils")]
+#[macro_use]
+extern crate proptest;
 extern crate rand;
 extern crate rand_core;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "service")]
+extern crate serde_cbor;
+#[cfg(any(feature = "service", feature = "vc", feature = "test-vectors"))]
+#[cfg_attr(any(feature = "vc", feature = "test-vectors"), macro_use)]
+extern crate serde_json;
 extern crate sha2;
+#[cfg(feature = "sled-store")]
+extern crate sled;
+#[cfg(feature = "secure-channel")]
+extern crate snow;
 extern crate subtle;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+extern crate zeroize;
 
 // The macros have to come first.
 #[macro_use]
 pub mod macros;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod acceleration;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod amacs;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod attributes;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod audit;
+#[cfg(all(feature = "backup", any(feature = "std", feature = "alloc")))]
+pub mod backup;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod batch;
+#[cfg(feature = "bbs")]
+pub mod bbs;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod ceremony;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod channel_binding;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod chunking;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod ciphertexts;
+pub mod clock;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod compact;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod consent;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod credential;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod derivation;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod device;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod dual_key;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod elgamal;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod eligibility;
+pub mod encoding;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod envelope;
 pub mod errors;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod export;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod fragment;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod freshness;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod gate;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod hashed_identifier;
+pub mod hashing;
+pub mod heapless;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod issuer;
+pub mod metrics;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod migration;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod nonces;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod non_revocation;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod nullifier;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod nullifier_sync;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod parameter_chain;
 pub mod parameters;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod pass;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod pedersen;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod policy;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod precomputed;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod predicates;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod prelude;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod presentation_request;
 pub mod proofs;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod public_tokens;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod qr;
+pub mod rng;
+#[cfg(feature = "service")]
+pub mod service;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod rate_limit;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod recovery;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod replay;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod report;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod revocation;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod schema;
+pub mod secret;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod self_check;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod storage;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod streaming;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod sybil;
+#[cfg(any(all(test, feature = "std"), feature = "test-utils"))]
+pub mod test_utils;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod ticket;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod tokens;
+pub mod transcript;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod trust;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod user;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod vault;
+#[cfg(feature = "verifier-core")]
+pub mod verifier_core;
+#[cfg(feature = "vc")]
+pub mod vc;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod voprf;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod wallet;
@@ -49,3 +49,299 @@ macro_rules! impl_serde_with_to_bytes_and_from_bytes {
         }
     }
 }
+
+/// Implement `aeonflux::cbor::Cbor` for a type with `to_bytes` and
+/// `from_bytes`, encoding it as a CBOR byte string of its canonical bytes.
+#[macro_export]
+macro_rules! impl_cbor_with_to_bytes_and_from_bytes {
+    ($t:tt) => {
+        impl $crate::cbor::Cbor for $t {
+            fn to_cbor(&self) -> Vec<u8> {
+                let bytes = self.to_bytes();
+                let mut v: Vec<u8> = Vec::with_capacity(9 + bytes.len());
+
+                $crate::cbor::push_bytes(&mut v, &bytes[..]);
+                v
+            }
+
+            fn from_cbor(bytes: &[u8]) -> Result<$t, $crate::cbor::CborError> {
+                let mut decoder = $crate::cbor::Decoder::new(bytes);
+                let inner: &[u8] = decoder.bytes()?;
+
+                decoder.finish()?;
+
+                match $t::from_bytes(inner) {
+                    Ok(x)  => Ok(x),
+                    Err(_) => Err($crate::cbor::CborError::InvalidValue),
+                }
+            }
+        }
+    }
+}
+
+/// Implement `TryFrom<&[u8]>` for a type with `from_bytes`, so that callers
+/// decoding untrusted input, e.g. across an FFI boundary or off the network,
+/// have one entry point for every wire type which reports exactly the errors
+/// `from_bytes` does.
+#[macro_export]
+macro_rules! impl_try_from_bytes {
+    ($t:tt, $error:ty) => {
+        impl<'a> ::core::convert::TryFrom<&'a [u8]> for $t {
+            type Error = $error;
+
+            fn try_from(bytes: &'a [u8]) -> Result<$t, $error> {
+                $t::from_bytes(bytes)
+            }
+        }
+    }
+}
+
+/// Enter a tracing span `$name`, with some non-sensitive `$fields`, until the
+/// end of the enclosing block.
+///
+/// Spans are at the `DEBUG` level.  Without the `tracing` feature this
+/// expands to nothing, and the fields are never evaluated.
+macro_rules! trace_span {
+    ($name:expr) => {
+        trace_span!($name,)
+    };
+    ($name:expr, $($fields:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::debug_span!($name, $($fields)*).entered();
+    };
+}
+
+/// Record whether `$result` succeeded, and why not if it did not, as an event
+/// in the current span.  With `encoded`, a success also records the length
+/// of its value's `to_bytes()`, e.g. the size of an issuance and its proof.
+///
+/// Without the `tracing` feature this expands to nothing.
+macro_rules! trace_result {
+    ($result:expr) => {
+        #[cfg(feature = "tracing")]
+        match $result {
+            Ok(_)      => ::tracing::debug!("succeeded"),
+            Err(ref e) => ::tracing::debug!(error = %e, "failed"),
+        }
+    };
+    ($result:expr, encoded) => {
+        #[cfg(feature = "tracing")]
+        match $result {
+            Ok(ref x)  => ::tracing::debug!(size = x.to_bytes().len(), "succeeded"),
+            Err(ref e) => ::tracing::debug!(error = %e, "failed"),
+        }
+    };
+}
+
+/// The items which the expansion of `credential!` names through `$crate`,
+/// so that it builds in crates without their own `rand_core` dependency, and
+/// in `no_std` crates with `alloc`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[doc(hidden)]
+pub mod export {
+    #[cfg(all(not(feature = "std"), feature = "alloc"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std::vec::Vec;
+
+    pub use rand_core::CryptoRng;
+    pub use rand_core::RngCore;
+}
+
+/// Declare a credential type with named attributes.
+///
+/// ```ignore
+/// credential! {
+///     /// A member of some group.
+///     pub struct Membership / MembershipRequest / MembershipPresentation {
+///         member: Identifier = Never,
+///     }
+/// }
+/// ```
+///
+/// declares `Membership`, holding each attribute as a `Scalar` field in the
+/// order given, along with a `schema::Schema` of the same name whose
+/// attributes have the given `schema::AttributeType`s and `schema::Reveal`
+/// rules.  The credential is obtained with `Membership::request()`, which
+/// makes a `MembershipRequest` for the issuer to read with `attributes()`
+/// and `issue()`, and `Membership::obtain_finish()`, and is presented with
+/// `Membership::show()`, which makes a `MembershipPresentation`, whose
+/// revealed attributes are read with methods named for them once it has
+/// been checked with `verify()`.  Everything is issued, presented, and
+/// verified under the schema, so a credential of one declared type never
+/// verifies as another.
+///
+/// Since the schema is named for the type, and its attributes for the
+/// fields, renaming either makes a new schema.
+///
+/// An issuer's key has one secret scalar for each attribute, and one made
+/// with `Issuer::create()` has `NUMBER_OF_ATTRIBUTES`, i.e. one, which is
+/// also all that the issuance proof, `proofs::issuance_revealed`, covers.  A
+/// type with more fields may be declared, e.g. to read its schema, but
+/// `issue()` checks the schema with `Schema::check()`, and the issuer's key
+/// against it, and refuses to issue it rather than failing to compute the
+/// MAC.
+#[macro_export]
+macro_rules! credential {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident / $request:ident / $presentation:ident {
+            $( $(#[$field_meta:meta])* $field:ident : $kind:ident = $reveal:ident ),* $(,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        $vis struct $name {
+            $( $(#[$field_meta])* pub $field: $crate::credential::RevealedAttribute, )*
+        }
+
+        impl $name {
+            /// The schema of this credential type.
+            pub fn schema() -> $crate::schema::Schema {
+                $crate::schema::Schema::new(stringify!($name))
+                    $( .attribute(stringify!($field),
+                                  $crate::schema::AttributeType::$kind,
+                                  $crate::schema::Reveal::$reveal) )*
+            }
+
+            /// The identifier of this credential type's schema.
+            pub fn schema_id() -> $crate::schema::SchemaId {
+                $name::schema().id()
+            }
+
+            /// The attributes, in the order of the schema.
+            pub fn to_attributes(&self) -> $crate::macros::export::Vec<$crate::credential::RevealedAttribute> {
+                let mut _attributes = $crate::macros::export::Vec::new();
+
+                $( _attributes.push(self.$field); )*
+                _attributes
+            }
+
+            /// Read the `attributes` of a credential of this type.
+            ///
+            /// # Errors
+            ///
+            /// * `SchemaError::WrongNumberOfAttributes` if there is not one
+            ///   attribute for each field.
+            pub fn from_attributes(
+                attributes: &[$crate::credential::RevealedAttribute],
+            ) -> Result<$name, $crate::errors::Error>
+            {
+                if attributes.len() != $name::schema().attributes.len() {
+                    return Err($crate::errors::SchemaError::WrongNumberOfAttributes.into());
+                }
+
+                let mut _attributes = attributes.iter();
+
+                Ok($name { $( $field: *_attributes.next().unwrap(), )* })
+            }
+
+            /// The attributes of the `user`'s credential, if they have one.
+            pub fn from_user(user: &$crate::user::User) -> Option<$name> {
+                user.credential.as_ref().and_then(|c| $name::from_attributes(&c.attributes).ok())
+            }
+
+            /// Request a credential with these attributes as the `user`.
+            pub fn request(&self, user: &$crate::user::User) -> $request {
+                $request { request: user.obtain(self.to_attributes()) }
+            }
+
+            /// Finish obtaining a credential of this type as the `user`.
+            pub fn obtain_finish(
+                user: &mut $crate::user::User,
+                issuance: &$crate::credential::CredentialIssuance,
+            ) -> Result<(), $crate::errors::Error>
+            {
+                user.obtain_finish_with_schema(Some(issuance), &$name::schema_id())
+            }
+
+            /// Present the `user`'s credential of this type.
+            pub fn show<R>(
+                user: &$crate::user::User,
+                nonces: &$crate::nonces::Nonces,
+                rng: &mut R,
+            ) -> Result<$presentation, $crate::errors::Error>
+            where
+                R: $crate::macros::export::RngCore + $crate::macros::export::CryptoRng,
+            {
+                Ok($presentation { presentation: user.show_with_schema(&$name::schema_id(), nonces, rng)? })
+            }
+        }
+
+        /// A request for a credential of this type.
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        $vis struct $request {
+            pub request: $crate::credential::CredentialRequest,
+        }
+
+        impl $request {
+            /// The requested attributes.
+            pub fn attributes(&self) -> Result<$name, $crate::errors::Error> {
+                $name::from_attributes(&self.request.attributes_revealed)
+            }
+
+            /// Issue the requested credential with an `issuer`.
+            ///
+            /// # Errors
+            ///
+            /// * Any error from `Schema::check()`, e.g.
+            ///   `SchemaError::WrongNumberOfAttributes` if this type does not
+            ///   have `NUMBER_OF_ATTRIBUTES` fields.
+            /// * `CredentialError::WrongNumberOfAttributes` if the request
+            ///   does not have one attribute for each field, or if the
+            ///   `issuer`'s key does not.
+            /// * Any error from `Issuer::issue_with_schema()`.
+            pub fn issue<R>(
+                &self,
+                issuer: &$crate::issuer::Issuer,
+                rng: &mut R,
+            ) -> Result<$crate::credential::CredentialIssuance, $crate::errors::Error>
+            where
+                R: $crate::macros::export::RngCore + $crate::macros::export::CryptoRng,
+            {
+                $name::schema().check()?;
+
+                if self.attributes().is_err() ||
+                    issuer.keypair.secret.xn.len() != $name::schema().attributes.len()
+                {
+                    return Err($crate::errors::CredentialError::WrongNumberOfAttributes.into());
+                }
+                issuer.issue_with_schema(&self.request, &$name::schema_id(), rng)
+            }
+        }
+
+        /// A presentation of a credential of this type.
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        $vis struct $presentation {
+            pub presentation: $crate::credential::CredentialPresentation,
+        }
+
+        impl $presentation {
+            /// Verify this presentation with an `issuer`.
+            pub fn verify(
+                &self,
+                issuer: &$crate::issuer::Issuer,
+            ) -> Result<$crate::credential::VerifiedCredential, $crate::errors::Error>
+            {
+                issuer.verify_with_schema(&self.presentation, &$name::schema_id())
+            }
+
+            $(
+                /// This attribute, if it is revealed.
+                ///
+                /// It is only to be trusted once the presentation verifies.
+                pub fn $field(&self) -> Option<&$crate::credential::RevealedAttribute> {
+                    $name::schema().revealed(&self.presentation, stringify!($field))
+                }
+            )*
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<$presentation, $crate::errors::CredentialError> {
+                Ok($presentation { presentation: $crate::credential::CredentialPresentation::from_bytes(bytes)? })
+            }
+
+            pub fn to_bytes(&self) -> $crate::macros::export::Vec<u8> {
+                self.presentation.to_bytes()
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Hooks for observing issuance and verification, e.g. from a Prometheus
+//! exporter.
+//!
+//! A `GateServer` opened `with_metrics()`, and the `_with_metrics` variants
+//! of the functions in `batch`, report every operation they perform to a
+//! `Metrics` implementation:
+//!
+//! * how many of each `Operation` succeeded,
+//! * how many failed, and for which reason, as an `errors::Error`,
+//! * the size of every batch, and
+//! * with the `std` feature, how long each operation, including creating or
+//!   verifying its proofs, took.
+//!
+//! Every method of `Metrics` does nothing by default, and `NoMetrics`, which
+//! is used unless another implementation is given, overrides none of them,
+//! so that integrators need only implement the counters they export.
+//! Implementations are called on the hot path, and should do no more than
+//! update an atomic or a histogram.
+//!
+//! Nothing reported identifies a holder: reasons are the coarse
+//! `errors::Error` kinds, and no attributes, nullifiers, or presentations
+//! are ever passed to a `Metrics`.
+
+#[cfg(not(feature = "std"))]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use errors::Error;
+
+/// An operation performed by an issuer or verifier.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Operation {
+    /// Issuing a credential over revealed attributes.
+    Issuance,
+    /// Issuing a credential over encrypted attributes.
+    BlindIssuance,
+    /// Verifying a presentation.
+    Verification,
+    /// Verifying a presentation in exchange for a report token.
+    ReportablePresentation,
+}
+
+/// A sink for counters and timings of issuer and verifier operations.
+pub trait Metrics {
+    /// An `operation` succeeded.
+    fn succeeded(&self, _operation: Operation) { }
+
+    /// An `operation` failed, because of `reason`.
+    fn failed(&self, _operation: Operation, _reason: Error) { }
+
+    /// A batch of `size` operations was started.
+    fn batch(&self, _operation: Operation, _size: usize) { }
+
+    /// An `operation`, or a batch of them, took `elapsed` to complete.
+    fn timed(&self, _operation: Operation, _elapsed: Duration) { }
+}
+
+impl<'a, M: Metrics + ?Sized> Metrics for &'a M {
+    fn succeeded(&self, operation: Operation) {
+        (**self).succeeded(operation)
+    }
+
+    fn failed(&self, operation: Operation, reason: Error) {
+        (**self).failed(operation, reason)
+    }
+
+    fn batch(&self, operation: Operation, size: usize) {
+        (**self).batch(operation, size)
+    }
+
+    fn timed(&self, operation: Operation, elapsed: Duration) {
+        (**self).timed(operation, elapsed)
+    }
+}
+
+/// Metrics which are discarded.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoMetrics;
+
+impl Metrics for NoMetrics { }
+
+/// Run an `operation`, reporting how long it took and whether it succeeded
+/// to `metrics`.
+pub fn observe<M, T, E, F>(metrics: &M, operation: Operation, f: F) -> Result<T, E>
+where
+    M: Metrics + ?Sized,
+    E: Copy + Into<Error>,
+    F: FnOnce() -> Result<T, E>,
+{
+    #[cfg(feature = "std")]
+    let start = Instant::now();
+
+    let result = f();
+
+    #[cfg(feature = "std")]
+    metrics.timed(operation, start.elapsed());
+
+    report(metrics, operation, &result);
+    result
+}
+
+/// Report the outcome of an `operation` which has already been run.
+pub fn report<M, T, E>(metrics: &M, operation: Operation, result: &Result<T, E>)
+where
+    M: Metrics + ?Sized,
+    E: Copy + Into<Error>,
+{
+    match *result {
+        Ok(_)      => metrics.succeeded(operation),
+        Err(ref e) => metrics.failed(operation, (*e).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    use errors::CredentialError;
+
+    #[derive(Default)]
+    struct Counters {
+        succeeded: Cell<usize>,
+        failed: RefCell<Vec<Error>>,
+        timed: Cell<usize>,
+    }
+
+    impl Metrics for Counters {
+        fn succeeded(&self, _operation: Operation) {
+            self.succeeded.set(self.succeeded.get() + 1);
+        }
+
+        fn failed(&self, _operation: Operation, reason: Error) {
+            self.failed.borrow_mut().push(reason);
+        }
+
+        fn timed(&self, _operation: Operation, _elapsed: Duration) {
+            self.timed.set(self.timed.get() + 1);
+        }
+    }
+
+    #[test]
+    fn observed_operations_are_counted_by_outcome() {
+        let counters = Counters::default();
+
+        assert!(observe(&counters, Operation::Verification, || Ok::<(), CredentialError>(())).is_ok());
+        assert!(observe(&counters, Operation::Verification, || Err::<(), _>(CredentialError::MacVerification)).is_err());
+
+        assert!(counters.succeeded.get() == 1);
+        assert!(counters.timed.get() == 2);
+        assert!(*counters.failed.borrow() == vec![Error::from(CredentialError::MacVerification)]);
+    }
+
+    #[test]
+    fn no_metrics_changes_nothing() {
+        let result = observe(&NoMetrics, Operation::Issuance, || Err::<(), _>(CredentialError::MissingData));
+
+        assert!(result == Err(CredentialError::MissingData));
+    }
+}
@@ -0,0 +1,168 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Migrating keys and credentials written by earlier revisions of this crate.
+//!
+//! Earlier revisions wrote issuer keys, user state, and credentials as their
+//! bare `to_bytes()` encodings, with nothing to say which type or version
+//! they were.  Those encodings are unchanged, so `migrate()` need only parse
+//! one as the type its caller knows it to be and seal it in an `envelope`,
+//! which names its kind and suite so that later format changes may be told
+//! apart from it.  Migration is incremental: a value which is already in an
+//! envelope is left as it is, so a deployment may migrate its files one at
+//! a time, and run the migration again after an interruption, while
+//! `open_either()` reads whichever encoding it finds in the meantime.
+//!
+//! Records in a `storage::Store` keep their bare encodings, and need no
+//! migration.
+//!
+//! Earlier revisions also hashed strings to `amacs::Message`s without a
+//! domain separator.  Credentials issued over such attributes still verify,
+//! but a verifier comparing a revealed attribute with one it hashed itself
+//! must use `legacy_message()` for them.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use sha2::Sha512;
+
+use amacs;
+use envelope;
+use envelope::Enveloped;
+use envelope::DEFAULT_SUITE;
+use errors::Error;
+use errors::MigrationError;
+
+/// The outcome of migrating a value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Migrated {
+    /// The value was already in an envelope, and should be left as it is.
+    Current,
+    /// The value was in its legacy encoding, and should be replaced by this
+    /// envelope.
+    Upgraded(Vec<u8>),
+}
+
+/// Migrate the legacy encoding of a `T` to an envelope.
+///
+/// # Errors
+///
+/// * `MigrationError::WrongKind` if the `bytes` are an envelope holding some
+///   other type.
+/// * `MigrationError::Malformed` if they are neither an envelope nor a legacy
+///   encoding of a `T`.
+pub fn migrate<T: Enveloped>(bytes: &[u8]) -> Result<Migrated, Error> {
+    if envelope::open::<T>(bytes).is_ok() {
+        return Ok(Migrated::Current);
+    }
+
+    match T::from_payload(DEFAULT_SUITE, bytes) {
+        Ok(value) => Ok(Migrated::Upgraded(envelope::seal(&value))),
+        Err(_) => match envelope::open_any(bytes) {
+            Ok(_)  => Err(MigrationError::WrongKind.into()),
+            Err(_) => Err(MigrationError::Malformed.into()),
+        },
+    }
+}
+
+/// Read a `T` from either an envelope or its legacy encoding.
+///
+/// # Errors
+///
+/// As for `migrate()`.
+pub fn open_either<T: Enveloped>(bytes: &[u8]) -> Result<T, Error> {
+    if let Ok((value, _)) = envelope::open::<T>(bytes) {
+        return Ok(value);
+    }
+
+    match T::from_payload(DEFAULT_SUITE, bytes) {
+        Ok(value) => Ok(value),
+        Err(_) => match envelope::open_any(bytes) {
+            Ok(_)  => Err(MigrationError::WrongKind.into()),
+            Err(_) => Err(MigrationError::Malformed.into()),
+        },
+    }
+}
+
+/// Hash a string to a `Message` as earlier revisions did, i.e. with SHA-512
+/// and no domain separator.
+pub fn legacy_message(source: &str) -> amacs::Message {
+    amacs::Message(vec![Scalar::hash_from_bytes::<Sha512>(source.as_bytes())])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use credential::Credential;
+    use elgamal;
+    use issuer::Issuer;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use user::User;
+
+    fn upgraded(migrated: Migrated) -> Vec<u8> {
+        match migrated {
+            Migrated::Upgraded(x) => x,
+            Migrated::Current     => panic!("the legacy encoding was not migrated"),
+        }
+    }
+
+    #[test]
+    fn legacy_encodings_round_trip_through_envelopes() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let key = elgamal::Keypair::generate(&mut rng);
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), Some(key.clone()));
+        let issuance = issuer.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let credential: Credential = user.credential.clone().unwrap();
+
+        let sealed = upgraded(migrate::<Issuer>(&issuer.to_bytes()).unwrap());
+        assert!(open_either::<Issuer>(&sealed).unwrap().to_bytes() == issuer.to_bytes());
+        assert!(migrate::<Issuer>(&sealed) == Ok(Migrated::Current));
+
+        let sealed = upgraded(migrate::<User>(&user.to_bytes()).unwrap());
+        assert!(open_either::<User>(&sealed).unwrap() == user);
+        assert!(open_either::<User>(&user.to_bytes()).unwrap() == user);
+        assert!(migrate::<User>(&sealed) == Ok(Migrated::Current));
+
+        let sealed = upgraded(migrate::<Credential>(&credential.to_bytes()).unwrap());
+        assert!(envelope::open::<Credential>(&sealed).unwrap().0 == credential);
+
+        let sealed = upgraded(migrate::<elgamal::Keypair>(&key.to_bytes()).unwrap());
+        assert!(open_either::<elgamal::Keypair>(&sealed).unwrap() == key);
+    }
+
+    #[test]
+    fn migration_rejects_other_types() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let sealed: Vec<u8> = envelope::seal(&issuer.system_parameters);
+
+        assert!(migrate::<Issuer>(&sealed) == Err(MigrationError::WrongKind.into()));
+        assert!(open_either::<Issuer>(&sealed).err() == Some(MigrationError::WrongKind.into()));
+        assert!(migrate::<User>(&issuer.to_bytes()) == Err(MigrationError::Malformed.into()));
+        assert!(migrate::<Issuer>(&[]) == Err(MigrationError::Malformed.into()));
+
+        // Legacy attributes were hashed without a domain separator.
+        let legacy = legacy_message("+14155550123");
+
+        assert!(legacy.0 == vec![Scalar::hash_from_bytes::<Sha512>(b"+14155550123")]);
+        assert!(legacy.0 != amacs::Message::from("+14155550123".to_string()).0);
+    }
+}
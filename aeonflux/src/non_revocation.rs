@@ -0,0 +1,1255 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Proofs that a credential has not been revoked, from a keyed-verification
+//! accumulator, with witnesses which holders cache and refresh themselves.
+//!
+//! A revocable credential is issued by a `RevocableIssuer` over two
+//! attributes: the identity \\( m_1 \\) from the holder's ordinary credential,
+//! and a revocation handle \\( y = m_2 \\), which the holder chooses at random
+//! when they make their `HandleRequest`.  The issuer learns the handle, and
+//! keeps it to revoke the credential with, but never learns the identity it
+//! is bound to, which the holder proves equal to their ordinary credential's
+//! hidden attribute as for a `device` credential.
+//!
+//! The issuer keeps an `Accumulator`, a secret scalar \\( \alpha \\) and a
+//! public value \\( V \\), and its holder's `NonRevocationWitness` is
+//! \\( W = (\alpha + y)^{-1} \cdot V \\), which only the issuer can compute.
+//! Revoking a handle \\( y_r \\) replaces the value with
+//! \\( V' = (\alpha + y_r)^{-1} \cdot V \\), and the issuer publishes the
+//! handle and the new value in an `AccumulatorDelta`.  From it, every other
+//! holder updates their own witness, without the issuer, as
+//! \\( W' = (y_r - y)^{-1} \cdot (W - V') \\), so a witness is computed once
+//! and then cheaply `refresh_witness()`ed at each change to the registry.
+//!
+//! To present, a holder rerandomises their witness and the value, as
+//! \\( \bar{W} = r \cdot W \\) and \\( \bar{V} = r \cdot V \\), and proves
+//! that \\( \bar{V} \\) is a multiple of the current value and that
+//! \\( Q = \bar{V} - \alpha \cdot \bar{W} = m_2 \cdot \bar{W} \\), for the same
+//! \\( m_2 \\) as in their revocable credential.  Only the issuer, knowing
+//! \\( \alpha \\), can compute \\( Q \\) and so verify the proof.
+//!
+//! # Note
+//!
+//! An `AccumulatorDelta` reveals the handles it revokes.  Since a handle is
+//! random and only ever shown to the issuer, this reveals nothing about the
+//! revoked holder's identity attribute, nor links their past presentations.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeSet;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeSet;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use amacs;
+use credential::Credential;
+use credential::CredentialPresentation;
+use credential::PresentationOptions;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use errors::RevocationError;
+use issuer::Issuer;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
+use pedersen;
+use proofs::device_enrollment;
+use proofs::device_issuance;
+use proofs::valid_revocable;
+use secret::Clear;
+use secret::REDACTED;
+use secret::Secret;
+use user::User;
+
+pub const SIZEOF_ACCUMULATOR_VALUE: usize = 8 + 32;
+pub const SIZEOF_NON_REVOCATION_WITNESS: usize = 32 + SIZEOF_ACCUMULATOR_VALUE + 32;
+
+/// The number of attributes in a revocable credential: the holder's
+/// identity, and a random revocation handle.
+pub const REVOCABLE_NUMBER_OF_ATTRIBUTES: usize = 2;
+
+pub const SIZEOF_REVOCABLE_CREDENTIAL: usize = 64 + amacs::SIZEOF_TAG;
+pub const SIZEOF_NON_REVOCATION_PRESENTATION: usize = 200 + valid_revocable::SIZEOF_PROOF;
+
+/// The issuer's parameters for revocable credentials.
+pub type RevocableIssuerParameters = amacs::PublicKey;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, RevocationError> {
+    match CompressedRistretto::from_slice(&bytes[..32]).decompress() {
+        Some(x) => Ok(x),
+        None    => Err(RevocationError::PointDecompressionError),
+    }
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<Scalar, RevocationError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(&bytes[..32]);
+
+    Scalar::from_canonical_bytes(tmp).ok_or(RevocationError::ScalarFormatError)
+}
+
+/// An accumulator's public value, \\( V \\), at some epoch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AccumulatorValue {
+    pub epoch: u64,
+    pub value: RistrettoPoint,
+}
+
+impl AccumulatorValue {
+    pub fn from_bytes(bytes: &[u8]) -> Result<AccumulatorValue, RevocationError> {
+        if bytes.len() != SIZEOF_ACCUMULATOR_VALUE {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+        Ok(AccumulatorValue { epoch: read_u64(&bytes[..8]), value: read_point(&bytes[8..])? })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ACCUMULATOR_VALUE);
+
+        v.extend(u64_to_bytes(self.epoch).iter());
+        v.extend(self.value.compress().as_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(AccumulatorValue, "A valid byte sequence representing an AccumulatorValue");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(AccumulatorValue);
+impl_try_from_bytes!(AccumulatorValue, RevocationError);
+
+/// The revocation of one handle, and the accumulator's value afterwards.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Removal {
+    pub handle: Scalar,
+    pub value: RistrettoPoint,
+}
+
+/// The handles revoked from one epoch of an accumulator to the next.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccumulatorDelta {
+    pub from_epoch: u64,
+    pub to_epoch: u64,
+    /// The revocations, in the order they were made.
+    pub removals: Vec<Removal>,
+}
+
+impl AccumulatorDelta {
+    pub fn from_bytes(bytes: &[u8]) -> Result<AccumulatorDelta, RevocationError> {
+        if bytes.len() < 20 || (bytes.len() - 20) % 64 != 0 {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let from_epoch: u64 = read_u64(&bytes[..8]);
+        let to_epoch: u64 = read_u64(&bytes[8..16]);
+        let count: usize = read_u64(&bytes[16..20]) as usize;
+
+        if to_epoch <= from_epoch {
+            return Err(RevocationError::WrongEpoch);
+        }
+        if count != (bytes.len() - 20) / 64 {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let mut removals: Vec<Removal> = Vec::with_capacity(count);
+
+        for chunk in bytes[20..].chunks(64) {
+            removals.push(Removal { handle: read_scalar(&chunk[..32])?, value: read_point(&chunk[32..])? });
+        }
+
+        Ok(AccumulatorDelta { from_epoch, to_epoch, removals })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(20 + 64 * self.removals.len());
+
+        v.extend(u64_to_bytes(self.from_epoch).iter());
+        v.extend(u64_to_bytes(self.to_epoch).iter());
+        v.extend(u64_to_bytes(self.removals.len() as u64)[..4].iter());
+
+        for removal in self.removals.iter() {
+            v.extend(removal.handle.as_bytes());
+            v.extend(removal.value.compress().as_bytes());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(AccumulatorDelta, "A valid byte sequence representing an AccumulatorDelta");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(AccumulatorDelta);
+impl_try_from_bytes!(AccumulatorDelta, RevocationError);
+
+/// An issuer's accumulator of unrevoked handles.
+#[derive(Clone, Debug)]
+pub struct Accumulator {
+    secret: Secret<Scalar>,
+    value: AccumulatorValue,
+    revoked: BTreeSet<[u8; 32]>,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for Accumulator {
+    fn drop(&mut self) {
+        self.secret.clear();
+    }
+}
+
+impl Accumulator {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Accumulator, RevocationError> {
+        let offset: usize = 32 + SIZEOF_ACCUMULATOR_VALUE;
+
+        if bytes.len() < offset || (bytes.len() - offset) % 32 != 0 {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let secret: Scalar = read_scalar(&bytes[..32])?;
+        let value = AccumulatorValue::from_bytes(&bytes[32..offset])?;
+        let mut revoked: BTreeSet<[u8; 32]> = BTreeSet::new();
+
+        for chunk in bytes[offset..].chunks(32) {
+            revoked.insert(read_scalar(chunk)?.to_bytes());
+        }
+
+        Ok(Accumulator { secret: Secret::new(secret), value, revoked })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(32 + SIZEOF_ACCUMULATOR_VALUE + 32 * self.revoked.len());
+
+        v.extend(self.secret.expose_secret().as_bytes());
+        v.extend(self.value.to_bytes());
+
+        for handle in self.revoked.iter() {
+            v.extend(handle.iter());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Accumulator, "A valid byte sequence representing an Accumulator");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Accumulator);
+impl_try_from_bytes!(Accumulator, RevocationError);
+
+impl Accumulator {
+    /// Create a new, empty accumulator at epoch zero.
+    pub fn create<R>(csprng: &mut R) -> Accumulator
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret: Scalar = Scalar::random(csprng);
+        let value = AccumulatorValue { epoch: 0, value: RistrettoPoint::random(csprng) };
+
+        Accumulator { secret: Secret::new(secret), value, revoked: BTreeSet::new() }
+    }
+
+    /// This accumulator's current value, for publishing to holders.
+    pub fn value(&self) -> AccumulatorValue {
+        self.value
+    }
+
+    /// Whether a `handle` has been revoked.
+    pub fn is_revoked(&self, handle: &Scalar) -> bool {
+        self.revoked.contains(handle.as_bytes())
+    }
+
+    /// \\( (\alpha + y)^{-1} \\) for a `handle` \\( y \\), if it is non-zero.
+    fn inverse(&self, handle: &Scalar) -> Option<Scalar> {
+        let mut sum: Scalar = self.secret.expose_secret() + handle;
+
+        if sum == Scalar::zero() {
+            return None;
+        }
+
+        let inverse: Scalar = sum.invert();
+
+        sum.clear();
+        Some(inverse)
+    }
+
+    /// \\( Q = \bar{V} - \alpha \cdot \bar{W} \\) for a rerandomised value and
+    /// witness, which is \\( y \cdot \bar{W} \\) for the witnessed handle.
+    pub(crate) fn unblind(&self, Vbar: &RistrettoPoint, Wbar: &RistrettoPoint) -> RistrettoPoint {
+        Vbar - self.secret.expose_secret() * Wbar
+    }
+
+    /// Compute the current witness for a credential's `handle`, e.g. right
+    /// after issuing it, as `RevocableIssuer::issue()` does.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::Revoked` if the handle has been revoked.
+    pub fn witness(&self, handle: &Scalar) -> Result<NonRevocationWitness, Error> {
+        if self.is_revoked(handle) {
+            return Err(RevocationError::Revoked.into());
+        }
+
+        let inverse: Scalar = self.inverse(handle).ok_or(RevocationError::Revoked)?;
+
+        Ok(NonRevocationWitness {
+            handle: Secret::new(*handle),
+            value: self.value,
+            W: &inverse * &self.value.value,
+        })
+    }
+
+    /// Revoke some `handles`, moving to the next epoch.
+    ///
+    /// # Returns
+    ///
+    /// The delta which holders apply to their witnesses.  Handles which were
+    /// already revoked are left out of it.
+    pub fn revoke(&mut self, handles: &[Scalar]) -> AccumulatorDelta {
+        let from_epoch: u64 = self.value.epoch;
+        let mut removals: Vec<Removal> = Vec::with_capacity(handles.len());
+
+        for handle in handles.iter() {
+            if self.is_revoked(handle) {
+                continue;
+            }
+            if let Some(inverse) = self.inverse(handle) {
+                self.value.value = &inverse * &self.value.value;
+            }
+            self.revoked.insert(handle.to_bytes());
+            removals.push(Removal { handle: *handle, value: self.value.value });
+        }
+        self.value.epoch += 1;
+
+        AccumulatorDelta { from_epoch, to_epoch: self.value.epoch, removals }
+    }
+
+    /// Verify a `presentation` of a revocable credential from `issuer`, and
+    /// that its handle has not been revoked.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::WrongEpoch` if the presentation was made against
+    ///   another value of this accumulator, e.g. because the holder has not
+    ///   refreshed their witness.
+    /// * `RevocationError::Credential` if the `issuer` has no key for
+    ///   revocable credentials.
+    /// * `RevocationError::InvalidProof` if the credential was not issued by
+    ///   the `issuer`, or its handle is not in this accumulator.
+    pub fn verify(&self, issuer: &RevocableIssuer, presentation: &NonRevocationPresentation) -> Result<(), Error> {
+        issuer.check_keypair()?;
+
+        if presentation.epoch != self.value.epoch {
+            return Err(RevocationError::WrongEpoch.into());
+        }
+
+        // A zero nonce would forge the MAC, and a zero r would make the
+        // proof trivial.
+        if presentation.P == RistrettoPoint::identity() ||
+           presentation.Vbar == RistrettoPoint::identity() ||
+           presentation.Wbar == RistrettoPoint::identity()
+        {
+            return Err(RevocationError::InvalidProof.into());
+        }
+
+        // The MAC is recomputed under the secret key, so this must remain
+        // constant-time.
+        let Z: RistrettoPoint = (&presentation.P * &issuer.keypair.secret.x0) +
+                                (&presentation.Cm1 * &issuer.keypair.secret.xn[0]) +
+                                (&presentation.Cm2 * &issuer.keypair.secret.xn[1]) -
+                                presentation.CQ;
+        let Q: RistrettoPoint = self.unblind(&presentation.Vbar, &presentation.Wbar);
+
+        let mut transcript = Transcript::new(b"AEONFLUX NON-REVOCATION");
+        let publics = valid_revocable::Publics {
+            A: &issuer.system_parameters.h,
+            P: &presentation.P,
+            X1: &issuer.keypair.public.Xn[0],
+            X2: &issuer.keypair.public.Xn[1],
+            Z: &Z,
+            Cm1: &presentation.Cm1,
+            Cm2: &presentation.Cm2,
+            V: &self.value.value,
+            Vbar: &presentation.Vbar,
+            Wbar: &presentation.Wbar,
+            Q: &Q,
+        };
+
+        if presentation.proof.verify(&mut transcript, publics).is_err() {
+            return Err(RevocationError::InvalidProof.into());
+        }
+        Ok(())
+    }
+}
+
+/// A holder's cached witness that their credential's handle is in an
+/// accumulator, as of some value of it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NonRevocationWitness {
+    pub(crate) handle: Secret<Scalar>,
+    /// The accumulator value this witness is for.
+    pub value: AccumulatorValue,
+    pub(crate) W: RistrettoPoint,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for NonRevocationWitness {
+    fn drop(&mut self) {
+        self.handle.clear();
+    }
+}
+
+impl NonRevocationWitness {
+    pub fn from_bytes(bytes: &[u8]) -> Result<NonRevocationWitness, RevocationError> {
+        if bytes.len() != SIZEOF_NON_REVOCATION_WITNESS {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let handle: Scalar = read_scalar(&bytes[..32])?;
+        let value = AccumulatorValue::from_bytes(&bytes[32..32 + SIZEOF_ACCUMULATOR_VALUE])?;
+        let W: RistrettoPoint = read_point(&bytes[32 + SIZEOF_ACCUMULATOR_VALUE..])?;
+
+        Ok(NonRevocationWitness { handle: Secret::new(handle), value, W })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_NON_REVOCATION_WITNESS);
+
+        v.extend(self.handle.expose_secret().as_bytes());
+        v.extend(self.value.to_bytes());
+        v.extend(self.W.compress().as_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(NonRevocationWitness,
+                                         "A valid byte sequence representing a NonRevocationWitness");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(NonRevocationWitness);
+impl_try_from_bytes!(NonRevocationWitness, RevocationError);
+
+impl NonRevocationWitness {
+    /// Update this witness with the next `delta` from the issuer.  Nothing is
+    /// changed unless the delta applies in full.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::WrongEpoch` if the delta does not start from this
+    ///   witness's epoch, e.g. because an earlier delta was missed.
+    /// * `RevocationError::Revoked` if the delta revokes this handle.
+    pub fn refresh_witness(&mut self, delta: &AccumulatorDelta) -> Result<(), Error> {
+        if delta.from_epoch != self.value.epoch || delta.to_epoch <= delta.from_epoch {
+            return Err(RevocationError::WrongEpoch.into());
+        }
+
+        let mut handle: Scalar = *self.handle.expose_secret();
+        let mut W: RistrettoPoint = self.W;
+
+        for removal in delta.removals.iter() {
+            if removal.handle == handle {
+                handle.clear();
+                return Err(RevocationError::Revoked.into());
+            }
+            W = &(removal.handle - handle).invert() * &(W - removal.value);
+        }
+        handle.clear();
+
+        self.W = W;
+        self.value.epoch = delta.to_epoch;
+
+        if let Some(removal) = delta.removals.last() {
+            self.value.value = removal.value;
+        }
+        Ok(())
+    }
+}
+
+/// A holder's request for a revocable credential over the identity of their
+/// ordinary credential and a fresh revocation handle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HandleRequest {
+    /// A presentation of the holder's identity credential.
+    pub presentation: CredentialPresentation,
+    /// A commitment to the identity, \\( B \cdot m_1 + A \cdot r_1 \\).
+    pub C1: RistrettoPoint,
+    /// The revocation handle, which the holder chose at random.  The issuer
+    /// keeps it, to revoke the credential with later.
+    pub handle: Scalar,
+    pub proof: device_enrollment::Proof,
+}
+
+impl HandleRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<HandleRequest, RevocationError> {
+        if bytes.len() < 72 {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let C1 = read_point(&bytes[00..32])?;
+        let handle = read_scalar(&bytes[32..64])?;
+        let length: usize = read_u64(&bytes[64..72]) as usize;
+
+        if bytes.len() - 72 < length {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let presentation = CredentialPresentation::from_bytes(&bytes[72..72 + length])?;
+        let proof: device_enrollment::Proof = match deserialize(&bytes[72 + length..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(RevocationError::WrongNumberOfBytes),
+        };
+
+        Ok(HandleRequest { presentation, C1, handle, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let presentation: Vec<u8> = self.presentation.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(72 + presentation.len() + device_enrollment::SIZEOF_PROOF);
+
+        v.extend(self.C1.compress().as_bytes());
+        v.extend(self.handle.as_bytes());
+        v.extend(u64_to_bytes(presentation.len() as u64).iter());
+        v.extend(presentation);
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(HandleRequest, "A valid byte sequence representing a HandleRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(HandleRequest);
+impl_try_from_bytes!(HandleRequest, RevocationError);
+
+/// The state a holder keeps between sending their `HandleRequest` and
+/// receiving the `RevocableIssuance` for it.
+#[derive(Debug)]
+pub struct HandleEnrollment {
+    identity: Secret<Scalar>,
+    handle: Secret<Scalar>,
+    r1: Ephemeral,
+    C1: RistrettoPoint,
+}
+
+impl Drop for HandleEnrollment {
+    fn drop(&mut self) {
+        self.identity.clear();
+        self.handle.clear();
+    }
+}
+
+impl HandleEnrollment {
+    /// Begin obtaining a revocable credential, with a fresh random handle,
+    /// for the holder of the `identity` credential.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::Credential` if the `identity` holds no credential,
+    ///   or it could not be presented.
+    pub fn new<R>(identity: &User, rng: &mut R) -> Result<(HandleEnrollment, HandleRequest), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        HandleEnrollment::new_with(identity, &PresentationOptions::new(), rng)
+    }
+
+    /// As `HandleEnrollment::new()`, presenting the `identity` credential
+    /// with some unscoped `options`, e.g. under its schema, with which the
+    /// issuer must verify it in `RevocableIssuer::issue_with()`.
+    pub fn new_with<R>(
+        identity: &User,
+        options: &PresentationOptions,
+        rng: &mut R,
+    ) -> Result<(HandleEnrollment, HandleRequest), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let credential: &Credential = identity.credential.as_ref().ok_or(CredentialError::MissingData)?;
+
+        let mut transcript = Transcript::new(b"AEONFLUX NON-REVOCATION ENROLLMENT");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+        let presentation: CredentialPresentation = identity.show_with(options, &nonces, &mut csprng)?.0;
+
+        let A: RistrettoPoint = identity.system_parameters.h;
+        let B: RistrettoPoint = identity.system_parameters.g;
+        let P: RistrettoPoint = presentation.rerandomized_nonce;
+        let Cm0: RistrettoPoint = presentation.attributes_blinded[0].into();
+
+        let identity: Secret<Scalar> = Secret::new(credential.attributes[0]);
+        let handle: Secret<Scalar> = Secret::new(Scalar::random(&mut csprng));
+        let r1: Ephemeral = Ephemeral::new(&mut csprng);
+        let C1: RistrettoPoint = pedersen::Commitment::to(&(&B * identity.expose_secret()), &r1, &A).into();
+
+        // The handle is shown to the issuer, so its commitment is unblinded.
+        let r2: Ephemeral = Scalar::zero().into();
+        let C2: RistrettoPoint = &B * handle.expose_secret();
+
+        let secrets = device_enrollment::Secrets {
+            m1: identity.expose_secret(),
+            m2: handle.expose_secret(),
+            z0: nonces[0].as_witness(),
+            r1: r1.as_witness(),
+            r2: r2.as_witness(),
+        };
+        let publics = device_enrollment::Publics {
+            B: &B,
+            A: &A,
+            P: &P,
+            Cm0: &Cm0,
+            C1: &C1,
+            C2: &C2,
+        };
+        let proof = device_enrollment::Proof::create(&mut transcript, publics, secrets);
+        let request = HandleRequest { presentation, C1, handle: *handle.expose_secret(), proof };
+
+        Ok((HandleEnrollment { identity, handle, r1, C1 }, request))
+    }
+
+    /// Verify the `issuance` for our request and, if valid, obtain the
+    /// revocable credential and its first witness.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::Issuance` if the issuer's proof did not verify.
+    pub fn finish(
+        self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &RevocableIssuerParameters,
+        issuance: &RevocableIssuance,
+    ) -> Result<(RevocableCredential, NonRevocationWitness), Error>
+    {
+        if issuer_parameters.Xn.len() != REVOCABLE_NUMBER_OF_ATTRIBUTES ||
+           issuance.P == RistrettoPoint::identity()
+        {
+            return Err(RevocationError::Issuance.into());
+        }
+
+        let C2: RistrettoPoint = &system_parameters.g * self.handle.expose_secret();
+
+        let mut transcript = Transcript::new(b"AEONFLUX NON-REVOCATION ISSUANCE");
+        let publics = device_issuance::Publics {
+            B: &system_parameters.g,
+            A: &system_parameters.h,
+            X1: &issuer_parameters.Xn[0],
+            X2: &issuer_parameters.Xn[1],
+            P: &issuance.P,
+            T1_0: &issuance.T1,
+            T1_1: &issuance.T1,
+            T2_0: &issuance.T2,
+            T2_1: &issuance.T2,
+            Cx0: &issuance.secret_key_commitment,
+            C1: &self.C1,
+            C2: &C2,
+            Q: &issuance.Q,
+        };
+
+        if issuance.proof.verify(&mut transcript, publics).is_err() {
+            return Err(RevocationError::Issuance.into());
+        }
+
+        // Since Q = (x0 + x1 * m1 + x2 * m2) * P + r1 * T1, removing the
+        // identity commitment's blinding leaves the MAC over our attributes.
+        let mac: RistrettoPoint = issuance.Q - (&issuance.T1 * self.r1.as_scalar());
+
+        let credential = RevocableCredential {
+            identity: *self.identity.expose_secret(),
+            handle: *self.handle.expose_secret(),
+            tag: amacs::Tag { nonce: issuance.P, mac },
+        };
+        let witness = NonRevocationWitness {
+            handle: Secret::new(*self.handle.expose_secret()),
+            value: issuance.value,
+            W: issuance.W,
+        };
+
+        Ok((credential, witness))
+    }
+}
+
+/// The issuer's response to a `HandleRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevocableIssuance {
+    pub P: RistrettoPoint,
+    /// The MAC over the identity and handle, still blinded by the identity
+    /// commitment's nonce.
+    pub Q: RistrettoPoint,
+    /// \\( X_1 \cdot b \\), for removing the identity commitment's blinding.
+    pub T1: RistrettoPoint,
+    /// \\( X_2 \cdot b \\).
+    pub T2: RistrettoPoint,
+    pub secret_key_commitment: RistrettoPoint,
+    /// The accumulator value the witness is for.
+    pub value: AccumulatorValue,
+    /// The holder's first witness, \\( W \\), for their handle.
+    pub W: RistrettoPoint,
+    pub proof: device_issuance::Proof,
+}
+
+impl RevocableIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RevocableIssuance, RevocationError> {
+        let offset: usize = 160 + SIZEOF_ACCUMULATOR_VALUE + 32;
+
+        if bytes.len() < offset {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let P = read_point(&bytes[00..32])?;
+        let Q = read_point(&bytes[32..64])?;
+        let T1 = read_point(&bytes[64..96])?;
+        let T2 = read_point(&bytes[96..128])?;
+        let secret_key_commitment = read_point(&bytes[128..160])?;
+        let value = AccumulatorValue::from_bytes(&bytes[160..160 + SIZEOF_ACCUMULATOR_VALUE])?;
+        let W = read_point(&bytes[160 + SIZEOF_ACCUMULATOR_VALUE..])?;
+        let proof: device_issuance::Proof = match deserialize(&bytes[offset..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(RevocationError::WrongNumberOfBytes),
+        };
+
+        Ok(RevocableIssuance { P, Q, T1, T2, secret_key_commitment, value, W, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(160 + SIZEOF_ACCUMULATOR_VALUE + 32 + device_issuance::SIZEOF_PROOF);
+
+        v.extend(self.P.compress().as_bytes());
+        v.extend(self.Q.compress().as_bytes());
+        v.extend(self.T1.compress().as_bytes());
+        v.extend(self.T2.compress().as_bytes());
+        v.extend(self.secret_key_commitment.compress().as_bytes());
+        v.extend(self.value.to_bytes());
+        v.extend(self.W.compress().as_bytes());
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RevocableIssuance, "A valid byte sequence representing a RevocableIssuance");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RevocableIssuance);
+impl_try_from_bytes!(RevocableIssuance, RevocationError);
+
+/// An issuer of revocable credentials.
+#[derive(Clone, Debug)]
+pub struct RevocableIssuer {
+    pub system_parameters: SystemParameters,
+    pub keypair: amacs::Keypair,
+}
+
+impl RevocableIssuer {
+    /// Create a new `RevocableIssuer` with a fresh key.
+    pub fn create<R>(system_parameters: SystemParameters, csprng: &mut R) -> RevocableIssuer
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = amacs::SecretKey::new(REVOCABLE_NUMBER_OF_ATTRIBUTES, csprng);
+        let public = secret.get_public_key(&system_parameters.h);
+
+        RevocableIssuer { system_parameters, keypair: amacs::Keypair { public, secret } }
+    }
+
+    /// Get this `RevocableIssuer`'s parameters for publishing to users.
+    pub fn get_issuer_parameters(&self) -> RevocableIssuerParameters {
+        self.keypair.public.clone()
+    }
+
+    fn check_keypair(&self) -> Result<(), RevocationError> {
+        if self.keypair.secret.xn.len() != REVOCABLE_NUMBER_OF_ATTRIBUTES ||
+           self.keypair.public.Xn.len() != REVOCABLE_NUMBER_OF_ATTRIBUTES
+        {
+            return Err(RevocationError::Credential(CredentialError::NoIssuerKey));
+        }
+        Ok(())
+    }
+
+    /// Issue a revocable credential to the holder of a credential from the
+    /// `identity` issuer, for the `request` they made, along with a witness
+    /// for its handle from the `accumulator`.
+    ///
+    /// The caller should keep `request.handle` with whatever account the
+    /// request came from, to revoke the credential with later.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::Enrollment` if the identity credential's
+    ///   presentation or the request's proof did not verify.
+    /// * `RevocationError::Revoked` if the requested handle was revoked.
+    /// * `RevocationError::Credential` if this issuer's key is not for
+    ///   revocable credentials.
+    pub fn issue<R>(
+        &self,
+        identity: &Issuer,
+        accumulator: &Accumulator,
+        request: &HandleRequest,
+        rng: &mut R,
+    ) -> Result<RevocableIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.issue_with(identity, &PresentationOptions::new(), accumulator, request, rng)
+    }
+
+    /// As `RevocableIssuer::issue()`, for a `request` whose identity
+    /// credential was presented with `options`, as in
+    /// `HandleEnrollment::new_with()`.
+    pub fn issue_with<R>(
+        &self,
+        identity: &Issuer,
+        options: &PresentationOptions,
+        accumulator: &Accumulator,
+        request: &HandleRequest,
+        rng: &mut R,
+    ) -> Result<RevocableIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.check_keypair()?;
+
+        if identity.verify_with(&request.presentation, options, None).is_err() || request.presentation.attributes_blinded.len() != 1 {
+            return Err(RevocationError::Enrollment.into());
+        }
+
+        let A: RistrettoPoint = self.system_parameters.h;
+        let B: RistrettoPoint = self.system_parameters.g;
+        let Cm0: RistrettoPoint = request.presentation.attributes_blinded[0].into();
+        let C2: RistrettoPoint = &B * &request.handle;
+
+        let mut transcript = Transcript::new(b"AEONFLUX NON-REVOCATION ENROLLMENT");
+        let publics = device_enrollment::Publics {
+            B: &B,
+            A: &A,
+            P: &request.presentation.rerandomized_nonce,
+            Cm0: &Cm0,
+            C1: &request.C1,
+            C2: &C2,
+        };
+
+        if request.proof.verify(&mut transcript, publics).is_err() {
+            return Err(RevocationError::Enrollment.into());
+        }
+
+        let witness: NonRevocationWitness = accumulator.witness(&request.handle)?;
+
+        let mut transcript = Transcript::new(b"AEONFLUX NON-REVOCATION ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let x0: &Scalar = &self.keypair.secret.x0;
+        let x1: &Scalar = &self.keypair.secret.xn[0];
+        let x2: &Scalar = &self.keypair.secret.xn[1];
+        let X1: &RistrettoPoint = &self.keypair.public.Xn[0];
+        let X2: &RistrettoPoint = &self.keypair.public.Xn[1];
+
+        // Commit to the issuer's secret x0.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let Cx0: RistrettoPoint = pedersen::Commitment::to(&(&B * x0), &x0_tilde, &A).into();
+
+        // As for a device credential, but since the handle's commitment is
+        // unblinded, t2 * C2 is simply x2 * m2 * P.
+        let b: Ephemeral = Ephemeral::new(&mut csprng);
+        let P: RistrettoPoint = &B * b.as_scalar();
+        let t1: Ephemeral = (b.as_scalar() * x1).into();
+        let t2: Ephemeral = (b.as_scalar() * x2).into();
+        let T1: RistrettoPoint = X1 * b.as_scalar();
+        let T2: RistrettoPoint = X2 * b.as_scalar();
+        let Q: RistrettoPoint = (&P * x0) + (&request.C1 * t1.as_scalar()) + (&C2 * t2.as_scalar());
+
+        let secrets = device_issuance::Secrets {
+            x0: x0,
+            x1: x1,
+            x2: x2,
+            x0_tilde: x0_tilde.as_witness(),
+            b: b.as_witness(),
+            t1: t1.as_witness(),
+            t2: t2.as_witness(),
+        };
+        let publics = device_issuance::Publics {
+            B: &B,
+            A: &A,
+            X1: X1,
+            X2: X2,
+            P: &P,
+            T1_0: &T1,
+            T1_1: &T1,
+            T2_0: &T2,
+            T2_1: &T2,
+            Cx0: &Cx0,
+            C1: &request.C1,
+            C2: &C2,
+            Q: &Q,
+        };
+        let proof = device_issuance::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(RevocableIssuance {
+            P,
+            Q,
+            T1,
+            T2,
+            secret_key_commitment: Cx0,
+            value: witness.value,
+            W: witness.W,
+            proof,
+        })
+    }
+}
+
+/// A credential over a holder's identity and a random revocation handle,
+/// which may be revoked from an `Accumulator` by its handle alone.
+#[derive(Clone, Eq, PartialEq)]
+pub struct RevocableCredential {
+    pub(crate) identity: Scalar,
+    handle: Scalar,
+    tag: amacs::Tag,
+}
+
+/// As with a `Credential`, tag and attributes are the holder's secrets.
+impl fmt::Debug for RevocableCredential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RevocableCredential {{ identity: {}, handle: {}, tag: {} }}", REDACTED, REDACTED, REDACTED)
+    }
+}
+
+impl RevocableCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RevocableCredential, RevocationError> {
+        if bytes.len() != SIZEOF_REVOCABLE_CREDENTIAL {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let identity = read_scalar(&bytes[00..32])?;
+        let handle = read_scalar(&bytes[32..64])?;
+        let tag = amacs::Tag::from_bytes(&bytes[64..])?;
+
+        Ok(RevocableCredential { identity, handle, tag })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_REVOCABLE_CREDENTIAL);
+
+        v.extend(self.identity.as_bytes());
+        v.extend(self.handle.as_bytes());
+        v.extend(self.tag.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RevocableCredential,
+                                         "A valid byte sequence representing a RevocableCredential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RevocableCredential);
+impl_try_from_bytes!(RevocableCredential, RevocationError);
+
+/// A presentation of a revocable credential, hiding its identity and handle,
+/// along with a proof that the handle has not been revoked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NonRevocationPresentation {
+    /// The rerandomised aMAC nonce.
+    pub P: RistrettoPoint,
+    /// A commitment to the rerandomised aMAC.
+    pub CQ: RistrettoPoint,
+    /// A commitment to the identity.
+    pub Cm1: RistrettoPoint,
+    /// A commitment to the handle.
+    pub Cm2: RistrettoPoint,
+    /// The accumulator epoch the proof was made against.
+    pub epoch: u64,
+    pub Vbar: RistrettoPoint,
+    pub Wbar: RistrettoPoint,
+    /// A proof that the credential is valid, and that the rerandomised
+    /// witness is for its handle.
+    pub proof: valid_revocable::Proof,
+}
+
+impl NonRevocationPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<NonRevocationPresentation, RevocationError> {
+        if bytes.len() != SIZEOF_NON_REVOCATION_PRESENTATION {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let P = read_point(&bytes[00..32])?;
+        let CQ = read_point(&bytes[32..64])?;
+        let Cm1 = read_point(&bytes[64..96])?;
+        let Cm2 = read_point(&bytes[96..128])?;
+        let epoch: u64 = read_u64(&bytes[128..136]);
+        let Vbar = read_point(&bytes[136..168])?;
+        let Wbar = read_point(&bytes[168..200])?;
+        let proof: valid_revocable::Proof = match deserialize(&bytes[200..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(RevocationError::WrongNumberOfBytes),
+        };
+
+        Ok(NonRevocationPresentation { P, CQ, Cm1, Cm2, epoch, Vbar, Wbar, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_NON_REVOCATION_PRESENTATION);
+
+        v.extend(self.P.compress().as_bytes());
+        v.extend(self.CQ.compress().as_bytes());
+        v.extend(self.Cm1.compress().as_bytes());
+        v.extend(self.Cm2.compress().as_bytes());
+        v.extend(u64_to_bytes(self.epoch).iter());
+        v.extend(self.Vbar.compress().as_bytes());
+        v.extend(self.Wbar.compress().as_bytes());
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(NonRevocationPresentation,
+                                         "A valid byte sequence representing a NonRevocationPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(NonRevocationPresentation);
+impl_try_from_bytes!(NonRevocationPresentation, RevocationError);
+
+impl NonRevocationPresentation {
+    /// Present a revocable `credential`, with a proof from its cached
+    /// `witness` that it has not been revoked.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::Credential` if the `issuer_parameters` are not for
+    ///   revocable credentials.
+    /// * `RevocationError::WrongHandle` if the `witness` is for another
+    ///   credential.
+    pub fn create<R>(
+        credential: &RevocableCredential,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &RevocableIssuerParameters,
+        witness: &NonRevocationWitness,
+        rng: &mut R,
+    ) -> Result<NonRevocationPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(NonRevocationPresentation::create_opened(credential, system_parameters, issuer_parameters, witness, rng)?.0)
+    }
+
+    /// As `NonRevocationPresentation::create()`, also returning the nonce
+    /// `z1` of the commitment to the identity, so that it may be linked to
+    /// another presentation.
+    pub(crate) fn create_opened<R>(
+        credential: &RevocableCredential,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &RevocableIssuerParameters,
+        witness: &NonRevocationWitness,
+        rng: &mut R,
+    ) -> Result<(NonRevocationPresentation, Ephemeral), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if issuer_parameters.Xn.len() != REVOCABLE_NUMBER_OF_ATTRIBUTES {
+            return Err(RevocationError::Credential(CredentialError::NoIssuerParameters).into());
+        }
+        if &credential.handle != witness.handle.expose_secret() {
+            return Err(RevocationError::WrongHandle.into());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX NON-REVOCATION");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let A: RistrettoPoint = system_parameters.h;
+        let tag: amacs::Tag = amacs::Rerandomization::new(&mut csprng).apply_to_tag(&credential.tag);
+        let P: RistrettoPoint = tag.nonce;
+
+        // Commit to the rerandomised aMAC and to each attribute.
+        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+        let z1: Ephemeral = Ephemeral::new(&mut csprng);
+        let z2: Ephemeral = Ephemeral::new(&mut csprng);
+        let CQ: RistrettoPoint = pedersen::Commitment::to(&tag.mac, &zQ, &A).into();
+        let Cm1: RistrettoPoint = pedersen::Commitment::to(&(&P * &credential.identity), &z1, &A).into();
+        let Cm2: RistrettoPoint = pedersen::Commitment::to(&(&P * &credential.handle), &z2, &A).into();
+        let Z: RistrettoPoint = (&issuer_parameters.Xn[0] * z1.as_scalar()) +
+                                (&issuer_parameters.Xn[1] * z2.as_scalar()) -
+                                (&A * zQ.as_scalar());
+
+        // Rerandomise the witness and the accumulator value.
+        let mut r: Scalar = Scalar::random(&mut csprng);
+        let Vbar: RistrettoPoint = &r * &witness.value.value;
+        let Wbar: RistrettoPoint = &r * &witness.W;
+        let Q: RistrettoPoint = &credential.handle * &Wbar;
+
+        let minus_zQ: Scalar = -zQ.as_scalar();
+        let proof = {
+            let secrets = valid_revocable::Secrets {
+                m1: &credential.identity,
+                m2: &credential.handle,
+                z1: z1.as_witness(),
+                z2: z2.as_witness(),
+                minus_zQ: &minus_zQ,
+                r: &r,
+            };
+            let publics = valid_revocable::Publics {
+                A: &A,
+                P: &P,
+                X1: &issuer_parameters.Xn[0],
+                X2: &issuer_parameters.Xn[1],
+                Z: &Z,
+                Cm1: &Cm1,
+                Cm2: &Cm2,
+                V: &witness.value.value,
+                Vbar: &Vbar,
+                Wbar: &Wbar,
+                Q: &Q,
+            };
+
+            valid_revocable::Proof::create(&mut transcript, publics, secrets)
+        };
+
+        r.clear();
+
+        Ok((NonRevocationPresentation { P, CQ, Cm1, Cm2, epoch: witness.value.epoch, Vbar, Wbar, proof }, z1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use test_utils::H;
+    use test_utils::holder;
+
+    /// Obtain a revocable credential for `user`, returning it, its witness,
+    /// and the handle the issuer would keep to revoke it.
+    fn revocable(
+        issuer: &Issuer,
+        revocable_issuer: &RevocableIssuer,
+        accumulator: &Accumulator,
+        user: &User,
+    ) -> (RevocableCredential, NonRevocationWitness, Scalar)
+    {
+        let mut rng = thread_rng();
+        let (enrollment, request) = HandleEnrollment::new(user, &mut rng).unwrap();
+        let request = HandleRequest::from_bytes(&request.to_bytes()).unwrap();
+        let issuance = revocable_issuer.issue(issuer, accumulator, &request, &mut rng).unwrap();
+        let issuance = RevocableIssuance::from_bytes(&issuance.to_bytes()).unwrap();
+        let (credential, witness) = enrollment.finish(&revocable_issuer.system_parameters,
+                                                      &revocable_issuer.get_issuer_parameters(),
+                                                      &issuance).unwrap();
+
+        (credential, witness, request.handle)
+    }
+
+    #[test]
+    fn witnesses_refresh_across_revocations() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let revocable_issuer = RevocableIssuer::create(SystemParameters::from(H), &mut rng);
+        let parameters = revocable_issuer.get_issuer_parameters();
+        let mut accumulator = Accumulator::create(&mut rng);
+        let (alice, mallory) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let (credential, mut witness, _) = revocable(&issuer, &revocable_issuer, &accumulator, &holder(&issuer, vec![alice], &mut rng));
+        let (_, mut revoked, handle) = revocable(&issuer, &revocable_issuer, &accumulator, &holder(&issuer, vec![mallory], &mut rng));
+
+        assert!(credential == RevocableCredential::from_bytes(&credential.to_bytes()).unwrap());
+
+        let presentation = NonRevocationPresentation::create(&credential, &revocable_issuer.system_parameters,
+                                                             &parameters, &witness, &mut rng).unwrap();
+        let presentation = NonRevocationPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+        assert!(accumulator.verify(&revocable_issuer, &presentation).is_ok());
+
+        let delta = accumulator.revoke(&[handle]);
+        let delta = AccumulatorDelta::from_bytes(&delta.to_bytes()).unwrap();
+
+        // The delta names the random handle, and never the identity.
+        assert!(delta.removals.len() == 1);
+        assert!(delta.removals[0].handle == handle);
+        assert!(delta.removals[0].handle != mallory);
+
+        // Stale presentations are refused until the witness is refreshed.
+        assert!(accumulator.verify(&revocable_issuer, &presentation).err() == Some(RevocationError::WrongEpoch.into()));
+
+        witness.refresh_witness(&delta).unwrap();
+
+        assert!(witness.value == accumulator.value());
+        assert!(witness == NonRevocationWitness::from_bytes(&witness.to_bytes()).unwrap());
+
+        let presentation = NonRevocationPresentation::create(&credential, &revocable_issuer.system_parameters,
+                                                             &parameters, &witness, &mut rng).unwrap();
+
+        assert!(accumulator.verify(&revocable_issuer, &presentation).is_ok());
+        assert!(witness.refresh_witness(&delta) == Err(RevocationError::WrongEpoch.into()));
+        assert!(revoked.refresh_witness(&delta) == Err(RevocationError::Revoked.into()));
+        assert!(accumulator.witness(&handle).err() == Some(RevocationError::Revoked.into()));
+    }
+
+    #[test]
+    fn revoked_handles_cannot_prove_non_revocation() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let revocable_issuer = RevocableIssuer::create(SystemParameters::from(H), &mut rng);
+        let parameters = revocable_issuer.get_issuer_parameters();
+        let mut accumulator = Accumulator::create(&mut rng);
+        let user = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+        let (credential, mut witness, handle) = revocable(&issuer, &revocable_issuer, &accumulator, &user);
+
+        assert!(NonRevocationPresentation::create(&credential, &revocable_issuer.system_parameters, &parameters,
+                                                  &accumulator.witness(&Scalar::one()).unwrap(), &mut rng)
+                .err() == Some(RevocationError::WrongHandle.into()));
+
+        accumulator.revoke(&[handle]);
+
+        // Pretending the old witness is for the new epoch doesn't help.
+        witness.value.epoch = accumulator.value().epoch;
+
+        let presentation = NonRevocationPresentation::create(&credential, &revocable_issuer.system_parameters,
+                                                             &parameters, &witness, &mut rng).unwrap();
+
+        assert!(accumulator.verify(&revocable_issuer, &presentation).err() == Some(RevocationError::InvalidProof.into()));
+
+        // Nor does asking for a new credential with the revoked handle.
+        let (_, mut request) = HandleEnrollment::new(&user, &mut rng).unwrap();
+        let fresh: Scalar = request.handle;
+
+        request.handle = handle;
+
+        assert!(revocable_issuer.issue(&issuer, &accumulator, &request, &mut rng).err() ==
+                Some(RevocationError::Enrollment.into()));
+
+        request.handle = fresh;
+        accumulator.revoke(&[fresh]);
+
+        assert!(revocable_issuer.issue(&issuer, &accumulator, &request, &mut rng).err() ==
+                Some(RevocationError::Revoked.into()));
+
+        let restored = Accumulator::from_bytes(&accumulator.to_bytes()).unwrap();
+
+        assert!(restored.is_revoked(&handle));
+        assert!(restored.value() == accumulator.value());
+    }
+}
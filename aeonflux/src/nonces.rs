@@ -18,8 +18,6 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
-use clear_on_drop::clear::Clear;
-
 use curve25519_dalek::ristretto::RistrettoBasepointTable;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
@@ -27,14 +25,39 @@ use curve25519_dalek::scalar::Scalar;
 use rand_core::CryptoRng;
 use rand_core::RngCore;
 
+use secret::Clear;
+use secret::Secret;
+
 
 /// An ephemeral key or nonce, used in elGamal encryptions and then discarded.
+///
+/// The scalar is never exposed.  It is passed to proofs as a `Witness`, and is
+/// overwritten when the `Ephemeral` is dropped.
 #[derive(Clone, Debug, Default)]
-pub struct Ephemeral(Scalar);
+pub struct Ephemeral(Secret<Scalar>);
+
+/// An `Ephemeral`, or another secret scalar, borrowed as the witness to a
+/// proof.
+///
+/// Only the proofs in this crate may read a `Witness`, so that the scalar it
+/// borrows cannot be copied out from under the `Ephemeral`.
+#[derive(Clone, Copy)]
+pub struct Witness<'a>(&'a Scalar);
+
+impl<'a> Witness<'a> {
+    /// Borrow a `secret` as a witness, without copying it.
+    pub(crate) fn from_secret(secret: &'a Secret<Scalar>) -> Witness<'a> {
+        Witness(secret.expose_secret())
+    }
+
+    pub(crate) fn scalar(&self) -> &'a Scalar {
+        self.0
+    }
+}
 
 impl From<Scalar> for Ephemeral {
     fn from(source: Scalar) -> Ephemeral {
-        Ephemeral(source)
+        Ephemeral(Secret::new(source))
     }
 }
 
@@ -45,17 +68,20 @@ impl Ephemeral {
     where
         R: CryptoRng + RngCore
     {
-        Ephemeral(Scalar::random(csprng))
+        Ephemeral(Secret::new(Scalar::random(csprng)))
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        self.0.to_bytes().to_vec()
+        self.0.expose_secret().to_bytes().to_vec()
     }
-}
 
-impl<'s, 'e: 's> From<&'e Ephemeral> for &'s Scalar {
-    fn from(source: &'e Ephemeral) -> &'s Scalar {
-        &source.0
+    /// Borrow this `Ephemeral` as the witness to a proof.
+    pub fn as_witness(&self) -> Witness {
+        Witness(self.0.expose_secret())
+    }
+
+    pub(crate) fn as_scalar(&self) -> &Scalar {
+        self.0.expose_secret()
     }
 }
 
@@ -70,7 +96,7 @@ impl<'a, 'b> Mul<&'b RistrettoBasepointTable> for &'a Ephemeral {
     type Output = RistrettoPoint;
 
     fn mul(self, other: &'b RistrettoBasepointTable) -> RistrettoPoint {
-        &self.0 * other
+        self.0.expose_secret() * other
     }
 }
 
@@ -78,7 +104,7 @@ impl<'a, 'b> Mul<&'a Ephemeral> for &'b RistrettoBasepointTable {
     type Output = RistrettoPoint;
 
     fn mul(self, other: &'a Ephemeral) -> RistrettoPoint {
-        self * &other.0
+        self * other.0.expose_secret()
     }
 }
 
@@ -86,7 +112,7 @@ impl<'a, 'b> Mul<&'a Ephemeral> for &'b RistrettoPoint {
     type Output = RistrettoPoint;
 
     fn mul(self, other: &'a Ephemeral) -> RistrettoPoint {
-        self * &other.0
+        self * other.0.expose_secret()
     }
 }
 
@@ -94,7 +120,7 @@ impl<'a, 'b> Mul<&'b RistrettoPoint> for &'a Ephemeral {
     type Output = RistrettoPoint;
 
     fn mul(self, other: &'b RistrettoPoint) -> RistrettoPoint {
-        &self.0 * other
+        self.0.expose_secret() * other
     }
 }
 
@@ -102,7 +128,7 @@ impl<'a, > Mul<&'a Ephemeral> for RistrettoPoint {
     type Output = RistrettoPoint;
 
     fn mul(self, other: &'a Ephemeral) -> RistrettoPoint {
-        self * &other.0
+        self * other.0.expose_secret()
     }
 }
 
@@ -110,7 +136,7 @@ impl<'b> Mul<&'b RistrettoPoint> for Ephemeral {
     type Output = RistrettoPoint;
 
     fn mul(self, other: &'b RistrettoPoint) -> RistrettoPoint {
-        &self.0 * other
+        self.0.expose_secret() * other
     }
 }
 
@@ -118,7 +144,7 @@ impl<'a> Mul<RistrettoPoint> for &'a Ephemeral {
     type Output = RistrettoPoint;
 
     fn mul(self, other: RistrettoPoint) -> RistrettoPoint {
-        &self.0 * other
+        self.0.expose_secret() * other
     }
 }
 
@@ -126,7 +152,7 @@ impl<'b> Mul<Ephemeral> for &'b RistrettoPoint {
     type Output = RistrettoPoint;
 
     fn mul(self, other: Ephemeral) -> RistrettoPoint {
-        self * &other.0
+        self * other.0.expose_secret()
     }
 }
 
@@ -134,7 +160,7 @@ impl Mul<RistrettoPoint> for Ephemeral {
     type Output = RistrettoPoint;
 
     fn mul(self, other: RistrettoPoint) -> RistrettoPoint {
-        &self.0 * other
+        self.0.expose_secret() * other
     }
 }
 
@@ -142,7 +168,7 @@ impl Mul<Ephemeral> for RistrettoPoint {
     type Output = RistrettoPoint;
 
     fn mul(self, other: Ephemeral) -> RistrettoPoint {
-        self * &other.0
+        self * other.0.expose_secret()
     }
 }
 
@@ -150,7 +176,7 @@ impl Neg for Ephemeral {
     type Output = Ephemeral;
 
     fn neg(self) -> Ephemeral {
-        Ephemeral(-self.0)
+        Ephemeral(Secret::new(-self.0.expose_secret()))
     }
 }
 
@@ -194,3 +220,114 @@ impl Nonces {
         self.0.iter()
     }
 }
+
+/// A batch of `Ephemeral`s generated ahead of time, to be handed out one at a
+/// time.
+///
+/// The whole batch is drawn from the generator in a single call, which on
+/// mobile platforms saves a system call per nonce during bursts of issuance
+/// or presentation.  Any nonces not handed out are overwritten when the pool
+/// is dropped.
+#[derive(Debug, Default)]
+pub struct NoncePool(Vec<Ephemeral>);
+
+impl Drop for NoncePool {
+    fn drop(&mut self) {
+        for x in self.0.iter_mut() {
+            x.clear();
+        }
+    }
+}
+
+impl NoncePool {
+    /// Generate a pool of `size` nonces.
+    pub fn new<R>(
+        csprng: &mut R,
+        size: usize,
+    ) -> NoncePool
+    where
+        R: CryptoRng + RngCore
+    {
+        let mut pool = NoncePool(Vec::with_capacity(size));
+
+        pool.refill(csprng, size);
+        pool
+    }
+
+    /// Generate another `size` nonces in one pass and add them to the pool.
+    pub fn refill<R>(
+        &mut self,
+        csprng: &mut R,
+        size: usize,
+    )
+    where
+        R: CryptoRng + RngCore
+    {
+        let mut bytes: Vec<u8> = Vec::with_capacity(64 * size);
+        let mut wide: [u8; 64] = [0u8; 64];
+
+        bytes.resize(64 * size, 0);
+        csprng.fill_bytes(&mut bytes);
+
+        for chunk in bytes.chunks(64) {
+            wide.copy_from_slice(chunk);
+            self.0.push(Ephemeral::from(Scalar::from_bytes_mod_order_wide(&wide)));
+        }
+        bytes[..].clear();
+        wide.clear();
+    }
+
+    /// The number of nonces left in the pool.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Take the next nonce from the pool, if there are any left.
+    pub fn take(&mut self) -> Option<Ephemeral> {
+        self.0.pop()
+    }
+
+    /// Take the next `size` nonces from the pool as `Nonces`, e.g. for
+    /// `User::show()`, if there are enough left.
+    pub fn take_nonces(&mut self, size: usize) -> Option<Nonces> {
+        if self.0.len() < size {
+            return None;
+        }
+
+        let at: usize = self.0.len() - size;
+
+        Some(Nonces(self.0.split_off(at)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn pools_hand_out_distinct_nonces() {
+        let mut rng = thread_rng();
+        let mut pool = NoncePool::new(&mut rng, 3);
+        let a: Ephemeral = pool.take().unwrap();
+
+        assert!(pool.len() == 2);
+        assert!(pool.take_nonces(3).is_none());
+
+        let nonces: Nonces = pool.take_nonces(2).unwrap();
+
+        assert!(pool.is_empty());
+        assert!(pool.take().is_none());
+        assert!(a.to_bytes() != nonces[0].to_bytes());
+        assert!(nonces[0].to_bytes() != nonces[1].to_bytes());
+
+        pool.refill(&mut rng, 1);
+
+        assert!(pool.len() == 1);
+    }
+}
@@ -0,0 +1,239 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! The standard derivation of scoped nullifiers from a credential's key.
+//!
+//! A holder's nullifier within a scope is
+//!
+//! \\[
+//!     N = k \cdot \mathrm{H2G}(\mathrm{scope}),
+//! \\]
+//!
+//! where \\( k \\) is the credential's hidden attribute, and
+//! \\( \mathrm{H2G} \\) is `hash_to_ristretto255` from RFC 9380, with
+//! `expand_message_xmd` over SHA-512 and the domain separation tag
+//! `SCOPED_NULLIFIER_DST`.  A scope made of several parts, e.g. an
+//! application, an epoch, and a counter, is first encoded with
+//! `encode_scope()`.  The nullifier is sent as its compressed encoding.
+//!
+//! `show()` presents a credential with its nullifier in a scope, under the
+//! credential proof's challenge, and `verify()` checks both.  So:
+//!
+//! * a credential has exactly one nullifier in each scope, and always shows
+//!   the same one there, so a verifier can detect repeated use;
+//! * nullifiers of the same credential in different scopes cannot be linked
+//!   to one another without knowing \\( k \\), under the decisional
+//!   Diffie-Hellman assumption; and
+//! * the presentation itself is rerandomised on every showing, so only the
+//!   nullifier links two showings within one scope.
+//!
+//! Every scoped tag in this crate, from `rate_limit`, `sybil`, `report`, and
+//! `acceleration`, is derived this way, each within scopes encoded under a
+//! label of its own, so that no two of them ever share a basepoint.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::CredentialPresentation;
+use credential::VerifiedCredential;
+use errors::CredentialError;
+use errors::Error;
+use issuer::Issuer;
+use nonces::Nonces;
+use rate_limit::Nullifier;
+use rate_limit::SIZEOF_NULLIFIER;
+use user::User;
+use voprf::expand_message_xmd;
+
+/// The domain separation tag for hashing a scope to the group.
+pub const SCOPED_NULLIFIER_DST: &'static [u8] = b"aeonflux-V1-ScopedNullifier-ristretto255_XMD:SHA-512_R255MAP_RO_";
+
+/// Hash a `scope` to a point, \\( \mathrm{H2G}(\mathrm{scope}) \\).
+pub fn hash_scope_to_group(scope: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&expand_message_xmd(scope, SCOPED_NULLIFIER_DST))
+}
+
+/// Encode a scope made of several `parts`, each as `I2OSP(len(part), 2) ||
+/// part`, so that no two lists of parts encode to the same scope.
+///
+/// # Errors
+///
+/// * `Error::Misuse` if a part is longer than 65535 bytes.
+pub fn encode_scope(parts: &[&[u8]]) -> Result<Vec<u8>, Error> {
+    let mut v: Vec<u8> = Vec::new();
+
+    for part in parts.iter() {
+        if part.len() > 0xFFFF {
+            return Err(Error::Misuse);
+        }
+
+        v.push((part.len() >> 8) as u8);
+        v.push(part.len() as u8);
+        v.extend(part.iter());
+    }
+    Ok(v)
+}
+
+/// Derive the nullifier, \\( k \cdot \mathrm{H2G}(\mathrm{scope}) \\), of a
+/// credential `key` within a `scope`.
+pub fn scoped_nullifier(scope: &[u8], key: &Scalar) -> Nullifier {
+    Nullifier::from(key * hash_scope_to_group(scope))
+}
+
+/// A credential presentation, bound to the credential's nullifier in some
+/// scope.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScopedPresentation {
+    pub nullifier: Nullifier,
+    pub presentation: CredentialPresentation,
+}
+
+impl ScopedPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ScopedPresentation, CredentialError> {
+        if bytes.len() < SIZEOF_NULLIFIER {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let nullifier = Nullifier::from_bytes(&bytes[..SIZEOF_NULLIFIER])?;
+        let presentation = CredentialPresentation::from_bytes(&bytes[SIZEOF_NULLIFIER..])?;
+
+        Ok(ScopedPresentation { nullifier, presentation })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.nullifier.0.iter());
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ScopedPresentation, "A valid byte sequence representing a ScopedPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ScopedPresentation);
+impl_try_from_bytes!(ScopedPresentation, CredentialError);
+
+/// Present the `user`'s credential with its nullifier in a `scope`.
+///
+/// The credential proof's challenge covers the statement that the nullifier
+/// is \\( k \cdot \mathrm{H2G}(\mathrm{scope}) \\) for the same \\( k \\) as
+/// the credential's hidden attribute.
+pub fn show<R>(
+    user: &User,
+    scope: &[u8],
+    nonces: &Nonces,
+    rng: &mut R,
+) -> Result<ScopedPresentation, Error>
+where
+    R: RngCore + CryptoRng,
+{
+    let basepoint: RistrettoPoint = hash_scope_to_group(scope);
+    let (presentation, N) = user.show_scoped(&basepoint, nonces, rng)?;
+
+    Ok(ScopedPresentation { nullifier: Nullifier::from(N), presentation })
+}
+
+/// Verify a `presentation` and that its nullifier was derived within `scope`
+/// from the same credential.
+///
+/// This does not check whether the nullifier was seen before, which is up
+/// to the caller, e.g. with a `rate_limit::NullifierStore`.
+pub fn verify(
+    issuer: &Issuer,
+    scope: &[u8],
+    presentation: &ScopedPresentation,
+) -> Result<VerifiedCredential, Error>
+{
+    let basepoint: RistrettoPoint = hash_scope_to_group(scope);
+    let N: RistrettoPoint = CompressedRistretto(presentation.nullifier.0).decompress()
+        .ok_or(CredentialError::PointDecompressionError)?;
+
+    issuer.verify_scoped(&presentation.presentation, &basepoint, &N)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use test_utils::holder;
+
+    #[test]
+    fn nullifiers_follow_the_formula() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let key = Scalar::random(&mut rng);
+        let user = holder(&issuer, vec![key], &mut rng);
+        let scope = encode_scope(&[b"example.com", b"vote 2024"]).unwrap();
+
+        let shown = show(&user, &scope, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let shown = ScopedPresentation::from_bytes(&shown.to_bytes()).unwrap();
+
+        assert!(shown.nullifier == scoped_nullifier(&scope, &key));
+        assert!(shown.nullifier == Nullifier::from(key * hash_scope_to_group(&scope)));
+        assert!(verify(&issuer, &scope, &shown).is_ok());
+
+        // Scope encodings are unambiguous.
+        assert!(encode_scope(&[b"ab", b"c"]) != encode_scope(&[b"a", b"bc"]));
+        assert!(hash_scope_to_group(b"") != hash_scope_to_group(&encode_scope(&[b""]).unwrap()));
+
+        // Parts too long for their length prefix are refused.
+        assert!(encode_scope(&[&[0u8; 0x10000][..]]) == Err(Error::Misuse));
+        assert!(encode_scope(&[&[0u8; 0xFFFF][..]]).is_ok());
+    }
+
+    #[test]
+    fn nullifiers_link_only_within_a_scope() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let alice = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+        let bob = holder(&issuer, vec![Scalar::random(&mut rng)], &mut rng);
+
+        let first = show(&alice, b"poll 1", &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let again = show(&alice, b"poll 1", &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let elsewhere = show(&alice, b"poll 2", &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let other = show(&bob, b"poll 1", &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        // Repeated use within a scope is detectable, though the presentations
+        // themselves are unlinkable.
+        assert!(first.nullifier == again.nullifier);
+        assert!(first.presentation != again.presentation);
+
+        // Different scopes and different holders give different nullifiers.
+        assert!(first.nullifier != elsewhere.nullifier);
+        assert!(first.nullifier != other.nullifier);
+
+        // A nullifier only verifies in the scope it was derived for, and with
+        // the credential it was derived from.
+        assert!(verify(&issuer, b"poll 2", &first).is_err());
+
+        let mut swapped = other.clone();
+
+        swapped.nullifier = first.nullifier;
+
+        assert!(verify(&issuer, b"poll 1", &swapped).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Synchronisation of spent nullifiers between several gates.
+//!
+//! Each gate keeps a `NullifierLedger` of the nullifiers it has accepted,
+//! scoped by epoch, and periodically gossips a `SyncMessage` containing
+//! whatever it has learned since the last message to each of its peers.
+//! Merging is a set union, so gates which have exchanged messages in any
+//! order and over any path agree on the spent nullifiers.
+//!
+//! When an epoch is over, any gate may close it.  This drops its nullifiers
+//! and leaves a tombstone, which is gossiped like anything else, so that late
+//! messages cannot resurrect the epoch and no gate will accept a spend in it.
+//!
+//! Every nullifier is recorded along with the gate which accepted it.  If two
+//! gates accepted the same nullifier before hearing of each other's spend,
+//! the merge reports it as a `DoubleSpend`, and every gate settles on the
+//! lower `GateId` as its origin.
+//!
+//! # Note
+//!
+//! Gossip narrows, but cannot close, the window in which a one-show ticket
+//! may be spent at two gates which have not yet heard from each other.  Gates
+//! which must never admit a double spend should instead check a single
+//! authoritative ledger.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use errors::Error;
+use errors::SyncError;
+use rate_limit::Nullifier;
+use rate_limit::SIZEOF_NULLIFIER;
+
+/// The size of an encoded nullifier and its originating gate.
+const SIZEOF_SYNC_ENTRY: usize = SIZEOF_NULLIFIER + 8;
+
+/// An identifier for a gate participating in synchronisation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct GateId(pub u64);
+
+/// A nullifier which was accepted at more than one gate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct DoubleSpend {
+    pub epoch: u64,
+    pub nullifier: Nullifier,
+    pub origins: (GateId, GateId),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Spend {
+    nullifier: Nullifier,
+    origin: GateId,
+    /// The local sequence number at which this spend was learned.
+    sequence: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct EpochSpends {
+    epoch: u64,
+    /// Spends, sorted by nullifier.
+    spends: Vec<Spend>,
+}
+
+/// A tiny helper for reading little-endian integers from a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SyncError> {
+        if self.bytes.len() - self.offset < n {
+            return Err(SyncError::WrongNumberOfBytes);
+        }
+        let slice = &self.bytes[self.offset..self.offset + n];
+
+        self.offset += n;
+
+        Ok(slice)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, SyncError> {
+        let mut x: u64 = 0;
+
+        for (i, byte) in self.take(n)?.iter().enumerate() {
+            x |= (*byte as u64) << (8 * i);
+        }
+        Ok(x)
+    }
+}
+
+fn push_uint(v: &mut Vec<u8>, x: u64, n: usize) {
+    for i in 0..n {
+        v.push((x >> (8 * i)) as u8);
+    }
+}
+
+/// A gossip message, carrying some or all of a gate's ledger to its peers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyncMessage {
+    /// The gate which sent this message.
+    pub sender: GateId,
+    /// The sender's sequence number as of this message.  Peers should pass
+    /// this to `NullifierLedger::delta()` to request the next message.
+    pub sequence: u64,
+    /// Epochs which have been closed, in ascending order.
+    pub tombstones: Vec<u64>,
+    /// Spent nullifiers and the gates which accepted them, by epoch.
+    pub spends: Vec<(u64, Vec<(Nullifier, GateId)>)>,
+}
+
+impl SyncMessage {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SyncMessage, SyncError> {
+        let mut reader = Reader { bytes: bytes, offset: 0 };
+
+        let sender = GateId(reader.uint(8)?);
+        let sequence = reader.uint(8)?;
+
+        let number_of_tombstones = reader.uint(4)? as usize;
+        let mut tombstones: Vec<u64> = Vec::new();
+
+        for _ in 0..number_of_tombstones {
+            tombstones.push(reader.uint(8)?);
+        }
+
+        let number_of_epochs = reader.uint(4)? as usize;
+        let mut spends: Vec<(u64, Vec<(Nullifier, GateId)>)> = Vec::new();
+
+        for _ in 0..number_of_epochs {
+            let epoch = reader.uint(8)?;
+            let count = reader.uint(4)? as usize;
+            let mut nullifiers: Vec<(Nullifier, GateId)> = Vec::new();
+
+            for _ in 0..count {
+                let entry = reader.take(SIZEOF_SYNC_ENTRY)?;
+                let nullifier = Nullifier::from_bytes(&entry[..SIZEOF_NULLIFIER])?;
+                let origin = GateId(Reader { bytes: &entry[SIZEOF_NULLIFIER..], offset: 0 }.uint(8)?);
+
+                nullifiers.push((nullifier, origin));
+            }
+            spends.push((epoch, nullifiers));
+        }
+
+        if reader.offset != bytes.len() {
+            return Err(SyncError::WrongNumberOfBytes);
+        }
+
+        Ok(SyncMessage { sender, sequence, tombstones, spends })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        push_uint(&mut v, self.sender.0, 8);
+        push_uint(&mut v, self.sequence, 8);
+        push_uint(&mut v, self.tombstones.len() as u64, 4);
+
+        for epoch in self.tombstones.iter() {
+            push_uint(&mut v, *epoch, 8);
+        }
+
+        push_uint(&mut v, self.spends.len() as u64, 4);
+
+        for &(epoch, ref nullifiers) in self.spends.iter() {
+            push_uint(&mut v, epoch, 8);
+            push_uint(&mut v, nullifiers.len() as u64, 4);
+
+            for &(ref nullifier, ref origin) in nullifiers.iter() {
+                v.extend(nullifier.0.iter());
+                push_uint(&mut v, origin.0, 8);
+            }
+        }
+        v
+    }
+
+    /// Whether this message carries nothing new.
+    pub fn is_empty(&self) -> bool {
+        self.tombstones.is_empty() && self.spends.is_empty()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SyncMessage, "A valid byte sequence representing a SyncMessage");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SyncMessage);
+impl_try_from_bytes!(SyncMessage, SyncError);
+
+/// A gate's epoch-scoped record of spent nullifiers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NullifierLedger {
+    gate: GateId,
+    /// Incremented whenever this ledger learns something new.
+    sequence: u64,
+    /// Open epochs, sorted by epoch.
+    epochs: Vec<EpochSpends>,
+    /// Closed epochs, sorted, along with the sequence number at which each
+    /// was closed.
+    tombstones: Vec<(u64, u64)>,
+}
+
+impl NullifierLedger {
+    /// Create a new, empty ledger for the gate with the given `GateId`.
+    pub fn new(gate: GateId) -> NullifierLedger {
+        NullifierLedger {
+            gate: gate,
+            sequence: 0,
+            epochs: Vec::new(),
+            tombstones: Vec::new(),
+        }
+    }
+
+    pub fn gate(&self) -> GateId {
+        self.gate
+    }
+
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Whether `epoch` has been closed.
+    pub fn is_closed(&self, epoch: u64) -> bool {
+        self.tombstones.binary_search_by(|&(e, _)| e.cmp(&epoch)).is_ok()
+    }
+
+    /// Whether `nullifier` has been spent in `epoch`.
+    pub fn contains(&self, epoch: u64, nullifier: &Nullifier) -> bool {
+        match self.epochs.binary_search_by(|e| e.epoch.cmp(&epoch)) {
+            Ok(i) => self.epochs[i].spends.binary_search_by(|s| s.nullifier.0.cmp(&nullifier.0)).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// The number of nullifiers spent in `epoch`.
+    pub fn len(&self, epoch: u64) -> usize {
+        match self.epochs.binary_search_by(|e| e.epoch.cmp(&epoch)) {
+            Ok(i) => self.epochs[i].spends.len(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Record `nullifier` as spent at this gate in `epoch`.
+    ///
+    /// # Errors
+    ///
+    /// * `SyncError::EpochClosed` if `epoch` has been closed.
+    /// * `SyncError::NullifierReused` if `nullifier` was already spent in
+    ///   `epoch`, at this gate or at any gate this one has heard from.
+    pub fn spend(&mut self, epoch: u64, nullifier: &Nullifier) -> Result<(), Error> {
+        let gate = self.gate;
+
+        match self.record(epoch, nullifier, gate)? {
+            None => Ok(()),
+            Some(_) => Err(SyncError::NullifierReused.into()),
+        }
+    }
+
+    /// Record a spend, returning the existing origin if it was already known.
+    fn record(&mut self, epoch: u64, nullifier: &Nullifier, origin: GateId)
+        -> Result<Option<GateId>, SyncError>
+    {
+        if self.is_closed(epoch) {
+            return Err(SyncError::EpochClosed);
+        }
+
+        let index = match self.epochs.binary_search_by(|e| e.epoch.cmp(&epoch)) {
+            Ok(i) => i,
+            Err(i) => {
+                self.epochs.insert(i, EpochSpends { epoch: epoch, spends: Vec::new() });
+                i
+            },
+        };
+        let sequence = self.sequence + 1;
+        let spends = &mut self.epochs[index].spends;
+
+        match spends.binary_search_by(|s| s.nullifier.0.cmp(&nullifier.0)) {
+            Ok(i) => {
+                let existing = spends[i].origin;
+
+                // Settle on the lowest origin, so that all gates converge.
+                if origin < existing {
+                    spends[i].origin = origin;
+                    spends[i].sequence = sequence;
+                    self.sequence = sequence;
+                }
+                Ok(Some(existing))
+            },
+            Err(i) => {
+                spends.insert(i, Spend { nullifier: *nullifier, origin: origin, sequence: sequence });
+                self.sequence = sequence;
+                Ok(None)
+            },
+        }
+    }
+
+    /// Close `epoch`, forgetting its nullifiers and refusing any further
+    /// spends in it.
+    pub fn close(&mut self, epoch: u64) {
+        if let Err(i) = self.tombstones.binary_search_by(|&(e, _)| e.cmp(&epoch)) {
+            self.sequence += 1;
+            self.tombstones.insert(i, (epoch, self.sequence));
+        }
+        self.epochs.retain(|e| e.epoch != epoch);
+    }
+
+    /// Close every open epoch before `epoch`.
+    pub fn close_before(&mut self, epoch: u64) {
+        let stale: Vec<u64> = self.epochs.iter().map(|e| e.epoch).filter(|e| *e < epoch).collect();
+
+        for e in stale.iter() {
+            self.close(*e);
+        }
+    }
+
+    /// Produce a message containing everything this ledger has learned after
+    /// the sequence number `since`.  Passing `0` produces the whole ledger.
+    pub fn delta(&self, since: u64) -> SyncMessage {
+        let tombstones: Vec<u64> = self.tombstones.iter()
+            .filter(|&&(_, sequence)| sequence > since)
+            .map(|&(epoch, _)| epoch)
+            .collect();
+        let mut spends: Vec<(u64, Vec<(Nullifier, GateId)>)> = Vec::new();
+
+        for e in self.epochs.iter() {
+            let nullifiers: Vec<(Nullifier, GateId)> = e.spends.iter()
+                .filter(|s| s.sequence > since)
+                .map(|s| (s.nullifier, s.origin))
+                .collect();
+
+            if !nullifiers.is_empty() {
+                spends.push((e.epoch, nullifiers));
+            }
+        }
+
+        SyncMessage { sender: self.gate, sequence: self.sequence, tombstones, spends }
+    }
+
+    /// Merge a peer's `message` into this ledger.
+    ///
+    /// Tombstones are applied first, so spends in epochs which either side
+    /// has closed are discarded.
+    ///
+    /// # Returns
+    ///
+    /// Any nullifiers which were accepted by different gates.
+    pub fn merge(&mut self, message: &SyncMessage) -> Vec<DoubleSpend> {
+        let mut double_spends: Vec<DoubleSpend> = Vec::new();
+
+        for epoch in message.tombstones.iter() {
+            self.close(*epoch);
+        }
+
+        for &(epoch, ref nullifiers) in message.spends.iter() {
+            for &(ref nullifier, origin) in nullifiers.iter() {
+                match self.record(epoch, nullifier, origin) {
+                    Ok(Some(existing)) if existing != origin => {
+                        double_spends.push(DoubleSpend {
+                            epoch: epoch,
+                            nullifier: *nullifier,
+                            origins: if existing < origin { (existing, origin) } else { (origin, existing) },
+                        });
+                    },
+                    _ => (),
+                }
+            }
+        }
+        double_spends
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::ristretto::RistrettoPoint;
+
+    fn nullifier() -> Nullifier {
+        Nullifier::from(RistrettoPoint::random(&mut thread_rng()))
+    }
+
+    #[test]
+    fn gossip_converges() {
+        let mut north = NullifierLedger::new(GateId(1));
+        let mut south = NullifierLedger::new(GateId(2));
+        let a = nullifier();
+        let b = nullifier();
+
+        north.spend(7, &a).unwrap();
+        south.spend(7, &b).unwrap();
+
+        assert!(north.merge(&south.delta(0)).is_empty());
+        assert!(south.merge(&north.delta(0)).is_empty());
+
+        assert!(north.contains(7, &b));
+        assert!(south.contains(7, &a));
+        assert!(south.spend(7, &a) == Err(SyncError::NullifierReused.into()));
+    }
+
+    #[test]
+    fn double_spend_is_reported() {
+        let mut north = NullifierLedger::new(GateId(1));
+        let mut south = NullifierLedger::new(GateId(2));
+        let a = nullifier();
+
+        north.spend(7, &a).unwrap();
+        south.spend(7, &a).unwrap();
+
+        let reported = south.merge(&north.delta(0));
+
+        assert!(reported.len() == 1);
+        assert!(reported[0].nullifier == a);
+        assert!(reported[0].origins == (GateId(1), GateId(2)));
+        assert!(north.merge(&south.delta(0)).len() == 1);
+    }
+
+    #[test]
+    fn tombstones_prevent_resurrection() {
+        let mut north = NullifierLedger::new(GateId(1));
+        let mut south = NullifierLedger::new(GateId(2));
+        let a = nullifier();
+        let b = nullifier();
+
+        north.spend(6, &a).unwrap();
+        south.merge(&north.delta(0));
+        north.spend(7, &b).unwrap();
+        north.close_before(7);
+
+        // South's stale copy of epoch 6 must not bring it back.
+        north.merge(&south.delta(0));
+        south.merge(&north.delta(0));
+
+        assert!(north.is_closed(6) && south.is_closed(6));
+        assert!(!north.contains(6, &a) && !south.contains(6, &a));
+        assert!(south.spend(6, &nullifier()) == Err(SyncError::EpochClosed.into()));
+        assert!(south.contains(7, &b));
+    }
+
+    #[test]
+    fn delta_and_bytes() {
+        let mut north = NullifierLedger::new(GateId(1));
+        let mut south = NullifierLedger::new(GateId(2));
+
+        north.spend(7, &nullifier()).unwrap();
+        south.merge(&north.delta(0));
+
+        let seen = north.sequence();
+
+        assert!(north.delta(seen).is_empty());
+
+        let a = nullifier();
+
+        north.spend(7, &a).unwrap();
+        north.close(5);
+
+        let delta = north.delta(seen);
+        let decoded = SyncMessage::from_bytes(&delta.to_bytes()).unwrap();
+
+        assert!(decoded == delta);
+        assert!(decoded.tombstones == vec![5]);
+        assert!(decoded.spends == vec![(7, vec![(a, GateId(1))])]);
+    }
+}
@@ -0,0 +1,335 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Issuer parameter bundles, signed along a chain of key rotations from a
+//! long-term root key.
+//!
+//! Each epoch, an issuer publishes a `ParameterBundle`: its system and
+//! issuer parameters, the period in which they are valid, and the public key
+//! which will sign the next epoch's bundle.  The first bundle is signed by
+//! the issuer's long-term root `RegistryKeypair`, and each later one by the
+//! key named in the bundle before it, so the root key may be kept offline.
+//! The root key may also sign any bundle directly, to re-anchor the chain
+//! if an epoch's key is lost.
+//!
+//! A client holding only the root public key keeps a `ParameterChain`, and
+//! `extend()`s it with each `SignedParameterBundle` it fetches, e.g. from an
+//! untrusted CDN.  A bundle is only accepted if it is signed by the current
+//! epoch's key or the root key, and is for a later epoch than the last, so
+//! an attacker can neither forge parameters nor roll a client back to old
+//! ones.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use clock::Clock;
+use encoding::u64_to_bytes;
+use errors::ChainError;
+use errors::Error;
+use errors::TrustError;
+use issuer::ExpiringIssuerParameters;
+use issuer::IssuerParameters;
+use parameters::SIZEOF_SYSTEM_PARAMETERS;
+use parameters::SystemParameters;
+use trust::SIZEOF_REGISTRY_SIGNATURE;
+use trust::RegistryKeypair;
+use trust::RegistrySignature;
+
+/// The transcript label for signatures over a `ParameterBundle`.
+const BUNDLE_SIGNATURE_LABEL: &'static [u8] = b"AEONFLUX PARAMETER BUNDLE";
+
+/// The size of a `ParameterBundle` without its issuer parameters.
+const SIZEOF_BUNDLE_HEADER: usize = 8 + SIZEOF_SYSTEM_PARAMETERS + 32;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// An issuer's parameters for one epoch, and the key for the next.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParameterBundle {
+    /// The epoch of these parameters, which increases with each rotation.
+    pub epoch: u64,
+    pub system_parameters: SystemParameters,
+    /// The issuer's parameters, and the period in which they are valid.
+    pub issuer_parameters: ExpiringIssuerParameters,
+    /// The public key which signs the bundle for the next epoch.
+    pub next_key: RistrettoPoint,
+}
+
+impl ParameterBundle {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ParameterBundle, ChainError> {
+        if bytes.len() < SIZEOF_BUNDLE_HEADER {
+            return Err(ChainError::WrongNumberOfBytes);
+        }
+
+        let epoch: u64 = read_u64(&bytes[..8]);
+        let system_parameters = SystemParameters::from_bytes(&bytes[8..8 + SIZEOF_SYSTEM_PARAMETERS])?;
+        let next_key: RistrettoPoint =
+            match CompressedRistretto::from_slice(&bytes[8 + SIZEOF_SYSTEM_PARAMETERS..SIZEOF_BUNDLE_HEADER])
+            .decompress()
+        {
+            Some(x) => x,
+            None    => return Err(ChainError::PointDecompressionError),
+        };
+        let issuer_parameters = ExpiringIssuerParameters::from_bytes(&bytes[SIZEOF_BUNDLE_HEADER..])?;
+
+        Ok(ParameterBundle { epoch, system_parameters, issuer_parameters, next_key })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_BUNDLE_HEADER);
+
+        v.extend(u64_to_bytes(self.epoch).iter());
+        v.extend(self.system_parameters.to_bytes());
+        v.extend(self.next_key.compress().as_bytes());
+        v.extend(self.issuer_parameters.to_bytes());
+        v
+    }
+
+    /// Sign this bundle with the previous epoch's key, or the root key.
+    pub fn sign<R>(self, keypair: &RegistryKeypair, csprng: &mut R) -> SignedParameterBundle
+    where
+        R: RngCore + CryptoRng,
+    {
+        let signature = keypair.sign(BUNDLE_SIGNATURE_LABEL, &self.to_bytes(), csprng);
+
+        SignedParameterBundle { bundle: self, signature }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ParameterBundle, "A valid byte sequence representing a ParameterBundle");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ParameterBundle);
+impl_try_from_bytes!(ParameterBundle, ChainError);
+
+/// A `ParameterBundle` along with a signature over its canonical encoding.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedParameterBundle {
+    bundle: ParameterBundle,
+    pub signature: RegistrySignature,
+}
+
+impl SignedParameterBundle {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignedParameterBundle, ChainError> {
+        if bytes.len() < SIZEOF_REGISTRY_SIGNATURE {
+            return Err(ChainError::WrongNumberOfBytes);
+        }
+
+        let split: usize = bytes.len() - SIZEOF_REGISTRY_SIGNATURE;
+        let bundle = ParameterBundle::from_bytes(&bytes[..split])?;
+        let signature = match RegistrySignature::from_bytes(&bytes[split..]) {
+            Ok(x)                              => x,
+            Err(TrustError::ScalarFormatError) => return Err(ChainError::ScalarFormatError),
+            Err(_)                             => return Err(ChainError::WrongNumberOfBytes),
+        };
+
+        Ok(SignedParameterBundle { bundle, signature })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = self.bundle.to_bytes();
+
+        v.extend(self.signature.to_bytes());
+        v
+    }
+
+    /// The bundle, without checking its signature.
+    pub fn bundle_unverified(&self) -> &ParameterBundle {
+        &self.bundle
+    }
+
+    /// Whether the bundle is signed by the key whose public half is `public`.
+    pub fn is_signed_by(&self, public: &RistrettoPoint) -> bool {
+        self.signature.verify(BUNDLE_SIGNATURE_LABEL, public, &self.bundle.to_bytes())
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignedParameterBundle,
+                                         "A valid byte sequence representing a SignedParameterBundle");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SignedParameterBundle);
+impl_try_from_bytes!(SignedParameterBundle, ChainError);
+
+/// A client's view of an issuer's chain of parameter bundles.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParameterChain {
+    root: RistrettoPoint,
+    head: Option<ParameterBundle>,
+}
+
+impl ParameterChain {
+    /// Start a chain trusting only the issuer's `root` public key.
+    pub fn new(root: RistrettoPoint) -> ParameterChain {
+        ParameterChain { root, head: None }
+    }
+
+    /// Verify a whole chain of `links`, oldest first, from the `root` key.
+    pub fn verify(root: RistrettoPoint, links: &[SignedParameterBundle]) -> Result<ParameterChain, Error> {
+        let mut chain = ParameterChain::new(root);
+
+        for link in links.iter() {
+            chain.extend(link)?;
+        }
+        Ok(chain)
+    }
+
+    /// The most recently accepted bundle, if any.
+    pub fn head(&self) -> Option<&ParameterBundle> {
+        self.head.as_ref()
+    }
+
+    /// The key which must sign the next bundle, unless the root key does.
+    pub fn current_key(&self) -> &RistrettoPoint {
+        match self.head {
+            Some(ref bundle) => &bundle.next_key,
+            None             => &self.root,
+        }
+    }
+
+    /// Accept the next bundle in the chain.
+    ///
+    /// # Returns
+    ///
+    /// The newly accepted bundle.
+    ///
+    /// # Errors
+    ///
+    /// * `ChainError::StaleEpoch` if the bundle is not for a later epoch
+    ///   than the head of the chain.
+    /// * `ChainError::VerificationFailure` if the bundle is signed by neither
+    ///   the current key nor the root key.
+    pub fn extend(&mut self, link: &SignedParameterBundle) -> Result<&ParameterBundle, Error> {
+        if let Some(ref head) = self.head {
+            if link.bundle.epoch <= head.epoch {
+                return Err(ChainError::StaleEpoch.into());
+            }
+        }
+        if !link.is_signed_by(self.current_key()) && !link.is_signed_by(&self.root) {
+            return Err(ChainError::VerificationFailure.into());
+        }
+
+        self.head = Some(link.bundle.clone());
+
+        Ok(&link.bundle)
+    }
+
+    /// The issuer parameters at the head of the chain, if they are valid
+    /// according to a `clock`.
+    ///
+    /// # Errors
+    ///
+    /// * `ChainError::Empty` if no bundle has been accepted.
+    /// * `ChainError::Credential(CredentialError::Expired)` if the head's
+    ///   parameters have expired, or are not yet valid.
+    pub fn current<C>(&self, clock: &C) -> Result<&IssuerParameters, Error>
+    where
+        C: Clock,
+    {
+        match self.head {
+            Some(ref bundle) => Ok(bundle.issuer_parameters.check(clock)?),
+            None             => Err(ChainError::Empty.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use clock::FixedClock;
+    use errors::CredentialError;
+    use issuer::Issuer;
+    use test_utils::H;
+
+    const DAY: u64 = 86400;
+
+    fn bundle(epoch: u64, next: &RegistryKeypair) -> ParameterBundle {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+
+        ParameterBundle {
+            epoch,
+            system_parameters,
+            issuer_parameters: issuer.get_expiring_issuer_parameters(epoch * DAY, (epoch + 1) * DAY),
+            next_key: next.public,
+        }
+    }
+
+    #[test]
+    fn chains_follow_rotations() {
+        let mut rng = thread_rng();
+        let root = RegistryKeypair::generate(&mut rng);
+        let first = RegistryKeypair::generate(&mut rng);
+        let second = RegistryKeypair::generate(&mut rng);
+        let third = RegistryKeypair::generate(&mut rng);
+
+        let links = vec![
+            bundle(1, &first).sign(&root, &mut rng),
+            bundle(2, &second).sign(&first, &mut rng),
+            bundle(3, &third).sign(&second, &mut rng),
+        ];
+        let links: Vec<SignedParameterBundle> = links.iter()
+            .map(|link| SignedParameterBundle::from_bytes(&link.to_bytes()).unwrap())
+            .collect();
+        let chain = ParameterChain::verify(root.public, &links).unwrap();
+
+        assert!(chain.head().unwrap().epoch == 3);
+        assert!(chain.current_key() == &third.public);
+        assert!(chain.current(&FixedClock::new(3 * DAY)) ==
+                Ok(&links[2].bundle_unverified().issuer_parameters.parameters));
+        assert!(chain.current(&FixedClock::new(4 * DAY)).err() ==
+                Some(ChainError::Credential(CredentialError::Expired)));
+
+        // The root key may re-anchor the chain at any later epoch.
+        let mut chain = chain;
+
+        assert!(chain.extend(&bundle(5, &first).sign(&root, &mut rng)).is_ok());
+    }
+
+    #[test]
+    fn forged_and_stale_bundles_are_refused() {
+        let mut rng = thread_rng();
+        let root = RegistryKeypair::generate(&mut rng);
+        let first = RegistryKeypair::generate(&mut rng);
+        let mallory = RegistryKeypair::generate(&mut rng);
+        let mut chain = ParameterChain::new(root.public);
+
+        assert!(chain.current(&FixedClock::new(0)).err() == Some(ChainError::Empty.into()));
+        assert!(chain.extend(&bundle(1, &first).sign(&mallory, &mut rng)).err() ==
+                Some(ChainError::VerificationFailure));
+
+        let genuine = bundle(1, &first).sign(&root, &mut rng);
+
+        chain.extend(&genuine).unwrap();
+
+        assert!(chain.extend(&genuine).err() == Some(ChainError::StaleEpoch.into()));
+        assert!(chain.extend(&bundle(2, &mallory).sign(&mallory, &mut rng)).err() ==
+                Some(ChainError::VerificationFailure));
+    }
+}
@@ -17,7 +17,9 @@ use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+#[cfg(any(feature = "std", feature = "alloc"))]
 use serde::de::Visitor;
 
 use rand_core::CryptoRng;
@@ -28,6 +30,12 @@ use errors::CredentialError;
 pub const NUMBER_OF_ATTRIBUTES: usize = 1;
 pub const SIZEOF_SYSTEM_PARAMETERS: usize = 64;
 
+/// The number of revealed attributes on a `Credential` during presentation.
+pub const PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES: usize = 0;
+
+/// The number of encrypted attributes on a `Credential` during presentation.
+pub const PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES: usize = 1;
+
 /// The `SystemParameters` define the system-wide context in which the anonymous
 /// credentials scheme and its proofs are constructed within.
 ///
@@ -80,6 +88,7 @@ impl SystemParameters {
         Ok(SystemParameters { g, h })
     }
 
+    #[cfg(any(feature = "std", feature = "alloc"))]
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Vec::with_capacity(64);
 
@@ -90,8 +99,12 @@ impl SystemParameters {
     }
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 impl_serde_with_to_bytes_and_from_bytes!(SystemParameters,
                                          "A valid byte sequence representing a SystemParameters");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SystemParameters);
+impl_try_from_bytes!(SystemParameters, CredentialError);
 
 impl SystemParameters {
     /// Generate the `SystemParameters` randomly via an RNG.
@@ -0,0 +1,726 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Access passes with a tier and a validity window.
+//!
+//! A pass is a credential over a fixed schema of three attributes: an
+//! `AccessTier`, and the start and end of a `ValidityWindow`, such as a
+//! `ValidityWindow::day_pass()` or `ValidityWindow::monthly()`.  The issuer
+//! sees the attributes when the pass is bought, but when it is presented the
+//! holder hides all of them, and proves only that a `PassPredicate`, i.e.
+//! "tier is at least N and now is within the window", holds for them.
+//!
+//! The predicates are built from the reusable statements in the
+//! `predicates` module, so integrators don't need to define their own
+//! attributes or proofs for the common case of gating access by tier and time.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use amacs;
+use errors::CredentialError;
+use errors::Error;
+use errors::PassError;
+use nonces::Ephemeral;
+use parameters::SystemParameters;
+use pedersen;
+use predicates::Predicate;
+use predicates::RangeProof;
+use predicates::sizeof_range_proof;
+use proofs::pass_issuance;
+use proofs::valid_pass;
+use secret::REDACTED;
+
+/// The number of attributes in a pass: its tier, and the start and end of
+/// its validity window.
+pub const PASS_NUMBER_OF_ATTRIBUTES: usize = 3;
+
+/// The number of bits in the range proof that a pass's tier is at least the
+/// required one.
+pub const TIER_RANGE_BITS: usize = 8;
+
+/// The number of bits in each range proof that the current time is within a
+/// pass's validity window.  This is also the base-2 logarithm of the longest
+/// allowable window, in seconds.
+pub const WINDOW_RANGE_BITS: usize = 32;
+
+/// The length of a day pass, in seconds.
+pub const DAY: u64 = 24 * 60 * 60;
+
+/// The length of a monthly pass, in seconds.
+pub const MONTH: u64 = 30 * DAY;
+
+pub const SIZEOF_PASS: usize = 1 + 8 + 8 + amacs::SIZEOF_TAG;
+
+fn push_u64(v: &mut Vec<u8>, x: u64) {
+    for i in 0..8 {
+        v.push((x >> (8 * i)) as u8);
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, PassError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(&bytes[..32]);
+
+    Ok(CompressedRistretto(tmp).decompress()?)
+}
+
+/// An access tier.  Higher tiers grant more access.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct AccessTier(pub u8);
+
+/// The times, in seconds since the Unix epoch, between which a pass is valid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ValidityWindow {
+    /// The first second at which the pass is valid.
+    pub not_before: u64,
+    /// The first second at which the pass is no longer valid.
+    pub not_after: u64,
+}
+
+impl ValidityWindow {
+    /// Create a new `ValidityWindow`.
+    ///
+    /// # Errors
+    ///
+    /// * `PassError::InvalidWindow` if the window is empty or longer than
+    ///   \\( 2^{32} - 1 \\) seconds.
+    pub fn new(not_before: u64, not_after: u64) -> Result<ValidityWindow, Error> {
+        let window = ValidityWindow { not_before, not_after };
+
+        window.check()?;
+
+        Ok(window)
+    }
+
+    /// A window of one `DAY`, beginning at `start`.
+    pub fn day_pass(start: u64) -> Result<ValidityWindow, Error> {
+        ValidityWindow::new(start, start.saturating_add(DAY))
+    }
+
+    /// A window of one `MONTH`, beginning at `start`.
+    pub fn monthly(start: u64) -> Result<ValidityWindow, Error> {
+        ValidityWindow::new(start, start.saturating_add(MONTH))
+    }
+
+    fn check(&self) -> Result<(), PassError> {
+        if self.not_after <= self.not_before ||
+           (self.not_after - self.not_before) >> WINDOW_RANGE_BITS != 0
+        {
+            return Err(PassError::InvalidWindow);
+        }
+        Ok(())
+    }
+
+    /// Whether the time `now` is within this window.
+    pub fn contains(&self, now: u64) -> bool {
+        self.not_before <= now && now < self.not_after
+    }
+}
+
+/// The attributes of a pass.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PassAttributes {
+    pub tier: AccessTier,
+    pub window: ValidityWindow,
+}
+
+impl PassAttributes {
+    fn to_scalars(&self) -> [Scalar; PASS_NUMBER_OF_ATTRIBUTES] {
+        [Scalar::from(self.tier.0 as u64),
+         Scalar::from(self.window.not_before),
+         Scalar::from(self.window.not_after)]
+    }
+
+    fn to_message(&self) -> amacs::Message {
+        let mut v: Vec<Scalar> = Vec::with_capacity(PASS_NUMBER_OF_ATTRIBUTES);
+
+        v.extend_from_slice(&self.to_scalars());
+
+        amacs::Message::from(v)
+    }
+}
+
+/// A predicate to be proven about a pass when it is presented.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PassPredicate {
+    /// The lowest tier which is granted access.
+    pub minimum_tier: AccessTier,
+    /// The current time, in seconds since the Unix epoch.
+    pub now: u64,
+}
+
+impl PassPredicate {
+    /// Whether a pass with some `attributes` satisfies this predicate.
+    pub fn is_satisfied_by(&self, attributes: &PassAttributes) -> bool {
+        attributes.tier >= self.minimum_tier && attributes.window.contains(self.now)
+    }
+
+    /// Expand this predicate into a statement about each attribute.
+    fn predicates(&self) -> Result<[(Predicate, usize); PASS_NUMBER_OF_ATTRIBUTES], PassError> {
+        let end: u64 = match self.now.checked_add(1) {
+            Some(x) => x,
+            None    => return Err(PassError::InvalidWindow),
+        };
+
+        Ok([(Predicate::AtLeast(self.minimum_tier.0 as u64), TIER_RANGE_BITS),
+            (Predicate::AtMost(self.now), WINDOW_RANGE_BITS),
+            (Predicate::AtLeast(end), WINDOW_RANGE_BITS)])
+    }
+
+    fn transcript(&self) -> Transcript {
+        let mut transcript = Transcript::new(b"AEONFLUX PASS");
+        let mut now = [0u8; 8];
+
+        for i in 0..8 {
+            now[i] = (self.now >> (8 * i)) as u8;
+        }
+
+        transcript.commit_bytes(b"minimum tier", &[self.minimum_tier.0]);
+        transcript.commit_bytes(b"now", &now);
+        transcript
+    }
+}
+
+/// The issuer's parameters for passes.
+pub type PassIssuerParameters = amacs::PublicKey;
+
+/// The issuer's response to a request for a pass.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PassIssuance {
+    pub tag: amacs::Tag,
+    pub secret_key_commitment: RistrettoPoint,
+    pub proof: pass_issuance::Proof,
+}
+
+impl PassIssuance {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PassIssuance, PassError> {
+        if bytes.len() < amacs::SIZEOF_TAG + 32 {
+            return Err(PassError::WrongNumberOfBytes);
+        }
+
+        let tag = amacs::Tag::from_bytes(&bytes[..amacs::SIZEOF_TAG])?;
+        let secret_key_commitment = read_point(&bytes[amacs::SIZEOF_TAG..])?;
+        let proof: pass_issuance::Proof = match deserialize(&bytes[amacs::SIZEOF_TAG + 32..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(PassError::WrongNumberOfBytes),
+        };
+
+        Ok(PassIssuance { tag, secret_key_commitment, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(amacs::SIZEOF_TAG + 32);
+
+        v.extend(self.tag.to_bytes());
+        v.extend(self.secret_key_commitment.compress().as_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PassIssuance, "A valid byte sequence representing a PassIssuance");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PassIssuance);
+impl_try_from_bytes!(PassIssuance, PassError);
+
+/// An issuer and verifier of passes.
+#[derive(Clone, Debug)]
+pub struct PassIssuer {
+    pub system_parameters: SystemParameters,
+    pub keypair: amacs::Keypair,
+}
+
+impl PassIssuer {
+    /// Create a new `PassIssuer` with a fresh key.
+    pub fn create<R>(system_parameters: SystemParameters, csprng: &mut R) -> PassIssuer
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = amacs::SecretKey::new(PASS_NUMBER_OF_ATTRIBUTES, csprng);
+        let public = secret.get_public_key(&system_parameters.h);
+
+        PassIssuer { system_parameters, keypair: amacs::Keypair { public, secret } }
+    }
+
+    /// Get this `PassIssuer`'s parameters for publishing to users.
+    pub fn get_issuer_parameters(&self) -> PassIssuerParameters {
+        self.keypair.public.clone()
+    }
+
+    /// Issue a pass with some `attributes`.
+    ///
+    /// # Errors
+    ///
+    /// * `PassError::InvalidWindow` if the `attributes` have an invalid
+    ///   `ValidityWindow`.
+    /// * `PassError::Credential` if this issuer's key is not for passes.
+    pub fn issue<R>(&self, attributes: &PassAttributes, rng: &mut R) -> Result<PassIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        attributes.window.check()?;
+
+        if self.keypair.secret.xn.len() != PASS_NUMBER_OF_ATTRIBUTES ||
+           self.keypair.public.Xn.len() != PASS_NUMBER_OF_ATTRIBUTES
+        {
+            return Err(PassError::Credential(CredentialError::NoIssuerKey).into());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX PASS ISSUANCE");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let m = attributes.to_scalars();
+        let tag: amacs::Tag = self.keypair.secret.mac(&attributes.to_message(), &mut csprng)?;
+
+        // Commit to the issuer's secret x0.
+        let x0_tilde: Ephemeral = Ephemeral::new(&mut csprng);
+        let Cx0: RistrettoPoint = pedersen::Commitment::to(&(&self.system_parameters.g * &self.keypair.secret.x0),
+                                                           &x0_tilde, &self.system_parameters.h).into();
+
+        let M1: RistrettoPoint = &tag.nonce * &m[0];
+        let M2: RistrettoPoint = &tag.nonce * &m[1];
+        let M3: RistrettoPoint = &tag.nonce * &m[2];
+
+        let secrets = pass_issuance::Secrets {
+            x0: &self.keypair.secret.x0,
+            x1: &self.keypair.secret.xn[0],
+            x2: &self.keypair.secret.xn[1],
+            x3: &self.keypair.secret.xn[2],
+            x0_tilde: x0_tilde.as_witness(),
+        };
+        let publics = pass_issuance::Publics {
+            B: &self.system_parameters.g,
+            A: &self.system_parameters.h,
+            P: &tag.nonce,
+            M1: &M1,
+            M2: &M2,
+            M3: &M3,
+            Q: &tag.mac,
+            Cx0: &Cx0,
+            X1: &self.keypair.public.Xn[0],
+            X2: &self.keypair.public.Xn[1],
+            X3: &self.keypair.public.Xn[2],
+        };
+        let proof = pass_issuance::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(PassIssuance { tag, secret_key_commitment: Cx0, proof })
+    }
+
+    /// Verify a `presentation` of a pass, and that it satisfies a `predicate`.
+    ///
+    /// # Errors
+    ///
+    /// * `PassError::VerificationFailure` if the pass was not issued by us.
+    /// * `PassError::Predicate` if the pass does not satisfy the `predicate`.
+    pub fn verify(&self, presentation: &PassPresentation, predicate: &PassPredicate) -> Result<(), Error> {
+        if self.keypair.secret.xn.len() != PASS_NUMBER_OF_ATTRIBUTES ||
+           self.keypair.public.Xn.len() != PASS_NUMBER_OF_ATTRIBUTES ||
+           presentation.Cm.len() != PASS_NUMBER_OF_ATTRIBUTES ||
+           presentation.range_proofs.len() != PASS_NUMBER_OF_ATTRIBUTES ||
+           presentation.P == RistrettoPoint::identity()
+        {
+            return Err(PassError::VerificationFailure.into());
+        }
+
+        let P: &RistrettoPoint = &presentation.P;
+        let A: &RistrettoPoint = &self.system_parameters.h;
+        let mut V: RistrettoPoint = P * &self.keypair.secret.x0;
+
+        // The MAC is recomputed under the secret key, so this must remain
+        // constant-time.
+        for (xi, Cmi) in self.keypair.secret.xn.iter().zip(presentation.Cm.iter()) {
+            V += &(Cmi * xi);
+        }
+        V -= &presentation.CQ;
+
+        let mut transcript = predicate.transcript();
+        let publics = valid_pass::Publics {
+            A: A,
+            P: P,
+            X1: &self.keypair.public.Xn[0],
+            X2: &self.keypair.public.Xn[1],
+            X3: &self.keypair.public.Xn[2],
+            V: &V,
+            Cm1: &presentation.Cm[0],
+            Cm2: &presentation.Cm[1],
+            Cm3: &presentation.Cm[2],
+        };
+
+        if presentation.proof.verify(&mut transcript, publics).is_err() {
+            return Err(PassError::VerificationFailure.into());
+        }
+
+        let predicates = predicate.predicates()?;
+
+        for i in 0..PASS_NUMBER_OF_ATTRIBUTES {
+            let (statement, bits) = predicates[i];
+
+            statement.verify(&mut transcript, bits, P, A, &presentation.Cm[i], &presentation.range_proofs[i])?;
+        }
+        Ok(())
+    }
+}
+
+/// A pass, held by a user.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Pass {
+    pub attributes: PassAttributes,
+    tag: amacs::Tag,
+}
+
+/// The attributes are revealed whenever the pass is shown, but the tag would
+/// link every presentation of it.
+impl fmt::Debug for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Pass {{ attributes: {:?}, tag: {} }}", self.attributes, REDACTED)
+    }
+}
+
+impl Pass {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Pass, PassError> {
+        if bytes.len() != SIZEOF_PASS {
+            return Err(PassError::WrongNumberOfBytes);
+        }
+
+        let tier = AccessTier(bytes[0]);
+        let window = ValidityWindow { not_before: read_u64(&bytes[1..9]), not_after: read_u64(&bytes[9..17]) };
+
+        window.check()?;
+
+        let tag = amacs::Tag::from_bytes(&bytes[17..])?;
+
+        Ok(Pass { attributes: PassAttributes { tier, window }, tag })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_PASS);
+
+        v.push(self.attributes.tier.0);
+        push_u64(&mut v, self.attributes.window.not_before);
+        push_u64(&mut v, self.attributes.window.not_after);
+        v.extend(self.tag.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Pass, "A valid byte sequence representing a Pass");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Pass);
+impl_try_from_bytes!(Pass, PassError);
+
+impl Pass {
+    /// Verify a pass `issuance` for some `attributes` and, if valid, obtain the `Pass`.
+    ///
+    /// # Errors
+    ///
+    /// * `PassError::Issuance` if the issuer's proof did not verify.
+    pub fn from_issuance(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &PassIssuerParameters,
+        attributes: &PassAttributes,
+        issuance: &PassIssuance,
+    ) -> Result<Pass, Error>
+    {
+        if issuer_parameters.Xn.len() != PASS_NUMBER_OF_ATTRIBUTES ||
+           issuance.tag.nonce == RistrettoPoint::identity()
+        {
+            return Err(PassError::Issuance.into());
+        }
+        attributes.window.check()?;
+
+        let mut transcript = Transcript::new(b"AEONFLUX PASS ISSUANCE");
+        let m = attributes.to_scalars();
+        let M1: RistrettoPoint = &issuance.tag.nonce * &m[0];
+        let M2: RistrettoPoint = &issuance.tag.nonce * &m[1];
+        let M3: RistrettoPoint = &issuance.tag.nonce * &m[2];
+
+        let publics = pass_issuance::Publics {
+            B: &system_parameters.g,
+            A: &system_parameters.h,
+            P: &issuance.tag.nonce,
+            M1: &M1,
+            M2: &M2,
+            M3: &M3,
+            Q: &issuance.tag.mac,
+            Cx0: &issuance.secret_key_commitment,
+            X1: &issuer_parameters.Xn[0],
+            X2: &issuer_parameters.Xn[1],
+            X3: &issuer_parameters.Xn[2],
+        };
+
+        if issuance.proof.verify(&mut transcript, publics).is_err() {
+            return Err(PassError::Issuance.into());
+        }
+
+        Ok(Pass { attributes: *attributes, tag: issuance.tag.clone() })
+    }
+
+    /// Present this pass, proving that it satisfies a `predicate` without
+    /// revealing its tier or validity window.
+    ///
+    /// # Errors
+    ///
+    /// * `PassError::Predicate` if this pass does not satisfy the `predicate`.
+    pub fn present<R>(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &PassIssuerParameters,
+        predicate: &PassPredicate,
+        rng: &mut R,
+    ) -> Result<PassPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if issuer_parameters.Xn.len() != PASS_NUMBER_OF_ATTRIBUTES {
+            return Err(PassError::Credential(CredentialError::NoIssuerParameters).into());
+        }
+
+        let predicates = predicate.predicates()?;
+        let mut transcript = predicate.transcript();
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let A: RistrettoPoint = system_parameters.h;
+        let tag: amacs::Tag = amacs::Rerandomization::new(&mut csprng).apply_to_tag(&self.tag);
+        let P: RistrettoPoint = tag.nonce;
+
+        // Commit to the rerandomised aMAC and to each attribute.
+        let zQ: Ephemeral = Ephemeral::new(&mut csprng);
+        let CQ: RistrettoPoint = pedersen::Commitment::to(&tag.mac, &zQ, &A).into();
+
+        let m = self.attributes.to_scalars();
+        let values: [u64; PASS_NUMBER_OF_ATTRIBUTES] = [self.attributes.tier.0 as u64,
+                                                        self.attributes.window.not_before,
+                                                        self.attributes.window.not_after];
+        let mut z: Vec<Ephemeral> = Vec::with_capacity(PASS_NUMBER_OF_ATTRIBUTES);
+        let mut Cm: Vec<RistrettoPoint> = Vec::with_capacity(PASS_NUMBER_OF_ATTRIBUTES);
+        let mut V: RistrettoPoint = -(&A * zQ.as_scalar());
+
+        for i in 0..PASS_NUMBER_OF_ATTRIBUTES {
+            let zi: Ephemeral = Ephemeral::new(&mut csprng);
+
+            Cm.push(pedersen::Commitment::to(&(&P * &m[i]), &zi, &A).into());
+            V += &(&issuer_parameters.Xn[i] * zi.as_scalar());
+            z.push(zi);
+        }
+
+        let minus_zQ: Scalar = -zQ.as_scalar();
+        let secrets = valid_pass::Secrets {
+            m1: &m[0],
+            m2: &m[1],
+            m3: &m[2],
+            z1: z[0].as_witness(),
+            z2: z[1].as_witness(),
+            z3: z[2].as_witness(),
+            minus_zQ: &minus_zQ,
+        };
+        let publics = valid_pass::Publics {
+            A: &A,
+            P: &P,
+            X1: &issuer_parameters.Xn[0],
+            X2: &issuer_parameters.Xn[1],
+            X3: &issuer_parameters.Xn[2],
+            V: &V,
+            Cm1: &Cm[0],
+            Cm2: &Cm[1],
+            Cm3: &Cm[2],
+        };
+        let proof = valid_pass::Proof::create(&mut transcript, publics, secrets);
+
+        let mut range_proofs: Vec<RangeProof> = Vec::with_capacity(PASS_NUMBER_OF_ATTRIBUTES);
+
+        for i in 0..PASS_NUMBER_OF_ATTRIBUTES {
+            let (statement, bits) = predicates[i];
+
+            range_proofs.push(statement.prove(&mut transcript, bits, &P, &A, &Cm[i],
+                                              values[i], z[i].as_witness(), &mut csprng)?);
+        }
+
+        Ok(PassPresentation { P, CQ, Cm, proof, range_proofs })
+    }
+}
+
+/// A presentation of a `Pass`, hiding its attributes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PassPresentation {
+    /// The rerandomised aMAC nonce.
+    pub P: RistrettoPoint,
+    /// A commitment to the rerandomised aMAC.
+    pub CQ: RistrettoPoint,
+    /// Commitments to the tier and validity window.
+    pub Cm: Vec<RistrettoPoint>,
+    pub proof: valid_pass::Proof,
+    /// Proofs of the predicate for each of the committed attributes, in order.
+    pub range_proofs: Vec<RangeProof>,
+}
+
+impl PassPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PassPresentation, PassError> {
+        let sizes: [usize; PASS_NUMBER_OF_ATTRIBUTES] = [sizeof_range_proof(TIER_RANGE_BITS),
+                                                         sizeof_range_proof(WINDOW_RANGE_BITS),
+                                                         sizeof_range_proof(WINDOW_RANGE_BITS)];
+        let mut offset: usize = 32 * (2 + PASS_NUMBER_OF_ATTRIBUTES);
+
+        if bytes.len() < offset + sizes.iter().sum::<usize>() {
+            return Err(PassError::WrongNumberOfBytes);
+        }
+
+        let P = read_point(&bytes[00..32])?;
+        let CQ = read_point(&bytes[32..64])?;
+        let mut Cm: Vec<RistrettoPoint> = Vec::with_capacity(PASS_NUMBER_OF_ATTRIBUTES);
+        let mut range_proofs: Vec<RangeProof> = Vec::with_capacity(PASS_NUMBER_OF_ATTRIBUTES);
+
+        for i in 0..PASS_NUMBER_OF_ATTRIBUTES {
+            Cm.push(read_point(&bytes[64 + 32 * i..])?);
+        }
+        for size in sizes.iter() {
+            range_proofs.push(RangeProof::from_bytes(&bytes[offset..offset + size])?);
+            offset += size;
+        }
+
+        let proof: valid_pass::Proof = match deserialize(&bytes[offset..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(PassError::WrongNumberOfBytes),
+        };
+
+        Ok(PassPresentation { P, CQ, Cm, proof, range_proofs })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.P.compress().as_bytes());
+        v.extend(self.CQ.compress().as_bytes());
+
+        for Cmi in self.Cm.iter() {
+            v.extend(Cmi.compress().as_bytes());
+        }
+        for range_proof in self.range_proofs.iter() {
+            v.extend(range_proof.to_bytes());
+        }
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PassPresentation, "A valid byte sequence representing a PassPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PassPresentation);
+impl_try_from_bytes!(PassPresentation, PassError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use test_utils::H;
+
+    const NOW: u64 = 1546300800;
+
+    fn obtain(issuer: &PassIssuer, tier: u8, window: ValidityWindow) -> Pass {
+        let attributes = PassAttributes { tier: AccessTier(tier), window };
+        let issuance = issuer.issue(&attributes, &mut thread_rng()).unwrap();
+        let issuance = PassIssuance::from_bytes(&issuance.to_bytes()).unwrap();
+
+        Pass::from_issuance(&issuer.system_parameters, &issuer.get_issuer_parameters(),
+                            &attributes, &issuance).unwrap()
+    }
+
+    #[test]
+    fn day_pass_present_and_verify() {
+        let issuer = PassIssuer::create(SystemParameters::from(H), &mut thread_rng());
+        let pass = obtain(&issuer, 2, ValidityWindow::day_pass(NOW - 60).unwrap());
+        let pass = Pass::from_bytes(&pass.to_bytes()).unwrap();
+        let predicate = PassPredicate { minimum_tier: AccessTier(2), now: NOW };
+        let presentation = pass.present(&issuer.system_parameters, &issuer.get_issuer_parameters(),
+                                        &predicate, &mut thread_rng()).unwrap();
+        let presentation = PassPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+        assert!(issuer.verify(&presentation, &predicate).is_ok());
+    }
+
+    #[test]
+    fn presentation_is_bound_to_predicate() {
+        let issuer = PassIssuer::create(SystemParameters::from(H), &mut thread_rng());
+        let pass = obtain(&issuer, 3, ValidityWindow::monthly(NOW - DAY).unwrap());
+        let predicate = PassPredicate { minimum_tier: AccessTier(1), now: NOW };
+        let presentation = pass.present(&issuer.system_parameters, &issuer.get_issuer_parameters(),
+                                        &predicate, &mut thread_rng()).unwrap();
+        let stricter = PassPredicate { minimum_tier: AccessTier(3), now: NOW };
+        let later = PassPredicate { minimum_tier: AccessTier(1), now: NOW + 1 };
+
+        assert!(issuer.verify(&presentation, &stricter).is_err());
+        assert!(issuer.verify(&presentation, &later).is_err());
+    }
+
+    #[test]
+    fn unsatisfied_predicates() {
+        let issuer = PassIssuer::create(SystemParameters::from(H), &mut thread_rng());
+        let pass = obtain(&issuer, 1, ValidityWindow::day_pass(NOW).unwrap());
+        let low_tier = PassPredicate { minimum_tier: AccessTier(2), now: NOW };
+        let expired = PassPredicate { minimum_tier: AccessTier(1), now: NOW + DAY };
+
+        assert!(!low_tier.is_satisfied_by(&pass.attributes));
+        assert!(pass.present(&issuer.system_parameters, &issuer.get_issuer_parameters(),
+                             &low_tier, &mut thread_rng()).is_err());
+        assert!(pass.present(&issuer.system_parameters, &issuer.get_issuer_parameters(),
+                             &expired, &mut thread_rng()).is_err());
+    }
+
+    #[test]
+    fn wrong_issuer() {
+        let issuer = PassIssuer::create(SystemParameters::from(H), &mut thread_rng());
+        let other = PassIssuer::create(SystemParameters::from(H), &mut thread_rng());
+        let pass = obtain(&issuer, 1, ValidityWindow::day_pass(NOW).unwrap());
+        let predicate = PassPredicate { minimum_tier: AccessTier(0), now: NOW };
+        let presentation = pass.present(&issuer.system_parameters, &issuer.get_issuer_parameters(),
+                                        &predicate, &mut thread_rng()).unwrap();
+
+        assert!(other.verify(&presentation, &predicate) == Err(PassError::VerificationFailure.into()));
+        assert!(ValidityWindow::new(NOW, NOW).is_err());
+    }
+}
@@ -28,6 +28,7 @@ use serde::de::Visitor;
 use errors::CredentialError;
 
 use nonces::Ephemeral;
+use precomputed::PrecomputedIssuerParameters;
 
 pub const SIZEOF_COMMITMENT: usize = 32;
 
@@ -65,11 +66,15 @@ impl SubAssign<Commitment> for RistrettoPoint {
 
 impl Commitment {
     pub fn from_bytes(bytes: &[u8]) -> Result<Commitment, CredentialError> {
+        if bytes.len() != SIZEOF_COMMITMENT {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
         let mut tmp: [u8; 32] = [0u8; 32];
 
         tmp.copy_from_slice(&bytes[0..32]);
 
-        Ok(Commitment(CompressedRistretto(tmp).decompress()?))
+        Ok(Commitment(CompressedRistretto(tmp).decompress().ok_or(CredentialError::PointDecompressionError)?))
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -83,6 +88,9 @@ impl Commitment {
 
 impl_serde_with_to_bytes_and_from_bytes!(Commitment,
                                          "A valid byte sequence representing a pedersen::Commitment");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Commitment);
+impl_try_from_bytes!(Commitment, CredentialError);
 
 impl Commitment {
     /// Create a Pedersen commitment to some `value` using the specified `nonce`
@@ -95,6 +103,18 @@ impl Commitment {
         Commitment(value + &(nonce * basepoint))
     }
 
+    /// As `Commitment::to()`, but using the `precomputed` table for the
+    /// `basepoint`, if there is one.
+    pub fn to_precomputed(
+        value: &RistrettoPoint,
+        nonce: &Ephemeral,
+        basepoint: &RistrettoPoint,
+        precomputed: &PrecomputedIssuerParameters,
+    ) -> Commitment
+    {
+        Commitment(value + &precomputed.mul(basepoint, nonce.as_scalar()))
+    }
+
     pub fn open(
         &self,
         value: &RistrettoPoint,
@@ -0,0 +1,682 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A typed builder for presentation policies.
+//!
+//! Writing a `PresentationRequest` by hand means choosing predicates and
+//! range sizes, and any non-revocation proof a verifier also wants must be
+//! asked for and checked separately.  A `PresentationPolicyBuilder` states
+//! the policy instead, e.g.
+//!
+//! ```ignore
+//! let (policy, instructions) = PresentationPolicyBuilder::new("Library", &schema)
+//!     .reveal("tier")
+//!     .prove_range("expiry", now..)
+//!     .purpose("to lend you books")
+//!     .require_roster(&roster)
+//!     .bind_challenge(nonce)
+//!     .build(&mut csprng)?;
+//! ```
+//!
+//! and checks it against the schema once, when it is built.  It produces
+//! both halves of the policy: the `PresentationPolicy` the verifier keeps,
+//! and the `PresentationInstructions` it sends to the holder, who answers
+//! them with a `PolicyResponse`.  A roster is a `non_revocation`
+//! accumulator of the random handles of revocable credentials, and a holder
+//! answers for it by presenting their `RevocableCredential` with its
+//! witness, refreshed to the value named in the instructions, and proving,
+//! within the same transcript as the rest of the response, that its identity
+//! is the presented credential's hidden attribute.  Neither the roster nor
+//! its deltas ever hold the identity attributes themselves.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::ops::{Bound, RangeBounds};
+#[cfg(feature = "std")]
+use std::ops::{Bound, RangeBounds};
+
+use bincode::deserialize;
+
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::Credential;
+use credential::RevealedAttribute;
+use credential::VerifiedCredential;
+use errors::CredentialError;
+use errors::Error;
+use errors::PolicyError;
+use errors::RevocationError;
+use issuer::Issuer;
+use non_revocation::Accumulator;
+use non_revocation::AccumulatorValue;
+use non_revocation::NonRevocationPresentation;
+use non_revocation::NonRevocationWitness;
+use non_revocation::RevocableCredential;
+use non_revocation::RevocableIssuer;
+use non_revocation::RevocableIssuerParameters;
+use non_revocation::SIZEOF_ACCUMULATOR_VALUE;
+use non_revocation::SIZEOF_NON_REVOCATION_PRESENTATION;
+use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use predicates::MAXIMUM_RANGE_BITS;
+use predicates::Predicate;
+use presentation_request::PresentationRequest;
+use presentation_request::PresentationResponse;
+use presentation_request::RequestedAttribute;
+use presentation_request::Requirement;
+use presentation_request::SIZEOF_REQUEST_CHALLENGE;
+use proofs::roster_link;
+use schema::Schema;
+use user::User;
+
+/// The size of an encoded `RosterProof`.
+pub const SIZEOF_ROSTER_PROOF: usize = SIZEOF_NON_REVOCATION_PRESENTATION + roster_link::SIZEOF_PROOF;
+
+/// The number of bits a `RangeProof` over the differences from the bounds
+/// of `min..=max` needs.
+fn bits_between(min: u64, max: u64) -> u8 {
+    let bits: u32 = 64 - (max - min).leading_zeros();
+
+    if bits == 0 { 1 } else { bits as u8 }
+}
+
+/// Begin the transcript in which a roster proof's link, for a response to a
+/// `request` and a presentation of a revocable credential, is made.
+fn roster_transcript(
+    request: &PresentationRequest,
+    response: &PresentationResponse,
+    revocable: &NonRevocationPresentation,
+) -> Transcript
+{
+    let mut transcript = request.transcript(&response.presentation.presentation);
+
+    transcript.commit_bytes(b"roster", b"non-revocation");
+    transcript.commit_bytes(b"revocable", &revocable.to_bytes());
+    transcript
+}
+
+/// A builder for a `PresentationPolicy` and its `PresentationInstructions`.
+pub struct PresentationPolicyBuilder<'a> {
+    verifier: String,
+    schema: &'a Schema,
+    attributes: Vec<RequestedAttribute>,
+    /// The number of attributes whose purpose has been given.
+    purposed: usize,
+    roster: Option<(&'a Accumulator, &'a RevocableIssuer)>,
+    challenge: Option<[u8; SIZEOF_REQUEST_CHALLENGE]>,
+    error: Option<PolicyError>,
+}
+
+impl<'a> PresentationPolicyBuilder<'a> {
+    /// Begin a policy, for a `verifier`, over credentials under a `schema`.
+    pub fn new(verifier: &str, schema: &'a Schema) -> PresentationPolicyBuilder<'a> {
+        PresentationPolicyBuilder {
+            verifier: String::from(verifier),
+            schema: schema,
+            attributes: Vec::new(),
+            purposed: 0,
+            roster: None,
+            challenge: None,
+            error: None,
+        }
+    }
+
+    fn require(mut self, attribute: &str, requirement: Requirement) -> PresentationPolicyBuilder<'a> {
+        self.attributes.push(RequestedAttribute {
+            label: String::from(attribute),
+            attribute: String::from(attribute),
+            requirement: requirement,
+            purpose: String::new(),
+        });
+        self
+    }
+
+    /// Require that the `attribute` be revealed.
+    pub fn reveal(self, attribute: &str) -> PresentationPolicyBuilder<'a> {
+        self.require(attribute, Requirement::Reveal)
+    }
+
+    /// Require that the `attribute`, an integer or a timestamp, lie within a
+    /// `range`, e.g. `now..` or `18..=65`, without revealing it.
+    ///
+    /// A range bounded on both sides is proven over only as many bits as it
+    /// spans, and so makes a smaller response.
+    pub fn prove_range<B>(mut self, attribute: &str, range: B) -> PresentationPolicyBuilder<'a>
+    where
+        B: RangeBounds<u64>,
+    {
+        let min: Option<u64> = match range.start_bound() {
+            Bound::Included(x) => Some(*x),
+            Bound::Excluded(x) => match x.checked_add(1) {
+                Some(x) => Some(x),
+                None    => { self.error = self.error.or(Some(PolicyError::InvalidRange)); return self },
+            },
+            Bound::Unbounded => None,
+        };
+        let max: Option<u64> = match range.end_bound() {
+            Bound::Included(x) => Some(*x),
+            Bound::Excluded(x) => match x.checked_sub(1) {
+                Some(x) => Some(x),
+                None    => { self.error = self.error.or(Some(PolicyError::InvalidRange)); return self },
+            },
+            Bound::Unbounded => None,
+        };
+
+        match (min, max) {
+            (Some(min), Some(max)) if min <= max => {
+                let bits: u8 = bits_between(min, max);
+
+                self.require(attribute, Requirement::Predicate(Predicate::AtLeast(min), bits))
+                    .require(attribute, Requirement::Predicate(Predicate::AtMost(max), bits))
+            },
+            (Some(min), None) => {
+                self.require(attribute, Requirement::Predicate(Predicate::AtLeast(min), MAXIMUM_RANGE_BITS as u8))
+            },
+            (None, Some(max)) => {
+                self.require(attribute, Requirement::Predicate(Predicate::AtMost(max), MAXIMUM_RANGE_BITS as u8))
+            },
+            _ => {
+                self.error = self.error.or(Some(PolicyError::InvalidRange));
+                self
+            },
+        }
+    }
+
+    /// Explain to the holder why the requirements added since the last
+    /// purpose are asked for, e.g. "to lend you books".
+    pub fn purpose(mut self, purpose: &str) -> PresentationPolicyBuilder<'a> {
+        for requested in self.attributes[self.purposed..].iter_mut() {
+            requested.purpose = String::from(purpose);
+        }
+        self.purposed = self.attributes.len();
+        self
+    }
+
+    /// Require that the holder of the credential hold a revocable credential
+    /// from the `issuer` of a `roster`, whose handle is on it, i.e. not
+    /// revoked from it, as of its current value.
+    pub fn require_roster(mut self, roster: &'a Accumulator, issuer: &'a RevocableIssuer) -> PresentationPolicyBuilder<'a> {
+        self.roster = Some((roster, issuer));
+        self
+    }
+
+    /// Bind responses to a `challenge` of the verifier's choosing, e.g. a
+    /// session's nonce, rather than a random one.
+    pub fn bind_challenge(mut self, challenge: [u8; SIZEOF_REQUEST_CHALLENGE]) -> PresentationPolicyBuilder<'a> {
+        self.challenge = Some(challenge);
+        self
+    }
+
+    /// Check the policy against the schema, and build it.
+    ///
+    /// # Returns
+    ///
+    /// The verifier's `PresentationPolicy`, and the `PresentationInstructions`
+    /// to send to holders.
+    ///
+    /// # Errors
+    ///
+    /// * `PolicyError::InvalidRange` if a range admitted no value, or was
+    ///   unbounded on both sides.
+    /// * `PolicyError::Request` if a requirement does not fit the schema, as
+    ///   for `PresentationRequest::check()`.
+    pub fn build<R>(self, csprng: &mut R) -> Result<(PresentationPolicy<'a>, PresentationInstructions), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if let Some(e) = self.error {
+            return Err(e.into());
+        }
+
+        let mut request = PresentationRequest::new(&self.verifier, self.schema, csprng);
+
+        if let Some(challenge) = self.challenge {
+            request.challenge = challenge;
+        }
+        request.attributes = self.attributes;
+        request.check(self.schema)?;
+
+        let instructions = PresentationInstructions {
+            request: request,
+            roster: self.roster.map(|(roster, _)| roster.value()),
+        };
+        let policy = PresentationPolicy {
+            schema: self.schema,
+            roster: self.roster,
+            instructions: instructions.clone(),
+        };
+
+        Ok((policy, instructions))
+    }
+}
+
+/// The verifier's half of a built policy.
+pub struct PresentationPolicy<'a> {
+    schema: &'a Schema,
+    roster: Option<(&'a Accumulator, &'a RevocableIssuer)>,
+    instructions: PresentationInstructions,
+}
+
+impl<'a> PresentationPolicy<'a> {
+    /// The instructions holders are sent.
+    pub fn instructions(&self) -> &PresentationInstructions {
+        &self.instructions
+    }
+
+    /// Verify a `response` to this policy's instructions with an `issuer`.
+    ///
+    /// # Returns
+    ///
+    /// The verified credential, and the value of each revealed attribute by
+    /// its name.
+    ///
+    /// # Errors
+    ///
+    /// * `PolicyError::Request` if the response does not verify, as for
+    ///   `PresentationRequest::verify()`.
+    /// * `PolicyError::MissingRosterProof` if a roster proof was required
+    ///   and not given, or given and not required.
+    /// * `PolicyError::Revocation(RevocationError::WrongEpoch)` if the
+    ///   roster has changed since the instructions were built, or the
+    ///   revocable credential was presented against another value of it.
+    /// * `PolicyError::Revocation(RevocationError::InvalidProof)` if the
+    ///   roster proof does not verify, or is for another credential.
+    pub fn verify(
+        &self,
+        issuer: &Issuer,
+        response: &PolicyResponse,
+    ) -> Result<(VerifiedCredential, Vec<(String, RevealedAttribute)>), Error>
+    {
+        let request: &PresentationRequest = &self.instructions.request;
+        let verified = request.verify(issuer, self.schema, &response.response)?;
+
+        match (self.roster, &response.roster) {
+            (None, &None) => (),
+            (Some((roster, revocable_issuer)), &Some(ref proof)) => {
+                if Some(roster.value()) != self.instructions.roster {
+                    return Err(PolicyError::Revocation(RevocationError::WrongEpoch).into());
+                }
+
+                roster.verify(revocable_issuer, &proof.presentation)?;
+
+                let presentation = &response.response.presentation.presentation;
+                let mut transcript = roster_transcript(request, &response.response, &proof.presentation);
+                let publics = roster_link::Publics {
+                    A: &issuer.system_parameters.h,
+                    P: &presentation.rerandomized_nonce,
+                    Cm0: &presentation.attributes_blinded[0].into(),
+                    P1: &proof.presentation.P,
+                    Cm1: &proof.presentation.Cm1,
+                };
+
+                if proof.link.verify(&mut transcript, publics).is_err() {
+                    return Err(PolicyError::Revocation(RevocationError::InvalidProof).into());
+                }
+            },
+            _ => return Err(PolicyError::MissingRosterProof.into()),
+        }
+
+        Ok(verified)
+    }
+}
+
+/// The holder's half of a built policy: the request to answer, and the
+/// roster value, if any, to answer for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresentationInstructions {
+    pub request: PresentationRequest,
+    /// The roster value a witness must be refreshed to.
+    pub roster: Option<AccumulatorValue>,
+}
+
+impl PresentationInstructions {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PresentationInstructions, PolicyError> {
+        let (roster, offset): (Option<AccumulatorValue>, usize) = match bytes.get(0) {
+            Some(&0) => (None, 1),
+            Some(&1) => {
+                let value = bytes.get(1..1 + SIZEOF_ACCUMULATOR_VALUE).ok_or(PolicyError::WrongNumberOfBytes)?;
+
+                (Some(AccumulatorValue::from_bytes(value)?), 1 + SIZEOF_ACCUMULATOR_VALUE)
+            },
+            Some(_) => return Err(PolicyError::Malformed),
+            None    => return Err(PolicyError::WrongNumberOfBytes),
+        };
+        let request = PresentationRequest::from_bytes(&bytes[offset..])?;
+
+        Ok(PresentationInstructions { request, roster })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        match self.roster {
+            None            => v.push(0),
+            Some(ref value) => { v.push(1); v.extend(value.to_bytes()); },
+        }
+        v.extend(self.request.to_bytes());
+        v
+    }
+
+    /// Describe these instructions in plain language, one line per
+    /// requirement, as `PresentationRequest::describe()`.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.request.describe();
+
+        if self.roster.is_some() {
+            let mut line: String = self.request.verifier.clone();
+
+            line.push_str(" asks you to show that your credential has not been revoked");
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Answer these instructions with the `user`'s credential under the
+    /// `schema`, and, if a roster is required, their `membership` of it.
+    ///
+    /// # Errors
+    ///
+    /// * `PolicyError::Request` if the request cannot be answered, as for
+    ///   `PresentationRequest::respond()`.
+    /// * `PolicyError::MissingRosterProof` if a roster is required and no
+    ///   `membership` was given.
+    /// * `PolicyError::Revocation(RevocationError::WrongEpoch)` if the
+    ///   witness is not for the roster value in these instructions.
+    /// * `PolicyError::Revocation(RevocationError::WrongHandle)` if the
+    ///   revocable credential or its witness is for another credential.
+    pub fn respond<R>(
+        &self,
+        user: &User,
+        schema: &Schema,
+        membership: Option<RosterMembership>,
+        rng: &mut R,
+    ) -> Result<PolicyResponse, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let membership: Option<RosterMembership> = match (self.roster, membership) {
+            (None, _)              => None,
+            (Some(_), None)        => return Err(PolicyError::MissingRosterProof.into()),
+            (Some(value), Some(m)) => {
+                if m.witness.value != value {
+                    return Err(PolicyError::Revocation(RevocationError::WrongEpoch).into());
+                }
+                Some(m)
+            },
+        };
+        let credential: &Credential = match user.credential {
+            Some(ref x) => x,
+            None        => return Err(PolicyError::Request(CredentialError::MissingData.into()).into()),
+        };
+        if let Some(m) = membership {
+            if credential.attributes[0] != m.credential.identity {
+                return Err(PolicyError::Revocation(RevocationError::WrongHandle).into());
+            }
+        }
+
+        let nonces: Nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+        let response: PresentationResponse = self.request.respond_with_nonces(user, schema, &nonces, rng)?;
+
+        let roster: Option<RosterProof> = match membership {
+            None    => None,
+            Some(m) => {
+                let (revocable, z1) = NonRevocationPresentation::create_opened(m.credential,
+                                                                               &user.system_parameters,
+                                                                               m.issuer_parameters,
+                                                                               m.witness, rng)?;
+                let presentation = &response.presentation.presentation;
+
+                let mut transcript = roster_transcript(&self.request, &response, &revocable);
+                let secrets = roster_link::Secrets {
+                    m0: &credential.attributes[0],
+                    z0: nonces[0].as_witness(),
+                    z1: z1.as_witness(),
+                };
+                let publics = roster_link::Publics {
+                    A: &user.system_parameters.h,
+                    P: &presentation.rerandomized_nonce,
+                    Cm0: &presentation.attributes_blinded[0].into(),
+                    P1: &revocable.P,
+                    Cm1: &revocable.Cm1,
+                };
+                let link = roster_link::Proof::create(&mut transcript, publics, secrets);
+
+                Some(RosterProof { presentation: revocable, link })
+            },
+        };
+
+        Ok(PolicyResponse { response, roster })
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PresentationInstructions,
+                                         "A valid byte sequence representing PresentationInstructions");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PresentationInstructions);
+impl_try_from_bytes!(PresentationInstructions, PolicyError);
+
+/// A holder's revocable credential from a roster's issuer, with their
+/// witness to the roster, for answering instructions which require it.
+#[derive(Clone, Copy, Debug)]
+pub struct RosterMembership<'a> {
+    pub credential: &'a RevocableCredential,
+    pub issuer_parameters: &'a RevocableIssuerParameters,
+    pub witness: &'a NonRevocationWitness,
+}
+
+/// A proof that the holder of the presented credential holds a revocable
+/// credential whose handle is on a roster.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RosterProof {
+    /// The revocable credential, presented against the roster.
+    pub presentation: NonRevocationPresentation,
+    /// A proof that its identity is the presented credential's hidden
+    /// attribute.
+    pub link: roster_link::Proof,
+}
+
+/// A holder's answer to some `PresentationInstructions`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyResponse {
+    pub response: PresentationResponse,
+    pub roster: Option<RosterProof>,
+}
+
+impl PolicyResponse {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PolicyResponse, PolicyError> {
+        let (roster, offset): (Option<RosterProof>, usize) = match bytes.get(0) {
+            Some(&0) => (None, 1),
+            Some(&1) => {
+                let roster: &[u8] = bytes.get(1..1 + SIZEOF_ROSTER_PROOF).ok_or(PolicyError::WrongNumberOfBytes)?;
+                let (presentation, link) = roster.split_at(SIZEOF_NON_REVOCATION_PRESENTATION);
+
+                (Some(RosterProof {
+                    presentation: NonRevocationPresentation::from_bytes(presentation)?,
+                    link: deserialize(link).or(Err(PolicyError::Malformed))?,
+                }), 1 + SIZEOF_ROSTER_PROOF)
+            },
+            Some(_) => return Err(PolicyError::Malformed),
+            None    => return Err(PolicyError::WrongNumberOfBytes),
+        };
+        let response = PresentationResponse::from_bytes(&bytes[offset..])?;
+
+        Ok(PolicyResponse { response, roster })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        match self.roster {
+            None => v.push(0),
+            Some(ref roster) => {
+                v.push(1);
+                v.extend(roster.presentation.to_bytes());
+                v.extend(roster.link.to_bytes().iter());
+            },
+        }
+        v.extend(self.response.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PolicyResponse, "A valid byte sequence representing a PolicyResponse");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PolicyResponse);
+impl_try_from_bytes!(PolicyResponse, PolicyError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    use credential::PresentationOptions;
+    use errors::RequestError;
+    use non_revocation::HandleEnrollment;
+    use parameters::SystemParameters;
+    use schema::AttributeType;
+    use schema::Reveal;
+    use schema::SchemaId;
+    use test_utils::H;
+
+    fn library() -> Schema {
+        Schema::new("library card").attribute("expiry", AttributeType::Timestamp, Reveal::Optional)
+    }
+
+    fn setup(expiry: u64) -> (Issuer, User) {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let id: SchemaId = library().id();
+        let issuance = issuer.issue_with_schema(&user.obtain(vec![Scalar::from(expiry)]), &id, &mut rng).unwrap();
+
+        user.obtain_finish_with_schema(Some(&issuance), &id).unwrap();
+
+        (issuer, user)
+    }
+
+    #[test]
+    fn policies_are_answered_and_verified() {
+        let mut rng = thread_rng();
+        let (issuer, user) = setup(1_700_000_000);
+        let schema = library();
+        let revocable_issuer = RevocableIssuer::create(user.system_parameters, &mut rng);
+        let mut roster = Accumulator::create(&mut rng);
+
+        // The holder enrols their library card for a revocable credential,
+        // whose random handle goes on the roster.
+        let id: SchemaId = schema.id();
+        let options = PresentationOptions::new().schema(&id);
+        let (enrollment, request) = HandleEnrollment::new_with(&user, &options, &mut rng).unwrap();
+        let issuance = revocable_issuer.issue_with(&issuer, &options, &roster, &request, &mut rng).unwrap();
+        let (revocable, witness) = enrollment.finish(&revocable_issuer.system_parameters,
+                                                     &revocable_issuer.get_issuer_parameters(),
+                                                     &issuance).unwrap();
+        let revocable_parameters = revocable_issuer.get_issuer_parameters();
+        let membership = RosterMembership {
+            credential: &revocable,
+            issuer_parameters: &revocable_parameters,
+            witness: &witness,
+        };
+
+        let (policy, instructions) = PresentationPolicyBuilder::new("Library", &schema)
+            .prove_range("expiry", 1_600_000_000..)
+            .purpose("to lend you books")
+            .require_roster(&roster, &revocable_issuer)
+            .bind_challenge([7u8; SIZEOF_REQUEST_CHALLENGE])
+            .build(&mut rng)
+            .unwrap();
+        let instructions = PresentationInstructions::from_bytes(&instructions.to_bytes()).unwrap();
+
+        assert!(&instructions == policy.instructions());
+        assert!(instructions.request.challenge == [7u8; SIZEOF_REQUEST_CHALLENGE]);
+        assert!(instructions.describe().len() == 2);
+
+        // The roster must be answered for.
+        assert!(instructions.respond(&user, &schema, None, &mut rng).err() == Some(PolicyError::MissingRosterProof.into()));
+
+        let response = instructions.respond(&user, &schema, Some(membership), &mut rng).unwrap();
+        let response = PolicyResponse::from_bytes(&response.to_bytes()).unwrap();
+
+        assert!(policy.verify(&issuer, &response).unwrap().1.is_empty());
+
+        let mut stripped = response.clone();
+
+        stripped.roster = None;
+
+        assert!(policy.verify(&issuer, &stripped).err() == Some(PolicyError::MissingRosterProof.into()));
+
+        // Once its handle is revoked, the credential no longer answers.
+        roster.revoke(&[request.handle]);
+
+        let (_, instructions) = PresentationPolicyBuilder::new("Library", &schema)
+            .require_roster(&roster, &revocable_issuer)
+            .build(&mut rng)
+            .unwrap();
+
+        assert!(instructions.respond(&user, &schema, Some(membership), &mut rng).err() ==
+                Some(PolicyError::Revocation(RevocationError::WrongEpoch).into()));
+        assert!(roster.verify(&revocable_issuer, &response.roster.unwrap().presentation).is_err());
+
+        // A bounded range is proven from both sides, over only the bits it
+        // spans.
+        let (bounded, bounded_instructions) = PresentationPolicyBuilder::new("Library", &schema)
+            .prove_range("expiry", 1_600_000_000..1_800_000_000)
+            .build(&mut rng)
+            .unwrap();
+
+        assert!(bounded_instructions.request.attributes.len() == 2);
+        assert!(bounded_instructions.request.attributes.iter().all(|a| match a.requirement {
+            Requirement::Predicate(_, bits) => bits == 28,
+            _ => false,
+        }));
+
+        let response = bounded_instructions.respond(&user, &schema, None, &mut rng).unwrap();
+
+        assert!(bounded.verify(&issuer, &response).is_ok());
+    }
+
+    #[test]
+    fn policies_are_checked_against_the_schema() {
+        let mut rng = thread_rng();
+        let schema = library();
+
+        assert!(PresentationPolicyBuilder::new("Library", &schema).reveal("name").build(&mut rng).err() ==
+                Some(PolicyError::Request(RequestError::UnknownAttribute)));
+        assert!(PresentationPolicyBuilder::new("Library", &schema).prove_range("expiry", 5..5).build(&mut rng).err() ==
+                Some(PolicyError::InvalidRange));
+        assert!(PresentationPolicyBuilder::new("Library", &schema).prove_range("expiry", ..).build(&mut rng).err() ==
+                Some(PolicyError::InvalidRange));
+
+        let hidden = Schema::new("library card").attribute("expiry", AttributeType::Timestamp, Reveal::Never);
+
+        assert!(PresentationPolicyBuilder::new("Library", &hidden).reveal("expiry").build(&mut rng).err() ==
+                Some(PolicyError::Request(RequestError::Schema(::errors::SchemaError::RevealRule))));
+        assert!(PresentationPolicyBuilder::new("Library", &hidden).prove_range("expiry", 0..=10).build(&mut rng).is_ok());
+    }
+}
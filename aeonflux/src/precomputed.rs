@@ -0,0 +1,200 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Precomputed basepoint tables for the system and issuer parameters.
+//!
+//! Most of the time spent issuing and showing credentials, outside of the
+//! proofs themselves, is in scalar multiplications by a handful of fixed
+//! points: the system generators \\( G \\) and \\( H \\), and the issuer's
+//! public points \\( X\_i \\).  A `PrecomputedIssuerParameters` builds a
+//! basepoint table for each of these once, when an `Issuer` or `User` is
+//! constructed, and thereafter every multiplication by one of those points
+//! uses its table.
+//!
+//! If the parameters held by an `Issuer` or `User` are later changed, any
+//! multiplication by a point without a table simply falls back to ordinary
+//! variable-base multiplication, so the tables can never be stale.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoBasepointTable;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use issuer::IssuerParameters;
+use parameters::SystemParameters;
+
+/// Basepoint tables for a set of `SystemParameters` and `IssuerParameters`.
+pub struct PrecomputedIssuerParameters {
+    system_parameters: SystemParameters,
+    issuer_parameters: IssuerParameters,
+    /// Each point with a table, other than the Ristretto basepoint, whose
+    /// table is built into `curve25519_dalek`.
+    tables: Vec<(RistrettoPoint, RistrettoBasepointTable)>,
+}
+
+impl PrecomputedIssuerParameters {
+    /// Build the tables for `system_parameters` and `issuer_parameters`.
+    pub fn new(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+    ) -> PrecomputedIssuerParameters
+    {
+        let mut precomputed = PrecomputedIssuerParameters::empty(system_parameters, issuer_parameters);
+        let mut points: Vec<RistrettoPoint> = Vec::with_capacity(2 + issuer_parameters.len());
+
+        points.push(system_parameters.g);
+        points.push(system_parameters.h);
+        points.extend(issuer_parameters.Xn.iter());
+
+        for point in points.iter() {
+            if *point != RISTRETTO_BASEPOINT_POINT && precomputed.table(point).is_none() {
+                precomputed.tables.push((*point, RistrettoBasepointTable::create(point)));
+            }
+        }
+
+        precomputed
+    }
+
+    /// Hold `system_parameters` and `issuer_parameters` without building any
+    /// tables, for short-lived `Issuer`s which would never recoup the cost.
+    pub(crate) fn empty(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+    ) -> PrecomputedIssuerParameters
+    {
+        PrecomputedIssuerParameters {
+            system_parameters: *system_parameters,
+            issuer_parameters: issuer_parameters.clone(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// The `SystemParameters` these tables were built for.
+    pub fn system_parameters(&self) -> &SystemParameters {
+        &self.system_parameters
+    }
+
+    /// The `IssuerParameters` these tables were built for.
+    pub fn issuer_parameters(&self) -> &IssuerParameters {
+        &self.issuer_parameters
+    }
+
+    /// The table for `point`, if we have one.
+    fn table(&self, point: &RistrettoPoint) -> Option<&RistrettoBasepointTable> {
+        if *point == RISTRETTO_BASEPOINT_POINT {
+            return Some(&RISTRETTO_BASEPOINT_TABLE);
+        }
+        for &(ref P, ref table) in self.tables.iter() {
+            if *point == *P {
+                return Some(table);
+            }
+        }
+
+        None
+    }
+
+    /// Compute `scalar * point`, using a table if `point` is one of the
+    /// precomputed generators or issuer public points.
+    ///
+    /// Whether a table is used depends only upon the public `point`, and both
+    /// paths are constant-time in the `scalar`.
+    pub fn mul(&self, point: &RistrettoPoint, scalar: &Scalar) -> RistrettoPoint {
+        match self.table(point) {
+            Some(table) => table * scalar,
+            None        => point * scalar,
+        }
+    }
+}
+
+impl fmt::Debug for PrecomputedIssuerParameters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PrecomputedIssuerParameters {{ system_parameters: {:?}, issuer_parameters: {:?} }}",
+               self.system_parameters, self.issuer_parameters)
+    }
+}
+
+/// Tables are equal if they were built for equal parameters.
+impl PartialEq for PrecomputedIssuerParameters {
+    fn eq(&self, other: &PrecomputedIssuerParameters) -> bool {
+        self.system_parameters == other.system_parameters &&
+            self.issuer_parameters == other.issuer_parameters
+    }
+}
+
+impl Eq for PrecomputedIssuerParameters { }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use credential::CredentialRequest;
+    use credential::RevealedAttribute;
+    use issuer::Issuer;
+    use nonces::Nonces;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use test_utils::H;
+    use user::User;
+
+    #[test]
+    fn mul_matches_variable_base() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let precomputed = PrecomputedIssuerParameters::new(&system_parameters, &issuer.get_issuer_parameters());
+        let unknown: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let scalar: Scalar = Scalar::random(&mut rng);
+
+        assert!(precomputed.table(&unknown).is_none());
+        assert!(PrecomputedIssuerParameters::empty(&system_parameters, &issuer.get_issuer_parameters())
+                .table(&system_parameters.h).is_none());
+
+        for point in [system_parameters.g, system_parameters.h,
+                      issuer.keypair.public.Xn[0], unknown].iter() {
+            assert!(precomputed.mul(point, &scalar) == point * &scalar);
+        }
+    }
+
+    #[test]
+    fn changed_parameters_fall_back() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let mut issuer = Issuer::create(system_parameters, &mut rng);
+
+        // Replace the issuer's key without rebuilding its tables.
+        issuer.keypair = Issuer::create(system_parameters, &mut rng).keypair;
+
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+        attributes.push(Scalar::random(&mut rng));
+
+        let request: CredentialRequest = user.obtain(attributes);
+        let issuance = issuer.issue(&request, &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let presentation = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+    }
+}
@@ -0,0 +1,535 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Reusable predicates over committed attributes.
+//!
+//! A `Predicate`, such as "at least 3" or "at most 1546300800", is proven
+//! about the value \\( v \\) within an attribute commitment
+//! \\( C = v P + z A \\), as found in a credential presentation, without
+//! revealing \\( v \\).
+//!
+//! Each predicate reduces to showing that some non-negative difference,
+//! e.g. \\( v - 3 \\), lies in \\( [0, 2^k) \\).  This is done with a
+//! `RangeProof`, which commits separately to each of the \\( k \\) bits of the
+//! difference and gives a disjunctive proof for each that it is either zero
+//! or one.  The blinding factors of the bit commitments are chosen so that
+//! they sum to the commitment to the difference, so the verifier can check
+//! the decomposition for free, and the commitment to the most significant bit
+//! need not be sent.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use encoding::bit;
+use encoding::choice_to_scalar;
+use encoding::conditional_select_scalar;
+use errors::Error;
+use errors::PredicateError;
+use nonces::Witness;
+use secret::Clear;
+
+/// The largest number of bits which a `RangeProof` can be made over.
+pub const MAXIMUM_RANGE_BITS: usize = 64;
+
+/// The length of an encoded `RangeProof` over a range of `bits` bits.
+pub fn sizeof_range_proof(bits: usize) -> usize {
+    1 + 32 + (bits - 1) * 32 + bits * 96
+}
+
+/// Compute `2^exponent` as a `Scalar`.
+fn power_of_two(exponent: usize) -> Scalar {
+    Scalar::from(1u64 << exponent)
+}
+
+/// A predicate about a committed value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Predicate {
+    /// The committed value is greater than or equal to the bound.
+    AtLeast(u64),
+    /// The committed value is less than or equal to the bound.
+    AtMost(u64),
+}
+
+impl Predicate {
+    /// Determine whether this predicate holds for a `value` and, if so,
+    /// return the difference which a `RangeProof` is made over.
+    ///
+    /// This branches only upon whether the predicate holds, which a prover
+    /// reveals regardless by failing to produce a proof.
+    fn difference(&self, value: u64, bits: usize) -> Result<u64, PredicateError> {
+        let difference: u64 = match *self {
+            Predicate::AtLeast(bound) => match value.checked_sub(bound) {
+                Some(x) => x,
+                None    => return Err(PredicateError::OutOfRange),
+            },
+            Predicate::AtMost(bound) => match bound.checked_sub(value) {
+                Some(x) => x,
+                None    => return Err(PredicateError::OutOfRange),
+            },
+        };
+
+        if bits < MAXIMUM_RANGE_BITS && difference >> bits != 0 {
+            return Err(PredicateError::OutOfRange);
+        }
+        Ok(difference)
+    }
+
+    /// Compute the commitment to the difference from a commitment, `C`, to
+    /// the value.
+    fn commitment_to_difference(&self, P: &RistrettoPoint, C: &RistrettoPoint) -> RistrettoPoint {
+        match *self {
+            Predicate::AtLeast(bound) => C - &(&Scalar::from(bound) * P),
+            Predicate::AtMost(bound)  => &(&Scalar::from(bound) * P) - C,
+        }
+    }
+
+    fn commit_to_transcript(&self, transcript: &mut Transcript) {
+        let (label, bound): (&'static [u8], u64) = match *self {
+            Predicate::AtLeast(bound) => (b"at least", bound),
+            Predicate::AtMost(bound)  => (b"at most", bound),
+        };
+        let mut bytes = [0u8; 8];
+
+        for i in 0..8 {
+            bytes[i] = (bound >> (8 * i)) as u8;
+        }
+
+        transcript.commit_bytes(b"predicate", label);
+        transcript.commit_bytes(b"bound", &bytes);
+    }
+
+    /// Determine whether this predicate holds for a `value`, using differences
+    /// of at most `bits` bits.
+    pub fn is_satisfied_by(&self, value: u64, bits: usize) -> bool {
+        self.difference(value, bits).is_ok()
+    }
+
+    /// Prove that this predicate holds for the `value` committed to in
+    /// `C = value * P + blinding * A`.
+    ///
+    /// # Inputs
+    ///
+    /// * `bits` is the size of the range, in bits, which the difference
+    ///   between the `value` and this predicate's bound is shown to lie in.
+    ///   It must be agreed upon with the verifier.
+    ///
+    /// # Errors
+    ///
+    /// * `PredicateError::OutOfRange` if the predicate does not hold, if the
+    ///   difference does not fit into `bits` bits, or if `bits` is zero or
+    ///   greater than `MAXIMUM_RANGE_BITS`.
+    pub fn prove<R>(
+        &self,
+        transcript: &mut Transcript,
+        bits: usize,
+        P: &RistrettoPoint,
+        A: &RistrettoPoint,
+        C: &RistrettoPoint,
+        value: u64,
+        blinding: Witness,
+        csprng: &mut R,
+    ) -> Result<RangeProof, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if bits == 0 || bits > MAXIMUM_RANGE_BITS {
+            return Err(PredicateError::OutOfRange.into());
+        }
+
+        let difference: u64 = self.difference(value, bits)?;
+        let D: RistrettoPoint = self.commitment_to_difference(P, C);
+        let mut d: Scalar = match *self {
+            Predicate::AtLeast(_) => *blinding.scalar(),
+            Predicate::AtMost(_)  => -blinding.scalar(),
+        };
+
+        self.commit_to_transcript(transcript);
+
+        let proof = RangeProof::create(transcript, bits, P, A, &D, difference, &d, csprng);
+
+        d.clear();
+
+        Ok(proof)
+    }
+
+    /// Verify a `proof` that this predicate holds for the value committed to
+    /// in `C`.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        bits: usize,
+        P: &RistrettoPoint,
+        A: &RistrettoPoint,
+        C: &RistrettoPoint,
+        proof: &RangeProof,
+    ) -> Result<(), Error>
+    {
+        let D: RistrettoPoint = self.commitment_to_difference(P, C);
+
+        self.commit_to_transcript(transcript);
+
+        proof.verify(transcript, bits, P, A, &D)
+    }
+}
+
+/// The responses of a disjunctive proof that a bit commitment, `Cb`, is to
+/// either zero or one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BitResponses {
+    /// The challenge for the branch where the bit is zero.  That for the
+    /// other branch is the overall challenge minus this one.
+    c0: Scalar,
+    s0: Scalar,
+    s1: Scalar,
+}
+
+/// A proof that a committed value lies within \\( [0, 2^k) \\).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RangeProof {
+    challenge: Scalar,
+    /// Commitments to every bit except the most significant.
+    bit_commitments: Vec<RistrettoPoint>,
+    responses: Vec<BitResponses>,
+}
+
+impl RangeProof {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RangeProof, PredicateError> {
+        if bytes.len() == 0 {
+            return Err(PredicateError::WrongNumberOfBytes);
+        }
+
+        let bits: usize = bytes[0] as usize;
+
+        if bits == 0 || bits > MAXIMUM_RANGE_BITS || bytes.len() != sizeof_range_proof(bits) {
+            return Err(PredicateError::WrongNumberOfBytes);
+        }
+
+        let mut scalars: Vec<Scalar> = Vec::with_capacity(1 + bits * 3);
+        let mut bit_commitments: Vec<RistrettoPoint> = Vec::with_capacity(bits - 1);
+        let scalars_offset: usize = 33 + (bits - 1) * 32;
+
+        for chunk in bytes[33..scalars_offset].chunks(32) {
+            bit_commitments.push(CompressedRistretto::from_slice(chunk).decompress()?);
+        }
+
+        for chunk in bytes[1..33].chunks(32).chain(bytes[scalars_offset..].chunks(32)) {
+            let mut tmp = [0u8; 32];
+
+            tmp.copy_from_slice(chunk);
+
+            match Scalar::from_canonical_bytes(tmp) {
+                Some(x) => scalars.push(x),
+                None    => return Err(PredicateError::ScalarFormatError),
+            }
+        }
+
+        let mut responses: Vec<BitResponses> = Vec::with_capacity(bits);
+
+        for i in 0..bits {
+            responses.push(BitResponses {
+                c0: scalars[1 + 3 * i],
+                s0: scalars[2 + 3 * i],
+                s1: scalars[3 + 3 * i],
+            });
+        }
+
+        Ok(RangeProof { challenge: scalars[0], bit_commitments, responses })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(sizeof_range_proof(self.bits()));
+
+        v.push(self.bits() as u8);
+        v.extend(self.challenge.as_bytes());
+
+        for Cb in self.bit_commitments.iter() {
+            v.extend(Cb.compress().as_bytes());
+        }
+        for response in self.responses.iter() {
+            v.extend(response.c0.as_bytes());
+            v.extend(response.s0.as_bytes());
+            v.extend(response.s1.as_bytes());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RangeProof, "A valid byte sequence representing a RangeProof");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RangeProof);
+impl_try_from_bytes!(RangeProof, PredicateError);
+
+impl RangeProof {
+    /// The number of bits in the range this proof was made over.
+    pub fn bits(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Compute the challenge from the bit commitments and the prover's
+    /// commitments for each branch of each bit's disjunction.
+    fn challenge(
+        transcript: &mut Transcript,
+        P: &RistrettoPoint,
+        A: &RistrettoPoint,
+        C: &RistrettoPoint,
+        bit_commitments: &[RistrettoPoint],
+        branch_commitments: &[(RistrettoPoint, RistrettoPoint)],
+    ) -> Scalar
+    {
+        let mut bytes = [0u8; 64];
+
+        transcript.commit_bytes(b"domain-sep", b"range_proof");
+        transcript.commit_bytes(b"bits", &[bit_commitments.len() as u8]);
+        transcript.commit_bytes(b"P", P.compress().as_bytes());
+        transcript.commit_bytes(b"A", A.compress().as_bytes());
+        transcript.commit_bytes(b"C", C.compress().as_bytes());
+
+        for (Cb, &(ref T0, ref T1)) in bit_commitments.iter().zip(branch_commitments.iter()) {
+            transcript.commit_bytes(b"Cb", Cb.compress().as_bytes());
+            transcript.commit_bytes(b"com T0", T0.compress().as_bytes());
+            transcript.commit_bytes(b"com T1", T1.compress().as_bytes());
+        }
+        transcript.challenge_bytes(b"chal", &mut bytes);
+
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    /// Prove that `C = value * P + blinding * A` for some `value` in
+    /// \\( [0, 2^{bits}) \\).
+    fn create<R>(
+        transcript: &mut Transcript,
+        bits: usize,
+        P: &RistrettoPoint,
+        A: &RistrettoPoint,
+        C: &RistrettoPoint,
+        value: u64,
+        blinding: &Scalar,
+        rng: &mut R,
+    ) -> RangeProof
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut csprng = transcript.fork_transcript()
+            .commit_witness_bytes(b"blinding", blinding.as_bytes())
+            .reseed_from_rng(rng);
+
+        // Choose the blinding factors for each bit so that they sum to that
+        // of the commitment, i.e. so that C = \sum 2^i Cb_i.
+        let mut r: Vec<Scalar> = Vec::with_capacity(bits);
+        let mut remainder: Scalar = *blinding;
+
+        for i in 0..bits - 1 {
+            let ri: Scalar = Scalar::random(&mut csprng);
+
+            remainder -= &power_of_two(i) * &ri;
+            r.push(ri);
+        }
+        r.push(&remainder * &power_of_two(bits - 1).invert());
+        remainder.clear();
+
+        let mut bit_commitments: Vec<RistrettoPoint> = Vec::with_capacity(bits);
+        let mut branch_commitments: Vec<(RistrettoPoint, RistrettoPoint)> = Vec::with_capacity(bits);
+        let mut simulated: Vec<(Scalar, Scalar)> = Vec::with_capacity(bits);
+        let mut nonces: Vec<Scalar> = Vec::with_capacity(bits);
+
+        // The bits of the value must not decide which branch we take, so the
+        // real and simulated branches are both computed as
+        // T = s' * A - c' * Cb', where for the real branch s' = w and c' = 0.
+        for i in 0..bits {
+            let b = bit(value, i);
+            let b_scalar: Scalar = choice_to_scalar(b);
+            let Cb: RistrettoPoint = &(A * &r[i]) + &(P * &b_scalar);
+
+            // Simulate the branch for the bit we don't have, and commit
+            // honestly to the other.
+            let w: Scalar = Scalar::random(&mut csprng);
+            let c: Scalar = Scalar::random(&mut csprng);
+            let s: Scalar = Scalar::random(&mut csprng);
+
+            let s0: Scalar = conditional_select_scalar(&w, &s, b);
+            let s1: Scalar = conditional_select_scalar(&s, &w, b);
+            let c0: Scalar = &c * &b_scalar;
+            let c1: Scalar = &c - &c0;
+
+            branch_commitments.push((&(A * &s0) - &(&c0 * &Cb),
+                                     &(A * &s1) - &(&c1 * &(Cb - P))));
+            bit_commitments.push(Cb);
+            simulated.push((c, s));
+            nonces.push(w);
+        }
+
+        let challenge: Scalar = RangeProof::challenge(transcript, P, A, C,
+                                                      &bit_commitments, &branch_commitments);
+        let mut responses: Vec<BitResponses> = Vec::with_capacity(bits);
+
+        for i in 0..bits {
+            let (c, s) = simulated[i];
+            let real_challenge: Scalar = &challenge - &c;
+            let real_response: Scalar = &nonces[i] + &(&real_challenge * &r[i]);
+            let b = bit(value, i);
+
+            responses.push(BitResponses {
+                c0: conditional_select_scalar(&real_challenge, &c, b),
+                s0: conditional_select_scalar(&real_response, &s, b),
+                s1: conditional_select_scalar(&s, &real_response, b),
+            });
+        }
+
+        for x in r.iter_mut().chain(nonces.iter_mut()) {
+            x.clear();
+        }
+
+        // The commitment to the most significant bit is implied by the others.
+        bit_commitments.pop();
+
+        RangeProof { challenge, bit_commitments, responses }
+    }
+
+    /// Verify that `C` is a commitment to some value in \\( [0, 2^{bits}) \\).
+    fn verify(
+        &self,
+        transcript: &mut Transcript,
+        bits: usize,
+        P: &RistrettoPoint,
+        A: &RistrettoPoint,
+        C: &RistrettoPoint,
+    ) -> Result<(), PredicateError>
+    {
+        if bits == 0 || self.bits() != bits || self.bit_commitments.len() != bits - 1 {
+            return Err(PredicateError::VerificationFailure);
+        }
+
+        // Everything here is public, so we may use variable-time arithmetic.
+        //
+        // Recover the commitment to the most significant bit.
+        let mut scalars: Vec<Scalar> = Vec::with_capacity(bits);
+        let mut points: Vec<RistrettoPoint> = Vec::with_capacity(bits);
+
+        scalars.push(Scalar::one());
+        points.push(*C);
+
+        for (i, Cb) in self.bit_commitments.iter().enumerate() {
+            scalars.push(-power_of_two(i));
+            points.push(*Cb);
+        }
+
+        let last: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(&scalars, &points);
+
+        let mut bit_commitments: Vec<RistrettoPoint> = self.bit_commitments.clone();
+
+        bit_commitments.push(&power_of_two(bits - 1).invert() * &last);
+
+        let mut branch_commitments: Vec<(RistrettoPoint, RistrettoPoint)> = Vec::with_capacity(bits);
+
+        for (Cb, response) in bit_commitments.iter().zip(self.responses.iter()) {
+            let c1: Scalar = &self.challenge - &response.c0;
+            let T0: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(
+                &[response.s0, -response.c0], &[*A, *Cb]);
+            let T1: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(
+                &[response.s1, -c1, c1], &[*A, *Cb, *P]);
+
+            branch_commitments.push((T0, T1));
+        }
+
+        let challenge: Scalar = RangeProof::challenge(transcript, P, A, C,
+                                                      &bit_commitments, &branch_commitments);
+
+        if challenge == self.challenge {
+            Ok(())
+        } else {
+            Err(PredicateError::VerificationFailure)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    use rand::thread_rng;
+
+    use nonces::Ephemeral;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    fn commit(value: u64) -> (RistrettoPoint, RistrettoPoint, RistrettoPoint, Ephemeral) {
+        let mut rng = thread_rng();
+        let A: RistrettoPoint = SystemParameters::from(H).h;
+        let P: RistrettoPoint = &Scalar::random(&mut rng) * &RISTRETTO_BASEPOINT_POINT;
+        let z: Ephemeral = Ephemeral::new(&mut rng);
+        let C: RistrettoPoint = &(&Scalar::from(value) * &P) + &(&z * &A);
+
+        (P, A, C, z)
+    }
+
+    #[test]
+    fn at_least() {
+        let (P, A, C, z) = commit(5);
+        let predicate = Predicate::AtLeast(3);
+        let proof = predicate.prove(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, 5, z.as_witness(), &mut thread_rng())
+            .unwrap();
+        let proof = RangeProof::from_bytes(&proof.to_bytes()).unwrap();
+
+        assert!(proof.to_bytes().len() == sizeof_range_proof(8));
+        assert!(predicate.verify(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, &proof).is_ok());
+        assert!(Predicate::AtLeast(4).verify(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, &proof).is_err());
+        assert!(predicate.verify(&mut Transcript::new(b"OTHER"), 8, &P, &A, &C, &proof).is_err());
+    }
+
+    #[test]
+    fn at_most() {
+        let (P, A, C, z) = commit(1546300800);
+        let predicate = Predicate::AtMost(1546300800);
+        let proof = predicate.prove(&mut Transcript::new(b"TEST"), 32, &P, &A, &C, 1546300800, z.as_witness(),
+                                    &mut thread_rng()).unwrap();
+
+        assert!(predicate.verify(&mut Transcript::new(b"TEST"), 32, &P, &A, &C, &proof).is_ok());
+        assert!(predicate.verify(&mut Transcript::new(b"TEST"), 16, &P, &A, &C, &proof).is_err());
+    }
+
+    #[test]
+    fn unsatisfied_predicates_cannot_be_proven() {
+        let (P, A, C, z) = commit(2);
+
+        assert!(Predicate::AtLeast(3).prove(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, 2, z.as_witness(),
+                                            &mut thread_rng()) == Err(PredicateError::OutOfRange.into()));
+        assert!(Predicate::AtMost(1).prove(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, 2, z.as_witness(),
+                                           &mut thread_rng()) == Err(PredicateError::OutOfRange.into()));
+        assert!(!Predicate::AtLeast(0).is_satisfied_by(256, 8));
+    }
+
+    #[test]
+    fn lying_about_the_value_fails() {
+        let (P, A, C, z) = commit(2);
+        let proof = Predicate::AtLeast(3).prove(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, 3, z.as_witness(),
+                                                &mut thread_rng()).unwrap();
+
+        assert!(Predicate::AtLeast(3).verify(&mut Transcript::new(b"TEST"), 8, &P, &A, &C, &proof).is_err());
+    }
+}
@@ -0,0 +1,713 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Structured requests, from a verifier, for what a presentation must show.
+//!
+//! A verifier sends a `PresentationRequest` naming the schema of the
+//! credential it wants, a fresh challenge, and a list of `RequestedAttribute`s:
+//! each names an attribute of the schema, whether it must be revealed or
+//! only shown to satisfy some `Predicate`, and a human-readable label and
+//! purpose.  The holder may `describe()` the request to ask for consent, and
+//! then `respond()` to it, which builds exactly the presentation requested
+//! and no more: every attribute which is not named stays hidden.
+//!
+//! A revealed attribute is opened with a proof that the presentation's
+//! commitment is to that value; a predicate is shown with a `RangeProof`
+//! over the commitment.  Both are bound to the request, so a response to one
+//! request is refused for any other.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::fmt::Write;
+
+#[cfg(not(feature = "std"))]
+use core::str;
+#[cfg(feature = "std")]
+use std::str;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::Credential;
+use credential::CredentialPresentation;
+use credential::RevealedAttribute;
+use credential::VerifiedCredential;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use errors::RequestError;
+use errors::SchemaError;
+use issuer::Issuer;
+use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+use predicates::MAXIMUM_RANGE_BITS;
+use predicates::Predicate;
+use predicates::RangeProof;
+use proofs::attribute_opening;
+use schema::AttributeType;
+use schema::Reveal;
+use schema::Schema;
+use schema::SchemaId;
+use schema::SchemaPresentation;
+use schema::SIZEOF_SCHEMA_ID;
+use user::User;
+
+/// The size of the challenge which makes each request fresh.
+pub const SIZEOF_REQUEST_CHALLENGE: usize = 32;
+
+/// The largest number of attributes a request may name.
+pub const MAXIMUM_REQUESTED_ATTRIBUTES: usize = 255;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// Read a string, preceded by its length as a single byte, from the start of
+/// `bytes`, returning it and the number of bytes read.
+pub(crate) fn read_string(bytes: &[u8]) -> Result<(String, usize), RequestError> {
+    let length: usize = *bytes.get(0).ok_or(RequestError::WrongNumberOfBytes)? as usize;
+    let string: &[u8] = bytes.get(1..1 + length).ok_or(RequestError::WrongNumberOfBytes)?;
+    let string: &str = str::from_utf8(string).or(Err(RequestError::Malformed))?;
+
+    Ok((String::from(string), 1 + length))
+}
+
+pub(crate) fn write_string(v: &mut Vec<u8>, string: &str) {
+    v.push(string.len() as u8);
+    v.extend(string.as_bytes());
+}
+
+/// The value of an integer attribute, if it is one.
+fn integer_value(attribute: &RevealedAttribute) -> Option<u64> {
+    let bytes: &[u8; 32] = attribute.as_bytes();
+
+    if bytes[8..].iter().any(|x| *x != 0) {
+        return None;
+    }
+    Some(read_u64(&bytes[..8]))
+}
+
+/// What a verifier requires of one attribute.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Requirement {
+    /// The attribute must be revealed.
+    Reveal,
+    /// The attribute must satisfy a `Predicate`, shown with a `RangeProof`
+    /// over differences of some number of bits, without being revealed.
+    Predicate(Predicate, u8),
+}
+
+impl Requirement {
+    pub(crate) fn write(&self, v: &mut Vec<u8>) {
+        match *self {
+            Requirement::Reveal => v.push(1),
+            Requirement::Predicate(Predicate::AtLeast(bound), bits) => {
+                v.push(2);
+                v.extend(u64_to_bytes(bound).iter());
+                v.push(bits);
+            },
+            Requirement::Predicate(Predicate::AtMost(bound), bits) => {
+                v.push(3);
+                v.extend(u64_to_bytes(bound).iter());
+                v.push(bits);
+            },
+        }
+    }
+
+    pub(crate) fn read(bytes: &[u8]) -> Result<(Requirement, usize), RequestError> {
+        let tag: u8 = *bytes.get(0).ok_or(RequestError::WrongNumberOfBytes)?;
+
+        if tag == 1 {
+            return Ok((Requirement::Reveal, 1));
+        }
+
+        let rest: &[u8] = bytes.get(1..10).ok_or(RequestError::WrongNumberOfBytes)?;
+        let bound: u64 = read_u64(&rest[..8]);
+        let predicate: Predicate = match tag {
+            2 => Predicate::AtLeast(bound),
+            3 => Predicate::AtMost(bound),
+            _ => return Err(RequestError::Malformed),
+        };
+
+        Ok((Requirement::Predicate(predicate, rest[8]), 10))
+    }
+}
+
+/// One attribute named in a `PresentationRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestedAttribute {
+    /// A short label, e.g. "Age check".
+    pub label: String,
+    /// The name of the attribute in the request's schema.
+    pub attribute: String,
+    pub requirement: Requirement,
+    /// Why the verifier asks for it, e.g. "To confirm you may buy alcohol".
+    pub purpose: String,
+}
+
+/// A verifier's request for a presentation of a credential under a schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresentationRequest {
+    /// The name of the verifier, as shown to the holder.
+    pub verifier: String,
+    /// The schema the credential must have been issued under.
+    pub schema: SchemaId,
+    pub challenge: [u8; SIZEOF_REQUEST_CHALLENGE],
+    pub attributes: Vec<RequestedAttribute>,
+}
+
+impl PresentationRequest {
+    /// Begin a request, from a `verifier`, for a credential under `schema`.
+    pub fn new<R>(verifier: &str, schema: &Schema, csprng: &mut R) -> PresentationRequest
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut challenge = [0u8; SIZEOF_REQUEST_CHALLENGE];
+
+        csprng.fill_bytes(&mut challenge);
+
+        PresentationRequest {
+            verifier: String::from(verifier),
+            schema: schema.id(),
+            challenge: challenge,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Require that the `attribute` be revealed.
+    pub fn reveal(mut self, label: &str, attribute: &str, purpose: &str) -> PresentationRequest {
+        self.attributes.push(RequestedAttribute {
+            label: String::from(label),
+            attribute: String::from(attribute),
+            requirement: Requirement::Reveal,
+            purpose: String::from(purpose),
+        });
+        self
+    }
+
+    /// Require that the `attribute` satisfy a `predicate`, over differences
+    /// of at most `bits` bits.
+    pub fn predicate(
+        mut self,
+        label: &str,
+        attribute: &str,
+        predicate: Predicate,
+        bits: u8,
+        purpose: &str,
+    ) -> PresentationRequest
+    {
+        self.attributes.push(RequestedAttribute {
+            label: String::from(label),
+            attribute: String::from(attribute),
+            requirement: Requirement::Predicate(predicate, bits),
+            purpose: String::from(purpose),
+        });
+        self
+    }
+
+    /// Check that this request may be satisfied by a credential under
+    /// `schema`.
+    ///
+    /// # Returns
+    ///
+    /// The position of each requested attribute in the schema, in order.
+    ///
+    /// # Errors
+    ///
+    /// * `RequestError::WrongSchema` if this request is for another schema.
+    /// * `RequestError::UnknownAttribute` if an attribute is not in the
+    ///   schema.
+    /// * `RequestError::Schema(SchemaError::RevealRule)` if an attribute the
+    ///   schema never reveals is requested to be revealed.
+    /// * `RequestError::NotAnInteger` if a predicate is requested over an
+    ///   attribute which is not an integer or a timestamp, or over zero or
+    ///   more than `MAXIMUM_RANGE_BITS` bits.
+    pub fn check(&self, schema: &Schema) -> Result<Vec<usize>, Error> {
+        if schema.id() != self.schema {
+            return Err(RequestError::WrongSchema.into());
+        }
+
+        let mut indices: Vec<usize> = Vec::with_capacity(self.attributes.len());
+
+        for requested in self.attributes.iter() {
+            let index: usize = schema.index_of(&requested.attribute).ok_or(RequestError::UnknownAttribute)?;
+            let attribute = &schema.attributes[index];
+
+            // Every attribute is hidden in the presentation itself, and
+            // opened separately when it is to be revealed.
+            if index < PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES || index >= NUMBER_OF_ATTRIBUTES {
+                return Err(RequestError::UnknownAttribute.into());
+            }
+
+            match requested.requirement {
+                Requirement::Reveal => if attribute.reveal == Reveal::Never {
+                    return Err(RequestError::Schema(SchemaError::RevealRule).into());
+                },
+                Requirement::Predicate(_, bits) => {
+                    if attribute.kind != AttributeType::Integer && attribute.kind != AttributeType::Timestamp {
+                        return Err(RequestError::NotAnInteger.into());
+                    }
+                    if bits == 0 || bits as usize > MAXIMUM_RANGE_BITS {
+                        return Err(RequestError::NotAnInteger.into());
+                    }
+                },
+            }
+            indices.push(index);
+        }
+        Ok(indices)
+    }
+
+    /// Describe this request in plain language, one line per requested
+    /// attribute, e.g. for a holder to consent to.
+    pub fn describe(&self) -> Vec<String> {
+        self.attributes.iter().map(|requested| {
+            let mut line: String = String::new();
+
+            // Writing to a String cannot fail.
+            let _ = write!(line, "{}: {} asks you to ", requested.label, self.verifier);
+            let _ = match requested.requirement {
+                Requirement::Reveal
+                    => write!(line, "reveal your {}", requested.attribute),
+                Requirement::Predicate(Predicate::AtLeast(bound), _)
+                    => write!(line, "show that your {} is at least {}", requested.attribute, bound),
+                Requirement::Predicate(Predicate::AtMost(bound), _)
+                    => write!(line, "show that your {} is at most {}", requested.attribute, bound),
+            };
+            let _ = write!(line, ", {}", requested.purpose);
+
+            line
+        }).collect()
+    }
+
+    /// Begin the transcript which every opening and range proof in a
+    /// response to this request, with some `presentation`, is made within.
+    pub(crate) fn transcript(&self, presentation: &CredentialPresentation) -> Transcript {
+        let mut transcript = Transcript::new(b"AEONFLUX PRESENTATION REQUEST");
+
+        transcript.commit_bytes(b"request", &self.to_bytes());
+        transcript.commit_bytes(b"presentation", &presentation.to_bytes());
+        transcript
+    }
+
+    /// Build exactly the presentation of the `user`'s credential which this
+    /// request asks for.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from `PresentationRequest::check()`.
+    /// * `RequestError::Credential(CredentialError::MissingData)` if the
+    ///   `user` has no credential.
+    /// * `RequestError::Unsatisfied` if the credential does not satisfy a
+    ///   requested predicate.
+    pub fn respond<R>(
+        &self,
+        user: &User,
+        schema: &Schema,
+        rng: &mut R,
+    ) -> Result<PresentationResponse, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let nonces: Nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+
+        self.respond_with_nonces(user, schema, &nonces, rng)
+    }
+
+    /// As `PresentationRequest::respond()`, with the `nonces` the
+    /// presentation's commitments are made with, so that further proofs
+    /// about the same commitments may be made alongside it.
+    pub(crate) fn respond_with_nonces<R>(
+        &self,
+        user: &User,
+        schema: &Schema,
+        nonces: &Nonces,
+        rng: &mut R,
+    ) -> Result<PresentationResponse, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let indices: Vec<usize> = self.check(schema)?;
+        let credential: &Credential = match user.credential {
+            Some(ref x) => x,
+            None        => return Err(RequestError::Credential(CredentialError::MissingData).into()),
+        };
+
+        let presentation: CredentialPresentation = user.show_with_schema(&self.schema, nonces, rng)?;
+
+        let mut transcript = self.transcript(&presentation);
+
+        let A: RistrettoPoint = user.system_parameters.h;
+        let P: RistrettoPoint = presentation.rerandomized_nonce;
+        let mut disclosures: Vec<Disclosure> = Vec::with_capacity(self.attributes.len());
+
+        for (requested, index) in self.attributes.iter().zip(indices.iter()) {
+            let blinded: usize = index - PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+            let value: &RevealedAttribute = &credential.attributes[*index];
+            let Cm: RistrettoPoint = presentation.attributes_blinded[blinded].into();
+
+            match requested.requirement {
+                Requirement::Reveal => {
+                    let W: RistrettoPoint = Cm - (value * P);
+                    let secrets = attribute_opening::Secrets { z0: nonces[blinded].as_witness() };
+                    let publics = attribute_opening::Publics { A: &A, W: &W };
+                    let proof = attribute_opening::Proof::create(&mut transcript, publics, secrets);
+
+                    disclosures.push(Disclosure::Revealed(*value, proof));
+                },
+                Requirement::Predicate(predicate, bits) => {
+                    let integer: u64 = integer_value(value).ok_or(RequestError::Unsatisfied)?;
+                    let proof = predicate.prove(&mut transcript, bits as usize, &P, &A, &Cm, integer,
+                                                nonces[blinded].as_witness(), rng)?;
+
+                    disclosures.push(Disclosure::Satisfied(proof));
+                },
+            }
+        }
+
+        Ok(PresentationResponse {
+            presentation: SchemaPresentation { schema: self.schema, presentation },
+            disclosures: disclosures,
+        })
+    }
+
+    /// Verify a `response` to this request with an `issuer`.
+    ///
+    /// # Returns
+    ///
+    /// The verified credential, and the value of each revealed attribute by
+    /// its label.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from `PresentationRequest::check()`.
+    /// * `RequestError::Credential` if the presentation does not verify.
+    /// * `RequestError::WrongNumberOfDisclosures` if the response does not
+    ///   answer each requested attribute, in order, with a disclosure of the
+    ///   requested kind.
+    /// * `RequestError::InvalidProof` if an opening or range proof does not
+    ///   verify.
+    pub fn verify(
+        &self,
+        issuer: &Issuer,
+        schema: &Schema,
+        response: &PresentationResponse,
+    ) -> Result<(VerifiedCredential, Vec<(String, RevealedAttribute)>), Error>
+    {
+        let indices: Vec<usize> = self.check(schema)?;
+
+        if response.presentation.schema != self.schema {
+            return Err(RequestError::WrongSchema.into());
+        }
+        if response.disclosures.len() != self.attributes.len() {
+            return Err(RequestError::WrongNumberOfDisclosures.into());
+        }
+
+        let presentation: &CredentialPresentation = &response.presentation.presentation;
+        let verified: VerifiedCredential = issuer.verify_with_schema(presentation, &self.schema)?;
+
+        let mut transcript = self.transcript(presentation);
+        let A: RistrettoPoint = issuer.system_parameters.h;
+        let P: RistrettoPoint = presentation.rerandomized_nonce;
+        let mut revealed: Vec<(String, RevealedAttribute)> = Vec::new();
+
+        for ((requested, index), disclosure) in self.attributes.iter().zip(indices.iter()).zip(response.disclosures.iter()) {
+            let blinded: usize = index - PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+            let Cm: RistrettoPoint = presentation.attributes_blinded[blinded].into();
+
+            match (&requested.requirement, disclosure) {
+                (&Requirement::Reveal, &Disclosure::Revealed(ref value, ref proof)) => {
+                    let W: RistrettoPoint = Cm - (value * P);
+                    let publics = attribute_opening::Publics { A: &A, W: &W };
+
+                    if proof.verify(&mut transcript, publics).is_err() {
+                        return Err(RequestError::InvalidProof.into());
+                    }
+                    revealed.push((requested.label.clone(), *value));
+                },
+                (&Requirement::Predicate(predicate, bits), &Disclosure::Satisfied(ref proof)) => {
+                    if predicate.verify(&mut transcript, bits as usize, &P, &A, &Cm, proof).is_err() {
+                        return Err(RequestError::InvalidProof.into());
+                    }
+                },
+                _ => return Err(RequestError::WrongNumberOfDisclosures.into()),
+            }
+        }
+
+        Ok((verified, revealed))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<PresentationRequest, RequestError> {
+        let (verifier, mut offset) = read_string(bytes)?;
+        let header: &[u8] = bytes.get(offset..offset + SIZEOF_SCHEMA_ID + SIZEOF_REQUEST_CHALLENGE + 1)
+            .ok_or(RequestError::WrongNumberOfBytes)?;
+        let mut schema = [0u8; SIZEOF_SCHEMA_ID];
+        let mut challenge = [0u8; SIZEOF_REQUEST_CHALLENGE];
+
+        schema.copy_from_slice(&header[..SIZEOF_SCHEMA_ID]);
+        challenge.copy_from_slice(&header[SIZEOF_SCHEMA_ID..SIZEOF_SCHEMA_ID + SIZEOF_REQUEST_CHALLENGE]);
+
+        let count: usize = header[SIZEOF_SCHEMA_ID + SIZEOF_REQUEST_CHALLENGE] as usize;
+        let mut attributes: Vec<RequestedAttribute> = Vec::with_capacity(count);
+
+        offset += header.len();
+
+        for _ in 0..count {
+            let (label, length) = read_string(&bytes[offset..])?;
+            offset += length;
+            let (attribute, length) = read_string(&bytes[offset..])?;
+            offset += length;
+            let (requirement, length) = Requirement::read(&bytes[offset..])?;
+            offset += length;
+            let (purpose, length) = read_string(&bytes[offset..])?;
+            offset += length;
+
+            attributes.push(RequestedAttribute { label, attribute, requirement, purpose });
+        }
+        if offset != bytes.len() {
+            return Err(RequestError::WrongNumberOfBytes);
+        }
+
+        Ok(PresentationRequest { verifier, schema: SchemaId(schema), challenge, attributes })
+    }
+
+    /// Encode this request.
+    ///
+    /// # Panics
+    ///
+    /// If a string is longer than 255 bytes, or more than
+    /// `MAXIMUM_REQUESTED_ATTRIBUTES` attributes are requested.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        assert!(self.verifier.len() <= 255 && self.attributes.len() <= MAXIMUM_REQUESTED_ATTRIBUTES);
+        assert!(self.attributes.iter().all(|a| a.label.len() <= 255 &&
+                                               a.attribute.len() <= 255 &&
+                                               a.purpose.len() <= 255));
+
+        write_string(&mut v, &self.verifier);
+        v.extend(self.schema.0.iter());
+        v.extend(self.challenge.iter());
+        v.push(self.attributes.len() as u8);
+
+        for requested in self.attributes.iter() {
+            write_string(&mut v, &requested.label);
+            write_string(&mut v, &requested.attribute);
+            requested.requirement.write(&mut v);
+            write_string(&mut v, &requested.purpose);
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PresentationRequest, "A valid byte sequence representing a PresentationRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PresentationRequest);
+impl_try_from_bytes!(PresentationRequest, RequestError);
+
+/// The answer to one `RequestedAttribute`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Disclosure {
+    /// The attribute's value, and a proof that the presentation's commitment
+    /// is to it.
+    Revealed(RevealedAttribute, attribute_opening::Proof),
+    /// A proof that the attribute satisfies the requested predicate.
+    Satisfied(RangeProof),
+}
+
+/// A holder's response to a `PresentationRequest`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresentationResponse {
+    pub presentation: SchemaPresentation,
+    /// The answer to each requested attribute, in the order requested.
+    pub disclosures: Vec<Disclosure>,
+}
+
+impl PresentationResponse {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PresentationResponse, RequestError> {
+        let count: usize = *bytes.get(0).ok_or(RequestError::WrongNumberOfBytes)? as usize;
+        let mut disclosures: Vec<Disclosure> = Vec::with_capacity(count);
+        let mut offset: usize = 1;
+
+        for _ in 0..count {
+            let header: &[u8] = bytes.get(offset..offset + 3).ok_or(RequestError::WrongNumberOfBytes)?;
+            let length: usize = header[1] as usize | (header[2] as usize) << 8;
+            let body: &[u8] = bytes.get(offset + 3..offset + 3 + length).ok_or(RequestError::WrongNumberOfBytes)?;
+
+            disclosures.push(match header[0] {
+                1 if body.len() >= 32 => {
+                    let mut tmp = [0u8; 32];
+
+                    tmp.copy_from_slice(&body[..32]);
+
+                    let value = Scalar::from_canonical_bytes(tmp).ok_or(RequestError::ScalarFormatError)?;
+                    let proof = deserialize(&body[32..]).or(Err(RequestError::Malformed))?;
+
+                    Disclosure::Revealed(value, proof)
+                },
+                2 => Disclosure::Satisfied(RangeProof::from_bytes(body)?),
+                _ => return Err(RequestError::Malformed),
+            });
+            offset += 3 + length;
+        }
+
+        let presentation = SchemaPresentation::from_bytes(&bytes[offset..])?;
+
+        Ok(PresentationResponse { presentation, disclosures })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.push(self.disclosures.len() as u8);
+
+        for disclosure in self.disclosures.iter() {
+            let (tag, body): (u8, Vec<u8>) = match *disclosure {
+                Disclosure::Revealed(ref value, ref proof) => {
+                    let mut body: Vec<u8> = value.to_bytes().to_vec();
+
+                    body.extend(proof.to_bytes().iter());
+                    (1, body)
+                },
+                Disclosure::Satisfied(ref proof) => (2, proof.to_bytes()),
+            };
+
+            v.push(tag);
+            v.push(body.len() as u8);
+            v.push((body.len() >> 8) as u8);
+            v.extend(body);
+        }
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PresentationResponse, "A valid byte sequence representing a PresentationResponse");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PresentationResponse);
+impl_try_from_bytes!(PresentationResponse, RequestError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use errors::PredicateError;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    fn age() -> Schema {
+        Schema::new("age").attribute("age", AttributeType::Integer, Reveal::Optional)
+    }
+
+    fn setup(value: u64) -> (Issuer, User) {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let id: SchemaId = age().id();
+        let issuance = issuer.issue_with_schema(&user.obtain(vec![Scalar::from(value)]), &id, &mut rng).unwrap();
+
+        user.obtain_finish_with_schema(Some(&issuance), &id).unwrap();
+
+        (issuer, user)
+    }
+
+    #[test]
+    fn requests_are_answered_exactly() {
+        let mut rng = thread_rng();
+        let (issuer, user) = setup(34);
+        let request = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .predicate("Age check", "age", Predicate::AtLeast(18), 8, "to sell you alcohol");
+        let request = PresentationRequest::from_bytes(&request.to_bytes()).unwrap();
+
+        assert!(request.describe() ==
+                vec![String::from("Age check: Corner Shop asks you to show that your age is at least 18, \
+                                   to sell you alcohol")]);
+
+        let response = request.respond(&user, &age(), &mut rng).unwrap();
+        let response = PresentationResponse::from_bytes(&response.to_bytes()).unwrap();
+        let (_, revealed) = request.verify(&issuer, &age(), &response).unwrap();
+
+        assert!(revealed.is_empty());
+
+        let reveal = PresentationRequest::new("Registry", &age(), &mut rng)
+            .reveal("Age", "age", "for the census");
+        let opened = reveal.respond(&user, &age(), &mut rng).unwrap();
+
+        assert!(reveal.verify(&issuer, &age(), &opened).unwrap().1 ==
+                vec![(String::from("Age"), Scalar::from(34u64))]);
+
+        // A response is bound to the request it answers.
+        let again = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .predicate("Age check", "age", Predicate::AtLeast(18), 8, "to sell you alcohol");
+
+        assert!(again.verify(&issuer, &age(), &response).err() == Some(RequestError::InvalidProof.into()));
+        assert!(request.verify(&issuer, &age(), &opened).err() == Some(RequestError::WrongNumberOfDisclosures.into()));
+    }
+
+    #[test]
+    fn unsatisfiable_requests_are_refused() {
+        let mut rng = thread_rng();
+        let (issuer, user) = setup(16);
+        let request = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .predicate("Age check", "age", Predicate::AtLeast(18), 8, "to sell you alcohol");
+
+        assert!(request.respond(&user, &age(), &mut rng).err() ==
+                Some(RequestError::Predicate(PredicateError::OutOfRange)));
+
+        let unknown = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .reveal("Name", "name", "to greet you");
+        let hidden = Schema::new("age").attribute("age", AttributeType::Integer, Reveal::Never);
+        let never = PresentationRequest::new("Corner Shop", &hidden, &mut rng)
+            .reveal("Age", "age", "to greet you");
+
+        assert!(unknown.check(&age()).err() == Some(RequestError::UnknownAttribute.into()));
+        assert!(never.check(&hidden).err() == Some(RequestError::Schema(SchemaError::RevealRule).into()));
+        assert!(never.check(&age()).err() == Some(RequestError::WrongSchema.into()));
+
+        // A response which omits a requested proof is refused.
+        let lenient = PresentationRequest::new("Corner Shop", &age(), &mut rng)
+            .predicate("Age check", "age", Predicate::AtMost(18), 8, "for the youth rate");
+        let mut response = lenient.respond(&user, &age(), &mut rng).unwrap();
+
+        assert!(lenient.verify(&issuer, &age(), &response).is_ok());
+
+        response.disclosures.clear();
+
+        assert!(lenient.verify(&issuer, &age(), &response).err() == Some(RequestError::WrongNumberOfDisclosures.into()));
+    }
+}
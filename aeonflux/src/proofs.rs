@@ -24,15 +24,52 @@ use curve25519_dalek::scalar::Scalar;
 #[allow(unused_imports)]
 use curve25519_dalek::traits::{MultiscalarMul, VartimeMultiscalarMul};
 
-use merlin::Transcript;
+use nonces::Witness;
 
+#[cfg(all(not(feature = "std"), feature = "alloc", not(feature = "getrandom")))]
+use rand::prng::ChaChaRng;
+#[cfg(feature = "std")]
+use rand::rngs::ThreadRng;
+#[cfg(feature = "std")]
 use rand::thread_rng;
 
-#[cfg(not(feature = "std"))]
+#[cfg(all(not(feature = "std"), feature = "alloc", not(feature = "getrandom")))]
+use rand_core::SeedableRng;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
 use core::iter;
 #[cfg(feature = "std")]
 use std::iter;
 
+#[cfg(all(not(feature = "std"), feature = "alloc", feature = "getrandom"))]
+use rng::GetrandomRng;
+
+// As above, the transcript traits are only used within submodules.
+#[allow(unused_imports)]
+use transcript::{ProofRngBuilder, ProofTranscript};
+
+/// The generator with which every proof's transcript generator is reseeded.
+///
+/// The nonces of a proof are derived from its transcript and its witnesses
+/// as well as from this generator, so where no system generator is
+/// available, the proof is merely deterministic: its nonces remain unknown
+/// to anyone without the witnesses.
+#[cfg(feature = "std")]
+fn proof_rng() -> ThreadRng {
+    thread_rng()
+}
+
+#[cfg(all(not(feature = "std"), feature = "alloc", feature = "getrandom"))]
+fn proof_rng() -> GetrandomRng {
+    GetrandomRng
+}
+
+#[cfg(all(not(feature = "std"), feature = "alloc", not(feature = "getrandom")))]
+fn proof_rng() -> ChaChaRng {
+    ChaChaRng::from_seed([0u8; 32])
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod issuance_revealed {
     use super::*;
 
@@ -40,7 +77,7 @@ pub mod issuance_revealed {
     pub struct Secrets<'a> {
         pub x0: &'a Scalar,
         pub x1: &'a Scalar,
-        pub x0_tilde: &'a Scalar,
+        pub x0_tilde: Witness<'a>,
         pub m1x1: &'a Scalar,
     }
 
@@ -75,6 +112,9 @@ pub mod issuance_revealed {
         m1x1: Scalar,
     }
 
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 160;
+
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
     pub struct Proof {
         challenge: Scalar,
@@ -82,9 +122,22 @@ pub mod issuance_revealed {
     }
 
     impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.x0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.x1.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.x0_tilde.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.m1x1.as_bytes());
+            bytes
+        }
+
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
             transcript.commit_bytes(b"domain-sep", "issuance_revealed".as_bytes());
             transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
             transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
@@ -96,10 +149,10 @@ pub mod issuance_revealed {
             let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
             let rng_ctor =
-                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.as_bytes());
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.scalar().as_bytes());
             let rng_ctor =
                 rng_ctor.commit_witness_bytes("m1x1".as_bytes(), secrets.m1x1.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
             let rand = Randomnesses {
                 x0: Scalar::random(&mut transcript_rng),
                 x1: Scalar::random(&mut transcript_rng),
@@ -128,7 +181,7 @@ pub mod issuance_revealed {
             let responses = Responses {
                 x0: &(&challenge * secrets.x0) + &rand.x0,
                 x1: &(&challenge * secrets.x1) + &rand.x1,
-                x0_tilde: &(&challenge * secrets.x0_tilde) + &rand.x0_tilde,
+                x0_tilde: &(&challenge * secrets.x0_tilde.scalar()) + &rand.x0_tilde,
                 m1x1: &(&challenge * secrets.m1x1) + &rand.m1x1,
             };
             Proof { challenge: challenge, responses: responses }
@@ -136,7 +189,7 @@ pub mod issuance_revealed {
 
         /// Verify the `Proof` using the public parameters `Publics`.
         #[allow(dead_code)]
-        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
             let responses = &self.responses;
             let minus_c = -&self.challenge;
             let commitments = Commitments {
@@ -177,21 +230,20 @@ pub mod issuance_revealed {
     }
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod attributes_blinded {
     use super::*;
 
     #[derive(Copy, Clone)]
     pub struct Secrets<'a> {
-        pub d: &'a Scalar,
-        pub e0: &'a Scalar,
+        pub d: Witness<'a>,
+        pub e0: Witness<'a>,
         pub m0: &'a Scalar,
-        pub nonce: &'a Scalar,
     }
 
     #[derive(Copy, Clone)]
     pub struct Publics<'a> {
         pub B: &'a RistrettoPoint,
-        pub A: &'a RistrettoPoint,
         pub D: &'a RistrettoPoint,
         pub encrypted_attribute_0_0: &'a RistrettoPoint,
         pub encrypted_attribute_0_1: &'a RistrettoPoint,
@@ -207,7 +259,6 @@ pub mod attributes_blinded {
         d: Scalar,
         e0: Scalar,
         m0: Scalar,
-        nonce: Scalar,
     }
 
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
@@ -215,9 +266,11 @@ pub mod attributes_blinded {
         d: Scalar,
         e0: Scalar,
         m0: Scalar,
-        nonce: Scalar,
     }
 
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 128;
+
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
     pub struct Proof {
         challenge: Scalar,
@@ -225,33 +278,35 @@ pub mod attributes_blinded {
     }
 
     impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.d.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.e0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.m0.as_bytes());
+            bytes
+        }
+
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
             transcript.commit_bytes(b"domain-sep", "attributes_blinded".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
-            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
             transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
-            transcript.commit_bytes(
-                "encrypted_attribute_0_0".as_bytes(),
-                publics.encrypted_attribute_0_0.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "encrypted_attribute_0_1".as_bytes(),
-                publics.encrypted_attribute_0_1.compress().as_bytes(),
-            );
+            transcript.commit_bytes("encrypted_attribute_0_0".as_bytes(), publics.encrypted_attribute_0_0.compress().as_bytes());
+            transcript.commit_bytes("encrypted_attribute_0_1".as_bytes(), publics.encrypted_attribute_0_1.compress().as_bytes());
             let rng_ctor = transcript.fork_transcript();
-            let rng_ctor = rng_ctor.commit_witness_bytes("d".as_bytes(), secrets.d.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("e0".as_bytes(), secrets.e0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("d".as_bytes(), secrets.d.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("e0".as_bytes(), secrets.e0.scalar().as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
-            let rng_ctor =
-                rng_ctor.commit_witness_bytes("nonce".as_bytes(), secrets.nonce.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
             let rand = Randomnesses {
                 d: Scalar::random(&mut transcript_rng),
                 e0: Scalar::random(&mut transcript_rng),
                 m0: Scalar::random(&mut transcript_rng),
-                nonce: Scalar::random(&mut transcript_rng),
             };
             let commitments = Commitments {
                 D: RistrettoPoint::multiscalar_mul(&[rand.d], &[*(publics.B)]),
@@ -279,16 +334,16 @@ pub mod attributes_blinded {
                 Scalar::from_bytes_mod_order_wide(&bytes)
             };
             let responses = Responses {
-                d: &(&challenge * secrets.d) + &rand.d,
-                e0: &(&challenge * secrets.e0) + &rand.e0,
+                d: &(&challenge * secrets.d.scalar()) + &rand.d,
+                e0: &(&challenge * secrets.e0.scalar()) + &rand.e0,
                 m0: &(&challenge * secrets.m0) + &rand.m0,
-                nonce: &(&challenge * secrets.nonce) + &rand.nonce,
             };
             Proof { challenge: challenge, responses: responses }
         }
+
         /// Verify the `Proof` using the public parameters `Publics`.
         #[allow(dead_code)]
-        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
             let responses = &self.responses;
             let minus_c = -&self.challenge;
             let commitments = Commitments {
@@ -298,29 +353,18 @@ pub mod attributes_blinded {
                 ),
                 encrypted_attribute_0_0: RistrettoPoint::vartime_multiscalar_mul(
                     (&[responses.e0]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.B)])
-                        .into_iter()
-                        .chain(iter::once(publics.encrypted_attribute_0_0)),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.encrypted_attribute_0_0)),
                 ),
                 encrypted_attribute_0_1: RistrettoPoint::vartime_multiscalar_mul(
                     (&[responses.m0, responses.e0]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.B), *(publics.D)])
-                        .into_iter()
-                        .chain(iter::once(publics.encrypted_attribute_0_1)),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.encrypted_attribute_0_1)),
                 ),
             };
             transcript.commit_bytes(b"domain-sep", "attributes_blinded".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
-            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
             transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
-            transcript.commit_bytes(
-                "encrypted_attribute_0_0".as_bytes(),
-                publics.encrypted_attribute_0_0.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "encrypted_attribute_0_1".as_bytes(),
-                publics.encrypted_attribute_0_1.compress().as_bytes(),
-            );
+            transcript.commit_bytes("encrypted_attribute_0_0".as_bytes(), publics.encrypted_attribute_0_0.compress().as_bytes());
+            transcript.commit_bytes("encrypted_attribute_0_1".as_bytes(), publics.encrypted_attribute_0_1.compress().as_bytes());
             transcript.commit_bytes("com D".as_bytes(), commitments.D.compress().as_bytes());
             transcript.commit_bytes(
                 "com encrypted_attribute_0_0".as_bytes(),
@@ -344,29 +388,30 @@ pub mod attributes_blinded {
     }
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod issuance_blinded {
     use super::*;
 
     #[derive(Copy, Clone)]
     pub struct Secrets<'a> {
-        pub x0_tilde: &'a Scalar,
         pub x0: &'a Scalar,
         pub x1: &'a Scalar,
-        pub s: &'a Scalar,
-        pub b: &'a Scalar,
-        pub t0: &'a Scalar,
+        pub x0_tilde: Witness<'a>,
+        pub b: Witness<'a>,
+        pub s: Witness<'a>,
+        pub t1: Witness<'a>,
     }
 
     #[derive(Copy, Clone)]
     pub struct Publics<'a> {
         pub B: &'a RistrettoPoint,
         pub A: &'a RistrettoPoint,
-        pub X0: &'a RistrettoPoint,
         pub X1: &'a RistrettoPoint,
         pub D: &'a RistrettoPoint,
         pub P: &'a RistrettoPoint,
-        pub T0_0: &'a RistrettoPoint,
-        pub T0_1: &'a RistrettoPoint,
+        pub T1_0: &'a RistrettoPoint,
+        pub T1_1: &'a RistrettoPoint,
+        pub Cx0: &'a RistrettoPoint,
         pub EQ_commitment: &'a RistrettoPoint,
         pub EQ_encryption: &'a RistrettoPoint,
         pub encrypted_attribute_0_0: &'a RistrettoPoint,
@@ -374,34 +419,37 @@ pub mod issuance_blinded {
     }
 
     struct Commitments {
-        X0: RistrettoPoint,
+        Cx0: RistrettoPoint,
         X1: RistrettoPoint,
         P: RistrettoPoint,
-        T0_0: RistrettoPoint,
-        T0_1: RistrettoPoint,
+        T1_0: RistrettoPoint,
+        T1_1: RistrettoPoint,
         EQ_commitment: RistrettoPoint,
         EQ_encryption: RistrettoPoint,
     }
 
     struct Randomnesses {
-        x0_tilde: Scalar,
         x0: Scalar,
         x1: Scalar,
-        s: Scalar,
+        x0_tilde: Scalar,
         b: Scalar,
-        t0: Scalar,
+        s: Scalar,
+        t1: Scalar,
     }
 
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
     struct Responses {
-        x0_tilde: Scalar,
         x0: Scalar,
         x1: Scalar,
-        s: Scalar,
+        x0_tilde: Scalar,
         b: Scalar,
-        t0: Scalar,
+        s: Scalar,
+        t1: Scalar,
     }
 
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 224;
+
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
     pub struct Proof {
         challenge: Scalar,
@@ -409,74 +457,77 @@ pub mod issuance_blinded {
     }
 
     impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.x0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.x1.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.x0_tilde.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.b.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.s.as_bytes());
+            bytes[192..224].copy_from_slice(self.responses.t1.as_bytes());
+            bytes
+        }
+
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
             transcript.commit_bytes(b"domain-sep", "issuance_blinded".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
-            transcript.commit_bytes("X0".as_bytes(), publics.X0.compress().as_bytes());
             transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
             transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
             transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
-            transcript.commit_bytes("T0_0".as_bytes(), publics.T0_0.compress().as_bytes());
-            transcript.commit_bytes("T0_1".as_bytes(), publics.T0_1.compress().as_bytes());
-            transcript.commit_bytes(
-                "EQ_commitment".as_bytes(),
-                publics.EQ_commitment.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "EQ_encryption".as_bytes(),
-                publics.EQ_encryption.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "encrypted_attribute_0_0".as_bytes(),
-                publics.encrypted_attribute_0_0.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "encrypted_attribute_0_1".as_bytes(),
-                publics.encrypted_attribute_0_1.compress().as_bytes(),
-            );
+            transcript.commit_bytes("T1_0".as_bytes(), publics.T1_0.compress().as_bytes());
+            transcript.commit_bytes("T1_1".as_bytes(), publics.T1_1.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("EQ_commitment".as_bytes(), publics.EQ_commitment.compress().as_bytes());
+            transcript.commit_bytes("EQ_encryption".as_bytes(), publics.EQ_encryption.compress().as_bytes());
+            transcript.commit_bytes("encrypted_attribute_0_0".as_bytes(), publics.encrypted_attribute_0_0.compress().as_bytes());
+            transcript.commit_bytes("encrypted_attribute_0_1".as_bytes(), publics.encrypted_attribute_0_1.compress().as_bytes());
             let rng_ctor = transcript.fork_transcript();
-            let rng_ctor =
-                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
             let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("s".as_bytes(), secrets.s.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("t0".as_bytes(), secrets.t0.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("s".as_bytes(), secrets.s.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("t1".as_bytes(), secrets.t1.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
             let rand = Randomnesses {
-                x0_tilde: Scalar::random(&mut transcript_rng),
                 x0: Scalar::random(&mut transcript_rng),
                 x1: Scalar::random(&mut transcript_rng),
-                s: Scalar::random(&mut transcript_rng),
+                x0_tilde: Scalar::random(&mut transcript_rng),
                 b: Scalar::random(&mut transcript_rng),
-                t0: Scalar::random(&mut transcript_rng),
+                s: Scalar::random(&mut transcript_rng),
+                t1: Scalar::random(&mut transcript_rng),
             };
             let commitments = Commitments {
-                X0: RistrettoPoint::multiscalar_mul(
+                Cx0: RistrettoPoint::multiscalar_mul(
                     &[rand.x0, rand.x0_tilde],
                     &[*(publics.B), *(publics.A)],
                 ),
                 X1: RistrettoPoint::multiscalar_mul(&[rand.x1], &[*(publics.A)]),
                 P: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.B)]),
-                T0_0: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.X0)]),
-                T0_1: RistrettoPoint::multiscalar_mul(&[rand.t0], &[*(publics.A)]),
+                T1_0: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.X1)]),
+                T1_1: RistrettoPoint::multiscalar_mul(&[rand.t1], &[*(publics.A)]),
                 EQ_commitment: RistrettoPoint::multiscalar_mul(
-                    &[rand.s, rand.t0],
+                    &[rand.s, rand.t1],
                     &[*(publics.B), *(publics.encrypted_attribute_0_0)],
                 ),
                 EQ_encryption: RistrettoPoint::multiscalar_mul(
-                    &[rand.s, rand.t0],
-                    &[*(publics.D), *(publics.encrypted_attribute_0_1)],
+                    &[rand.s, rand.t1, rand.x0],
+                    &[*(publics.D), *(publics.encrypted_attribute_0_1), *(publics.P)],
                 ),
             };
-            transcript.commit_bytes("com X0".as_bytes(), commitments.X0.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
             transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
             transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
-            transcript.commit_bytes("com T0_0".as_bytes(), commitments.T0_0.compress().as_bytes());
-            transcript.commit_bytes("com T0_1".as_bytes(), commitments.T0_1.compress().as_bytes());
+            transcript.commit_bytes("com T1_0".as_bytes(), commitments.T1_0.compress().as_bytes());
+            transcript.commit_bytes("com T1_1".as_bytes(), commitments.T1_1.compress().as_bytes());
             transcript.commit_bytes(
                 "com EQ_commitment".as_bytes(),
                 commitments.EQ_commitment.compress().as_bytes(),
@@ -491,25 +542,25 @@ pub mod issuance_blinded {
                 Scalar::from_bytes_mod_order_wide(&bytes)
             };
             let responses = Responses {
-                x0_tilde: &(&challenge * secrets.x0_tilde) + &rand.x0_tilde,
                 x0: &(&challenge * secrets.x0) + &rand.x0,
                 x1: &(&challenge * secrets.x1) + &rand.x1,
-                s: &(&challenge * secrets.s) + &rand.s,
-                b: &(&challenge * secrets.b) + &rand.b,
-                t0: &(&challenge * secrets.t0) + &rand.t0,
+                x0_tilde: &(&challenge * secrets.x0_tilde.scalar()) + &rand.x0_tilde,
+                b: &(&challenge * secrets.b.scalar()) + &rand.b,
+                s: &(&challenge * secrets.s.scalar()) + &rand.s,
+                t1: &(&challenge * secrets.t1.scalar()) + &rand.t1,
             };
             Proof { challenge: challenge, responses: responses }
         }
 
         /// Verify the `Proof` using the public parameters `Publics`.
         #[allow(dead_code)]
-        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
             let responses = &self.responses;
             let minus_c = -&self.challenge;
             let commitments = Commitments {
-                X0: RistrettoPoint::vartime_multiscalar_mul(
+                Cx0: RistrettoPoint::vartime_multiscalar_mul(
                     (&[responses.x0, responses.x0_tilde]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.X0)),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.Cx0)),
                 ),
                 X1: RistrettoPoint::vartime_multiscalar_mul(
                     (&[responses.x1]).into_iter().chain(iter::once(&(minus_c))),
@@ -519,57 +570,41 @@ pub mod issuance_blinded {
                     (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
                     (&[*(publics.B)]).into_iter().chain(iter::once(publics.P)),
                 ),
-                T0_0: RistrettoPoint::vartime_multiscalar_mul(
+                T1_0: RistrettoPoint::vartime_multiscalar_mul(
                     (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.X0)]).into_iter().chain(iter::once(publics.T0_0)),
+                    (&[*(publics.X1)]).into_iter().chain(iter::once(publics.T1_0)),
                 ),
-                T0_1: RistrettoPoint::vartime_multiscalar_mul(
-                    (&[responses.t0]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.T0_1)),
+                T1_1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.t1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.T1_1)),
                 ),
                 EQ_commitment: RistrettoPoint::vartime_multiscalar_mul(
-                    (&[responses.s, responses.t0]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.B), *(publics.encrypted_attribute_0_0)])
-                        .into_iter()
-                        .chain(iter::once(publics.EQ_commitment)),
+                    (&[responses.s, responses.t1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.encrypted_attribute_0_0)]).into_iter().chain(iter::once(publics.EQ_commitment)),
                 ),
                 EQ_encryption: RistrettoPoint::vartime_multiscalar_mul(
-                    (&[responses.s, responses.t0]).into_iter().chain(iter::once(&(minus_c))),
-                    (&[*(publics.D), *(publics.encrypted_attribute_0_1)])
-                        .into_iter()
-                        .chain(iter::once(publics.EQ_encryption)),
+                    (&[responses.s, responses.t1, responses.x0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.D), *(publics.encrypted_attribute_0_1), *(publics.P)]).into_iter().chain(iter::once(publics.EQ_encryption)),
                 ),
             };
             transcript.commit_bytes(b"domain-sep", "issuance_blinded".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
-            transcript.commit_bytes("X0".as_bytes(), publics.X0.compress().as_bytes());
             transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
             transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
             transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
-            transcript.commit_bytes("T0_0".as_bytes(), publics.T0_0.compress().as_bytes());
-            transcript.commit_bytes("T0_1".as_bytes(), publics.T0_1.compress().as_bytes());
-            transcript.commit_bytes(
-                "EQ_commitment".as_bytes(),
-                publics.EQ_commitment.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "EQ_encryption".as_bytes(),
-                publics.EQ_encryption.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "encrypted_attribute_0_0".as_bytes(),
-                publics.encrypted_attribute_0_0.compress().as_bytes(),
-            );
-            transcript.commit_bytes(
-                "encrypted_attribute_0_1".as_bytes(),
-                publics.encrypted_attribute_0_1.compress().as_bytes(),
-            );
-            transcript.commit_bytes("com X0".as_bytes(), commitments.X0.compress().as_bytes());
+            transcript.commit_bytes("T1_0".as_bytes(), publics.T1_0.compress().as_bytes());
+            transcript.commit_bytes("T1_1".as_bytes(), publics.T1_1.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("EQ_commitment".as_bytes(), publics.EQ_commitment.compress().as_bytes());
+            transcript.commit_bytes("EQ_encryption".as_bytes(), publics.EQ_encryption.compress().as_bytes());
+            transcript.commit_bytes("encrypted_attribute_0_0".as_bytes(), publics.encrypted_attribute_0_0.compress().as_bytes());
+            transcript.commit_bytes("encrypted_attribute_0_1".as_bytes(), publics.encrypted_attribute_0_1.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
             transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
             transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
-            transcript.commit_bytes("com T0_0".as_bytes(), commitments.T0_0.compress().as_bytes());
-            transcript.commit_bytes("com T0_1".as_bytes(), commitments.T0_1.compress().as_bytes());
+            transcript.commit_bytes("com T1_0".as_bytes(), commitments.T1_0.compress().as_bytes());
+            transcript.commit_bytes("com T1_1".as_bytes(), commitments.T1_1.compress().as_bytes());
             transcript.commit_bytes(
                 "com EQ_commitment".as_bytes(),
                 commitments.EQ_commitment.compress().as_bytes(),
@@ -598,8 +633,8 @@ pub mod valid_credential {
     #[derive(Copy, Clone)]
     pub struct Secrets<'a> {
         pub m0: &'a Scalar,
-        pub z0: &'a Scalar,
-        pub minus_zQ: &'a Scalar,
+        pub z0: Witness<'a>,
+        pub minus_zQ: Witness<'a>,
     }
 
     #[derive(Copy, Clone)]
@@ -612,11 +647,21 @@ pub mod valid_credential {
         pub Cm0: &'a RistrettoPoint,
     }
 
+    /// The publics of a scoped tag, \\( N = m_0 \cdot H \\), on the hidden
+    /// attribute, which may be proven under the same challenge as the
+    /// credential, reusing its response for \\( m_0 \\).
+    #[derive(Copy, Clone)]
+    pub struct ScopedPublics<'a> {
+        pub Hn: &'a RistrettoPoint,
+        pub N: &'a RistrettoPoint,
+    }
+
     struct Commitments {
         Cm0: RistrettoPoint,
         V: RistrettoPoint,
     }
 
+    #[cfg(any(feature = "std", feature = "alloc"))]
     struct Randomnesses {
         m0: Scalar,
         z0: Scalar,
@@ -630,6 +675,9 @@ pub mod valid_credential {
         minus_zQ: Scalar,
     }
 
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 128;
+
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
     pub struct Proof {
         challenge: Scalar,
@@ -637,10 +685,77 @@ pub mod valid_credential {
     }
 
     impl Proof {
+        /// Decode a `Proof` from the same 128 bytes as its `bincode`
+        /// serialisation, without allocating.
+        pub fn from_bytes(bytes: &[u8]) -> Result<Proof, ()> {
+            if bytes.len() != SIZEOF_PROOF {
+                return Err(());
+            }
+
+            let mut scalars: [Scalar; 4] = [Scalar::zero(); 4];
+
+            for i in 0..4 {
+                let mut tmp: [u8; 32] = [0u8; 32];
+
+                tmp.copy_from_slice(&bytes[32 * i..32 * (i + 1)]);
+                scalars[i] = Scalar::from_canonical_bytes(tmp).ok_or(())?;
+            }
+
+            Ok(Proof {
+                challenge: scalars[0],
+                responses: Responses { m0: scalars[1], z0: scalars[2], minus_zQ: scalars[3] },
+            })
+        }
+
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.z0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.minus_zQ.as_bytes());
+            bytes
+        }
+
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
-            transcript.commit_bytes(b"domain-sep", "valid_credential".as_bytes());
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            Proof::create_with(transcript, publics, None, secrets)
+        }
+
+        /// Create a `Proof` which also shows that the `scoped` tag was
+        /// computed from the hidden attribute, for no more than the size of a
+        /// `Proof` without it.
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        pub fn create_scoped<T: ProofTranscript>(
+            transcript: &mut T,
+            publics: Publics,
+            scoped: ScopedPublics,
+            secrets: Secrets,
+        ) -> Proof
+        {
+            Proof::create_with(transcript, publics, Some(scoped), secrets)
+        }
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        fn create_with<T: ProofTranscript>(
+            transcript: &mut T,
+            publics: Publics,
+            scoped: Option<ScopedPublics>,
+            secrets: Secrets,
+        ) -> Proof
+        {
+            match scoped {
+                Some(ref scoped) => {
+                    transcript.commit_bytes(b"domain-sep", "valid_credential_scoped".as_bytes());
+                    transcript.commit_bytes("Hn".as_bytes(), scoped.Hn.compress().as_bytes());
+                    transcript.commit_bytes("N".as_bytes(), scoped.N.compress().as_bytes());
+                },
+                None => transcript.commit_bytes(b"domain-sep", "valid_credential".as_bytes()),
+            }
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
             transcript.commit_bytes("X0".as_bytes(), publics.X0.compress().as_bytes());
@@ -649,10 +764,10 @@ pub mod valid_credential {
             transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
             let rng_ctor = transcript.fork_transcript();
             let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
             let rng_ctor =
-                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
             let rand = Randomnesses {
                 m0: Scalar::random(&mut transcript_rng),
                 z0: Scalar::random(&mut transcript_rng),
@@ -670,6 +785,11 @@ pub mod valid_credential {
             };
             transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
             transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            if let Some(ref scoped) = scoped {
+                let N: RistrettoPoint = scoped.Hn * rand.m0;
+
+                transcript.commit_bytes("com N".as_bytes(), N.compress().as_bytes());
+            }
             let challenge = {
                 let mut bytes = [0; 64];
                 transcript.challenge_bytes(b"chal", &mut bytes);
@@ -677,17 +797,46 @@ pub mod valid_credential {
             };
             let responses = Responses {
                 m0: &(&challenge * secrets.m0) + &rand.m0,
-                z0: &(&challenge * secrets.z0) + &rand.z0,
-                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+                minus_zQ: &(&challenge * secrets.minus_zQ.scalar()) + &rand.minus_zQ,
             };
             Proof { challenge: challenge, responses: responses }
         }
 
         /// Verify the `Proof` using the public parameters `Publics`.
         #[allow(dead_code)]
-        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            self.verify_with(transcript, publics, None)
+        }
+
+        /// Verify a `Proof` made with `Proof::create_scoped()`.
+        pub fn verify_scoped<T: ProofTranscript>(
+            &self,
+            transcript: &mut T,
+            publics: Publics,
+            scoped: ScopedPublics,
+        ) -> Result<(), ()>
+        {
+            self.verify_with(transcript, publics, Some(scoped))
+        }
+
+        fn verify_with<T: ProofTranscript>(
+            &self,
+            transcript: &mut T,
+            publics: Publics,
+            scoped: Option<ScopedPublics>,
+        ) -> Result<(), ()>
+        {
             let responses = &self.responses;
             let minus_c = -&self.challenge;
+            // Without an allocator there is no multiscalar multiplication, so
+            // the commitments are recomputed with separate multiplications.
+            #[cfg(not(any(feature = "std", feature = "alloc")))]
+            let commitments = Commitments {
+                Cm0: &(&(&responses.m0 * publics.P) + &(&responses.z0 * publics.A)) + &(&minus_c * publics.Cm0),
+                V: &(&(&responses.z0 * publics.X0) + &(&responses.minus_zQ * publics.A)) + &(&minus_c * publics.V),
+            };
+            #[cfg(any(feature = "std", feature = "alloc"))]
             let commitments = Commitments {
                 Cm0: RistrettoPoint::vartime_multiscalar_mul(
                     (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
@@ -698,7 +847,14 @@ pub mod valid_credential {
                     (&[*(publics.X0), *(publics.A)]).into_iter().chain(iter::once(publics.V)),
                 ),
             };
-            transcript.commit_bytes(b"domain-sep", "valid_credential".as_bytes());
+            match scoped {
+                Some(ref scoped) => {
+                    transcript.commit_bytes(b"domain-sep", "valid_credential_scoped".as_bytes());
+                    transcript.commit_bytes("Hn".as_bytes(), scoped.Hn.compress().as_bytes());
+                    transcript.commit_bytes("N".as_bytes(), scoped.N.compress().as_bytes());
+                },
+                None => transcript.commit_bytes(b"domain-sep", "valid_credential".as_bytes()),
+            }
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
             transcript.commit_bytes("X0".as_bytes(), publics.X0.compress().as_bytes());
@@ -707,6 +863,11 @@ pub mod valid_credential {
             transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
             transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
             transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            if let Some(ref scoped) = scoped {
+                let N: RistrettoPoint = &(&responses.m0 * scoped.Hn) + &(&minus_c * scoped.N);
+
+                transcript.commit_bytes("com N".as_bytes(), N.compress().as_bytes());
+            }
             let challenge = {
                 let mut bytes = [0; 64];
                 transcript.challenge_bytes(b"chal", &mut bytes);
@@ -721,14 +882,15 @@ pub mod valid_credential {
     }
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod committed_values_equal {
     use super::*;
 
     #[derive(Copy, Clone)]
     pub struct Secrets<'a> {
         pub m0: &'a Scalar,
-        pub z0: &'a Scalar,
-        pub z1: &'a Scalar,
+        pub z0: Witness<'a>,
+        pub z1: Witness<'a>,
     }
 
     #[derive(Copy, Clone)]
@@ -758,6 +920,9 @@ pub mod committed_values_equal {
         z1: Scalar,
     }
 
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 128;
+
     #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
     pub struct Proof {
         challenge: Scalar,
@@ -765,9 +930,21 @@ pub mod committed_values_equal {
     }
 
     impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.z0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.z1.as_bytes());
+            bytes
+        }
+
         /// Create a `Proof` from the given `Publics` and `Secrets`.
         #[allow(dead_code)]
-        pub fn create(transcript: &mut Transcript, publics: Publics, secrets: Secrets) -> Proof {
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
             transcript.commit_bytes(b"domain-sep", "committed_values_equal".as_bytes());
             transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
             transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
@@ -776,9 +953,9 @@ pub mod committed_values_equal {
             transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
             let rng_ctor = transcript.fork_transcript();
             let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.as_bytes());
-            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.as_bytes());
-            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut thread_rng());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
             let rand = Randomnesses {
                 m0: Scalar::random(&mut transcript_rng),
                 z0: Scalar::random(&mut transcript_rng),
@@ -803,15 +980,15 @@ pub mod committed_values_equal {
             };
             let responses = Responses {
                 m0: &(&challenge * secrets.m0) + &rand.m0,
-                z0: &(&challenge * secrets.z0) + &rand.z0,
-                z1: &(&challenge * secrets.z1) + &rand.z1,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+                z1: &(&challenge * secrets.z1.scalar()) + &rand.z1,
             };
             Proof { challenge: challenge, responses: responses }
         }
 
         /// Verify the `Proof` using the public parameters `Publics`.
         #[allow(dead_code)]
-        pub fn verify(&self, transcript: &mut Transcript, publics: Publics) -> Result<(), ()> {
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
             let responses = &self.responses;
             let minus_c = -&self.challenge;
             let commitments = Commitments {
@@ -845,3 +1022,2374 @@ pub mod committed_values_equal {
         }
     }
 }
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod attribute_opening {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub z0: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub W: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        W: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        z0: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        z0: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 64;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Decode a `Proof` from the same 64 bytes as its `bincode`
+        /// serialisation, without allocating.
+        pub fn from_bytes(bytes: &[u8]) -> Result<Proof, ()> {
+            if bytes.len() != SIZEOF_PROOF {
+                return Err(());
+            }
+
+            let mut scalars: [Scalar; 2] = [Scalar::zero(); 2];
+
+            for i in 0..2 {
+                let mut tmp: [u8; 32] = [0u8; 32];
+
+                tmp.copy_from_slice(&bytes[32 * i..32 * (i + 1)]);
+                scalars[i] = Scalar::from_canonical_bytes(tmp).ok_or(())?;
+            }
+
+            Ok(Proof { challenge: scalars[0], responses: Responses { z0: scalars[1] } })
+        }
+
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.z0.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "attribute_opening".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("W".as_bytes(), publics.W.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                z0: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                W: RistrettoPoint::multiscalar_mul(&[rand.z0], &[*(publics.A)]),
+            };
+            transcript.commit_bytes("com W".as_bytes(), commitments.W.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                W: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.W)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "attribute_opening".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("W".as_bytes(), publics.W.compress().as_bytes());
+            transcript.commit_bytes("com W".as_bytes(), commitments.W.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod sybil_link {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub z0: Witness<'a>,
+        pub e0: Witness<'a>,
+        pub t: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub Y: &'a RistrettoPoint,
+        pub Hs: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub E0_0: &'a RistrettoPoint,
+        pub E0_1: &'a RistrettoPoint,
+        pub ET_0: &'a RistrettoPoint,
+        pub ET_1: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        E0_0: RistrettoPoint,
+        E0_1: RistrettoPoint,
+        ET_0: RistrettoPoint,
+        ET_1: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        z0: Scalar,
+        e0: Scalar,
+        t: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        z0: Scalar,
+        e0: Scalar,
+        t: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 160;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.z0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.e0.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.t.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "sybil_link".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("Y".as_bytes(), publics.Y.compress().as_bytes());
+            transcript.commit_bytes("Hs".as_bytes(), publics.Hs.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("E0_0".as_bytes(), publics.E0_0.compress().as_bytes());
+            transcript.commit_bytes("E0_1".as_bytes(), publics.E0_1.compress().as_bytes());
+            transcript.commit_bytes("ET_0".as_bytes(), publics.ET_0.compress().as_bytes());
+            transcript.commit_bytes("ET_1".as_bytes(), publics.ET_1.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("e0".as_bytes(), secrets.e0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("t".as_bytes(), secrets.t.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+                e0: Scalar::random(&mut transcript_rng),
+                t: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                E0_0: RistrettoPoint::multiscalar_mul(&[rand.e0], &[*(publics.B)]),
+                E0_1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.e0],
+                    &[*(publics.B), *(publics.D)],
+                ),
+                ET_0: RistrettoPoint::multiscalar_mul(&[rand.t], &[*(publics.B)]),
+                ET_1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.t],
+                    &[*(publics.Hs), *(publics.Y)],
+                ),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com E0_0".as_bytes(), commitments.E0_0.compress().as_bytes());
+            transcript.commit_bytes("com E0_1".as_bytes(), commitments.E0_1.compress().as_bytes());
+            transcript.commit_bytes("com ET_0".as_bytes(), commitments.ET_0.compress().as_bytes());
+            transcript.commit_bytes("com ET_1".as_bytes(), commitments.ET_1.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+                e0: &(&challenge * secrets.e0.scalar()) + &rand.e0,
+                t: &(&challenge * secrets.t.scalar()) + &rand.t,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                E0_0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.e0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.E0_0)),
+                ),
+                E0_1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.e0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.E0_1)),
+                ),
+                ET_0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.t]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.ET_0)),
+                ),
+                ET_1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.t]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.Hs), *(publics.Y)]).into_iter().chain(iter::once(publics.ET_1)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "sybil_link".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("Y".as_bytes(), publics.Y.compress().as_bytes());
+            transcript.commit_bytes("Hs".as_bytes(), publics.Hs.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("E0_0".as_bytes(), publics.E0_0.compress().as_bytes());
+            transcript.commit_bytes("E0_1".as_bytes(), publics.E0_1.compress().as_bytes());
+            transcript.commit_bytes("ET_0".as_bytes(), publics.ET_0.compress().as_bytes());
+            transcript.commit_bytes("ET_1".as_bytes(), publics.ET_1.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com E0_0".as_bytes(), commitments.E0_0.compress().as_bytes());
+            transcript.commit_bytes("com E0_1".as_bytes(), commitments.E0_1.compress().as_bytes());
+            transcript.commit_bytes("com ET_0".as_bytes(), commitments.ET_0.compress().as_bytes());
+            transcript.commit_bytes("com ET_1".as_bytes(), commitments.ET_1.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod batch_dleq {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub k: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub G: &'a RistrettoPoint,
+        pub Y: &'a RistrettoPoint,
+        pub M: &'a RistrettoPoint,
+        pub Z: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Y: RistrettoPoint,
+        Z: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        k: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        k: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 64;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.k.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "batch_dleq".as_bytes());
+            transcript.commit_bytes("G".as_bytes(), publics.G.compress().as_bytes());
+            transcript.commit_bytes("Y".as_bytes(), publics.Y.compress().as_bytes());
+            transcript.commit_bytes("M".as_bytes(), publics.M.compress().as_bytes());
+            transcript.commit_bytes("Z".as_bytes(), publics.Z.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("k".as_bytes(), secrets.k.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                k: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Y: RistrettoPoint::multiscalar_mul(&[rand.k], &[*(publics.G)]),
+                Z: RistrettoPoint::multiscalar_mul(&[rand.k], &[*(publics.M)]),
+            };
+            transcript.commit_bytes("com Y".as_bytes(), commitments.Y.compress().as_bytes());
+            transcript.commit_bytes("com Z".as_bytes(), commitments.Z.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                k: &(&challenge * secrets.k.scalar()) + &rand.k,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Y: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.k]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.G)]).into_iter().chain(iter::once(publics.Y)),
+                ),
+                Z: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.k]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.M)]).into_iter().chain(iter::once(publics.Z)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "batch_dleq".as_bytes());
+            transcript.commit_bytes("G".as_bytes(), publics.G.compress().as_bytes());
+            transcript.commit_bytes("Y".as_bytes(), publics.Y.compress().as_bytes());
+            transcript.commit_bytes("M".as_bytes(), publics.M.compress().as_bytes());
+            transcript.commit_bytes("Z".as_bytes(), publics.Z.compress().as_bytes());
+            transcript.commit_bytes("com Y".as_bytes(), commitments.Y.compress().as_bytes());
+            transcript.commit_bytes("com Z".as_bytes(), commitments.Z.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod scoped_nullifier {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub z0: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub Hn: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub N: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        N: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        z0: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        z0: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 96;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.z0.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "scoped_nullifier".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Hn".as_bytes(), publics.Hn.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("N".as_bytes(), publics.N.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                N: RistrettoPoint::multiscalar_mul(&[rand.m0], &[*(publics.Hn)]),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com N".as_bytes(), commitments.N.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                N: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.Hn)]).into_iter().chain(iter::once(publics.N)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "scoped_nullifier".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Hn".as_bytes(), publics.Hn.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("N".as_bytes(), publics.N.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com N".as_bytes(), commitments.N.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod roster_link {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub z0: Witness<'a>,
+        pub z1: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub P1: &'a RistrettoPoint,
+        pub Cm1: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        Cm1: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        z0: Scalar,
+        z1: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        z0: Scalar,
+        z1: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 128;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.z0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.z1.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "roster_link".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("P1".as_bytes(), publics.P1.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+                z1: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z1],
+                    &[*(publics.P1), *(publics.A)],
+                ),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+                z1: &(&challenge * secrets.z1.scalar()) + &rand.z1,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                Cm1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P1), *(publics.A)]).into_iter().chain(iter::once(publics.Cm1)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "roster_link".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("P1".as_bytes(), publics.P1.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod valid_revocable {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m1: &'a Scalar,
+        pub m2: &'a Scalar,
+        pub z1: Witness<'a>,
+        pub z2: Witness<'a>,
+        pub minus_zQ: &'a Scalar,
+        pub r: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub Z: &'a RistrettoPoint,
+        pub Cm1: &'a RistrettoPoint,
+        pub Cm2: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Vbar: &'a RistrettoPoint,
+        pub Wbar: &'a RistrettoPoint,
+        pub Q: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm1: RistrettoPoint,
+        Cm2: RistrettoPoint,
+        Z: RistrettoPoint,
+        Vbar: RistrettoPoint,
+        Q: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+        r: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+        r: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 224;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m1.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.m2.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.z1.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.z2.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.minus_zQ.as_bytes());
+            bytes[192..224].copy_from_slice(self.responses.r.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "valid_revocable".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("Z".as_bytes(), publics.Z.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Vbar".as_bytes(), publics.Vbar.compress().as_bytes());
+            transcript.commit_bytes("Wbar".as_bytes(), publics.Wbar.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m1".as_bytes(), secrets.m1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m2".as_bytes(), secrets.m2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z2".as_bytes(), secrets.z2.scalar().as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("r".as_bytes(), secrets.r.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m1: Scalar::random(&mut transcript_rng),
+                m2: Scalar::random(&mut transcript_rng),
+                z1: Scalar::random(&mut transcript_rng),
+                z2: Scalar::random(&mut transcript_rng),
+                minus_zQ: Scalar::random(&mut transcript_rng),
+                r: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.z1],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.z2],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Z: RistrettoPoint::multiscalar_mul(
+                    &[rand.z1, rand.z2, rand.minus_zQ],
+                    &[*(publics.X1), *(publics.X2), *(publics.A)],
+                ),
+                Vbar: RistrettoPoint::multiscalar_mul(&[rand.r], &[*(publics.V)]),
+                Q: RistrettoPoint::multiscalar_mul(&[rand.m2], &[*(publics.Wbar)]),
+            };
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com Z".as_bytes(), commitments.Z.compress().as_bytes());
+            transcript.commit_bytes("com Vbar".as_bytes(), commitments.Vbar.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m1: &(&challenge * secrets.m1) + &rand.m1,
+                m2: &(&challenge * secrets.m2) + &rand.m2,
+                z1: &(&challenge * secrets.z1.scalar()) + &rand.z1,
+                z2: &(&challenge * secrets.z2.scalar()) + &rand.z2,
+                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+                r: &(&challenge * secrets.r) + &rand.r,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.z1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm1)),
+                ),
+                Cm2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.z2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm2)),
+                ),
+                Z: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z1, responses.z2, responses.minus_zQ]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1), *(publics.X2), *(publics.A)]).into_iter().chain(iter::once(publics.Z)),
+                ),
+                Vbar: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.r]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.V)]).into_iter().chain(iter::once(publics.Vbar)),
+                ),
+                Q: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.Wbar)]).into_iter().chain(iter::once(publics.Q)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "valid_revocable".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("Z".as_bytes(), publics.Z.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Vbar".as_bytes(), publics.Vbar.compress().as_bytes());
+            transcript.commit_bytes("Wbar".as_bytes(), publics.Wbar.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com Z".as_bytes(), commitments.Z.compress().as_bytes());
+            transcript.commit_bytes("com Vbar".as_bytes(), commitments.Vbar.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod eligibility {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub e0: Witness<'a>,
+        pub r: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub D: &'a RistrettoPoint,
+        pub E0_0: &'a RistrettoPoint,
+        pub E0_1: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Vbar: &'a RistrettoPoint,
+        pub Wbar: &'a RistrettoPoint,
+        pub Q: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        E0_0: RistrettoPoint,
+        E0_1: RistrettoPoint,
+        Q: RistrettoPoint,
+        Vbar: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        e0: Scalar,
+        r: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        e0: Scalar,
+        r: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 128;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.e0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.r.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "eligibility".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E0_0".as_bytes(), publics.E0_0.compress().as_bytes());
+            transcript.commit_bytes("E0_1".as_bytes(), publics.E0_1.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Vbar".as_bytes(), publics.Vbar.compress().as_bytes());
+            transcript.commit_bytes("Wbar".as_bytes(), publics.Wbar.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("e0".as_bytes(), secrets.e0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("r".as_bytes(), secrets.r.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                e0: Scalar::random(&mut transcript_rng),
+                r: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                E0_0: RistrettoPoint::multiscalar_mul(&[rand.e0], &[*(publics.B)]),
+                E0_1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.e0],
+                    &[*(publics.B), *(publics.D)],
+                ),
+                Q: RistrettoPoint::multiscalar_mul(&[rand.m0], &[*(publics.Wbar)]),
+                Vbar: RistrettoPoint::multiscalar_mul(&[rand.r], &[*(publics.V)]),
+            };
+            transcript.commit_bytes("com E0_0".as_bytes(), commitments.E0_0.compress().as_bytes());
+            transcript.commit_bytes("com E0_1".as_bytes(), commitments.E0_1.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            transcript.commit_bytes("com Vbar".as_bytes(), commitments.Vbar.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                e0: &(&challenge * secrets.e0.scalar()) + &rand.e0,
+                r: &(&challenge * secrets.r) + &rand.r,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                E0_0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.e0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.E0_0)),
+                ),
+                E0_1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.e0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.D)]).into_iter().chain(iter::once(publics.E0_1)),
+                ),
+                Q: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.Wbar)]).into_iter().chain(iter::once(publics.Q)),
+                ),
+                Vbar: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.r]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.V)]).into_iter().chain(iter::once(publics.Vbar)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "eligibility".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("D".as_bytes(), publics.D.compress().as_bytes());
+            transcript.commit_bytes("E0_0".as_bytes(), publics.E0_0.compress().as_bytes());
+            transcript.commit_bytes("E0_1".as_bytes(), publics.E0_1.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Vbar".as_bytes(), publics.Vbar.compress().as_bytes());
+            transcript.commit_bytes("Wbar".as_bytes(), publics.Wbar.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("com E0_0".as_bytes(), commitments.E0_0.compress().as_bytes());
+            transcript.commit_bytes("com E0_1".as_bytes(), commitments.E0_1.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            transcript.commit_bytes("com Vbar".as_bytes(), commitments.Vbar.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod pass_issuance {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub x0: &'a Scalar,
+        pub x1: &'a Scalar,
+        pub x2: &'a Scalar,
+        pub x3: &'a Scalar,
+        pub x0_tilde: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub M1: &'a RistrettoPoint,
+        pub M2: &'a RistrettoPoint,
+        pub M3: &'a RistrettoPoint,
+        pub Q: &'a RistrettoPoint,
+        pub Cx0: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub X3: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Q: RistrettoPoint,
+        Cx0: RistrettoPoint,
+        X1: RistrettoPoint,
+        X2: RistrettoPoint,
+        X3: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x3: Scalar,
+        x0_tilde: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x3: Scalar,
+        x0_tilde: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 192;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.x0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.x1.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.x2.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.x3.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.x0_tilde.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "pass_issuance".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("M1".as_bytes(), publics.M1.compress().as_bytes());
+            transcript.commit_bytes("M2".as_bytes(), publics.M2.compress().as_bytes());
+            transcript.commit_bytes("M3".as_bytes(), publics.M3.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("X3".as_bytes(), publics.X3.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x2".as_bytes(), secrets.x2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x3".as_bytes(), secrets.x3.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                x0: Scalar::random(&mut transcript_rng),
+                x1: Scalar::random(&mut transcript_rng),
+                x2: Scalar::random(&mut transcript_rng),
+                x3: Scalar::random(&mut transcript_rng),
+                x0_tilde: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Q: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x1, rand.x2, rand.x3],
+                    &[*(publics.P), *(publics.M1), *(publics.M2), *(publics.M3)],
+                ),
+                Cx0: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x0_tilde],
+                    &[*(publics.B), *(publics.A)],
+                ),
+                X1: RistrettoPoint::multiscalar_mul(&[rand.x1], &[*(publics.A)]),
+                X2: RistrettoPoint::multiscalar_mul(&[rand.x2], &[*(publics.A)]),
+                X3: RistrettoPoint::multiscalar_mul(&[rand.x3], &[*(publics.A)]),
+            };
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            transcript.commit_bytes("com X3".as_bytes(), commitments.X3.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                x0: &(&challenge * secrets.x0) + &rand.x0,
+                x1: &(&challenge * secrets.x1) + &rand.x1,
+                x2: &(&challenge * secrets.x2) + &rand.x2,
+                x3: &(&challenge * secrets.x3) + &rand.x3,
+                x0_tilde: &(&challenge * secrets.x0_tilde.scalar()) + &rand.x0_tilde,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Q: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x1, responses.x2, responses.x3]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.M1), *(publics.M2), *(publics.M3)]).into_iter().chain(iter::once(publics.Q)),
+                ),
+                Cx0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x0_tilde]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.Cx0)),
+                ),
+                X1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X1)),
+                ),
+                X2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X2)),
+                ),
+                X3: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x3]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X3)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "pass_issuance".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("M1".as_bytes(), publics.M1.compress().as_bytes());
+            transcript.commit_bytes("M2".as_bytes(), publics.M2.compress().as_bytes());
+            transcript.commit_bytes("M3".as_bytes(), publics.M3.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("X3".as_bytes(), publics.X3.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            transcript.commit_bytes("com X3".as_bytes(), commitments.X3.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod valid_pass {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m1: &'a Scalar,
+        pub m2: &'a Scalar,
+        pub m3: &'a Scalar,
+        pub z1: Witness<'a>,
+        pub z2: Witness<'a>,
+        pub z3: Witness<'a>,
+        pub minus_zQ: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub X3: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Cm1: &'a RistrettoPoint,
+        pub Cm2: &'a RistrettoPoint,
+        pub Cm3: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm1: RistrettoPoint,
+        Cm2: RistrettoPoint,
+        Cm3: RistrettoPoint,
+        V: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m1: Scalar,
+        m2: Scalar,
+        m3: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        z3: Scalar,
+        minus_zQ: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m1: Scalar,
+        m2: Scalar,
+        m3: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        z3: Scalar,
+        minus_zQ: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 256;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m1.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.m2.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.m3.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.z1.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.z2.as_bytes());
+            bytes[192..224].copy_from_slice(self.responses.z3.as_bytes());
+            bytes[224..256].copy_from_slice(self.responses.minus_zQ.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "valid_pass".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("X3".as_bytes(), publics.X3.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("Cm3".as_bytes(), publics.Cm3.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m1".as_bytes(), secrets.m1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m2".as_bytes(), secrets.m2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m3".as_bytes(), secrets.m3.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z2".as_bytes(), secrets.z2.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z3".as_bytes(), secrets.z3.scalar().as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m1: Scalar::random(&mut transcript_rng),
+                m2: Scalar::random(&mut transcript_rng),
+                m3: Scalar::random(&mut transcript_rng),
+                z1: Scalar::random(&mut transcript_rng),
+                z2: Scalar::random(&mut transcript_rng),
+                z3: Scalar::random(&mut transcript_rng),
+                minus_zQ: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.z1],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.z2],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm3: RistrettoPoint::multiscalar_mul(
+                    &[rand.m3, rand.z3],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                V: RistrettoPoint::multiscalar_mul(
+                    &[rand.z1, rand.z2, rand.z3, rand.minus_zQ],
+                    &[*(publics.X1), *(publics.X2), *(publics.X3), *(publics.A)],
+                ),
+            };
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com Cm3".as_bytes(), commitments.Cm3.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m1: &(&challenge * secrets.m1) + &rand.m1,
+                m2: &(&challenge * secrets.m2) + &rand.m2,
+                m3: &(&challenge * secrets.m3) + &rand.m3,
+                z1: &(&challenge * secrets.z1.scalar()) + &rand.z1,
+                z2: &(&challenge * secrets.z2.scalar()) + &rand.z2,
+                z3: &(&challenge * secrets.z3.scalar()) + &rand.z3,
+                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.z1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm1)),
+                ),
+                Cm2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.z2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm2)),
+                ),
+                Cm3: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m3, responses.z3]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm3)),
+                ),
+                V: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z1, responses.z2, responses.z3, responses.minus_zQ]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1), *(publics.X2), *(publics.X3), *(publics.A)]).into_iter().chain(iter::once(publics.V)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "valid_pass".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("X3".as_bytes(), publics.X3.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("Cm3".as_bytes(), publics.Cm3.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com Cm3".as_bytes(), commitments.Cm3.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod device_enrollment {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m1: &'a Scalar,
+        pub m2: &'a Scalar,
+        pub z0: Witness<'a>,
+        pub r1: Witness<'a>,
+        pub r2: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub C1: &'a RistrettoPoint,
+        pub C2: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        C1: RistrettoPoint,
+        C2: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m1: Scalar,
+        m2: Scalar,
+        z0: Scalar,
+        r1: Scalar,
+        r2: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m1: Scalar,
+        m2: Scalar,
+        z0: Scalar,
+        r1: Scalar,
+        r2: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 192;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m1.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.m2.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.z0.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.r1.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.r2.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "device_enrollment".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("C1".as_bytes(), publics.C1.compress().as_bytes());
+            transcript.commit_bytes("C2".as_bytes(), publics.C2.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m1".as_bytes(), secrets.m1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m2".as_bytes(), secrets.m2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("r1".as_bytes(), secrets.r1.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("r2".as_bytes(), secrets.r2.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m1: Scalar::random(&mut transcript_rng),
+                m2: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+                r1: Scalar::random(&mut transcript_rng),
+                r2: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                C1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.r1],
+                    &[*(publics.B), *(publics.A)],
+                ),
+                C2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.r2],
+                    &[*(publics.B), *(publics.A)],
+                ),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com C1".as_bytes(), commitments.C1.compress().as_bytes());
+            transcript.commit_bytes("com C2".as_bytes(), commitments.C2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m1: &(&challenge * secrets.m1) + &rand.m1,
+                m2: &(&challenge * secrets.m2) + &rand.m2,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+                r1: &(&challenge * secrets.r1.scalar()) + &rand.r1,
+                r2: &(&challenge * secrets.r2.scalar()) + &rand.r2,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                C1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.r1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.C1)),
+                ),
+                C2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.r2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.C2)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "device_enrollment".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("C1".as_bytes(), publics.C1.compress().as_bytes());
+            transcript.commit_bytes("C2".as_bytes(), publics.C2.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com C1".as_bytes(), commitments.C1.compress().as_bytes());
+            transcript.commit_bytes("com C2".as_bytes(), commitments.C2.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod device_issuance {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub x0: &'a Scalar,
+        pub x1: &'a Scalar,
+        pub x2: &'a Scalar,
+        pub x0_tilde: Witness<'a>,
+        pub b: Witness<'a>,
+        pub t1: Witness<'a>,
+        pub t2: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub T1_0: &'a RistrettoPoint,
+        pub T1_1: &'a RistrettoPoint,
+        pub T2_0: &'a RistrettoPoint,
+        pub T2_1: &'a RistrettoPoint,
+        pub Cx0: &'a RistrettoPoint,
+        pub C1: &'a RistrettoPoint,
+        pub C2: &'a RistrettoPoint,
+        pub Q: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cx0: RistrettoPoint,
+        X1: RistrettoPoint,
+        X2: RistrettoPoint,
+        P: RistrettoPoint,
+        T1_0: RistrettoPoint,
+        T1_1: RistrettoPoint,
+        T2_0: RistrettoPoint,
+        T2_1: RistrettoPoint,
+        Q: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x0_tilde: Scalar,
+        b: Scalar,
+        t1: Scalar,
+        t2: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        x0: Scalar,
+        x1: Scalar,
+        x2: Scalar,
+        x0_tilde: Scalar,
+        b: Scalar,
+        t1: Scalar,
+        t2: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 256;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.x0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.x1.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.x2.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.x0_tilde.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.b.as_bytes());
+            bytes[192..224].copy_from_slice(self.responses.t1.as_bytes());
+            bytes[224..256].copy_from_slice(self.responses.t2.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "device_issuance".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("T1_0".as_bytes(), publics.T1_0.compress().as_bytes());
+            transcript.commit_bytes("T1_1".as_bytes(), publics.T1_1.compress().as_bytes());
+            transcript.commit_bytes("T2_0".as_bytes(), publics.T2_0.compress().as_bytes());
+            transcript.commit_bytes("T2_1".as_bytes(), publics.T2_1.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("C1".as_bytes(), publics.C1.compress().as_bytes());
+            transcript.commit_bytes("C2".as_bytes(), publics.C2.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("x0".as_bytes(), secrets.x0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x1".as_bytes(), secrets.x1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("x2".as_bytes(), secrets.x2.as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("x0_tilde".as_bytes(), secrets.x0_tilde.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("b".as_bytes(), secrets.b.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("t1".as_bytes(), secrets.t1.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("t2".as_bytes(), secrets.t2.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                x0: Scalar::random(&mut transcript_rng),
+                x1: Scalar::random(&mut transcript_rng),
+                x2: Scalar::random(&mut transcript_rng),
+                x0_tilde: Scalar::random(&mut transcript_rng),
+                b: Scalar::random(&mut transcript_rng),
+                t1: Scalar::random(&mut transcript_rng),
+                t2: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cx0: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.x0_tilde],
+                    &[*(publics.B), *(publics.A)],
+                ),
+                X1: RistrettoPoint::multiscalar_mul(&[rand.x1], &[*(publics.A)]),
+                X2: RistrettoPoint::multiscalar_mul(&[rand.x2], &[*(publics.A)]),
+                P: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.B)]),
+                T1_0: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.X1)]),
+                T1_1: RistrettoPoint::multiscalar_mul(&[rand.t1], &[*(publics.A)]),
+                T2_0: RistrettoPoint::multiscalar_mul(&[rand.b], &[*(publics.X2)]),
+                T2_1: RistrettoPoint::multiscalar_mul(&[rand.t2], &[*(publics.A)]),
+                Q: RistrettoPoint::multiscalar_mul(
+                    &[rand.x0, rand.t1, rand.t2],
+                    &[*(publics.P), *(publics.C1), *(publics.C2)],
+                ),
+            };
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
+            transcript.commit_bytes("com T1_0".as_bytes(), commitments.T1_0.compress().as_bytes());
+            transcript.commit_bytes("com T1_1".as_bytes(), commitments.T1_1.compress().as_bytes());
+            transcript.commit_bytes("com T2_0".as_bytes(), commitments.T2_0.compress().as_bytes());
+            transcript.commit_bytes("com T2_1".as_bytes(), commitments.T2_1.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                x0: &(&challenge * secrets.x0) + &rand.x0,
+                x1: &(&challenge * secrets.x1) + &rand.x1,
+                x2: &(&challenge * secrets.x2) + &rand.x2,
+                x0_tilde: &(&challenge * secrets.x0_tilde.scalar()) + &rand.x0_tilde,
+                b: &(&challenge * secrets.b.scalar()) + &rand.b,
+                t1: &(&challenge * secrets.t1.scalar()) + &rand.t1,
+                t2: &(&challenge * secrets.t2.scalar()) + &rand.t2,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cx0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.x0_tilde]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.A)]).into_iter().chain(iter::once(publics.Cx0)),
+                ),
+                X1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X1)),
+                ),
+                X2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X2)),
+                ),
+                P: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.P)),
+                ),
+                T1_0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1)]).into_iter().chain(iter::once(publics.T1_0)),
+                ),
+                T1_1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.t1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.T1_1)),
+                ),
+                T2_0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.b]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X2)]).into_iter().chain(iter::once(publics.T2_0)),
+                ),
+                T2_1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.t2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.T2_1)),
+                ),
+                Q: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.x0, responses.t1, responses.t2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.C1), *(publics.C2)]).into_iter().chain(iter::once(publics.Q)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "device_issuance".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("T1_0".as_bytes(), publics.T1_0.compress().as_bytes());
+            transcript.commit_bytes("T1_1".as_bytes(), publics.T1_1.compress().as_bytes());
+            transcript.commit_bytes("T2_0".as_bytes(), publics.T2_0.compress().as_bytes());
+            transcript.commit_bytes("T2_1".as_bytes(), publics.T2_1.compress().as_bytes());
+            transcript.commit_bytes("Cx0".as_bytes(), publics.Cx0.compress().as_bytes());
+            transcript.commit_bytes("C1".as_bytes(), publics.C1.compress().as_bytes());
+            transcript.commit_bytes("C2".as_bytes(), publics.C2.compress().as_bytes());
+            transcript.commit_bytes("Q".as_bytes(), publics.Q.compress().as_bytes());
+            transcript.commit_bytes("com Cx0".as_bytes(), commitments.Cx0.compress().as_bytes());
+            transcript.commit_bytes("com X1".as_bytes(), commitments.X1.compress().as_bytes());
+            transcript.commit_bytes("com X2".as_bytes(), commitments.X2.compress().as_bytes());
+            transcript.commit_bytes("com P".as_bytes(), commitments.P.compress().as_bytes());
+            transcript.commit_bytes("com T1_0".as_bytes(), commitments.T1_0.compress().as_bytes());
+            transcript.commit_bytes("com T1_1".as_bytes(), commitments.T1_1.compress().as_bytes());
+            transcript.commit_bytes("com T2_0".as_bytes(), commitments.T2_0.compress().as_bytes());
+            transcript.commit_bytes("com T2_1".as_bytes(), commitments.T2_1.compress().as_bytes());
+            transcript.commit_bytes("com Q".as_bytes(), commitments.Q.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod valid_device {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m1: &'a Scalar,
+        pub m2: &'a Scalar,
+        pub z1: Witness<'a>,
+        pub z2: Witness<'a>,
+        pub minus_zQ: &'a Scalar,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub X1: &'a RistrettoPoint,
+        pub X2: &'a RistrettoPoint,
+        pub V: &'a RistrettoPoint,
+        pub Cm1: &'a RistrettoPoint,
+        pub Cm2: &'a RistrettoPoint,
+        pub H: &'a RistrettoPoint,
+        pub N: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm1: RistrettoPoint,
+        Cm2: RistrettoPoint,
+        V: RistrettoPoint,
+        N: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m1: Scalar,
+        m2: Scalar,
+        z1: Scalar,
+        z2: Scalar,
+        minus_zQ: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 192;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m1.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.m2.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.z1.as_bytes());
+            bytes[128..160].copy_from_slice(self.responses.z2.as_bytes());
+            bytes[160..192].copy_from_slice(self.responses.minus_zQ.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "valid_device".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("H".as_bytes(), publics.H.compress().as_bytes());
+            transcript.commit_bytes("N".as_bytes(), publics.N.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m1".as_bytes(), secrets.m1.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("m2".as_bytes(), secrets.m2.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z1".as_bytes(), secrets.z1.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z2".as_bytes(), secrets.z2.scalar().as_bytes());
+            let rng_ctor =
+                rng_ctor.commit_witness_bytes("minus_zQ".as_bytes(), secrets.minus_zQ.as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m1: Scalar::random(&mut transcript_rng),
+                m2: Scalar::random(&mut transcript_rng),
+                z1: Scalar::random(&mut transcript_rng),
+                z2: Scalar::random(&mut transcript_rng),
+                minus_zQ: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::multiscalar_mul(
+                    &[rand.m1, rand.z1],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                Cm2: RistrettoPoint::multiscalar_mul(
+                    &[rand.m2, rand.z2],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                V: RistrettoPoint::multiscalar_mul(
+                    &[rand.z1, rand.z2, rand.minus_zQ],
+                    &[*(publics.X1), *(publics.X2), *(publics.A)],
+                ),
+                N: RistrettoPoint::multiscalar_mul(&[rand.m1], &[*(publics.H)]),
+            };
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            transcript.commit_bytes("com N".as_bytes(), commitments.N.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m1: &(&challenge * secrets.m1) + &rand.m1,
+                m2: &(&challenge * secrets.m2) + &rand.m2,
+                z1: &(&challenge * secrets.z1.scalar()) + &rand.z1,
+                z2: &(&challenge * secrets.z2.scalar()) + &rand.z2,
+                minus_zQ: &(&challenge * secrets.minus_zQ) + &rand.minus_zQ,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1, responses.z1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm1)),
+                ),
+                Cm2: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m2, responses.z2]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm2)),
+                ),
+                V: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.z1, responses.z2, responses.minus_zQ]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.X1), *(publics.X2), *(publics.A)]).into_iter().chain(iter::once(publics.V)),
+                ),
+                N: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m1]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.H)]).into_iter().chain(iter::once(publics.N)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "valid_device".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("X1".as_bytes(), publics.X1.compress().as_bytes());
+            transcript.commit_bytes("X2".as_bytes(), publics.X2.compress().as_bytes());
+            transcript.commit_bytes("V".as_bytes(), publics.V.compress().as_bytes());
+            transcript.commit_bytes("Cm1".as_bytes(), publics.Cm1.compress().as_bytes());
+            transcript.commit_bytes("Cm2".as_bytes(), publics.Cm2.compress().as_bytes());
+            transcript.commit_bytes("H".as_bytes(), publics.H.compress().as_bytes());
+            transcript.commit_bytes("N".as_bytes(), publics.N.compress().as_bytes());
+            transcript.commit_bytes("com Cm1".as_bytes(), commitments.Cm1.compress().as_bytes());
+            transcript.commit_bytes("com Cm2".as_bytes(), commitments.Cm2.compress().as_bytes());
+            transcript.commit_bytes("com V".as_bytes(), commitments.V.compress().as_bytes());
+            transcript.commit_bytes("com N".as_bytes(), commitments.N.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod correct_share {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub s: &'a Scalar,
+        pub r: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub B: &'a RistrettoPoint,
+        pub A: &'a RistrettoPoint,
+        pub Y: &'a RistrettoPoint,
+        pub X: &'a RistrettoPoint,
+        pub E0: &'a RistrettoPoint,
+        pub E1: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        X: RistrettoPoint,
+        E0: RistrettoPoint,
+        E1: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        s: Scalar,
+        r: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        s: Scalar,
+        r: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 96;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.s.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.r.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "correct_share".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("Y".as_bytes(), publics.Y.compress().as_bytes());
+            transcript.commit_bytes("X".as_bytes(), publics.X.compress().as_bytes());
+            transcript.commit_bytes("E0".as_bytes(), publics.E0.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("s".as_bytes(), secrets.s.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("r".as_bytes(), secrets.r.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                s: Scalar::random(&mut transcript_rng),
+                r: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                X: RistrettoPoint::multiscalar_mul(&[rand.s], &[*(publics.A)]),
+                E0: RistrettoPoint::multiscalar_mul(&[rand.r], &[*(publics.B)]),
+                E1: RistrettoPoint::multiscalar_mul(
+                    &[rand.s, rand.r],
+                    &[*(publics.B), *(publics.Y)],
+                ),
+            };
+            transcript.commit_bytes("com X".as_bytes(), commitments.X.compress().as_bytes());
+            transcript.commit_bytes("com E0".as_bytes(), commitments.E0.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                s: &(&challenge * secrets.s) + &rand.s,
+                r: &(&challenge * secrets.r.scalar()) + &rand.r,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                X: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.s]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.A)]).into_iter().chain(iter::once(publics.X)),
+                ),
+                E0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.r]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B)]).into_iter().chain(iter::once(publics.E0)),
+                ),
+                E1: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.s, responses.r]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.B), *(publics.Y)]).into_iter().chain(iter::once(publics.E1)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "correct_share".as_bytes());
+            transcript.commit_bytes("B".as_bytes(), publics.B.compress().as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("Y".as_bytes(), publics.Y.compress().as_bytes());
+            transcript.commit_bytes("X".as_bytes(), publics.X.compress().as_bytes());
+            transcript.commit_bytes("E0".as_bytes(), publics.E0.compress().as_bytes());
+            transcript.commit_bytes("E1".as_bytes(), publics.E1.compress().as_bytes());
+            transcript.commit_bytes("com X".as_bytes(), commitments.X.compress().as_bytes());
+            transcript.commit_bytes("com E0".as_bytes(), commitments.E0.compress().as_bytes());
+            transcript.commit_bytes("com E1".as_bytes(), commitments.E1.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod commitment_export {
+    use super::*;
+
+    #[derive(Copy, Clone)]
+    pub struct Secrets<'a> {
+        pub m0: &'a Scalar,
+        pub z0: Witness<'a>,
+        pub r: Witness<'a>,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct Publics<'a> {
+        pub A: &'a RistrettoPoint,
+        pub P: &'a RistrettoPoint,
+        pub G: &'a RistrettoPoint,
+        pub H: &'a RistrettoPoint,
+        pub Cm0: &'a RistrettoPoint,
+        pub C: &'a RistrettoPoint,
+    }
+
+    struct Commitments {
+        Cm0: RistrettoPoint,
+        C: RistrettoPoint,
+    }
+
+    struct Randomnesses {
+        m0: Scalar,
+        z0: Scalar,
+        r: Scalar,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    struct Responses {
+        m0: Scalar,
+        z0: Scalar,
+        r: Scalar,
+    }
+
+    /// The size of an encoded `Proof`.
+    pub const SIZEOF_PROOF: usize = 128;
+
+    #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+    pub struct Proof {
+        challenge: Scalar,
+        responses: Responses,
+    }
+
+    impl Proof {
+        /// Encode this `Proof` as the same bytes as its `bincode`
+        /// serialisation.
+        pub fn to_bytes(&self) -> [u8; SIZEOF_PROOF] {
+            let mut bytes: [u8; SIZEOF_PROOF] = [0u8; SIZEOF_PROOF];
+
+            bytes[00..32].copy_from_slice(self.challenge.as_bytes());
+            bytes[32..64].copy_from_slice(self.responses.m0.as_bytes());
+            bytes[64..96].copy_from_slice(self.responses.z0.as_bytes());
+            bytes[96..128].copy_from_slice(self.responses.r.as_bytes());
+            bytes
+        }
+
+        /// Create a `Proof` from the given `Publics` and `Secrets`.
+        #[allow(dead_code)]
+        pub fn create<T: ProofTranscript>(transcript: &mut T, publics: Publics, secrets: Secrets) -> Proof {
+            transcript.commit_bytes(b"domain-sep", "commitment_export".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("G".as_bytes(), publics.G.compress().as_bytes());
+            transcript.commit_bytes("H".as_bytes(), publics.H.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("C".as_bytes(), publics.C.compress().as_bytes());
+            let rng_ctor = transcript.fork_transcript();
+            let rng_ctor = rng_ctor.commit_witness_bytes("m0".as_bytes(), secrets.m0.as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("z0".as_bytes(), secrets.z0.scalar().as_bytes());
+            let rng_ctor = rng_ctor.commit_witness_bytes("r".as_bytes(), secrets.r.scalar().as_bytes());
+            let mut transcript_rng = rng_ctor.reseed_from_rng(&mut proof_rng());
+            let rand = Randomnesses {
+                m0: Scalar::random(&mut transcript_rng),
+                z0: Scalar::random(&mut transcript_rng),
+                r: Scalar::random(&mut transcript_rng),
+            };
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.z0],
+                    &[*(publics.P), *(publics.A)],
+                ),
+                C: RistrettoPoint::multiscalar_mul(
+                    &[rand.m0, rand.r],
+                    &[*(publics.G), *(publics.H)],
+                ),
+            };
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com C".as_bytes(), commitments.C.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            let responses = Responses {
+                m0: &(&challenge * secrets.m0) + &rand.m0,
+                z0: &(&challenge * secrets.z0.scalar()) + &rand.z0,
+                r: &(&challenge * secrets.r.scalar()) + &rand.r,
+            };
+            Proof { challenge: challenge, responses: responses }
+        }
+
+        /// Verify the `Proof` using the public parameters `Publics`.
+        #[allow(dead_code)]
+        pub fn verify<T: ProofTranscript>(&self, transcript: &mut T, publics: Publics) -> Result<(), ()> {
+            let responses = &self.responses;
+            let minus_c = -&self.challenge;
+            let commitments = Commitments {
+                Cm0: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.z0]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.P), *(publics.A)]).into_iter().chain(iter::once(publics.Cm0)),
+                ),
+                C: RistrettoPoint::vartime_multiscalar_mul(
+                    (&[responses.m0, responses.r]).into_iter().chain(iter::once(&(minus_c))),
+                    (&[*(publics.G), *(publics.H)]).into_iter().chain(iter::once(publics.C)),
+                ),
+            };
+            transcript.commit_bytes(b"domain-sep", "commitment_export".as_bytes());
+            transcript.commit_bytes("A".as_bytes(), publics.A.compress().as_bytes());
+            transcript.commit_bytes("P".as_bytes(), publics.P.compress().as_bytes());
+            transcript.commit_bytes("G".as_bytes(), publics.G.compress().as_bytes());
+            transcript.commit_bytes("H".as_bytes(), publics.H.compress().as_bytes());
+            transcript.commit_bytes("Cm0".as_bytes(), publics.Cm0.compress().as_bytes());
+            transcript.commit_bytes("C".as_bytes(), publics.C.compress().as_bytes());
+            transcript.commit_bytes("com Cm0".as_bytes(), commitments.Cm0.compress().as_bytes());
+            transcript.commit_bytes("com C".as_bytes(), commitments.C.compress().as_bytes());
+            let challenge = {
+                let mut bytes = [0; 64];
+                transcript.challenge_bytes(b"chal", &mut bytes);
+                Scalar::from_bytes_mod_order_wide(&bytes)
+            };
+            if challenge == self.challenge {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+}
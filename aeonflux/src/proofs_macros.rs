@@ -64,41 +64,74 @@ create_nipk!(issuance_revealed,
 ///
 /// * `B` the basepoint,
 /// * `D` the `User`'s public elGamal encryption key,
-/// * `encrypted_attribute_0`...`encrypted_attribute_n` the encrypted attributes,
+/// * `encrypted_attribute_0_0`...`encrypted_attribute_n_0` the commitment
+///   parts of the encrypted attributes,
+/// * `encrypted_attribute_0_1`...`encrypted_attribute_n_1` the encryption
+///   parts of the encrypted attributes,
 ///
 /// # Proof Statements
 ///
-// DOCDOC
+/// The user knows the secret key for `D`, and each encrypted attribute is an
+/// elGamal encryption to `D` of the basepoint multiplied by a known scalar.
 create_nipk!(attributes_blinded,
-             (d, e0, m0, nonce),
-             (B, A, D, encrypted_attribute_0_0, encrypted_attribute_0_1)
+             (d, e0, m0),
+             (B, D, encrypted_attribute_0_0, encrypted_attribute_0_1)
              :
              D = (B * d),
              encrypted_attribute_0_0 = (B * e0),
              encrypted_attribute_0_1 = (B * m0 + D * e0)
 );
 
-// XXX The T0_0 and T0_1, etc., should be the same points but we need to pass
-//     them in twice because the zkp macro won't let us pass in AND proofs
-//     w.r.t. the same value, e.g.
+/// A NIPK showing correct blinded issuance.
+///
+/// # Inputs
+///
+/// Secrets:
+///
+/// * `x0, x1` are the `Issuer`'s private key material.
+/// * `x0_tilde` is a blinding factor for the secret key.
+/// * `b` is the discrete log of the aMAC nonce, `P`.
+/// * `s` is the randomness used to encrypt the aMAC to the user.
+/// * `t1` is `b * x1`.
+///
+/// Publics:
+///
+/// * `B` and `A` are generators of the group, where `A` is chosen orthogonally
+///   such that `log_B(A)` is intractible.
+/// * `X1` is the issuer's public key material.
+/// * `D` is the `User`'s public elGamal encryption key.
+/// * `P` is the aMAC nonce.
+/// * `T1_0` and `T1_1` are both the auxiliary commitment `T1 = b * X1`.
+/// * `Cx0` is a Pedersen commitment to the secret key `x0`.
+/// * `EQ_commitment` and `EQ_encryption` are the elGamal encryption to `D` of
+///   the aMAC, `Q`.
+/// * `encrypted_attribute_0_0` and `encrypted_attribute_0_1` are the user's
+///   elGamal encryption of their hidden attribute.
+///
+/// # Proof Statements
+///
+/// The encryption of `Q` decrypts to `(x0 + x1 * m0) * P`, where `m0` is the
+/// hidden attribute, since:
+///
+///     EQ_encryption - d * EQ_commitment = t1 * m0 * B + x0 * P
+///                                       = (x0 + x1 * m0) * b * B
 //
-//     T0 = (X0 * b), T0 = (h * t0),
-//     T1 = (X1 * b), T1 = (h * t1),
+// XXX The T1_0 and T1_1 are the same point but we need to pass them in twice
+//     because the zkp macro won't let us pass in AND proofs w.r.t. the same
+//     value, e.g. T1 = (X1 * b), T1 = (A * t1).
 create_nipk!(issuance_blinded,
-             (x0_tilde, x0, x1, s, b, t0),
-             (B, A, X0, X1, D, P, T0_0, T0_1,
+             (x0, x1, x0_tilde, b, s, t1),
+             (B, A, X1, D, P, T1_0, T1_1, Cx0,
               EQ_commitment, EQ_encryption,
               encrypted_attribute_0_0, encrypted_attribute_0_1)
              :
-             X0 = (B * x0 + A * x0_tilde),
+             Cx0 = (B * x0 + A * x0_tilde),
              X1 = (A * x1),
-             P  = (B * b),
-             T0_0 = (X0 * b), T0_1 = (A * t0), // XXX the zkp crate doesn't like this, hack around it
-             EQ_commitment = (B * s + encrypted_attribute_0_0 * t0),
-             EQ_encryption = (D * s + encrypted_attribute_0_1 * t0
-// This part is only if there were revealed attributes:
-//                              + x0 * P + x1m1 * P + x2m2 * P
-                              )
+             P = (B * b),
+             T1_0 = (X1 * b),
+             T1_1 = (A * t1),
+             EQ_commitment = (B * s + encrypted_attribute_0_0 * t1),
+             EQ_encryption = (D * s + encrypted_attribute_0_1 * t1 + P * x0)
 );
 
 create_nipk!(valid_credential,
@@ -119,3 +152,182 @@ create_nipk!(committed_values_equal,
              Cm0 = (P * m0 + A * z0),
              Cm1 = (A * m0 + B * z1)
 );
+
+/// Prove knowledge of the blinding factor, `z0`, of a publicly known value
+/// within an attribute commitment, i.e. that `W = Cm0 - P * m0` for some `m0`
+/// which the verifier has computed themselves.
+create_nipk!(attribute_opening,
+             (z0),
+             (A, W)
+             :
+             W = (A * z0)
+);
+
+/// Prove that the hidden attribute in a presentation of one credential, `Cm0`,
+/// is the same as the attribute encrypted in a blind request for another,
+/// `(E0_0, E0_1)`, and that `(ET_0, ET_1)` is an elGamal encryption, to the
+/// tag key `Y`, of the attribute's scope-specific point `Hs * m0`.
+create_nipk!(sybil_link,
+             (m0, z0, e0, t),
+             (B, A, P, D, Y, Hs, Cm0, E0_0, E0_1, ET_0, ET_1)
+             :
+             Cm0 = (P * m0 + A * z0),
+             E0_0 = (B * e0),
+             E0_1 = (B * m0 + D * e0),
+             ET_0 = (B * t),
+             ET_1 = (Hs * m0 + Y * t)
+);
+
+/// Prove that the discrete log of `Y` with respect to `G` is equal to that
+/// of `Z` with respect to `M`, where `M` and `Z` are random linear
+/// combinations of a batch of blinded and signed tokens.
+create_nipk!(batch_dleq,
+             (k),
+             (G, Y, M, Z)
+             :
+             Y = (G * k),
+             Z = (M * k)
+);
+
+/// Prove that `N` is the nullifier for the hidden attribute in a credential
+/// presentation, `Cm0`, with respect to the basepoint `Hn`.
+create_nipk!(scoped_nullifier,
+             (m0, z0),
+             (A, P, Hn, Cm0, N)
+             :
+             Cm0 = (P * m0 + A * z0),
+             N = (Hn * m0)
+);
+
+/// Prove that the user's blinded attribute in a credential request,
+/// `(E0_0, E0_1)`, is an element of a keyed-verification accumulator with
+/// value `V`, for which the accumulator's keeper issued a witness, where `Q`
+/// is computed by the verifier as `Vbar - alpha * Wbar`.
+create_nipk!(eligibility,
+             (m0, e0, r),
+             (B, D, E0_0, E0_1, V, Vbar, Wbar, Q)
+             :
+             E0_0 = (B * e0),
+             E0_1 = (B * m0 + D * e0),
+             Q = (Wbar * m0),
+             Vbar = (V * r)
+);
+
+/// A NIPK showing correct issuance of a three-attribute access pass, where
+/// `Mi = P * mi` for each of the pass's revealed attributes `mi`.
+create_nipk!(pass_issuance,
+             (x0, x1, x2, x3, x0_tilde),
+             (B, A, P, M1, M2, M3, Q, Cx0, X1, X2, X3)
+             :
+             Q = (P * x0 + M1 * x1 + M2 * x2 + M3 * x3),
+             Cx0 = (B * x0 + A * x0_tilde),
+             X1 = (A * x1),
+             X2 = (A * x2),
+             X3 = (A * x3)
+);
+
+/// A NIPK showing knowledge of a valid three-attribute access pass, with
+/// all of its attributes hidden in the commitments `Cm1`, `Cm2`, and `Cm3`.
+create_nipk!(valid_pass,
+             (m1, m2, m3, z1, z2, z3, minus_zQ),
+             (A, P, X1, X2, X3, V, Cm1, Cm2, Cm3)
+             :
+             Cm1 = (P * m1 + A * z1),
+             Cm2 = (P * m2 + A * z2),
+             Cm3 = (P * m3 + A * z3),
+             V = (X1 * z1 + X2 * z2 + X3 * z3 + A * minus_zQ)
+);
+
+/// A NIPK showing that the commitments `C1` and `C2` in a device enrollment
+/// open to the identity attribute `m1`, which is also committed to in the
+/// presentation `(P, Cm0)` of the holder's credential, and to a device key
+/// `m2`.
+create_nipk!(device_enrollment,
+             (m1, m2, z0, r1, r2),
+             (B, A, P, Cm0, C1, C2)
+             :
+             Cm0 = (P * m1 + A * z0),
+             C1 = (B * m1 + A * r1),
+             C2 = (B * m2 + A * r2)
+);
+
+/// A NIPK showing correct issuance of a device credential over the
+/// committed attributes `C1` and `C2`, where `T1 = X1 * b = A * t1` and
+/// `T2 = X2 * b = A * t2` let the holder remove the commitments' blinding.
+create_nipk!(device_issuance,
+             (x0, x1, x2, x0_tilde, b, t1, t2),
+             (B, A, X1, X2, P, T1_0, T1_1, T2_0, T2_1, Cx0, C1, C2, Q)
+             :
+             Cx0 = (B * x0 + A * x0_tilde),
+             X1 = (A * x1),
+             X2 = (A * x2),
+             P = (B * b),
+             T1_0 = (X1 * b),
+             T1_1 = (A * t1),
+             T2_0 = (X2 * b),
+             T2_1 = (A * t2),
+             Q = (P * x0 + C1 * t1 + C2 * t2)
+);
+
+/// A NIPK showing knowledge of a valid device credential, with its identity
+/// and device key hidden in `Cm1` and `Cm2`, and that `N` is the identity's
+/// nullifier for the scope hashed to `H`.
+create_nipk!(valid_device,
+             (m1, m2, z1, z2, minus_zQ),
+             (A, P, X1, X2, V, Cm1, Cm2, H, N)
+             :
+             Cm1 = (P * m1 + A * z1),
+             Cm2 = (P * m2 + A * z2),
+             V = (X1 * z1 + X2 * z2 + A * minus_zQ),
+             N = (H * m1)
+);
+
+/// A NIPK showing that `(E0, E1)` is an elGamal encryption, to the guardian
+/// key `Y`, of the share `s` whose commitment `X` is fixed by a sharing's
+/// polynomial.
+create_nipk!(correct_share,
+             (s, r),
+             (B, A, Y, X, E0, E1)
+             :
+             X = (A * s),
+             E0 = (B * r),
+             E1 = (B * s + Y * r)
+);
+
+/// A NIPK showing that `C` is a Pedersen commitment, under the caller-chosen
+/// generators `G` and `H`, to the hidden attribute `m0` committed to in the
+/// presentation `(P, Cm0)` of a credential.
+create_nipk!(commitment_export,
+             (m0, z0, r),
+             (A, P, G, H, Cm0, C)
+             :
+             Cm0 = (P * m0 + A * z0),
+             C = (G * m0 + H * r)
+);
+
+/// A NIPK showing knowledge of a valid revocable credential, with its identity
+/// and revocation handle hidden in `Cm1` and `Cm2`, and that the handle is in
+/// the accumulator with value `V`, i.e. that `Vbar = V * r` and
+/// `Q = Vbar - Wbar * alpha = Wbar * m2` for the rerandomised witness `Wbar`.
+create_nipk!(valid_revocable,
+             (m1, m2, z1, z2, minus_zQ, r),
+             (A, P, X1, X2, Z, Cm1, Cm2, V, Vbar, Wbar, Q)
+             :
+             Cm1 = (P * m1 + A * z1),
+             Cm2 = (P * m2 + A * z2),
+             Z = (X1 * z1 + X2 * z2 + A * minus_zQ),
+             Vbar = (V * r),
+             Q = (Wbar * m2)
+);
+
+/// A NIPK showing that the hidden attribute `m0` of a credential presentation
+/// `(P, Cm0)` is the identity committed to in a revocable credential's
+/// presentation `(P1, Cm1)`, so that a roster proof is for the presented
+/// credential.
+create_nipk!(roster_link,
+             (m0, z0, z1),
+             (A, P, Cm0, P1, Cm1)
+             :
+             Cm0 = (P * m0 + A * z0),
+             Cm1 = (P1 * m0 + A * z1)
+);
@@ -0,0 +1,516 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Publicly verifiable anonymous tokens, from blind Schnorr signatures.
+//!
+//! The tokens in `tokens` and the credentials in `issuer` may only be
+//! verified with the issuer's secret key.  A gate operated by a third party,
+//! who must not hold that key, may instead accept the tokens here, which
+//! anyone may verify against the issuer's public key.  They are bearer
+//! tokens: unlike `tokens::TokenRedemption`, a redeemed token is not bound to
+//! any request, and so must only be sent over a confidential channel.
+//!
+//! # Protocol
+//!
+//! The issuer has a secret key \\( x \\) and public key \\( X = x \cdot B \\).
+//! A token is a Schnorr signature \\( (R', s') \\) on a random preimage
+//! \\( t \\), with \\( s' \cdot B = R' + H(X, R', t) \cdot X \\).  It is
+//! issued blindly, as in the clause blind Schnorr scheme of Fuchsbauer,
+//! Plouviez, and Seurin, which runs two blind Schnorr sessions of which the
+//! issuer completes only one at random.  Plain blind Schnorr signatures may
+//! be forged by a client running many sessions concurrently (the "ROS"
+//! attack); the issuer's random choice defeats it.
+//!
+//! 1. The issuer chooses \\( r_0, r_1 \\) and sends a `TokenCommitment` to
+//!    \\( R_i = r_i \cdot B \\).
+//! 2. For each \\( i \\), the client chooses blinding factors
+//!    \\( \alpha_i, \beta_i \\), computes
+//!    \\( R'_i = R_i + \alpha_i \cdot B + \beta_i \cdot X \\) and
+//!    \\( c'_i = H(X, R'_i, t) \\), and sends a `TokenChallenge` of
+//!    \\( c_i = c'_i + \beta_i \\).
+//! 3. The issuer chooses a bit \\( b \\) and answers with only
+//!    \\( s = r_b + c_b \cdot x \\).
+//! 4. The client checks \\( s \cdot B = R_b + c_b \cdot X \\), and unblinds
+//!    the token \\( (R'_b, s + \alpha_b) \\).
+//!
+//! Each `SigningSession` is consumed by its answer, so that the issuer can
+//! never answer both clauses of a session.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use errors::Error;
+use errors::TokenError;
+use hashing::Hasher;
+use secret::Secret;
+use tokens::SIZEOF_TOKEN_PREIMAGE;
+use tokens::SpentTokens;
+use tokens::TokenPreimage;
+
+pub const SIZEOF_PUBLIC_TOKEN_KEYPAIR: usize = 64;
+pub const SIZEOF_TOKEN_COMMITMENT: usize = 64;
+pub const SIZEOF_TOKEN_CHALLENGE: usize = 64;
+pub const SIZEOF_TOKEN_SIGNATURE_RESPONSE: usize = 33;
+pub const SIZEOF_PUBLIC_TOKEN: usize = SIZEOF_TOKEN_PREIMAGE + 64;
+
+fn decompress(bytes: &[u8]) -> Result<RistrettoPoint, TokenError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    Ok(CompressedRistretto(tmp).decompress()?)
+}
+
+fn canonical_scalar(bytes: &[u8]) -> Result<Scalar, TokenError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    match Scalar::from_canonical_bytes(tmp) {
+        Some(x) => Ok(x),
+        None    => Err(TokenError::ScalarFormatError),
+    }
+}
+
+/// The Schnorr challenge, \\( H(X, R, t) \\).
+fn challenge(public: &RistrettoPoint, R: &RistrettoPoint, preimage: &TokenPreimage) -> Scalar {
+    let mut h = Hasher::new(b"aeonflux public token v1");
+
+    h.input(public.compress().as_bytes()).input(R.compress().as_bytes()).input(&preimage.0[..]);
+    h.finalize_scalar()
+}
+
+/// An issuer's key for signing publicly verifiable tokens.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublicTokenKeypair {
+    secret: Secret<Scalar>,
+    pub public: RistrettoPoint,
+}
+
+impl PublicTokenKeypair {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicTokenKeypair, TokenError> {
+        if bytes.len() != SIZEOF_PUBLIC_TOKEN_KEYPAIR {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        let secret: Scalar = canonical_scalar(&bytes[00..32])?;
+        let public: RistrettoPoint = decompress(&bytes[32..64])?;
+
+        Ok(PublicTokenKeypair { secret: Secret::new(secret), public })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_PUBLIC_TOKEN_KEYPAIR);
+
+        v.extend(self.secret.expose_secret().to_bytes().iter());
+        v.extend(self.public.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PublicTokenKeypair, "A valid byte sequence representing a PublicTokenKeypair");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PublicTokenKeypair);
+impl_try_from_bytes!(PublicTokenKeypair, TokenError);
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for PublicTokenKeypair {
+    fn drop(&mut self) {
+        self.secret.clear();
+    }
+}
+
+impl PublicTokenKeypair {
+    /// Generate a new token signing key.
+    pub fn generate<R>(csprng: &mut R) -> PublicTokenKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret: Scalar = Scalar::random(csprng);
+        let public: RistrettoPoint = &secret * &RISTRETTO_BASEPOINT_TABLE;
+
+        PublicTokenKeypair { secret: Secret::new(secret), public }
+    }
+
+    /// Begin signing a token, returning the issuer's state for the session
+    /// and the commitment to send to the client.
+    pub fn commit<R>(&self, csprng: &mut R) -> (SigningSession, TokenCommitment)
+    where
+        R: RngCore + CryptoRng,
+    {
+        let r0: Scalar = Scalar::random(csprng);
+        let r1: Scalar = Scalar::random(csprng);
+        let commitment = TokenCommitment {
+            R: [&r0 * &RISTRETTO_BASEPOINT_TABLE, &r1 * &RISTRETTO_BASEPOINT_TABLE],
+        };
+
+        (SigningSession { r: [Secret::new(r0), Secret::new(r1)] }, commitment)
+    }
+
+    /// Answer a client's `challenge` for a `session`, consuming it.
+    pub fn respond<R>(&self, session: SigningSession, challenge: &TokenChallenge, csprng: &mut R)
+        -> TokenSignatureResponse
+    where
+        R: RngCore + CryptoRng,
+    {
+        let clause: usize = (csprng.next_u32() & 1) as usize;
+        let s: Scalar = session.r[clause].expose_secret() + &(&challenge.c[clause] * self.secret.expose_secret());
+
+        TokenSignatureResponse { clause: clause as u8, s }
+    }
+}
+
+/// An issuer's secret nonces for one signing session.
+#[derive(Debug)]
+pub struct SigningSession {
+    r: [Secret<Scalar>; 2],
+}
+
+/// Overwrite the nonces with null bytes when they go out of scope.
+impl Drop for SigningSession {
+    fn drop(&mut self) {
+        self.r[0].clear();
+        self.r[1].clear();
+    }
+}
+
+/// The issuer's commitments to the nonces of both clauses of a session.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TokenCommitment {
+    pub R: [RistrettoPoint; 2],
+}
+
+impl TokenCommitment {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenCommitment, TokenError> {
+        if bytes.len() != SIZEOF_TOKEN_COMMITMENT {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        Ok(TokenCommitment { R: [decompress(&bytes[00..32])?, decompress(&bytes[32..64])?] })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TOKEN_COMMITMENT);
+
+        v.extend(self.R[0].compress().to_bytes().iter());
+        v.extend(self.R[1].compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenCommitment, "A valid byte sequence representing a TokenCommitment");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenCommitment);
+impl_try_from_bytes!(TokenCommitment, TokenError);
+
+impl TokenCommitment {
+    /// Blind this commitment for a new token under the issuer's `public`
+    /// key.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the `TokenChallenge` to send to the issuer, and the
+    /// `PendingPublicToken` which the client must keep until it answers.
+    pub fn blind<R>(&self, public: &RistrettoPoint, csprng: &mut R) -> (TokenChallenge, PendingPublicToken)
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut preimage = TokenPreimage([0u8; SIZEOF_TOKEN_PREIMAGE]);
+
+        csprng.fill_bytes(&mut preimage.0);
+
+        let alpha: [Scalar; 2] = [Scalar::random(csprng), Scalar::random(csprng)];
+        let beta: [Scalar; 2] = [Scalar::random(csprng), Scalar::random(csprng)];
+        let mut blinded: [RistrettoPoint; 2] = self.R;
+        let mut c: [Scalar; 2] = [Scalar::zero(); 2];
+
+        for i in 0..2 {
+            blinded[i] += &(&alpha[i] * &RISTRETTO_BASEPOINT_TABLE) + &(public * &beta[i]);
+            c[i] = &challenge(public, &blinded[i], &preimage) + &beta[i];
+        }
+
+        let pending = PendingPublicToken {
+            preimage,
+            commitment: *self,
+            challenge: c,
+            blinded,
+            alpha: [Secret::new(alpha[0]), Secret::new(alpha[1])],
+        };
+
+        (TokenChallenge { c }, pending)
+    }
+}
+
+/// The client's blinded challenges for both clauses of a session.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TokenChallenge {
+    pub c: [Scalar; 2],
+}
+
+impl TokenChallenge {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenChallenge, TokenError> {
+        if bytes.len() != SIZEOF_TOKEN_CHALLENGE {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        Ok(TokenChallenge { c: [canonical_scalar(&bytes[00..32])?, canonical_scalar(&bytes[32..64])?] })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TOKEN_CHALLENGE);
+
+        v.extend(self.c[0].to_bytes().iter());
+        v.extend(self.c[1].to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenChallenge, "A valid byte sequence representing a TokenChallenge");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenChallenge);
+impl_try_from_bytes!(TokenChallenge, TokenError);
+
+/// The issuer's answer to one clause of a session.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TokenSignatureResponse {
+    /// Which clause, `0` or `1`, the issuer chose to answer.
+    pub clause: u8,
+    pub s: Scalar,
+}
+
+impl TokenSignatureResponse {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenSignatureResponse, TokenError> {
+        if bytes.len() != SIZEOF_TOKEN_SIGNATURE_RESPONSE {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+        if bytes[0] > 1 {
+            return Err(TokenError::ProofFailure);
+        }
+
+        Ok(TokenSignatureResponse { clause: bytes[0], s: canonical_scalar(&bytes[1..33])? })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TOKEN_SIGNATURE_RESPONSE);
+
+        v.push(self.clause);
+        v.extend(self.s.to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenSignatureResponse,
+                                         "A valid byte sequence representing a TokenSignatureResponse");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenSignatureResponse);
+impl_try_from_bytes!(TokenSignatureResponse, TokenError);
+
+/// A client's secret state for a token awaiting the issuer's answer.
+#[derive(Clone, Debug)]
+pub struct PendingPublicToken {
+    pub preimage: TokenPreimage,
+    commitment: TokenCommitment,
+    challenge: [Scalar; 2],
+    blinded: [RistrettoPoint; 2],
+    alpha: [Secret<Scalar>; 2],
+}
+
+/// Overwrite the blinding factors with null bytes when they go out of scope.
+impl Drop for PendingPublicToken {
+    fn drop(&mut self) {
+        self.alpha[0].clear();
+        self.alpha[1].clear();
+    }
+}
+
+impl PendingPublicToken {
+    /// Check the issuer's `response` and unblind the signed token.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::ProofFailure` if the response is not a valid signature
+    ///   under the issuer's `public` key.
+    pub fn unblind(&self, response: &TokenSignatureResponse, public: &RistrettoPoint)
+        -> Result<PublicToken, Error>
+    {
+        let b: usize = match response.clause {
+            0 => 0,
+            1 => 1,
+            _ => return Err(TokenError::ProofFailure.into()),
+        };
+        let check = RistrettoPoint::vartime_multiscalar_mul(&[response.s, -self.challenge[b]],
+                                                            &[RISTRETTO_BASEPOINT_POINT,
+                                                              *public]);
+
+        if check != self.commitment.R[b] {
+            return Err(TokenError::ProofFailure.into());
+        }
+
+        Ok(PublicToken {
+            preimage: self.preimage,
+            R: self.blinded[b],
+            s: &response.s + self.alpha[b].expose_secret(),
+        })
+    }
+}
+
+/// A publicly verifiable token: a Schnorr signature on its preimage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PublicToken {
+    pub preimage: TokenPreimage,
+    pub R: RistrettoPoint,
+    pub s: Scalar,
+}
+
+impl PublicToken {
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicToken, TokenError> {
+        if bytes.len() != SIZEOF_PUBLIC_TOKEN {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        let mut preimage = TokenPreimage([0u8; SIZEOF_TOKEN_PREIMAGE]);
+
+        preimage.0.copy_from_slice(&bytes[00..32]);
+
+        let R: RistrettoPoint = decompress(&bytes[32..64])?;
+        let s: Scalar = canonical_scalar(&bytes[64..96])?;
+
+        Ok(PublicToken { preimage, R, s })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_PUBLIC_TOKEN);
+
+        v.extend(self.preimage.0.iter());
+        v.extend(self.R.compress().to_bytes().iter());
+        v.extend(self.s.to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(PublicToken, "A valid byte sequence representing a PublicToken");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PublicToken);
+impl_try_from_bytes!(PublicToken, TokenError);
+
+impl PublicToken {
+    /// Verify this token's signature under an issuer's `public` key.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::InvalidMac` if the signature does not verify.
+    pub fn verify(&self, public: &RistrettoPoint) -> Result<(), Error> {
+        let c: Scalar = challenge(public, &self.R, &self.preimage);
+        let check = RistrettoPoint::vartime_multiscalar_mul(&[self.s, -c],
+                                                            &[RISTRETTO_BASEPOINT_POINT,
+                                                              *public]);
+
+        if check != self.R {
+            return Err(TokenError::InvalidMac.into());
+        }
+        Ok(())
+    }
+
+    /// Verify this token under an issuer's `public` key, and spend it.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::InvalidMac` if the signature does not verify.
+    /// * `TokenError::DoubleSpend` if the token was already in the `spent`
+    ///   store.
+    pub fn redeem<S>(&self, public: &RistrettoPoint, spent: &mut S) -> Result<(), Error>
+    where
+        S: SpentTokens,
+    {
+        self.verify(public)?;
+
+        if !spent.insert(&self.preimage) {
+            return Err(TokenError::DoubleSpend.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use rand::thread_rng;
+
+    fn issue(keypair: &PublicTokenKeypair) -> PublicToken {
+        let mut csprng = thread_rng();
+        let (session, commitment) = keypair.commit(&mut csprng);
+        let commitment = TokenCommitment::from_bytes(&commitment.to_bytes()).unwrap();
+        let (challenge, pending) = commitment.blind(&keypair.public, &mut csprng);
+        let challenge = TokenChallenge::from_bytes(&challenge.to_bytes()).unwrap();
+        let response = keypair.respond(session, &challenge, &mut csprng);
+        let response = TokenSignatureResponse::from_bytes(&response.to_bytes()).unwrap();
+
+        pending.unblind(&response, &keypair.public).unwrap()
+    }
+
+    #[test]
+    fn issue_and_redeem_publicly() {
+        let mut csprng = thread_rng();
+        let keypair = PublicTokenKeypair::generate(&mut csprng);
+        let public: RistrettoPoint = keypair.public;
+        let mut spent: HashSet<TokenPreimage> = HashSet::new();
+
+        // Issue enough tokens that both clauses are very likely answered.
+        for _ in 0..8 {
+            let token = PublicToken::from_bytes(&issue(&keypair).to_bytes()).unwrap();
+
+            assert!(token.redeem(&public, &mut spent).is_ok());
+            assert!(token.redeem(&public, &mut spent) == Err(TokenError::DoubleSpend.into()));
+        }
+    }
+
+    #[test]
+    fn forged_tokens_are_rejected() {
+        let mut csprng = thread_rng();
+        let keypair = PublicTokenKeypair::generate(&mut csprng);
+        let other = PublicTokenKeypair::generate(&mut csprng);
+        let mut token = issue(&keypair);
+
+        assert!(token.verify(&other.public) == Err(TokenError::InvalidMac.into()));
+
+        token.preimage.0[0] ^= 1;
+
+        assert!(token.verify(&keypair.public) == Err(TokenError::InvalidMac.into()));
+
+        // A response from another key doesn't unblind.
+        let (session, commitment) = keypair.commit(&mut csprng);
+        let (challenge, pending) = commitment.blind(&keypair.public, &mut csprng);
+        let response = other.respond(session, &challenge, &mut csprng);
+
+        assert!(pending.unblind(&response, &keypair.public) == Err(TokenError::ProofFailure.into()));
+    }
+}
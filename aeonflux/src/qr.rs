@@ -0,0 +1,398 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Splitting serialised credentials and presentations into QR-code-sized
+//! chunks, and reassembling them.
+//!
+//! At physical gates the only transport is often a camera, so a message is
+//! split into `Chunk`s which are displayed as a (possibly animated) sequence
+//! of QR codes.  Each chunk carries:
+//!
+//! * an 8-byte message identifier, which is a truncated SHA-512 digest of
+//!   the whole message and is also used to check it after reassembly,
+//! * its index and the total number of chunks, and
+//! * a CRC-32 over the chunk, to detect misreads.
+//!
+//! Messages are split and reassembled by `chunking`, as for `fragment`.
+//! Chunks may be scanned in any order and any number of times.  A `Chunk`
+//! may be rendered as raw bytes, for QR byte mode, or as RFC 4648 base32
+//! text, which uses only characters from the denser QR alphanumeric mode.
+//!
+//! # Wire format
+//!
+//! ```text
+//! version (1) || message id (8) || index (2, LE) || total (2, LE) || data || crc32 (4, LE)
+//! ```
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use chunking;
+use chunking::Rejection;
+use chunking::SIZEOF_POSITION;
+use errors::Error;
+use errors::QrError;
+
+/// The current version of the chunk format.
+pub const CHUNK_VERSION: u8 = 1;
+
+/// The length of a chunk's header in bytes.
+pub const SIZEOF_CHUNK_HEADER: usize = 1 + 8 + SIZEOF_POSITION;
+
+/// The length of a chunk's trailing checksum in bytes.
+pub const SIZEOF_CHUNK_CHECKSUM: usize = 4;
+
+/// The maximum number of chunks a message may be split into.
+pub const MAXIMUM_CHUNKS: usize = chunking::MAXIMUM_PIECES;
+
+/// A default for the maximum encoded size of a chunk, chosen so that a
+/// base32-encoded chunk fits in a version 10 QR code at error correction
+/// level M.
+pub const DEFAULT_MAXIMUM_CHUNK_SIZE: usize = 192;
+
+/// The prefix of a chunk's text encoding.
+pub const CHUNK_TEXT_PREFIX: &'static str = "AF1:";
+
+const BASE32_ALPHABET: &'static [u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Compute the CRC-32 (IEEE 802.3) of some `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xffff_ffff;
+
+    for byte in bytes.iter() {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            let mask: u32 = (!(crc & 1)).wrapping_add(1);
+
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Compute the 8-byte identifier of a `message`.
+fn message_id(message: &[u8]) -> [u8; 8] {
+    let digest = Sha512::digest(message);
+    let mut id = [0u8; 8];
+
+    id.copy_from_slice(&digest[..8]);
+    id
+}
+
+impl From<Rejection> for QrError {
+    fn from(source: Rejection) -> QrError {
+        match source {
+            Rejection::InvalidIndex      => QrError::InvalidIndex,
+            Rejection::MismatchedMessage => QrError::MismatchedMessage,
+        }
+    }
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut s: String = String::with_capacity((bytes.len() * 8 + 4) / 5);
+    let mut buffer: u32 = 0;
+    let mut bits: u32 = 0;
+
+    for byte in bytes.iter() {
+        buffer = (buffer << 8) | (*byte as u32);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            s.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        s.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    s
+}
+
+fn base32_decode(text: &str) -> Result<Vec<u8>, QrError> {
+    let mut v: Vec<u8> = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits: u32 = 0;
+
+    for c in text.bytes() {
+        let value: u32 = match c {
+            b'A'...b'Z' => (c - b'A') as u32,
+            b'2'...b'7' => (c - b'2') as u32 + 26,
+            _           => return Err(QrError::InvalidEncoding),
+        };
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            v.push((buffer >> bits) as u8);
+        }
+    }
+
+    // Any leftover bits must be padding.
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return Err(QrError::InvalidEncoding);
+    }
+    Ok(v)
+}
+
+/// One QR-code-sized piece of a message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chunk {
+    pub message_id: [u8; 8],
+    pub index: u16,
+    pub total: u16,
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Chunk, QrError> {
+        if bytes.len() < SIZEOF_CHUNK_HEADER + SIZEOF_CHUNK_CHECKSUM {
+            return Err(QrError::WrongNumberOfBytes);
+        }
+
+        let length: usize = bytes.len() - SIZEOF_CHUNK_CHECKSUM;
+        let mut checksum: u32 = 0;
+
+        for (i, byte) in bytes[length..].iter().enumerate() {
+            checksum |= (*byte as u32) << (8 * i);
+        }
+        if crc32(&bytes[..length]) != checksum {
+            return Err(QrError::ChecksumMismatch);
+        }
+        if bytes[0] != CHUNK_VERSION {
+            return Err(QrError::UnknownVersion);
+        }
+
+        let mut message_id = [0u8; 8];
+
+        message_id.copy_from_slice(&bytes[1..9]);
+
+        let (index, total) = chunking::read_position(&bytes[9..])?;
+
+        Ok(Chunk { message_id, index, total, data: bytes[SIZEOF_CHUNK_HEADER..length].to_vec() })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CHUNK_HEADER + self.data.len() + SIZEOF_CHUNK_CHECKSUM);
+
+        v.push(CHUNK_VERSION);
+        v.extend(self.message_id.iter());
+        chunking::push_position(&mut v, self.index, self.total);
+        v.extend(self.data.iter());
+
+        let checksum: u32 = crc32(&v);
+
+        for i in 0..4 {
+            v.push((checksum >> (8 * i)) as u8);
+        }
+        v
+    }
+
+    /// Decode a chunk from its QR alphanumeric text form.
+    pub fn from_text(text: &str) -> Result<Chunk, QrError> {
+        if !text.starts_with(CHUNK_TEXT_PREFIX) {
+            return Err(QrError::InvalidEncoding);
+        }
+
+        Chunk::from_bytes(&base32_decode(&text[CHUNK_TEXT_PREFIX.len()..])?)
+    }
+
+    /// Encode this chunk as text using only QR alphanumeric characters.
+    pub fn to_text(&self) -> String {
+        let mut s: String = String::from(CHUNK_TEXT_PREFIX);
+
+        s.push_str(&base32_encode(&self.to_bytes()));
+        s
+    }
+}
+
+impl_try_from_bytes!(Chunk, QrError);
+
+/// Split a `message` into chunks of at most `maximum_chunk_size` bytes
+/// each, once encoded with `Chunk::to_bytes()`.
+///
+/// # Errors
+///
+/// * `QrError::WrongNumberOfBytes` if `maximum_chunk_size` leaves no room
+///   for any data after the chunk header and checksum.
+/// * `QrError::TooManyChunks` if the message would need more than
+///   `MAXIMUM_CHUNKS` chunks.
+pub fn split(message: &[u8], maximum_chunk_size: usize) -> Result<Vec<Chunk>, Error> {
+    if maximum_chunk_size <= SIZEOF_CHUNK_HEADER + SIZEOF_CHUNK_CHECKSUM {
+        return Err(QrError::WrongNumberOfBytes.into());
+    }
+
+    let data_size: usize = maximum_chunk_size - SIZEOF_CHUNK_HEADER - SIZEOF_CHUNK_CHECKSUM;
+    let pieces: Vec<&[u8]> = chunking::split(message, data_size).ok_or(QrError::TooManyChunks)?;
+    let total: u16 = pieces.len() as u16;
+    let id: [u8; 8] = message_id(message);
+
+    Ok(pieces.iter().enumerate().map(|(index, data)| {
+        Chunk { message_id: id, index: index as u16, total: total, data: data.to_vec() }
+    }).collect())
+}
+
+/// Collects scanned chunks, in any order, until a message is complete.
+#[derive(Clone, Debug, Default)]
+pub struct Reassembler(chunking::Reassembler<[u8; 8]>);
+
+impl Reassembler {
+    pub fn new() -> Reassembler {
+        Reassembler::default()
+    }
+
+    /// The number of distinct chunks received so far.
+    pub fn received(&self) -> usize {
+        self.0.received()
+    }
+
+    /// The total number of chunks in the message, once any has been received.
+    pub fn total(&self) -> Option<usize> {
+        self.0.total()
+    }
+
+    /// Forget all chunks received so far, e.g. to scan a different message.
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    /// Add a scanned `chunk`.  Chunks which were already received are ignored.
+    ///
+    /// # Errors
+    ///
+    /// * `QrError::MismatchedMessage` if the chunk belongs to a different
+    ///   message than those already received.
+    /// * `QrError::InvalidIndex` if the chunk's index is not below its total.
+    /// * `QrError::MessageDigestMismatch` if every chunk was received but the
+    ///   reassembled message was corrupt, in which case the reassembler is
+    ///   reset.
+    ///
+    /// # Returns
+    ///
+    /// The reassembled message, if this was its last missing chunk.
+    pub fn push(&mut self, chunk: Chunk) -> Result<Option<Vec<u8>>, Error> {
+        let id: [u8; 8] = chunk.message_id;
+        let message: Vec<u8> = match self.0.push(id, chunk.index, chunk.total, chunk.data) {
+            Ok(Some(x)) => x,
+            Ok(None)    => return Ok(None),
+            Err(e)      => return Err(QrError::from(e).into()),
+        };
+
+        if message_id(&message) != id {
+            return Err(QrError::MessageDigestMismatch.into());
+        }
+        Ok(Some(message))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert!(crc32(b"123456789") == 0xcbf4_3926);
+    }
+
+    #[test]
+    fn base32_roundtrip() {
+        assert!(base32_encode(b"foobar") == "MZXW6YTBOI");
+
+        for length in 0..12 {
+            let m = message(length);
+
+            assert!(base32_decode(&base32_encode(&m)).unwrap() == m);
+        }
+    }
+
+    #[test]
+    fn split_and_reassemble_out_of_order_with_duplicates() {
+        let m = message(1000);
+        let chunks = split(&m, DEFAULT_MAXIMUM_CHUNK_SIZE).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        assert!(chunks.len() == 6);
+
+        for chunk in chunks.iter() {
+            assert!(chunk.to_bytes().len() <= DEFAULT_MAXIMUM_CHUNK_SIZE);
+        }
+
+        let order = [3, 1, 1, 0, 5, 3, 2];
+
+        for i in order.iter() {
+            let text = chunks[*i].to_text();
+
+            assert!(reassembler.push(Chunk::from_text(&text).unwrap()).unwrap().is_none());
+        }
+        assert!(reassembler.received() == 5);
+        assert!(reassembler.push(chunks[4].clone()).unwrap() == Some(m));
+        assert!(reassembler.total().is_none());
+    }
+
+    #[test]
+    fn empty_message() {
+        let chunks = split(&[], DEFAULT_MAXIMUM_CHUNK_SIZE).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        assert!(chunks.len() == 1);
+        assert!(reassembler.push(chunks[0].clone()).unwrap() == Some(Vec::new()));
+    }
+
+    #[test]
+    fn corrupted_chunk_rejected() {
+        let chunks = split(&message(100), 64).unwrap();
+        let mut bytes = chunks[0].to_bytes();
+
+        bytes[20] ^= 0x01;
+
+        assert!(Chunk::from_bytes(&bytes) == Err(QrError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn mismatched_message_rejected() {
+        let first = split(&message(100), 64).unwrap();
+        let second = split(&message(101), 64).unwrap();
+        let mut reassembler = Reassembler::new();
+
+        reassembler.push(first[0].clone()).unwrap();
+
+        assert!(reassembler.push(second[1].clone()) == Err(QrError::MismatchedMessage.into()));
+    }
+
+    #[test]
+    fn out_of_range_indices_are_refused() {
+        let mut chunk = split(&message(10), DEFAULT_MAXIMUM_CHUNK_SIZE).unwrap()[0].clone();
+
+        chunk.index = chunk.total;
+
+        assert!(Chunk::from_bytes(&chunk.to_bytes()) == Err(QrError::InvalidIndex));
+        assert!(Reassembler::new().push(chunk) == Err(QrError::InvalidIndex.into()));
+    }
+
+    #[test]
+    fn chunk_size_too_small() {
+        assert!(split(&message(10), SIZEOF_CHUNK_HEADER + SIZEOF_CHUNK_CHECKSUM)
+                == Err(QrError::WrongNumberOfBytes.into()));
+    }
+}
@@ -0,0 +1,599 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Anonymous, per-epoch rate limiting.
+//!
+//! A `RateLimiter` permits each credential holder to perform at most \\( k \\)
+//! actions per epoch, without learning which holder performed any of them.
+//! Every action is accompanied by a presentation of the holder's credential
+//! and a `Nullifier`, \\( N = m \cdot H_{e,i} \\), where \\( m \\) is the hidden
+//! attribute and \\( H_{e,i} \\) is derived from the limiter's scope, the
+//! epoch \\( e \\), and an action counter \\( i < k \\).  Since there are only
+//! \\( k \\) valid counters per epoch, a holder can produce at most \\( k \\)
+//! distinct nullifiers per epoch, and the limiter rejects any it has seen
+//! before.
+//!
+//! When the epoch rolls over, the nullifier store is cleared.
+//!
+//! # Note
+//!
+//! The action counter is revealed to the verifier.  Nullifiers for different
+//! counters are unlinkable, but the verifier does learn, e.g., that an action
+//! was some holder's third in this epoch.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use clock::Clock;
+use credential::SIZEOF_CREDENTIAL_PRESENTATION;
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use credential::VerifiedCredential;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use errors::RateLimitError;
+use issuer::Issuer;
+use nonces::Nonces;
+use nullifier::encode_scope;
+use nullifier::hash_scope_to_group;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use user::User;
+
+pub const SIZEOF_NULLIFIER: usize = 32;
+
+/// The domain separator for the scopes of rate limited actions.
+const RATE_LIMIT_SCOPE_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux rate limit action v1";
+
+/// The nullifier scope of the `counter`th action in an `epoch` within a rate
+/// limiting `scope`, whose basepoint \\( H_{e,i} \\) is hashed from it as in
+/// `nullifier::scoped_nullifier()`.
+///
+/// # Errors
+///
+/// * `Error::Misuse` if the `scope` is longer than 65535 bytes.
+pub fn action_scope(scope: &[u8], epoch: u64, counter: u32) -> Result<Vec<u8>, Error> {
+    encode_scope(&[RATE_LIMIT_SCOPE_DOMAIN_SEPARATOR, scope, &u64_to_bytes(epoch), &u64_to_bytes(counter as u64)])
+}
+
+/// A tag which is unique per hidden attribute, scope, epoch, and counter.
+///
+/// This is kept in its compressed form, so that it may be hashed and stored
+/// cheaply.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Nullifier(pub [u8; SIZEOF_NULLIFIER]);
+
+impl Nullifier {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Nullifier, CredentialError> {
+        if bytes.len() != SIZEOF_NULLIFIER {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; SIZEOF_NULLIFIER];
+
+        tmp.copy_from_slice(bytes);
+
+        // Check that the nullifier is a valid point before accepting it.
+        CompressedRistretto(tmp).decompress()?;
+
+        Ok(Nullifier(tmp))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Nullifier, "A valid byte sequence representing a Nullifier");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Nullifier);
+impl_try_from_bytes!(Nullifier, CredentialError);
+
+impl From<RistrettoPoint> for Nullifier {
+    fn from(source: RistrettoPoint) -> Nullifier {
+        Nullifier(source.compress().to_bytes())
+    }
+}
+
+/// A store of the nullifiers which have been seen in the current epoch.
+pub trait NullifierStore {
+    /// Record a nullifier as seen.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the nullifier was not previously seen, and `false` otherwise.
+    fn insert(&mut self, nullifier: &Nullifier) -> bool;
+
+    /// Whether a nullifier has been seen.
+    fn contains(&self, nullifier: &Nullifier) -> bool;
+
+    /// Forget all nullifiers, e.g. because the epoch rolled over.
+    fn clear(&mut self);
+}
+
+#[cfg(feature = "std")]
+impl NullifierStore for HashSet<Nullifier> {
+    fn insert(&mut self, nullifier: &Nullifier) -> bool {
+        HashSet::insert(self, *nullifier)
+    }
+
+    fn contains(&self, nullifier: &Nullifier) -> bool {
+        HashSet::contains(self, nullifier)
+    }
+
+    fn clear(&mut self) {
+        HashSet::clear(self)
+    }
+}
+
+/// The public configuration of a rate limiter, shared by clients and the
+/// server.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateLimitConfig {
+    /// A public string identifying what is being rate limited, e.g. `b"sends"`.
+    pub scope: Vec<u8>,
+    /// The number of actions, \\( k \\), each credential may perform per epoch.
+    pub actions_per_epoch: u32,
+    /// The length of an epoch in seconds.  This must be non-zero.
+    pub epoch_length: u64,
+}
+
+impl RateLimitConfig {
+    /// The epoch containing the time `now`, in seconds since the Unix epoch.
+    pub fn epoch_at(&self, now: u64) -> u64 {
+        if self.epoch_length == 0 {
+            return now;
+        }
+        now / self.epoch_length
+    }
+
+    /// The epoch containing the current time according to a `clock`.
+    pub fn current_epoch<C>(&self, clock: &C) -> u64
+    where
+        C: Clock,
+    {
+        self.epoch_at(clock.now())
+    }
+}
+
+/// A presentation of a credential for one rate-limited action.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateLimitPresentation {
+    /// A presentation of the holder's credential.
+    pub presentation: CredentialPresentation,
+    /// The epoch this action was performed in.
+    pub epoch: u64,
+    /// The action counter within the epoch.
+    pub counter: u32,
+    /// The nullifier for the hidden attribute, epoch, and counter, which the
+    /// proof in the `presentation` shows was computed from the presented
+    /// attribute.
+    pub nullifier: Nullifier,
+}
+
+impl RateLimitPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RateLimitPresentation, CredentialError> {
+        let offset: usize = SIZEOF_CREDENTIAL_PRESENTATION + 8 + 4 + SIZEOF_NULLIFIER;
+
+        if bytes.len() != offset {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let presentation = CredentialPresentation::from_bytes(&bytes[..SIZEOF_CREDENTIAL_PRESENTATION])?;
+
+        let mut epoch: u64 = 0;
+        let mut counter: u32 = 0;
+
+        for (i, byte) in bytes[SIZEOF_CREDENTIAL_PRESENTATION..SIZEOF_CREDENTIAL_PRESENTATION + 8]
+            .iter().enumerate()
+        {
+            epoch |= (*byte as u64) << (8 * i);
+        }
+        for (i, byte) in bytes[SIZEOF_CREDENTIAL_PRESENTATION + 8..SIZEOF_CREDENTIAL_PRESENTATION + 12]
+            .iter().enumerate()
+        {
+            counter |= (*byte as u32) << (8 * i);
+        }
+
+        let nullifier = Nullifier::from_bytes(&bytes[SIZEOF_CREDENTIAL_PRESENTATION + 12..offset])?;
+
+        Ok(RateLimitPresentation { presentation, epoch, counter, nullifier })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CREDENTIAL_PRESENTATION + 12 + SIZEOF_NULLIFIER);
+
+        v.extend(self.presentation.to_bytes());
+
+        for i in 0..8 {
+            v.push((self.epoch >> (8 * i)) as u8);
+        }
+        for i in 0..4 {
+            v.push((self.counter >> (8 * i)) as u8);
+        }
+
+        v.extend(self.nullifier.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RateLimitPresentation,
+                                         "A valid byte sequence representing a RateLimitPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RateLimitPresentation);
+impl_try_from_bytes!(RateLimitPresentation, CredentialError);
+
+impl RateLimitPresentation {
+    /// Present the `user`'s credential for the `counter`th action in `epoch`.
+    ///
+    /// Most callers should use `RateLimitClient::present()`, which keeps
+    /// track of the counter.
+    ///
+    /// # Errors
+    ///
+    /// * `RateLimitError::LimitExceeded` if the `counter` is not below
+    ///   `config.actions_per_epoch`.
+    /// * `RateLimitError::Credential(CredentialError::MissingData)` if the
+    ///   `user` has no credential.
+    /// * `Error::Misuse` if the `config`'s scope is longer than 65535 bytes.
+    pub fn create<R>(
+        user: &User,
+        config: &RateLimitConfig,
+        epoch: u64,
+        counter: u32,
+        rng: &mut R,
+    ) -> Result<RateLimitPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if counter >= config.actions_per_epoch {
+            return Err(RateLimitError::LimitExceeded.into());
+        }
+
+        let transcript = Transcript::new(b"AEONFLUX RATE LIMIT");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        // The nullifier is proven under the same challenge as the credential,
+        // rather than with a separate proof.
+        let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+        let Hn: RistrettoPoint = hash_scope_to_group(&action_scope(&config.scope, epoch, counter)?);
+        let (presentation, N) = user.show_scoped(&Hn, &nonces, &mut csprng)?;
+
+        Ok(RateLimitPresentation {
+            presentation: presentation,
+            epoch: epoch,
+            counter: counter,
+            nullifier: Nullifier::from(N),
+        })
+    }
+}
+
+/// A credential holder's view of a rate limiter, which counts the actions
+/// they have performed in the current epoch.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateLimitClient {
+    config: RateLimitConfig,
+    epoch: u64,
+    used: u32,
+}
+
+impl RateLimitClient {
+    pub fn new(config: RateLimitConfig, now: u64) -> RateLimitClient {
+        let epoch: u64 = config.epoch_at(now);
+
+        RateLimitClient { config, epoch, used: 0 }
+    }
+
+    /// The number of actions remaining in the epoch containing `now`.
+    pub fn remaining(&self, now: u64) -> u32 {
+        if self.config.epoch_at(now) > self.epoch {
+            return self.config.actions_per_epoch;
+        }
+        self.config.actions_per_epoch.saturating_sub(self.used)
+    }
+
+    /// Present the `user`'s credential for the next action at time `now`.
+    ///
+    /// # Errors
+    ///
+    /// * `RateLimitError::LimitExceeded` if all of this epoch's actions have
+    ///   been used.
+    pub fn present<R>(
+        &mut self,
+        user: &User,
+        now: u64,
+        rng: &mut R,
+    ) -> Result<RateLimitPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let epoch: u64 = self.config.epoch_at(now);
+
+        if epoch > self.epoch {
+            self.epoch = epoch;
+            self.used = 0;
+        }
+
+        let presentation = RateLimitPresentation::create(user, &self.config, self.epoch, self.used, rng)?;
+
+        self.used += 1;
+
+        Ok(presentation)
+    }
+}
+
+/// A server which issues credentials and admits at most
+/// `config.actions_per_epoch` actions per credential per epoch.
+pub struct RateLimiter<S: NullifierStore> {
+    config: RateLimitConfig,
+    issuer: Issuer,
+    nullifiers: S,
+    epoch: u64,
+}
+
+impl<S: NullifierStore> RateLimiter<S> {
+    /// Create a new `RateLimiter` at time `now`, storing nullifiers in the
+    /// (presumably empty) `nullifiers`.
+    pub fn new(config: RateLimitConfig, issuer: Issuer, nullifiers: S, now: u64) -> RateLimiter<S> {
+        let epoch: u64 = config.epoch_at(now);
+
+        RateLimiter { config, issuer, nullifiers, epoch }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    pub fn issuer(&self) -> &Issuer {
+        &self.issuer
+    }
+
+    /// The current epoch, as of the last call to `roll_over()` or `verify()`.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn nullifiers(&self) -> &S {
+        &self.nullifiers
+    }
+
+    /// Issue a credential, as `Issuer::issue()`.
+    pub fn issue<R>(&self, request: &CredentialRequest, rng: &mut R)
+        -> Result<CredentialIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.issuer.issue(request, rng)?)
+    }
+
+    /// Issue a credential over a blinded attribute, as `Issuer::blind_issue()`.
+    pub fn blind_issue<R>(&self, request: &CredentialBlindRequest, rng: &mut R)
+        -> Result<CredentialBlindIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.issuer.blind_issue(request, rng)?)
+    }
+
+    /// Advance to the epoch containing `now`, clearing the nullifier store if
+    /// it has changed.
+    pub fn roll_over(&mut self, now: u64) {
+        let epoch: u64 = self.config.epoch_at(now);
+
+        if epoch > self.epoch {
+            self.nullifiers.clear();
+            self.epoch = epoch;
+        }
+    }
+
+    /// Verify a `presentation` for an action at time `now`, and record its
+    /// nullifier.
+    ///
+    /// # Errors
+    ///
+    /// * `RateLimitError::WrongEpoch` if the presentation was not made for
+    ///   the epoch containing `now`.
+    /// * `RateLimitError::LimitExceeded` if the presentation's counter is not
+    ///   below `config.actions_per_epoch`.
+    /// * `RateLimitError::Credential` if the credential presentation was
+    ///   malformed.
+    /// * `RateLimitError::VerificationFailure` if the presentation's proof,
+    ///   which covers both the credential and the nullifier, did not verify.
+    /// * `RateLimitError::NullifierReused` if the nullifier was already seen
+    ///   in this epoch.
+    /// * `Error::Misuse` if the `config`'s scope is longer than 65535 bytes.
+    pub fn verify(
+        &mut self,
+        presentation: &RateLimitPresentation,
+        now: u64,
+    ) -> Result<VerifiedCredential, Error>
+    {
+        self.roll_over(now);
+
+        if presentation.epoch != self.epoch {
+            return Err(RateLimitError::WrongEpoch.into());
+        }
+        if presentation.counter >= self.config.actions_per_epoch {
+            return Err(RateLimitError::LimitExceeded.into());
+        }
+        if presentation.presentation.attributes_blinded.len() != 1 {
+            return Err(RateLimitError::Credential(CredentialError::WrongNumberOfAttributes).into());
+        }
+
+        let Hn: RistrettoPoint = hash_scope_to_group(&action_scope(&self.config.scope, presentation.epoch,
+                                                                   presentation.counter)?);
+        let N: RistrettoPoint = CompressedRistretto(presentation.nullifier.0).decompress()?;
+
+        let verified: VerifiedCredential = match self.issuer.verify_scoped(&presentation.presentation, &Hn, &N) {
+            Ok(x) => x,
+            Err(e) if e == Error::from(CredentialError::MacVerification)
+                   => return Err(RateLimitError::VerificationFailure.into()),
+            Err(e) => return Err(e),
+        };
+
+        if !self.nullifiers.insert(&presentation.nullifier) {
+            return Err(RateLimitError::NullifierReused.into());
+        }
+
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use parameters::SystemParameters;
+    use proofs::scoped_nullifier;
+    use test_utils::H;
+
+    const EPOCH_LENGTH: u64 = 3600;
+    const NOW: u64 = 1_538_000_000;
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            scope: b"test sends".to_vec(),
+            actions_per_epoch: 2,
+            epoch_length: EPOCH_LENGTH,
+        }
+    }
+
+    fn setup() -> (RateLimiter<HashSet<Nullifier>>, User) {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let limiter = RateLimiter::new(config(), issuer, HashSet::new(), NOW);
+        let mut user: User = User::new(system_parameters, limiter.issuer().get_issuer_parameters(), None);
+        let issuance = limiter.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        (limiter, user)
+    }
+
+    #[test]
+    fn actions_up_to_limit() {
+        let mut rng = thread_rng();
+        let (mut limiter, user) = setup();
+        let mut client = RateLimitClient::new(config(), NOW);
+
+        for _ in 0..2 {
+            let presentation = client.present(&user, NOW, &mut rng).unwrap();
+            let presentation = RateLimitPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+            assert!(presentation.to_bytes().len() == SIZEOF_CREDENTIAL_PRESENTATION + 12 + SIZEOF_NULLIFIER);
+
+            assert!(limiter.verify(&presentation, NOW).is_ok());
+        }
+
+        assert!(client.remaining(NOW) == 0);
+        assert!(client.present(&user, NOW, &mut rng) == Err(RateLimitError::LimitExceeded.into()));
+    }
+
+    #[test]
+    fn nullifier_shares_the_credential_challenge() {
+        let mut rng = thread_rng();
+        let (_, user) = setup();
+        let epoch = config().epoch_at(NOW);
+        let presentation = RateLimitPresentation::create(&user, &config(), epoch, 0, &mut rng).unwrap();
+
+        // A separate nullifier proof would add a whole `scoped_nullifier`
+        // proof, as it does for an `AcceleratedPresentation`.
+        let separate: usize = presentation.to_bytes().len() + scoped_nullifier::SIZEOF_PROOF;
+
+        assert!(presentation.to_bytes().len() == 300);
+        assert!(separate == 396);
+    }
+
+    #[test]
+    fn nullifier_reuse_rejected() {
+        let mut rng = thread_rng();
+        let (mut limiter, user) = setup();
+        let epoch = config().epoch_at(NOW);
+
+        let first = RateLimitPresentation::create(&user, &config(), epoch, 0, &mut rng).unwrap();
+        let second = RateLimitPresentation::create(&user, &config(), epoch, 0, &mut rng).unwrap();
+
+        assert!(first.nullifier == second.nullifier);
+        assert!(limiter.verify(&first, NOW).is_ok());
+        assert!(limiter.verify(&second, NOW) == Err(RateLimitError::NullifierReused.into()));
+    }
+
+    #[test]
+    fn epoch_rollover() {
+        let mut rng = thread_rng();
+        let (mut limiter, user) = setup();
+        let mut client = RateLimitClient::new(config(), NOW);
+        let later: u64 = NOW + EPOCH_LENGTH;
+
+        let stale = client.present(&user, NOW, &mut rng).unwrap();
+
+        assert!(limiter.verify(&stale, NOW).is_ok());
+
+        client.present(&user, NOW, &mut rng).unwrap();
+
+        assert!(client.remaining(later) == 2);
+
+        let fresh = client.present(&user, later, &mut rng).unwrap();
+
+        assert!(fresh.counter == 0);
+        assert!(limiter.verify(&fresh, later).is_ok());
+        assert!(limiter.nullifiers().len() == 1);
+        assert!(limiter.verify(&stale, later) == Err(RateLimitError::WrongEpoch.into()));
+    }
+
+    #[test]
+    fn counter_beyond_limit_rejected() {
+        let mut rng = thread_rng();
+        let (mut limiter, user) = setup();
+        let mut generous = config();
+
+        generous.actions_per_epoch = 3;
+
+        let epoch = generous.epoch_at(NOW);
+        let presentation = RateLimitPresentation::create(&user, &generous, epoch, 2, &mut rng).unwrap();
+
+        assert!(limiter.verify(&presentation, NOW) == Err(RateLimitError::LimitExceeded.into()));
+    }
+
+    #[test]
+    fn forged_nullifier_rejected() {
+        let mut rng = thread_rng();
+        let (mut limiter, user) = setup();
+        let epoch = config().epoch_at(NOW);
+        let mut presentation = RateLimitPresentation::create(&user, &config(), epoch, 0, &mut rng).unwrap();
+
+        presentation.nullifier = Nullifier::from(hash_scope_to_group(&action_scope(b"other", epoch, 0).unwrap()));
+
+        assert!(limiter.verify(&presentation, NOW) == Err(RateLimitError::VerificationFailure.into()));
+    }
+}
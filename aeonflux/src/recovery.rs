@@ -0,0 +1,626 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Emergency recovery of a wallet's master secret from threshold guardians.
+//!
+//! A holder who fears losing every copy of their wallet may choose \\( n \\)
+//! guardians, e.g. friends or institutions each holding an elGamal keypair,
+//! and a threshold \\( t \\).  Their `RecoverySecret` \\( s \\) is the root
+//! of their wallet, through `RecoverySecret::derivation_key()`, and is never
+//! written down: instead, it is Shamir-shared, in the exponent, to the
+//! guardians.  The holder picks a random polynomial
+//!
+//! \\[
+//!     f(x) = s + a\_1 x + \cdots + a\_{t-1} x^{t-1},
+//! \\]
+//!
+//! publishes the commitments \\( C\_j = A \cdot a\_j \\) to its coefficients,
+//! and encrypts each share \\( B \cdot f(i) \\) to the \\( i \\)th guardian,
+//! with a `proofs::correct_share` proof that the ciphertext holds the share
+//! committed to by
+//!
+//! \\[
+//!     X\_i = \sum\_j C\_j \cdot i^j = A \cdot f(i).
+//! \\]
+//!
+//! Anyone, and in particular each guardian, may check the whole `Sharing`
+//! with `Sharing::verify()` before agreeing to keep it.
+//!
+//! To recover, the holder asks their guardians, through whichever channel
+//! they trust to authenticate them, to decrypt their shares.  Each guardian
+//! returns a `DecryptedShare` with a DLEQ proof that it was decrypted with
+//! their key, as in `vault::Opening`, so a guardian cannot lock the holder
+//! out by lying about their share.  Any \\( t \\) valid shares are combined
+//! with Lagrange coefficients into \\( S = B \cdot s \\), from which the same
+//! `DerivationKey`, and thus the same wallet, is derived again.  Fewer than
+//! \\( t \\) guardians learn nothing about \\( S \\).
+//!
+//! The sharing itself is not secret, and should be kept wherever the holder
+//! will find it after losing their devices, e.g. with each guardian.
+
+// We denote group elements with capital and scalars with lowercased names.
+#![allow(non_snake_case)]
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fmt;
+
+use bincode::deserialize;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use derivation::DerivationKey;
+use elgamal;
+use elgamal::Encryption;
+use elgamal::Message;
+use elgamal::SIZEOF_ENCRYPTION;
+use elgamal::SIZEOF_PUBLIC_KEY;
+use encoding::u64_to_bytes;
+use errors::Error;
+use errors::RecoveryError;
+use hashing::Hasher;
+use nonces::Ephemeral;
+use parameters::SystemParameters;
+use proofs::batch_dleq;
+use proofs::correct_share;
+use secret::REDACTED;
+use secret::Secret;
+
+/// The most guardians a secret may be shared to.
+pub const MAXIMUM_GUARDIANS: usize = 255;
+
+pub const SIZEOF_SHARE_PROOF: usize = 96;
+pub const SIZEOF_ENCRYPTED_SHARE: usize = SIZEOF_ENCRYPTION + SIZEOF_SHARE_PROOF;
+pub const SIZEOF_DECRYPTED_SHARE: usize = 8 + 32 + 64;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// Derive the root of a wallet from the recovered point \\( B \cdot s \\).
+fn derive(root: &RistrettoPoint) -> DerivationKey {
+    let mut h = Hasher::new(b"aeonflux recovery seed v1");
+
+    h.input(root.compress().as_bytes());
+
+    let mut seed = Secret::new(h.finalize_key());
+    let key = DerivationKey::from_seed(seed.expose_secret());
+
+    seed.clear();
+    key
+}
+
+/// The secret a holder shares to their guardians, and derives their wallet
+/// from.
+pub struct RecoverySecret(Secret<Scalar>);
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for RecoverySecret {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl fmt::Debug for RecoverySecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecoverySecret({})", REDACTED)
+    }
+}
+
+impl RecoverySecret {
+    pub fn generate<R>(csprng: &mut R) -> RecoverySecret
+    where
+        R: RngCore + CryptoRng,
+    {
+        RecoverySecret(Secret::new(Scalar::random(csprng)))
+    }
+
+    /// The root of the wallet protected by this secret.
+    pub fn derivation_key(&self) -> DerivationKey {
+        derive(&(self.0.expose_secret() * &RISTRETTO_BASEPOINT_TABLE))
+    }
+
+    /// Share this secret to some `guardians`, any `threshold` of whom may
+    /// later recover it.
+    ///
+    /// # Errors
+    ///
+    /// * `RecoveryError::InvalidThreshold` if `threshold` is zero or greater
+    ///   than the number of guardians, or if there are more than
+    ///   `MAXIMUM_GUARDIANS`.
+    pub fn share<R>(
+        &self,
+        system_parameters: &SystemParameters,
+        guardians: &[elgamal::PublicKey],
+        threshold: usize,
+        csprng: &mut R,
+    ) -> Result<Sharing, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if threshold == 0 || threshold > guardians.len() || guardians.len() > MAXIMUM_GUARDIANS {
+            return Err(RecoveryError::InvalidThreshold.into());
+        }
+
+        let mut coefficients: Vec<Ephemeral> = Vec::with_capacity(threshold);
+
+        coefficients.push(Ephemeral::from(*self.0.expose_secret()));
+
+        for _ in 1..threshold {
+            coefficients.push(Ephemeral::new(csprng));
+        }
+
+        let commitments: Vec<RistrettoPoint> = coefficients.iter()
+            .map(|a| system_parameters.h * a.as_scalar())
+            .collect();
+        let mut sharing = Sharing {
+            threshold,
+            commitments,
+            guardians: guardians.to_vec(),
+            shares: Vec::with_capacity(guardians.len()),
+        };
+
+        for (i, guardian) in guardians.iter().enumerate() {
+            let index: u64 = i as u64 + 1;
+            let s = Ephemeral::from(evaluate(&coefficients, index));
+            let r = Ephemeral::new(csprng);
+            let encryption = guardian.encrypt(&Message::from(s.as_scalar()), &r);
+            let X = sharing.commitment_to(index);
+
+            let mut transcript = share_transcript(threshold, index);
+            let secrets = correct_share::Secrets { s: s.as_scalar(), r: r.as_witness() };
+            let publics = correct_share::Publics {
+                B: &RISTRETTO_BASEPOINT_POINT,
+                A: &system_parameters.h,
+                Y: &guardian.0,
+                X: &X,
+                E0: &encryption.commitment,
+                E1: &encryption.encryption,
+            };
+            let proof = correct_share::Proof::create(&mut transcript, publics, secrets);
+
+            sharing.shares.push(EncryptedShare { encryption, proof });
+        }
+        Ok(sharing)
+    }
+}
+
+/// Evaluate the polynomial with the given `coefficients` at `index`.
+fn evaluate(coefficients: &[Ephemeral], index: u64) -> Scalar {
+    let x = Scalar::from(index);
+
+    coefficients.iter().rev().fold(Scalar::zero(), |acc, a| acc * x + a.as_scalar())
+}
+
+/// The transcript under which the share at `index` is proven correct.
+fn share_transcript(threshold: usize, index: u64) -> Transcript {
+    let mut transcript = Transcript::new(b"AEONFLUX RECOVERY SHARE");
+
+    transcript.commit_bytes(b"threshold", &u64_to_bytes(threshold as u64));
+    transcript.commit_bytes(b"index", &u64_to_bytes(index));
+    transcript
+}
+
+/// The transcript under which the decryption of the share at `index` is
+/// proven.
+fn decryption_transcript(index: u64, share: &RistrettoPoint) -> Transcript {
+    let mut transcript = Transcript::new(b"AEONFLUX RECOVERY DECRYPTION");
+
+    transcript.commit_bytes(b"index", &u64_to_bytes(index));
+    transcript.commit_bytes(b"share", share.compress().as_bytes());
+    transcript
+}
+
+/// One guardian's share, encrypted to their key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedShare {
+    pub encryption: Encryption,
+    pub proof: correct_share::Proof,
+}
+
+impl EncryptedShare {
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedShare, RecoveryError> {
+        if bytes.len() != SIZEOF_ENCRYPTED_SHARE {
+            return Err(RecoveryError::WrongNumberOfBytes);
+        }
+
+        let encryption = Encryption::from_bytes(&bytes[..SIZEOF_ENCRYPTION])?;
+        let proof: correct_share::Proof = match deserialize(&bytes[SIZEOF_ENCRYPTION..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(RecoveryError::WrongNumberOfBytes),
+        };
+
+        Ok(EncryptedShare { encryption, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENCRYPTED_SHARE);
+
+        v.extend(self.encryption.to_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(EncryptedShare, "A valid byte sequence representing an EncryptedShare");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(EncryptedShare);
+impl_try_from_bytes!(EncryptedShare, RecoveryError);
+
+/// A recovery secret, shared to some guardians.
+///
+/// The guardian at position `i` in `guardians` holds the share at index
+/// `i + 1`, since the secret itself is the polynomial's value at zero.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sharing {
+    pub threshold: usize,
+    /// The commitments \\( C\_j = A \cdot a\_j \\) to the polynomial's
+    /// coefficients, beginning with the secret's.
+    pub commitments: Vec<RistrettoPoint>,
+    pub guardians: Vec<elgamal::PublicKey>,
+    pub shares: Vec<EncryptedShare>,
+}
+
+impl Sharing {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Sharing, RecoveryError> {
+        if bytes.len() < 16 {
+            return Err(RecoveryError::WrongNumberOfBytes);
+        }
+
+        let threshold: usize = read_u64(&bytes[00..08]) as usize;
+        let guardians: usize = read_u64(&bytes[08..16]) as usize;
+
+        if threshold == 0 || threshold > guardians || guardians > MAXIMUM_GUARDIANS {
+            return Err(RecoveryError::InvalidThreshold);
+        }
+        if bytes.len() != 16 + 32 * threshold + (SIZEOF_PUBLIC_KEY + SIZEOF_ENCRYPTED_SHARE) * guardians {
+            return Err(RecoveryError::WrongNumberOfBytes);
+        }
+
+        let (commitment_bytes, rest) = bytes[16..].split_at(32 * threshold);
+        let (guardian_bytes, share_bytes) = rest.split_at(SIZEOF_PUBLIC_KEY * guardians);
+
+        let mut sharing = Sharing {
+            threshold,
+            commitments: Vec::with_capacity(threshold),
+            guardians: Vec::with_capacity(guardians),
+            shares: Vec::with_capacity(guardians),
+        };
+
+        for chunk in commitment_bytes.chunks(32) {
+            sharing.commitments.push(CompressedRistretto::from_slice(chunk).decompress()?);
+        }
+        for chunk in guardian_bytes.chunks(SIZEOF_PUBLIC_KEY) {
+            sharing.guardians.push(elgamal::PublicKey::from_bytes(chunk)?);
+        }
+        for chunk in share_bytes.chunks(SIZEOF_ENCRYPTED_SHARE) {
+            sharing.shares.push(EncryptedShare::from_bytes(chunk)?);
+        }
+        Ok(sharing)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(
+            16 + 32 * self.commitments.len() + (SIZEOF_PUBLIC_KEY + SIZEOF_ENCRYPTED_SHARE) * self.shares.len());
+
+        v.extend(u64_to_bytes(self.threshold as u64).iter());
+        v.extend(u64_to_bytes(self.guardians.len() as u64).iter());
+
+        for commitment in self.commitments.iter() {
+            v.extend(commitment.compress().as_bytes());
+        }
+        for guardian in self.guardians.iter() {
+            v.extend(guardian.to_bytes());
+        }
+        for share in self.shares.iter() {
+            v.extend(share.to_bytes());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Sharing, "A valid byte sequence representing a Sharing");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Sharing);
+impl_try_from_bytes!(Sharing, RecoveryError);
+
+impl Sharing {
+    /// The commitment \\( X\_i = A \cdot f(i) \\) to the share at `index`.
+    fn commitment_to(&self, index: u64) -> RistrettoPoint {
+        let x = Scalar::from(index);
+
+        self.commitments.iter().rev().fold(RistrettoPoint::identity(), |acc, C| acc * x + C)
+    }
+
+    /// The index of the share held by the `guardian`.
+    fn index_of(&self, guardian: &elgamal::PublicKey) -> Result<u64, RecoveryError> {
+        match self.guardians.iter().position(|g| g == guardian) {
+            Some(i) => Ok(i as u64 + 1),
+            None    => Err(RecoveryError::NotAGuardian),
+        }
+    }
+
+    /// Check that every share was encrypted to its guardian from the same
+    /// polynomial of degree less than the threshold.
+    ///
+    /// # Errors
+    ///
+    /// * `RecoveryError::InvalidThreshold` if the sharing does not have one
+    ///   commitment for each degree below the threshold, or one share for
+    ///   each guardian.
+    /// * `RecoveryError::InvalidSharing` if any share's proof did not verify.
+    pub fn verify(&self, system_parameters: &SystemParameters) -> Result<(), Error> {
+        if self.threshold == 0 ||
+            self.commitments.len() != self.threshold ||
+            self.shares.len() != self.guardians.len() ||
+            self.guardians.len() < self.threshold
+        {
+            return Err(RecoveryError::InvalidThreshold.into());
+        }
+
+        for (i, (guardian, share)) in self.guardians.iter().zip(self.shares.iter()).enumerate() {
+            let index: u64 = i as u64 + 1;
+            let X = self.commitment_to(index);
+
+            let mut transcript = share_transcript(self.threshold, index);
+            let publics = correct_share::Publics {
+                B: &RISTRETTO_BASEPOINT_POINT,
+                A: &system_parameters.h,
+                Y: &guardian.0,
+                X: &X,
+                E0: &share.encryption.commitment,
+                E1: &share.encryption.encryption,
+            };
+
+            share.proof.verify(&mut transcript, publics).or(Err(RecoveryError::InvalidSharing))?;
+        }
+        Ok(())
+    }
+
+    /// Decrypt the share held by the guardian with the given `keypair`, for
+    /// sending to the holder when they ask to recover.
+    ///
+    /// # Errors
+    ///
+    /// * `RecoveryError::NotAGuardian` if the `keypair` is not one of this
+    ///   sharing's guardians.
+    pub fn decrypt_share(&self, keypair: &elgamal::Keypair) -> Result<DecryptedShare, Error> {
+        let index: u64 = self.index_of(&keypair.public)?;
+        let encryption: &Encryption = &self.shares[index as usize - 1].encryption;
+        let share: RistrettoPoint = keypair.secret.decrypt(encryption);
+        let shared: RistrettoPoint = encryption.encryption - share;
+
+        let mut transcript = decryption_transcript(index, &share);
+        let secrets = batch_dleq::Secrets { k: keypair.secret.as_witness() };
+        let publics = batch_dleq::Publics {
+            G: &RISTRETTO_BASEPOINT_POINT,
+            Y: &keypair.public.0,
+            M: &encryption.commitment,
+            Z: &shared,
+        };
+        let proof = batch_dleq::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(DecryptedShare { index, share, proof })
+    }
+
+    /// Check that a `decrypted` share was decrypted by its guardian.
+    ///
+    /// # Errors
+    ///
+    /// * `RecoveryError::NotAGuardian` if this sharing has no share at the
+    ///   decrypted share's index.
+    /// * `RecoveryError::InvalidDecryption` if the decryption proof did not
+    ///   verify.
+    pub fn verify_share(&self, decrypted: &DecryptedShare) -> Result<(), Error> {
+        if decrypted.index == 0 || decrypted.index as usize > self.shares.len() {
+            return Err(RecoveryError::NotAGuardian.into());
+        }
+
+        let guardian: &elgamal::PublicKey = &self.guardians[decrypted.index as usize - 1];
+        let encryption: &Encryption = &self.shares[decrypted.index as usize - 1].encryption;
+        let shared: RistrettoPoint = encryption.encryption - decrypted.share;
+
+        let mut transcript = decryption_transcript(decrypted.index, &decrypted.share);
+        let publics = batch_dleq::Publics {
+            G: &RISTRETTO_BASEPOINT_POINT,
+            Y: &guardian.0,
+            M: &encryption.commitment,
+            Z: &shared,
+        };
+
+        decrypted.proof.verify(&mut transcript, publics).or(Err(RecoveryError::InvalidDecryption.into()))
+    }
+
+    /// Recover the wallet's root from some guardians' `decrypted` shares.
+    ///
+    /// Shares which do not verify, and repeated shares from the same
+    /// guardian, are skipped, so that the holder may pass along everything
+    /// they received.
+    ///
+    /// # Errors
+    ///
+    /// * `RecoveryError::NotEnoughShares` if fewer than the threshold of
+    ///   distinct, valid shares were given.
+    pub fn recover(&self, decrypted: &[DecryptedShare]) -> Result<DerivationKey, Error> {
+        let mut valid: Vec<&DecryptedShare> = Vec::with_capacity(self.threshold);
+
+        for share in decrypted.iter() {
+            if valid.len() == self.threshold {
+                break;
+            }
+            if valid.iter().any(|v| v.index == share.index) {
+                continue;
+            }
+            if self.verify_share(share).is_ok() {
+                valid.push(share);
+            }
+        }
+
+        if valid.len() < self.threshold {
+            return Err(RecoveryError::NotEnoughShares.into());
+        }
+
+        let mut root: RistrettoPoint = RistrettoPoint::identity();
+
+        for share in valid.iter() {
+            let i = Scalar::from(share.index);
+            let mut lagrange = Scalar::one();
+
+            for other in valid.iter().filter(|o| o.index != share.index) {
+                let j = Scalar::from(other.index);
+
+                lagrange *= j * (j - i).invert();
+            }
+            root += share.share * lagrange;
+        }
+        Ok(derive(&root))
+    }
+}
+
+/// A guardian's decryption of their share, with a proof that it was made
+/// with their key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecryptedShare {
+    pub index: u64,
+    pub share: RistrettoPoint,
+    pub proof: batch_dleq::Proof,
+}
+
+impl DecryptedShare {
+    pub fn from_bytes(bytes: &[u8]) -> Result<DecryptedShare, RecoveryError> {
+        if bytes.len() != SIZEOF_DECRYPTED_SHARE {
+            return Err(RecoveryError::WrongNumberOfBytes);
+        }
+
+        let index: u64 = read_u64(&bytes[00..08]);
+        let share: RistrettoPoint = CompressedRistretto::from_slice(&bytes[08..40]).decompress()?;
+        let proof: batch_dleq::Proof = match deserialize(&bytes[40..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(RecoveryError::WrongNumberOfBytes),
+        };
+
+        Ok(DecryptedShare { index, share, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_DECRYPTED_SHARE);
+
+        v.extend(u64_to_bytes(self.index).iter());
+        v.extend(self.share.compress().as_bytes());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(DecryptedShare, "A valid byte sequence representing a DecryptedShare");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(DecryptedShare);
+impl_try_from_bytes!(DecryptedShare, RecoveryError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use derivation::Purpose;
+    use test_utils::H;
+
+    fn same_root(a: &DerivationKey, b: &DerivationKey) -> bool {
+        a.purpose(Purpose::Nonce).ephemeral(0).as_scalar() == b.purpose(Purpose::Nonce).ephemeral(0).as_scalar()
+    }
+
+    #[test]
+    fn any_threshold_of_guardians_recover_the_wallet() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let keypairs: Vec<elgamal::Keypair> = (0..5).map(|_| elgamal::Keypair::generate(&mut rng)).collect();
+        let guardians: Vec<elgamal::PublicKey> = keypairs.iter().map(|k| k.public).collect();
+        let secret = RecoverySecret::generate(&mut rng);
+
+        let sharing = secret.share(&system_parameters, &guardians, 3, &mut rng).unwrap();
+        let sharing = Sharing::from_bytes(&sharing.to_bytes()).unwrap();
+
+        assert!(sharing.verify(&system_parameters).is_ok());
+
+        let decrypted: Vec<DecryptedShare> = keypairs.iter()
+            .map(|k| sharing.decrypt_share(k).unwrap())
+            .map(|d| DecryptedShare::from_bytes(&d.to_bytes()).unwrap())
+            .collect();
+
+        // Any three guardians will do, in any order.
+        let ours = secret.derivation_key();
+
+        assert!(same_root(&ours, &sharing.recover(&decrypted[..3]).unwrap()));
+        assert!(same_root(&ours, &sharing.recover(&[decrypted[4].clone(), decrypted[1].clone(), decrypted[2].clone()]).unwrap()));
+
+        // Two, even if one sends their share twice, will not.
+        let repeated = [decrypted[0].clone(), decrypted[3].clone(), decrypted[0].clone()];
+
+        assert!(sharing.recover(&repeated).err() == Some(RecoveryError::NotEnoughShares.into()));
+    }
+
+    #[test]
+    fn dishonest_dealers_and_guardians_are_caught() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let keypairs: Vec<elgamal::Keypair> = (0..3).map(|_| elgamal::Keypair::generate(&mut rng)).collect();
+        let guardians: Vec<elgamal::PublicKey> = keypairs.iter().map(|k| k.public).collect();
+        let secret = RecoverySecret::generate(&mut rng);
+        let sharing = secret.share(&system_parameters, &guardians, 2, &mut rng).unwrap();
+
+        assert!(secret.share(&system_parameters, &guardians, 4, &mut rng).is_err());
+
+        // A share swapped for another is not from the committed polynomial.
+        let mut swapped = sharing.clone();
+
+        swapped.shares.swap(0, 1);
+
+        assert!(swapped.verify(&system_parameters) == Err(RecoveryError::InvalidSharing.into()));
+
+        // A guardian lying about their share is skipped, and the rest are
+        // enough to recover.
+        let mut lying = sharing.decrypt_share(&keypairs[0]).unwrap();
+
+        lying.share += RISTRETTO_BASEPOINT_POINT;
+
+        assert!(sharing.verify_share(&lying) == Err(RecoveryError::InvalidDecryption.into()));
+
+        let decrypted = [lying, sharing.decrypt_share(&keypairs[1]).unwrap(), sharing.decrypt_share(&keypairs[2]).unwrap()];
+
+        assert!(same_root(&secret.derivation_key(), &sharing.recover(&decrypted).unwrap()));
+        assert!(sharing.decrypt_share(&elgamal::Keypair::generate(&mut rng)).is_err());
+    }
+}
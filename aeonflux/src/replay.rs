@@ -0,0 +1,496 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Recording gate sessions, and replaying them to find where they failed.
+//!
+//! Either side of a gate session may keep a `Recorder`, and `record()` every
+//! `GateMessage` it sends or receives, along with the time, into a `Bundle`
+//! which can be sent to whoever is debugging the failure.  A bundle always
+//! holds the public system and issuer parameters.  It holds secrets only if
+//! they were added with `Recorder::with_issuer()` or
+//! `Recorder::with_holder()`, and `Bundle::strip_secrets()` removes them
+//! again before the bundle leaves a trusted machine.
+//!
+//! `replay()` re-executes every check which the recorded session made, in
+//! order, and stops at the first one which fails:
+//!
+//! * an issuance's proof of correct issuance is checked with the issuer's
+//!   public parameters, as `User::obtain_finish()` would;
+//! * with the issuer's secrets, requests are re-issued, checking a blind
+//!   request's proof of well-formed ciphertexts, and presentations are
+//!   verified again, and checked against the verdict which was recorded;
+//! * with the holder's secrets, a blind issuance's proof is checked, and
+//!   the ciphertexts decrypted, as `User::blind_obtain_finish()` would.
+//!
+//! Checks whose secrets were stripped are skipped, and counted as such.
+//! Replay is deterministic: re-issuing draws its randomness from a fixed
+//! seed, and its results are discarded.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use credential::RevealedAttribute;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use errors::ReplayError;
+use gate::GateMessage;
+use issuer::Issuer;
+use issuer::IssuerParameters;
+use parameters::SIZEOF_SYSTEM_PARAMETERS;
+use parameters::SystemParameters;
+use rng::DeterministicRng;
+use user::User;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// Read a length-prefixed field at `offset` in `bytes`, returning it and the
+/// offset following it.
+fn read_field(bytes: &[u8], offset: usize) -> Result<(&[u8], usize), ReplayError> {
+    if bytes.len() < offset + 8 {
+        return Err(ReplayError::WrongNumberOfBytes);
+    }
+
+    let length: usize = read_u64(&bytes[offset..offset + 8]) as usize;
+    let start: usize = offset + 8;
+
+    if bytes.len() - start < length {
+        return Err(ReplayError::WrongNumberOfBytes);
+    }
+    Ok((&bytes[start..start + length], start + length))
+}
+
+fn write_field(v: &mut Vec<u8>, field: &[u8]) {
+    v.extend(u64_to_bytes(field.len() as u64).iter());
+    v.extend(field);
+}
+
+/// Which side of a session sent a message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Sender {
+    Client,
+    Server,
+}
+
+impl Sender {
+    fn to_byte(&self) -> u8 {
+        match *self {
+            Sender::Client => 0,
+            Sender::Server => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Sender, ReplayError> {
+        match byte {
+            0 => Ok(Sender::Client),
+            1 => Ok(Sender::Server),
+            _ => Err(ReplayError::UnknownSender),
+        }
+    }
+}
+
+/// One recorded message.
+///
+/// The message is kept as it was encoded, so that one which could not be
+/// decoded is still recorded, and its decoding replayed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub sender: Sender,
+    pub now: u64,
+    pub message: Vec<u8>,
+}
+
+/// A recorded session, with or without the secrets needed to replay every
+/// check made in it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Bundle {
+    pub system_parameters: SystemParameters,
+    pub issuer_parameters: IssuerParameters,
+    /// The encoded `Issuer`, if its secrets were recorded.
+    pub issuer: Option<Vec<u8>>,
+    /// The encoded `User`, and the plaintexts of its blind requests, if the
+    /// holder's secrets were recorded.
+    pub holder: Option<(Vec<u8>, Vec<RevealedAttribute>)>,
+    pub steps: Vec<Step>,
+}
+
+impl Bundle {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Bundle, ReplayError> {
+        if bytes.len() < SIZEOF_SYSTEM_PARAMETERS {
+            return Err(ReplayError::WrongNumberOfBytes);
+        }
+
+        let system_parameters = SystemParameters::from_bytes(&bytes[..SIZEOF_SYSTEM_PARAMETERS])?;
+        let (field, offset) = read_field(bytes, SIZEOF_SYSTEM_PARAMETERS)?;
+        let issuer_parameters = IssuerParameters::from_bytes(field)?;
+        let (field, offset) = read_field(bytes, offset)?;
+        let issuer: Option<Vec<u8>> = if field.is_empty() { None } else { Some(field.to_vec()) };
+        let (field, mut offset) = read_field(bytes, offset)?;
+
+        let holder: Option<(Vec<u8>, Vec<RevealedAttribute>)> = if field.is_empty() {
+            None
+        } else {
+            let (attribute_bytes, next) = read_field(bytes, offset)?;
+
+            if attribute_bytes.len() % 32 != 0 {
+                return Err(ReplayError::WrongNumberOfBytes);
+            }
+
+            let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(attribute_bytes.len() / 32);
+
+            for chunk in attribute_bytes.chunks(32) {
+                let mut tmp = [0u8; 32];
+
+                tmp.copy_from_slice(chunk);
+                attributes.push(Scalar::from_canonical_bytes(tmp).ok_or(CredentialError::ScalarFormatError)?);
+            }
+            offset = next;
+
+            Some((field.to_vec(), attributes))
+        };
+
+        let mut steps: Vec<Step> = Vec::new();
+
+        while offset < bytes.len() {
+            if bytes.len() < offset + 9 {
+                return Err(ReplayError::WrongNumberOfBytes);
+            }
+
+            let sender = Sender::from_byte(bytes[offset])?;
+            let now: u64 = read_u64(&bytes[offset + 1..offset + 9]);
+            let (message, next) = read_field(bytes, offset + 9)?;
+
+            steps.push(Step { sender, now, message: message.to_vec() });
+            offset = next;
+        }
+
+        Ok(Bundle { system_parameters, issuer_parameters, issuer, holder, steps })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.system_parameters.to_bytes());
+        write_field(&mut v, &self.issuer_parameters.to_bytes());
+
+        match self.issuer {
+            Some(ref issuer) => write_field(&mut v, issuer),
+            None             => write_field(&mut v, &[]),
+        }
+        match self.holder {
+            Some((ref user, ref attributes)) => {
+                let mut attribute_bytes: Vec<u8> = Vec::with_capacity(32 * attributes.len());
+
+                for attribute in attributes.iter() {
+                    attribute_bytes.extend(attribute.as_bytes());
+                }
+                write_field(&mut v, user);
+                write_field(&mut v, &attribute_bytes);
+            },
+            None => write_field(&mut v, &[]),
+        }
+        for step in self.steps.iter() {
+            v.push(step.sender.to_byte());
+            v.extend(u64_to_bytes(step.now).iter());
+            write_field(&mut v, &step.message);
+        }
+        v
+    }
+
+    /// Remove the issuer's and the holder's secrets, leaving only what was
+    /// public or sent over the wire.
+    pub fn strip_secrets(&mut self) {
+        if let Some(ref mut issuer) = self.issuer {
+            for byte in issuer.iter_mut() {
+                *byte = 0;
+            }
+        }
+        if let Some((ref mut user, ref mut attributes)) = self.holder {
+            for byte in user.iter_mut() {
+                *byte = 0;
+            }
+            for attribute in attributes.iter_mut() {
+                *attribute = Scalar::zero();
+            }
+        }
+        self.issuer = None;
+        self.holder = None;
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Bundle, "A valid byte sequence representing a Bundle");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Bundle);
+impl_try_from_bytes!(Bundle, ReplayError);
+
+/// Records the messages of a session into a `Bundle`.
+pub struct Recorder {
+    bundle: Bundle,
+}
+
+impl Recorder {
+    /// Start recording a session under the given public parameters.
+    pub fn new(system_parameters: SystemParameters, issuer_parameters: IssuerParameters) -> Recorder {
+        Recorder {
+            bundle: Bundle { system_parameters, issuer_parameters, issuer: None, holder: None, steps: Vec::new() },
+        }
+    }
+
+    /// Also record the `issuer`'s secrets, so that replay can re-issue and
+    /// re-verify.
+    pub fn with_issuer(mut self, issuer: &Issuer) -> Recorder {
+        self.bundle.issuer = Some(issuer.to_bytes());
+        self
+    }
+
+    /// Also record the holder's secrets: their `user`, and the plaintext
+    /// `attributes` of their blind request, if any.
+    pub fn with_holder(mut self, user: &User, attributes: &[RevealedAttribute]) -> Recorder {
+        self.bundle.holder = Some((user.to_bytes(), attributes.to_vec()));
+        self
+    }
+
+    /// Record a `message` sent by `sender` at time `now`.
+    pub fn record(&mut self, sender: Sender, now: u64, message: &GateMessage) {
+        self.record_bytes(sender, now, &message.to_bytes());
+    }
+
+    /// Record an encoded `message`, e.g. one received which did not decode.
+    pub fn record_bytes(&mut self, sender: Sender, now: u64, message: &[u8]) {
+        self.bundle.steps.push(Step { sender, now, message: message.to_vec() });
+    }
+
+    /// Stop recording, returning the `Bundle`.
+    pub fn finish(self) -> Bundle {
+        self.bundle
+    }
+}
+
+/// A check made while replaying a session.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Check {
+    /// That a recorded message decoded.
+    Decode,
+    /// That the issuer would issue for a request over revealed attributes.
+    Issue,
+    /// That an issuance's proof of correct issuance verified.
+    IssuanceProof,
+    /// That a blind request's proof of well-formed ciphertexts verified, so
+    /// that the issuer would issue for it.
+    BlindRequestProof,
+    /// That a blind issuance's proof verified, and that it was made over the
+    /// holder's ciphertexts.
+    BlindIssuanceProof,
+    /// That a presentation verified.
+    Presentation,
+    /// That the recorded verdict was the one the presentation deserved.
+    Verdict,
+}
+
+/// The first check which failed in a replay.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Failure {
+    /// The index of the step, in `Bundle::steps`, at which the check failed.
+    pub step: usize,
+    pub check: Check,
+    pub reason: Error,
+}
+
+/// The result of replaying a `Bundle`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Replayed {
+    /// The checks which passed, before the failure if there was one.
+    pub passed: usize,
+    /// The checks which could not be made without stripped secrets.
+    pub skipped: Vec<(usize, Check)>,
+    /// The first check which failed, if any did.
+    pub failure: Option<Failure>,
+}
+
+/// Replay every check made in the session recorded in `bundle`, stopping at
+/// the first which fails.
+///
+/// # Errors
+///
+/// * `ReplayError::Credential` if the bundle's recorded secrets could not
+///   be decoded.
+pub fn replay(bundle: &Bundle) -> Result<Replayed, Error> {
+    let issuer: Option<Issuer> = match bundle.issuer {
+        Some(ref bytes) => Some(Issuer::from_bytes(bytes)?),
+        None            => None,
+    };
+    if let Some((ref user, _)) = bundle.holder {
+        User::from_bytes(user)?;
+    }
+
+    let mut rng = DeterministicRng::new([0u8; 32]);
+    let mut replayed = Replayed { passed: 0, skipped: Vec::new(), failure: None };
+    // Whether the last presentation verified when replayed, if it could be.
+    let mut deserved: Option<bool> = None;
+
+    for (index, step) in bundle.steps.iter().enumerate() {
+        let message: GateMessage = match GateMessage::from_bytes(&step.message) {
+            Ok(x)  => x,
+            Err(e) => {
+                replayed.failure = Some(Failure { step: index, check: Check::Decode, reason: e.into() });
+                break;
+            },
+        };
+        replayed.passed += 1;
+
+        let (check, result): (Check, Option<Result<(), Error>>) = match message {
+            GateMessage::IssuanceRequest(ref request) => {
+                (Check::Issue, issuer.as_ref().map(|i| i.issue(request, &mut rng).map(|_| ())))
+            },
+            GateMessage::Issuance(ref issuance) => {
+                let mut user = User::new(bundle.system_parameters, bundle.issuer_parameters.clone(), None);
+
+                (Check::IssuanceProof, Some(user.obtain_finish(Some(issuance))))
+            },
+            GateMessage::BlindIssuanceRequest(ref request) => {
+                (Check::BlindRequestProof,
+                 issuer.as_ref().map(|i| i.blind_issue(request, &mut rng).map(|_| ())))
+            },
+            GateMessage::BlindIssuance(ref issuance) => {
+                let result = match bundle.holder {
+                    Some((ref user, ref attributes)) => {
+                        let mut user = User::from_bytes(user)?;
+
+                        Some(user.blind_obtain_finish(Some(issuance), attributes))
+                    },
+                    None => None,
+                };
+                (Check::BlindIssuanceProof, result)
+            },
+            GateMessage::Presentation(ref presentation) => {
+                let result = issuer.as_ref().map(|i| i.verify(presentation).map(|_| ()));
+
+                deserved = result.as_ref().map(|r| r.is_ok());
+
+                (Check::Presentation, result)
+            },
+            GateMessage::Verdict(verdict) => {
+                let result = deserved.map(|d| if d == verdict { Ok(()) } else { Err(Error::Protocol) });
+
+                (Check::Verdict, result)
+            },
+            // Report tokens are checked by the desk which signed them, whose
+            // keys are not recorded.
+            GateMessage::ReportablePresentation(_) |
+            GateMessage::ReportToken(_) => continue,
+        };
+
+        match result {
+            None         => replayed.skipped.push((index, check)),
+            Some(Ok(())) => replayed.passed += 1,
+            Some(Err(e)) => {
+                replayed.failure = Some(Failure { step: index, check, reason: e });
+                break;
+            },
+        }
+    }
+    Ok(replayed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use gate::GateClient;
+    use gate::GateConfig;
+    use gate::GateServer;
+    use test_utils::H;
+
+    /// Run an issuance and a presentation to `verifier`, recording both.
+    fn record(issuer: &Issuer, verifier: &Issuer) -> Bundle {
+        let mut rng = thread_rng();
+        let user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let mut client = GateClient::new(user, GateConfig::default());
+        let mut recorder = Recorder::new(issuer.system_parameters, issuer.get_issuer_parameters())
+            .with_issuer(verifier);
+
+        let request = client.start_issuance(vec![Scalar::random(&mut rng)], 0).unwrap();
+        let response = GateServer::open(issuer, GateConfig::default(), 0).handle(&request, 1, &mut rng).unwrap();
+
+        recorder.record(Sender::Client, 0, &request);
+        recorder.record(Sender::Server, 1, &response);
+        client.handle(&response, 2).unwrap();
+
+        let presentation = client.start_presentation(3, &mut rng).unwrap();
+        let verdict = GateServer::open(verifier, GateConfig::default(), 3).handle(&presentation, 4, &mut rng).unwrap();
+
+        recorder.record(Sender::Client, 3, &presentation);
+        recorder.record(Sender::Server, 4, &verdict);
+        recorder.finish()
+    }
+
+    #[test]
+    fn replay_pinpoints_the_first_failing_check() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let other = Issuer::create(SystemParameters::from(H), &mut rng);
+
+        // A session which succeeded replays cleanly.
+        let bundle = Bundle::from_bytes(&record(&issuer, &issuer).to_bytes()).unwrap();
+        let replayed = replay(&bundle).unwrap();
+
+        assert!(replayed.failure.is_none());
+        assert!(replayed.skipped.is_empty());
+
+        // A presentation shown to the wrong verifier fails at verification,
+        // not at the verdict it was rightly given.
+        let replayed = replay(&record(&issuer, &other)).unwrap();
+
+        assert!(replayed.failure.map(|f| (f.step, f.check)) == Some((2, Check::Presentation)));
+
+        // A corrupted message is pinpointed where it was received.
+        let mut corrupted = record(&issuer, &issuer);
+
+        corrupted.steps[1].message[40] ^= 1;
+
+        let failure = replay(&corrupted).unwrap().failure.unwrap();
+
+        assert!(failure.step == 1);
+        assert!(failure.check == Check::Decode || failure.check == Check::IssuanceProof);
+    }
+
+    #[test]
+    fn stripped_bundles_skip_secret_checks() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let mut bundle = record(&issuer, &issuer);
+
+        bundle.strip_secrets();
+
+        let bundle = Bundle::from_bytes(&bundle.to_bytes()).unwrap();
+
+        assert!(bundle.issuer.is_none());
+        assert!(!bundle.to_bytes().windows(32).any(|w| w == issuer.keypair.secret.x0.as_bytes()));
+
+        let replayed = replay(&bundle).unwrap();
+
+        // The issuance proof is public, but re-issuing, re-verifying the
+        // presentation, and so checking its verdict are not.
+        assert!(replayed.failure.is_none());
+        assert!(replayed.skipped == vec![(0, Check::Issue), (2, Check::Presentation), (3, Check::Verdict)]);
+    }
+}
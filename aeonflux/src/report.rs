@@ -0,0 +1,330 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Anonymous report tokens, issued to holders upon entry.
+//!
+//! A verifier running an entry, e.g. an event or a venue, may let the
+//! holders it admitted later submit feedback or reports about it, without
+//! learning which of them did so.  To enter, a holder sends a
+//! `ReportRequest`: a presentation of their credential with its nullifier in
+//! the entry's scope, as in `nullifier`, together with one blinded token, as
+//! in `tokens`.  The verifier's `ReportDesk` checks the presentation, refuses
+//! a second entry with the same nullifier, and signs the token.
+//!
+//! The holder unblinds it into a `ReportToken`, which may be redeemed exactly
+//! once, bound to the feedback it accompanies.  Since the token was blinded,
+//! its redemption cannot be linked to the entry it was issued at, nor to the
+//! holder's credential; it shows only that whoever sent the feedback entered.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::VerifiedCredential;
+use errors::Error;
+use errors::ReportError;
+use errors::TokenError;
+use hashing::Hasher;
+use issuer::Issuer;
+use nonces::Nonces;
+use nullifier;
+use nullifier::ScopedPresentation;
+use nullifier::encode_scope;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use rate_limit::NullifierStore;
+use tokens::PendingToken;
+use tokens::SpentTokens;
+use tokens::Token;
+use tokens::TokenKeypair;
+use tokens::TokenRedemption;
+use tokens::TokenRequest;
+use tokens::TokenResponse;
+use user::User;
+
+/// The domain separator for the scope of an entry's nullifiers.
+const REPORT_SCOPE_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux report entry v1";
+
+/// The domain separator used when binding a redemption to its feedback.
+const REPORT_FEEDBACK_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux report feedback v1";
+
+/// The nullifier scope of an `entry`, which holders' nullifiers at the entry
+/// are derived in, as in `nullifier::scoped_nullifier()`.
+pub fn entry_scope(entry: &[u8]) -> Result<Vec<u8>, Error> {
+    encode_scope(&[REPORT_SCOPE_DOMAIN_SEPARATOR, entry])
+}
+
+/// The data a report token's redemption is bound to, for some `feedback`.
+fn feedback_binding(feedback: &[u8]) -> [u8; 32] {
+    let mut hash = Hasher::new(REPORT_FEEDBACK_DOMAIN_SEPARATOR);
+
+    hash.input(feedback);
+    hash.finalize_key()
+}
+
+/// A holder's request to enter, and to be given a report token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReportRequest {
+    pub presentation: ScopedPresentation,
+    pub token: TokenRequest,
+}
+
+impl ReportRequest {
+    /// Present the `user`'s credential at an `entry`, and request a report
+    /// token for it.
+    ///
+    /// # Returns
+    ///
+    /// The `ReportRequest` to send to the verifier, and the `PendingReport`
+    /// which the holder must keep until the response arrives.
+    pub fn create<R>(
+        user: &User,
+        entry: &[u8],
+        rng: &mut R,
+    ) -> Result<(ReportRequest, PendingReport), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let nonces: Nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+        let presentation: ScopedPresentation = nullifier::show(user, &entry_scope(entry)?, &nonces, rng)?;
+        let (token, pending) = TokenRequest::create(1, rng)?;
+
+        Ok((ReportRequest { presentation, token: token.clone() }, PendingReport { request: token, pending }))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<ReportRequest, ReportError> {
+        let count: usize = *bytes.get(0).ok_or(ReportError::WrongNumberOfBytes)? as usize;
+        let end: usize = 1 + 32 * count;
+
+        if bytes.len() < end {
+            return Err(ReportError::WrongNumberOfBytes);
+        }
+
+        let token = TokenRequest::from_bytes(&bytes[..end])?;
+        let presentation = ScopedPresentation::from_bytes(&bytes[end..])?;
+
+        Ok(ReportRequest { presentation, token })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = self.token.to_bytes();
+
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ReportRequest, "A valid byte sequence representing a ReportRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ReportRequest);
+impl_try_from_bytes!(ReportRequest, ReportError);
+
+/// A holder's secret state for a report token awaiting signature.
+#[derive(Clone, Debug)]
+pub struct PendingReport {
+    request: TokenRequest,
+    pending: Vec<PendingToken>,
+}
+
+impl PendingReport {
+    /// Verify the verifier's `response`, under its report key `public`, and
+    /// unblind the report token.
+    pub fn finish(&self, response: &TokenResponse, public: &RistrettoPoint) -> Result<ReportToken, Error> {
+        let mut tokens: Vec<Token> = response.unblind(&self.request, &self.pending, public)?;
+
+        match tokens.pop() {
+            Some(x) => Ok(ReportToken(x)),
+            None    => Err(ReportError::Token(TokenError::WrongNumberOfTokens).into()),
+        }
+    }
+}
+
+/// A one-time token for submitting feedback about an entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReportToken(pub Token);
+
+impl ReportToken {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ReportToken, ReportError> {
+        Ok(ReportToken(Token::from_bytes(bytes)?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Redeem this token to accompany some `feedback`.
+    pub fn redeem(&self, feedback: &[u8]) -> TokenRedemption {
+        self.0.redeem(&feedback_binding(feedback))
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(ReportToken, "A valid byte sequence representing a ReportToken");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(ReportToken);
+impl_try_from_bytes!(ReportToken, ReportError);
+
+/// A verifier's issuance and redemption of report tokens for one entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReportDesk {
+    pub entry: Vec<u8>,
+    keypair: TokenKeypair,
+}
+
+impl ReportDesk {
+    /// Open a desk for an `entry`, with a fresh report key.
+    pub fn new<R>(entry: &[u8], csprng: &mut R) -> ReportDesk
+    where
+        R: RngCore + CryptoRng,
+    {
+        ReportDesk { entry: entry.to_vec(), keypair: TokenKeypair::generate(csprng) }
+    }
+
+    /// The public report key, which holders unblind their tokens under.
+    pub fn public(&self) -> RistrettoPoint {
+        self.keypair.public
+    }
+
+    /// Admit the holder of a `request`, and sign their report token.
+    ///
+    /// # Errors
+    ///
+    /// * `ReportError::Credential` if the presentation, or its nullifier in
+    ///   this entry's scope, did not verify with the `issuer`.
+    /// * `ReportError::Token(TokenError::WrongNumberOfTokens)` unless exactly
+    ///   one token was requested.
+    /// * `ReportError::AlreadyAdmitted` if the nullifier is in `entries`.
+    pub fn admit<S>(
+        &self,
+        issuer: &Issuer,
+        request: &ReportRequest,
+        entries: &mut S,
+    ) -> Result<(VerifiedCredential, TokenResponse), Error>
+    where
+        S: NullifierStore + ?Sized,
+    {
+        let verified = nullifier::verify(issuer, &entry_scope(&self.entry)?, &request.presentation)?;
+
+        if request.token.blinded.len() != 1 {
+            return Err(ReportError::Token(TokenError::WrongNumberOfTokens).into());
+        }
+        if !entries.insert(&request.presentation.nullifier) {
+            return Err(ReportError::AlreadyAdmitted.into());
+        }
+
+        Ok((verified, self.keypair.sign(&request.token)?))
+    }
+
+    /// Accept some `feedback` accompanied by a report token's `redemption`.
+    ///
+    /// # Errors
+    ///
+    /// * `ReportError::Token(TokenError::InvalidMac)` if the token was not
+    ///   issued by this desk, or was redeemed for other feedback.
+    /// * `ReportError::Token(TokenError::DoubleSpend)` if the token was in
+    ///   the `spent` store.
+    pub fn redeem<T>(
+        &self,
+        redemption: &TokenRedemption,
+        feedback: &[u8],
+        spent: &mut T,
+    ) -> Result<(), Error>
+    where
+        T: SpentTokens,
+    {
+        Ok(self.keypair.redeem(redemption, &feedback_binding(feedback), spent)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use parameters::SystemParameters;
+    use rate_limit::Nullifier;
+    use test_utils::H;
+    use tokens::TokenPreimage;
+
+    fn setup() -> (Issuer, User) {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        (issuer, user)
+    }
+
+    #[test]
+    fn entry_yields_one_report() {
+        let mut rng = thread_rng();
+        let (issuer, user) = setup();
+        let desk = ReportDesk::new(b"concert 2024-06-01", &mut rng);
+        let mut entries: HashSet<Nullifier> = HashSet::new();
+        let mut spent: HashSet<TokenPreimage> = HashSet::new();
+
+        let (request, pending) = ReportRequest::create(&user, &desk.entry, &mut rng).unwrap();
+        let request = ReportRequest::from_bytes(&request.to_bytes()).unwrap();
+        let (_, response) = desk.admit(&issuer, &request, &mut entries).unwrap();
+        let token = pending.finish(&TokenResponse::from_bytes(&response.to_bytes()).unwrap(), &desk.public()).unwrap();
+        let token = ReportToken::from_bytes(&token.to_bytes()).unwrap();
+
+        // The token's preimage is chosen by the holder, and never shown at
+        // entry, so its redemption does not reveal the entry.
+        assert!(!request.to_bytes().windows(32).any(|w| w == &token.0.preimage.0[..]));
+
+        let redemption = token.redeem(b"the queue was too long");
+
+        assert!(desk.redeem(&redemption, b"the music was too loud", &mut spent).err() ==
+                Some(ReportError::Token(TokenError::InvalidMac)));
+        assert!(desk.redeem(&redemption, b"the queue was too long", &mut spent).is_ok());
+        assert!(desk.redeem(&redemption, b"the queue was too long", &mut spent).err() ==
+                Some(ReportError::Token(TokenError::DoubleSpend)));
+    }
+
+    #[test]
+    fn entries_are_not_repeated() {
+        let mut rng = thread_rng();
+        let (issuer, user) = setup();
+        let desk = ReportDesk::new(b"concert 2024-06-01", &mut rng);
+        let other = ReportDesk::new(b"concert 2024-06-02", &mut rng);
+        let mut entries: HashSet<Nullifier> = HashSet::new();
+
+        let (first, _) = ReportRequest::create(&user, &desk.entry, &mut rng).unwrap();
+        let (second, _) = ReportRequest::create(&user, &desk.entry, &mut rng).unwrap();
+
+        // A presentation for one entry is refused at another.
+        assert!(other.admit(&issuer, &first, &mut HashSet::<Nullifier>::new()).is_err());
+
+        assert!(desk.admit(&issuer, &first, &mut entries).is_ok());
+        assert!(desk.admit(&issuer, &second, &mut entries).err() == Some(ReportError::AlreadyAdmitted.into()));
+
+        // Nor may one entry ask for several tokens.
+        let (mut greedy, _) = ReportRequest::create(&user, &other.entry, &mut rng).unwrap();
+
+        greedy.token = TokenRequest::create(2, &mut rng).unwrap().0;
+
+        assert!(other.admit(&issuer, &greedy, &mut HashSet::<Nullifier>::new()).err() ==
+                Some(ReportError::Token(TokenError::WrongNumberOfTokens)));
+    }
+}
@@ -0,0 +1,928 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Revocation registries, and signed deltas for distributing their updates.
+//!
+//! A `RevocationRegistry` is a named set of revoked identifiers at some
+//! epoch.  Rather than have every gate download the whole registry whenever
+//! it changes, its administrator publishes a `RevocationDelta` from each
+//! epoch to the next, listing the identifiers added and removed along with a
+//! digest of the registry which results, and signs it with a
+//! `RegistryKeypair` as a `SignedRevocationDelta`.
+//!
+//! A gate applies each delta in turn.  A delta is only applied if its
+//! signature verifies, if it starts from the gate's current epoch, and if
+//! the result matches its digest, so a gate which missed an update, or whose
+//! copy diverged, refuses the delta and knows to fetch the whole registry
+//! again.  The last `MAXIMUM_ROLLBACK_DEPTH` applied deltas are kept, so that
+//! a gate may `rollback()` to an earlier epoch, e.g. when the administrator
+//! retracts an update.
+//!
+//! Identifiers may also be suspended, rather than revoked, for some
+//! `Suspension`, a range of time after which the hold lapses on its own,
+//! e.g. while a moderation decision is pending.  A registry's `standing()`
+//! for an identifier tells suspension apart from revocation, and a gate's
+//! `SuspensionPolicy` decides what `Access` a suspended holder keeps.
+//! Suspensions travel in the same deltas as revocations; a registry or delta
+//! without any is encoded exactly as before suspensions existed.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeMap;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeSet;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeSet;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use errors::Error;
+use errors::RevocationError;
+use errors::TrustError;
+use hashing::Hasher;
+use hashing::SIZEOF_HASHED_KEY;
+use trust::SIZEOF_REGISTRY_SIGNATURE;
+use trust::RegistryKeypair;
+use trust::RegistrySignature;
+
+/// The maximum length of a registry name, or of a revoked identifier, in bytes.
+pub const MAXIMUM_SIZEOF_REVOCATION_ID: usize = 255;
+
+/// The number of applied deltas a `RevocationRegistry` keeps for rolling back.
+pub const MAXIMUM_ROLLBACK_DEPTH: usize = 24;
+
+/// The transcript label for signatures over a `RevocationDelta`.
+const DELTA_SIGNATURE_LABEL: &'static [u8] = b"AEONFLUX REVOCATION DELTA";
+
+/// A cursor for decoding the canonical encoding.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], RevocationError> {
+        if self.bytes.len() < n {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, RevocationError> {
+        let mut x: u64 = 0;
+
+        for (i, byte) in self.take(n)?.iter().enumerate() {
+            x |= (*byte as u64) << (8 * i);
+        }
+        Ok(x)
+    }
+
+    fn identifier(&mut self) -> Result<Vec<u8>, RevocationError> {
+        let length: usize = self.uint(1)? as usize;
+
+        Ok(self.take(length)?.to_vec())
+    }
+
+    fn identifiers(&mut self) -> Result<Vec<Vec<u8>>, RevocationError> {
+        let count: u64 = self.uint(4)?;
+        let mut ids: Vec<Vec<u8>> = Vec::new();
+
+        for _ in 0..count {
+            ids.push(self.identifier()?);
+        }
+        Ok(ids)
+    }
+
+    fn suspensions(&mut self) -> Result<Vec<(Vec<u8>, Suspension)>, RevocationError> {
+        let count: u64 = self.uint(4)?;
+        let mut suspensions: Vec<(Vec<u8>, Suspension)> = Vec::new();
+
+        for _ in 0..count {
+            let id: Vec<u8> = self.identifier()?;
+            let from: u64 = self.uint(8)?;
+            let until: u64 = self.uint(8)?;
+
+            suspensions.push((id, Suspension { from, until }));
+        }
+        Ok(suspensions)
+    }
+}
+
+fn push_uint(v: &mut Vec<u8>, x: u64, n: usize) {
+    for i in 0..n {
+        v.push((x >> (8 * i)) as u8);
+    }
+}
+
+fn push_identifier(v: &mut Vec<u8>, id: &[u8]) {
+    v.push(id.len() as u8);
+    v.extend(id);
+}
+
+fn push_suspensions<'a, I>(v: &mut Vec<u8>, count: usize, suspensions: I)
+where
+    I: Iterator<Item = (&'a Vec<u8>, &'a Suspension)>,
+{
+    push_uint(v, count as u64, 4);
+
+    for (id, suspension) in suspensions {
+        push_identifier(v, id);
+        push_uint(v, suspension.from, 8);
+        push_uint(v, suspension.until, 8);
+    }
+}
+
+fn check_identifier(id: &[u8]) -> Result<(), RevocationError> {
+    if id.is_empty() || id.len() > MAXIMUM_SIZEOF_REVOCATION_ID {
+        return Err(RevocationError::InvalidIdentifier);
+    }
+    Ok(())
+}
+
+/// A temporary hold on an identifier, in effect from `from` until, but not
+/// including, `until`, both in seconds since the Unix epoch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Suspension {
+    pub from: u64,
+    pub until: u64,
+}
+
+impl Suspension {
+    /// Whether this suspension is in effect at time `now`.
+    pub fn is_in_effect(&self, now: u64) -> bool {
+        self.from <= now && now < self.until
+    }
+
+    fn check(&self) -> Result<(), RevocationError> {
+        if self.from >= self.until {
+            return Err(RevocationError::InvalidSuspension);
+        }
+        Ok(())
+    }
+}
+
+/// The standing of an identifier in a registry at some time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Standing {
+    /// The identifier is neither revoked nor suspended.
+    Good,
+    /// The identifier is under a suspension which is in effect.
+    Suspended(Suspension),
+    /// The identifier is revoked.  This takes precedence over any suspension.
+    Revoked,
+}
+
+/// What a gate allows a holder to do.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Access {
+    /// Everything the holder's credential allows.
+    Full,
+    /// Some lesser access chosen by the gate, e.g. read-only.
+    Restricted,
+    /// Nothing.
+    Denied,
+}
+
+/// A gate's policy for holders whose identifiers are suspended.
+///
+/// A revoked holder is always `Access::Denied`, and a holder in good
+/// standing always `Access::Full`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct SuspensionPolicy {
+    /// The access kept by a suspended holder.
+    pub suspended: Access,
+}
+
+impl Default for SuspensionPolicy {
+    fn default() -> SuspensionPolicy {
+        SuspensionPolicy { suspended: Access::Restricted }
+    }
+}
+
+impl SuspensionPolicy {
+    /// The access allowed to the holder of `id` under a `registry` at time
+    /// `now`.
+    pub fn access(&self, registry: &RevocationRegistry, id: &[u8], now: u64) -> Access {
+        match registry.standing(id, now) {
+            Standing::Good         => Access::Full,
+            Standing::Suspended(_) => self.suspended,
+            Standing::Revoked      => Access::Denied,
+        }
+    }
+}
+
+/// A change to a revocation registry from one epoch to the next.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevocationDelta {
+    /// The name of the registry this delta applies to.
+    pub registry: Vec<u8>,
+    /// The epoch this delta applies to.
+    pub from_epoch: u64,
+    /// The epoch of the registry after this delta is applied.
+    pub to_epoch: u64,
+    /// The identifiers newly revoked, in order.
+    pub added: Vec<Vec<u8>>,
+    /// The identifiers no longer revoked, in order.
+    pub removed: Vec<Vec<u8>>,
+    /// The identifiers newly suspended, or whose suspensions changed, in
+    /// order.
+    pub suspended: Vec<(Vec<u8>, Suspension)>,
+    /// The identifiers no longer suspended, in order.
+    pub lifted: Vec<Vec<u8>>,
+    /// The `RevocationRegistry::digest()` of the registry after this delta is
+    /// applied.
+    pub digest: [u8; SIZEOF_HASHED_KEY],
+}
+
+impl RevocationDelta {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RevocationDelta, RevocationError> {
+        let mut reader = Reader { bytes };
+
+        let registry: Vec<u8> = reader.identifier()?;
+        let from_epoch: u64 = reader.uint(8)?;
+        let to_epoch: u64 = reader.uint(8)?;
+        let mut digest = [0u8; SIZEOF_HASHED_KEY];
+
+        digest.copy_from_slice(reader.take(SIZEOF_HASHED_KEY)?);
+
+        let added: Vec<Vec<u8>> = reader.identifiers()?;
+        let removed: Vec<Vec<u8>> = reader.identifiers()?;
+        let mut suspended: Vec<(Vec<u8>, Suspension)> = Vec::new();
+        let mut lifted: Vec<Vec<u8>> = Vec::new();
+
+        // The suspension sections are only present, and then never both
+        // empty, when the delta changes any suspension.
+        if !reader.bytes.is_empty() {
+            suspended = reader.suspensions()?;
+            lifted = reader.identifiers()?;
+
+            if suspended.is_empty() && lifted.is_empty() {
+                return Err(RevocationError::Malformed);
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let delta = RevocationDelta { registry, from_epoch, to_epoch, added, removed, suspended, lifted, digest };
+
+        delta.check()?;
+
+        Ok(delta)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        push_identifier(&mut v, &self.registry);
+        push_uint(&mut v, self.from_epoch, 8);
+        push_uint(&mut v, self.to_epoch, 8);
+        v.extend(self.digest.iter());
+        push_uint(&mut v, self.added.len() as u64, 4);
+
+        for id in self.added.iter() {
+            push_identifier(&mut v, id);
+        }
+        push_uint(&mut v, self.removed.len() as u64, 4);
+
+        for id in self.removed.iter() {
+            push_identifier(&mut v, id);
+        }
+        if !self.suspended.is_empty() || !self.lifted.is_empty() {
+            push_suspensions(&mut v, self.suspended.len(), self.suspended.iter().map(|&(ref id, ref x)| (id, x)));
+            push_uint(&mut v, self.lifted.len() as u64, 4);
+
+            for id in self.lifted.iter() {
+                push_identifier(&mut v, id);
+            }
+        }
+        v
+    }
+
+    /// Check that this delta moves to a later epoch, and that its identifiers
+    /// and suspensions are valid and listed once each, in order.
+    fn check(&self) -> Result<(), RevocationError> {
+        check_identifier(&self.registry)?;
+
+        if self.to_epoch <= self.from_epoch {
+            return Err(RevocationError::WrongEpoch);
+        }
+        for ids in [&self.added, &self.removed, &self.lifted].iter() {
+            for id in ids.iter() {
+                check_identifier(id)?;
+            }
+            if ids.windows(2).any(|w| w[0] >= w[1]) {
+                return Err(RevocationError::Malformed);
+            }
+        }
+        for &(ref id, ref suspension) in self.suspended.iter() {
+            check_identifier(id)?;
+            suspension.check().or(Err(RevocationError::Malformed))?;
+        }
+        if self.suspended.windows(2).any(|w| w[0].0 >= w[1].0) ||
+           self.suspended.iter().any(|&(ref id, _)| self.lifted.binary_search(id).is_ok())
+        {
+            return Err(RevocationError::Malformed);
+        }
+        Ok(())
+    }
+
+    /// Sign this delta with the registry administrator's `keypair`.
+    pub fn sign<R>(self, keypair: &RegistryKeypair, csprng: &mut R) -> SignedRevocationDelta
+    where
+        R: RngCore + CryptoRng,
+    {
+        let signature = keypair.sign(DELTA_SIGNATURE_LABEL, &self.to_bytes(), csprng);
+
+        SignedRevocationDelta { delta: self, signature }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RevocationDelta, "A valid byte sequence representing a RevocationDelta");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RevocationDelta);
+impl_try_from_bytes!(RevocationDelta, RevocationError);
+
+/// A `RevocationDelta` along with a signature over its canonical encoding.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedRevocationDelta {
+    delta: RevocationDelta,
+    pub signature: RegistrySignature,
+}
+
+impl SignedRevocationDelta {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignedRevocationDelta, RevocationError> {
+        if bytes.len() < SIZEOF_REGISTRY_SIGNATURE {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        let split: usize = bytes.len() - SIZEOF_REGISTRY_SIGNATURE;
+        let delta = RevocationDelta::from_bytes(&bytes[..split])?;
+        let signature = match RegistrySignature::from_bytes(&bytes[split..]) {
+            Ok(x)                              => x,
+            Err(TrustError::ScalarFormatError) => return Err(RevocationError::ScalarFormatError),
+            Err(_)                             => return Err(RevocationError::WrongNumberOfBytes),
+        };
+
+        Ok(SignedRevocationDelta { delta, signature })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = self.delta.to_bytes();
+
+        v.extend(self.signature.to_bytes());
+        v
+    }
+
+    /// Verify the signature with the registry administrator's `public` key.
+    ///
+    /// # Returns
+    ///
+    /// The signed `RevocationDelta`.
+    pub fn verify(&self, public: &RistrettoPoint) -> Result<&RevocationDelta, Error> {
+        if self.signature.verify(DELTA_SIGNATURE_LABEL, public, &self.delta.to_bytes()) {
+            Ok(&self.delta)
+        } else {
+            Err(RevocationError::VerificationFailure.into())
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignedRevocationDelta,
+                                         "A valid byte sequence representing a SignedRevocationDelta");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SignedRevocationDelta);
+impl_try_from_bytes!(SignedRevocationDelta, RevocationError);
+
+/// A delta which was applied to a registry, with the suspensions it
+/// replaced, so that it may be rolled back.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Applied {
+    delta: RevocationDelta,
+    replaced: Vec<(Vec<u8>, Suspension)>,
+}
+
+/// A named set of revoked and suspended identifiers, at some epoch.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevocationRegistry {
+    name: Vec<u8>,
+    epoch: u64,
+    revoked: BTreeSet<Vec<u8>>,
+    suspended: BTreeMap<Vec<u8>, Suspension>,
+    /// The deltas applied most recently, oldest first.
+    history: Vec<Applied>,
+}
+
+impl RevocationRegistry {
+    /// The full encoding of this registry, for gates to download when they
+    /// cannot apply a delta.  The rollback history is not included.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RevocationRegistry, RevocationError> {
+        let mut reader = Reader { bytes };
+
+        let name: Vec<u8> = reader.identifier()?;
+        let epoch: u64 = reader.uint(8)?;
+        let ids: Vec<Vec<u8>> = reader.identifiers()?;
+        let mut suspensions: Vec<(Vec<u8>, Suspension)> = Vec::new();
+
+        if !reader.bytes.is_empty() {
+            suspensions = reader.suspensions()?;
+
+            if suspensions.is_empty() {
+                return Err(RevocationError::Malformed);
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(RevocationError::WrongNumberOfBytes);
+        }
+
+        check_identifier(&name)?;
+
+        let mut registry = RevocationRegistry::empty(&name, epoch);
+
+        for id in ids.into_iter() {
+            check_identifier(&id)?;
+
+            if !registry.revoked.insert(id) {
+                return Err(RevocationError::Malformed);
+            }
+        }
+        for (id, suspension) in suspensions.into_iter() {
+            check_identifier(&id)?;
+            suspension.check().or(Err(RevocationError::Malformed))?;
+
+            if registry.suspended.insert(id, suspension).is_some() {
+                return Err(RevocationError::Malformed);
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        push_identifier(&mut v, &self.name);
+        push_uint(&mut v, self.epoch, 8);
+        push_uint(&mut v, self.revoked.len() as u64, 4);
+
+        for id in self.revoked.iter() {
+            push_identifier(&mut v, id);
+        }
+        if !self.suspended.is_empty() {
+            push_suspensions(&mut v, self.suspended.len(), self.suspended.iter());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RevocationRegistry,
+                                         "A valid byte sequence representing a RevocationRegistry");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RevocationRegistry);
+impl_try_from_bytes!(RevocationRegistry, RevocationError);
+
+impl RevocationRegistry {
+    /// Create an empty registry called `name` at some `epoch`.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::InvalidIdentifier` if the `name` is empty or
+    ///   longer than `MAXIMUM_SIZEOF_REVOCATION_ID`.
+    pub fn new(name: &[u8], epoch: u64) -> Result<RevocationRegistry, Error> {
+        check_identifier(name)?;
+
+        Ok(RevocationRegistry::empty(name, epoch))
+    }
+
+    fn empty(name: &[u8], epoch: u64) -> RevocationRegistry {
+        RevocationRegistry {
+            name: name.to_vec(),
+            epoch,
+            revoked: BTreeSet::new(),
+            suspended: BTreeMap::new(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn len(&self) -> usize {
+        self.revoked.len()
+    }
+
+    /// Whether `id` is revoked.
+    pub fn is_revoked(&self, id: &[u8]) -> bool {
+        self.revoked.contains(id)
+    }
+
+    /// The suspension of `id`, if any, whether or not it is in effect.
+    pub fn suspension(&self, id: &[u8]) -> Option<Suspension> {
+        self.suspended.get(id).cloned()
+    }
+
+    /// The standing of `id` at time `now`.
+    pub fn standing(&self, id: &[u8], now: u64) -> Standing {
+        if self.revoked.contains(id) {
+            return Standing::Revoked;
+        }
+        match self.suspended.get(id) {
+            Some(x) if x.is_in_effect(now) => Standing::Suspended(*x),
+            _                              => Standing::Good,
+        }
+    }
+
+    /// A digest of this registry's name, epoch, and revoked and suspended
+    /// identifiers.
+    pub fn digest(&self) -> [u8; SIZEOF_HASHED_KEY] {
+        let mut h = Hasher::new(b"aeonflux revocation registry v1");
+
+        h.input_u8_prefixed(&self.name).input_u64(self.epoch).input_u64(self.revoked.len() as u64);
+
+        for id in self.revoked.iter() {
+            h.input_u8_prefixed(id);
+        }
+
+        // Registries without suspensions keep the digests they had before
+        // suspensions existed.
+        if !self.suspended.is_empty() {
+            h.input_u64(self.suspended.len() as u64);
+
+            for (id, suspension) in self.suspended.iter() {
+                h.input_u8_prefixed(id).input_u64(suspension.from).input_u64(suspension.until);
+            }
+        }
+        h.finalize_key()
+    }
+
+    /// The delta which takes this registry to `next`, a later epoch of the
+    /// same registry.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::WrongRegistry` if `next` has another name.
+    /// * `RevocationError::WrongEpoch` if `next` is not at a later epoch.
+    pub fn delta_to(&self, next: &RevocationRegistry) -> Result<RevocationDelta, Error> {
+        if next.name != self.name {
+            return Err(RevocationError::WrongRegistry.into());
+        }
+        if next.epoch <= self.epoch {
+            return Err(RevocationError::WrongEpoch.into());
+        }
+
+        Ok(RevocationDelta {
+            registry: self.name.clone(),
+            from_epoch: self.epoch,
+            to_epoch: next.epoch,
+            added: next.revoked.difference(&self.revoked).cloned().collect(),
+            removed: self.revoked.difference(&next.revoked).cloned().collect(),
+            suspended: next.suspended.iter()
+                .filter(|&(id, x)| self.suspended.get(id) != Some(x))
+                .map(|(id, x)| (id.clone(), *x))
+                .collect(),
+            lifted: self.suspended.keys().filter(|id| !next.suspended.contains_key(*id)).cloned().collect(),
+            digest: next.digest(),
+        })
+    }
+
+    /// Revoke `id`, as the registry administrator, before the next epoch.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `id` was not already revoked.
+    pub fn revoke(&mut self, id: &[u8]) -> Result<bool, Error> {
+        check_identifier(id)?;
+
+        Ok(self.revoked.insert(id.to_vec()))
+    }
+
+    /// Stop revoking `id`, as the registry administrator, before the next
+    /// epoch.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `id` was revoked.
+    pub fn unrevoke(&mut self, id: &[u8]) -> bool {
+        self.revoked.remove(id)
+    }
+
+    /// Suspend `id` for a `suspension`, as the registry administrator, before
+    /// the next epoch, replacing any earlier suspension of it.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::InvalidIdentifier` if `id` is empty or too long.
+    /// * `RevocationError::InvalidSuspension` if the suspension ends before
+    ///   it begins.
+    pub fn suspend(&mut self, id: &[u8], suspension: Suspension) -> Result<(), Error> {
+        check_identifier(id)?;
+        suspension.check()?;
+
+        self.suspended.insert(id.to_vec(), suspension);
+        Ok(())
+    }
+
+    /// Lift any suspension of `id`, as the registry administrator, before the
+    /// next epoch.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `id` was suspended.
+    pub fn lift(&mut self, id: &[u8]) -> bool {
+        self.suspended.remove(id).is_some()
+    }
+
+    /// Lift every suspension which has ended by time `now`, as the registry
+    /// administrator, before the next epoch.
+    pub fn lift_lapsed(&mut self, now: u64) {
+        let lapsed: Vec<Vec<u8>> = self.suspended.iter()
+            .filter(|&(_, x)| x.until <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in lapsed.iter() {
+            self.suspended.remove(id);
+        }
+    }
+
+    /// Move to a later `epoch`, as the registry administrator.
+    pub fn advance(&mut self, epoch: u64) -> Result<(), Error> {
+        if epoch <= self.epoch {
+            return Err(RevocationError::WrongEpoch.into());
+        }
+        self.epoch = epoch;
+        Ok(())
+    }
+
+    /// Verify a `signed` delta with the administrator's `public` key, and
+    /// apply it.  Nothing is changed unless the delta applies in full.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::VerificationFailure` if the signature did not
+    ///   verify.
+    /// * `RevocationError::WrongRegistry` if the delta is for another
+    ///   registry.
+    /// * `RevocationError::WrongEpoch` if the delta does not start from this
+    ///   registry's epoch, e.g. because an earlier delta was missed.
+    /// * `RevocationError::Diverged` if the delta does not apply cleanly, or
+    ///   its digest does not match the result, in which case the whole
+    ///   registry should be fetched again.
+    pub fn apply(&mut self, signed: &SignedRevocationDelta, public: &RistrettoPoint) -> Result<(), Error> {
+        let delta: &RevocationDelta = signed.verify(public)?;
+
+        if delta.registry != self.name {
+            return Err(RevocationError::WrongRegistry.into());
+        }
+        if delta.from_epoch != self.epoch {
+            return Err(RevocationError::WrongEpoch.into());
+        }
+        if delta.added.iter().any(|id| self.revoked.contains(id)) ||
+           delta.removed.iter().any(|id| !self.revoked.contains(id)) ||
+           delta.lifted.iter().any(|id| !self.suspended.contains_key(id))
+        {
+            return Err(RevocationError::Diverged.into());
+        }
+
+        let mut next: RevocationRegistry = RevocationRegistry {
+            name: self.name.clone(),
+            epoch: delta.to_epoch,
+            revoked: self.revoked.clone(),
+            suspended: self.suspended.clone(),
+            history: Vec::new(),
+        };
+        let mut replaced: Vec<(Vec<u8>, Suspension)> = Vec::new();
+
+        for id in delta.added.iter() {
+            next.revoked.insert(id.clone());
+        }
+        for id in delta.removed.iter() {
+            next.revoked.remove(id);
+        }
+        for &(ref id, ref suspension) in delta.suspended.iter() {
+            if let Some(x) = next.suspended.insert(id.clone(), *suspension) {
+                replaced.push((id.clone(), x));
+            }
+        }
+        for id in delta.lifted.iter() {
+            if let Some(x) = next.suspended.remove(id) {
+                replaced.push((id.clone(), x));
+            }
+        }
+        if next.digest() != delta.digest {
+            return Err(RevocationError::Diverged.into());
+        }
+
+        self.epoch = next.epoch;
+        self.revoked = next.revoked;
+        self.suspended = next.suspended;
+        self.history.push(Applied { delta: delta.clone(), replaced });
+
+        if self.history.len() > MAXIMUM_ROLLBACK_DEPTH {
+            self.history.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Undo the most recently applied delta.
+    ///
+    /// # Returns
+    ///
+    /// The epoch rolled back to.
+    ///
+    /// # Errors
+    ///
+    /// * `RevocationError::NothingToRollBack` if no delta has been applied
+    ///   since this registry was created or decoded, or the last
+    ///   `MAXIMUM_ROLLBACK_DEPTH` have already been undone.
+    pub fn rollback(&mut self) -> Result<u64, Error> {
+        let applied: Applied = self.history.pop().ok_or(RevocationError::NothingToRollBack)?;
+        let delta: &RevocationDelta = &applied.delta;
+
+        for id in delta.added.iter() {
+            self.revoked.remove(id);
+        }
+        for id in delta.removed.iter() {
+            self.revoked.insert(id.clone());
+        }
+        for &(ref id, _) in delta.suspended.iter() {
+            self.suspended.remove(id);
+        }
+        for &(ref id, ref suspension) in applied.replaced.iter() {
+            self.suspended.insert(id.clone(), *suspension);
+        }
+        self.epoch = delta.from_epoch;
+
+        Ok(self.epoch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    fn updated(registry: &RevocationRegistry, revoke: &[&[u8]], unrevoke: &[&[u8]]) -> RevocationRegistry {
+        let mut next = registry.clone();
+
+        for id in revoke.iter() {
+            next.revoke(id).unwrap();
+        }
+        for id in unrevoke.iter() {
+            next.unrevoke(id);
+        }
+        next.advance(registry.epoch() + 1).unwrap();
+        next
+    }
+
+    #[test]
+    fn deltas_apply_and_roll_back() {
+        let mut rng = thread_rng();
+        let keypair = RegistryKeypair::generate(&mut rng);
+        let published = RevocationRegistry::new(b"members", 7).unwrap();
+        let mut gate = RevocationRegistry::from_bytes(&published.to_bytes()).unwrap();
+
+        let first = updated(&published, &[b"alice", b"bob"], &[]);
+        let second = updated(&first, &[b"carol"], &[b"alice"]);
+
+        let delta = published.delta_to(&first).unwrap().sign(&keypair, &mut rng);
+        let delta = SignedRevocationDelta::from_bytes(&delta.to_bytes()).unwrap();
+
+        gate.apply(&delta, &keypair.public).unwrap();
+
+        assert!(gate.epoch() == 8);
+        assert!(gate.is_revoked(b"alice"));
+
+        gate.apply(&first.delta_to(&second).unwrap().sign(&keypair, &mut rng), &keypair.public).unwrap();
+
+        assert!(gate.to_bytes() == second.to_bytes());
+        assert!(!gate.is_revoked(b"alice"));
+        assert!(gate.is_revoked(b"carol"));
+
+        assert!(gate.rollback() == Ok(8));
+        assert!(gate.to_bytes() == first.to_bytes());
+        assert!(gate.rollback() == Ok(7));
+        assert!(gate.len() == 0);
+        assert!(gate.rollback() == Err(RevocationError::NothingToRollBack.into()));
+    }
+
+    #[test]
+    fn bad_deltas_are_refused() {
+        let mut rng = thread_rng();
+        let keypair = RegistryKeypair::generate(&mut rng);
+        let other = RegistryKeypair::generate(&mut rng);
+        let published = RevocationRegistry::new(b"members", 7).unwrap();
+        let first = updated(&published, &[b"alice"], &[]);
+        let second = updated(&first, &[b"bob"], &[]);
+        let mut gate = published.clone();
+
+        let forged = published.delta_to(&first).unwrap().sign(&other, &mut rng);
+        let skipped = first.delta_to(&second).unwrap().sign(&keypair, &mut rng);
+
+        assert!(gate.apply(&forged, &keypair.public) == Err(RevocationError::VerificationFailure.into()));
+        assert!(gate.apply(&skipped, &keypair.public) == Err(RevocationError::WrongEpoch.into()));
+
+        // A gate whose copy has diverged refuses the delta, and is unchanged.
+        let mut diverged = published.clone();
+
+        diverged.revoke(b"mallory").unwrap();
+
+        let delta = published.delta_to(&first).unwrap().sign(&keypair, &mut rng);
+
+        assert!(diverged.apply(&delta, &keypair.public) == Err(RevocationError::Diverged.into()));
+        assert!(diverged.epoch() == 7);
+        assert!(gate.apply(&delta, &keypair.public).is_ok());
+        assert!(gate.apply(&delta, &keypair.public) == Err(RevocationError::WrongEpoch.into()));
+    }
+
+    #[test]
+    fn suspensions_lapse_and_roll_back() {
+        let mut rng = thread_rng();
+        let keypair = RegistryKeypair::generate(&mut rng);
+        let published = RevocationRegistry::new(b"members", 7).unwrap();
+        let mut gate = published.clone();
+
+        let mut first = updated(&published, &[b"bob"], &[]);
+
+        first.suspend(b"alice", Suspension { from: 100, until: 200 }).unwrap();
+        first.suspend(b"bob", Suspension { from: 100, until: 200 }).unwrap();
+
+        assert!(first.suspend(b"carol", Suspension { from: 200, until: 200 }) == Err(RevocationError::InvalidSuspension.into()));
+
+        let mut second = first.clone();
+
+        second.suspend(b"alice", Suspension { from: 100, until: 300 }).unwrap();
+        second.lift(b"bob");
+        second.advance(9).unwrap();
+
+        let delta = published.delta_to(&first).unwrap().sign(&keypair, &mut rng);
+        let delta = SignedRevocationDelta::from_bytes(&delta.to_bytes()).unwrap();
+
+        gate.apply(&delta, &keypair.public).unwrap();
+
+        assert!(gate.to_bytes() == first.to_bytes());
+        assert!(RevocationRegistry::from_bytes(&first.to_bytes()).unwrap().digest() == first.digest());
+        assert!(gate.standing(b"alice", 99) == Standing::Good);
+        assert!(gate.standing(b"alice", 150) == Standing::Suspended(Suspension { from: 100, until: 200 }));
+        assert!(gate.standing(b"alice", 200) == Standing::Good);
+        assert!(gate.standing(b"bob", 150) == Standing::Revoked);
+
+        gate.apply(&first.delta_to(&second).unwrap().sign(&keypair, &mut rng), &keypair.public).unwrap();
+
+        assert!(gate.to_bytes() == second.to_bytes());
+        assert!(gate.standing(b"alice", 250) != Standing::Good);
+        assert!(gate.suspension(b"bob") == None);
+
+        assert!(gate.rollback() == Ok(8));
+        assert!(gate.to_bytes() == first.to_bytes());
+        assert!(gate.rollback() == Ok(7));
+        assert!(gate.to_bytes() == published.to_bytes());
+
+        second.lift_lapsed(300);
+
+        assert!(second.suspension(b"alice") == None);
+    }
+
+    #[test]
+    fn suspended_holders_get_the_policy_access() {
+        let mut registry = RevocationRegistry::new(b"members", 7).unwrap();
+
+        registry.revoke(b"bob").unwrap();
+        registry.suspend(b"alice", Suspension { from: 100, until: 200 }).unwrap();
+        registry.suspend(b"bob", Suspension { from: 100, until: 200 }).unwrap();
+
+        let lenient = SuspensionPolicy::default();
+        let strict = SuspensionPolicy { suspended: Access::Denied };
+
+        assert!(lenient.access(&registry, b"alice", 150) == Access::Restricted);
+        assert!(strict.access(&registry, b"alice", 150) == Access::Denied);
+        assert!(lenient.access(&registry, b"alice", 250) == Access::Full);
+        assert!(lenient.access(&registry, b"bob", 250) == Access::Denied);
+        assert!(lenient.access(&registry, b"carol", 150) == Access::Full);
+    }
+}
@@ -0,0 +1,183 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Sources of randomness.
+//!
+//! Every function in this crate which needs randomness takes a `csprng`
+//! argument which is any `Rng`, so that callers decide where it comes from:
+//!
+//! * `GetrandomRng`, available with the `getrandom` feature (on by default),
+//!   asks the platform for every byte and works on `no_std` targets wherever
+//!   the `getrandom` crate does.  `default_rng` returns one.
+//!
+//! * `OsRng`, available with the `os_rng` feature, is `rand`'s handle to the
+//!   operating system's generator.
+//!
+//! * `DeterministicRng` expands a fixed seed, so that tests may be
+//!   reproduced exactly.  It must never be used to generate real keys.
+
+use rand::prng::ChaChaRng;
+
+use rand_core::CryptoRng;
+use rand_core::Error;
+#[cfg(feature = "getrandom")]
+use rand_core::ErrorKind;
+use rand_core::RngCore;
+use rand_core::SeedableRng;
+#[cfg(feature = "getrandom")]
+use rand_core::impls;
+
+#[cfg(feature = "os_rng")]
+pub use rand::rngs::OsRng;
+
+/// A cryptographically secure random number generator.
+pub trait Rng: RngCore + CryptoRng { }
+
+impl<R> Rng for R where R: RngCore + CryptoRng { }
+
+/// The generator which should be used when there is no reason to use another.
+#[cfg(feature = "getrandom")]
+pub type DefaultRng = GetrandomRng;
+
+/// Get a handle to the default generator.
+#[cfg(feature = "getrandom")]
+pub fn default_rng() -> DefaultRng {
+    GetrandomRng
+}
+
+/// A generator which asks the platform, via `getrandom`, for every byte.
+///
+/// # Panics
+///
+/// `next_u32`, `next_u64`, and `fill_bytes` panic if the platform has no
+/// source of randomness.  Use `try_fill_bytes` to handle this instead.
+#[cfg(feature = "getrandom")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GetrandomRng;
+
+#[cfg(feature = "getrandom")]
+impl RngCore for GetrandomRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if self.try_fill_bytes(dest).is_err() {
+            panic!("no source of randomness is available");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        match ::getrandom::getrandom(dest) {
+            Ok(())  => Ok(()),
+            Err(_)  => Err(Error::new(ErrorKind::Unavailable, "getrandom failed")),
+        }
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl CryptoRng for GetrandomRng { }
+
+/// Get a handle to the operating system's generator.
+#[cfg(feature = "os_rng")]
+pub fn os_rng() -> Result<OsRng, Error> {
+    OsRng::new()
+}
+
+/// A generator whose output is entirely determined by its seed, for tests.
+#[derive(Clone, Debug)]
+pub struct DeterministicRng(ChaChaRng);
+
+impl DeterministicRng {
+    /// Create a generator from a 32-byte `seed`.
+    pub fn new(seed: [u8; 32]) -> DeterministicRng {
+        DeterministicRng(ChaChaRng::from_seed(seed))
+    }
+}
+
+/// Create a generator from a short `seed`, e.g. a test case number.
+impl From<u64> for DeterministicRng {
+    fn from(seed: u64) -> DeterministicRng {
+        let mut bytes = [0u8; 32];
+
+        for i in 0..8 {
+            bytes[i] = (seed >> (8 * i)) as u8;
+        }
+        DeterministicRng::new(bytes)
+    }
+}
+
+impl RngCore for DeterministicRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+/// The output is as unpredictable as the seed, which is what a test wants,
+/// and being a `CryptoRng` means it may be passed to any function here.
+impl CryptoRng for DeterministicRng { }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::vec::Vec;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use credential::RevealedAttribute;
+    use issuer::Issuer;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use user::User;
+
+    fn issue<R: Rng>(csprng: &mut R) -> Vec<u8> {
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, csprng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+        attributes.push(Scalar::random(csprng));
+
+        issuer.issue(&user.obtain(attributes), csprng).unwrap().to_bytes()
+    }
+
+    #[test]
+    fn deterministic_rng_is_reproducible() {
+        assert!(issue(&mut DeterministicRng::from(7)) == issue(&mut DeterministicRng::from(7)));
+        assert!(issue(&mut DeterministicRng::from(7)) != issue(&mut DeterministicRng::from(8)));
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn default_rng_issues() {
+        let mut rng = default_rng();
+        let mut bytes = [0u8; 32];
+
+        assert!(rng.try_fill_bytes(&mut bytes).is_ok());
+        assert!(bytes != [0u8; 32]);
+        assert!(issue(&mut rng).len() > 0);
+    }
+}
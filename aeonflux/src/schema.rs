@@ -0,0 +1,891 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Credential schemas, naming the attributes of a credential.
+//!
+//! A `Schema` is an ordered list of named attributes, each with a type and a
+//! rule for whether it may be revealed in a presentation, so that an issuer
+//! and its verifiers agree on what each attribute means rather than on its
+//! position.  A schema is identified by the `SchemaId` of its encoding, which
+//! credentials and presentations carry as a `SchemaCredential` or
+//! `SchemaPresentation`, and which a verifier looks up in a
+//! `SchemaRegistry` of the schemas it accepts.
+//!
+//! Since the `SchemaId` is a hash of the whole schema, any change to a name,
+//! type, or rule, or to their order, makes a new schema.  So that adding an
+//! attribute does not orphan every credential issued before, an issuer may
+//! publish a `SchemaUpgrade` declaring a default for each added attribute.
+//! A verifier which registers it accepts old presentations under the new
+//! schema with `SchemaRegistry::verify_as()`, which says of each attribute
+//! whether the credential proves it or it was only defaulted.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeMap;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::string::String;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::str;
+#[cfg(feature = "std")]
+use std::str;
+
+use merlin::Transcript;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::Credential;
+use credential::CredentialPresentation;
+use credential::RevealedAttribute;
+use credential::VerifiedCredential;
+use curve25519_dalek::scalar::Scalar;
+use errors::Error;
+use errors::SchemaError;
+use hashing::Hasher;
+use hashing::SIZEOF_HASHED_KEY;
+use issuer::Issuer;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+
+/// The size of an encoded `SchemaId`.
+pub const SIZEOF_SCHEMA_ID: usize = SIZEOF_HASHED_KEY;
+
+/// The hash of a `Schema`'s encoding, by which it is referred to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct SchemaId(pub [u8; SIZEOF_SCHEMA_ID]);
+
+/// Bind a `schema`, if any, into a proof's `transcript`, so that proofs made
+/// under one schema never verify under another.  Credentials without a
+/// schema leave their transcripts as they were.
+pub(crate) fn bind_schema(transcript: &mut Transcript, schema: Option<&SchemaId>) {
+    if let Some(id) = schema {
+        transcript.commit_bytes(b"schema", &id.0[..]);
+    }
+}
+
+/// What kind of value an attribute holds.
+///
+/// These values are part of the schema encoding, and must never be reused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum AttributeType {
+    /// An arbitrary scalar.
+    Scalar = 1,
+    /// An unsigned integer, e.g. a tier.
+    Integer = 2,
+    /// Seconds since the Unix epoch.
+    Timestamp = 3,
+    /// An identifier hashed to a scalar, as in `hashed_identifier`.
+    Identifier = 4,
+}
+
+impl AttributeType {
+    fn from_byte(byte: u8) -> Result<AttributeType, SchemaError> {
+        match byte {
+            1 => Ok(AttributeType::Scalar),
+            2 => Ok(AttributeType::Integer),
+            3 => Ok(AttributeType::Timestamp),
+            4 => Ok(AttributeType::Identifier),
+            _ => Err(SchemaError::Malformed),
+        }
+    }
+}
+
+/// Whether an attribute may be revealed in a presentation.
+///
+/// These values are part of the schema encoding, and must never be reused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum Reveal {
+    /// The attribute must be revealed.
+    Always = 1,
+    /// The attribute must stay hidden.
+    Never = 2,
+    /// The attribute may be revealed or hidden.
+    Optional = 3,
+}
+
+impl Reveal {
+    fn from_byte(byte: u8) -> Result<Reveal, SchemaError> {
+        match byte {
+            1 => Ok(Reveal::Always),
+            2 => Ok(Reveal::Never),
+            3 => Ok(Reveal::Optional),
+            _ => Err(SchemaError::Malformed),
+        }
+    }
+
+    pub(crate) fn allows(&self, revealed: bool) -> bool {
+        match *self {
+            Reveal::Always   => revealed,
+            Reveal::Never    => !revealed,
+            Reveal::Optional => true,
+        }
+    }
+}
+
+/// One attribute of a `Schema`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AttributeSchema {
+    pub name: String,
+    pub kind: AttributeType,
+    pub reveal: Reveal,
+}
+
+/// An ordered list of named attributes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schema {
+    pub name: String,
+    pub attributes: Vec<AttributeSchema>,
+}
+
+/// Read a name, preceded by its length as a single byte, from the start of
+/// `bytes`, returning it and the number of bytes read.
+fn read_name(bytes: &[u8]) -> Result<(String, usize), SchemaError> {
+    let length: usize = *bytes.get(0).ok_or(SchemaError::WrongNumberOfBytes)? as usize;
+    let name: &[u8] = bytes.get(1..1 + length).ok_or(SchemaError::WrongNumberOfBytes)?;
+    let name: &str = str::from_utf8(name).or(Err(SchemaError::Malformed))?;
+
+    Ok((String::from(name), 1 + length))
+}
+
+fn write_name(v: &mut Vec<u8>, name: &str) {
+    v.push(name.len() as u8);
+    v.extend(name.as_bytes());
+}
+
+impl Schema {
+    /// Create a schema named `name`, with no attributes.
+    pub fn new(name: &str) -> Schema {
+        Schema { name: String::from(name), attributes: Vec::new() }
+    }
+
+    /// Append an attribute to this schema.
+    pub fn attribute(mut self, name: &str, kind: AttributeType, reveal: Reveal) -> Schema {
+        self.attributes.push(AttributeSchema { name: String::from(name), kind, reveal });
+        self
+    }
+
+    /// The position of the attribute called `name`, if there is one.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.attributes.iter().position(|a| a.name == name)
+    }
+
+    /// The identifier of this schema.
+    pub fn id(&self) -> SchemaId {
+        let mut h = Hasher::new(b"aeonflux schema v1");
+
+        h.input(&self.to_bytes());
+
+        SchemaId(h.finalize_key())
+    }
+
+    /// Check that this schema describes the attributes of every credential,
+    /// and that presentations, which reveal the first
+    /// `PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES` attributes and hide the
+    /// rest, follow its reveal rules.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::WrongNumberOfAttributes` if this schema does not have
+    ///   `NUMBER_OF_ATTRIBUTES` attributes.
+    /// * `SchemaError::RevealRule` if a presentation would reveal an
+    ///   attribute which must be hidden, or the reverse.
+    pub fn check(&self) -> Result<(), Error> {
+        if self.attributes.len() != NUMBER_OF_ATTRIBUTES {
+            return Err(SchemaError::WrongNumberOfAttributes.into());
+        }
+        for (index, attribute) in self.attributes.iter().enumerate() {
+            if !attribute.reveal.allows(index < PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES) {
+                return Err(SchemaError::RevealRule.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The revealed attribute called `name` in a `presentation` made under
+    /// this schema, if it is revealed.
+    pub fn revealed<'a>(
+        &self,
+        presentation: &'a CredentialPresentation,
+        name: &str,
+    ) -> Option<&'a RevealedAttribute>
+    {
+        presentation.attributes_revealed.get(self.index_of(name)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Schema, SchemaError> {
+        let (name, mut offset) = read_name(bytes)?;
+        let count: usize = *bytes.get(offset).ok_or(SchemaError::WrongNumberOfBytes)? as usize;
+        let mut attributes: Vec<AttributeSchema> = Vec::with_capacity(count);
+
+        offset += 1;
+
+        for _ in 0..count {
+            let (name, length) = read_name(&bytes[offset..])?;
+
+            offset += length;
+
+            let kind_and_reveal: &[u8] = bytes.get(offset..offset + 2).ok_or(SchemaError::WrongNumberOfBytes)?;
+            let kind: AttributeType = AttributeType::from_byte(kind_and_reveal[0])?;
+            let reveal: Reveal = Reveal::from_byte(kind_and_reveal[1])?;
+
+            offset += 2;
+            attributes.push(AttributeSchema { name, kind, reveal });
+        }
+        if offset != bytes.len() {
+            return Err(SchemaError::WrongNumberOfBytes);
+        }
+
+        Ok(Schema { name, attributes })
+    }
+
+    /// Encode this schema.
+    ///
+    /// # Panics
+    ///
+    /// If a name is longer than 255 bytes, or there are more than 255
+    /// attributes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        assert!(self.name.len() <= 255 && self.attributes.len() <= 255);
+        assert!(self.attributes.iter().all(|a| a.name.len() <= 255));
+
+        write_name(&mut v, &self.name);
+        v.push(self.attributes.len() as u8);
+
+        for attribute in self.attributes.iter() {
+            write_name(&mut v, &attribute.name);
+            v.push(attribute.kind as u8);
+            v.push(attribute.reveal as u8);
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Schema, "A valid byte sequence representing a Schema");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Schema);
+impl_try_from_bytes!(Schema, SchemaError);
+
+/// Read a `SchemaId` from the start of `bytes`.
+fn read_schema_id(bytes: &[u8]) -> Result<SchemaId, SchemaError> {
+    if bytes.len() < SIZEOF_SCHEMA_ID {
+        return Err(SchemaError::WrongNumberOfBytes);
+    }
+
+    let mut id = [0u8; SIZEOF_SCHEMA_ID];
+
+    id.copy_from_slice(&bytes[..SIZEOF_SCHEMA_ID]);
+
+    Ok(SchemaId(id))
+}
+
+/// A `Credential`, along with the schema it was issued under.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaCredential {
+    pub schema: SchemaId,
+    pub credential: Credential,
+}
+
+impl SchemaCredential {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SchemaCredential, SchemaError> {
+        let schema: SchemaId = read_schema_id(bytes)?;
+        let credential: Credential = Credential::from_bytes(&bytes[SIZEOF_SCHEMA_ID..])?;
+
+        Ok(SchemaCredential { schema, credential })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.schema.0.iter());
+        v.extend(self.credential.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SchemaCredential, "A valid byte sequence representing a SchemaCredential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SchemaCredential);
+impl_try_from_bytes!(SchemaCredential, SchemaError);
+
+/// A `CredentialPresentation`, along with the schema its credential was
+/// issued under.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaPresentation {
+    pub schema: SchemaId,
+    pub presentation: CredentialPresentation,
+}
+
+impl SchemaPresentation {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SchemaPresentation, SchemaError> {
+        let schema: SchemaId = read_schema_id(bytes)?;
+        let presentation = CredentialPresentation::from_bytes(&bytes[SIZEOF_SCHEMA_ID..])?;
+
+        Ok(SchemaPresentation { schema, presentation })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.schema.0.iter());
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SchemaPresentation, "A valid byte sequence representing a SchemaPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SchemaPresentation);
+impl_try_from_bytes!(SchemaPresentation, SchemaError);
+
+/// A declared path from an old schema to a new one which adds attributes,
+/// under which credentials of the old schema present as if they held a
+/// default value for each added attribute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaUpgrade {
+    pub from: Schema,
+    pub to: Schema,
+    /// The default of each attribute of `to` which is not in `from`, in the
+    /// order of `to`.
+    pub defaults: Vec<(String, RevealedAttribute)>,
+}
+
+/// Read a schema, preceded by its length as two big-endian bytes, from the
+/// start of `bytes`, returning it and the number of bytes read.
+fn read_schema(bytes: &[u8]) -> Result<(Schema, usize), SchemaError> {
+    let length: &[u8] = bytes.get(..2).ok_or(SchemaError::WrongNumberOfBytes)?;
+    let length: usize = (length[0] as usize) << 8 | length[1] as usize;
+    let schema: &[u8] = bytes.get(2..2 + length).ok_or(SchemaError::WrongNumberOfBytes)?;
+
+    Ok((Schema::from_bytes(schema)?, 2 + length))
+}
+
+fn write_schema(v: &mut Vec<u8>, schema: &Schema) {
+    let bytes: Vec<u8> = schema.to_bytes();
+
+    v.push((bytes.len() >> 8) as u8);
+    v.push(bytes.len() as u8);
+    v.extend(bytes);
+}
+
+impl SchemaUpgrade {
+    /// Declare an upgrade from the schema `from` to `to`, with `defaults` for
+    /// the attributes which `to` adds.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::Incompatible` if an attribute of `from` is missing
+    ///   from `to`, or has another type or reveal rule there, or if any
+    ///   attribute added by `to` has no default, or a default is given for an
+    ///   attribute which was not added, or if `to` adds no attributes.
+    pub fn new(
+        from: &Schema,
+        to: &Schema,
+        defaults: &[(&str, RevealedAttribute)],
+    ) -> Result<SchemaUpgrade, Error>
+    {
+        for attribute in from.attributes.iter() {
+            match to.attributes.iter().find(|a| a.name == attribute.name) {
+                Some(a) if a.kind == attribute.kind && a.reveal == attribute.reveal => (),
+                _ => return Err(SchemaError::Incompatible.into()),
+            }
+        }
+
+        let mut ordered: Vec<(String, RevealedAttribute)> = Vec::new();
+
+        for attribute in to.attributes.iter().filter(|a| from.index_of(&a.name).is_none()) {
+            match defaults.iter().find(|&&(name, _)| name == attribute.name) {
+                Some(&(_, value)) => ordered.push((attribute.name.clone(), value)),
+                None              => return Err(SchemaError::Incompatible.into()),
+            }
+        }
+        if ordered.len() != defaults.len() || ordered.is_empty() {
+            return Err(SchemaError::Incompatible.into());
+        }
+
+        Ok(SchemaUpgrade { from: from.clone(), to: to.clone(), defaults: ordered })
+    }
+
+    /// The declared default of the attribute called `name`, if it is one the
+    /// upgrade adds.
+    pub fn default_of(&self, name: &str) -> Option<&RevealedAttribute> {
+        self.defaults.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref value)| value)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<SchemaUpgrade, SchemaError> {
+        let (from, mut offset) = read_schema(bytes)?;
+        let (to, length) = read_schema(&bytes[offset..])?;
+
+        offset += length;
+
+        let count: usize = *bytes.get(offset).ok_or(SchemaError::WrongNumberOfBytes)? as usize;
+        let mut names: Vec<String> = Vec::with_capacity(count);
+        let mut values: Vec<RevealedAttribute> = Vec::with_capacity(count);
+
+        offset += 1;
+
+        for _ in 0..count {
+            let (name, length) = read_name(&bytes[offset..])?;
+
+            offset += length;
+
+            let value: &[u8] = bytes.get(offset..offset + 32).ok_or(SchemaError::WrongNumberOfBytes)?;
+            let mut tmp = [0u8; 32];
+
+            tmp.copy_from_slice(value);
+            offset += 32;
+            names.push(name);
+            values.push(Scalar::from_canonical_bytes(tmp).ok_or(SchemaError::Malformed)?);
+        }
+        if offset != bytes.len() {
+            return Err(SchemaError::WrongNumberOfBytes);
+        }
+
+        let defaults: Vec<(&str, RevealedAttribute)> = names.iter().map(|n| n.as_str()).zip(values.into_iter()).collect();
+
+        SchemaUpgrade::new(&from, &to, &defaults)
+    }
+
+    /// Encode this upgrade.
+    ///
+    /// # Panics
+    ///
+    /// As `Schema::to_bytes()`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        write_schema(&mut v, &self.from);
+        write_schema(&mut v, &self.to);
+        v.push(self.defaults.len() as u8);
+
+        for &(ref name, ref value) in self.defaults.iter() {
+            write_name(&mut v, name);
+            v.extend(value.as_bytes());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SchemaUpgrade, "A valid byte sequence representing a SchemaUpgrade");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SchemaUpgrade);
+impl_try_from_bytes!(SchemaUpgrade, SchemaError);
+
+/// What a verifier knows of one attribute of a presentation, and how.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Evidence {
+    /// The attribute was revealed, and the credential proves it.
+    Revealed(RevealedAttribute),
+    /// The attribute was hidden, and the credential proves some value for it.
+    Hidden,
+    /// The credential's schema has no such attribute, and it is taken to be
+    /// the upgrade's declared default, which nothing about the holder
+    /// proves.
+    Defaulted(RevealedAttribute),
+}
+
+impl Evidence {
+    /// The attribute's value, whether proven or defaulted, if it is known.
+    pub fn value(&self) -> Option<&RevealedAttribute> {
+        match *self {
+            Evidence::Revealed(ref x)  => Some(x),
+            Evidence::Hidden           => None,
+            Evidence::Defaulted(ref x) => Some(x),
+        }
+    }
+
+    /// Whether the credential proves this attribute.
+    pub fn is_proven(&self) -> bool {
+        match *self {
+            Evidence::Defaulted(_) => false,
+            _                      => true,
+        }
+    }
+}
+
+/// A presentation verified under some schema, perhaps through an upgrade
+/// from the one its credential was issued under.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradedPresentation {
+    /// The schema the presentation was verified under.
+    pub schema: SchemaId,
+    /// The schema its credential was issued under.
+    pub issued_under: SchemaId,
+    pub verified: VerifiedCredential,
+    /// The name of each attribute of `schema`, in order, and what is known
+    /// of it.
+    pub attributes: Vec<(String, Evidence)>,
+}
+
+impl UpgradedPresentation {
+    /// What is known of the attribute called `name`.
+    pub fn evidence(&self, name: &str) -> Option<&Evidence> {
+        self.attributes.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref e)| e)
+    }
+}
+
+/// The schemas a verifier accepts, by their identifiers, and the upgrades
+/// between them.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<SchemaId, Schema>,
+    upgrades: BTreeMap<(SchemaId, SchemaId), SchemaUpgrade>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> SchemaRegistry {
+        SchemaRegistry { schemas: BTreeMap::new(), upgrades: BTreeMap::new() }
+    }
+
+    /// Accept a `schema`, returning its identifier.
+    ///
+    /// # Errors
+    ///
+    /// Any error from `Schema::check`.
+    pub fn register(&mut self, schema: Schema) -> Result<SchemaId, Error> {
+        schema.check()?;
+
+        let id: SchemaId = schema.id();
+
+        self.schemas.insert(id, schema);
+
+        Ok(id)
+    }
+
+    /// Accept presentations of credentials issued under an `upgrade`'s old
+    /// schema as presentations under its new one.
+    ///
+    /// The new schema need not be one credentials can be issued under yet,
+    /// e.g. if it holds more attributes than `NUMBER_OF_ATTRIBUTES`.  Only
+    /// single upgrades are followed, so an issuer which upgrades twice
+    /// should publish an upgrade from each old schema to the newest.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::UnknownSchema` if the old schema is not accepted.
+    pub fn register_upgrade(&mut self, upgrade: SchemaUpgrade) -> Result<(), Error> {
+        let from: SchemaId = upgrade.from.id();
+
+        if !self.schemas.contains_key(&from) {
+            return Err(SchemaError::UnknownSchema.into());
+        }
+        self.upgrades.insert((from, upgrade.to.id()), upgrade);
+
+        Ok(())
+    }
+
+    /// The accepted schema with the identifier `id`.
+    pub fn get(&self, id: &SchemaId) -> Option<&Schema> {
+        self.schemas.get(id)
+    }
+
+    /// Verify a `presentation` with an `issuer`, as
+    /// `Issuer::verify_with_schema`, and return the schema it was made under.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::UnknownSchema` if the schema is not accepted.
+    /// * `SchemaError::Credential` if the presentation does not verify.
+    pub fn verify(
+        &self,
+        issuer: &Issuer,
+        presentation: &SchemaPresentation,
+    ) -> Result<(&Schema, VerifiedCredential), Error>
+    {
+        let schema: &Schema = self.get(&presentation.schema).ok_or(SchemaError::UnknownSchema)?;
+        let verified: VerifiedCredential = issuer.verify_with_schema(&presentation.presentation, &presentation.schema)?;
+
+        Ok((schema, verified))
+    }
+
+    /// Verify a `presentation` with an `issuer` under the `target` schema,
+    /// either directly or through a registered upgrade from the schema it
+    /// was made under.
+    ///
+    /// # Returns
+    ///
+    /// The verified presentation, with the `Evidence` for each attribute of
+    /// the target schema, so that a policy can tell a proven attribute from
+    /// one which was only defaulted.
+    ///
+    /// # Errors
+    ///
+    /// * `SchemaError::UnknownSchema` if the presentation's schema is not
+    ///   accepted, or is neither the target nor upgraded to it.
+    /// * `SchemaError::Credential` if the presentation does not verify.
+    pub fn verify_as(
+        &self,
+        issuer: &Issuer,
+        presentation: &SchemaPresentation,
+        target: &SchemaId,
+    ) -> Result<UpgradedPresentation, Error>
+    {
+        let upgrade: Option<&SchemaUpgrade> = if presentation.schema == *target {
+            None
+        } else {
+            Some(self.upgrades.get(&(presentation.schema, *target)).ok_or(SchemaError::UnknownSchema)?)
+        };
+        let (from, verified) = self.verify(issuer, presentation)?;
+        let to: &Schema = upgrade.map(|u| &u.to).unwrap_or(from);
+        let revealed: &Vec<RevealedAttribute> = &presentation.presentation.attributes_revealed;
+        let mut attributes: Vec<(String, Evidence)> = Vec::with_capacity(to.attributes.len());
+
+        for attribute in to.attributes.iter() {
+            let evidence: Evidence = match from.index_of(&attribute.name) {
+                Some(i) => match revealed.get(i) {
+                    Some(value) => Evidence::Revealed(*value),
+                    None        => Evidence::Hidden,
+                },
+                None => match upgrade.and_then(|u| u.default_of(&attribute.name)) {
+                    Some(value) => Evidence::Defaulted(*value),
+                    None        => return Err(SchemaError::Incompatible.into()),
+                },
+            };
+
+            attributes.push((attribute.name.clone(), evidence));
+        }
+
+        Ok(UpgradedPresentation { schema: *target, issued_under: presentation.schema, verified, attributes })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use amacs;
+    use errors::CredentialError;
+    use nonces::Nonces;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use user::User;
+
+    credential! {
+        /// A member of some group.
+        pub struct Membership / MembershipRequest / MembershipPresentation {
+            member: Identifier = Never,
+        }
+    }
+
+    credential! {
+        /// A member of some group, with a tier.
+        pub struct TieredMembership / TieredMembershipRequest / TieredMembershipPresentation {
+            member: Identifier = Never,
+            tier: Integer = Always,
+        }
+    }
+
+    fn membership() -> Schema {
+        Schema::new("membership").attribute("member", AttributeType::Identifier, Reveal::Never)
+    }
+
+    #[test]
+    fn schemas_roundtrip_and_are_checked() {
+        let schema = membership();
+
+        assert!(Schema::from_bytes(&schema.to_bytes()).unwrap() == schema);
+        assert!(Schema::from_bytes(&schema.to_bytes()[1..]).is_err());
+        assert!(schema.check().is_ok());
+        assert!(schema.index_of("member") == Some(0));
+
+        let renamed = Schema::new("membership").attribute("admin", AttributeType::Identifier, Reveal::Never);
+        let revealed = Schema::new("membership").attribute("member", AttributeType::Identifier, Reveal::Always);
+
+        assert!(schema.id() != renamed.id());
+        assert!(revealed.check() == Err(SchemaError::RevealRule.into()));
+        assert!(Schema::new("empty").check() == Err(SchemaError::WrongNumberOfAttributes.into()));
+    }
+
+    #[test]
+    fn registries_verify_known_schemas() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut registry = SchemaRegistry::new();
+        let id: SchemaId = registry.register(membership()).unwrap();
+        let issuance = issuer.issue_with_schema(&user.obtain(vec![Scalar::random(&mut rng)]), &id, &mut rng).unwrap();
+
+        user.obtain_finish_with_schema(Some(&issuance), &id).unwrap();
+
+        let presentation = SchemaPresentation {
+            schema: id,
+            presentation: user.show_with_schema(&id, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap(),
+        };
+        let decoded = SchemaPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+        assert!(decoded == presentation);
+        assert!(registry.verify(&issuer, &decoded).unwrap().0 == &membership());
+
+        let unknown = SchemaPresentation { schema: SchemaId([0u8; SIZEOF_SCHEMA_ID]), ..presentation };
+
+        assert!(registry.verify(&issuer, &unknown).err() == Some(SchemaError::UnknownSchema.into()));
+    }
+
+    #[test]
+    fn credentials_are_bound_to_their_schema() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let a: SchemaId = membership().id();
+        let b: SchemaId = Schema::new("moderator").attribute("member", AttributeType::Identifier, Reveal::Never).id();
+        let issuance = issuer.issue_with_schema(&user.obtain(vec![Scalar::random(&mut rng)]), &a, &mut rng).unwrap();
+
+        assert!(user.obtain_finish_with_schema(Some(&issuance), &b).is_err());
+        assert!(user.obtain_finish(Some(&issuance)).is_err());
+        assert!(user.obtain_finish_with_schema(Some(&issuance), &a).is_ok());
+
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+
+        // Neither a presentation claiming another schema, nor one without a
+        // schema, verifies.
+        let under_b = user.show_with_schema(&b, &nonces, &mut rng).unwrap();
+        let unbound = user.show(&nonces, &mut rng).unwrap();
+        let under_a = user.show_with_schema(&a, &nonces, &mut rng).unwrap();
+
+        assert!(issuer.verify_with_schema(&under_b, &b).is_err());
+        assert!(issuer.verify_with_schema(&under_a, &b).is_err());
+        assert!(issuer.verify(&unbound).is_err());
+        assert!(issuer.verify_with_schema(&under_a, &a).is_ok());
+    }
+
+    #[test]
+    fn upgrades_declare_every_added_attribute() {
+        let old = membership();
+        let new = membership().attribute("tier", AttributeType::Integer, Reveal::Always);
+        let tier = Scalar::from(1u64);
+
+        let upgrade = SchemaUpgrade::new(&old, &new, &[("tier", tier)]).unwrap();
+
+        assert!(SchemaUpgrade::from_bytes(&upgrade.to_bytes()).unwrap() == upgrade);
+        assert!(upgrade.default_of("tier") == Some(&tier));
+        assert!(upgrade.default_of("member") == None);
+
+        // Every added attribute needs a default, and only those.
+        let changed = Schema::new("membership").attribute("member", AttributeType::Identifier, Reveal::Optional)
+            .attribute("tier", AttributeType::Integer, Reveal::Always);
+
+        assert!(SchemaUpgrade::new(&old, &new, &[]) == Err(SchemaError::Incompatible.into()));
+        assert!(SchemaUpgrade::new(&old, &new, &[("tier", tier), ("member", tier)]) == Err(SchemaError::Incompatible.into()));
+        assert!(SchemaUpgrade::new(&old, &changed, &[("tier", tier)]) == Err(SchemaError::Incompatible.into()));
+        assert!(SchemaUpgrade::new(&old, &old, &[]) == Err(SchemaError::Incompatible.into()));
+    }
+
+    #[test]
+    fn old_credentials_present_under_upgraded_schemas() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut registry = SchemaRegistry::new();
+        let old: SchemaId = registry.register(membership()).unwrap();
+        let new = membership().attribute("tier", AttributeType::Integer, Reveal::Always);
+        let issuance = issuer.issue_with_schema(&user.obtain(vec![Scalar::random(&mut rng)]), &old, &mut rng).unwrap();
+
+        user.obtain_finish_with_schema(Some(&issuance), &old).unwrap();
+
+        let presentation = SchemaPresentation {
+            schema: old,
+            presentation: user.show_with_schema(&old, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap(),
+        };
+
+        // Until the upgrade is registered, the old presentation is unknown
+        // under the new schema.
+        assert!(registry.verify_as(&issuer, &presentation, &new.id()).err() == Some(SchemaError::UnknownSchema.into()));
+
+        registry.register_upgrade(SchemaUpgrade::new(&membership(), &new, &[("tier", Scalar::from(1u64))]).unwrap()).unwrap();
+
+        let upgraded = registry.verify_as(&issuer, &presentation, &new.id()).unwrap();
+
+        assert!(upgraded.issued_under == old);
+        assert!(upgraded.evidence("member") == Some(&Evidence::Hidden));
+        assert!(upgraded.evidence("tier") == Some(&Evidence::Defaulted(Scalar::from(1u64))));
+        assert!(!upgraded.evidence("tier").unwrap().is_proven());
+
+        // Presenting under the old schema itself proves every attribute.
+        let direct = registry.verify_as(&issuer, &presentation, &old).unwrap();
+
+        assert!(direct.attributes.iter().all(|&(_, ref e)| e.is_proven()));
+    }
+
+    #[test]
+    fn declared_credential_types_are_typed_end_to_end() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let member = Membership { member: Scalar::random(&mut rng) };
+
+        // The declared schema is the one written out by hand.
+        assert!(Membership::schema().attributes == membership().attributes);
+        assert!(Membership::schema().check().is_ok());
+
+        let request: MembershipRequest = member.request(&user);
+
+        assert!(request.attributes().unwrap() == member);
+
+        let issuance = request.issue(&issuer, &mut rng).unwrap();
+
+        Membership::obtain_finish(&mut user, &issuance).unwrap();
+
+        assert!(Membership::from_user(&user) == Some(member.clone()));
+
+        let presentation = Membership::show(&user, &Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let presentation = MembershipPresentation::from_bytes(&presentation.to_bytes()).unwrap();
+
+        assert!(presentation.verify(&issuer).is_ok());
+        assert!(presentation.member() == None);
+
+        // Credentials of the declared type verify under no other schema.
+        assert!(issuer.verify_with_schema(&presentation.presentation, &membership().id()).is_err());
+        assert!(Membership::from_attributes(&[]) == Err(SchemaError::WrongNumberOfAttributes.into()));
+    }
+
+    #[test]
+    fn declared_credential_types_refuse_issuers_of_another_size() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let member = TieredMembership { member: Scalar::random(&mut rng), tier: Scalar::from(2u64) };
+        let request = member.request(&user);
+
+        assert!(TieredMembership::schema().attributes.len() == 2);
+        assert!(request.attributes().unwrap() == member);
+        assert!(request.issue(&issuer, &mut rng).err() == Some(SchemaError::WrongNumberOfAttributes.into()));
+
+        // Nor is a declared type issued by an issuer whose key is sized for
+        // another.
+        let mut secret = issuer.keypair.secret.clone();
+
+        secret.xn.push(Scalar::random(&mut rng));
+
+        let public = secret.get_public_key(&system_parameters.h);
+        let wider = Issuer::new(system_parameters, amacs::Keypair { public, secret });
+        let request = Membership { member: Scalar::random(&mut rng) }.request(&user);
+
+        assert!(request.issue(&wider, &mut rng).err() == Some(CredentialError::WrongNumberOfAttributes.into()));
+    }
+}
@@ -0,0 +1,191 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A wrapper for secret values which keeps them out of logs.
+//!
+//! Every type holding key material, blinding factors, or a user's secrets
+//! keeps them in a `Secret`, whose `Debug` implementation prints
+//! `[REDACTED]`, so that deriving `Debug` on anything containing them, or
+//! formatting an error which captured one, never writes them anywhere.
+//!
+//! Secrets are overwritten when they go out of scope by `Clear`, whose
+//! writes are volatile, as through `zeroize`, so that the compiler may not
+//! elide them as dead stores.  Only the value's final location is cleared:
+//! moving a value copies it, so types holding secrets are kept in place,
+//! and borrowed rather than moved out of.
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::ptr;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic;
+
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::ptr;
+#[cfg(feature = "std")]
+use std::sync::atomic;
+
+use curve25519_dalek::scalar::Scalar;
+
+use zeroize::Zeroize;
+
+/// What is printed in place of a secret value.
+pub const REDACTED: &'static str = "[REDACTED]";
+
+/// Values which may be overwritten with null bytes, in a way the compiler
+/// may not optimise away.
+pub trait Clear {
+    /// Overwrite this value with null bytes.
+    fn clear(&mut self);
+}
+
+impl Clear for u8 {
+    fn clear(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Clear for u64 {
+    fn clear(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// `Scalar` does not implement `Zeroize` in this version of
+/// `curve25519-dalek`, so it is overwritten as `zeroize` would a type whose
+/// default is zero: with a volatile write, then a fence, so that the write
+/// is neither elided nor reordered after the memory is reused.
+impl Clear for Scalar {
+    fn clear(&mut self) {
+        unsafe { ptr::write_volatile(self, Scalar::zero()); }
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+    }
+}
+
+impl<T: Clear> Clear for [T] {
+    fn clear(&mut self) {
+        for x in self.iter_mut() {
+            x.clear();
+        }
+    }
+}
+
+impl<T: Clear, const N: usize> Clear for [T; N] {
+    fn clear(&mut self) {
+        self[..].clear();
+    }
+}
+
+/// A secret value, which is redacted when formatted with `Debug`.
+///
+/// The value is only reachable through `expose_secret`, so that every use of
+/// it is easy to find.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wrap a secret `value`.
+    pub fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    /// Borrow the secret value.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+
+    /// Mutably borrow the secret value.
+    pub fn expose_secret_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Clear> Secret<T> {
+    /// Overwrite the secret value with null bytes.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(source: T) -> Secret<T> {
+        Secret(source)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::string::String;
+    use std::vec::Vec;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use amacs;
+    use elgamal;
+    use nonces::Ephemeral;
+
+    use rand::thread_rng;
+
+    /// Read every byte of a `value`, as it is in memory, with volatile reads.
+    fn volatile_bytes<T>(value: &T) -> Vec<u8> {
+        let start = value as *const T as *const u8;
+
+        (0..::std::mem::size_of::<T>()).map(|i| unsafe { ptr::read_volatile(start.add(i)) }).collect()
+    }
+
+    #[test]
+    fn cleared_secrets_are_null_in_memory() {
+        let mut rng = thread_rng();
+        let mut scalar: Secret<Scalar> = Secret::new(Scalar::random(&mut rng));
+        let mut key: Secret<[u8; 32]> = Secret::new([0xa5; 32]);
+        let mut scalars: [Scalar; 3] = [Scalar::random(&mut rng), Scalar::random(&mut rng), Scalar::one()];
+
+        scalar.clear();
+        key.clear();
+        scalars.clear();
+
+        assert!(volatile_bytes(&scalar).iter().all(|b| *b == 0));
+        assert!(volatile_bytes(&key).iter().all(|b| *b == 0));
+        assert!(volatile_bytes(&scalars).iter().all(|b| *b == 0));
+        assert!(*scalar.expose_secret() == Scalar::zero());
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let mut rng = thread_rng();
+        let scalar: Scalar = Scalar::random(&mut rng);
+        let hex: String = format!("{:?}", scalar);
+        let secret: Secret<Scalar> = Secret::new(scalar);
+
+        assert!(format!("{:?}", secret) == REDACTED);
+        assert!(*secret.expose_secret() == scalar);
+
+        let elgamal_keypair = elgamal::Keypair::generate(&mut rng);
+        let amacs_key = amacs::SecretKey::new(2, &mut rng);
+        let ephemeral = Ephemeral::from(scalar);
+
+        for formatted in [format!("{:?}", elgamal_keypair.secret),
+                          format!("{:?}", amacs_key),
+                          format!("{:?}", ephemeral)].iter() {
+            assert!(formatted.contains(REDACTED));
+            assert!(!formatted.contains(&hex[..]));
+        }
+    }
+}
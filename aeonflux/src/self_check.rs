@@ -0,0 +1,470 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Startup checks that an issuer's loaded parameters actually work.
+//!
+//! A key file which was truncated, corrupted, or written for some other
+//! epoch usually decodes without complaint, and is only noticed once every
+//! presentation starts failing.  `Issuer::self_check()` instead runs a whole
+//! issue, present, and verify round trip against the loaded parameters,
+//! acting as a holder who knows only the issuer's public parameters, and
+//! checks that an altered presentation is refused, so that a service may
+//! refuse to start rather than turn traffic away.
+//!
+//! `Issuer::self_check_against()` also takes the `ParameterBundle` which
+//! holders are expected to have fetched, checks that the bundle is for the
+//! loaded key and currently valid, and makes the round trip with the
+//! bundle's parameters instead of its own.
+//!
+//! A verifier provisioned with a `HeaplessVerifier` runs the same checks
+//! with `HeaplessVerifier::self_check()` and
+//! `HeaplessVerifier::self_check_against()`, which issue under the key it
+//! holds, and verify each presentation with `HeaplessVerifier::verify()` and,
+//! with the `verifier-core` feature, `verifier_core::verify()` as well, so
+//! that the exact path it verifies traffic with is the one checked.  These
+//! need `alloc` to act as the holder, and so are run where the verifier is
+//! provisioned, or on a reader which has an allocator at startup.
+//!
+//! Each check is a `Stage` of a `SelfCheckReport`.  Stages run in order, and
+//! once any of them fails, those after it are skipped.
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::convert::TryFrom;
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use credential::CredentialPresentation;
+use errors::Error;
+use errors::SelfCheckError;
+use heapless::HeaplessPresentation;
+use heapless::HeaplessVerifier;
+use issuer::Issuer;
+use issuer::IssuerParameters;
+use nonces::Nonces;
+use parameter_chain::ParameterBundle;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
+use user::User;
+#[cfg(feature = "verifier-core")]
+use verifier_core;
+#[cfg(feature = "verifier-core")]
+use verifier_core::Scratch;
+
+/// One check made by a self-check.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Stage {
+    /// That the issuer's public parameters are those of its secret key.
+    Keys,
+    /// That the published parameters are the issuer's, and currently valid.
+    Epoch,
+    /// That the issuer can issue a credential.
+    Issuance,
+    /// That a holder can verify the issuance and present the credential.
+    Presentation,
+    /// That the issuer accepts the presentation.
+    Verification,
+    /// That the issuer refuses the presentation once it is altered.
+    Rejection,
+}
+
+/// The stages of a round trip, in the order they are run.
+const ROUND_TRIP: [Stage; 4] = [Stage::Issuance, Stage::Presentation, Stage::Verification, Stage::Rejection];
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Stage::Keys         => write!(f, "keys"),
+            Stage::Epoch        => write!(f, "epoch"),
+            Stage::Issuance     => write!(f, "issuance"),
+            Stage::Presentation => write!(f, "presentation"),
+            Stage::Verification => write!(f, "verification"),
+            Stage::Rejection    => write!(f, "rejection"),
+        }
+    }
+}
+
+/// What one stage of a self-check found.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Outcome {
+    Passed,
+    Failed(SelfCheckError),
+    /// The stage was not run, because an earlier one failed.
+    Skipped,
+}
+
+/// The outcome of every stage of a self-check.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SelfCheckReport {
+    /// The epoch of the published parameters checked against, if any.
+    pub epoch: Option<u64>,
+    /// Each stage, and its outcome, in the order they were run.
+    pub stages: Vec<(Stage, Outcome)>,
+}
+
+impl SelfCheckReport {
+    fn new(epoch: Option<u64>) -> SelfCheckReport {
+        SelfCheckReport { epoch, stages: Vec::new() }
+    }
+
+    /// Record the `result` of a `stage`, returning its value if it passed.
+    fn record<T>(&mut self, stage: Stage, result: Result<T, SelfCheckError>) -> Option<T> {
+        match result {
+            Ok(x) => {
+                self.stages.push((stage, Outcome::Passed));
+                Some(x)
+            },
+            Err(e) => {
+                self.stages.push((stage, Outcome::Failed(e)));
+                None
+            },
+        }
+    }
+
+    /// Record each of the `stages` which was not reached as skipped.
+    fn skip_remaining(&mut self, stages: &[Stage]) {
+        for stage in stages.iter() {
+            if self.outcome(*stage).is_none() {
+                self.stages.push((*stage, Outcome::Skipped));
+            }
+        }
+    }
+
+    /// Whether every stage passed.
+    pub fn is_ok(&self) -> bool {
+        self.stages.iter().all(|&(_, outcome)| outcome == Outcome::Passed)
+    }
+
+    /// The outcome of a `stage`, if it was part of the check.
+    pub fn outcome(&self, stage: Stage) -> Option<Outcome> {
+        self.stages.iter().find(|&&(s, _)| s == stage).map(|&(_, outcome)| outcome)
+    }
+
+    /// The stage which failed, and why.
+    pub fn failure(&self) -> Option<(Stage, SelfCheckError)> {
+        self.stages.iter().filter_map(|&(stage, outcome)| match outcome {
+            Outcome::Failed(e) => Some((stage, e)),
+            _                  => None,
+        }).next()
+    }
+
+    /// Whether every stage passed, and if not, why the first one failed.
+    ///
+    /// # Errors
+    ///
+    /// * The `SelfCheckError` of the failed stage.
+    pub fn check(&self) -> Result<(), Error> {
+        match self.failure() {
+            Some((_, e)) => Err(e.into()),
+            None         => Ok(()),
+        }
+    }
+}
+
+/// One line per stage, e.g. for a service's startup log.
+impl fmt::Display for SelfCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(epoch) = self.epoch {
+            writeln!(f, "self-check against epoch {}:", epoch)?;
+        }
+        for &(stage, outcome) in self.stages.iter() {
+            match outcome {
+                Outcome::Passed    => writeln!(f, "{}: passed", stage)?,
+                Outcome::Failed(e) => writeln!(f, "{}: FAILED: {}", stage, e)?,
+                Outcome::Skipped   => writeln!(f, "{}: skipped", stage)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Issuer {
+    /// Check that this issuer's public parameters are those of its secret
+    /// key, and that a credential issued to a holder who knows only them
+    /// can be presented and verified.
+    pub fn self_check<R>(&self, rng: &mut R) -> SelfCheckReport
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut report = SelfCheckReport::new(None);
+
+        if report.record(Stage::Keys, self.check_keys()).is_some() {
+            round_trip(self, self.system_parameters, self.get_issuer_parameters(),
+                       |presentation| self.verify(presentation).map(|_| ()), &mut report, rng);
+        }
+        report.skip_remaining(&ROUND_TRIP);
+        report
+    }
+
+    /// As `Issuer::self_check()`, but also checking that the published
+    /// `bundle` is for this issuer and valid at the time `now`, and making
+    /// the round trip with the bundle's parameters, as holders will.
+    pub fn self_check_against<R>(&self, bundle: &ParameterBundle, now: u64, rng: &mut R) -> SelfCheckReport
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut report = SelfCheckReport::new(Some(bundle.epoch));
+
+        if report.record(Stage::Keys, self.check_keys()).is_some() &&
+           report.record(Stage::Epoch, self.check_epoch(bundle, now)).is_some()
+        {
+            round_trip(self, bundle.system_parameters, bundle.issuer_parameters.parameters.clone(),
+                       |presentation| self.verify(presentation).map(|_| ()), &mut report, rng);
+        }
+        report.skip_remaining(&[Stage::Epoch]);
+        report.skip_remaining(&ROUND_TRIP);
+        report
+    }
+
+    fn check_keys(&self) -> Result<(), SelfCheckError> {
+        if self.keypair.secret.xn.len() < NUMBER_OF_ATTRIBUTES ||
+           self.keypair.secret.get_public_key(&self.system_parameters.h) != self.keypair.public
+        {
+            return Err(SelfCheckError::KeyMismatch);
+        }
+        Ok(())
+    }
+
+    fn check_epoch(&self, bundle: &ParameterBundle, now: u64) -> Result<(), SelfCheckError> {
+        if bundle.system_parameters != self.system_parameters ||
+           bundle.issuer_parameters.parameters != self.keypair.public
+        {
+            return Err(SelfCheckError::EpochMismatch);
+        }
+        if !bundle.issuer_parameters.is_valid_at(now) {
+            return Err(SelfCheckError::Expired);
+        }
+        Ok(())
+    }
+}
+
+impl HeaplessVerifier {
+    /// Check that this verifier's public key is that of its secret key, and
+    /// that a credential issued under its key to a holder who knows only the
+    /// issuer's public parameters can be presented and verified by it.
+    pub fn self_check<R>(&self, rng: &mut R) -> SelfCheckReport
+    where
+        R: RngCore + CryptoRng,
+    {
+        let issuer: Issuer = self.to_issuer();
+        let mut report = SelfCheckReport::new(None);
+
+        if report.record(Stage::Keys, issuer.check_keys()).is_some() {
+            round_trip(&issuer, issuer.system_parameters, issuer.get_issuer_parameters(),
+                       |presentation| self.verify_presentation(presentation), &mut report, rng);
+        }
+        report.skip_remaining(&ROUND_TRIP);
+        report
+    }
+
+    /// As `HeaplessVerifier::self_check()`, but also checking that the
+    /// published `bundle` is for this verifier's key and valid at the time
+    /// `now`, and making the round trip with the bundle's parameters.
+    pub fn self_check_against<R>(&self, bundle: &ParameterBundle, now: u64, rng: &mut R) -> SelfCheckReport
+    where
+        R: RngCore + CryptoRng,
+    {
+        let issuer: Issuer = self.to_issuer();
+        let mut report = SelfCheckReport::new(Some(bundle.epoch));
+
+        if report.record(Stage::Keys, issuer.check_keys()).is_some() &&
+           report.record(Stage::Epoch, issuer.check_epoch(bundle, now)).is_some()
+        {
+            round_trip(&issuer, bundle.system_parameters, bundle.issuer_parameters.parameters.clone(),
+                       |presentation| self.verify_presentation(presentation), &mut report, rng);
+        }
+        report.skip_remaining(&[Stage::Epoch]);
+        report.skip_remaining(&ROUND_TRIP);
+        report
+    }
+
+    /// Verify a `presentation` as a reader would: decoded into a
+    /// `HeaplessPresentation`, and, with the `verifier-core` feature, from
+    /// its encoding within a `Scratch` buffer as well.
+    fn verify_presentation(&self, presentation: &CredentialPresentation) -> Result<(), Error> {
+        self.verify(&HeaplessPresentation::try_from(presentation)?)?;
+
+        #[cfg(feature = "verifier-core")]
+        verifier_core::verify(self, &presentation.to_bytes(), &mut Scratch::new())?;
+
+        Ok(())
+    }
+}
+
+/// Have the `issuer` issue a credential to a holder with the given
+/// parameters, have them present it, and `verify` both it and an altered
+/// copy of it.
+fn round_trip<R, V>(
+    issuer: &Issuer,
+    system_parameters: SystemParameters,
+    issuer_parameters: IssuerParameters,
+    verify: V,
+    report: &mut SelfCheckReport,
+    rng: &mut R,
+)
+where
+    R: RngCore + CryptoRng,
+    V: Fn(&CredentialPresentation) -> Result<(), Error>,
+{
+    let mut holder = User::new(system_parameters, issuer_parameters, None);
+    let mut attributes: Vec<Scalar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+    for _ in 0..NUMBER_OF_ATTRIBUTES {
+        attributes.push(Scalar::random(rng));
+    }
+
+    let request = holder.obtain(attributes);
+    let issued = issuer.issue(&request, rng).map_err(SelfCheckError::Issuance);
+    let issuance = match report.record(Stage::Issuance, issued) {
+        Some(issuance) => issuance,
+        None           => return,
+    };
+
+    let nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
+    let shown = holder.obtain_finish(Some(&issuance))
+        .and_then(|_| holder.show(&nonces, rng))
+        .map_err(SelfCheckError::Presentation);
+    let presentation = match report.record(Stage::Presentation, shown) {
+        Some(presentation) => presentation,
+        None               => return,
+    };
+
+    let verified = verify(&presentation).map_err(SelfCheckError::Verification);
+
+    if report.record(Stage::Verification, verified).is_none() {
+        return;
+    }
+
+    let mut altered = presentation.clone();
+
+    altered.rerandomized_nonce += system_parameters.g;
+
+    let refused = match verify(&altered) {
+        Ok(_)  => Err(SelfCheckError::AcceptedForgery),
+        Err(_) => Ok(()),
+    };
+
+    report.record(Stage::Rejection, refused);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use amacs;
+    use test_utils::H;
+
+    #[test]
+    fn sound_parameters_pass_and_corrupted_keys_are_caught() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let report = issuer.self_check(&mut rng);
+
+        assert!(report.is_ok());
+        assert!(report.stages.iter().map(|&(stage, _)| stage).collect::<Vec<_>>() ==
+                vec![Stage::Keys, Stage::Issuance, Stage::Presentation, Stage::Verification, Stage::Rejection]);
+
+        // A secret key which no longer matches the public parameters.
+        let mut keypair: amacs::Keypair = amacs::Keypair::from_bytes(&issuer.keypair.to_bytes()).unwrap();
+
+        keypair.secret.xn[0] += Scalar::one();
+
+        let corrupted = Issuer::new(issuer.system_parameters, keypair);
+        let report = corrupted.self_check(&mut rng);
+
+        assert!(report.check() == Err(SelfCheckError::KeyMismatch.into()));
+        assert!(report.outcome(Stage::Keys) == Some(Outcome::Failed(SelfCheckError::KeyMismatch)));
+        assert!(report.outcome(Stage::Verification) == Some(Outcome::Skipped));
+    }
+
+    #[test]
+    fn mismatched_and_expired_epochs_are_caught() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let other = Issuer::create(system_parameters, &mut rng);
+        let next_key = other.system_parameters.g;
+
+        let bundle = ParameterBundle {
+            epoch: 7,
+            system_parameters,
+            issuer_parameters: issuer.get_expiring_issuer_parameters(1000, 2000),
+            next_key,
+        };
+        let report = issuer.self_check_against(&bundle, 1500, &mut rng);
+
+        assert!(report.is_ok());
+        assert!(report.epoch == Some(7));
+
+        let report = issuer.self_check_against(&bundle, 2000, &mut rng);
+
+        assert!(report.failure() == Some((Stage::Epoch, SelfCheckError::Expired)));
+        assert!(report.outcome(Stage::Issuance) == Some(Outcome::Skipped));
+
+        // A verifier loaded with another epoch's key.
+        let report = other.self_check_against(&bundle, 1500, &mut rng);
+
+        assert!(report.failure() == Some((Stage::Epoch, SelfCheckError::EpochMismatch)));
+    }
+
+    #[test]
+    fn verifiers_check_their_own_verification_path() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let verifier = HeaplessVerifier::try_from(&issuer).unwrap();
+        let report = verifier.self_check(&mut rng);
+
+        assert!(report.is_ok());
+        assert!(report.stages.iter().map(|&(stage, _)| stage).collect::<Vec<_>>() ==
+                vec![Stage::Keys, Stage::Issuance, Stage::Presentation, Stage::Verification, Stage::Rejection]);
+
+        // A verifier whose public key was corrupted in its flash.
+        let mut bytes = verifier.to_bytes();
+        let length: usize = bytes.len();
+
+        bytes[length - 32..].copy_from_slice(system_parameters.g.compress().as_bytes());
+
+        let corrupted = HeaplessVerifier::from_bytes(&bytes).unwrap();
+        let report = corrupted.self_check(&mut rng);
+
+        assert!(report.failure() == Some((Stage::Keys, SelfCheckError::KeyMismatch)));
+        assert!(report.outcome(Stage::Verification) == Some(Outcome::Skipped));
+
+        // A verifier provisioned for another epoch's key.
+        let other = Issuer::create(system_parameters, &mut rng);
+        let bundle = ParameterBundle {
+            epoch: 3,
+            system_parameters,
+            issuer_parameters: other.get_expiring_issuer_parameters(1000, 2000),
+            next_key: system_parameters.g,
+        };
+        let report = verifier.self_check_against(&bundle, 1500, &mut rng);
+
+        assert!(report.failure() == Some((Stage::Epoch, SelfCheckError::EpochMismatch)));
+        assert!(HeaplessVerifier::try_from(&other).unwrap().self_check_against(&bundle, 1500, &mut rng).is_ok());
+    }
+}
@@ -0,0 +1,431 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Ready-made HTTP request/response types and handlers for an issuer
+//! service.
+//!
+//! This module is only available with the `service` feature.  It does not
+//! depend on any particular HTTP server: a server passes each `Request` to
+//! `handle()`, and writes the `Response` it gets back, e.g.
+//!
+//! ```rust,ignore
+//! let request = aeonflux::service::Request {
+//!     method: &method,
+//!     path: &path,
+//!     content_type: headers.get("content-type"),
+//!     accept: headers.get("accept"),
+//!     body: &body,
+//! };
+//! let response = aeonflux::service::handle(&issuer, &request, &mut csprng);
+//!
+//! reply(response.status, response.content_type, response.body);
+//! ```
+//!
+//! With the `axum-service` feature, `router::router()` does exactly this for
+//! axum.
+//!
+//! # Endpoints
+//!
+//! | Method | Path              | Request             | Response             |
+//! |--------|-------------------|---------------------|----------------------|
+//! | `GET`  | `/v1/parameters`  |                     | `ParametersResponse` |
+//! | `POST` | `/v1/issue`       | `IssueRequest`      | `IssueResponse`      |
+//! | `POST` | `/v1/blind-issue` | `BlindIssueRequest` | `BlindIssueResponse` |
+//! | `POST` | `/v1/verify`      | `VerifyRequest`     | `VerifyResponse`     |
+//!
+//! # Content negotiation
+//!
+//! Request bodies may be `application/json` (the default, if no
+//! `Content-Type` is given) or `application/cbor`.  Responses are CBOR if the
+//! `Accept` header asks for `application/cbor`, and otherwise JSON.
+//!
+//! Failures are returned as an `ErrorResponse` in the same format, with a
+//! status code of 400 for undecodable requests or invalid protocol messages,
+//! 403 for presentations which did not verify, 404 and 405 for unknown paths
+//! and methods, 415 for unsupported content types, and 500 for misconfigured
+//! issuers.
+//!
+//! # Note
+//!
+//! Issuance through these handlers performs no authorisation.  Services
+//! which must decide *who* may obtain a credential should check requests to
+//! `issue` and `blind_issue` before handing them over.
+
+use std::string::String;
+use std::string::ToString;
+use std::vec::Vec;
+
+#[cfg(feature = "axum-service")]
+pub mod router;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use errors::DecodeError;
+use errors::Error;
+use errors::ServiceError;
+use issuer::Issuer;
+use issuer::IssuerParameters;
+use parameters::SystemParameters;
+use rng::Rng;
+
+pub const CONTENT_TYPE_JSON: &'static str = "application/json";
+pub const CONTENT_TYPE_CBOR: &'static str = "application/cbor";
+
+pub const STATUS_OK: u16 = 200;
+pub const STATUS_BAD_REQUEST: u16 = 400;
+pub const STATUS_FORBIDDEN: u16 = 403;
+pub const STATUS_NOT_FOUND: u16 = 404;
+pub const STATUS_METHOD_NOT_ALLOWED: u16 = 405;
+pub const STATUS_UNSUPPORTED_MEDIA_TYPE: u16 = 415;
+pub const STATUS_INTERNAL_SERVER_ERROR: u16 = 500;
+
+/// The parts of an HTTP request which the handlers need.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Request<'a> {
+    /// The request method, e.g. `"POST"`.
+    pub method: &'a str,
+    /// The request path, without any query string.
+    pub path: &'a str,
+    /// The value of the `Content-Type` header, if there was one.
+    pub content_type: Option<&'a str>,
+    /// The value of the `Accept` header, if there was one.
+    pub accept: Option<&'a str>,
+    pub body: &'a [u8],
+}
+
+/// The HTTP response to a `Request`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// The value of the `Content-Type` header.
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+/// The body of a response to `GET /v1/parameters`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ParametersResponse {
+    pub system_parameters: SystemParameters,
+    pub issuer_parameters: IssuerParameters,
+}
+
+/// The body of a request to `POST /v1/issue`.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IssueRequest {
+    pub request: CredentialRequest,
+}
+
+/// The body of a response to `POST /v1/issue`.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IssueResponse {
+    pub issuance: CredentialIssuance,
+}
+
+/// The body of a request to `POST /v1/blind-issue`.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlindIssueRequest {
+    pub request: CredentialBlindRequest,
+}
+
+/// The body of a response to `POST /v1/blind-issue`.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlindIssueResponse {
+    pub issuance: CredentialBlindIssuance,
+}
+
+/// The body of a request to `POST /v1/verify`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    pub presentation: CredentialPresentation,
+}
+
+/// The body of a response to `POST /v1/verify`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub valid: bool,
+}
+
+/// The body of any failed response.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// A stable, machine-readable error code, e.g. `"verification_failure"`.
+    pub error: String,
+    /// A human-readable description of the error.
+    pub message: String,
+}
+
+impl ServiceError {
+    /// The HTTP status code for this error.
+    pub fn status(&self) -> u16 {
+        match *self {
+            ServiceError::MalformedRequest                                   => STATUS_BAD_REQUEST,
+            ServiceError::MalformedResponse                                  => STATUS_INTERNAL_SERVER_ERROR,
+            ServiceError::MethodNotAllowed                                   => STATUS_METHOD_NOT_ALLOWED,
+            ServiceError::NotFound                                           => STATUS_NOT_FOUND,
+            ServiceError::UnsupportedMediaType                               => STATUS_UNSUPPORTED_MEDIA_TYPE,
+            ServiceError::Credential(Error::Proof(_))                        |
+            ServiceError::Credential(Error::Expired)                         => STATUS_FORBIDDEN,
+            ServiceError::Credential(Error::Misuse)                          => STATUS_INTERNAL_SERVER_ERROR,
+            ServiceError::Credential(_)                                      => STATUS_BAD_REQUEST,
+        }
+    }
+
+    /// A stable, machine-readable code for this error.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ServiceError::MalformedRequest                                   => "malformed_request",
+            ServiceError::MalformedResponse                                  => "malformed_response",
+            ServiceError::MethodNotAllowed                                   => "method_not_allowed",
+            ServiceError::NotFound                                           => "not_found",
+            ServiceError::UnsupportedMediaType                               => "unsupported_media_type",
+            ServiceError::Credential(Error::Proof(_))                        => "verification_failure",
+            ServiceError::Credential(Error::Expired)                         => "expired",
+            ServiceError::Credential(Error::Decode(DecodeError::WrongNumberOfElements)) => "wrong_number_of_attributes",
+            ServiceError::Credential(Error::Decode(DecodeError::MissingData)) => "missing_data",
+            ServiceError::Credential(Error::Misuse)                          => "issuer_misconfigured",
+            ServiceError::Credential(_)                                      => "invalid_credential_data",
+        }
+    }
+}
+
+/// The wire format of a request or response body.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Json,
+    Cbor,
+}
+
+impl Format {
+    fn content_type(&self) -> &'static str {
+        match *self {
+            Format::Json => CONTENT_TYPE_JSON,
+            Format::Cbor => CONTENT_TYPE_CBOR,
+        }
+    }
+
+    /// Determine the format of a request body from its `Content-Type`.
+    pub fn of_request(request: &Request) -> Result<Format, ServiceError> {
+        let value: &str = match request.content_type {
+            Some(x) => x,
+            None    => return Ok(Format::Json),
+        };
+        let essence: &str = value.split(';').next().unwrap_or("").trim();
+
+        if essence.eq_ignore_ascii_case(CONTENT_TYPE_JSON) {
+            Ok(Format::Json)
+        } else if essence.eq_ignore_ascii_case(CONTENT_TYPE_CBOR) {
+            Ok(Format::Cbor)
+        } else {
+            Err(ServiceError::UnsupportedMediaType)
+        }
+    }
+
+    /// Determine the format of a response body from the request's `Accept`.
+    pub fn of_response(request: &Request) -> Format {
+        match request.accept {
+            Some(x) if x.contains(CONTENT_TYPE_CBOR) => Format::Cbor,
+            _                                        => Format::Json,
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, ServiceError> {
+        match *self {
+            Format::Json => ::serde_json::from_slice(body).map_err(|_| ServiceError::MalformedRequest),
+            Format::Cbor => ::serde_cbor::from_slice(body).map_err(|_| ServiceError::MalformedRequest),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ServiceError> {
+        match *self {
+            Format::Json => ::serde_json::to_vec(value).map_err(|_| ServiceError::MalformedResponse),
+            Format::Cbor => ::serde_cbor::to_vec(value).map_err(|_| ServiceError::MalformedResponse),
+        }
+    }
+
+    /// Respond with the given `status` and `value`, or, if the `value` cannot
+    /// be encoded, with an empty body and the status of that error.
+    fn respond<T: Serialize>(&self, status: u16, value: &T) -> Response {
+        match self.encode(value) {
+            Ok(body) => Response { status, content_type: self.content_type(), body },
+            Err(e)   => Response { status: e.status(), content_type: self.content_type(), body: Vec::new() },
+        }
+    }
+}
+
+/// Render the result of a handler in the negotiated response `format`.
+fn respond<T: Serialize>(format: Format, result: Result<T, ServiceError>) -> Response {
+    match result {
+        Ok(body) => format.respond(STATUS_OK, &body),
+        Err(e)   => format.respond(e.status(), &ErrorResponse {
+            error: e.code().to_string(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Handle `GET /v1/parameters`.
+pub fn parameters(issuer: &Issuer, request: &Request) -> Response {
+    respond(Format::of_response(request), Ok(ParametersResponse {
+        system_parameters: issuer.system_parameters,
+        issuer_parameters: issuer.get_issuer_parameters(),
+    }))
+}
+
+/// Handle `POST /v1/issue`.
+pub fn issue<R>(issuer: &Issuer, request: &Request, csprng: &mut R) -> Response
+where
+    R: Rng,
+{
+    let result = Format::of_request(request)
+        .and_then(|format| format.decode::<IssueRequest>(request.body))
+        .and_then(|body| Ok(issuer.issue(&body.request, csprng)?))
+        .map(|issuance| IssueResponse { issuance });
+
+    respond(Format::of_response(request), result)
+}
+
+/// Handle `POST /v1/blind-issue`.
+pub fn blind_issue<R>(issuer: &Issuer, request: &Request, csprng: &mut R) -> Response
+where
+    R: Rng,
+{
+    let result = Format::of_request(request)
+        .and_then(|format| format.decode::<BlindIssueRequest>(request.body))
+        .and_then(|body| Ok(issuer.blind_issue(&body.request, csprng)?))
+        .map(|issuance| BlindIssueResponse { issuance });
+
+    respond(Format::of_response(request), result)
+}
+
+/// Handle `POST /v1/verify`.
+pub fn verify(issuer: &Issuer, request: &Request) -> Response {
+    let result = Format::of_request(request)
+        .and_then(|format| format.decode::<VerifyRequest>(request.body))
+        .and_then(|body| Ok(issuer.verify(&body.presentation)?))
+        .map(|_| VerifyResponse { valid: true });
+
+    respond(Format::of_response(request), result)
+}
+
+/// Dispatch a `request` for any endpoint to its handler.
+pub fn handle<R>(issuer: &Issuer, request: &Request, csprng: &mut R) -> Response
+where
+    R: Rng,
+{
+    let get: bool = request.method.eq_ignore_ascii_case("GET");
+    let post: bool = request.method.eq_ignore_ascii_case("POST");
+
+    match request.path {
+        "/v1/parameters"  if get  => parameters(issuer, request),
+        "/v1/issue"       if post => issue(issuer, request, csprng),
+        "/v1/blind-issue" if post => blind_issue(issuer, request, csprng),
+        "/v1/verify"      if post => verify(issuer, request),
+        "/v1/parameters"  |
+        "/v1/issue"       |
+        "/v1/blind-issue" |
+        "/v1/verify"              => respond::<()>(Format::of_response(request),
+                                                   Err(ServiceError::MethodNotAllowed)),
+        _                         => respond::<()>(Format::of_response(request),
+                                                   Err(ServiceError::NotFound)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use nonces::Nonces;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use test_utils::H;
+    use user::User;
+
+    use rand::thread_rng;
+
+    fn request<'a>(path: &'a str, content_type: &'a str, accept: &'a str, body: &'a [u8]) -> Request<'a> {
+        Request {
+            method: "POST",
+            path,
+            content_type: Some(content_type),
+            accept: Some(accept),
+            body,
+        }
+    }
+
+    #[test]
+    fn issue_and_verify_over_json_and_cbor() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+        let body = Format::Json.encode(&IssueRequest { request: user.obtain(vec![Scalar::random(&mut rng)]) }).unwrap();
+        let response = handle(&issuer, &request("/v1/issue", CONTENT_TYPE_JSON, CONTENT_TYPE_CBOR, &body),
+                              &mut rng);
+
+        assert!(response.status == STATUS_OK);
+        assert!(response.content_type == CONTENT_TYPE_CBOR);
+
+        let issued: IssueResponse = Format::Cbor.decode(&response.body).unwrap();
+
+        user.obtain_finish(Some(&issued.issuance)).unwrap();
+
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let body = Format::Cbor.encode(&VerifyRequest { presentation: user.show(&nonces, &mut rng).unwrap() }).unwrap();
+        let response = handle(&issuer, &request("/v1/verify", CONTENT_TYPE_CBOR, CONTENT_TYPE_JSON, &body),
+                              &mut rng);
+
+        assert!(response.status == STATUS_OK);
+        assert!(Format::Json.decode::<VerifyResponse>(&response.body).unwrap().valid);
+    }
+
+    #[test]
+    fn structured_errors() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+
+        let response = verify(&issuer, &request("/v1/verify", "text/plain", CONTENT_TYPE_JSON, b"hello"));
+        let error: ErrorResponse = Format::Json.decode(&response.body).unwrap();
+
+        assert!(response.status == STATUS_UNSUPPORTED_MEDIA_TYPE);
+        assert!(error.error == "unsupported_media_type");
+
+        let response = verify(&issuer, &request("/v1/verify", CONTENT_TYPE_JSON, CONTENT_TYPE_JSON, b"{}"));
+        let error: ErrorResponse = Format::Json.decode(&response.body).unwrap();
+
+        assert!(response.status == STATUS_BAD_REQUEST);
+        assert!(error.error == "malformed_request");
+
+        let mut get = request("/v1/verify", CONTENT_TYPE_JSON, CONTENT_TYPE_JSON, b"");
+
+        get.method = "GET";
+        assert!(handle(&issuer, &get, &mut rng).status == STATUS_METHOD_NOT_ALLOWED);
+
+        get.path = "/v1/parameters";
+        assert!(handle(&issuer, &get, &mut rng).status == STATUS_OK);
+
+        get.path = "/v2/parameters";
+        assert!(handle(&issuer, &get, &mut rng).status == STATUS_NOT_FOUND);
+    }
+
+    #[test]
+    fn content_type_parameters_are_ignored() {
+        let request = request("/v1/verify", "Application/CBOR; charset=binary", "*/*", b"");
+
+        assert!(Format::of_request(&request) == Ok(Format::Cbor));
+        assert!(Format::of_response(&request) == Format::Json);
+    }
+}
@@ -0,0 +1,144 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! An axum `Router` serving `service::handle()`.
+//!
+//! This module is only available with the `axum-service` feature.  The
+//! simplest way to stand up an issuer is:
+//!
+//! ```rust,ignore
+//! let app = aeonflux::service::router::router(issuer);
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
+//!
+//! axum::serve(listener, app).await?;
+//! ```
+//!
+//! Every request is passed to `service::handle()` as it is, so the endpoints,
+//! content negotiation, and errors are exactly those of the `service` module.
+
+use std::future::{ready, Ready};
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::header;
+use axum::http::HeaderMap;
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
+use axum::http::Method;
+use axum::http::StatusCode;
+use axum::http::Uri;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Router;
+
+use issuer::Issuer;
+use rng::default_rng;
+use service;
+
+/// The shared state of an issuer service.
+pub type IssuerState = Arc<Issuer>;
+
+fn header_str(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Handle any request, by passing it to `service::handle()`.
+pub fn handler(
+    State(issuer): State<IssuerState>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Ready<Response>
+{
+    let request = service::Request {
+        method: method.as_str(),
+        path: uri.path(),
+        content_type: header_str(&headers, header::CONTENT_TYPE),
+        accept: header_str(&headers, header::ACCEPT),
+        body: &body,
+    };
+    let response: service::Response = service::handle(&issuer, &request, &mut default_rng());
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    ready((status, [(header::CONTENT_TYPE, HeaderValue::from_static(response.content_type))], response.body).into_response())
+}
+
+/// Create an axum `Router` serving every endpoint for an `issuer`.
+pub fn router(issuer: Issuer) -> Router {
+    Router::new().fallback(handler).with_state(Arc::new(issuer))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    use parameters::SystemParameters;
+    use service::CONTENT_TYPE_JSON;
+    use service::ParametersResponse;
+    use test_utils::H;
+
+    use rand::thread_rng;
+
+    fn body(response: Ready<Response>) -> (StatusCode, Vec<u8>) {
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn noop(_: *const ()) { }
+        fn clone(_: *const ()) -> RawWaker { RawWaker::new(0 as *const (), &VTABLE) }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let waker = unsafe { Waker::from_raw(RawWaker::new(0 as *const (), &VTABLE)) };
+        let mut context = Context::from_waker(&waker);
+        let mut response = response;
+
+        let response: Response = match Pin::new(&mut response).poll(&mut context) {
+            Poll::Ready(x) => x,
+            Poll::Pending  => panic!(),
+        };
+        let status = response.status();
+        let mut body = Box::pin(::axum::body::to_bytes(response.into_body(), usize::max_value()));
+
+        match body.as_mut().poll(&mut context) {
+            Poll::Ready(Ok(x)) => (status, x.to_vec()),
+            _                  => panic!(),
+        }
+    }
+
+    #[test]
+    fn requests_are_passed_to_handle() {
+        let mut rng = thread_rng();
+        let issuer: IssuerState = Arc::new(Issuer::create(SystemParameters::from(H), &mut rng));
+        let mut headers = HeaderMap::new();
+
+        headers.insert(header::ACCEPT, HeaderValue::from_static(CONTENT_TYPE_JSON));
+
+        let (status, response) = body(handler(State(issuer.clone()), Method::GET, "/v1/parameters".parse().unwrap(),
+                                              headers.clone(), Bytes::new()));
+        let parameters: ParametersResponse = ::serde_json::from_slice(&response).unwrap();
+
+        assert!(status == StatusCode::OK);
+        assert!(parameters.issuer_parameters == issuer.get_issuer_parameters());
+
+        let (status, _) = body(handler(State(issuer.clone()), Method::POST, "/v1/parameters".parse().unwrap(),
+                                       headers.clone(), Bytes::new()));
+
+        assert!(status == StatusCode::METHOD_NOT_ALLOWED);
+
+        let (status, _) = body(handler(State(issuer.clone()), Method::GET, "/v2/parameters".parse().unwrap(),
+                                       headers, Bytes::new()));
+
+        assert!(status == StatusCode::NOT_FOUND);
+    }
+}
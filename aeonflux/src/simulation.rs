@@ -0,0 +1,305 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! An in-process simulation of gate sessions over an unreliable network.
+//!
+//! A `Simulation` runs a `GateClient` against fresh `GateServer` sessions
+//! for an `Issuer`, carrying every message as a sealed envelope over a
+//! simulated network which loses packets at random and which an adversary
+//! may tamper with according to a script.  Each scripted `Tamper` applies to
+//! the next message sent in its `Direction`:
+//!
+//! * `Drop` loses the message,
+//! * `Duplicate` delivers it twice,
+//! * `Replay` first delivers the previous message sent in that direction,
+//!   e.g. one from an earlier session, so that it arrives out of order,
+//! * `Truncate` delivers only a prefix of it, and
+//! * `Downgrade` rewrites its envelope to claim an earlier version.
+//!
+//! Time only moves when a message is sent, or when a client waiting on a
+//! lost answer is left to time out, and randomness comes from a seeded
+//! `DeterministicRng`, so every run is reproducible from its seed and script.
+//! Everything which happens on the network is recorded as an `Event`.
+//!
+//! This module is only available with the `simulation` feature.
+
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+use rand_core::RngCore;
+
+use credential::RevealedAttribute;
+use envelope::open;
+use envelope::seal;
+use errors::Error;
+use errors::GateError;
+use gate::ClientState;
+use gate::GateClient;
+use gate::GateConfig;
+use gate::GateMessage;
+use gate::GateServer;
+use gate::ServerState;
+use issuer::Issuer;
+use rng::DeterministicRng;
+
+/// Which way a message travels.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Direction {
+    ToServer,
+    ToClient,
+}
+
+impl Direction {
+    fn index(&self) -> usize {
+        match *self {
+            Direction::ToServer => 0,
+            Direction::ToClient => 1,
+        }
+    }
+}
+
+/// What the adversary does to a single message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Tamper {
+    Deliver,
+    Drop,
+    Duplicate,
+    Replay,
+    /// Deliver only the first this many bytes.
+    Truncate(usize),
+    Downgrade,
+}
+
+/// Something which happened on the simulated network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// A message was lost, at random or by the adversary.
+    Dropped { now: u64, direction: Direction },
+    /// A packet of `length` bytes arrived.
+    Delivered { now: u64, direction: Direction, length: usize },
+    /// A packet which arrived was refused by its recipient.
+    Refused { now: u64, direction: Direction, error: Error },
+}
+
+/// The states of both sides once a simulated session has ended.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    pub client: ClientState,
+    pub server: ServerState,
+}
+
+/// A simulated network between gate clients and an issuer.
+pub struct Simulation<'a> {
+    pub issuer: &'a Issuer,
+    pub config: GateConfig,
+    /// The number of seconds each message takes to arrive.
+    pub latency: u64,
+    /// The percentage of messages lost at random.
+    pub loss: u8,
+    /// Everything which has happened on the network so far.
+    pub events: Vec<Event>,
+    now: u64,
+    rng: DeterministicRng,
+    scripts: [VecDeque<Tamper>; 2],
+    history: [Vec<Vec<u8>>; 2],
+}
+
+impl<'a> Simulation<'a> {
+    /// Create a reliable network for an `issuer`, whose randomness is derived
+    /// from a `seed`.
+    pub fn new(issuer: &'a Issuer, config: GateConfig, seed: u64) -> Simulation<'a> {
+        Simulation {
+            issuer: issuer,
+            config: config,
+            latency: 1,
+            loss: 0,
+            events: Vec::new(),
+            now: 0,
+            rng: DeterministicRng::from(seed),
+            scripts: [VecDeque::new(), VecDeque::new()],
+            history: [Vec::new(), Vec::new()],
+        }
+    }
+
+    /// The current simulated time.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Tamper with the next messages sent in a `direction`, one `Tamper` per
+    /// message, after any already scripted.
+    pub fn script(&mut self, direction: Direction, tampers: &[Tamper]) {
+        self.scripts[direction.index()].extend(tampers.iter().cloned());
+    }
+
+    /// Run an issuance session for a `client` over revealed `attributes`.
+    pub fn obtain(&mut self, client: &mut GateClient, attributes: Vec<RevealedAttribute>) -> Outcome {
+        let message = client.start_issuance(attributes, self.now);
+
+        self.run(client, message)
+    }
+
+    /// Run a blind issuance session for a `client` over encrypted
+    /// `attributes`.
+    pub fn blind_obtain(&mut self, client: &mut GateClient, attributes: Vec<RevealedAttribute>) -> Outcome {
+        let message = client.start_blind_issuance(attributes, self.now, &mut self.rng);
+
+        self.run(client, message)
+    }
+
+    /// Run a presentation session for a `client`.
+    pub fn present(&mut self, client: &mut GateClient) -> Outcome {
+        let message = client.start_presentation(self.now, &mut self.rng);
+
+        self.run(client, message)
+    }
+
+    fn run(&mut self, client: &mut GateClient, message: Result<GateMessage, GateError>) -> Outcome {
+        let issuer: &'a Issuer = self.issuer;
+        let mut server = GateServer::open(issuer, self.config, self.now);
+
+        let message: GateMessage = match message {
+            Ok(x)  => x,
+            Err(_) => return Outcome { client: client.state(), server: server.state().clone() },
+        };
+        let mut answers: Vec<GateMessage> = Vec::new();
+
+        for packet in self.transmit(Direction::ToServer, seal(&message)) {
+            let now = self.now;
+            let result: Result<GateMessage, Error> = match open::<GateMessage>(&packet) {
+                Ok((request, _)) => server.handle(&request, now, &mut self.rng),
+                Err(x)           => Err(x.into()),
+            };
+
+            match result {
+                Ok(answer) => answers.push(answer),
+                Err(error) => self.events.push(Event::Refused { now, direction: Direction::ToServer, error }),
+            }
+        }
+        for answer in answers.iter() {
+            for packet in self.transmit(Direction::ToClient, seal(answer)) {
+                let now = self.now;
+                let result: Result<ClientState, Error> = match open::<GateMessage>(&packet) {
+                    Ok((x, _)) => client.handle(&x, now),
+                    Err(x)     => Err(x.into()),
+                };
+
+                if let Err(error) = result {
+                    self.events.push(Event::Refused { now, direction: Direction::ToClient, error });
+                }
+            }
+        }
+
+        // A client still waiting has lost its answer, and gives up.
+        match client.state() {
+            ClientState::AwaitingIssuance { .. }      |
+            ClientState::AwaitingBlindIssuance { .. } |
+            ClientState::AwaitingVerdict { .. }       => {
+                self.now += self.config.timeout + 1;
+                client.poll_timeout(self.now);
+            },
+            _ => (),
+        }
+
+        Outcome { client: client.state(), server: server.state().clone() }
+    }
+
+    /// Send a message in a `direction`, returning the packets which arrive.
+    fn transmit(&mut self, direction: Direction, message: Vec<u8>) -> Vec<Vec<u8>> {
+        let d: usize = direction.index();
+        let tamper: Tamper = self.scripts[d].pop_front().unwrap_or(Tamper::Deliver);
+        let lost: bool = (self.rng.next_u32() % 100) < self.loss as u32;
+        let mut packets: Vec<Vec<u8>> = Vec::new();
+
+        self.now += self.latency;
+
+        if tamper == Tamper::Replay {
+            if let Some(previous) = self.history[d].last() {
+                packets.push(previous.clone());
+            }
+        }
+        self.history[d].push(message.clone());
+
+        match tamper {
+            Tamper::Deliver | Tamper::Replay => packets.push(message),
+            Tamper::Drop                     => (),
+            Tamper::Duplicate                => {
+                packets.push(message.clone());
+                packets.push(message);
+            },
+            Tamper::Truncate(length)         => packets.push(message[..length.min(message.len())].to_vec()),
+            Tamper::Downgrade                => {
+                let mut downgraded: Vec<u8> = message;
+
+                downgraded[0] = downgraded[0].wrapping_sub(1);
+                packets.push(downgraded);
+            },
+        }
+        if lost {
+            packets.clear();
+        }
+
+        let now = self.now;
+
+        if packets.is_empty() {
+            self.events.push(Event::Dropped { now, direction });
+        }
+        for packet in packets.iter() {
+            self.events.push(Event::Delivered { now, direction, length: packet.len() });
+        }
+        packets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use errors::DecodeError;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use user::User;
+
+    #[test]
+    fn scripted_tampering() {
+        let mut rng = DeterministicRng::from(1);
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut client = GateClient::new(user, GateConfig::default());
+        let mut simulation = Simulation::new(&issuer, GateConfig::default(), 2);
+
+        // The first answer is lost, so the client times out and retries.
+        simulation.script(Direction::ToClient, &[Tamper::Drop]);
+
+        assert!(simulation.obtain(&mut client, vec![Scalar::random(&mut rng)]).client ==
+                ClientState::Failed(GateError::Timeout));
+        assert!(simulation.obtain(&mut client, vec![Scalar::random(&mut rng)]).client == ClientState::Holding);
+
+        // A truncated presentation is refused, and a duplicated verdict is
+        // refused the second time.
+        simulation.script(Direction::ToServer, &[Tamper::Truncate(40)]);
+        simulation.present(&mut client);
+        simulation.script(Direction::ToClient, &[Tamper::Duplicate]);
+
+        let before: usize = simulation.events.len();
+
+        assert!(simulation.present(&mut client).client == ClientState::Admitted);
+        assert!(simulation.events[before..].iter().any(|e| match *e {
+            Event::Refused { direction: Direction::ToClient, error: Error::Protocol, .. } => true,
+            _ => false,
+        }));
+        assert!(simulation.events.iter().any(|e| match *e {
+            Event::Refused { direction: Direction::ToServer, error: Error::Decode(DecodeError::Malformed), .. } => true,
+            _ => false,
+        }));
+    }
+}
@@ -0,0 +1,655 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Persistent storage of protocol state.
+//!
+//! A `Store` is a key-value store of bytes with an atomic compare-and-swap.
+//! On top of any `Store`:
+//!
+//! * `RecordStore` gets and puts issuer key material and wallet contents,
+//!   i.e. any `Record`, by an identifier chosen by the caller,
+//! * `RevocationStore` records which identifiers each revocation registry
+//!   has revoked, and
+//! * `NullifierSetStore` records the nullifiers spent in each scope, and
+//!   `StoredNullifiers` adapts one scope to the `NullifierStore` used by
+//!   `RateLimiter` and `TicketVerifier`.
+//!
+//! Every kind of state is kept under its own key prefix, so one `Store` may
+//! hold all of them.  A `MemoryStore` is always available, a `FileStore`,
+//! keeping each value in its own file, with `std`, and a `SledStore` with the
+//! `sled-store` feature.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeMap;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::ErrorKind;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::string::String;
+
+#[cfg(feature = "backup")]
+use backup::Backup;
+use consent::DisclosureLog;
+use credential::Credential;
+use device::DeviceCredential;
+use encoding::u64_to_bytes;
+use errors::Error;
+use errors::StorageError;
+use issuer::Issuer;
+use pass::Pass;
+use rate_limit::Nullifier;
+use rate_limit::NullifierStore;
+use recovery::Sharing;
+use tokens::Token;
+use tokens::TokenKeypair;
+use user::User;
+use vault::SealedAttributes;
+use voprf::ServerKeypair;
+use wallet::WalletEntry;
+
+/// The key prefix of revoked identifiers.
+const REVOCATION_PREFIX: &'static [u8] = b"revoked/";
+
+/// The key prefix of spent nullifiers.
+const NULLIFIER_PREFIX: &'static [u8] = b"nullifier/";
+
+/// A key-value store of bytes.
+pub trait Store {
+    /// The value stored under `key`, if any.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Store `value` under `key`, replacing any existing value.
+    fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), StorageError>;
+
+    /// Remove any value stored under `key`.
+    fn remove(&mut self, key: &[u8]) -> Result<(), StorageError>;
+
+    /// Atomically replace the value under `key` with `new`, only if it is
+    /// currently `expected`.  `None` stands for there being no value, both as
+    /// the `expected` and the `new` value.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value was replaced, and `false` if it was not `expected`.
+    fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<bool, StorageError>;
+
+    /// Remove every value whose key begins with `prefix`.
+    fn remove_prefix(&mut self, prefix: &[u8]) -> Result<(), StorageError>;
+}
+
+/// A type which may be kept in a `RecordStore`.
+pub trait Record: Sized {
+    /// The key prefix of records of this type.
+    const PREFIX: &'static [u8];
+
+    fn to_record(&self) -> Vec<u8>;
+
+    fn from_record(bytes: &[u8]) -> Result<Self, StorageError>;
+}
+
+macro_rules! impl_record_with_to_bytes_and_from_bytes {
+    ($t:tt, $prefix:expr) => {
+        impl Record for $t {
+            const PREFIX: &'static [u8] = $prefix;
+
+            fn to_record(&self) -> Vec<u8> {
+                self.to_bytes()[..].to_vec()
+            }
+
+            fn from_record(bytes: &[u8]) -> Result<$t, StorageError> {
+                $t::from_bytes(bytes).or(Err(StorageError::Corrupt))
+            }
+        }
+    }
+}
+
+// Issuer key material.
+impl_record_with_to_bytes_and_from_bytes!(Issuer, b"issuer/");
+impl_record_with_to_bytes_and_from_bytes!(TokenKeypair, b"token-keypair/");
+impl_record_with_to_bytes_and_from_bytes!(ServerKeypair, b"voprf-keypair/");
+
+// Issuer records of received attributes, which are only ever stored sealed.
+impl_record_with_to_bytes_and_from_bytes!(SealedAttributes, b"sealed/");
+
+// Wallet contents.
+impl_record_with_to_bytes_and_from_bytes!(User, b"user/");
+impl_record_with_to_bytes_and_from_bytes!(Credential, b"credential/");
+impl_record_with_to_bytes_and_from_bytes!(DeviceCredential, b"device-credential/");
+impl_record_with_to_bytes_and_from_bytes!(Pass, b"pass/");
+impl_record_with_to_bytes_and_from_bytes!(Token, b"token/");
+impl_record_with_to_bytes_and_from_bytes!(DisclosureLog, b"disclosures/");
+impl_record_with_to_bytes_and_from_bytes!(WalletEntry, b"wallet/");
+impl_record_with_to_bytes_and_from_bytes!(Sharing, b"recovery-sharing/");
+#[cfg(feature = "backup")]
+impl_record_with_to_bytes_and_from_bytes!(Backup, b"issuer-backup/");
+
+/// Concatenate a key `prefix` and the `parts` of a key, each but the last
+/// preceded by its length, as in `encoding::u64_to_bytes`, so that no two
+/// keys collide.
+fn key(prefix: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut v: Vec<u8> = Vec::with_capacity(prefix.len() + parts.iter().map(|x| 8 + x.len()).sum::<usize>());
+
+    v.extend_from_slice(prefix);
+
+    for (i, part) in parts.iter().enumerate() {
+        if i + 1 < parts.len() {
+            v.extend_from_slice(&u64_to_bytes(part.len() as u64));
+        }
+        v.extend_from_slice(part);
+    }
+    v
+}
+
+/// Typed access to the `Record`s in a `Store`.
+pub trait RecordStore: Store {
+    /// The record of type `R` stored under `id`, if any.
+    ///
+    /// # Errors
+    ///
+    /// * `StorageError::Corrupt` if the record could not be decoded.
+    fn get_record<R: Record>(&self, id: &[u8]) -> Result<Option<R>, Error> {
+        match self.get(&key(R::PREFIX, &[id]))? {
+            Some(x) => Ok(Some(R::from_record(&x)?)),
+            None    => Ok(None),
+        }
+    }
+
+    /// Store a `record` under `id`, replacing any existing one.
+    fn put_record<R: Record>(&mut self, id: &[u8], record: &R) -> Result<(), Error> {
+        Ok(self.put(&key(R::PREFIX, &[id]), &record.to_record())?)
+    }
+
+    /// Remove any record of type `R` stored under `id`.
+    fn remove_record<R: Record>(&mut self, id: &[u8]) -> Result<(), Error> {
+        Ok(self.remove(&key(R::PREFIX, &[id]))?)
+    }
+
+    /// Atomically replace the record under `id` with `new`, only if it is
+    /// currently `expected`, as with `Store::compare_and_swap`.
+    ///
+    /// Records are compared by their encodings.
+    fn compare_and_swap_record<R: Record>(
+        &mut self,
+        id: &[u8],
+        expected: Option<&R>,
+        new: Option<&R>,
+    ) -> Result<bool, Error>
+    {
+        let expected: Option<Vec<u8>> = expected.map(|x| x.to_record());
+        let new: Option<Vec<u8>> = new.map(|x| x.to_record());
+
+        Ok(self.compare_and_swap(&key(R::PREFIX, &[id]),
+                                 expected.as_ref().map(|x| &x[..]),
+                                 new.as_ref().map(|x| &x[..]))?)
+    }
+}
+
+impl<S: Store> RecordStore for S { }
+
+/// The identifiers revoked by each revocation registry in a `Store`.
+pub trait RevocationStore: Store {
+    /// Revoke `id` in the given `registry`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `id` was not already revoked.
+    fn revoke(&mut self, registry: &[u8], id: &[u8]) -> Result<bool, Error> {
+        Ok(self.compare_and_swap(&key(REVOCATION_PREFIX, &[registry, id]), None, Some(&b""[..]))?)
+    }
+
+    /// Whether `id` has been revoked in the given `registry`.
+    fn is_revoked(&self, registry: &[u8], id: &[u8]) -> Result<bool, Error> {
+        Ok(self.get(&key(REVOCATION_PREFIX, &[registry, id]))?.is_some())
+    }
+
+    /// Forget every identifier revoked in the given `registry`.
+    fn clear_revocations(&mut self, registry: &[u8]) -> Result<(), Error> {
+        Ok(self.remove_prefix(&key(REVOCATION_PREFIX, &[registry, &b""[..]]))?)
+    }
+}
+
+impl<S: Store> RevocationStore for S { }
+
+/// The nullifiers spent in each scope in a `Store`.
+pub trait NullifierSetStore: Store {
+    /// Record `nullifier` as spent in `scope`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the nullifier was not already spent.
+    fn insert_nullifier(&mut self, scope: &[u8], nullifier: &Nullifier) -> Result<bool, Error> {
+        Ok(self.compare_and_swap(&key(NULLIFIER_PREFIX, &[scope, &nullifier.0[..]]), None, Some(&b""[..]))?)
+    }
+
+    /// Whether `nullifier` has been spent in `scope`.
+    fn contains_nullifier(&self, scope: &[u8], nullifier: &Nullifier) -> Result<bool, Error> {
+        Ok(self.get(&key(NULLIFIER_PREFIX, &[scope, &nullifier.0[..]]))?.is_some())
+    }
+
+    /// Forget every nullifier spent in `scope`.
+    fn clear_nullifiers(&mut self, scope: &[u8]) -> Result<(), Error> {
+        Ok(self.remove_prefix(&key(NULLIFIER_PREFIX, &[scope, &b""[..]]))?)
+    }
+}
+
+impl<S: Store> NullifierSetStore for S { }
+
+/// The nullifiers of one `scope` of a `Store`, as a `NullifierStore`.
+///
+/// Since a `NullifierStore` cannot report errors, a nullifier which could
+/// not be checked or recorded is treated as already spent, and a failure to
+/// clear the scope leaves its nullifiers in place, so that a failing store
+/// refuses presentations rather than permitting replays.
+pub struct StoredNullifiers<S: Store> {
+    store: S,
+    scope: Vec<u8>,
+}
+
+impl<S: Store> StoredNullifiers<S> {
+    pub fn new(store: S, scope: &[u8]) -> StoredNullifiers<S> {
+        StoredNullifiers { store, scope: scope.to_vec() }
+    }
+
+    /// Consume this set, returning the underlying `Store`.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<S: Store> NullifierStore for StoredNullifiers<S> {
+    fn insert(&mut self, nullifier: &Nullifier) -> bool {
+        self.store.insert_nullifier(&self.scope, nullifier).unwrap_or(false)
+    }
+
+    fn contains(&self, nullifier: &Nullifier) -> bool {
+        self.store.contains_nullifier(&self.scope, nullifier).unwrap_or(true)
+    }
+
+    fn clear(&mut self) {
+        let _ = self.store.clear_nullifiers(&self.scope);
+    }
+}
+
+/// A `Store` held in memory, e.g. for tests, or for state which is rebuilt
+/// upon restarting.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct MemoryStore(BTreeMap<Vec<u8>, Vec<u8>>);
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore(BTreeMap::new())
+    }
+}
+
+impl Store for MemoryStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.0.get(key).cloned())
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        self.0.insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Result<(), StorageError> {
+        self.0.remove(key);
+        Ok(())
+    }
+
+    fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<bool, StorageError>
+    {
+        if self.0.get(key).map(|x| &x[..]) != expected {
+            return Ok(false);
+        }
+        match new {
+            Some(x) => self.0.insert(key.to_vec(), x.to_vec()),
+            None    => self.0.remove(key),
+        };
+        Ok(true)
+    }
+
+    fn remove_prefix(&mut self, prefix: &[u8]) -> Result<(), StorageError> {
+        let keys: Vec<Vec<u8>> = self.0.range(prefix.to_vec()..)
+            .map(|(k, _)| k)
+            .take_while(|k| k.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        for k in keys.iter() {
+            self.0.remove(k);
+        }
+        Ok(())
+    }
+}
+
+/// A `Store` which keeps each value in its own file, within one directory.
+///
+/// Values are replaced by writing a temporary file and renaming it over the
+/// old one, so a reader never sees a partially written value.  Each
+/// `compare_and_swap` is only atomic with respect to others made through the
+/// same `FileStore`, so no two processes should use the same directory.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    directory: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileStore {
+    /// Open, or create, the store in the directory at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<FileStore, Error> {
+        fs::create_dir_all(path.as_ref()).or(Err(StorageError::Backend))?;
+
+        Ok(FileStore { directory: path.as_ref().to_path_buf() })
+    }
+
+    /// The file holding the value under `key`, which is named by the key in
+    /// hexadecimal.
+    fn path(&self, key: &[u8]) -> PathBuf {
+        self.directory.join(to_hex(key))
+    }
+}
+
+#[cfg(feature = "std")]
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &'static [u8; 16] = b"0123456789abcdef";
+
+    let mut s = String::with_capacity(2 * bytes.len() + 2);
+
+    // Prefixed, so that the empty key still names a file.
+    s.push_str("k-");
+    for b in bytes.iter() {
+        s.push(DIGITS[(b >> 4) as usize] as char);
+        s.push(DIGITS[(b & 0x0f) as usize] as char);
+    }
+    s
+}
+
+#[cfg(feature = "std")]
+fn from_hex(name: &str) -> Option<Vec<u8>> {
+    let digits: &[u8] = name.as_bytes();
+
+    if !name.starts_with("k-") || digits.len() % 2 != 0 {
+        return None;
+    }
+
+    let nibble = |c: u8| -> Option<u8> {
+        match c {
+            b'0'...b'9' => Some(c - b'0'),
+            b'a'...b'f' => Some(c - b'a' + 10),
+            _           => None,
+        }
+    };
+    let mut bytes: Vec<u8> = Vec::with_capacity(digits.len() / 2 - 1);
+
+    for pair in digits[2..].chunks(2) {
+        bytes.push((nibble(pair[0])? << 4) | nibble(pair[1])?);
+    }
+    Some(bytes)
+}
+
+#[cfg(feature = "std")]
+impl Store for FileStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        match fs::read(self.path(key)) {
+            Ok(x)                                         => Ok(Some(x)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(_)                                        => Err(StorageError::Backend),
+        }
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        let path: PathBuf = self.path(key);
+        let temporary: PathBuf = path.with_extension("tmp");
+        let mut file = fs::File::create(&temporary).or(Err(StorageError::Backend))?;
+
+        file.write_all(value).or(Err(StorageError::Backend))?;
+        file.sync_all().or(Err(StorageError::Backend))?;
+        fs::rename(&temporary, &path).or(Err(StorageError::Backend))
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Result<(), StorageError> {
+        match fs::remove_file(self.path(key)) {
+            Ok(())                                        => Ok(()),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(_)                                        => Err(StorageError::Backend),
+        }
+    }
+
+    fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<bool, StorageError>
+    {
+        if self.get(key)?.as_ref().map(|x| &x[..]) != expected {
+            return Ok(false);
+        }
+        match new {
+            Some(x) => self.put(key, x)?,
+            None    => self.remove(key)?,
+        };
+        Ok(true)
+    }
+
+    fn remove_prefix(&mut self, prefix: &[u8]) -> Result<(), StorageError> {
+        let entries = fs::read_dir(&self.directory).or(Err(StorageError::Backend))?;
+
+        for entry in entries {
+            let entry = entry.or(Err(StorageError::Backend))?;
+            let key: Option<Vec<u8>> = entry.file_name().to_str().and_then(from_hex);
+
+            match key {
+                Some(ref k) if k.starts_with(prefix) => self.remove(k)?,
+                _                                    => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `Store` backed by a sled tree on disk.
+#[cfg(feature = "sled-store")]
+#[derive(Clone)]
+pub struct SledStore(pub ::sled::Tree);
+
+#[cfg(feature = "sled-store")]
+impl SledStore {
+    /// Open, or create, the sled database at `path` and use its `aeonflux`
+    /// tree.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SledStore, StorageError> {
+        let db = ::sled::open(path).or(Err(StorageError::Backend))?;
+
+        Ok(SledStore(db.open_tree(b"aeonflux").or(Err(StorageError::Backend))?))
+    }
+
+    /// Write everything stored so far to disk.
+    pub fn flush(&self) -> Result<(), StorageError> {
+        self.0.flush().map(|_| ()).or(Err(StorageError::Backend))
+    }
+}
+
+#[cfg(feature = "sled-store")]
+impl Store for SledStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.0.get(key) {
+            Ok(x)  => Ok(x.map(|v| v.to_vec())),
+            Err(_) => Err(StorageError::Backend),
+        }
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        self.0.insert(key, value).map(|_| ()).or(Err(StorageError::Backend))
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Result<(), StorageError> {
+        self.0.remove(key).map(|_| ()).or(Err(StorageError::Backend))
+    }
+
+    fn compare_and_swap(
+        &mut self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<bool, StorageError>
+    {
+        match self.0.compare_and_swap(key, expected, new) {
+            Ok(Ok(()))   => Ok(true),
+            Ok(Err(_))   => Ok(false),
+            Err(_)       => Err(StorageError::Backend),
+        }
+    }
+
+    fn remove_prefix(&mut self, prefix: &[u8]) -> Result<(), StorageError> {
+        for k in self.0.scan_prefix(prefix).keys() {
+            let k = k.or(Err(StorageError::Backend))?;
+
+            self.0.remove(k).or(Err(StorageError::Backend))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    #[test]
+    fn records_round_trip_and_swap() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let rotated = Issuer::create(system_parameters, &mut rng);
+        let mut store = MemoryStore::new();
+
+        assert!(store.get_record::<Issuer>(b"gate").unwrap().is_none());
+
+        store.put_record(b"gate", &issuer).unwrap();
+
+        assert!(store.get_record::<Issuer>(b"gate").unwrap().unwrap().to_bytes() == issuer.to_bytes());
+        assert!(store.get_record::<User>(b"gate").unwrap().is_none());
+
+        // Only the holder of the current key may rotate it.
+        assert!(!store.compare_and_swap_record(b"gate", Some(&rotated), Some(&issuer)).unwrap());
+        assert!(store.compare_and_swap_record(b"gate", Some(&issuer), Some(&rotated)).unwrap());
+        assert!(store.get_record::<Issuer>(b"gate").unwrap().unwrap().to_bytes() == rotated.to_bytes());
+
+        store.put(&key(Issuer::PREFIX, &[&b"broken"[..]]), b"not an issuer").unwrap();
+
+        assert!(store.get_record::<Issuer>(b"broken").err() == Some(StorageError::Corrupt.into()));
+    }
+
+    #[test]
+    fn revocations_and_nullifiers_are_scoped() {
+        let mut store = MemoryStore::new();
+        let nullifier = Nullifier([7u8; 32]);
+
+        assert!(store.revoke(b"registry", b"alice").unwrap());
+        assert!(!store.revoke(b"registry", b"alice").unwrap());
+        assert!(store.is_revoked(b"registry", b"alice").unwrap());
+        assert!(!store.is_revoked(b"registr", b"yalice").unwrap());
+
+        let mut nullifiers = StoredNullifiers::new(store, b"epoch 1");
+
+        assert!(nullifiers.insert(&nullifier));
+        assert!(!nullifiers.insert(&nullifier));
+        assert!(nullifiers.contains(&nullifier));
+
+        nullifiers.clear();
+
+        assert!(!nullifiers.contains(&nullifier));
+
+        // Clearing the nullifiers leaves every other kind of state alone.
+        let store: MemoryStore = nullifiers.into_inner();
+
+        assert!(store.is_revoked(b"registry", b"alice").unwrap());
+    }
+
+    #[test]
+    fn file_store_persists_values() {
+        let directory = ::std::env::temp_dir().join(format!("aeonflux-file-store-{}", ::std::process::id()));
+        let mut store = FileStore::open(&directory).unwrap();
+
+        store.put(b"revoked/alice", b"1").unwrap();
+        store.put(b"", b"empty key").unwrap();
+
+        assert!(!store.compare_and_swap(b"nullifier/x", Some(b"1"), Some(b"2")).unwrap());
+        assert!(store.compare_and_swap(b"nullifier/x", None, Some(b"1")).unwrap());
+
+        let mut store = FileStore::open(&directory).unwrap();
+
+        assert!(store.get(b"revoked/alice").unwrap() == Some(b"1".to_vec()));
+        assert!(store.get(b"").unwrap() == Some(b"empty key".to_vec()));
+
+        store.remove_prefix(b"revoked/").unwrap();
+
+        assert!(store.get(b"revoked/alice").unwrap().is_none());
+        assert!(store.get(b"nullifier/x").unwrap() == Some(b"1".to_vec()));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(feature = "sled-store")]
+    #[test]
+    fn sled_store_persists_values() {
+        let directory = ::std::env::temp_dir().join(format!("aeonflux-sled-store-{}", ::std::process::id()));
+        let mut store = SledStore::open(&directory).unwrap();
+
+        store.put(b"revoked/alice", b"1").unwrap();
+
+        assert!(!store.compare_and_swap(b"nullifier/x", Some(b"1"), Some(b"2")).unwrap());
+        assert!(store.compare_and_swap(b"nullifier/x", None, Some(b"1")).unwrap());
+
+        store.flush().unwrap();
+        drop(store);
+
+        let mut store = SledStore::open(&directory).unwrap();
+
+        assert!(store.get(b"revoked/alice").unwrap() == Some(b"1".to_vec()));
+
+        store.remove_prefix(b"revoked/").unwrap();
+
+        assert!(store.get(b"revoked/alice").unwrap().is_none());
+        assert!(store.get(b"nullifier/x").unwrap() == Some(b"1".to_vec()));
+
+        drop(store);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Streamed responses to batches of thousands of credential requests.
+//!
+//! Rather than issuing a whole batch and sending it as one multi-megabyte
+//! response, an issuer may `stream_issuance()`, which issues each credential
+//! only as its frame is taken, and a client may feed whatever bytes have
+//! arrived to an `IssuanceDecoder`, finishing each credential as soon as its
+//! frame is complete.  Neither side ever holds more than one frame beyond
+//! what it needs.
+//!
+//! A stream is a sequence of frames, each a four-byte little-endian length
+//! followed by that many bytes: a kind, then the eight-byte index of the
+//! request it answers, then its payload.  The first frame is a header
+//! carrying the number of issuances to follow; each further frame carries
+//! either the `CredentialIssuance` for the request at its index, or a
+//! refusal, if the issuer could not issue for that request.  Frames arrive
+//! in the order of the requests, and no frame may be longer than
+//! `MAXIMUM_FRAME_SIZE`.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use credential::CredentialIssuance;
+use credential::CredentialRequest;
+use encoding::u64_to_bytes;
+use errors::Error;
+use errors::StreamError;
+use issuer::Issuer;
+use user::User;
+
+/// The longest frame, in bytes, which a decoder will buffer.
+pub const MAXIMUM_FRAME_SIZE: usize = 1 << 16;
+
+/// The frame kinds, which are part of the encoding and must never be reused.
+const FRAME_HEADER: u8 = 0;
+const FRAME_ISSUANCE: u8 = 1;
+const FRAME_REFUSAL: u8 = 2;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+/// Encode a frame of some `kind`, answering the request at `index`.
+fn encode_frame(kind: u8, index: u64, payload: &[u8]) -> Vec<u8> {
+    let length: usize = 1 + 8 + payload.len();
+    let mut v: Vec<u8> = Vec::with_capacity(4 + length);
+
+    for i in 0..4 {
+        v.push((length >> (8 * i)) as u8);
+    }
+    v.push(kind);
+    v.extend(u64_to_bytes(index).iter());
+    v.extend(payload.iter());
+    v
+}
+
+/// The frames of a streamed batch issuance, each issued when it is taken.
+pub struct IssuanceStream<'a, R: 'a> {
+    issuer: &'a Issuer,
+    requests: &'a [CredentialRequest],
+    csprng: &'a mut R,
+    next: Option<usize>,
+}
+
+/// Issue a credential for each of `requests`, as `Issuer::issue()`, one
+/// frame at a time.
+///
+/// The first frame taken from the stream is its header, and each further
+/// frame the issuance for the next request, or a refusal if it could not be
+/// issued.
+pub fn stream_issuance<'a, R>(
+    issuer: &'a Issuer,
+    requests: &'a [CredentialRequest],
+    csprng: &'a mut R,
+) -> IssuanceStream<'a, R>
+where
+    R: RngCore + CryptoRng,
+{
+    IssuanceStream { issuer, requests, csprng, next: None }
+}
+
+impl<'a, R> Iterator for IssuanceStream<'a, R>
+where
+    R: RngCore + CryptoRng,
+{
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let index: usize = match self.next {
+            None => {
+                self.next = Some(0);
+
+                return Some(encode_frame(FRAME_HEADER, 0, &u64_to_bytes(self.requests.len() as u64)));
+            },
+            Some(i) if i < self.requests.len() => i,
+            Some(_) => return None,
+        };
+
+        self.next = Some(index + 1);
+
+        let frame = match self.issuer.issue(&self.requests[index], &mut *self.csprng) {
+            Ok(issuance) => encode_frame(FRAME_ISSUANCE, index as u64, &issuance.to_bytes()),
+            Err(_)       => encode_frame(FRAME_REFUSAL, index as u64, &[]),
+        };
+
+        Some(frame)
+    }
+}
+
+/// One complete frame of a streamed batch issuance, after the header.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Frame {
+    /// The issuance for the request at an index.
+    Issuance(usize, CredentialIssuance),
+    /// The issuer refused the request at an index.
+    Refusal(usize),
+}
+
+/// An incremental decoder of a streamed batch issuance.
+#[derive(Debug, Default)]
+pub struct IssuanceDecoder {
+    buffer: Vec<u8>,
+    expected: Option<usize>,
+    received: usize,
+}
+
+impl IssuanceDecoder {
+    pub fn new() -> IssuanceDecoder {
+        IssuanceDecoder::default()
+    }
+
+    /// Buffer some `bytes` as they arrive.  They need not be whole frames.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend(bytes.iter());
+    }
+
+    /// The number of issuances the stream's header announced, once it has
+    /// been decoded.
+    pub fn expected(&self) -> Option<usize> {
+        self.expected
+    }
+
+    /// Take the next complete frame from the buffered bytes, if one has
+    /// arrived.
+    ///
+    /// # Errors
+    ///
+    /// * `StreamError::FrameTooLarge` if a frame's length is greater than
+    ///   `MAXIMUM_FRAME_SIZE`.
+    /// * `StreamError::Malformed` if the stream does not begin with its
+    ///   header, a frame is of an unknown kind, out of order, or beyond the
+    ///   number announced, or its payload is not what its kind requires.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        loop {
+            if self.buffer.len() < 4 {
+                return Ok(None);
+            }
+
+            let mut length: usize = 0;
+
+            for (i, byte) in self.buffer[..4].iter().enumerate() {
+                length |= (*byte as usize) << (8 * i);
+            }
+
+            if length > MAXIMUM_FRAME_SIZE {
+                return Err(StreamError::FrameTooLarge.into());
+            }
+            if length < 1 + 8 {
+                return Err(StreamError::Malformed.into());
+            }
+            if self.buffer.len() < 4 + length {
+                return Ok(None);
+            }
+
+            let frame: Vec<u8> = self.buffer.drain(..4 + length).skip(4).collect();
+            let kind: u8 = frame[0];
+            let index: u64 = read_u64(&frame[1..9]);
+            let payload: &[u8] = &frame[9..];
+
+            let expected: usize = match (self.expected, kind) {
+                (None, FRAME_HEADER) => {
+                    if index != 0 || payload.len() != 8 {
+                        return Err(StreamError::Malformed.into());
+                    }
+                    self.expected = Some(read_u64(payload) as usize);
+                    continue;
+                },
+                (Some(n), FRAME_ISSUANCE) | (Some(n), FRAME_REFUSAL) => n,
+                _ => return Err(StreamError::Malformed.into()),
+            };
+
+            if index != self.received as u64 || self.received >= expected {
+                return Err(StreamError::Malformed.into());
+            }
+            self.received += 1;
+
+            return match kind {
+                FRAME_ISSUANCE => Ok(Some(Frame::Issuance(index as usize, CredentialIssuance::from_bytes(payload)?))),
+                _ if payload.len() == 0 => Ok(Some(Frame::Refusal(index as usize))),
+                _ => Err(StreamError::Malformed.into()),
+            };
+        }
+    }
+
+    /// Take the next complete frame, as with `next_frame()`, and finish
+    /// obtaining its credential as the user at its index in `users`, who
+    /// made the corresponding request.
+    ///
+    /// # Returns
+    ///
+    /// The index of the request the frame answered, and whether its
+    /// credential was issued and verified, or `None` if no frame is complete.
+    ///
+    /// # Errors
+    ///
+    /// * `StreamError::Malformed` if there is no user for a frame's index.
+    /// * Any error from `next_frame()`.
+    pub fn finish_next(&mut self, users: &mut [User]) -> Result<Option<(usize, Result<(), Error>)>, Error> {
+        let frame = match self.next_frame()? {
+            Some(frame) => frame,
+            None        => return Ok(None),
+        };
+
+        match frame {
+            Frame::Issuance(index, ref issuance) => match users.get_mut(index) {
+                Some(user) => Ok(Some((index, user.obtain_finish(Some(issuance))))),
+                None       => Err(StreamError::Malformed.into()),
+            },
+            Frame::Refusal(index) => Ok(Some((index, Err(StreamError::Refused.into())))),
+        }
+    }
+
+    /// Check that the whole stream has been decoded.
+    ///
+    /// # Errors
+    ///
+    /// * `StreamError::Truncated` if the header, or any frame it announced,
+    ///   has not yet been received, or part of a frame remains buffered.
+    pub fn finish(&self) -> Result<(), Error> {
+        match self.expected {
+            Some(n) if n == self.received && self.buffer.len() == 0 => Ok(()),
+            _                                                      => Err(StreamError::Truncated.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    #[test]
+    fn issuances_are_verified_as_they_arrive() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut users: Vec<User> = Vec::new();
+        let mut requests: Vec<CredentialRequest> = Vec::new();
+
+        for _ in 0..8 {
+            let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+            requests.push(user.obtain(vec![Scalar::random(&mut rng)]));
+            users.push(user);
+        }
+
+        // One request cannot be issued.
+        requests[5].attributes_revealed.clear();
+
+        let stream: Vec<u8> = stream_issuance(&issuer, &requests, &mut rng).flat_map(|frame| frame).collect();
+        let mut decoder = IssuanceDecoder::new();
+        let mut finished: Vec<(usize, bool)> = Vec::new();
+
+        // Deliver the stream in awkwardly sized pieces.
+        for chunk in stream.chunks(37) {
+            decoder.feed(chunk);
+
+            while let Some((index, result)) = decoder.finish_next(&mut users).unwrap() {
+                finished.push((index, result.is_ok()));
+            }
+        }
+
+        assert!(decoder.finish().is_ok());
+        assert!(decoder.expected() == Some(8));
+        assert!(finished == (0..8).map(|i| (i, i != 5)).collect::<Vec<_>>());
+        assert!(users[0].credential.is_some() && users[5].credential.is_none());
+    }
+
+    #[test]
+    fn malformed_streams_are_rejected() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let requests = vec![user.obtain(vec![Scalar::random(&mut rng)])];
+        let frames: Vec<Vec<u8>> = stream_issuance(&issuer, &requests, &mut rng).collect();
+
+        // A stream which stops early is truncated.
+        let mut truncated = IssuanceDecoder::new();
+
+        truncated.feed(&frames[0]);
+        truncated.feed(&frames[1][..10]);
+
+        assert!(truncated.next_frame() == Ok(None));
+        assert!(truncated.finish() == Err(StreamError::Truncated.into()));
+
+        // Issuances must follow the header.
+        let mut headless = IssuanceDecoder::new();
+
+        headless.feed(&frames[1]);
+
+        assert!(headless.next_frame() == Err(StreamError::Malformed.into()));
+
+        // Oversized frames are refused before they are buffered in full.
+        let mut oversized = IssuanceDecoder::new();
+
+        oversized.feed(&[0xff, 0xff, 0xff, 0x00]);
+
+        assert!(oversized.next_frame() == Err(StreamError::FrameTooLarge.into()));
+    }
+}
@@ -0,0 +1,452 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Sybil-resistant linking between credential types.
+//!
+//! A user holding a credential of one type (e.g. over their phone number) may
+//! request a blinded credential of another type (e.g. a membership) over the
+//! same hidden attribute.  Along with the request, they present the first
+//! credential and prove that:
+//!
+//! 1. the presented attribute is the same as the encrypted one, and
+//! 2. they have encrypted \\( m \cdot H_s \\), where \\( H_s \\) is derived
+//!    from a public scope string, to the issuer's tag key \\( Y = y \cdot B \\).
+//!
+//! The issuer decrypts it and keys it with the same secret, so that the
+//! `SybilTag` is \\( T = y \cdot m \cdot H_s \\).  The tag is deterministic
+//! per attribute and scope, so an issuer which records the tags it has seen
+//! can refuse to issue more than one linked credential per underlying
+//! attribute, without learning the attribute.
+//!
+//! An attribute such as a phone number has little entropy, so a tag which
+//! anyone could compute from the scope would let them recover it by
+//! enumeration.  Only the holder of the tag key can compute a tag, as with
+//! any keyed pseudorandom function: it should be kept by whoever enforces
+//! the limit, and the tags it records are worthless without it.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::deserialize;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::SIZEOF_CREDENTIAL_PRESENTATION;
+use credential::Credential;
+use credential::CredentialBlindRequest;
+use credential::CredentialPresentation;
+use credential::EncryptedAttribute;
+use elgamal;
+use elgamal::SIZEOF_ENCRYPTION;
+use errors::CredentialError;
+use errors::Error;
+use issuer::Issuer;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use nullifier::encode_scope;
+use nullifier::hash_scope_to_group;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use proofs::sybil_link;
+use user::User;
+
+pub const SIZEOF_SYBIL_TAG: usize = 32;
+
+/// The domain separator for the scopes of linked credentials.
+const SYBIL_SCOPE_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux sybil scope v2";
+
+/// The encoding of a sybil-resistance `scope`, whose basepoint \\( H_s \\)
+/// is hashed from it as in `nullifier::scoped_nullifier()`.
+///
+/// # Errors
+///
+/// * `Error::Misuse` if the `scope` is longer than 65535 bytes.
+pub fn link_scope(scope: &[u8]) -> Result<Vec<u8>, Error> {
+    encode_scope(&[SYBIL_SCOPE_DOMAIN_SEPARATOR, scope])
+}
+
+/// A tag which is unique per hidden attribute, scope, and tag key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SybilTag(pub RistrettoPoint);
+
+impl SybilTag {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SybilTag, CredentialError> {
+        if bytes.len() != SIZEOF_SYBIL_TAG {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 32];
+
+        tmp.copy_from_slice(bytes);
+
+        Ok(SybilTag(CompressedRistretto(tmp).decompress().ok_or(CredentialError::PointDecompressionError)?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.compress().to_bytes().to_vec()
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SybilTag, "A valid byte sequence representing a SybilTag");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SybilTag);
+impl_try_from_bytes!(SybilTag, CredentialError);
+
+/// A blind request for a credential, linked to a presentation of another
+/// credential over the same attribute.
+#[derive(Debug, Eq, PartialEq)]
+pub struct LinkedCredentialRequest {
+    /// A presentation of the credential being linked from.
+    pub presentation: CredentialPresentation,
+    /// The hidden attribute's point in the given scope, encrypted to the
+    /// issuer's tag key.
+    pub encrypted_tag: elgamal::Encryption,
+    /// A blind request for the credential being linked to.
+    pub request: CredentialBlindRequest,
+    /// A proof that the `presentation`, `encrypted_tag`, and `request` share
+    /// an attribute.
+    pub proof: sybil_link::Proof,
+}
+
+impl LinkedCredentialRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<LinkedCredentialRequest, CredentialError> {
+        let mut offset: usize = SIZEOF_CREDENTIAL_PRESENTATION + SIZEOF_ENCRYPTION + 4;
+
+        if bytes.len() < offset {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let presentation = CredentialPresentation::from_bytes(&bytes[..SIZEOF_CREDENTIAL_PRESENTATION])?;
+        let encrypted_tag = elgamal::Encryption::from_bytes(&bytes[SIZEOF_CREDENTIAL_PRESENTATION..offset - 4])?;
+
+        let mut length: usize = 0;
+
+        for (i, byte) in bytes[offset - 4..offset].iter().enumerate() {
+            length |= (*byte as usize) << (8 * i);
+        }
+
+        if bytes.len() - offset < length {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let request = CredentialBlindRequest::from_bytes(&bytes[offset..offset + length])?;
+
+        offset += length;
+
+        let proof: sybil_link::Proof = match deserialize(&bytes[offset..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(CredentialError::MissingData),
+        };
+
+        Ok(LinkedCredentialRequest { presentation, encrypted_tag, request, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let request: Vec<u8> = self.request.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_CREDENTIAL_PRESENTATION + SIZEOF_ENCRYPTION +
+                                                4 + request.len());
+
+        v.extend(self.presentation.to_bytes());
+        v.extend(self.encrypted_tag.to_bytes());
+
+        for i in 0..4 {
+            v.push((request.len() >> (8 * i)) as u8);
+        }
+
+        v.extend(request);
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(LinkedCredentialRequest,
+                                         "A valid byte sequence representing a LinkedCredentialRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(LinkedCredentialRequest);
+impl_try_from_bytes!(LinkedCredentialRequest, CredentialError);
+
+impl LinkedCredentialRequest {
+    /// Create a blind request for a credential, as the `target` user, over the
+    /// same attribute as the `source` user's credential.
+    ///
+    /// # Inputs
+    ///
+    /// * `source` is a `User` holding the credential being linked from.
+    /// * `target` is a `User` who will hold the new credential.  Both must use
+    ///   the same `SystemParameters`.
+    /// * `tag_key` is the public tag key of the issuer enforcing the limit.
+    /// * `scope` is a public string identifying what the sybil resistance
+    ///   applies to, e.g. `b"group memberships"`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::MissingData` if the `source` has no credential.
+    /// * `CredentialError::NoSystemParameters` if the users' system parameters
+    ///   differ.
+    /// * `Error::Misuse` if the `scope` is longer than 65535 bytes.
+    pub fn create<R>(
+        source: &User,
+        target: &mut User,
+        tag_key: &elgamal::PublicKey,
+        scope: &[u8],
+        rng: &mut R,
+    ) -> Result<LinkedCredentialRequest, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if source.system_parameters != target.system_parameters {
+            return Err(CredentialError::NoSystemParameters.into());
+        }
+
+        let credential: &Credential = match source.credential {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+
+        let mut transcript = Transcript::new(b"AEONFLUX SYBIL LINK");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
+        let nonces: Nonces = Nonces::new(&mut csprng, NUMBER_OF_ATTRIBUTES);
+        let presentation: CredentialPresentation = source.show(&nonces, &mut csprng)?;
+        let (request, ephemerals) = target.blind_obtain_with_ephemerals(&credential.attributes,
+                                                                        &mut csprng)?;
+
+        let Hs: RistrettoPoint = hash_scope_to_group(&link_scope(scope)?);
+        let t: Ephemeral = Ephemeral::new(&mut csprng);
+        let encrypted_tag = tag_key.encrypt(&elgamal::Message(&Hs * &credential.attributes[0]), &t);
+        let D: RistrettoPoint = request.public_key.into();
+        let Y: RistrettoPoint = (*tag_key).into();
+        let E0: EncryptedAttribute = match request.attributes_blinded {
+            Some(ref x) => x[0],
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+
+        let proof = {
+            let secrets = sybil_link::Secrets {
+                m0: &credential.attributes[0],
+                z0: nonces[0].as_witness(),
+                e0: ephemerals[0].as_witness(),
+                t: t.as_witness(),
+            };
+            let publics = sybil_link::Publics {
+                B: &source.system_parameters.g,
+                A: &source.system_parameters.h,
+                P: &presentation.rerandomized_nonce,
+                D: &D,
+                Y: &Y,
+                Hs: &Hs,
+                Cm0: &presentation.attributes_blinded[0].into(),
+                E0_0: &E0.commitment,
+                E0_1: &E0.encryption,
+                ET_0: &encrypted_tag.commitment,
+                ET_1: &encrypted_tag.encryption,
+            };
+
+            sybil_link::Proof::create(&mut transcript, publics, secrets)
+        };
+
+        Ok(LinkedCredentialRequest {
+            presentation: presentation,
+            encrypted_tag: encrypted_tag,
+            request: request,
+            proof: proof,
+        })
+    }
+
+    /// Verify the presentation of the linked-from credential, and that it
+    /// shares its hidden attribute with the blind request, and compute the
+    /// attribute's `SybilTag` with the secret `tag_key`.
+    ///
+    /// # Note
+    ///
+    /// If this returns successfully, the issuer MUST check that the returned
+    /// `SybilTag` has not been seen before within this `scope` (or has been
+    /// seen fewer times than the permitted limit) before passing the `request`
+    /// to `Issuer::blind_issue()`.
+    ///
+    /// # Inputs
+    ///
+    /// * `source_issuer` is the `Issuer` of the linked-from credential.
+    /// * `tag_key` is the tag keypair whose public key was given to `create()`.
+    /// * `scope` is the same scope string given to `create()`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::VerificationFailure` if the presentation or the
+    ///   linking proof did not verify.
+    /// * `CredentialError::WrongNumberOfAttributes` if the request did not
+    ///   contain exactly one blinded attribute.
+    /// * `Error::Misuse` if the `scope` is longer than 65535 bytes.
+    ///
+    /// # Returns
+    ///
+    /// The `SybilTag` for the hidden attribute.
+    pub fn verify(
+        &self,
+        source_issuer: &Issuer,
+        tag_key: &elgamal::Keypair,
+        scope: &[u8],
+    ) -> Result<SybilTag, Error>
+    {
+        source_issuer.verify(&self.presentation)?;
+
+        let E0: &EncryptedAttribute = match self.request.attributes_blinded {
+            Some(ref x) if x.len() == 1 => &x[0],
+            _                           => return Err(CredentialError::WrongNumberOfAttributes.into()),
+        };
+        if self.presentation.attributes_blinded.len() != 1 {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let Hs: RistrettoPoint = hash_scope_to_group(&link_scope(scope)?);
+        let D: RistrettoPoint = self.request.public_key.into();
+        let Y: RistrettoPoint = tag_key.public.into();
+
+        let mut transcript = Transcript::new(b"AEONFLUX SYBIL LINK");
+        let publics = sybil_link::Publics {
+            B: &source_issuer.system_parameters.g,
+            A: &source_issuer.system_parameters.h,
+            P: &self.presentation.rerandomized_nonce,
+            D: &D,
+            Y: &Y,
+            Hs: &Hs,
+            Cm0: &self.presentation.attributes_blinded[0].into(),
+            E0_0: &E0.commitment,
+            E0_1: &E0.encryption,
+            ET_0: &self.encrypted_tag.commitment,
+            ET_1: &self.encrypted_tag.encryption,
+        };
+
+        if self.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::VerificationFailure.into());
+        }
+
+        let point: RistrettoPoint = tag_key.secret.decrypt(&self.encrypted_tag);
+
+        Ok(SybilTag(tag_key.secret.0.expose_secret() * &point))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use credential::CredentialBlindIssuance;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    const SCOPE: &'static [u8] = b"test memberships";
+
+    fn phone_user(system_parameters: SystemParameters, issuer: &Issuer, attribute: Scalar) -> User {
+        let mut rng = thread_rng();
+        let mut user: User = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue(&user.obtain(vec![attribute]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+        user
+    }
+
+    #[test]
+    fn linked_issuance() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let phone_issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let membership_issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+
+        let tag_key = elgamal::Keypair::generate(&mut rng);
+
+        let alice_phone: User = phone_user(system_parameters, &phone_issuer, Scalar::random(&mut rng));
+        let mut alice_membership: User = User::new(system_parameters,
+                                                   membership_issuer.get_issuer_parameters(), None);
+
+        let request = LinkedCredentialRequest::create(&alice_phone, &mut alice_membership,
+                                                      &tag_key.public, SCOPE, &mut rng).unwrap();
+        let request = LinkedCredentialRequest::from_bytes(&request.to_bytes()).unwrap();
+        let _tag: SybilTag = request.verify(&phone_issuer, &tag_key, SCOPE).unwrap();
+
+        let issuance: CredentialBlindIssuance = membership_issuer.blind_issue(&request.request,
+                                                                              &mut rng).unwrap();
+
+        alice_membership.blind_obtain_finish(Some(&issuance),
+                                             &alice_phone.credential.as_ref().unwrap().attributes).unwrap();
+
+        let nonces: Nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = alice_membership.show(&nonces, &mut rng).unwrap();
+
+        assert!(membership_issuer.verify(&presentation).is_ok());
+    }
+
+    #[test]
+    fn sybil_tag_repeats_per_attribute_and_scope() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let phone_issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let membership_issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let tag_key = elgamal::Keypair::generate(&mut rng);
+        let other_key = elgamal::Keypair::generate(&mut rng);
+        let alice_attribute: Scalar = Scalar::random(&mut rng);
+        let alice_phone: User = phone_user(system_parameters, &phone_issuer, alice_attribute);
+        let bob_phone: User = phone_user(system_parameters, &phone_issuer, Scalar::random(&mut rng));
+
+        let mut tag = |phone: &User, key: &elgamal::Keypair, scope: &[u8]| -> SybilTag {
+            let mut membership = User::new(system_parameters, membership_issuer.get_issuer_parameters(), None);
+
+            LinkedCredentialRequest::create(phone, &mut membership, &key.public, scope, &mut rng).unwrap()
+                .verify(&phone_issuer, key, scope).unwrap()
+        };
+
+        let alice_first = tag(&alice_phone, &tag_key, SCOPE);
+        let alice_second = tag(&alice_phone, &tag_key, SCOPE);
+        let alice_other_scope = tag(&alice_phone, &tag_key, b"other");
+        let alice_other_key = tag(&alice_phone, &other_key, SCOPE);
+        let bob_first = tag(&bob_phone, &tag_key, SCOPE);
+
+        assert!(alice_first == alice_second);
+        assert!(alice_first != alice_other_scope);
+        assert!(alice_first != alice_other_key);
+        assert!(alice_first != bob_first);
+
+        // Knowing the scope and guessing the attribute is not enough to
+        // compute the tag.
+        assert!(alice_first.0 != &hash_scope_to_group(&link_scope(SCOPE).unwrap()) * &alice_attribute);
+    }
+
+    #[test]
+    fn linked_request_wrong_scope_or_key() {
+        let mut rng = thread_rng();
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let phone_issuer: Issuer = Issuer::create(system_parameters, &mut rng);
+        let tag_key = elgamal::Keypair::generate(&mut rng);
+        let other_key = elgamal::Keypair::generate(&mut rng);
+        let alice_phone: User = phone_user(system_parameters, &phone_issuer, Scalar::random(&mut rng));
+        let mut alice_membership: User = User::new(system_parameters,
+                                                   phone_issuer.get_issuer_parameters(), None);
+
+        let request = LinkedCredentialRequest::create(&alice_phone, &mut alice_membership,
+                                                      &tag_key.public, SCOPE, &mut rng).unwrap();
+
+        assert!(request.verify(&phone_issuer, &tag_key, b"other").is_err());
+        assert!(request.verify(&phone_issuer, &other_key, SCOPE).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Deterministic fixtures and proptest strategies, for property-testing
+//! integrations against realistic values.
+//!
+//! A `Fixture` is an issuer, a user holding a credential from it, and a
+//! presentation of that credential, all generated from a `DeterministicRng`
+//! with a given seed, so that any failing case may be reproduced from its
+//! seed alone.  (The keys and attributes are reproduced exactly; the proofs,
+//! whose transcript generators are also reseeded from the platform, are
+//! not.)  `Fixture` implements `Arbitrary`, and the functions here
+//! return strategies for the values from which protocol messages are built,
+//! and for corruptions of any encoding.
+//!
+//! The crate's own unit tests share `H` and `holder()` from here as well.
+//! Downstream, the module is only available with the `test-utils` feature,
+//! as are the strategies, which need `proptest`.  None of the keys it
+//! produces may be used for anything but tests.
+
+use std::fmt;
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use merlin::Transcript;
+
+#[cfg(feature = "test-utils")]
+use proptest::prelude::*;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use channel_binding::bind_channel;
+use credential::CredentialPresentation;
+use credential::RevealedAttribute;
+use derivation::DerivationKey;
+use derivation::Purpose;
+#[cfg(feature = "test-utils")]
+use elgamal;
+use encoding::u64_to_bytes;
+use freshness::bind_timestamp;
+use issuer::Issuer;
+use nonces::Ephemeral;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
+use pedersen;
+use proofs::valid_credential;
+#[cfg(feature = "test-utils")]
+use rate_limit::Nullifier;
+use rng::DeterministicRng;
+use schema::bind_schema;
+use user::User;
+
+/// The hash from which every fixture's `SystemParameters` are derived.
+pub const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+/// The `SystemParameters` shared by every fixture.
+pub fn system_parameters() -> SystemParameters {
+    SystemParameters::from(H)
+}
+
+/// A fresh user holding a credential over `attributes` from `issuer`.
+pub fn holder<C>(issuer: &Issuer, attributes: Vec<RevealedAttribute>, csprng: &mut C) -> User
+where
+    C: RngCore + CryptoRng,
+{
+    let user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+
+    obtain(issuer, user, attributes, csprng)
+}
+
+/// Have `user` obtain a credential over `attributes` from `issuer`.
+fn obtain<C>(issuer: &Issuer, mut user: User, attributes: Vec<RevealedAttribute>, csprng: &mut C) -> User
+where
+    C: RngCore + CryptoRng,
+{
+    let issuance = issuer.issue(&user.obtain(attributes), csprng).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+    user
+}
+
+/// A presentation, without any credential, which any key would accept but
+/// for its nonce: with the identity for `P`, the commitments
+/// `Cm0 = z0·A` and `CQ = -minus_zQ·A` satisfy the `valid_credential`
+/// statement under every issuer key.  Every verifier must reject it.
+pub fn identity_forgery<C>(issuer: &Issuer, csprng: &mut C) -> CredentialPresentation
+where
+    C: RngCore + CryptoRng,
+{
+    let X0: RistrettoPoint = issuer.get_issuer_parameters().Xn[0];
+    let A: RistrettoPoint = issuer.system_parameters.h;
+    let P: RistrettoPoint = RistrettoPoint::identity();
+    let z0: Ephemeral = Ephemeral::new(csprng);
+    let zQ: Ephemeral = Ephemeral::new(csprng);
+    let minus_zQ: Ephemeral = -zQ.clone();
+    let Cm0: pedersen::Commitment = pedersen::Commitment::to(&P, &z0, &A);
+    let CQ: pedersen::Commitment = pedersen::Commitment::to(&P, &zQ, &A);
+    let V: RistrettoPoint = &X0 * z0.as_scalar() + &A * minus_zQ.as_scalar();
+
+    let mut transcript = Transcript::new(b"AEONFLUX SHOW");
+
+    bind_schema(&mut transcript, None);
+    bind_timestamp(&mut transcript, None);
+    bind_channel(&mut transcript, None);
+
+    let publics = valid_credential::Publics {
+        B: &issuer.system_parameters.g,
+        A: &A,
+        X0: &X0,
+        P: &P,
+        V: &V,
+        Cm0: &Cm0.into(),
+    };
+    let secrets = valid_credential::Secrets {
+        m0: &Scalar::random(csprng),
+        z0: z0.as_witness(),
+        minus_zQ: minus_zQ.as_witness(),
+    };
+
+    CredentialPresentation {
+        rerandomized_mac_commitment: CQ,
+        rerandomized_nonce: P,
+        attributes_revealed: Vec::new(),
+        attributes_blinded: vec![Cm0],
+        proof: valid_credential::Proof::create(&mut transcript, publics, secrets),
+    }
+}
+
+/// The issuer, user, and presentation generated from a `seed`.
+///
+/// The user's key and the presentation's nonces are derived from a
+/// `DerivationKey` for the same seed.
+pub struct Fixture {
+    pub seed: u64,
+    pub issuer: Issuer,
+    /// A user holding a credential over `attributes` from `issuer`.
+    pub user: User,
+    pub attributes: Vec<RevealedAttribute>,
+    /// A presentation of the user's credential, which `issuer` accepts.
+    pub presentation: CredentialPresentation,
+}
+
+impl Fixture {
+    /// Generate the fixture for a `seed`.
+    pub fn new(seed: u64) -> Fixture {
+        let mut rng = DeterministicRng::from(seed);
+        let root = DerivationKey::from_seed(&u64_to_bytes(seed));
+        let system_parameters = system_parameters();
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let user = root.recover_user(system_parameters, issuer.get_issuer_parameters(), 0);
+        let attributes: Vec<RevealedAttribute> = (0..NUMBER_OF_ATTRIBUTES).map(|_| Scalar::random(&mut rng)).collect();
+        let mut user = obtain(&issuer, user, attributes.clone(), &mut rng);
+        let nonces = root.purpose(Purpose::Nonce).nonces(0, NUMBER_OF_ATTRIBUTES);
+        let presentation = user.show(&nonces, &mut rng).unwrap();
+
+        Fixture { seed, issuer, user, attributes, presentation }
+    }
+}
+
+/// Only the seed is printed, from which the rest may be regenerated, and so
+/// that failing cases do not print keys.
+impl fmt::Debug for Fixture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fixture {{ seed: {} }}", self.seed)
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl Arbitrary for Fixture {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Fixture>;
+
+    fn arbitrary_with(_args: ()) -> BoxedStrategy<Fixture> {
+        any::<u64>().prop_map(Fixture::new).boxed()
+    }
+}
+
+#[cfg(feature = "test-utils")]
+/// Uniformly random scalars.
+pub fn scalar() -> impl Strategy<Value = Scalar> {
+    any::<[u8; 32]>().prop_map(Scalar::from_bytes_mod_order)
+}
+
+#[cfg(feature = "test-utils")]
+/// Uniformly random Ristretto points.
+pub fn ristretto_point() -> impl Strategy<Value = RistrettoPoint> {
+    (any::<[u8; 32]>(), any::<[u8; 32]>()).prop_map(|(a, b)| {
+        let mut bytes = [0u8; 64];
+
+        bytes[..32].copy_from_slice(&a);
+        bytes[32..].copy_from_slice(&b);
+
+        RistrettoPoint::from_uniform_bytes(&bytes)
+    })
+}
+
+#[cfg(feature = "test-utils")]
+/// The revealed attributes of a credential request.
+pub fn attributes() -> impl Strategy<Value = Vec<RevealedAttribute>> {
+    prop::collection::vec(scalar(), NUMBER_OF_ATTRIBUTES)
+}
+
+#[cfg(feature = "test-utils")]
+/// Nullifiers, which are valid Ristretto points.
+pub fn nullifier() -> impl Strategy<Value = Nullifier> {
+    ristretto_point().prop_map(Nullifier::from)
+}
+
+#[cfg(feature = "test-utils")]
+/// ElGamal keypairs, generated from a seed.
+pub fn elgamal_keypair() -> impl Strategy<Value = elgamal::Keypair> {
+    any::<u64>().prop_map(|seed| elgamal::Keypair::generate(&mut DeterministicRng::from(seed)))
+}
+
+/// One of the ways in which an encoding may be corrupted in transit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Corruption {
+    /// Flip the bits of `mask` in the byte at `index`, modulo the length.
+    Flip { index: usize, mask: u8 },
+    /// Keep only the first `length` bytes, modulo the length.
+    Truncate { length: usize },
+    /// Append a `byte`.
+    Extend { byte: u8 },
+}
+
+impl Corruption {
+    /// Apply this corruption to an `encoding`.
+    pub fn apply(&self, encoding: &[u8]) -> Vec<u8> {
+        let mut v: Vec<u8> = encoding.to_vec();
+
+        match *self {
+            Corruption::Flip { index, mask } => if !v.is_empty() {
+                let i: usize = index % v.len();
+
+                v[i] ^= mask;
+            },
+            Corruption::Truncate { length } => if !v.is_empty() {
+                v.truncate(length % v.len());
+            },
+            Corruption::Extend { byte } => v.push(byte),
+        }
+        v
+    }
+}
+
+#[cfg(feature = "test-utils")]
+/// Corruptions which always change an encoding.
+pub fn corruption() -> impl Strategy<Value = Corruption> {
+    prop_oneof![
+        (any::<usize>(), 1u8..255).prop_map(|(index, mask)| Corruption::Flip { index, mask }),
+        any::<usize>().prop_map(|length| Corruption::Truncate { length }),
+        any::<u8>().prop_map(|byte| Corruption::Extend { byte }),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "test-utils")]
+    use proptest::test_runner::Config;
+
+    #[test]
+    fn fixtures_are_deterministic() {
+        assert!(Fixture::new(7).issuer.to_bytes() == Fixture::new(7).issuer.to_bytes());
+        assert!(Fixture::new(7).attributes == Fixture::new(7).attributes);
+        assert!(Fixture::new(7).issuer.to_bytes() != Fixture::new(8).issuer.to_bytes());
+    }
+
+    #[cfg(feature = "test-utils")]
+    proptest! {
+        #![proptest_config(Config::with_cases(16))]
+
+        #[test]
+        fn fixtures_verify(fixture in any::<Fixture>()) {
+            prop_assert!(fixture.issuer.verify(&fixture.presentation).is_ok());
+        }
+
+        #[test]
+        fn corrupted_presentations_are_rejected(fixture in any::<Fixture>(), corruption in corruption()) {
+            let encoding: Vec<u8> = corruption.apply(&fixture.presentation.to_bytes());
+
+            // A corruption which decodes to the same presentation, e.g. in
+            // trailing bytes, is harmless.
+            if let Ok(presentation) = CredentialPresentation::from_bytes(&encoding) {
+                if presentation != fixture.presentation {
+                    prop_assert!(fixture.issuer.verify(&presentation).is_err());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Known-answer test vectors, for checking other implementations against
+//! this one byte for byte.
+//!
+//! Each vector is generated from a `DeterministicRng` seeded with the
+//! vector's `seed`, and records every key, nonce, and message used along
+//! with every ciphertext, tag, request, issuance, and presentation made
+//! from them, all as lowercase hexadecimal of their `to_bytes` encodings.
+//! The `test-vectors` binary prints them as JSON.
+//!
+//! An implementation using a different generator cannot reproduce the tags
+//! or proofs, which are randomised, but it can check that:
+//!
+//! * the ElGamal public key and encryption follow from the recorded secret
+//!   key, message, and nonce,
+//! * the nullifier basepoint follows from its recorded inputs,
+//! * its own decoders accept every encoding, and
+//! * the recorded tag and presentation verify under the recorded keys.
+
+use std::string::String;
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use serde_json::Value;
+
+use amacs;
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use credential::RevealedAttribute;
+use elgamal;
+use issuer::Issuer;
+use issuer::IssuerParameters;
+use nonces::Ephemeral;
+use nonces::Nonces;
+use nullifier::hash_scope_to_group;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use parameters::SystemParameters;
+use rate_limit::action_scope;
+use rng::DeterministicRng;
+use user::User;
+
+/// The version of the vector format, which changes whenever any encoding
+/// within it does.
+pub const TEST_VECTORS_VERSION: u64 = 2;
+
+/// The hash from which every vector's `SystemParameters` are derived.
+const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                      127,   4, 178,  70, 212, 141, 119, 112,
+                      153, 154, 135,  11, 227, 132, 247,  47,
+                       68, 192,  72, 200,  23,  88,  51,  82, ];
+
+/// The scope of every vector's nullifier basepoint.
+const NULLIFIER_SCOPE: &'static [u8] = b"aeonflux test vectors";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(value: &Value) -> Result<Vec<u8>, String> {
+    let text: &str = value.as_str().ok_or(String::from("expected a hex string"))?;
+
+    if text.len() % 2 != 0 || !text.is_ascii() {
+        return Err(format!("invalid hex: {}", text));
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).or(Err(format!("invalid hex: {}", text))))
+        .collect()
+}
+
+fn unhex_scalar(value: &Value) -> Result<Scalar, String> {
+    let bytes: Vec<u8> = unhex(value)?;
+    let mut scalar = [0u8; 32];
+
+    if bytes.len() != 32 {
+        return Err(String::from("expected a 32-byte scalar"));
+    }
+    scalar.copy_from_slice(&bytes);
+
+    Scalar::from_canonical_bytes(scalar).ok_or(String::from("non-canonical scalar"))
+}
+
+/// Generate the vector for a `seed`.
+pub fn generate(seed: u64) -> Value {
+    let mut rng = DeterministicRng::from(seed);
+    let system_parameters = SystemParameters::from(H);
+
+    // ElGamal
+    let elgamal_keypair = elgamal::Keypair::generate(&mut rng);
+    let elgamal_message: Scalar = Scalar::random(&mut rng);
+    let elgamal_nonce = Ephemeral::new(&mut rng);
+    let encryption = elgamal_keypair.encrypt(&elgamal::Message::from(&elgamal_message), &elgamal_nonce);
+
+    // Algebraic MACs
+    let amac_key = amacs::SecretKey::new(NUMBER_OF_ATTRIBUTES, &mut rng);
+    let amac_message: Vec<Scalar> = (0..NUMBER_OF_ATTRIBUTES).map(|_| Scalar::random(&mut rng)).collect();
+    let tag = amac_key.mac(&amacs::Message(amac_message.clone()), &mut rng).unwrap();
+
+    // Credentials
+    let issuer = Issuer::create(system_parameters, &mut rng);
+    let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+    let mut user = User::new(system_parameters, issuer_parameters.clone(), None);
+    let attributes: Vec<RevealedAttribute> = (0..NUMBER_OF_ATTRIBUTES).map(|_| Scalar::random(&mut rng)).collect();
+    let request: CredentialRequest = user.obtain(attributes.clone());
+    let issuance: CredentialIssuance = issuer.issue(&request, &mut rng).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+
+    let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+    let presentation: CredentialPresentation = user.show(&nonces, &mut rng).unwrap();
+
+    // Hashing
+    let basepoint = hash_scope_to_group(&action_scope(NULLIFIER_SCOPE, seed, 0).unwrap());
+
+    json!({
+        "seed": seed,
+        "system_parameters": hex(&system_parameters.to_bytes()),
+        "elgamal": {
+            "secret_key": hex(&elgamal_keypair.secret.to_bytes()),
+            "public_key": hex(&elgamal_keypair.public.to_bytes()),
+            "message": hex(elgamal_message.as_bytes()),
+            "nonce": hex(&elgamal_nonce.to_bytes()),
+            "encryption": hex(&encryption.to_bytes()),
+        },
+        "amac": {
+            "secret_key": hex(&amac_key.to_bytes()),
+            "message": amac_message.iter().map(|x| hex(x.as_bytes())).collect::<Vec<String>>(),
+            "tag": hex(&tag.to_bytes()),
+        },
+        "credential": {
+            "issuer": hex(&issuer.to_bytes()),
+            "issuer_parameters": hex(&issuer_parameters.to_bytes()),
+            "attributes": attributes.iter().map(|x| hex(x.as_bytes())).collect::<Vec<String>>(),
+            "request": hex(&request.to_bytes()),
+            "issuance": hex(&issuance.to_bytes()),
+            "nonces": nonces.iter().map(|x| hex(&x.to_bytes())).collect::<Vec<String>>(),
+            "presentation": hex(&presentation.to_bytes()),
+        },
+        "nullifier_basepoint": {
+            "scope": hex(NULLIFIER_SCOPE),
+            "epoch": seed,
+            "counter": 0,
+            "point": hex(basepoint.compress().as_bytes()),
+        },
+    })
+}
+
+/// Generate the vectors for seeds `0` up to `count`.
+pub fn generate_all(count: u64) -> Value {
+    json!({
+        "version": TEST_VECTORS_VERSION,
+        "vectors": (0..count).map(generate).collect::<Vec<Value>>(),
+    })
+}
+
+/// Check a single `vector` against this implementation.
+///
+/// # Returns
+///
+/// A description of the first mismatch, if there was one.
+pub fn check(vector: &Value) -> Result<(), String> {
+    let seed: u64 = vector["seed"].as_u64().ok_or(String::from("missing seed"))?;
+    let expected: Value = generate(seed);
+
+    // The deterministic computations are checked from their recorded
+    // inputs, rather than only by regenerating them.
+    let elgamal_secret = elgamal::SecretKey::from_bytes(&unhex(&vector["elgamal"]["secret_key"])?)
+        .or(Err(String::from("invalid ElGamal secret key")))?;
+    let elgamal_public = elgamal::PublicKey::from(&elgamal_secret);
+    let message = elgamal::Message::from(&unhex_scalar(&vector["elgamal"]["message"])?);
+    let nonce = Ephemeral::from(unhex_scalar(&vector["elgamal"]["nonce"])?);
+
+    if elgamal_public.to_bytes() != unhex(&vector["elgamal"]["public_key"])? {
+        return Err(String::from("ElGamal public key mismatch"));
+    }
+    if elgamal_public.encrypt(&message, &nonce).to_bytes() != unhex(&vector["elgamal"]["encryption"])? {
+        return Err(String::from("ElGamal encryption mismatch"));
+    }
+
+    let amac_key = amacs::SecretKey::from_bytes(&unhex(&vector["amac"]["secret_key"])?)
+        .or(Err(String::from("invalid aMAC secret key")))?;
+    let tag = amacs::Tag::from_bytes(&unhex(&vector["amac"]["tag"])?)
+        .or(Err(String::from("invalid aMAC tag")))?;
+    let mut amac_message: Vec<Scalar> = Vec::new();
+
+    for x in vector["amac"]["message"].as_array().ok_or(String::from("missing aMAC message"))?.iter() {
+        amac_message.push(unhex_scalar(x)?);
+    }
+    if amac_key.verify(&tag, &amacs::Message(amac_message)).is_err() {
+        return Err(String::from("aMAC tag does not verify"));
+    }
+
+    let issuer = Issuer::from_bytes(&unhex(&vector["credential"]["issuer"])?)
+        .or(Err(String::from("invalid issuer")))?;
+    let presentation = CredentialPresentation::from_bytes(&unhex(&vector["credential"]["presentation"])?)
+        .or(Err(String::from("invalid presentation")))?;
+
+    if issuer.verify(&presentation).is_err() {
+        return Err(String::from("presentation does not verify"));
+    }
+
+    // Everything else must match exactly what this implementation generates.
+    for field in ["system_parameters", "elgamal", "amac", "credential", "nullifier_basepoint"].iter() {
+        if vector[*field] != expected[*field] {
+            return Err(format!("{} mismatch for seed {}", field, seed));
+        }
+    }
+    Ok(())
+}
+
+/// Check every vector in a file produced by `generate_all`.
+pub fn check_all(vectors: &Value) -> Result<(), String> {
+    if vectors["version"].as_u64() != Some(TEST_VECTORS_VERSION) {
+        return Err(String::from("unknown test vector version"));
+    }
+    for vector in vectors["vectors"].as_array().ok_or(String::from("missing vectors"))?.iter() {
+        check(vector)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn generated_vectors_check() {
+        let vectors: Value = generate_all(2);
+
+        assert!(check_all(&vectors).is_ok());
+
+        let mut tampered: Value = vectors.clone();
+
+        tampered["vectors"][1]["elgamal"]["encryption"] = vectors["vectors"][0]["elgamal"]["encryption"].clone();
+        assert!(check_all(&tampered).is_err());
+    }
+
+    /// Check a file of vectors, e.g. from an earlier release, named by the
+    /// `AEONFLUX_TEST_VECTORS` environment variable.
+    #[test]
+    fn recorded_vectors_check() {
+        let path = match env::var("AEONFLUX_TEST_VECTORS") {
+            Ok(x)  => x,
+            Err(_) => return,
+        };
+        let text: String = fs::read_to_string(path).unwrap();
+        let vectors: Value = ::serde_json::from_str(&text).unwrap();
+
+        check_all(&vectors).unwrap();
+    }
+}
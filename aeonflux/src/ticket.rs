@@ -0,0 +1,693 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Venue-scoped, one-show entry tickets.
+//!
+//! A ticket is a credential over a random serial number, blindly issued
+//! under an aMAC key which is derived from a `TicketIssuer`'s master secret
+//! and the ticket's `TicketScope`, i.e. a venue ID and a validity window.  A
+//! ticket for one scope therefore cannot be presented under any other.
+//!
+//! To enter a venue, the holder reveals the serial number and proves, in
+//! zero-knowledge, knowledge of a rerandomised MAC over it.  Since the serial
+//! was blinded during issuance, the issuer cannot link the entry to the
+//! purchase, but the verifier can use the serial as a nullifier to reject a
+//! second showing of the same ticket.
+//!
+//! The resulting `CompactTicket` is at most `MAXIMUM_SIZEOF_COMPACT_TICKET`
+//! bytes, which is small enough to fit into a single QR code.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use amacs;
+use compact;
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use errors::TicketError;
+use hashing::Hasher;
+use issuer::Issuer;
+use issuer::IssuerParameters;
+use parameters::SystemParameters;
+use pedersen;
+use precomputed::PrecomputedIssuerParameters;
+use proofs::attribute_opening;
+use rate_limit::Nullifier;
+use rate_limit::NullifierStore;
+use secret::Clear;
+use user::User;
+
+/// The maximum length of a venue ID in bytes.
+pub const MAXIMUM_SIZEOF_VENUE_ID: usize = 16;
+
+/// The length of an encoded `TicketScope` without its venue ID.
+const SIZEOF_TICKET_SCOPE_HEADER: usize = 1 + 8 + 8;
+
+/// The length of a `CompactTicket` without its venue ID.
+const SIZEOF_COMPACT_TICKET_BODY: usize = 3 * 32 + attribute_opening::SIZEOF_PROOF;
+
+/// The maximum length of an encoded `CompactTicket` in bytes.
+pub const MAXIMUM_SIZEOF_COMPACT_TICKET: usize = SIZEOF_TICKET_SCOPE_HEADER +
+                                                 MAXIMUM_SIZEOF_VENUE_ID +
+                                                 SIZEOF_COMPACT_TICKET_BODY;
+
+/// The domain separator used when deriving per-scope aMAC keys.
+const TICKET_KEY_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux ticket key v1";
+
+fn push_u64(v: &mut Vec<u8>, x: u64) {
+    v.extend(u64_to_bytes(x).iter());
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+fn read_point(bytes: &[u8]) -> Result<RistrettoPoint, TicketError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(&bytes[..32]);
+
+    Ok(CompressedRistretto(tmp).decompress()?)
+}
+
+fn read_scalar(bytes: &[u8]) -> Result<Scalar, TicketError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(&bytes[..32]);
+
+    match Scalar::from_canonical_bytes(tmp) {
+        Some(x) => Ok(x),
+        None    => Err(TicketError::ScalarFormatError),
+    }
+}
+
+/// The venue and validity window which a ticket is bound to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TicketScope {
+    venue_id: Vec<u8>,
+    /// The first second, since the Unix epoch, at which the ticket is valid.
+    pub not_before: u64,
+    /// The first second, since the Unix epoch, at which the ticket is no
+    /// longer valid.
+    pub not_after: u64,
+}
+
+impl TicketScope {
+    /// Create a new `TicketScope`.
+    ///
+    /// # Errors
+    ///
+    /// * `TicketError::InvalidScope` if the `venue_id` is empty or longer than
+    ///   `MAXIMUM_SIZEOF_VENUE_ID`, or if `not_before` is not before
+    ///   `not_after`.
+    pub fn new(venue_id: &[u8], not_before: u64, not_after: u64) -> Result<TicketScope, Error> {
+        let scope = TicketScope { venue_id: venue_id.to_vec(), not_before, not_after };
+
+        scope.check()?;
+
+        Ok(scope)
+    }
+
+    fn check(&self) -> Result<(), TicketError> {
+        if self.venue_id.len() == 0 || self.venue_id.len() > MAXIMUM_SIZEOF_VENUE_ID {
+            return Err(TicketError::InvalidScope);
+        }
+        if self.not_before >= self.not_after {
+            return Err(TicketError::InvalidScope);
+        }
+        Ok(())
+    }
+
+    pub fn venue_id(&self) -> &[u8] {
+        &self.venue_id
+    }
+
+    /// Whether the time `now` falls within this scope's validity window.
+    pub fn contains(&self, now: u64) -> bool {
+        self.not_before <= now && now < self.not_after
+    }
+
+    /// Decode a `TicketScope` from the front of some `bytes`.
+    ///
+    /// # Returns
+    ///
+    /// The scope and the number of bytes it occupied.
+    fn decode(bytes: &[u8]) -> Result<(TicketScope, usize), TicketError> {
+        if bytes.len() < SIZEOF_TICKET_SCOPE_HEADER {
+            return Err(TicketError::WrongNumberOfBytes);
+        }
+
+        let length: usize = bytes[0] as usize;
+
+        if bytes.len() < SIZEOF_TICKET_SCOPE_HEADER + length {
+            return Err(TicketError::WrongNumberOfBytes);
+        }
+
+        let venue_id: &[u8] = &bytes[1..1 + length];
+        let not_before: u64 = read_u64(&bytes[1 + length..9 + length]);
+        let not_after: u64 = read_u64(&bytes[9 + length..17 + length]);
+
+        let scope = TicketScope { venue_id: venue_id.to_vec(), not_before, not_after };
+
+        scope.check()?;
+
+        Ok((scope, SIZEOF_TICKET_SCOPE_HEADER + length))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<TicketScope, TicketError> {
+        let (scope, length) = TicketScope::decode(bytes)?;
+
+        if length != bytes.len() {
+            return Err(TicketError::WrongNumberOfBytes);
+        }
+
+        Ok(scope)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TICKET_SCOPE_HEADER + self.venue_id.len());
+
+        v.push(self.venue_id.len() as u8);
+        v.extend(self.venue_id.iter());
+        push_u64(&mut v, self.not_before);
+        push_u64(&mut v, self.not_after);
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TicketScope, "A valid byte sequence representing a TicketScope");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TicketScope);
+impl_try_from_bytes!(TicketScope, TicketError);
+
+/// An issuer of tickets, which derives a distinct aMAC key for every
+/// `TicketScope` from a single master secret.
+///
+/// The same `TicketIssuer` (or a copy of it) is used by the venue to verify
+/// tickets.
+#[derive(Clone)]
+pub struct TicketIssuer {
+    pub system_parameters: SystemParameters,
+    master: [u8; 32],
+}
+
+/// Overwrite the master secret with null bytes when it goes out of scope.
+impl Drop for TicketIssuer {
+    fn drop(&mut self) {
+        self.master.clear();
+    }
+}
+
+impl TicketIssuer {
+    /// Create a `TicketIssuer` from a previously generated `master` secret.
+    pub fn new(system_parameters: SystemParameters, master: [u8; 32]) -> TicketIssuer {
+        TicketIssuer { system_parameters, master }
+    }
+
+    /// Create a `TicketIssuer` with a new random master secret.
+    pub fn generate<R>(system_parameters: SystemParameters, csprng: &mut R) -> TicketIssuer
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut master = [0u8; 32];
+
+        csprng.fill_bytes(&mut master);
+
+        TicketIssuer { system_parameters, master }
+    }
+
+    /// Hash the master secret and the encoded `scope` to a scalar.
+    ///
+    /// The scope is fed to the hash field by field, rather than encoded
+    /// first, so that deriving a key allocates nothing and never copies the
+    /// master secret to the heap.
+    fn derive_scalar(&self, scope: &TicketScope, index: u8) -> Scalar {
+        let mut hash = Hasher::new(TICKET_KEY_DOMAIN_SEPARATOR);
+
+        hash.input(&self.master[..])
+            .input(&[index][..])
+            .input_u8_prefixed(&scope.venue_id[..])
+            .input_u64(scope.not_before)
+            .input_u64(scope.not_after);
+        hash.finalize_scalar()
+    }
+
+    /// Derive the `Issuer` for tickets within a `scope`.
+    pub fn issuer(&self, scope: &TicketScope) -> Issuer {
+        let mut xn: Vec<Scalar> = Vec::with_capacity(1);
+
+        xn.push(self.derive_scalar(scope, 1));
+
+        let secret = amacs::SecretKey { x0: self.derive_scalar(scope, 0), xn: xn };
+        let public = secret.get_public_key(&self.system_parameters.h);
+
+        // The derived issuer is discarded after a single issuance or
+        // verification, so building basepoint tables for it is not worth it.
+        let precomputed = PrecomputedIssuerParameters::empty(&self.system_parameters, &public);
+
+        Issuer {
+            system_parameters: self.system_parameters,
+            keypair: amacs::Keypair { public, secret },
+            precomputed,
+        }
+    }
+
+    /// Get the `IssuerParameters` for tickets within a `scope`, for
+    /// publishing to users.
+    pub fn issuer_parameters(&self, scope: &TicketScope) -> IssuerParameters {
+        self.issuer(scope).get_issuer_parameters()
+    }
+
+    /// Mint a ticket for the scope given in the `request`.
+    ///
+    /// # Note
+    ///
+    /// The caller is responsible for deciding whether the requester is
+    /// entitled to a ticket for `request.scope`, e.g. by checking a payment
+    /// or another credential, before calling this.
+    pub fn mint<R>(&self, request: &TicketRequest, rng: &mut R)
+        -> Result<CredentialBlindIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.issuer(&request.scope).blind_issue(&request.request, rng)?)
+    }
+}
+
+/// A request for a ticket within some scope.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TicketRequest {
+    pub scope: TicketScope,
+    /// A blind request for a credential over the ticket's serial number.
+    pub request: CredentialBlindRequest,
+}
+
+impl TicketRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TicketRequest, TicketError> {
+        let (scope, offset) = TicketScope::decode(bytes)?;
+        let request = CredentialBlindRequest::from_bytes(&bytes[offset..])?;
+
+        Ok(TicketRequest { scope, request })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = self.scope.to_bytes();
+
+        v.extend(self.request.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TicketRequest, "A valid byte sequence representing a TicketRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TicketRequest);
+impl_try_from_bytes!(TicketRequest, TicketError);
+
+impl TicketRequest {
+    /// Request a ticket within a `scope`, from an issuer whose parameters for
+    /// that scope are `issuer_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// The `TicketRequest` to send to the issuer, and a `PendingTicket` to
+    /// keep until the issuer responds.
+    pub fn create<R>(
+        system_parameters: SystemParameters,
+        issuer_parameters: IssuerParameters,
+        scope: TicketScope,
+        rng: &mut R,
+    ) -> Result<(TicketRequest, PendingTicket), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let serial: Scalar = Scalar::random(rng);
+        let mut user: User = User::new(system_parameters, issuer_parameters, None);
+        let request: CredentialBlindRequest = user.blind_obtain(&[serial], rng)?;
+
+        Ok((TicketRequest { scope: scope.clone(), request }, PendingTicket { scope, user, serial }))
+    }
+}
+
+/// A ticket which has been requested but not yet minted.
+pub struct PendingTicket {
+    scope: TicketScope,
+    user: User,
+    serial: Scalar,
+}
+
+/// Overwrite the serial number with null bytes when it goes out of scope.
+impl Drop for PendingTicket {
+    fn drop(&mut self) {
+        self.serial.clear();
+    }
+}
+
+impl PendingTicket {
+    /// Check the issuer's response and obtain the `Ticket`.
+    pub fn finish(mut self, issuance: &CredentialBlindIssuance) -> Result<Ticket, Error> {
+        let serial: Scalar = self.serial;
+
+        self.user.blind_obtain_finish(Some(issuance), &[serial])?;
+
+        let mac: amacs::Tag = match self.user.credential.take() {
+            Some(credential) => credential.mac,
+            None             => return Err(TicketError::Credential(CredentialError::MissingData).into()),
+        };
+
+        Ok(Ticket {
+            system_parameters: self.user.system_parameters,
+            scope: self.scope.clone(),
+            serial: serial,
+            mac: mac,
+        })
+    }
+}
+
+/// A minted ticket, which may be presented once.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ticket {
+    system_parameters: SystemParameters,
+    scope: TicketScope,
+    serial: Scalar,
+    mac: amacs::Tag,
+}
+
+/// Overwrite the serial number with null bytes when it goes out of scope.
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.serial.clear();
+    }
+}
+
+/// Begin the transcript of a ticket's proof of a valid MAC, which is bound
+/// to the ticket's `scope`.
+fn ticket_transcript(scope: &TicketScope) -> Transcript {
+    let mut transcript = Transcript::new(b"AEONFLUX TICKET");
+
+    transcript.commit_bytes(b"scope", &scope.to_bytes());
+    transcript
+}
+
+impl Ticket {
+    pub fn scope(&self) -> &TicketScope {
+        &self.scope
+    }
+
+    /// Present this ticket.  Since a ticket can only be shown once, this
+    /// consumes it.
+    ///
+    /// The presentation is that of `compact::prove_opening()`: a commitment,
+    /// \\( C_Q = Q + z_Q A \\), to a rerandomised MAC, \\( (P, Q) \\),
+    /// the revealed serial number, and a Schnorr proof of knowledge of
+    /// \\( -z_Q \\) such that \\( V = (x_0 + x_1 s) P - C_Q = -z_Q A \\),
+    /// over a transcript bound to the ticket's scope.
+    pub fn present<R>(self, rng: &mut R) -> CompactTicket
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut transcript = ticket_transcript(&self.scope);
+        let (CQ, P, proof) = compact::prove_opening(&mut transcript, &self.system_parameters.h,
+                                                    &self.mac, &self.serial, rng);
+
+        CompactTicket {
+            scope: self.scope.clone(),
+            rerandomized_mac_commitment: CQ,
+            rerandomized_nonce: P,
+            serial: self.serial,
+            proof: proof,
+        }
+    }
+}
+
+/// A presentation of a `Ticket`, with an encoding of at most
+/// `MAXIMUM_SIZEOF_COMPACT_TICKET` bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompactTicket {
+    pub scope: TicketScope,
+    pub rerandomized_mac_commitment: pedersen::Commitment,
+    pub rerandomized_nonce: RistrettoPoint,
+    pub serial: Scalar,
+    proof: attribute_opening::Proof,
+}
+
+impl CompactTicket {
+    pub fn from_bytes(bytes: &[u8]) -> Result<CompactTicket, TicketError> {
+        let (scope, offset) = TicketScope::decode(bytes)?;
+
+        if bytes.len() != offset + SIZEOF_COMPACT_TICKET_BODY {
+            return Err(TicketError::WrongNumberOfBytes);
+        }
+
+        let body: &[u8] = &bytes[offset..];
+
+        Ok(CompactTicket {
+            scope: scope,
+            rerandomized_mac_commitment: pedersen::Commitment::from_bytes(&body[00..32])
+                .map_err(|_| TicketError::PointDecompressionError)?,
+            rerandomized_nonce: read_point(&body[32..64])?,
+            serial: read_scalar(&body[64..96])?,
+            proof: attribute_opening::Proof::from_bytes(&body[96..])
+                .map_err(|_| TicketError::ScalarFormatError)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(MAXIMUM_SIZEOF_COMPACT_TICKET);
+
+        v.extend(self.scope.to_bytes());
+        v.extend(self.rerandomized_mac_commitment.to_bytes());
+        v.extend(self.rerandomized_nonce.compress().as_bytes());
+        v.extend(self.serial.as_bytes());
+        v.extend(self.proof.to_bytes().iter());
+
+        v
+    }
+
+    /// The nullifier for this ticket, which is its serial number.
+    pub fn nullifier(&self) -> Nullifier {
+        Nullifier(self.serial.to_bytes())
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(CompactTicket, "A valid byte sequence representing a CompactTicket");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CompactTicket);
+impl_try_from_bytes!(CompactTicket, TicketError);
+
+/// A verifier of tickets at a single venue.
+pub struct TicketVerifier<S: NullifierStore> {
+    issuer: TicketIssuer,
+    venue_id: Vec<u8>,
+    nullifiers: S,
+}
+
+impl<S: NullifierStore> TicketVerifier<S> {
+    /// Create a verifier for tickets to the venue `venue_id`, recording used
+    /// tickets in `nullifiers`.
+    ///
+    /// # Note
+    ///
+    /// Since ticket serial numbers are random, the same `nullifiers` may be
+    /// kept across validity windows, and should not be cleared until every
+    /// window it has seen tickets for has closed.
+    pub fn new(issuer: TicketIssuer, venue_id: &[u8], nullifiers: S) -> TicketVerifier<S> {
+        TicketVerifier { issuer, venue_id: venue_id.to_vec(), nullifiers }
+    }
+
+    pub fn nullifiers(&self) -> &S {
+        &self.nullifiers
+    }
+
+    /// Mutable access to the store of used tickets, e.g. for merging in
+    /// tickets used at other gates.
+    pub fn nullifiers_mut(&mut self) -> &mut S {
+        &mut self.nullifiers
+    }
+
+    /// Verify a `ticket` presented at time `now`, and record it as used.
+    ///
+    /// # Errors
+    ///
+    /// * `TicketError::WrongVenue` if the ticket is for another venue.
+    /// * `TicketError::OutsideWindow` if `now` is outside the ticket's
+    ///   validity window.
+    /// * `TicketError::VerificationFailure` if the ticket was not minted by
+    ///   this verifier's issuer for its stated scope.
+    /// * `TicketError::AlreadyUsed` if the ticket was already presented.
+    pub fn verify(&mut self, ticket: &CompactTicket, now: u64) -> Result<(), Error> {
+        if ticket.scope.venue_id != self.venue_id {
+            return Err(TicketError::WrongVenue.into());
+        }
+        if !ticket.scope.contains(now) {
+            return Err(TicketError::OutsideWindow.into());
+        }
+
+        // Recompute the MAC.  Only the scope's secret scalars are derived,
+        // rather than a whole `Issuer`, to avoid allocating and computing its
+        // public key on every ticket.
+        let mut x0: Scalar = self.issuer.derive_scalar(&ticket.scope, 0);
+        let mut x1: Scalar = self.issuer.derive_scalar(&ticket.scope, 1);
+        let mut x: Scalar = &x0 + &(&x1 * &ticket.serial);
+        let mut transcript = ticket_transcript(&ticket.scope);
+        let verified = compact::verify_opening(&mut transcript, &self.issuer.system_parameters.h, &x,
+                                               &ticket.rerandomized_nonce,
+                                               &ticket.rerandomized_mac_commitment,
+                                               &ticket.serial, &ticket.proof);
+
+        x0.clear();
+        x1.clear();
+        x.clear();
+
+        if verified.is_err() {
+            return Err(TicketError::VerificationFailure.into());
+        }
+        if !self.nullifiers.insert(&ticket.nullifier()) {
+            return Err(TicketError::AlreadyUsed.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use curve25519_dalek::traits::Identity;
+
+    use rand::thread_rng;
+
+    use test_utils::H;
+
+    const VENUE: &'static [u8] = b"Wembley Arena";
+    const NOT_BEFORE: u64 = 1_538_000_000;
+    const NOT_AFTER: u64 = 1_538_086_400;
+
+    fn mint(issuer: &TicketIssuer, scope: TicketScope) -> Ticket {
+        let mut rng = thread_rng();
+        let issuer_parameters = issuer.issuer_parameters(&scope);
+        let (request, pending) = TicketRequest::create(issuer.system_parameters, issuer_parameters,
+                                                       scope, &mut rng).unwrap();
+        let request = TicketRequest::from_bytes(&request.to_bytes()).unwrap();
+        let issuance = issuer.mint(&request, &mut rng).unwrap();
+
+        pending.finish(&issuance).unwrap()
+    }
+
+    fn setup() -> (TicketIssuer, TicketVerifier<HashSet<Nullifier>>) {
+        let mut rng = thread_rng();
+        let issuer = TicketIssuer::generate(SystemParameters::from(H), &mut rng);
+        let verifier = TicketVerifier::new(issuer.clone(), VENUE, HashSet::new());
+
+        (issuer, verifier)
+    }
+
+    #[test]
+    fn ticket_mint_present_verify() {
+        let mut rng = thread_rng();
+        let (issuer, mut verifier) = setup();
+        let scope = TicketScope::new(VENUE, NOT_BEFORE, NOT_AFTER).unwrap();
+        let ticket = mint(&issuer, scope).present(&mut rng);
+        let bytes = ticket.to_bytes();
+
+        assert!(bytes.len() <= 200);
+        assert!(bytes.len() <= MAXIMUM_SIZEOF_COMPACT_TICKET);
+
+        let ticket = CompactTicket::from_bytes(&bytes).unwrap();
+
+        assert!(verifier.verify(&ticket, NOT_BEFORE).is_ok());
+    }
+
+    #[test]
+    fn ticket_second_showing_rejected() {
+        let mut rng = thread_rng();
+        let (issuer, mut verifier) = setup();
+        let scope = TicketScope::new(VENUE, NOT_BEFORE, NOT_AFTER).unwrap();
+        let ticket = mint(&issuer, scope);
+        let first = ticket.clone().present(&mut rng);
+        let second = ticket.present(&mut rng);
+
+        assert!(first != second);
+        assert!(verifier.verify(&first, NOT_BEFORE).is_ok());
+        assert!(verifier.verify(&second, NOT_BEFORE) == Err(TicketError::AlreadyUsed.into()));
+    }
+
+    #[test]
+    fn ticket_wrong_venue_and_window() {
+        let mut rng = thread_rng();
+        let (issuer, mut verifier) = setup();
+        let elsewhere = TicketScope::new(b"Royal Albert", NOT_BEFORE, NOT_AFTER).unwrap();
+        let scope = TicketScope::new(VENUE, NOT_BEFORE, NOT_AFTER).unwrap();
+
+        let ticket = mint(&issuer, elsewhere).present(&mut rng);
+
+        assert!(verifier.verify(&ticket, NOT_BEFORE) == Err(TicketError::WrongVenue.into()));
+
+        let ticket = mint(&issuer, scope).present(&mut rng);
+
+        assert!(verifier.verify(&ticket, NOT_BEFORE - 1) == Err(TicketError::OutsideWindow.into()));
+        assert!(verifier.verify(&ticket, NOT_AFTER) == Err(TicketError::OutsideWindow.into()));
+    }
+
+    #[test]
+    fn ticket_extended_window_rejected() {
+        let mut rng = thread_rng();
+        let (issuer, mut verifier) = setup();
+        let scope = TicketScope::new(VENUE, NOT_BEFORE, NOT_AFTER).unwrap();
+        let mut ticket = mint(&issuer, scope).present(&mut rng);
+
+        ticket.scope.not_after += 86_400;
+
+        assert!(verifier.verify(&ticket, NOT_AFTER) == Err(TicketError::VerificationFailure.into()));
+    }
+
+    #[test]
+    fn ticket_identity_nonce_rejected() {
+        let mut rng = thread_rng();
+        let (issuer, mut verifier) = setup();
+        let scope = TicketScope::new(VENUE, NOT_BEFORE, NOT_AFTER).unwrap();
+        let mut ticket = mint(&issuer, scope).present(&mut rng);
+
+        ticket.rerandomized_nonce = RistrettoPoint::identity();
+
+        assert!(verifier.verify(&ticket, NOT_BEFORE) == Err(TicketError::VerificationFailure.into()));
+    }
+
+    #[test]
+    fn ticket_scope_validation() {
+        assert!(TicketScope::new(b"", NOT_BEFORE, NOT_AFTER) == Err(TicketError::InvalidScope.into()));
+        assert!(TicketScope::new(&[0u8; 17], NOT_BEFORE, NOT_AFTER) == Err(TicketError::InvalidScope.into()));
+        assert!(TicketScope::new(VENUE, NOT_AFTER, NOT_BEFORE) == Err(TicketError::InvalidScope.into()));
+    }
+}
@@ -0,0 +1,667 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Lightweight anonymous tokens, in the style of Privacy Pass.
+//!
+//! Where a full credential is overkill, e.g. for rate-limiting, an issuer may
+//! instead sign a batch of blinded tokens.  Each token may later be redeemed
+//! exactly once, unlinkably to its issuance.
+//!
+//! # Protocol
+//!
+//! The issuer has a secret key \\( k \\) and public key \\( Y = k \cdot G \\).
+//!
+//! 1. The client chooses a random preimage \\( t \\), maps it to a point
+//!    \\( T = H(t) \\), and blinds it as \\( M = r \cdot T \\).
+//! 2. The issuer signs \\( Z = k \cdot M \\) for each blinded token, and
+//!    proves with a single batched DLEQ proof that it used the same \\( k \\)
+//!    for every token as in \\( Y \\).
+//! 3. The client unblinds \\( N = r^{-1} \cdot Z = k \cdot T \\).
+//! 4. To redeem, the client sends \\( t \\) and a MAC over some request
+//!    binding data, keyed by \\( (t, N) \\).  The issuer recomputes
+//!    \\( N = k \cdot H(t) \\), checks the MAC, and checks that \\( t \\) has
+//!    not been spent before.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use core::cmp;
+#[cfg(feature = "std")]
+use std::cmp;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+use bincode::deserialize;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use subtle::ConstantTimeEq;
+
+use errors::Error;
+use errors::TokenError;
+use hashing::Hasher;
+use nonces::Witness;
+use proofs::batch_dleq;
+use secret::Secret;
+
+pub const SIZEOF_TOKEN_PREIMAGE: usize = 32;
+pub const SIZEOF_TOKEN_KEYPAIR: usize = 64;
+pub const SIZEOF_TOKEN: usize = 64;
+pub const SIZEOF_TOKEN_REDEMPTION: usize = 64;
+
+/// The maximum number of tokens which may be signed in one batch.
+pub const MAXIMUM_TOKENS_PER_REQUEST: usize = 100;
+
+/// The domain separator used when mapping token preimages to points.
+const TOKEN_DOMAIN_SEPARATOR: &'static [u8] = b"aeonflux token v1";
+
+fn decompress(bytes: &[u8]) -> Result<RistrettoPoint, TokenError> {
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    Ok(CompressedRistretto(tmp).decompress()?)
+}
+
+/// Read a one-byte count of 32-byte items, checking it against the maximum.
+fn read_points(bytes: &[u8]) -> Result<(Vec<RistrettoPoint>, usize), TokenError> {
+    if bytes.len() < 1 {
+        return Err(TokenError::WrongNumberOfBytes);
+    }
+
+    let count: usize = bytes[0] as usize;
+    let end: usize = 1 + 32 * count;
+
+    if count > MAXIMUM_TOKENS_PER_REQUEST {
+        return Err(TokenError::WrongNumberOfTokens);
+    }
+    if bytes.len() < end {
+        return Err(TokenError::WrongNumberOfBytes);
+    }
+
+    let mut points: Vec<RistrettoPoint> = Vec::with_capacity(count);
+
+    for chunk in bytes[1..end].chunks(32) {
+        points.push(decompress(chunk)?);
+    }
+
+    Ok((points, end))
+}
+
+fn write_points(points: &[RistrettoPoint], v: &mut Vec<u8>) {
+    v.push(points.len() as u8);
+
+    for point in points.iter() {
+        v.extend(point.compress().to_bytes().iter());
+    }
+}
+
+/// The random value which a client maps to a token.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct TokenPreimage(pub [u8; SIZEOF_TOKEN_PREIMAGE]);
+
+impl TokenPreimage {
+    /// Map this preimage to a point, \\( T = H(t) \\).
+    pub fn to_point(&self) -> RistrettoPoint {
+        let mut hash = Hasher::new(TOKEN_DOMAIN_SEPARATOR);
+
+        hash.input(&self.0[..]);
+        hash.finalize_point()
+    }
+}
+
+/// Compute the redemption MAC for a token over some `binding` data.
+fn redemption_mac(preimage: &TokenPreimage, point: &RistrettoPoint, binding: &[u8]) -> [u8; 32] {
+    let mut transcript = Transcript::new(b"AEONFLUX TOKEN REDEMPTION");
+    let mut mac = [0u8; 32];
+
+    transcript.commit_bytes(b"preimage", &preimage.0);
+    transcript.commit_bytes(b"point", point.compress().as_bytes());
+    transcript.commit_bytes(b"binding", binding);
+    transcript.challenge_bytes(b"mac", &mut mac);
+
+    mac
+}
+
+/// Derive the coefficients for combining a batch of blinded and signed tokens.
+fn batch_coefficients(
+    transcript: &mut Transcript,
+    public: &RistrettoPoint,
+    blinded: &[RistrettoPoint],
+    signed: &[RistrettoPoint],
+) -> Vec<Scalar>
+{
+    let mut coefficients: Vec<Scalar> = Vec::with_capacity(blinded.len());
+
+    transcript.commit_bytes(b"Y", public.compress().as_bytes());
+
+    for (M, Z) in blinded.iter().zip(signed.iter()) {
+        transcript.commit_bytes(b"M", M.compress().as_bytes());
+        transcript.commit_bytes(b"Z", Z.compress().as_bytes());
+    }
+    for _ in 0..blinded.len() {
+        let mut bytes = [0u8; 64];
+
+        transcript.challenge_bytes(b"c", &mut bytes);
+        coefficients.push(Scalar::from_bytes_mod_order_wide(&bytes));
+    }
+
+    coefficients
+}
+
+/// Compute \\( \sum c_i P_i \\).
+///
+/// The coefficients and points are always public, so this uses variable-time
+/// arithmetic.
+fn combine(coefficients: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+    let length: usize = cmp::min(coefficients.len(), points.len());
+
+    RistrettoPoint::vartime_multiscalar_mul(&coefficients[..length], &points[..length])
+}
+
+/// An issuer's token signing key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenKeypair {
+    secret: Secret<Scalar>,
+    pub public: RistrettoPoint,
+}
+
+impl TokenKeypair {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenKeypair, TokenError> {
+        if bytes.len() != SIZEOF_TOKEN_KEYPAIR {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 32];
+
+        tmp.copy_from_slice(&bytes[00..32]);
+
+        let secret = match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => x,
+            None    => return Err(TokenError::ScalarFormatError),
+        };
+        let public = decompress(&bytes[32..64])?;
+
+        Ok(TokenKeypair { secret: Secret::new(secret), public })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TOKEN_KEYPAIR);
+
+        v.extend(self.secret.expose_secret().to_bytes().iter());
+        v.extend(self.public.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenKeypair, "A valid byte sequence representing a TokenKeypair");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenKeypair);
+impl_try_from_bytes!(TokenKeypair, TokenError);
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for TokenKeypair {
+    fn drop(&mut self) {
+        self.secret.clear();
+    }
+}
+
+impl TokenKeypair {
+    /// Generate a new token signing key.
+    pub fn generate<R>(csprng: &mut R) -> TokenKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret: Scalar = Scalar::random(csprng);
+        let public: RistrettoPoint = &secret * &RISTRETTO_BASEPOINT_TABLE;
+
+        TokenKeypair { secret: Secret::new(secret), public }
+    }
+
+    /// Sign a batch of blinded tokens.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::WrongNumberOfTokens` if the `request` is empty or
+    ///   contains more than `MAXIMUM_TOKENS_PER_REQUEST` tokens.
+    pub fn sign(&self, request: &TokenRequest) -> Result<TokenResponse, Error> {
+        if request.blinded.len() == 0 || request.blinded.len() > MAXIMUM_TOKENS_PER_REQUEST {
+            return Err(TokenError::WrongNumberOfTokens.into());
+        }
+
+        let mut signed: Vec<RistrettoPoint> = Vec::with_capacity(request.blinded.len());
+
+        for M in request.blinded.iter() {
+            signed.push(M * self.secret.expose_secret());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX TOKEN ISSUANCE");
+        let coefficients = batch_coefficients(&mut transcript, &self.public, &request.blinded, &signed);
+        let M: RistrettoPoint = combine(&coefficients, &request.blinded);
+        let Z: RistrettoPoint = combine(&coefficients, &signed);
+
+        let secrets = batch_dleq::Secrets { k: Witness::from_secret(&self.secret) };
+        let publics = batch_dleq::Publics {
+            G: &RISTRETTO_BASEPOINT_POINT,
+            Y: &self.public,
+            M: &M,
+            Z: &Z,
+        };
+        let proof = batch_dleq::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(TokenResponse { signed, proof })
+    }
+
+    /// Redeem a token, binding it to some request-specific `binding` data.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::InvalidMac` if the redemption was not made with a token
+    ///   signed by this key, or was for different `binding` data.
+    /// * `TokenError::DoubleSpend` if the token was already in the `spent` store.
+    pub fn redeem<S>(
+        &self,
+        redemption: &TokenRedemption,
+        binding: &[u8],
+        spent: &mut S,
+    ) -> Result<(), Error>
+    where
+        S: SpentTokens,
+    {
+        let N: RistrettoPoint = redemption.preimage.to_point() * self.secret.expose_secret();
+        let mac: [u8; 32] = redemption_mac(&redemption.preimage, &N, binding);
+
+        if mac[..].ct_eq(&redemption.mac[..]).unwrap_u8() != 1 {
+            return Err(TokenError::InvalidMac.into());
+        }
+        if !spent.insert(&redemption.preimage) {
+            return Err(TokenError::DoubleSpend.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A store of the preimages of tokens which have been redeemed.
+pub trait SpentTokens {
+    /// Record a token as spent.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the token was not previously spent, and `false` otherwise.
+    fn insert(&mut self, preimage: &TokenPreimage) -> bool;
+
+    /// Whether a token has been spent.
+    fn contains(&self, preimage: &TokenPreimage) -> bool;
+}
+
+#[cfg(feature = "std")]
+impl SpentTokens for HashSet<TokenPreimage> {
+    fn insert(&mut self, preimage: &TokenPreimage) -> bool {
+        HashSet::insert(self, *preimage)
+    }
+
+    fn contains(&self, preimage: &TokenPreimage) -> bool {
+        HashSet::contains(self, preimage)
+    }
+}
+
+/// A client's secret state for a token awaiting signature.
+#[derive(Clone, Debug)]
+pub struct PendingToken {
+    pub preimage: TokenPreimage,
+    blinding: Secret<Scalar>,
+}
+
+/// Overwrite the blinding factor with null bytes when it goes out of scope.
+impl Drop for PendingToken {
+    fn drop(&mut self) {
+        self.blinding.clear();
+    }
+}
+
+/// A request from a client for a batch of blinded tokens to be signed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenRequest {
+    pub blinded: Vec<RistrettoPoint>,
+}
+
+impl TokenRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenRequest, TokenError> {
+        let (blinded, end) = read_points(bytes)?;
+
+        if end != bytes.len() {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        Ok(TokenRequest { blinded })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(1 + 32 * self.blinded.len());
+
+        write_points(&self.blinded, &mut v);
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenRequest, "A valid byte sequence representing a TokenRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenRequest);
+impl_try_from_bytes!(TokenRequest, TokenError);
+
+impl TokenRequest {
+    /// Create a request for `count` new tokens.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::WrongNumberOfTokens` if `count` is zero or greater than
+    ///   `MAXIMUM_TOKENS_PER_REQUEST`.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the `TokenRequest` to send to the issuer, and the
+    /// `PendingToken`s which the client must keep until the response arrives.
+    pub fn create<R>(count: usize, csprng: &mut R) -> Result<(TokenRequest, Vec<PendingToken>), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        if count == 0 || count > MAXIMUM_TOKENS_PER_REQUEST {
+            return Err(TokenError::WrongNumberOfTokens.into());
+        }
+
+        let mut blinded: Vec<RistrettoPoint> = Vec::with_capacity(count);
+        let mut pending: Vec<PendingToken> = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let mut preimage = TokenPreimage([0u8; SIZEOF_TOKEN_PREIMAGE]);
+
+            csprng.fill_bytes(&mut preimage.0);
+
+            let blinding: Scalar = Scalar::random(csprng);
+
+            blinded.push(preimage.to_point() * &blinding);
+            pending.push(PendingToken { preimage, blinding: Secret::new(blinding) });
+        }
+
+        Ok((TokenRequest { blinded }, pending))
+    }
+}
+
+/// An issuer's signatures on a batch of blinded tokens.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenResponse {
+    pub signed: Vec<RistrettoPoint>,
+    pub proof: batch_dleq::Proof,
+}
+
+impl TokenResponse {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenResponse, TokenError> {
+        let (signed, end) = read_points(bytes)?;
+        let proof: batch_dleq::Proof = match deserialize(&bytes[end..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(TokenError::WrongNumberOfBytes),
+        };
+
+        Ok(TokenResponse { signed, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(1 + 32 * self.signed.len());
+
+        write_points(&self.signed, &mut v);
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenResponse, "A valid byte sequence representing a TokenResponse");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenResponse);
+impl_try_from_bytes!(TokenResponse, TokenError);
+
+impl TokenResponse {
+    /// Verify the issuer's batch proof and unblind the signed tokens.
+    ///
+    /// # Inputs
+    ///
+    /// * `request` is the `TokenRequest` which was sent to the issuer.
+    /// * `pending` are the `PendingToken`s returned with the `request`.
+    /// * `public` is the issuer's well-known token public key.
+    ///
+    /// # Errors
+    ///
+    /// * `TokenError::WrongNumberOfTokens` if the response had a different
+    ///   number of tokens than the request.
+    /// * `TokenError::ProofFailure` if the proof did not verify.
+    pub fn unblind(
+        &self,
+        request: &TokenRequest,
+        pending: &[PendingToken],
+        public: &RistrettoPoint,
+    ) -> Result<Vec<Token>, Error>
+    {
+        if self.signed.len() != request.blinded.len() || pending.len() != request.blinded.len() {
+            return Err(TokenError::WrongNumberOfTokens.into());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX TOKEN ISSUANCE");
+        let coefficients = batch_coefficients(&mut transcript, public, &request.blinded, &self.signed);
+        let M: RistrettoPoint = combine(&coefficients, &request.blinded);
+        let Z: RistrettoPoint = combine(&coefficients, &self.signed);
+
+        let publics = batch_dleq::Publics {
+            G: &RISTRETTO_BASEPOINT_POINT,
+            Y: public,
+            M: &M,
+            Z: &Z,
+        };
+
+        if self.proof.verify(&mut transcript, publics).is_err() {
+            return Err(TokenError::ProofFailure.into());
+        }
+
+        let mut tokens: Vec<Token> = Vec::with_capacity(self.signed.len());
+
+        for (Z, token) in self.signed.iter().zip(pending.iter()) {
+            tokens.push(Token {
+                preimage: token.preimage,
+                point: Z * &token.blinding.expose_secret().invert(),
+            });
+        }
+
+        Ok(tokens)
+    }
+}
+
+/// An unblinded, signed token, ready to be redeemed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    pub preimage: TokenPreimage,
+    point: RistrettoPoint,
+}
+
+impl Token {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Token, TokenError> {
+        if bytes.len() != SIZEOF_TOKEN {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        let mut preimage = TokenPreimage([0u8; SIZEOF_TOKEN_PREIMAGE]);
+
+        preimage.0.copy_from_slice(&bytes[00..32]);
+
+        let point = decompress(&bytes[32..64])?;
+
+        Ok(Token { preimage, point })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TOKEN);
+
+        v.extend(self.preimage.0.iter());
+        v.extend(self.point.compress().to_bytes().iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Token, "A valid byte sequence representing a Token");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Token);
+impl_try_from_bytes!(Token, TokenError);
+
+impl Token {
+    /// Redeem this token for a request described by `binding`, e.g. a hash of
+    /// the request path and body.
+    pub fn redeem(&self, binding: &[u8]) -> TokenRedemption {
+        TokenRedemption {
+            preimage: self.preimage,
+            mac: redemption_mac(&self.preimage, &self.point, binding),
+        }
+    }
+}
+
+/// A token being spent.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TokenRedemption {
+    pub preimage: TokenPreimage,
+    pub mac: [u8; 32],
+}
+
+impl TokenRedemption {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TokenRedemption, TokenError> {
+        if bytes.len() != SIZEOF_TOKEN_REDEMPTION {
+            return Err(TokenError::WrongNumberOfBytes);
+        }
+
+        let mut preimage = TokenPreimage([0u8; SIZEOF_TOKEN_PREIMAGE]);
+        let mut mac = [0u8; 32];
+
+        preimage.0.copy_from_slice(&bytes[00..32]);
+        mac.copy_from_slice(&bytes[32..64]);
+
+        Ok(TokenRedemption { preimage, mac })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_TOKEN_REDEMPTION);
+
+        v.extend(self.preimage.0.iter());
+        v.extend(self.mac.iter());
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TokenRedemption,
+                                         "A valid byte sequence representing a TokenRedemption");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TokenRedemption);
+impl_try_from_bytes!(TokenRedemption, TokenError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    fn issue(keypair: &TokenKeypair, count: usize) -> Vec<Token> {
+        let mut csprng = thread_rng();
+        let (request, pending) = TokenRequest::create(count, &mut csprng).unwrap();
+        let request = TokenRequest::from_bytes(&request.to_bytes()).unwrap();
+        let response = keypair.sign(&request).unwrap();
+        let response = TokenResponse::from_bytes(&response.to_bytes()).unwrap();
+
+        response.unblind(&request, &pending, &keypair.public).unwrap()
+    }
+
+    #[test]
+    fn issue_and_redeem() {
+        let mut csprng = thread_rng();
+        let keypair = TokenKeypair::generate(&mut csprng);
+        let tokens = issue(&keypair, 5);
+        let mut spent: HashSet<TokenPreimage> = HashSet::new();
+
+        assert!(tokens.len() == 5);
+
+        for token in tokens.iter() {
+            let redemption = TokenRedemption::from_bytes(&token.redeem(b"GET /").to_bytes()).unwrap();
+
+            assert!(keypair.redeem(&redemption, b"GET /", &mut spent).is_ok());
+        }
+        assert!(spent.len() == 5);
+    }
+
+    #[test]
+    fn double_spend() {
+        let mut csprng = thread_rng();
+        let keypair = TokenKeypair::generate(&mut csprng);
+        let tokens = issue(&keypair, 1);
+        let mut spent: HashSet<TokenPreimage> = HashSet::new();
+        let redemption = tokens[0].redeem(b"GET /");
+
+        assert!(keypair.redeem(&redemption, b"GET /", &mut spent).is_ok());
+        assert!(keypair.redeem(&redemption, b"GET /", &mut spent) == Err(TokenError::DoubleSpend.into()));
+    }
+
+    #[test]
+    fn wrong_binding_or_key() {
+        let mut csprng = thread_rng();
+        let keypair = TokenKeypair::generate(&mut csprng);
+        let other = TokenKeypair::generate(&mut csprng);
+        let tokens = issue(&keypair, 1);
+        let mut spent: HashSet<TokenPreimage> = HashSet::new();
+        let redemption = tokens[0].redeem(b"GET /");
+
+        assert!(keypair.redeem(&redemption, b"POST /", &mut spent) == Err(TokenError::InvalidMac.into()));
+        assert!(other.redeem(&redemption, b"GET /", &mut spent) == Err(TokenError::InvalidMac.into()));
+        // Failed redemptions don't spend the token.
+        assert!(!spent.contains(&redemption.preimage));
+    }
+
+    #[test]
+    fn response_from_wrong_key_is_rejected() {
+        let mut csprng = thread_rng();
+        let keypair = TokenKeypair::generate(&mut csprng);
+        let other = TokenKeypair::generate(&mut csprng);
+        let (request, pending) = TokenRequest::create(3, &mut csprng).unwrap();
+        let response = other.sign(&request).unwrap();
+
+        assert!(response.unblind(&request, &pending, &keypair.public) == Err(TokenError::ProofFailure.into()));
+    }
+
+    #[test]
+    fn request_size_limits() {
+        let mut csprng = thread_rng();
+
+        assert!(TokenRequest::create(0, &mut csprng).is_err());
+        assert!(TokenRequest::create(MAXIMUM_TOKENS_PER_REQUEST + 1, &mut csprng).is_err());
+        assert!(TokenRequest::create(MAXIMUM_TOKENS_PER_REQUEST, &mut csprng).is_ok());
+    }
+}
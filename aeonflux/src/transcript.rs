@@ -0,0 +1,417 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Proof transcripts over a choice of hash function.
+//!
+//! Every proof in the `proofs` module is written against the
+//! `ProofTranscript` trait, rather than against merlin directly, so the
+//! primitive which derives its challenges and nonces may be chosen by the
+//! deployment:
+//!
+//! * `merlin::Transcript`, over Strobe and Keccak-f[1600], the default.
+//! * `HashTranscript<Sha512>`, for deployments which must use a FIPS
+//!   approved hash function.
+//! * `HashTranscript<blake3::Hasher>`, with the `blake3-transcripts`
+//!   feature.
+//!
+//! Which one a message was proven with is named by its `Suite`, and
+//! `Suite::transcript()` begins a `SuiteTranscript` for it, so the proof code
+//! itself never forks.  A proof made under one suite does not verify under
+//! another.
+
+use merlin::Transcript;
+use merlin::TranscriptRng;
+use merlin::TranscriptRngConstructor;
+
+use rand::prng::ChaChaRng;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+use rand_core::SeedableRng;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+#[cfg(feature = "blake3-transcripts")]
+use blake3;
+
+use encoding::u64_to_bytes;
+
+/// A Fiat-Shamir transcript, as used by the proofs in this crate.
+pub trait ProofTranscript {
+    /// The builder of the generator from which a proof draws its nonces.
+    type RngBuilder: ProofRngBuilder;
+
+    /// Append a public `message`, under some `label`.
+    fn commit_bytes(&mut self, label: &'static [u8], message: &[u8]);
+
+    /// Fill `dest` with a challenge bound to everything committed so far.
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]);
+
+    /// Begin building a generator bound to the transcript so far.
+    fn fork_transcript(&self) -> Self::RngBuilder;
+}
+
+/// A builder of a proof's nonce generator, bound to its transcript and
+/// witnesses.
+pub trait ProofRngBuilder: Sized {
+    type Rng: RngCore + CryptoRng;
+
+    /// Bind the generator to a secret `witness`, under some `label`.
+    fn commit_witness_bytes(self, label: &'static [u8], witness: &[u8]) -> Self;
+
+    /// Finish the generator, mixing in some entropy from `rng`.
+    fn reseed_from_rng<R>(self, rng: &mut R) -> Self::Rng
+    where
+        R: RngCore + CryptoRng;
+}
+
+impl ProofTranscript for Transcript {
+    type RngBuilder = TranscriptRngConstructor;
+
+    fn commit_bytes(&mut self, label: &'static [u8], message: &[u8]) {
+        Transcript::commit_bytes(self, label, message)
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        Transcript::challenge_bytes(self, label, dest)
+    }
+
+    fn fork_transcript(&self) -> TranscriptRngConstructor {
+        Transcript::fork_transcript(self)
+    }
+}
+
+impl ProofRngBuilder for TranscriptRngConstructor {
+    type Rng = TranscriptRng;
+
+    fn commit_witness_bytes(self, label: &'static [u8], witness: &[u8]) -> TranscriptRngConstructor {
+        TranscriptRngConstructor::commit_witness_bytes(self, label, witness)
+    }
+
+    fn reseed_from_rng<R>(self, rng: &mut R) -> TranscriptRng
+    where
+        R: RngCore + CryptoRng,
+    {
+        TranscriptRngConstructor::reseed_from_rng(self, rng)
+    }
+}
+
+/// A hash function which a `HashTranscript` may be built upon.
+pub trait TranscriptHash: Clone + Default {
+    /// Append some `bytes` to the hash input.
+    fn update(&mut self, bytes: &[u8]);
+
+    /// Finish the hash, with at least 64 bytes of output.
+    fn finalize_wide(self) -> [u8; 64];
+}
+
+impl TranscriptHash for Sha512 {
+    fn update(&mut self, bytes: &[u8]) {
+        self.input(bytes);
+    }
+
+    fn finalize_wide(self) -> [u8; 64] {
+        let mut output = [0u8; 64];
+
+        output.copy_from_slice(&self.result()[..]);
+        output
+    }
+}
+
+#[cfg(feature = "blake3-transcripts")]
+impl TranscriptHash for blake3::Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        blake3::Hasher::update(self, bytes);
+    }
+
+    fn finalize_wide(self) -> [u8; 64] {
+        let mut output = [0u8; 64];
+
+        self.finalize_xof().fill(&mut output);
+        output
+    }
+}
+
+/// The operations framed into a `HashTranscript`, so that no sequence of
+/// them may be mistaken for another.
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum Operation {
+    Begin = 1,
+    Commit = 2,
+    Challenge = 3,
+    Output = 4,
+    Witness = 5,
+    Reseed = 6,
+}
+
+fn absorb<H: TranscriptHash>(hash: &mut H, operation: Operation, label: &[u8], message: &[u8]) {
+    hash.update(&[operation as u8]);
+    hash.update(&u64_to_bytes(label.len() as u64));
+    hash.update(label);
+    hash.update(&u64_to_bytes(message.len() as u64));
+    hash.update(message);
+}
+
+/// A Fiat-Shamir transcript over a plain hash function `H`.
+///
+/// Each commitment is framed with its length and appended to a running
+/// hash, and each challenge is expanded from a copy of it in counter mode,
+/// then appended in turn, so that later challenges depend on earlier ones.
+#[derive(Clone)]
+pub struct HashTranscript<H: TranscriptHash> {
+    state: H,
+}
+
+impl<H: TranscriptHash> HashTranscript<H> {
+    /// Begin a transcript within the domain named by `label`.
+    pub fn new(label: &'static [u8]) -> HashTranscript<H> {
+        let mut state = H::default();
+
+        absorb(&mut state, Operation::Begin, b"aeonflux hash transcript v1", label);
+
+        HashTranscript { state }
+    }
+}
+
+impl<H: TranscriptHash> ProofTranscript for HashTranscript<H> {
+    type RngBuilder = HashRngBuilder<H>;
+
+    fn commit_bytes(&mut self, label: &'static [u8], message: &[u8]) {
+        absorb(&mut self.state, Operation::Commit, label, message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        absorb(&mut self.state, Operation::Challenge, label, &u64_to_bytes(dest.len() as u64));
+
+        for (i, chunk) in dest.chunks_mut(64).enumerate() {
+            let mut block = self.state.clone();
+
+            block.update(&u64_to_bytes(i as u64));
+            chunk.copy_from_slice(&block.finalize_wide()[..chunk.len()]);
+        }
+        absorb(&mut self.state, Operation::Output, label, dest);
+    }
+
+    fn fork_transcript(&self) -> HashRngBuilder<H> {
+        HashRngBuilder { state: self.state.clone() }
+    }
+}
+
+/// The builder of a nonce generator forked from a `HashTranscript`.
+#[derive(Clone)]
+pub struct HashRngBuilder<H: TranscriptHash> {
+    state: H,
+}
+
+impl<H: TranscriptHash> ProofRngBuilder for HashRngBuilder<H> {
+    type Rng = ChaChaRng;
+
+    fn commit_witness_bytes(mut self, label: &'static [u8], witness: &[u8]) -> HashRngBuilder<H> {
+        absorb(&mut self.state, Operation::Witness, label, witness);
+        self
+    }
+
+    fn reseed_from_rng<R>(mut self, rng: &mut R) -> ChaChaRng
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut entropy = [0u8; 32];
+        let mut seed = [0u8; 32];
+
+        rng.fill_bytes(&mut entropy);
+        absorb(&mut self.state, Operation::Reseed, b"rng", &entropy);
+        seed.copy_from_slice(&self.state.finalize_wide()[..32]);
+
+        ChaChaRng::from_seed(seed)
+    }
+}
+
+/// A transcript for whichever hash function a `Suite` names.
+#[derive(Clone)]
+pub enum SuiteTranscript {
+    Merlin(Transcript),
+    Sha512(HashTranscript<Sha512>),
+    #[cfg(feature = "blake3-transcripts")]
+    Blake3(HashTranscript<blake3::Hasher>),
+}
+
+/// The builder of a nonce generator forked from a `SuiteTranscript`.
+pub enum SuiteRngBuilder {
+    Merlin(TranscriptRngConstructor),
+    Sha512(HashRngBuilder<Sha512>),
+    #[cfg(feature = "blake3-transcripts")]
+    Blake3(HashRngBuilder<blake3::Hasher>),
+}
+
+/// The nonce generator of a proof made with a `SuiteTranscript`.
+pub enum SuiteRng {
+    Merlin(TranscriptRng),
+    Hash(ChaChaRng),
+}
+
+impl ProofTranscript for SuiteTranscript {
+    type RngBuilder = SuiteRngBuilder;
+
+    fn commit_bytes(&mut self, label: &'static [u8], message: &[u8]) {
+        match *self {
+            SuiteTranscript::Merlin(ref mut t) => t.commit_bytes(label, message),
+            SuiteTranscript::Sha512(ref mut t) => t.commit_bytes(label, message),
+            #[cfg(feature = "blake3-transcripts")]
+            SuiteTranscript::Blake3(ref mut t) => t.commit_bytes(label, message),
+        }
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        match *self {
+            SuiteTranscript::Merlin(ref mut t) => t.challenge_bytes(label, dest),
+            SuiteTranscript::Sha512(ref mut t) => t.challenge_bytes(label, dest),
+            #[cfg(feature = "blake3-transcripts")]
+            SuiteTranscript::Blake3(ref mut t) => t.challenge_bytes(label, dest),
+        }
+    }
+
+    fn fork_transcript(&self) -> SuiteRngBuilder {
+        match *self {
+            SuiteTranscript::Merlin(ref t) => SuiteRngBuilder::Merlin(t.fork_transcript()),
+            SuiteTranscript::Sha512(ref t) => SuiteRngBuilder::Sha512(t.fork_transcript()),
+            #[cfg(feature = "blake3-transcripts")]
+            SuiteTranscript::Blake3(ref t) => SuiteRngBuilder::Blake3(t.fork_transcript()),
+        }
+    }
+}
+
+impl ProofRngBuilder for SuiteRngBuilder {
+    type Rng = SuiteRng;
+
+    fn commit_witness_bytes(self, label: &'static [u8], witness: &[u8]) -> SuiteRngBuilder {
+        match self {
+            SuiteRngBuilder::Merlin(b) => SuiteRngBuilder::Merlin(b.commit_witness_bytes(label, witness)),
+            SuiteRngBuilder::Sha512(b) => SuiteRngBuilder::Sha512(b.commit_witness_bytes(label, witness)),
+            #[cfg(feature = "blake3-transcripts")]
+            SuiteRngBuilder::Blake3(b) => SuiteRngBuilder::Blake3(b.commit_witness_bytes(label, witness)),
+        }
+    }
+
+    fn reseed_from_rng<R>(self, rng: &mut R) -> SuiteRng
+    where
+        R: RngCore + CryptoRng,
+    {
+        match self {
+            SuiteRngBuilder::Merlin(b) => SuiteRng::Merlin(b.reseed_from_rng(rng)),
+            SuiteRngBuilder::Sha512(b) => SuiteRng::Hash(b.reseed_from_rng(rng)),
+            #[cfg(feature = "blake3-transcripts")]
+            SuiteRngBuilder::Blake3(b) => SuiteRng::Hash(b.reseed_from_rng(rng)),
+        }
+    }
+}
+
+impl RngCore for SuiteRng {
+    fn next_u32(&mut self) -> u32 {
+        match *self {
+            SuiteRng::Merlin(ref mut r) => r.next_u32(),
+            SuiteRng::Hash(ref mut r)   => r.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match *self {
+            SuiteRng::Merlin(ref mut r) => r.next_u64(),
+            SuiteRng::Hash(ref mut r)   => r.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match *self {
+            SuiteRng::Merlin(ref mut r) => r.fill_bytes(dest),
+            SuiteRng::Hash(ref mut r)   => r.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ::rand_core::Error> {
+        match *self {
+            SuiteRng::Merlin(ref mut r) => r.try_fill_bytes(dest),
+            SuiteRng::Hash(ref mut r)   => r.try_fill_bytes(dest),
+        }
+    }
+}
+
+impl CryptoRng for SuiteRng { }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    use envelope::Suite;
+    use nonces::Ephemeral;
+    use proofs::attribute_opening;
+
+    #[test]
+    fn hash_transcripts_frame_their_input() {
+        let mut a: HashTranscript<Sha512> = HashTranscript::new(b"aeonflux test");
+        let mut b: HashTranscript<Sha512> = HashTranscript::new(b"aeonflux test");
+        let mut x = [0u8; 100];
+        let mut y = [0u8; 100];
+
+        a.commit_bytes(b"ab", b"c");
+        b.commit_bytes(b"a", b"bc");
+        a.challenge_bytes(b"chal", &mut x);
+        b.challenge_bytes(b"chal", &mut y);
+
+        assert!(&x[..] != &y[..]);
+
+        // Successive challenges differ, and are reproducible.
+        let mut c: HashTranscript<Sha512> = HashTranscript::new(b"aeonflux test");
+        let mut z = [0u8; 100];
+
+        c.commit_bytes(b"ab", b"c");
+        c.challenge_bytes(b"chal", &mut z);
+        assert!(&x[..] == &z[..]);
+
+        a.challenge_bytes(b"chal", &mut x);
+        assert!(&x[..] != &z[..]);
+    }
+
+    #[test]
+    fn proofs_verify_only_under_their_suite() {
+        let mut rng = thread_rng();
+        let z0 = Ephemeral::new(&mut rng);
+        let A: RistrettoPoint = RistrettoPoint::random(&mut rng);
+        let W: RistrettoPoint = A * z0.as_scalar();
+        let publics = attribute_opening::Publics { A: &A, W: &W };
+        let secrets = attribute_opening::Secrets { z0: z0.as_witness() };
+        let suites = [Suite::Ristretto255Sha512MacGgm, Suite::Ristretto255Sha512HashTranscriptMacGgm];
+
+        for suite in suites.iter() {
+            let proof = attribute_opening::Proof::create(&mut suite.transcript(b"aeonflux test"), publics, secrets);
+
+            for other in suites.iter() {
+                let verified = proof.verify(&mut other.transcript(b"aeonflux test"), publics).is_ok();
+
+                assert!(verified == (suite == other));
+            }
+        }
+
+        // A generic proof works over any transcript directly, too.
+        let mut transcript: HashTranscript<Sha512> = HashTranscript::new(b"aeonflux test");
+        let proof = attribute_opening::Proof::create(&mut transcript, publics, secrets);
+        let W2: RistrettoPoint = RISTRETTO_BASEPOINT_POINT * Scalar::from(2u64);
+        let wrong = attribute_opening::Publics { A: &A, W: &W2 };
+
+        assert!(proof.verify(&mut HashTranscript::<Sha512>::new(b"aeonflux test"), publics).is_ok());
+        assert!(proof.verify(&mut HashTranscript::<Sha512>::new(b"aeonflux test"), wrong).is_err());
+    }
+}
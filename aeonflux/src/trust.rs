@@ -0,0 +1,555 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A registry of trusted issuers, for gates which accept credentials from
+//! several federated issuers.
+//!
+//! A `TrustRegistry` maps issuer IDs to the parameters a gate should accept
+//! from that issuer, the range of epochs in which it is trusted, and a
+//! `TrustPolicy` restricting what it is trusted for.  The registry has a
+//! canonical encoding, so that it may be signed by whoever administers the
+//! federation with a `RegistryKeypair`, distributed to gates, and audited.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use errors::Error;
+use errors::TrustError;
+use issuer::IssuerParameters;
+use parameters::SIZEOF_SYSTEM_PARAMETERS;
+use parameters::SystemParameters;
+use secret::Clear;
+use secret::Secret;
+
+/// The maximum length of an issuer ID, or of a scope in a `TrustPolicy`, in bytes.
+pub const MAXIMUM_SIZEOF_ISSUER_ID: usize = 255;
+
+pub const SIZEOF_REGISTRY_SIGNATURE: usize = 64;
+
+/// A cursor for decoding the canonical encoding.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TrustError> {
+        if self.bytes.len() < n {
+            return Err(TrustError::WrongNumberOfBytes);
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, TrustError> {
+        let mut x: u64 = 0;
+
+        for (i, byte) in self.take(n)?.iter().enumerate() {
+            x |= (*byte as u64) << (8 * i);
+        }
+        Ok(x)
+    }
+}
+
+fn push_uint(v: &mut Vec<u8>, x: u64, n: usize) {
+    for i in 0..n {
+        v.push((x >> (8 * i)) as u8);
+    }
+}
+
+/// What a trusted issuer's credentials may be accepted for.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrustPolicy {
+    /// The scopes, e.g. gate or venue names, for which this issuer's
+    /// credentials are accepted.  If empty, they are accepted for any scope.
+    pub scopes: Vec<Vec<u8>>,
+}
+
+impl TrustPolicy {
+    /// Whether this policy permits a `scope`.
+    pub fn permits(&self, scope: &[u8]) -> bool {
+        self.scopes.len() == 0 || self.scopes.iter().any(|x| &x[..] == scope)
+    }
+}
+
+/// An issuer which is trusted by a `TrustRegistry`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrustedIssuer {
+    /// A unique, human-meaningful identifier for the issuer.
+    pub id: Vec<u8>,
+    pub system_parameters: SystemParameters,
+    pub issuer_parameters: IssuerParameters,
+    /// The first epoch in which this issuer is trusted.
+    pub first_epoch: u64,
+    /// The last epoch in which this issuer is trusted.
+    pub last_epoch: u64,
+    pub policy: TrustPolicy,
+}
+
+impl TrustedIssuer {
+    fn decode(reader: &mut Reader) -> Result<TrustedIssuer, TrustError> {
+        let id_length = reader.uint(1)? as usize;
+        let id: Vec<u8> = reader.take(id_length)?.to_vec();
+        let system_parameters = SystemParameters::from_bytes(reader.take(SIZEOF_SYSTEM_PARAMETERS)?)?;
+        let issuer_parameters_length = reader.uint(2)? as usize;
+        let issuer_parameters = IssuerParameters::from_bytes(reader.take(issuer_parameters_length)?)?;
+        let first_epoch = reader.uint(8)?;
+        let last_epoch = reader.uint(8)?;
+        let number_of_scopes = reader.uint(1)? as usize;
+        let mut scopes: Vec<Vec<u8>> = Vec::with_capacity(number_of_scopes);
+
+        for _ in 0..number_of_scopes {
+            let scope_length = reader.uint(1)? as usize;
+
+            scopes.push(reader.take(scope_length)?.to_vec());
+        }
+
+        if id.len() == 0 {
+            return Err(TrustError::InvalidIssuerId);
+        }
+
+        Ok(TrustedIssuer {
+            id, system_parameters, issuer_parameters, first_epoch, last_epoch,
+            policy: TrustPolicy { scopes },
+        })
+    }
+
+    fn encode(&self, v: &mut Vec<u8>) {
+        let issuer_parameters: Vec<u8> = self.issuer_parameters.to_bytes();
+
+        v.push(self.id.len() as u8);
+        v.extend(self.id.iter());
+        v.extend(self.system_parameters.to_bytes());
+        push_uint(v, issuer_parameters.len() as u64, 2);
+        v.extend(issuer_parameters);
+        push_uint(v, self.first_epoch, 8);
+        push_uint(v, self.last_epoch, 8);
+        v.push(self.policy.scopes.len() as u8);
+
+        for scope in self.policy.scopes.iter() {
+            v.push(scope.len() as u8);
+            v.extend(scope.iter());
+        }
+    }
+}
+
+/// A versioned set of trusted issuers, kept sorted by issuer ID so that its
+/// encoding is canonical.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrustRegistry {
+    /// A version number, which should be incremented on every change so
+    /// that gates can refuse to roll back to an older registry.
+    pub version: u64,
+    issuers: Vec<TrustedIssuer>,
+}
+
+impl TrustRegistry {
+    pub fn from_bytes(bytes: &[u8]) -> Result<TrustRegistry, TrustError> {
+        let mut reader = Reader { bytes };
+        let version = reader.uint(8)?;
+        let number_of_issuers = reader.uint(2)? as usize;
+        let mut registry = TrustRegistry { version, issuers: Vec::with_capacity(number_of_issuers) };
+
+        for _ in 0..number_of_issuers {
+            let issuer = TrustedIssuer::decode(&mut reader)?;
+
+            // Reject non-canonical encodings, with unsorted or duplicate IDs.
+            if let Some(previous) = registry.issuers.last() {
+                if previous.id >= issuer.id {
+                    return Err(TrustError::InvalidIssuerId);
+                }
+            }
+            registry.issuers.push(issuer);
+        }
+
+        if reader.bytes.len() != 0 {
+            return Err(TrustError::WrongNumberOfBytes);
+        }
+
+        Ok(registry)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        push_uint(&mut v, self.version, 8);
+        push_uint(&mut v, self.issuers.len() as u64, 2);
+
+        for issuer in self.issuers.iter() {
+            issuer.encode(&mut v);
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(TrustRegistry, "A valid byte sequence representing a TrustRegistry");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(TrustRegistry);
+impl_try_from_bytes!(TrustRegistry, TrustError);
+
+impl TrustRegistry {
+    pub fn new(version: u64) -> TrustRegistry {
+        TrustRegistry { version, issuers: Vec::new() }
+    }
+
+    /// Add a trusted issuer, replacing any existing issuer with the same ID.
+    ///
+    /// # Errors
+    ///
+    /// * `TrustError::InvalidIssuerId` if the issuer's ID, or any scope in its
+    ///   policy, is empty or longer than `MAXIMUM_SIZEOF_ISSUER_ID`, or if its
+    ///   policy has more than 255 scopes.
+    pub fn insert(&mut self, issuer: TrustedIssuer) -> Result<(), Error> {
+        if issuer.id.len() == 0 || issuer.id.len() > MAXIMUM_SIZEOF_ISSUER_ID {
+            return Err(TrustError::InvalidIssuerId.into());
+        }
+        if issuer.policy.scopes.len() > 255 {
+            return Err(TrustError::InvalidIssuerId.into());
+        }
+        for scope in issuer.policy.scopes.iter() {
+            if scope.len() == 0 || scope.len() > MAXIMUM_SIZEOF_ISSUER_ID {
+                return Err(TrustError::InvalidIssuerId.into());
+            }
+        }
+
+        match self.issuers.binary_search_by(|x| x.id.cmp(&issuer.id)) {
+            Ok(index)  => self.issuers[index] = issuer,
+            Err(index) => self.issuers.insert(index, issuer),
+        }
+        Ok(())
+    }
+
+    /// Remove the issuer with some `id`, returning it if it was present.
+    pub fn remove(&mut self, id: &[u8]) -> Option<TrustedIssuer> {
+        match self.issuers.binary_search_by(|x| x.id[..].cmp(id)) {
+            Ok(index) => Some(self.issuers.remove(index)),
+            Err(_)    => None,
+        }
+    }
+
+    pub fn get(&self, id: &[u8]) -> Option<&TrustedIssuer> {
+        match self.issuers.binary_search_by(|x| x.id[..].cmp(id)) {
+            Ok(index) => Some(&self.issuers[index]),
+            Err(_)    => None,
+        }
+    }
+
+    /// Iterate over the trusted issuers, in order of their IDs.
+    pub fn iter(&self) -> ::core::slice::Iter<TrustedIssuer> {
+        self.issuers.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.issuers.len()
+    }
+
+    /// Determine whether credentials from the issuer with some `id` should be
+    /// accepted in an `epoch` for a `scope`.
+    ///
+    /// # Returns
+    ///
+    /// The `TrustedIssuer`, whose parameters should be used to handle the
+    /// credential.
+    pub fn accepts(&self, id: &[u8], epoch: u64, scope: &[u8]) -> Result<&TrustedIssuer, Error> {
+        let issuer: &TrustedIssuer = match self.get(id) {
+            Some(x) => x,
+            None    => return Err(TrustError::UnknownIssuer.into()),
+        };
+
+        if epoch < issuer.first_epoch || epoch > issuer.last_epoch {
+            return Err(TrustError::EpochNotAccepted.into());
+        }
+        if !issuer.policy.permits(scope) {
+            return Err(TrustError::ScopeNotAccepted.into());
+        }
+        Ok(issuer)
+    }
+
+    /// Sign this registry with the federation's `keypair`.
+    pub fn sign<R>(self, keypair: &RegistryKeypair, csprng: &mut R) -> SignedTrustRegistry
+    where
+        R: RngCore + CryptoRng,
+    {
+        let signature = keypair.sign(REGISTRY_SIGNATURE_LABEL, &self.to_bytes(), csprng);
+
+        SignedTrustRegistry { registry: self, signature }
+    }
+}
+
+/// The transcript label for signatures over a `TrustRegistry`.
+const REGISTRY_SIGNATURE_LABEL: &'static [u8] = b"AEONFLUX TRUST REGISTRY";
+
+/// Produce the challenge for a signature made under some transcript `label`.
+fn signature_challenge(label: &'static [u8], public: &RistrettoPoint, message: &[u8], R: &RistrettoPoint) -> Scalar {
+    let mut transcript = Transcript::new(label);
+    let mut bytes = [0u8; 64];
+
+    transcript.commit_bytes(b"X", public.compress().as_bytes());
+    transcript.commit_bytes(b"registry", message);
+    transcript.commit_bytes(b"com X", R.compress().as_bytes());
+    transcript.challenge_bytes(b"chal", &mut bytes);
+
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// A Schnorr signature over a `TrustRegistry`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RegistrySignature {
+    challenge: Scalar,
+    response: Scalar,
+}
+
+impl RegistrySignature {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RegistrySignature, TrustError> {
+        if bytes.len() != SIZEOF_REGISTRY_SIGNATURE {
+            return Err(TrustError::WrongNumberOfBytes);
+        }
+
+        let mut challenge = [0u8; 32];
+        let mut response = [0u8; 32];
+
+        challenge.copy_from_slice(&bytes[00..32]);
+        response.copy_from_slice(&bytes[32..64]);
+
+        match (Scalar::from_canonical_bytes(challenge), Scalar::from_canonical_bytes(response)) {
+            (Some(c), Some(s)) => Ok(RegistrySignature { challenge: c, response: s }),
+            _                  => Err(TrustError::ScalarFormatError),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_REGISTRY_SIGNATURE);
+
+        v.extend(self.challenge.as_bytes());
+        v.extend(self.response.as_bytes());
+        v
+    }
+
+    /// Check that this is a signature by `public` over `message` under some
+    /// transcript `label`.
+    pub(crate) fn verify(&self, label: &'static [u8], public: &RistrettoPoint, message: &[u8]) -> bool {
+        // Everything here is public, so we may use variable-time arithmetic.
+        let R: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(
+            &[self.response, -self.challenge],
+            &[RISTRETTO_BASEPOINT_POINT, *public]);
+
+        signature_challenge(label, public, message, &R) == self.challenge
+    }
+}
+
+impl_try_from_bytes!(RegistrySignature, TrustError);
+
+/// The key pair which a federation administrator uses to sign registries.
+#[derive(Clone, Debug)]
+pub struct RegistryKeypair {
+    secret: Secret<Scalar>,
+    pub public: RistrettoPoint,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for RegistryKeypair {
+    fn drop(&mut self) {
+        self.secret.clear();
+    }
+}
+
+impl RegistryKeypair {
+    pub fn generate<R>(csprng: &mut R) -> RegistryKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret: Scalar = Scalar::random(csprng);
+        let public: RistrettoPoint = &secret * &RISTRETTO_BASEPOINT_TABLE;
+
+        RegistryKeypair { secret: Secret::new(secret), public }
+    }
+
+    /// The secret key, for binding credentials to it in `dual_key`.
+    pub(crate) fn secret(&self) -> &Scalar {
+        self.secret.expose_secret()
+    }
+
+    /// Sign a `message` under some transcript `label`, which must differ for
+    /// each kind of message this key signs.
+    pub(crate) fn sign<R>(&self, label: &'static [u8], message: &[u8], csprng: &mut R) -> RegistrySignature
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut transcript = Transcript::new(label);
+        let mut rng = transcript.fork_transcript()
+            .commit_witness_bytes(b"x", self.secret.expose_secret().as_bytes())
+            .reseed_from_rng(csprng);
+
+        let mut r: Scalar = Scalar::random(&mut rng);
+        let R: RistrettoPoint = &r * &RISTRETTO_BASEPOINT_TABLE;
+        let challenge: Scalar = signature_challenge(label, &self.public, message, &R);
+        let response: Scalar = &(&challenge * self.secret.expose_secret()) + &r;
+
+        r.clear();
+
+        RegistrySignature { challenge, response }
+    }
+}
+
+/// A `TrustRegistry` along with a signature over its canonical encoding.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedTrustRegistry {
+    registry: TrustRegistry,
+    pub signature: RegistrySignature,
+}
+
+impl SignedTrustRegistry {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignedTrustRegistry, TrustError> {
+        if bytes.len() < SIZEOF_REGISTRY_SIGNATURE {
+            return Err(TrustError::WrongNumberOfBytes);
+        }
+
+        let split: usize = bytes.len() - SIZEOF_REGISTRY_SIGNATURE;
+        let registry = TrustRegistry::from_bytes(&bytes[..split])?;
+        let signature = RegistrySignature::from_bytes(&bytes[split..])?;
+
+        Ok(SignedTrustRegistry { registry, signature })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = self.registry.to_bytes();
+
+        v.extend(self.signature.to_bytes());
+        v
+    }
+
+    /// Verify the signature with the federation's `public` key.
+    ///
+    /// # Returns
+    ///
+    /// The signed `TrustRegistry`.
+    pub fn verify(&self, public: &RistrettoPoint) -> Result<&TrustRegistry, Error> {
+        if self.signature.verify(REGISTRY_SIGNATURE_LABEL, public, &self.registry.to_bytes()) {
+            Ok(&self.registry)
+        } else {
+            Err(TrustError::VerificationFailure.into())
+        }
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SignedTrustRegistry,
+                                         "A valid byte sequence representing a SignedTrustRegistry");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SignedTrustRegistry);
+impl_try_from_bytes!(SignedTrustRegistry, TrustError);
+
+/// Decode a registry signing key's public half.
+pub fn registry_public_key_from_bytes(bytes: &[u8]) -> Result<RistrettoPoint, TrustError> {
+    if bytes.len() != 32 {
+        return Err(TrustError::WrongNumberOfBytes);
+    }
+
+    Ok(CompressedRistretto::from_slice(bytes).decompress()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use issuer::Issuer;
+    use test_utils::H;
+
+    fn trusted(id: &[u8], scopes: Vec<Vec<u8>>) -> TrustedIssuer {
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut thread_rng());
+
+        TrustedIssuer {
+            id: id.to_vec(),
+            system_parameters: system_parameters,
+            issuer_parameters: issuer.get_issuer_parameters(),
+            first_epoch: 10,
+            last_epoch: 20,
+            policy: TrustPolicy { scopes },
+        }
+    }
+
+    fn registry() -> TrustRegistry {
+        let mut registry = TrustRegistry::new(1);
+
+        registry.insert(trusted(b"westminster", vec![b"north gate".to_vec()])).unwrap();
+        registry.insert(trusted(b"camden", Vec::new())).unwrap();
+        registry
+    }
+
+    #[test]
+    fn registry_roundtrip_is_canonical() {
+        let registry = registry();
+        let bytes = registry.to_bytes();
+
+        assert!(registry.iter().next().unwrap().id == b"camden".to_vec());
+        assert!(TrustRegistry::from_bytes(&bytes).unwrap() == registry);
+        assert!(TrustRegistry::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn registry_accepts() {
+        let registry = registry();
+
+        assert!(registry.accepts(b"camden", 10, b"anywhere").is_ok());
+        assert!(registry.accepts(b"westminster", 20, b"north gate").is_ok());
+        assert!(registry.accepts(b"westminster", 20, b"south gate") == Err(TrustError::ScopeNotAccepted.into()));
+        assert!(registry.accepts(b"camden", 21, b"anywhere") == Err(TrustError::EpochNotAccepted.into()));
+        assert!(registry.accepts(b"hackney", 15, b"anywhere") == Err(TrustError::UnknownIssuer.into()));
+    }
+
+    #[test]
+    fn signed_registry() {
+        let mut rng = thread_rng();
+        let keypair = RegistryKeypair::generate(&mut rng);
+        let other = RegistryKeypair::generate(&mut rng);
+        let signed = registry().sign(&keypair, &mut rng);
+        let signed = SignedTrustRegistry::from_bytes(&signed.to_bytes()).unwrap();
+
+        assert!(signed.verify(&keypair.public).unwrap().len() == 2);
+        assert!(signed.verify(&other.public) == Err(TrustError::VerificationFailure.into()));
+
+        let mut tampered = signed.clone();
+
+        tampered.registry.remove(b"westminster");
+
+        assert!(tampered.verify(&keypair.public) == Err(TrustError::VerificationFailure.into()));
+    }
+
+    #[test]
+    fn insert_replaces_and_validates() {
+        let mut registry = registry();
+
+        registry.insert(trusted(b"camden", vec![b"east gate".to_vec()])).unwrap();
+
+        assert!(registry.len() == 2);
+        assert!(registry.get(b"camden").unwrap().policy.permits(b"east gate"));
+        assert!(registry.insert(trusted(b"", Vec::new())) == Err(TrustError::InvalidIssuerId.into()));
+    }
+}
@@ -16,6 +16,7 @@ use alloc::vec::Vec;
 use std::vec::Vec;
 
 use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::Identity;
 
 use merlin::Transcript;
@@ -26,21 +27,37 @@ use rand_core::CryptoRng;
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
+use zeroize::Zeroize;
+
 use amacs;
+use channel_binding::ChannelBinding;
+use channel_binding::bind_channel;
+use ciphertexts;
+use credential::BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES;
 use credential::Credential;
+use credential::CredentialBlindIssuance;
+use credential::CredentialBlindRequest;
 use credential::CredentialIssuance;
 use credential::CredentialPresentation;
+use credential::PresentationOptions;
 use credential::CredentialRequest;
+use credential::EncryptedAttribute;
 use credential::RevealedAttribute;
 use elgamal;
 use errors::CredentialError;
+use errors::Error;
+use freshness::bind_timestamp;
 use issuer::IssuerParameters;
 use nonces::Ephemeral;
 use nonces::Nonces;
 use parameters::SystemParameters;
 use pedersen;
+use precomputed::PrecomputedIssuerParameters;
+use proofs::issuance_blinded;
 use proofs::issuance_revealed;
 use proofs::valid_credential;
+use schema::bind_schema;
+use schema::SchemaId;
 
 /// DOCDOC
 #[derive(Debug, Eq, PartialEq)]
@@ -49,6 +66,8 @@ pub struct User {
     pub issuer_parameters: IssuerParameters,
     pub key: Option<elgamal::Keypair>,
     pub credential: Option<Credential>,
+    /// Basepoint tables for the system and issuer parameters.
+    pub precomputed: PrecomputedIssuerParameters,
 }
 
 impl User {
@@ -76,28 +95,43 @@ impl User {
             credential = Some(Credential::from_bytes(&bytes[160..])?);
         }
 
-        Ok(User {
-            system_parameters,
-            issuer_parameters,
-            key,
-            credential,
-        })
+        let mut user = User::new(system_parameters, issuer_parameters, key);
+
+        user.credential = credential;
+
+        Ok(user)
     }
 
+    /// Encode this user, including their secret key and attributes.
+    ///
+    /// The encoding is written into a buffer of its final size, and the
+    /// intermediate encodings of the key and credential are wiped, so that
+    /// the only copy of either is in the returned bytes, which the caller
+    /// must wipe in turn.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut v: Vec<u8> = Vec::new();
+        let mut v: Vec<u8> = Vec::with_capacity(256);
 
         v.extend(self.system_parameters.to_bytes());
         v.extend(self.issuer_parameters.to_bytes());
 
         match self.key {
             None        => v.extend([0u8; 64].iter()),
-            Some(ref x) => v.extend(x.to_bytes().iter()),
+            Some(ref x) => {
+                let mut key: Vec<u8> = x.to_bytes();
+
+                v.extend(key.iter());
+                key.zeroize();
+            },
         }
 
         match self.credential {
             None        => v.extend([0u8; 96].iter()),
-            Some(ref x) => v.extend(x.to_bytes().iter()),
+            Some(ref x) => {
+                let mut credential: Vec<u8> = x.to_bytes();
+
+                v.extend(credential.iter());
+                credential.zeroize();
+            },
         }
 
         v
@@ -105,6 +139,9 @@ impl User {
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(User, "A valid byte sequence representing a User");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(User);
+impl_try_from_bytes!(User, CredentialError);
 
 impl User {
     /// DOCDOC
@@ -114,11 +151,14 @@ impl User {
         key: Option<elgamal::Keypair>,
     ) -> User
     {
+        let precomputed = PrecomputedIssuerParameters::new(&system_parameters, &issuer_parameters);
+
         User {
             system_parameters: system_parameters,
             issuer_parameters: issuer_parameters,
             key: key,
             credential: None,
+            precomputed: precomputed,
         }
     }
 
@@ -140,16 +180,42 @@ impl User {
     pub fn obtain_finish(
         &mut self,
         issuance: Option<&CredentialIssuance>,
-    ) -> Result<(), CredentialError>
+    ) -> Result<(), Error>
+    {
+        self.obtain_finish_under(issuance, None)
+    }
+
+    /// As `User::obtain_finish()`, for an issuance made with
+    /// `Issuer::issue_with_schema()` under a `schema`.
+    ///
+    /// The issuance proof only verifies for the schema it was made under.
+    pub fn obtain_finish_with_schema(
+        &mut self,
+        issuance: Option<&CredentialIssuance>,
+        schema: &SchemaId,
+    ) -> Result<(), Error>
+    {
+        self.obtain_finish_under(issuance, Some(schema))
+    }
+
+    fn obtain_finish_under(
+        &mut self,
+        issuance: Option<&CredentialIssuance>,
+        schema: Option<&SchemaId>,
+    ) -> Result<(), Error>
     {
+        trace_span!("obtain_finish", schema = schema.is_some());
+
         let mut transcript = Transcript::new(b"AEONFLUX ISSUANCE");
 
+        bind_schema(&mut transcript, schema);
+
         let issue: &CredentialIssuance = match issuance {
             Some(i) => i,
-            None    => return Err(CredentialError::CredentialIssuance),
+            None    => return Err(CredentialError::CredentialIssuance.into()),
         };
         let X1: RistrettoPoint = match self.issuer_parameters.Xn.get(0) {
-            None => return Err(CredentialError::NoIssuerParameters),
+            None => return Err(CredentialError::NoIssuerParameters.into()),
             Some(x) => *x,
         };
 
@@ -163,7 +229,7 @@ impl User {
         };
 
         if issue.proof.verify(&mut transcript, publics).is_err() {
-            Err(CredentialError::CredentialIssuance)
+            Err(CredentialError::CredentialIssuance.into())
         } else {
             self.credential = Some(issue.credential.clone());
 
@@ -177,16 +243,127 @@ impl User {
         &self,
         nonces: &Nonces,
         rng: &mut R,
-    ) -> Result<CredentialPresentation, CredentialError>
+    ) -> Result<CredentialPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.show_with(&PresentationOptions::new(), nonces, rng)?.0)
+    }
+
+    /// As `User::show()`, for a credential obtained under a `schema`, which
+    /// must be verified with `Issuer::verify_with_schema()`.
+    pub fn show_with_schema<R>(
+        &self,
+        schema: &SchemaId,
+        nonces: &Nonces,
+        rng: &mut R,
+    ) -> Result<CredentialPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.show_with(&PresentationOptions::new().schema(schema), nonces, rng)?.0)
+    }
+
+    /// As `User::show()`, but also produce a tag, \\( N = m_0 \cdot H \\), on
+    /// the hidden attribute for some scoped `basepoint` \\( H \\), e.g. a
+    /// nullifier, which must be verified with `Issuer::verify_scoped()`.
+    ///
+    /// The presentation's proof covers the tag under the same challenge, and
+    /// reuses its response for the hidden attribute, so the presentation is
+    /// no larger than one without a tag.  Other statements, such as range or
+    /// membership proofs, are not covered, and keep their own proofs.
+    ///
+    /// # Returns
+    ///
+    /// The presentation, and the tag it is bound to.
+    pub fn show_scoped<R>(
+        &self,
+        basepoint: &RistrettoPoint,
+        nonces: &Nonces,
+        rng: &mut R,
+    ) -> Result<(CredentialPresentation, RistrettoPoint), Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        match self.show_with(&PresentationOptions::new().scoped(basepoint), nonces, rng)? {
+            (presentation, Some(N)) => Ok((presentation, N)),
+            (_, None)               => Err(CredentialError::MissingData.into()),
+        }
+    }
+
+    /// As `User::show()`, but bind the presentation to a `timestamp`, in
+    /// seconds since the Unix epoch, which must be verified with
+    /// `Issuer::verify_at()`.
+    ///
+    /// See the `freshness` module for presenting with a `Clock`.
+    pub fn show_at<R>(
+        &self,
+        timestamp: u64,
+        nonces: &Nonces,
+        rng: &mut R,
+    ) -> Result<CredentialPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.show_with(&PresentationOptions::new().at(timestamp), nonces, rng)?.0)
+    }
+
+    /// As `User::show()`, but bind the presentation to the connection it is
+    /// sent over, by way of its `channel` binding, e.g. a TLS exporter value,
+    /// so that it must be verified with `Issuer::verify_bound()` for the same
+    /// connection, and cannot be replayed over another.
+    pub fn show_bound<R>(
+        &self,
+        channel: &ChannelBinding,
+        nonces: &Nonces,
+        rng: &mut R,
+    ) -> Result<CredentialPresentation, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        Ok(self.show_with(&PresentationOptions::new().bound(channel), nonces, rng)?.0)
+    }
+
+    /// As `User::show()`, with any combination of `options`, which must be
+    /// verified with `Issuer::verify_with()` under the same options.
+    ///
+    /// # Returns
+    ///
+    /// The presentation, and, if the `options` are `scoped()`, the tag it is
+    /// bound to.
+    pub fn show_with<R>(
+        &self,
+        options: &PresentationOptions,
+        nonces: &Nonces,
+        rng: &mut R,
+    ) -> Result<(CredentialPresentation, Option<RistrettoPoint>), Error>
     where
         R: RngCore + CryptoRng,
     {
+        trace_span!("show",
+                    schema = options.schema.is_some(),
+                    scoped = options.scope.is_some(),
+                    fresh = options.timestamp.is_some(),
+                    bound = options.channel.is_some());
+
         let credential: &Credential = match self.credential {
             Some(ref x) => x,
-            None        => return Err(CredentialError::MissingData),
+            None        => return Err(CredentialError::MissingData.into()),
         };
 
+        if credential.attributes.len() == 0 ||
+           nonces.iter().count() != credential.attributes.len() ||
+           self.issuer_parameters.Xn.len() < credential.attributes.len()
+        {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
         let mut transcript = Transcript::new(b"AEONFLUX SHOW");
+
+        bind_schema(&mut transcript, options.schema);
+        bind_timestamp(&mut transcript, options.timestamp);
+        bind_channel(&mut transcript, options.channel);
+
         let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
 
         const N_ATTRIBUTES: usize = 1;
@@ -203,13 +380,13 @@ impl User {
 
         // Commit to the rerandomised aMAC.
         let zQ: Ephemeral = Ephemeral::new(&mut csprng);
-        let CQ: pedersen::Commitment = pedersen::Commitment::to(&Q, &zQ, &A);
+        let CQ: pedersen::Commitment = pedersen::Commitment::to_precomputed(&Q, &zQ, &A, &self.precomputed);
 
         // Commit to the hidden attributes.
         let mut commitments: Vec<pedersen::Commitment> = Vec::with_capacity(N_ATTRIBUTES);
 
         for (zi, mi) in nonces.iter().zip(credential.attributes.iter()) {
-            let Cmi: pedersen::Commitment = pedersen::Commitment::to(&(mi * P), zi, &A);
+            let Cmi: pedersen::Commitment = pedersen::Commitment::to_precomputed(&(mi * P), zi, &A, &self.precomputed);
 
             commitments.push(Cmi);
         }
@@ -218,16 +395,16 @@ impl User {
         let mut V: RistrettoPoint = RistrettoPoint::identity();
 
         for (index, zi) in nonces.iter().enumerate() {
-            V += &(zi * self.issuer_parameters.Xn[index]);
+            V += self.precomputed.mul(&self.issuer_parameters.Xn[index], zi.as_scalar());
         }
-        V -= &zQ * &A;
+        V -= self.precomputed.mul(&A, zQ.as_scalar());
 
         let minus_zQ = -zQ;
 
         let valid_credential_secrets = valid_credential::Secrets {
             m0: &credential.attributes[0],
-            z0: (&nonces[0]).into(),
-            minus_zQ: (&minus_zQ).into(),
+            z0: nonces[0].as_witness(),
+            minus_zQ: minus_zQ.as_witness(),
         };
         let valid_credential_publics = valid_credential::Publics {
             B: &B,
@@ -237,32 +414,197 @@ impl User {
             V: &V,
             Cm0: &commitments[0].into(),
         };
-        let valid_credential_proof = valid_credential::Proof::create(&mut transcript,
-                                                                     valid_credential_publics,
-                                                                     valid_credential_secrets);
+        let tag: Option<RistrettoPoint> = options.scope.map(|basepoint| basepoint * &credential.attributes[0]);
+        let valid_credential_proof = match (options.scope, tag.as_ref()) {
+            (Some(basepoint), Some(N)) => {
+                valid_credential::Proof::create_scoped(&mut transcript,
+                                                       valid_credential_publics,
+                                                       valid_credential::ScopedPublics { Hn: basepoint, N: N },
+                                                       valid_credential_secrets)
+            },
+            _ => valid_credential::Proof::create(&mut transcript,
+                                                 valid_credential_publics,
+                                                 valid_credential_secrets),
+        };
 
-        Ok(CredentialPresentation {
+        Ok((CredentialPresentation {
             proof: valid_credential_proof,
             rerandomized_mac_commitment: CQ,
             rerandomized_nonce: rerandomized_mac.nonce,
             attributes_revealed: Vec::with_capacity(0),
             attributes_blinded: commitments,
-        })
+        }, tag))
     }
 }
 
 impl User {
-    pub fn blind_request<C>(
+    /// Request a credential over some attributes without revealing them to the
+    /// issuer.
+    ///
+    /// If this `User` does not yet have an elGamal `key`, one is generated.
+    /// Each attribute is encrypted to the user's public key, and a proof is
+    /// created that the ciphertexts are well-formed.
+    ///
+    /// # Note
+    ///
+    /// The plaintext `attributes` are not stored in the request, and must be
+    /// passed to `User::blind_obtain_finish()` again once the issuer responds.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::WrongNumberOfAttributes` if `attributes` does not
+    ///   contain exactly `BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES`
+    ///   attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `CredentialBlindRequest` for sending to the issuer.
+    pub fn blind_obtain<R>(
+        &mut self,
+        attributes: &[RevealedAttribute],
+        rng: &mut R,
+    ) -> Result<CredentialBlindRequest, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        trace_span!("blind_obtain", attributes = attributes.len());
+
+        let request = self.blind_obtain_with_ephemerals(attributes, rng).map(|(request, _ephemerals)| request);
+
+        trace_result!(request, encoded);
+
+        request
+    }
+
+    /// As `User::blind_obtain()`, but also return the `Nonces` used to encrypt
+    /// each attribute, for use in further proofs about the ciphertexts.
+    pub(crate) fn blind_obtain_with_ephemerals<R>(
         &mut self,
-        csprng: &mut C,
-    ) -> CredentialBlindRequest
+        attributes: &[RevealedAttribute],
+        rng: &mut R,
+    ) -> Result<(CredentialBlindRequest, Nonces), Error>
     where
-        C: CryptoRng + RngCore,
+        R: RngCore + CryptoRng,
     {
+        if attributes.len() != BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        let mut transcript = Transcript::new(b"AEONFLUX BLIND REQUEST");
+        let mut csprng = transcript.fork_transcript().reseed_from_rng(rng);
+
         if self.key.is_none() {
-            self.key = elgamal::Keypair::generate::<C>(&mut csprng);
+            self.key = Some(elgamal::Keypair::generate(&mut csprng));
+        }
+
+        let key: &elgamal::Keypair = self.key.as_ref()?;
+
+        // Encrypt the attributes to our own public key, and prove that every
+        // ciphertext is well-formed with a single aggregated proof.
+        let (encryptions, plaintexts) = key.public.encrypt_attributes(attributes, &mut csprng);
+        let proof = ciphertexts::prove(&mut transcript, &self.system_parameters.g, key, &encryptions, &plaintexts)?;
+        let request = CredentialBlindRequest {
+            attributes_revealed: None,
+            attributes_blinded: Some(encryptions),
+            attributes_blinded_proof: Some(proof),
+            public_key: key.public,
+        };
+
+        Ok((request, plaintexts.nonces))
+    }
+
+    /// Verify a blinded issuance and, if valid, decrypt and store the
+    /// resulting `Credential`.
+    ///
+    /// # Inputs
+    ///
+    /// * `issuance` is the issuer's response to our `CredentialBlindRequest`.
+    /// * `attributes` are the same plaintext attributes which were passed to
+    ///   `User::blind_obtain()`.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::CredentialIssuance` if there was no `issuance`, or
+    ///   its proof did not verify.
+    /// * `CredentialError::NoIssuerParameters` if the issuer parameters were
+    ///   not the correct length.
+    /// * `CredentialError::WrongNumberOfAttributes` if the `issuance` was not
+    ///   computed over exactly the given `attributes`.
+    /// * `CredentialError::MissingData` if this `User` has no elGamal `key`.
+    pub fn blind_obtain_finish(
+        &mut self,
+        issuance: Option<&CredentialBlindIssuance>,
+        attributes: &[RevealedAttribute],
+    ) -> Result<(), Error>
+    {
+        trace_span!("blind_obtain_finish", attributes = attributes.len());
+
+        let mut transcript = Transcript::new(b"AEONFLUX BLIND ISSUANCE");
+
+        let issue: &CredentialBlindIssuance = match issuance {
+            Some(i) => i,
+            None    => return Err(CredentialError::CredentialIssuance.into()),
+        };
+        let X1: RistrettoPoint = match self.issuer_parameters.Xn.get(0) {
+            None => return Err(CredentialError::NoIssuerParameters.into()),
+            Some(x) => *x,
+        };
+        let key: &elgamal::Keypair = match self.key {
+            Some(ref x) => x,
+            None        => return Err(CredentialError::MissingData.into()),
+        };
+
+        if attributes.len() != BLIND_ISSUANCE_NUMBER_OF_BLINDED_ATTRIBUTES ||
+           issue.encrypted_attributes.len() != attributes.len() ||
+           issue.auxiliary_commitments.len() != attributes.len()
+        {
+            return Err(CredentialError::WrongNumberOfAttributes.into());
+        }
+
+        // Check that the issuer computed the aMAC over our own ciphertexts,
+        // without revealing which of our hidden attributes didn't match.
+        let mut matched: bool = true;
+
+        for (Ei, mi) in issue.encrypted_attributes.iter().zip(attributes.iter()) {
+            matched &= key.secret.decrypt(Ei) == elgamal::Message::from(mi).0;
+        }
+        if !matched {
+            return Err(CredentialError::CredentialIssuance.into());
         }
 
-        unimplemented!();
+        let D: RistrettoPoint = key.public.into();
+        let E0: &EncryptedAttribute = &issue.encrypted_attributes[0];
+
+        let publics = issuance_blinded::Publics {
+            B: &self.system_parameters.g,
+            A: &self.system_parameters.h,
+            X1: &X1,
+            D: &D,
+            P: &issue.blinding_commitment,
+            T1_0: &issue.auxiliary_commitments[0],
+            T1_1: &issue.auxiliary_commitments[0],
+            Cx0: &issue.secret_key_commitment.into(),
+            EQ_commitment: &issue.encrypted_mac.commitment,
+            EQ_encryption: &issue.encrypted_mac.encryption,
+            encrypted_attribute_0_0: &E0.commitment,
+            encrypted_attribute_0_1: &E0.encryption,
+        };
+
+        if issue.proof.verify(&mut transcript, publics).is_err() {
+            return Err(CredentialError::CredentialIssuance.into());
+        }
+
+        let Q: RistrettoPoint = key.secret.decrypt(&issue.encrypted_mac);
+        let attributes: Vec<Scalar> = attributes.to_vec();
+
+        self.credential = Some(Credential {
+            mac: amacs::Tag {
+                nonce: issue.blinding_commitment,
+                mac: Q,
+            },
+            attributes: attributes,
+        });
+
+        Ok(())
     }
 }
@@ -0,0 +1,270 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Issuer-side storage of received attributes as ciphertexts.
+//!
+//! An issuer which must keep the attributes revealed to it, e.g. to settle
+//! later disputes over what was attested, can `SealedAttributes::seal()`
+//! them as elGamal encryptions under a separate storage key, and store only
+//! the result.  The storage key's secret half is kept offline, so a dump of
+//! the issuer's database reveals no plaintext attributes.
+//!
+//! In a dispute, the holder of the storage key `open()`s one attribute of a
+//! record, giving the point it decrypts to with a DLEQ proof that the
+//! decryption used the storage key.  Anyone with the storage public key may
+//! then check the `Opening` against the stored ciphertext, and whether it is
+//! of a claimed attribute, without learning the storage key.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use bincode::deserialize;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use merlin::Transcript;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::RevealedAttribute;
+use elgamal;
+use elgamal::Encryption;
+use elgamal::SIZEOF_ENCRYPTION;
+use errors::CredentialError;
+use errors::Error;
+use errors::VaultError;
+use proofs::batch_dleq;
+
+/// A record of attributes, each encrypted to an issuer's storage key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SealedAttributes {
+    pub encryptions: Vec<Encryption>,
+}
+
+impl SealedAttributes {
+    pub fn from_bytes(bytes: &[u8]) -> Result<SealedAttributes, VaultError> {
+        if bytes.len() % SIZEOF_ENCRYPTION != 0 {
+            return Err(VaultError::WrongNumberOfBytes);
+        }
+
+        let mut encryptions: Vec<Encryption> = Vec::with_capacity(bytes.len() / SIZEOF_ENCRYPTION);
+
+        for chunk in bytes.chunks(SIZEOF_ENCRYPTION) {
+            encryptions.push(Encryption::from_bytes(chunk)?);
+        }
+        Ok(SealedAttributes { encryptions })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ENCRYPTION * self.encryptions.len());
+
+        for encryption in self.encryptions.iter() {
+            v.extend(encryption.to_bytes());
+        }
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SealedAttributes, "A valid byte sequence representing a SealedAttributes");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SealedAttributes);
+impl_try_from_bytes!(SealedAttributes, VaultError);
+
+/// The transcript under which the decryption of an `encryption` is proven.
+fn transcript(encryption: &Encryption, plaintext: &RistrettoPoint) -> Transcript {
+    let mut transcript = Transcript::new(b"AEONFLUX SEALED ATTRIBUTE");
+
+    transcript.commit_bytes(b"encryption", &encryption.to_bytes());
+    transcript.commit_bytes(b"plaintext", plaintext.compress().as_bytes());
+    transcript
+}
+
+impl SealedAttributes {
+    /// Seal some revealed `attributes`, e.g. a `CredentialRequest`'s
+    /// `attributes_revealed`, to the storage key `public`.
+    ///
+    /// The nonces of the encryptions are cleared once sealing is done, so
+    /// only the storage key can open the record.
+    pub fn seal<R>(
+        public: &elgamal::PublicKey,
+        attributes: &[RevealedAttribute],
+        csprng: &mut R,
+    ) -> SealedAttributes
+    where
+        R: RngCore + CryptoRng,
+    {
+        let (encryptions, _plaintexts) = public.encrypt_attributes(attributes, csprng);
+
+        SealedAttributes { encryptions }
+    }
+
+    /// Open the attribute at `index` with the storage `keypair`.
+    ///
+    /// # Errors
+    ///
+    /// * `VaultError::NoSuchAttribute` if the record has no attribute at
+    ///   `index`.
+    pub fn open(&self, keypair: &elgamal::Keypair, index: usize) -> Result<Opening, Error> {
+        let encryption: &Encryption = self.encryptions.get(index).ok_or(VaultError::NoSuchAttribute)?;
+        let plaintext: RistrettoPoint = keypair.secret.decrypt(encryption);
+        let shared: RistrettoPoint = encryption.encryption - plaintext;
+
+        let mut transcript = transcript(encryption, &plaintext);
+        let secrets = batch_dleq::Secrets { k: keypair.secret.as_witness() };
+        let publics = batch_dleq::Publics {
+            G: &RISTRETTO_BASEPOINT_POINT,
+            Y: &keypair.public.0,
+            M: &encryption.commitment,
+            Z: &shared,
+        };
+        let proof = batch_dleq::Proof::create(&mut transcript, publics, secrets);
+
+        Ok(Opening { plaintext, proof })
+    }
+
+    /// Check that an `opening` of the attribute at `index` was made with the
+    /// storage key `public`.
+    ///
+    /// # Errors
+    ///
+    /// * `VaultError::NoSuchAttribute` if the record has no attribute at
+    ///   `index`.
+    /// * `VaultError::InvalidProof` if the opening's proof did not verify.
+    pub fn verify_opening(
+        &self,
+        public: &elgamal::PublicKey,
+        index: usize,
+        opening: &Opening,
+    ) -> Result<(), Error>
+    {
+        let encryption: &Encryption = self.encryptions.get(index).ok_or(VaultError::NoSuchAttribute)?;
+        let shared: RistrettoPoint = encryption.encryption - opening.plaintext;
+
+        let mut transcript = transcript(encryption, &opening.plaintext);
+        let publics = batch_dleq::Publics {
+            G: &RISTRETTO_BASEPOINT_POINT,
+            Y: &public.0,
+            M: &encryption.commitment,
+            Z: &shared,
+        };
+
+        opening.proof.verify(&mut transcript, publics).or(Err(VaultError::InvalidProof.into()))
+    }
+}
+
+/// The decryption of one sealed attribute, with a proof that it was made with
+/// the storage key.
+///
+/// Since attributes are encrypted as points, the opening does not give the
+/// attribute itself, but can be checked against a claimed one with `is_of()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Opening {
+    pub plaintext: RistrettoPoint,
+    pub proof: batch_dleq::Proof,
+}
+
+impl Opening {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Opening, VaultError> {
+        if bytes.len() < 32 {
+            return Err(VaultError::WrongNumberOfBytes);
+        }
+
+        let plaintext: RistrettoPoint = CompressedRistretto::from_slice(&bytes[..32]).decompress()
+            .ok_or(CredentialError::PointDecompressionError)?;
+        let proof: batch_dleq::Proof = match deserialize(&bytes[32..]) {
+            Ok(x)   => x,
+            Err(_x) => return Err(VaultError::WrongNumberOfBytes),
+        };
+
+        Ok(Opening { plaintext, proof })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        v.extend(self.plaintext.compress().to_bytes().iter());
+
+        v.extend(self.proof.to_bytes().iter());
+        v
+    }
+
+    /// Whether this opening is of the claimed `attribute`.
+    pub fn is_of(&self, attribute: &RevealedAttribute) -> bool {
+        self.plaintext == attribute * &RISTRETTO_BASEPOINT_TABLE
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(Opening, "A valid byte sequence representing an Opening");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(Opening);
+impl_try_from_bytes!(Opening, VaultError);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn sealed_attributes_open_verifiably() {
+        let mut rng = thread_rng();
+        let storage = elgamal::Keypair::generate(&mut rng);
+        let attributes: Vec<Scalar> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+
+        let sealed = SealedAttributes::seal(&storage.public, &attributes, &mut rng);
+        let sealed = SealedAttributes::from_bytes(&sealed.to_bytes()).unwrap();
+
+        // The stored record says nothing about the attributes in the clear.
+        for attribute in attributes.iter() {
+            assert!(!sealed.to_bytes().windows(32).any(|w| w == attribute.as_bytes()));
+        }
+
+        let opening = sealed.open(&storage, 1).unwrap();
+        let opening = Opening::from_bytes(&opening.to_bytes()).unwrap();
+
+        assert!(sealed.verify_opening(&storage.public, 1, &opening).is_ok());
+        assert!(opening.is_of(&attributes[1]));
+        assert!(!opening.is_of(&attributes[0]));
+        assert!(sealed.open(&storage, 2) == Err(VaultError::NoSuchAttribute.into()));
+    }
+
+    #[test]
+    fn false_openings_are_refused() {
+        let mut rng = thread_rng();
+        let storage = elgamal::Keypair::generate(&mut rng);
+        let other = elgamal::Keypair::generate(&mut rng);
+        let attributes: Vec<Scalar> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+        let sealed = SealedAttributes::seal(&storage.public, &attributes, &mut rng);
+
+        // Claiming another plaintext, or another attribute's opening,
+        // invalidates the proof.
+        let mut claimed = sealed.open(&storage, 0).unwrap();
+
+        claimed.plaintext = &attributes[1] * &RISTRETTO_BASEPOINT_TABLE;
+
+        assert!(sealed.verify_opening(&storage.public, 0, &claimed) == Err(VaultError::InvalidProof.into()));
+        assert!(sealed.verify_opening(&storage.public, 1, &sealed.open(&storage, 0).unwrap()).is_err());
+
+        // An opening with any other key is refused.
+        let forged = sealed.open(&other, 0).unwrap();
+
+        assert!(sealed.verify_opening(&storage.public, 0, &forged) == Err(VaultError::InvalidProof.into()));
+        assert!(SealedAttributes::from_bytes(&sealed.to_bytes()[1..]) == Err(VaultError::WrongNumberOfBytes));
+    }
+}
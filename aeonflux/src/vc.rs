@@ -0,0 +1,246 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Envelopes in the W3C Verifiable Credentials data model.
+//!
+//! A `Credential` may be wrapped in a `VerifiableCredential`, for a holder's
+//! wallet, and a `CredentialPresentation` in a `VerifiablePresentation`, for
+//! relying parties whose pipelines expect one.  In both, the aMAC and its
+//! proofs are carried opaquely in the `proofValue` of a proof whose `type`
+//! is one of ours, so a relying party must still hand the presentation to
+//! `Issuer::verify`: nothing in the envelope is checked by a generic VC
+//! verifier.
+//!
+//! Attributes are listed in the `credentialSubject` as hexadecimal scalars,
+//! revealed attributes of a presentation by their index, so that policy
+//! engines may inspect them without decoding the proof.  Only revealed
+//! attributes appear in a `VerifiablePresentation`.
+
+use std::string::String;
+use std::vec::Vec;
+
+use serde_json::Map;
+use serde_json::Value;
+
+use credential::Credential;
+use credential::CredentialPresentation;
+use errors::VcError;
+
+/// The base context of every credential and presentation.
+pub const VC_CONTEXT: &'static str = "https://www.w3.org/2018/credentials/v1";
+
+/// The additional type of a `VerifiableCredential` holding a `Credential`.
+pub const VC_CREDENTIAL_TYPE: &'static str = "AeonfluxCredential";
+
+/// The additional type of a `VerifiablePresentation` holding a
+/// `CredentialPresentation`.
+pub const VC_PRESENTATION_TYPE: &'static str = "AeonfluxPresentation";
+
+/// The proof type of a `Credential`, whose `proofValue` is its encoding.
+pub const VC_CREDENTIAL_PROOF_TYPE: &'static str = "AeonfluxAlgebraicMac2018";
+
+/// The proof type of a `CredentialPresentation`, whose `proofValue` is its
+/// encoding.
+pub const VC_PRESENTATION_PROOF_TYPE: &'static str = "AeonfluxPresentationProof2018";
+
+/// The multibase prefix for lowercase hexadecimal, which every `proofValue`
+/// is encoded with.
+const MULTIBASE_BASE16: char = 'f';
+
+/// What an envelope says about who issued it and when.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VcMetadata {
+    /// A URI identifying the issuer.
+    pub issuer: String,
+    /// An XML Schema `dateTime`, e.g. `2018-09-01T00:00:00Z`.
+    pub issuance_date: String,
+    /// Any contexts, beyond `VC_CONTEXT`, defining the terms used here.
+    pub contexts: Vec<String>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Result<Vec<u8>, VcError> {
+    if text.len() % 2 != 0 || !text.is_ascii() {
+        return Err(VcError::InvalidProofValue);
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).or(Err(VcError::InvalidProofValue)))
+        .collect()
+}
+
+fn contexts(metadata: &VcMetadata) -> Value {
+    let mut contexts: Vec<Value> = Vec::with_capacity(1 + metadata.contexts.len());
+
+    contexts.push(Value::from(VC_CONTEXT));
+    contexts.extend(metadata.contexts.iter().map(|x| Value::from(x.as_str())));
+
+    Value::Array(contexts)
+}
+
+fn proof(proof_type: &str, bytes: &[u8]) -> Value {
+    json!({
+        "type": proof_type,
+        "proofValue": format!("{}{}", MULTIBASE_BASE16, hex(bytes)),
+    })
+}
+
+/// Check that `envelope` has the given `types` and return its proof's bytes.
+fn open(envelope: &Value, types: &[&str], proof_type: &str) -> Result<Vec<u8>, VcError> {
+    let listed: &Vec<Value> = envelope.get("type").and_then(Value::as_array).ok_or(VcError::MissingField)?;
+
+    for t in types.iter() {
+        if !listed.iter().any(|x| x.as_str() == Some(*t)) {
+            return Err(VcError::UnexpectedType);
+        }
+    }
+
+    let proof: &Map<String, Value> = envelope.get("proof").and_then(Value::as_object).ok_or(VcError::MissingField)?;
+
+    if proof.get("type").and_then(Value::as_str) != Some(proof_type) {
+        return Err(VcError::UnknownProofType);
+    }
+
+    let value: &str = proof.get("proofValue").and_then(Value::as_str).ok_or(VcError::MissingField)?;
+
+    if !value.starts_with(MULTIBASE_BASE16) {
+        return Err(VcError::InvalidProofValue);
+    }
+    unhex(&value[1..])
+}
+
+impl Credential {
+    /// Wrap this credential in a `VerifiableCredential`.
+    ///
+    /// The envelope holds the credential's tag, and so must be kept as
+    /// secret as the credential itself.
+    pub fn to_vc(&self, metadata: &VcMetadata) -> Value {
+        let attributes: Vec<Value> = self.attributes.iter().map(|x| Value::from(hex(x.as_bytes()))).collect();
+
+        json!({
+            "@context": contexts(metadata),
+            "type": ["VerifiableCredential", VC_CREDENTIAL_TYPE],
+            "issuer": metadata.issuer,
+            "issuanceDate": metadata.issuance_date,
+            "credentialSubject": { "attributes": attributes },
+            "proof": proof(VC_CREDENTIAL_PROOF_TYPE, &self.to_bytes()),
+        })
+    }
+
+    /// Unwrap a credential from a `VerifiableCredential`.
+    ///
+    /// # Errors
+    ///
+    /// * `VcError::UnexpectedType` or `VcError::UnknownProofType` if the
+    ///   envelope does not hold a `Credential`.
+    /// * `VcError::Credential` if the credential could not be decoded.
+    pub fn from_vc(envelope: &Value) -> Result<Credential, VcError> {
+        let bytes: Vec<u8> = open(envelope, &["VerifiableCredential", VC_CREDENTIAL_TYPE],
+                                  VC_CREDENTIAL_PROOF_TYPE)?;
+
+        Ok(Credential::from_bytes(&bytes)?)
+    }
+}
+
+impl CredentialPresentation {
+    /// Wrap this presentation in a `VerifiablePresentation`.
+    pub fn to_vp(&self, metadata: &VcMetadata) -> Value {
+        let mut revealed: Map<String, Value> = Map::new();
+
+        for (i, attribute) in self.attributes_revealed.iter().enumerate() {
+            revealed.insert(format!("{}", i), Value::from(hex(attribute.as_bytes())));
+        }
+
+        json!({
+            "@context": contexts(metadata),
+            "type": ["VerifiablePresentation", VC_PRESENTATION_TYPE],
+            "verifiableCredential": [{
+                "@context": contexts(metadata),
+                "type": ["VerifiableCredential", VC_CREDENTIAL_TYPE],
+                "issuer": metadata.issuer,
+                "issuanceDate": metadata.issuance_date,
+                "credentialSubject": { "revealedAttributes": revealed },
+            }],
+            "proof": proof(VC_PRESENTATION_PROOF_TYPE, &self.to_bytes()),
+        })
+    }
+
+    /// Unwrap a presentation from a `VerifiablePresentation`.
+    ///
+    /// This does not verify the presentation, which must then be given to
+    /// `Issuer::verify`.
+    ///
+    /// # Errors
+    ///
+    /// * `VcError::UnexpectedType` or `VcError::UnknownProofType` if the
+    ///   envelope does not hold a `CredentialPresentation`.
+    /// * `VcError::Credential` if the presentation could not be decoded.
+    pub fn from_vp(envelope: &Value) -> Result<CredentialPresentation, VcError> {
+        let bytes: Vec<u8> = open(envelope, &["VerifiablePresentation", VC_PRESENTATION_TYPE],
+                                  VC_PRESENTATION_PROOF_TYPE)?;
+
+        Ok(CredentialPresentation::from_bytes(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use credential::RevealedAttribute;
+    use issuer::Issuer;
+    use nonces::Nonces;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use user::User;
+
+    #[test]
+    fn vc_envelopes_round_trip() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+        attributes.push(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(&user.obtain(attributes), &mut rng).unwrap();
+        let metadata = VcMetadata {
+            issuer: String::from("did:example:issuer"),
+            issuance_date: String::from("2018-09-01T00:00:00Z"),
+            contexts: Vec::new(),
+        };
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let vc: Value = issuance.credential.to_vc(&metadata);
+
+        assert!(vc["@context"][0] == VC_CONTEXT);
+        assert!(Credential::from_vc(&vc).unwrap().to_bytes() == issuance.credential.to_bytes());
+
+        let presentation = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+        let vp: Value = presentation.to_vp(&metadata);
+        let decoded = CredentialPresentation::from_vp(&vp).unwrap();
+
+        assert!(issuer.verify(&decoded).is_ok());
+        assert!(CredentialPresentation::from_vp(&vc).err() == Some(VcError::UnexpectedType));
+
+        let mut forged: Value = vp.clone();
+
+        forged["proof"]["type"] = Value::from("Ed25519Signature2018");
+        assert!(CredentialPresentation::from_vp(&forged).err() == Some(VcError::UnknownProofType));
+    }
+}
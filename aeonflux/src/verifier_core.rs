@@ -0,0 +1,213 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Presentation verification within a fixed memory budget.
+//!
+//! Where `HeaplessVerifier::verify` decodes a presentation onto the stack,
+//! `verify` here decodes it into a `Scratch` buffer provided by the caller,
+//! and computes the MAC within that same buffer, so that a device may place
+//! the buffer wherever it likes, e.g. in a statically allocated section, and
+//! reuse it for every presentation.
+//!
+//! A `Scratch` is at most `SIZEOF_SCRATCH` bytes.  Aside from it, verifying
+//! uses only a fixed amount of stack for the decoded proof and the proof's
+//! transcript, which does not depend on the presentation.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use errors::CredentialError;
+use errors::Error;
+use heapless::HeaplessVerifier;
+use heapless::SIZEOF_HEAPLESS_PRESENTATION;
+use heapless::canonical_scalar;
+use heapless::decompress;
+use parameters::PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES;
+use parameters::PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+use proofs::valid_credential;
+use secret::Clear;
+
+/// The number of points held in a `Scratch`: the rerandomised MAC commitment
+/// and nonce, each blinded attribute, and the recomputed MAC.
+pub const SCRATCH_POINTS: usize = 3 + PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES;
+
+/// The number of scalars held in a `Scratch`: each revealed attribute, and
+/// the MAC key applied to them.
+pub const SCRATCH_SCALARS: usize = 1 + PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES;
+
+/// An upper bound on the size of a `Scratch`, on every backend.
+pub const SIZEOF_SCRATCH: usize = 160 * SCRATCH_POINTS + 32 * SCRATCH_SCALARS;
+
+/// Working space for verifying a single presentation.
+///
+/// Part of it is derived from the issuer's secret key while verifying, and
+/// so it is cleared after every verification and when dropped.
+pub struct Scratch {
+    points: [RistrettoPoint; SCRATCH_POINTS],
+    scalars: [Scalar; SCRATCH_SCALARS],
+}
+
+impl Scratch {
+    /// Create an empty `Scratch`.
+    pub fn new() -> Scratch {
+        Scratch {
+            points: [RistrettoPoint::identity(); SCRATCH_POINTS],
+            scalars: [Scalar::zero(); SCRATCH_SCALARS],
+        }
+    }
+
+    fn clear(&mut self) {
+        for point in self.points.iter_mut() {
+            *point = RistrettoPoint::identity();
+        }
+        for scalar in self.scalars.iter_mut() {
+            scalar.clear();
+        }
+    }
+}
+
+impl Default for Scratch {
+    fn default() -> Scratch {
+        Scratch::new()
+    }
+}
+
+/// Overwrite the scratch space when it goes out of scope.
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Decode the encoded `presentation` into `scratch` and verify it, exactly
+/// as `Issuer::verify` would.
+///
+/// # Errors
+///
+/// * `CredentialError::WrongNumberOfBytes` if `presentation` is not
+///   `SIZEOF_HEAPLESS_PRESENTATION` bytes long.
+/// * `CredentialError::PointDecompressionError`,
+///   `CredentialError::ScalarFormatError`, or `CredentialError::MissingData`
+///   if a field could not be decoded.
+/// * `CredentialError::VerificationFailure` if the nonce is the identity.
+/// * `CredentialError::MacVerification` if the presentation is invalid.
+pub fn verify(
+    verifier: &HeaplessVerifier,
+    presentation: &[u8],
+    scratch: &mut Scratch,
+) -> Result<(), Error>
+{
+    let result = decode_and_verify(verifier, presentation, scratch);
+
+    scratch.clear();
+
+    result
+}
+
+fn decode_and_verify(
+    verifier: &HeaplessVerifier,
+    bytes: &[u8],
+    scratch: &mut Scratch,
+) -> Result<(), Error>
+{
+    if bytes.len() != SIZEOF_HEAPLESS_PRESENTATION {
+        return Err(CredentialError::WrongNumberOfBytes.into());
+    }
+
+    // The points are laid out as the commitment, the nonce, the blinded
+    // attributes, and then the recomputed MAC; the scalars as the revealed
+    // attributes and then the MAC key.
+    let (decoded, V_prime) = scratch.points.split_at_mut(2 + PRESENTATION_NUMBER_OF_BLINDED_ATTRIBUTES);
+    let (attributes_revealed, x) = scratch.scalars.split_at_mut(PRESENTATION_NUMBER_OF_REVEALED_ATTRIBUTES);
+    let mut offset: usize = 64;
+
+    decoded[0] = decompress(&bytes[00..32])?;
+    decoded[1] = decompress(&bytes[32..64])?;
+
+    for attribute in attributes_revealed.iter_mut() {
+        *attribute = canonical_scalar(&bytes[offset..offset + 32])?;
+        offset += 32;
+    }
+    for attribute in decoded[2..].iter_mut() {
+        *attribute = decompress(&bytes[offset..offset + 32])?;
+        offset += 32;
+    }
+
+    let proof = valid_credential::Proof::from_bytes(&bytes[offset..])
+        .or(Err(CredentialError::MissingData))?;
+
+    verifier.verify_parts(&decoded[0], &decoded[1], attributes_revealed, &decoded[2..],
+                          &proof, &mut x[0], &mut V_prime[0])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::convert::TryFrom;
+    use std::mem;
+    use std::vec::Vec;
+
+    use rand::thread_rng;
+
+    use credential::RevealedAttribute;
+    use issuer::Issuer;
+    use nonces::Nonces;
+    use parameters::NUMBER_OF_ATTRIBUTES;
+    use parameters::SystemParameters;
+    use test_utils::H;
+    use test_utils::identity_forgery;
+    use user::User;
+
+    #[test]
+    fn scratch_fits_budget() {
+        assert!(mem::size_of::<Scratch>() <= SIZEOF_SCRATCH);
+    }
+
+    #[test]
+    fn verify_with_scratch() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+        let mut attributes: Vec<RevealedAttribute> = Vec::new();
+
+        attributes.push(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(&user.obtain(attributes), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let presentation: Vec<u8> = user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng)
+            .unwrap().to_bytes();
+        let verifier = HeaplessVerifier::try_from(&issuer).unwrap();
+        let other = HeaplessVerifier::try_from(&Issuer::create(system_parameters, &mut rng)).unwrap();
+        let mut scratch = Scratch::new();
+
+        assert!(verify(&verifier, &presentation, &mut scratch).is_ok());
+        assert!(verify(&other, &presentation, &mut scratch) == Err(CredentialError::MacVerification.into()));
+        assert!(verify(&verifier, &presentation[1..], &mut scratch) == Err(CredentialError::WrongNumberOfBytes.into()));
+
+        // The same scratch space may be reused for the next presentation.
+        assert!(verify(&verifier, &presentation, &mut scratch).is_ok());
+        assert!(scratch.scalars.iter().all(|x| *x == Scalar::zero()));
+    }
+
+    #[test]
+    fn identity_nonces_are_rejected() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let verifier = HeaplessVerifier::try_from(&issuer).unwrap();
+        let forged: Vec<u8> = identity_forgery(&issuer, &mut rng).to_bytes();
+        let mut scratch = Scratch::new();
+
+        assert!(verify(&verifier, &forged, &mut scratch) == Err(CredentialError::VerificationFailure.into()));
+    }
+}
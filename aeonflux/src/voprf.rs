@@ -0,0 +1,632 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! The verifiable oblivious pseudorandom function (VOPRF) mode of
+//! [RFC 9497](https://www.rfc-editor.org/rfc/rfc9497.html), instantiated with
+//! the `ristretto255-SHA512` ciphersuite.
+//!
+//! This allows issuers to offer standard-conformant blind evaluation alongside
+//! credentials.  Unlike the rest of this crate, which uses merlin transcripts,
+//! every hash in this module follows the RFC byte-for-byte, so that outputs
+//! interoperate with other conforming implementations.
+//!
+//! # Protocol
+//!
+//! 1. The client calls `blind()` on its input, and sends the blinded element.
+//! 2. The server calls `ServerKeypair::blind_evaluate()` on a batch of
+//!    blinded elements, and returns the evaluated elements and a proof.
+//! 3. The client calls `finalize()` to verify the proof and unblind each
+//!    output.
+
+#[cfg(not(feature = "std"))]
+use core::cmp;
+#[cfg(feature = "std")]
+use std::cmp;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use errors::Error;
+use errors::VoprfError;
+use secret::Secret;
+
+pub const SIZEOF_ELEMENT: usize = 32;
+pub const SIZEOF_SCALAR: usize = 32;
+pub const SIZEOF_PROOF: usize = 64;
+/// The length of an output of the function, `Nh` in the RFC.
+pub const SIZEOF_OUTPUT: usize = 64;
+
+/// The ciphersuite context string, for mode `0x01` (VOPRF).
+pub const CONTEXT_STRING: &'static [u8] = b"OPRFV1-\x01-ristretto255-SHA512";
+
+/// The input block size of SHA-512 in bytes, `s_in_bytes` in RFC 9380.
+const SHA512_BLOCK_SIZE: usize = 128;
+
+/// Append `I2OSP(len(bytes), 2) || bytes` to `v`.
+fn push_length_prefixed(v: &mut Vec<u8>, bytes: &[u8]) {
+    v.push((bytes.len() >> 8) as u8);
+    v.push(bytes.len() as u8);
+    v.extend(bytes.iter());
+}
+
+/// Concatenate a domain separation tag prefix with the context string.
+fn dst(prefix: &[u8]) -> Vec<u8> {
+    let mut v: Vec<u8> = Vec::with_capacity(prefix.len() + CONTEXT_STRING.len());
+
+    v.extend(prefix.iter());
+    v.extend(CONTEXT_STRING.iter());
+
+    v
+}
+
+/// `expand_message_xmd` from RFC 9380, with SHA-512 and a 64-byte output.
+///
+/// Since the output length is exactly one SHA-512 digest, only `b_0` and
+/// `b_1` are computed.
+pub(crate) fn expand_message_xmd(msg: &[u8], dst: &[u8]) -> [u8; 64] {
+    let mut b_0 = Sha512::default();
+
+    b_0.input(&[0u8; SHA512_BLOCK_SIZE][..]);
+    b_0.input(msg);
+    b_0.input(&[0u8, 64u8, 0u8][..]);   // I2OSP(64, 2) || I2OSP(0, 1)
+    b_0.input(dst);
+    b_0.input(&[dst.len() as u8][..]);
+
+    let b_0 = b_0.result();
+
+    let mut b_1 = Sha512::default();
+
+    b_1.input(&b_0[..]);
+    b_1.input(&[1u8][..]);
+    b_1.input(dst);
+    b_1.input(&[dst.len() as u8][..]);
+
+    let mut output = [0u8; 64];
+
+    output.copy_from_slice(&b_1.result()[..]);
+    output
+}
+
+/// `HashToGroup` for ristretto255: `hash_to_ristretto255` with
+/// `DST = "HashToGroup-" || contextString`.
+pub fn hash_to_group(input: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&expand_message_xmd(input, &dst(b"HashToGroup-")))
+}
+
+/// `HashToScalar` for ristretto255, with a custom domain separation tag.
+fn hash_to_scalar_with_dst(input: &[u8], dst: &[u8]) -> Scalar {
+    Scalar::from_bytes_mod_order_wide(&expand_message_xmd(input, dst))
+}
+
+/// `HashToScalar` for ristretto255, with `DST = "HashToScalar-" || contextString`.
+pub fn hash_to_scalar(input: &[u8]) -> Scalar {
+    hash_to_scalar_with_dst(input, &dst(b"HashToScalar-"))
+}
+
+/// `SerializeElement`: the 32-byte compressed Ristretto encoding.
+pub fn serialize_element(element: &RistrettoPoint) -> [u8; SIZEOF_ELEMENT] {
+    element.compress().to_bytes()
+}
+
+/// `DeserializeElement`: decompress a Ristretto point, rejecting the identity.
+pub fn deserialize_element(bytes: &[u8]) -> Result<RistrettoPoint, VoprfError> {
+    if bytes.len() != SIZEOF_ELEMENT {
+        return Err(VoprfError::WrongNumberOfBytes);
+    }
+
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    let element: RistrettoPoint = CompressedRistretto(tmp).decompress()?;
+
+    if element == RistrettoPoint::identity() {
+        return Err(VoprfError::PointDecompressionError);
+    }
+
+    Ok(element)
+}
+
+/// `DeserializeScalar`: a canonical little-endian scalar.
+pub fn deserialize_scalar(bytes: &[u8]) -> Result<Scalar, VoprfError> {
+    if bytes.len() != SIZEOF_SCALAR {
+        return Err(VoprfError::WrongNumberOfBytes);
+    }
+
+    let mut tmp = [0u8; 32];
+
+    tmp.copy_from_slice(bytes);
+
+    match Scalar::from_canonical_bytes(tmp) {
+        Some(x) => Ok(x),
+        None    => Err(VoprfError::ScalarFormatError),
+    }
+}
+
+/// Compute the composite elements `(M, Z)` for a batch of `blinded` and
+/// `evaluated` elements, per `ComputeComposites` in the RFC.
+///
+/// If the server's `secret` is given, `Z` is computed as `secret * M`, per
+/// `ComputeCompositesFast`.
+fn compute_composites(
+    secret: Option<&Scalar>,
+    public: &RistrettoPoint,
+    blinded: &[RistrettoPoint],
+    evaluated: &[RistrettoPoint],
+) -> (RistrettoPoint, RistrettoPoint)
+{
+    let mut seed_transcript: Vec<u8> = Vec::new();
+
+    push_length_prefixed(&mut seed_transcript, &serialize_element(public));
+    push_length_prefixed(&mut seed_transcript, &dst(b"Seed-"));
+
+    let seed = Sha512::digest(&seed_transcript);
+
+    let length: usize = cmp::min(blinded.len(), evaluated.len());
+    let mut coefficients: Vec<Scalar> = Vec::with_capacity(length);
+
+    for (i, (C, D)) in blinded.iter().zip(evaluated.iter()).enumerate() {
+        let mut composite_transcript: Vec<u8> = Vec::new();
+
+        push_length_prefixed(&mut composite_transcript, &seed[..]);
+        composite_transcript.push((i >> 8) as u8);
+        composite_transcript.push(i as u8);
+        push_length_prefixed(&mut composite_transcript, &serialize_element(C));
+        push_length_prefixed(&mut composite_transcript, &serialize_element(D));
+        composite_transcript.extend(b"Composite".iter());
+
+        coefficients.push(hash_to_scalar(&composite_transcript));
+    }
+
+    // The coefficients and elements are all public, so we may use
+    // variable-time arithmetic for everything except the server's secret.
+    let M: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(&coefficients, &blinded[..length]);
+    let Z: RistrettoPoint = match secret {
+        Some(k) => k * &M,
+        None    => RistrettoPoint::vartime_multiscalar_mul(&coefficients, &evaluated[..length]),
+    };
+
+    (M, Z)
+}
+
+/// Compute the proof challenge from its transcript elements.
+fn challenge(
+    public: &RistrettoPoint,
+    M: &RistrettoPoint,
+    Z: &RistrettoPoint,
+    t2: &RistrettoPoint,
+    t3: &RistrettoPoint,
+) -> Scalar
+{
+    let mut challenge_transcript: Vec<u8> = Vec::new();
+
+    push_length_prefixed(&mut challenge_transcript, &serialize_element(public));
+    push_length_prefixed(&mut challenge_transcript, &serialize_element(M));
+    push_length_prefixed(&mut challenge_transcript, &serialize_element(Z));
+    push_length_prefixed(&mut challenge_transcript, &serialize_element(t2));
+    push_length_prefixed(&mut challenge_transcript, &serialize_element(t3));
+    challenge_transcript.extend(b"Challenge".iter());
+
+    hash_to_scalar(&challenge_transcript)
+}
+
+/// A proof that a batch of elements were evaluated with the key for `pkS`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Proof {
+    pub c: Scalar,
+    pub s: Scalar,
+}
+
+impl Proof {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Proof, VoprfError> {
+        if bytes.len() != SIZEOF_PROOF {
+            return Err(VoprfError::WrongNumberOfBytes);
+        }
+
+        let c = deserialize_scalar(&bytes[00..32])?;
+        let s = deserialize_scalar(&bytes[32..64])?;
+
+        Ok(Proof { c, s })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_PROOF);
+
+        v.extend(self.c.to_bytes().iter());
+        v.extend(self.s.to_bytes().iter());
+
+        v
+    }
+
+    /// `VerifyProof`, with `A` as the group generator and `B` as `public`.
+    fn verify(
+        &self,
+        public: &RistrettoPoint,
+        blinded: &[RistrettoPoint],
+        evaluated: &[RistrettoPoint],
+    ) -> Result<(), VoprfError>
+    {
+        let (M, Z) = compute_composites(None, public, blinded, evaluated);
+        // Everything here is public, so we may use variable-time arithmetic.
+        let t2: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(
+            &[self.s, self.c], &[RISTRETTO_BASEPOINT_POINT, *public]);
+        let t3: RistrettoPoint = RistrettoPoint::vartime_multiscalar_mul(&[self.s, self.c], &[M, Z]);
+
+        if challenge(public, &M, &Z, &t2, &t3) == self.c {
+            Ok(())
+        } else {
+            Err(VoprfError::VerificationFailure)
+        }
+    }
+}
+
+impl_try_from_bytes!(Proof, VoprfError);
+
+/// A server's VOPRF key pair, `(skS, pkS)`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServerKeypair {
+    secret: Secret<Scalar>,
+    pub public: RistrettoPoint,
+}
+
+/// Overwrite secret key material with null bytes when it goes out of scope.
+impl Drop for ServerKeypair {
+    fn drop(&mut self) {
+        self.secret.clear();
+    }
+}
+
+impl ServerKeypair {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ServerKeypair, VoprfError> {
+        if bytes.len() != SIZEOF_SCALAR {
+            return Err(VoprfError::WrongNumberOfBytes);
+        }
+
+        let secret: Scalar = deserialize_scalar(bytes)?;
+
+        if secret == Scalar::zero() {
+            return Err(VoprfError::ScalarFormatError);
+        }
+
+        Ok(ServerKeypair::from_secret(secret))
+    }
+
+    /// Serialise the secret key, `skS`.  The public key is recomputed from it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.secret.expose_secret().to_bytes().to_vec()
+    }
+
+    fn from_secret(secret: Scalar) -> ServerKeypair {
+        let public: RistrettoPoint = &secret * &RISTRETTO_BASEPOINT_TABLE;
+
+        ServerKeypair { secret: Secret::new(secret), public }
+    }
+
+    /// Generate a new random key pair.
+    pub fn generate<R>(csprng: &mut R) -> ServerKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut secret: Scalar = Scalar::random(csprng);
+
+        while secret == Scalar::zero() {
+            secret = Scalar::random(csprng);
+        }
+
+        ServerKeypair::from_secret(secret)
+    }
+
+    /// `DeriveKeyPair`: deterministically derive a key pair from a 32-byte
+    /// `seed` and some public `info`.
+    pub fn derive(seed: &[u8; 32], info: &[u8]) -> Result<ServerKeypair, Error> {
+        if info.len() > 0xffff {
+            return Err(VoprfError::DeriveKeyPairError.into());
+        }
+
+        let mut derive_input: Vec<u8> = Vec::with_capacity(32 + 2 + info.len() + 1);
+
+        derive_input.extend(seed.iter());
+        push_length_prefixed(&mut derive_input, info);
+        derive_input.push(0u8);
+
+        let dst = dst(b"DeriveKeyPair");
+        let counter_index: usize = derive_input.len() - 1;
+
+        for counter in 0..256usize {
+            derive_input[counter_index] = counter as u8;
+
+            let secret: Scalar = hash_to_scalar_with_dst(&derive_input, &dst);
+
+            if secret != Scalar::zero() {
+                return Ok(ServerKeypair::from_secret(secret));
+            }
+        }
+
+        Err(VoprfError::DeriveKeyPairError.into())
+    }
+
+    /// `BlindEvaluate` over a batch of `blinded` elements, with a single proof.
+    pub fn blind_evaluate<R>(
+        &self,
+        blinded: &[RistrettoPoint],
+        csprng: &mut R,
+    ) -> (Vec<RistrettoPoint>, Proof)
+    where
+        R: RngCore + CryptoRng,
+    {
+        let r: Scalar = Scalar::random(csprng);
+
+        self.blind_evaluate_with_randomness(blinded, &r)
+    }
+
+    /// `BlindEvaluate` with a fixed proof randomness, `r`, for test vectors.
+    fn blind_evaluate_with_randomness(
+        &self,
+        blinded: &[RistrettoPoint],
+        r: &Scalar,
+    ) -> (Vec<RistrettoPoint>, Proof)
+    {
+        let mut evaluated: Vec<RistrettoPoint> = Vec::with_capacity(blinded.len());
+
+        for element in blinded.iter() {
+            evaluated.push(self.secret.expose_secret() * element);
+        }
+
+        let (M, Z) = compute_composites(Some(self.secret.expose_secret()), &self.public, blinded, &evaluated);
+        let t2: RistrettoPoint = r * &RISTRETTO_BASEPOINT_TABLE;
+        let t3: RistrettoPoint = r * &M;
+        let c: Scalar = challenge(&self.public, &M, &Z, &t2, &t3);
+        let s: Scalar = r - &(&c * self.secret.expose_secret());
+
+        (evaluated, Proof { c, s })
+    }
+
+    /// `Evaluate`: compute the output for an `input` directly, without blinding.
+    pub fn evaluate(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let element: RistrettoPoint = hash_to_group(input);
+
+        if element == RistrettoPoint::identity() {
+            return Err(VoprfError::InvalidInput.into());
+        }
+
+        Ok(finalize_hash(input, &(self.secret.expose_secret() * &element)))
+    }
+}
+
+impl_try_from_bytes!(ServerKeypair, VoprfError);
+
+/// Compute `Hash(I2OSP(len(input), 2) || input || I2OSP(len(N), 2) || N || "Finalize")`.
+fn finalize_hash(input: &[u8], unblinded: &RistrettoPoint) -> Vec<u8> {
+    let mut hash_input: Vec<u8> = Vec::new();
+
+    push_length_prefixed(&mut hash_input, input);
+    push_length_prefixed(&mut hash_input, &serialize_element(unblinded));
+    hash_input.extend(b"Finalize".iter());
+
+    Sha512::digest(&hash_input).to_vec()
+}
+
+/// A client's secret blinding scalar for one input.
+#[derive(Clone, Debug)]
+pub struct Blind(pub(crate) Secret<Scalar>);
+
+/// Overwrite the blind with null bytes when it goes out of scope.
+impl Drop for Blind {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// `Blind`: blind an `input` for evaluation by the server.
+///
+/// # Errors
+///
+/// * `VoprfError::InvalidInput` if the `input` maps to the identity element.
+///
+/// # Returns
+///
+/// A tuple of the secret `Blind`, which the client must keep, and the blinded
+/// element to send to the server.
+pub fn blind<R>(input: &[u8], csprng: &mut R) -> Result<(Blind, RistrettoPoint), Error>
+where
+    R: RngCore + CryptoRng,
+{
+    let mut r: Scalar = Scalar::random(csprng);
+
+    while r == Scalar::zero() {
+        r = Scalar::random(csprng);
+    }
+
+    blind_with(input, Blind(Secret::new(r)))
+}
+
+fn blind_with(input: &[u8], blind: Blind) -> Result<(Blind, RistrettoPoint), Error> {
+    let element: RistrettoPoint = hash_to_group(input);
+
+    if element == RistrettoPoint::identity() {
+        return Err(VoprfError::InvalidInput.into());
+    }
+
+    let blinded: RistrettoPoint = blind.0.expose_secret() * &element;
+
+    Ok((blind, blinded))
+}
+
+/// `Finalize`: verify the server's `proof` over a batch, and unblind the outputs.
+///
+/// # Inputs
+///
+/// * `inputs`, `blinds`, `blinded`, and `evaluated` are the client's inputs,
+///   their `Blind`s and blinded elements, and the server's evaluated
+///   elements, in the same order.
+/// * `public` is the server's public key, `pkS`.
+///
+/// # Errors
+///
+/// * `VoprfError::WrongNumberOfElements` if the batches are not all the same length.
+/// * `VoprfError::VerificationFailure` if the `proof` did not verify.
+///
+/// # Returns
+///
+/// The 64-byte output for each input.
+pub fn finalize(
+    inputs: &[&[u8]],
+    blinds: &[Blind],
+    blinded: &[RistrettoPoint],
+    evaluated: &[RistrettoPoint],
+    public: &RistrettoPoint,
+    proof: &Proof,
+) -> Result<Vec<Vec<u8>>, Error>
+{
+    let n: usize = inputs.len();
+
+    if blinds.len() != n || blinded.len() != n || evaluated.len() != n || n == 0 {
+        return Err(VoprfError::WrongNumberOfElements.into());
+    }
+
+    proof.verify(public, blinded, evaluated)?;
+
+    let mut outputs: Vec<Vec<u8>> = Vec::with_capacity(n);
+
+    for ((input, blind), element) in inputs.iter().zip(blinds.iter()).zip(evaluated.iter()) {
+        let unblinded: RistrettoPoint = &blind.0.expose_secret().invert() * element;
+
+        outputs.push(finalize_hash(input, &unblinded));
+    }
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let bytes = s.as_bytes();
+        let nibble = |c: u8| -> u8 {
+            match c {
+                b'0'...b'9' => c - b'0',
+                b'a'...b'f' => c - b'a' + 10,
+                _           => panic!(),
+            }
+        };
+
+        bytes.chunks(2).map(|pair| (nibble(pair[0]) << 4) | nibble(pair[1])).collect()
+    }
+
+    fn scalar(s: &str) -> Scalar {
+        deserialize_scalar(&hex(s)).unwrap()
+    }
+
+    // The ristretto255-SHA512 VOPRF test vectors from RFC 9497, Appendix A.1.2.
+    const SEED: [u8; 32] = [0xa3; 32];
+    const KEY_INFO: &'static [u8] = b"test key";
+    const SK_SM: &'static str = "e6f73f344b79b379f1a0dd37e07ff62e38d9f71345ce62ae3a9bc60b04ccd909";
+    const PK_SM: &'static str = "c803e2cc6b05fc15064549b5920659ca4a77b2cca6f04f6b357009335476ad4e";
+    const PROOF_RANDOM_SCALAR: &'static str = "222a5e897cf59db8145db8d16e597e8facb80ae7d4e26d9881aa6f61d645fc0e";
+    const BLIND: &'static str = "64d37aed22a27f5191de1c1d69fadb899d8862b58eb4220029e036ec4c1f6706";
+    const BLINDED_ELEMENT: &'static str = "863f330cc1a1259ed5a5998a23acfd37fb4351a793a5b3c090b642ddc439b945";
+    const EVALUATION_ELEMENT: &'static str = "aa8fa048764d5623868679402ff6108d2521884fa138cd7f9c7669a9a014267e";
+    const PROOF: &'static str = "ddef93772692e535d1a53903db24367355cc2cc78de93b3be5a8ffcc6985dd06\
+                                 6d4346421d17bf5117a2a1ff0fcb2a759f58a539dfbe857a40bce4cf49ec600d";
+    const OUTPUT: &'static str = "b58cfbe118e0cb94d79b5fd6a6dafb98764dff49c14e1770b566e42402da1a7d\
+                                  a4d8527693914139caee5bd03903af43a491351d23b430948dd50cde10d32b3c";
+
+    #[test]
+    fn rfc9497_derive_keypair() {
+        let keypair = ServerKeypair::derive(&SEED, KEY_INFO).unwrap();
+
+        assert!(keypair.to_bytes() == hex(SK_SM));
+        assert!(serialize_element(&keypair.public).to_vec() == hex(PK_SM));
+    }
+
+    #[test]
+    fn rfc9497_test_vector_1() {
+        let keypair = ServerKeypair::derive(&SEED, KEY_INFO).unwrap();
+        let input: &[u8] = &[0u8];
+
+        let (blind, blinded) = blind_with(input, Blind(Secret::new(scalar(BLIND)))).unwrap();
+
+        assert!(serialize_element(&blinded).to_vec() == hex(BLINDED_ELEMENT));
+
+        let (evaluated, proof) = keypair.blind_evaluate_with_randomness(&[blinded],
+                                                                        &scalar(PROOF_RANDOM_SCALAR));
+
+        assert!(serialize_element(&evaluated[0]).to_vec() == hex(EVALUATION_ELEMENT));
+        assert!(proof.to_bytes() == hex(PROOF));
+
+        let outputs = finalize(&[input], &[blind], &[blinded], &evaluated, &keypair.public, &proof).unwrap();
+
+        assert!(outputs[0] == hex(OUTPUT));
+        assert!(keypair.evaluate(input).unwrap() == hex(OUTPUT));
+    }
+
+    #[test]
+    fn batch_blind_evaluate_and_finalize() {
+        let mut csprng = thread_rng();
+        let keypair = ServerKeypair::generate(&mut csprng);
+        let inputs: [&[u8]; 3] = [b"one", b"two", b"three"];
+
+        let mut blinds: Vec<Blind> = Vec::new();
+        let mut blinded: Vec<RistrettoPoint> = Vec::new();
+
+        for input in inputs.iter() {
+            let (b, m) = blind(input, &mut csprng).unwrap();
+
+            blinds.push(b);
+            blinded.push(m);
+        }
+
+        let (evaluated, proof) = keypair.blind_evaluate(&blinded, &mut csprng);
+        let proof = Proof::from_bytes(&proof.to_bytes()).unwrap();
+        let outputs = finalize(&inputs, &blinds, &blinded, &evaluated, &keypair.public, &proof).unwrap();
+
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            assert!(*output == keypair.evaluate(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn finalize_rejects_wrong_key() {
+        let mut csprng = thread_rng();
+        let keypair = ServerKeypair::generate(&mut csprng);
+        let other = ServerKeypair::generate(&mut csprng);
+        let input: &[u8] = b"input";
+
+        let (b, blinded) = blind(input, &mut csprng).unwrap();
+        let (evaluated, proof) = keypair.blind_evaluate(&[blinded], &mut csprng);
+
+        assert!(finalize(&[input], &[b], &[blinded], &evaluated, &other.public, &proof)
+                == Err(VoprfError::VerificationFailure.into()));
+    }
+
+    #[test]
+    fn deserialize_element_rejects_identity() {
+        assert!(deserialize_element(&[0u8; 32]).is_err());
+        assert!(deserialize_element(&serialize_element(&RISTRETTO_BASEPOINT_POINT)).is_ok());
+    }
+}
@@ -0,0 +1,444 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A holder's collection of credentials, and keeping them valid.
+//!
+//! A `Wallet` keeps each credential as a `WalletEntry`: the `User` holding
+//! it, the time its issuer parameters stop being valid, and the identifier
+//! under which the issuer may revoke it.  From these the wallet reports, as
+//! `Notice`s,
+//!
+//! * with `Wallet::expiring()`, which credentials have expired or will
+//!   within some margin, and
+//! * with `Wallet::invalidated_by()`, which credentials a new
+//!   `RevocationDelta` revokes or suspends.
+//!
+//! `Wallet::refresh_all()` then has every expiring credential re-issued by
+//! an `IssuerClient`: for each, the wallet presents the old credential, asks
+//! for a new one over the same attributes under the issuer's current
+//! parameters, and replaces the entry only once the issuance verifies.  A
+//! revoked credential is left for the application to remove, since its
+//! issuer will refuse to re-issue it.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeMap;
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+use credential::CredentialIssuance;
+use credential::CredentialPresentation;
+use credential::CredentialRequest;
+use encoding::u64_to_bytes;
+use errors::CredentialError;
+use errors::Error;
+use issuer::ExpiringIssuerParameters;
+use nonces::Nonces;
+use parameters::NUMBER_OF_ATTRIBUTES;
+use revocation::RevocationDelta;
+use revocation::Suspension;
+use user::User;
+
+/// The issuer of a wallet's credentials, as seen from the wallet, e.g. a
+/// client of its re-issuance endpoint.
+pub trait IssuerClient {
+    /// The issuer's current parameters, and the period they are valid in.
+    fn parameters(&mut self) -> Result<ExpiringIssuerParameters, Error>;
+
+    /// Re-issue a credential for a `request` under the current parameters,
+    /// to the holder of the credential shown in `presentation`.
+    ///
+    /// The issuer decides what the `presentation` must prove, e.g. that it
+    /// verifies under its previous key and that the credential is not
+    /// revoked.
+    fn reissue(
+        &mut self,
+        presentation: &CredentialPresentation,
+        request: &CredentialRequest,
+    ) -> Result<CredentialIssuance, Error>;
+}
+
+/// A credential held in a `Wallet`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct WalletEntry {
+    /// The holder of the credential.
+    pub user: User,
+    /// The first second, since the Unix epoch, at which the credential's
+    /// issuer parameters are no longer valid.
+    pub not_after: u64,
+    /// The identifier under which the issuer may revoke the credential.
+    pub revocation_id: Vec<u8>,
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut x: u64 = 0;
+
+    for (i, byte) in bytes.iter().take(8).enumerate() {
+        x |= (*byte as u64) << (8 * i);
+    }
+    x
+}
+
+impl WalletEntry {
+    pub fn from_bytes(bytes: &[u8]) -> Result<WalletEntry, CredentialError> {
+        if bytes.len() < 16 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let not_after: u64 = read_u64(&bytes[..8]);
+        let length: usize = read_u64(&bytes[8..16]) as usize;
+
+        if bytes.len() - 16 < length {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let revocation_id: Vec<u8> = bytes[16..16 + length].to_vec();
+        let user: User = User::from_bytes(&bytes[16 + length..])?;
+
+        Ok(WalletEntry { user, not_after, revocation_id })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut user: Vec<u8> = self.user.to_bytes();
+        let mut v: Vec<u8> = Vec::with_capacity(16 + self.revocation_id.len() + user.len());
+
+        v.extend(u64_to_bytes(self.not_after).iter());
+        v.extend(u64_to_bytes(self.revocation_id.len() as u64).iter());
+        v.extend(self.revocation_id.iter());
+        v.extend(user.iter());
+
+        user.zeroize();
+        v
+    }
+
+    /// Export this entry, e.g. to back it up, as `WalletEntry::to_bytes()`,
+    /// in a buffer which is wiped when it is dropped.
+    ///
+    /// The export holds the user's secret key, and no other copy of it is
+    /// left behind on the heap.
+    pub fn export(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.to_bytes())
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(WalletEntry, "A valid byte sequence representing a WalletEntry");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(WalletEntry);
+impl_try_from_bytes!(WalletEntry, CredentialError);
+
+/// Why a credential in a `Wallet` needs its holder's attention.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reason {
+    /// The credential's issuer parameters expire at the given time.
+    Expiring(u64),
+    /// The credential's issuer parameters have expired.
+    Expired,
+    /// The credential was revoked.
+    Revoked,
+    /// The credential was suspended.
+    Suspended(Suspension),
+}
+
+/// A report about one credential in a `Wallet`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Notice {
+    /// The name the credential is kept under.
+    pub name: Vec<u8>,
+    pub reason: Reason,
+}
+
+/// What `Wallet::refresh_all()` did.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RefreshReport {
+    /// The names of the credentials which were re-issued.
+    pub refreshed: Vec<Vec<u8>>,
+    /// The names of the credentials which could not be, and why.  These are
+    /// kept as they were.
+    pub failed: Vec<(Vec<u8>, Error)>,
+}
+
+/// A holder's credentials, by names of their choosing.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Wallet {
+    entries: BTreeMap<Vec<u8>, WalletEntry>,
+}
+
+impl Wallet {
+    pub fn new() -> Wallet {
+        Wallet { entries: BTreeMap::new() }
+    }
+
+    /// Keep an `entry` under `name`, returning any entry it replaced.
+    pub fn insert(&mut self, name: &[u8], entry: WalletEntry) -> Option<WalletEntry> {
+        self.entries.insert(name.to_vec(), entry)
+    }
+
+    /// The entry kept under `name`.
+    pub fn get(&self, name: &[u8]) -> Option<&WalletEntry> {
+        self.entries.get(name)
+    }
+
+    /// Stop keeping the entry under `name`, returning it.
+    pub fn remove(&mut self, name: &[u8]) -> Option<WalletEntry> {
+        self.entries.remove(name)
+    }
+
+    /// The names of the entries, in order.
+    pub fn names(&self) -> Vec<&[u8]> {
+        self.entries.keys().map(|x| &x[..]).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The credentials which have expired by the time `now`, or will within
+    /// the following `margin` seconds.
+    pub fn expiring(&self, now: u64, margin: u64) -> Vec<Notice> {
+        let mut notices: Vec<Notice> = Vec::new();
+
+        for (name, entry) in self.entries.iter() {
+            let reason: Reason = if entry.not_after <= now {
+                Reason::Expired
+            } else if entry.not_after - now <= margin {
+                Reason::Expiring(entry.not_after)
+            } else {
+                continue;
+            };
+
+            notices.push(Notice { name: name.clone(), reason });
+        }
+        notices
+    }
+
+    /// The credentials which a `delta` to their issuer's revocation
+    /// registry revokes or suspends.
+    ///
+    /// The `delta` should already have been verified, e.g. with
+    /// `SignedRevocationDelta::verify()`.
+    pub fn invalidated_by(&self, delta: &RevocationDelta) -> Vec<Notice> {
+        let mut notices: Vec<Notice> = Vec::new();
+
+        for (name, entry) in self.entries.iter() {
+            let reason: Reason = if delta.added.contains(&entry.revocation_id) {
+                Reason::Revoked
+            } else {
+                match delta.suspended.iter().find(|&&(ref id, _)| id == &entry.revocation_id) {
+                    Some(&(_, suspension)) => Reason::Suspended(suspension),
+                    None                   => continue,
+                }
+            };
+
+            notices.push(Notice { name: name.clone(), reason });
+        }
+        notices
+    }
+
+    /// Have the credential under `name` re-issued by a `client`, under the
+    /// issuer's current `parameters`, keeping the old one on failure.
+    fn refresh<C, R>(
+        &mut self,
+        name: &[u8],
+        client: &mut C,
+        parameters: &ExpiringIssuerParameters,
+        rng: &mut R,
+    ) -> Result<(), Error>
+    where
+        C: IssuerClient,
+        R: RngCore + CryptoRng,
+    {
+        let entry: &mut WalletEntry = self.entries.get_mut(name).ok_or(CredentialError::MissingData)?;
+        let attributes = entry.user.credential.as_ref().ok_or(CredentialError::MissingData)?.attributes.clone();
+        let presentation = entry.user.show(&Nonces::new(rng, NUMBER_OF_ATTRIBUTES), rng)?;
+        let mut user = User::new(entry.user.system_parameters, parameters.parameters.clone(), entry.user.key.clone());
+        let request = user.obtain(attributes);
+        let issuance = client.reissue(&presentation, &request)?;
+
+        user.obtain_finish(Some(&issuance))?;
+
+        entry.user = user;
+        entry.not_after = parameters.not_after;
+
+        Ok(())
+    }
+
+    /// Have every credential which `Wallet::expiring()` reports re-issued by
+    /// a `client`.
+    ///
+    /// Each credential is re-issued separately, and one which fails is kept
+    /// as it was and reported, without stopping the others.
+    ///
+    /// # Errors
+    ///
+    /// * `CredentialError::Expired` if the `client`'s current parameters are
+    ///   not valid at the time `now`, in which case nothing is re-issued.
+    /// * Any error from `IssuerClient::parameters()`.
+    pub fn refresh_all<C, R>(
+        &mut self,
+        client: &mut C,
+        now: u64,
+        margin: u64,
+        rng: &mut R,
+    ) -> Result<RefreshReport, Error>
+    where
+        C: IssuerClient,
+        R: RngCore + CryptoRng,
+    {
+        let parameters: ExpiringIssuerParameters = client.parameters()?;
+        let mut report: RefreshReport = RefreshReport::default();
+
+        if !parameters.is_valid_at(now) {
+            return Err(CredentialError::Expired.into());
+        }
+
+        for notice in self.expiring(now, margin) {
+            match self.refresh(&notice.name, client, &parameters, rng) {
+                Ok(())  => report.refreshed.push(notice.name),
+                Err(x) => report.failed.push((notice.name, x)),
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    use issuer::Issuer;
+    use parameters::SystemParameters;
+    use test_utils::H;
+
+    /// An issuer which has rotated from its `old` key to a `new` one, and
+    /// re-issues any credential which verifies under the old key.
+    struct Rotated {
+        old: Issuer,
+        new: Issuer,
+        not_after: u64,
+    }
+
+    impl IssuerClient for Rotated {
+        fn parameters(&mut self) -> Result<ExpiringIssuerParameters, Error> {
+            Ok(self.new.get_expiring_issuer_parameters(0, self.not_after))
+        }
+
+        fn reissue(
+            &mut self,
+            presentation: &CredentialPresentation,
+            request: &CredentialRequest,
+        ) -> Result<CredentialIssuance, Error>
+        {
+            self.old.verify(presentation)?;
+            self.new.issue(request, &mut thread_rng())
+        }
+    }
+
+    fn entry(issuer: &Issuer, not_after: u64, revocation_id: &[u8]) -> WalletEntry {
+        let mut rng = thread_rng();
+        let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), None);
+        let issuance = issuer.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        WalletEntry { user, not_after, revocation_id: revocation_id.to_vec() }
+    }
+
+    #[test]
+    fn wallets_report_expiring_and_invalidated_credentials() {
+        let mut rng = thread_rng();
+        let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+        let mut wallet = Wallet::new();
+
+        wallet.insert(b"gym", entry(&issuer, 100, b"gym-id"));
+        wallet.insert(b"library", entry(&issuer, 1000, b"library-id"));
+        wallet.insert(b"transit", entry(&issuer, 150, b"transit-id"));
+
+        let decoded = WalletEntry::from_bytes(&wallet.get(b"gym").unwrap().to_bytes()).unwrap();
+
+        assert!(&decoded == wallet.get(b"gym").unwrap());
+
+        assert!(wallet.expiring(120, 50) == vec![
+            Notice { name: b"gym".to_vec(), reason: Reason::Expired },
+            Notice { name: b"transit".to_vec(), reason: Reason::Expiring(150) },
+        ]);
+        assert!(wallet.expiring(0, 10).is_empty());
+
+        let suspension = Suspension { from: 100, until: 200 };
+        let delta = RevocationDelta {
+            registry: b"members".to_vec(),
+            from_epoch: 1,
+            to_epoch: 2,
+            added: vec![b"library-id".to_vec(), b"someone-else".to_vec()],
+            removed: Vec::new(),
+            suspended: vec![(b"transit-id".to_vec(), suspension)],
+            lifted: Vec::new(),
+            digest: [0u8; 32],
+        };
+
+        assert!(wallet.invalidated_by(&delta) == vec![
+            Notice { name: b"library".to_vec(), reason: Reason::Revoked },
+            Notice { name: b"transit".to_vec(), reason: Reason::Suspended(suspension) },
+        ]);
+    }
+
+    #[test]
+    fn refreshing_reissues_only_expiring_credentials() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let old = Issuer::create(system_parameters, &mut rng);
+        let stranger = Issuer::create(system_parameters, &mut rng);
+        let mut wallet = Wallet::new();
+
+        wallet.insert(b"expiring", entry(&old, 100, b"a"));
+        wallet.insert(b"elsewhere", entry(&stranger, 100, b"b"));
+        wallet.insert(b"fresh", entry(&old, 1000, b"c"));
+
+        let attributes = wallet.get(b"expiring").unwrap().user.credential.as_ref().unwrap().attributes.clone();
+        let mut client = Rotated { old, new: Issuer::create(system_parameters, &mut rng), not_after: 2000 };
+        let report = wallet.refresh_all(&mut client, 50, 100, &mut rng).unwrap();
+
+        assert!(report.refreshed == vec![b"expiring".to_vec()]);
+        assert!(report.failed.len() == 1);
+        assert!(report.failed[0].0 == b"elsewhere".to_vec());
+
+        // The refreshed credential holds the same attributes under the new
+        // key, and verifies there.
+        let refreshed = wallet.get(b"expiring").unwrap();
+        let presentation = refreshed.user.show(&Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES), &mut rng).unwrap();
+
+        assert!(refreshed.not_after == 2000);
+        assert!(refreshed.user.credential.as_ref().unwrap().attributes == attributes);
+        assert!(client.new.verify(&presentation).is_ok());
+        assert!(wallet.get(b"fresh").unwrap().not_after == 1000);
+
+        // Parameters which are no longer valid re-issue nothing.
+        assert!(wallet.refresh_all(&mut client, 5000, 100, &mut rng) == Err(CredentialError::Expired.into()));
+    }
+}
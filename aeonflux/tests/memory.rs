@@ -0,0 +1,186 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! That secrets leave no recoverable copies on the heap once dropped.
+//!
+//! These tests run under an allocator which, while armed with a secret's
+//! bytes, reads every block freed back to it with volatile reads, and
+//! counts the blocks in which the secret is still to be found.
+
+extern crate aeonflux;
+extern crate curve25519_dalek;
+extern crate rand;
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand::thread_rng;
+
+use aeonflux::elgamal;
+use aeonflux::export::ExportedCommitment;
+use aeonflux::export::Generators;
+use aeonflux::issuer::Issuer;
+use aeonflux::nonces::Ephemeral;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::user::User;
+use aeonflux::wallet::WalletEntry;
+
+const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                      127,   4, 178,  70, 212, 141, 119, 112,
+                      153, 154, 135,  11, 227, 132, 247,  47,
+                       68, 192,  72, 200,  23,  88,  51,  82, ];
+
+/// The secret being looked for in freed blocks.
+static mut CANARY: [u8; 32] = [0u8; 32];
+static ARMED: AtomicBool = AtomicBool::new(false);
+static FOUND: AtomicUsize = AtomicUsize::new(0);
+
+/// Only one test may arm the allocator at once.
+static LOCK: Mutex<()> = Mutex::new(());
+
+struct Inspector;
+
+unsafe fn contains_canary(block: *const u8, size: usize) -> bool {
+    let canary: [u8; 32] = ptr::read_volatile(ptr::addr_of!(CANARY));
+
+    if size < canary.len() {
+        return false;
+    }
+    (0..size - canary.len() + 1).any(|start| {
+        (0..canary.len()).all(|i| ptr::read_volatile(block.add(start + i)) == canary[i])
+    })
+}
+
+unsafe impl GlobalAlloc for Inspector {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, block: *mut u8, layout: Layout) {
+        if ARMED.load(Ordering::SeqCst) && contains_canary(block, layout.size()) {
+            FOUND.fetch_add(1, Ordering::SeqCst);
+        }
+        System.dealloc(block, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Inspector = Inspector;
+
+/// Look for the `secret` in every block freed until `disarm()`.
+fn arm(secret: &[u8; 32]) {
+    unsafe { ptr::write_volatile(ptr::addr_of_mut!(CANARY), *secret); }
+    FOUND.store(0, Ordering::SeqCst);
+    ARMED.store(true, Ordering::SeqCst);
+}
+
+/// Stop looking, and return the number of freed blocks the secret was in.
+fn disarm() -> usize {
+    ARMED.store(false, Ordering::SeqCst);
+    FOUND.load(Ordering::SeqCst)
+}
+
+/// A copy of a secret `key`, taken before the allocator is armed.
+fn secret_bytes(key: &elgamal::SecretKey) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+
+    bytes.copy_from_slice(&key.to_bytes());
+    bytes
+}
+
+fn holder(issuer: &Issuer) -> User {
+    let mut rng = thread_rng();
+    let key = elgamal::Keypair::generate(&mut rng);
+    let mut user = User::new(issuer.system_parameters, issuer.get_issuer_parameters(), Some(key));
+    let issuance = issuer.issue(&user.obtain(vec![Scalar::random(&mut rng)]), &mut rng).unwrap();
+
+    user.obtain_finish(Some(&issuance)).unwrap();
+    user
+}
+
+#[test]
+fn the_inspector_finds_unwiped_secrets() {
+    let _lock = LOCK.lock().unwrap();
+    let secret: Box<[u8; 32]> = Box::new([0x5a; 32]);
+
+    arm(&secret);
+    drop(secret);
+
+    assert!(disarm() == 1);
+}
+
+#[test]
+fn dropped_keys_and_ephemerals_are_wiped() {
+    let _lock = LOCK.lock().unwrap();
+    let mut rng = thread_rng();
+
+    let key: Box<elgamal::SecretKey> = Box::new(elgamal::SecretKey::generate(&mut rng));
+
+    arm(&secret_bytes(&key));
+    drop(key);
+
+    assert!(disarm() == 0);
+
+    let scalar: Scalar = Scalar::random(&mut rng);
+    let ephemeral: Box<Ephemeral> = Box::new(Ephemeral::from(scalar));
+
+    arm(scalar.as_bytes());
+    drop(ephemeral);
+
+    assert!(disarm() == 0);
+
+    // A key borrowed as a witness leaves no copy behind once dropped.
+    let keypair: Box<elgamal::Keypair> = Box::new(elgamal::Keypair::generate(&mut rng));
+
+    arm(&secret_bytes(&keypair.secret));
+
+    let _ = keypair.secret.as_witness();
+
+    drop(keypair);
+
+    assert!(disarm() == 0);
+}
+
+#[test]
+fn dropped_blinding_factors_and_wallet_exports_are_wiped() {
+    let _lock = LOCK.lock().unwrap();
+    let mut rng = thread_rng();
+    let issuer = Issuer::create(SystemParameters::from(H), &mut rng);
+    let user = holder(&issuer);
+
+    let generators = Generators::hashed(b"memory tests");
+    let (_exported, opening) = ExportedCommitment::create(&user, &generators, b"", &mut rng).unwrap();
+    let opening = Box::new(opening);
+
+    arm(opening.blinding().as_bytes());
+    drop(opening);
+
+    assert!(disarm() == 0);
+
+    let key: [u8; 32] = secret_bytes(&user.key.as_ref().unwrap().secret);
+    let entry = WalletEntry { user: user, not_after: 0, revocation_id: vec![1, 2, 3] };
+
+    arm(&key);
+
+    let export = entry.export();
+
+    assert!(export.len() > 32);
+
+    drop(export);
+
+    assert!(disarm() == 0);
+}
@@ -0,0 +1,110 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Gate sessions under adversarial and lossy networks.
+
+extern crate aeonflux;
+extern crate curve25519_dalek;
+
+use curve25519_dalek::scalar::Scalar;
+
+use aeonflux::errors::Error;
+use aeonflux::errors::GateError;
+use aeonflux::gate::ClientState;
+use aeonflux::gate::GateClient;
+use aeonflux::gate::GateConfig;
+use aeonflux::gate::ServerState;
+use aeonflux::issuer::Issuer;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::rng::DeterministicRng;
+use aeonflux::simulation::Direction;
+use aeonflux::simulation::Event;
+use aeonflux::simulation::Simulation;
+use aeonflux::simulation::Tamper;
+use aeonflux::user::User;
+
+const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                      127,   4, 178,  70, 212, 141, 119, 112,
+                      153, 154, 135,  11, 227, 132, 247,  47,
+                       68, 192,  72, 200,  23,  88,  51,  82, ];
+
+fn setup(seed: u64) -> (Issuer, GateClient) {
+    let mut rng = DeterministicRng::from(seed);
+    let system_parameters = SystemParameters::from(H);
+    let issuer = Issuer::create(system_parameters, &mut rng);
+    let user = User::new(system_parameters, issuer.get_issuer_parameters(), None);
+
+    (issuer, GateClient::new(user, GateConfig::default()))
+}
+
+fn refused(events: &[Event], direction: Direction, error: Error) -> bool {
+    events.iter().any(|e| match *e {
+        Event::Refused { direction: d, error: x, .. } => d == direction && x == error,
+        _                                             => false,
+    })
+}
+
+#[test]
+fn downgraded_envelopes_are_refused() {
+    let (issuer, mut client) = setup(1);
+    let mut simulation = Simulation::new(&issuer, GateConfig::default(), 1);
+
+    simulation.script(Direction::ToServer, &[Tamper::Downgrade]);
+
+    let outcome = simulation.obtain(&mut client, vec![Scalar::one()]);
+
+    assert!(outcome.client == ClientState::Failed(GateError::Timeout));
+    assert!(outcome.server == ServerState::AwaitingRequest { since: 0 });
+    assert!(refused(&simulation.events, Direction::ToServer, Error::Unsupported));
+}
+
+#[test]
+fn replayed_requests_are_answered_once() {
+    let (issuer, mut client) = setup(2);
+    let mut simulation = Simulation::new(&issuer, GateConfig::default(), 2);
+
+    assert!(simulation.obtain(&mut client, vec![Scalar::one()]).client == ClientState::Holding);
+
+    // The issuance request from the first session arrives before the
+    // presentation, and is answered instead of it.
+    simulation.script(Direction::ToServer, &[Tamper::Replay]);
+
+    let outcome = simulation.present(&mut client);
+
+    assert!(outcome.server == ServerState::Issued);
+    assert!(outcome.client == ClientState::Failed(GateError::Timeout));
+    assert!(refused(&simulation.events, Direction::ToServer, Error::Protocol));
+    assert!(refused(&simulation.events, Direction::ToClient, Error::Protocol));
+
+    // The client may simply present again.
+    assert!(simulation.present(&mut client).client == ClientState::Admitted);
+}
+
+#[test]
+fn lossy_networks_eventually_admit() {
+    let (issuer, mut client) = setup(3);
+    let mut simulation = Simulation::new(&issuer, GateConfig::default(), 3);
+
+    simulation.loss = 50;
+
+    for _ in 0..64 {
+        if simulation.obtain(&mut client, vec![Scalar::one()]).client == ClientState::Holding {
+            break;
+        }
+    }
+    assert!(client.state() == ClientState::Holding);
+
+    for _ in 0..64 {
+        if simulation.present(&mut client).client == ClientState::Admitted {
+            break;
+        }
+    }
+    assert!(client.state() == ClientState::Admitted);
+    assert!(simulation.events.iter().any(|e| match *e { Event::Dropped { .. } => true, _ => false }));
+}
@@ -0,0 +1,49 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Serve the `CredentialIssuer` service from an issuer saved with
+//! `Issuer::to_bytes()`.
+//!
+//! ```text
+//! world-gate-grpc ISSUER_FILE [ADDRESS]
+//! ```
+//!
+//! The address defaults to `0.0.0.0:50051`.
+
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::process;
+
+use aeonflux::issuer::Issuer;
+
+use world_gate_grpc::IssuerService;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:50051";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let path = match args.next() {
+        Some(path) => path,
+        None       => {
+            eprintln!("usage: world-gate-grpc ISSUER_FILE [ADDRESS]");
+            process::exit(2);
+        },
+    };
+    let address: SocketAddr = args.next().as_deref().unwrap_or(DEFAULT_ADDRESS).parse()?;
+    let issuer = Issuer::from_bytes(&fs::read(&path)?).map_err(|e| format!("{}: {}", path, e))?;
+
+    tonic::transport::Server::builder()
+        .add_service(IssuerService::new(issuer).into_server())
+        .serve(address)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! gRPC wire types, generated from `proto/worldgate.proto`, and, with the
+//! `server` feature, a tonic service which serves them from an aeonflux
+//! `Issuer`.
+//!
+//! ```rust,ignore
+//! let service = IssuerService::new(issuer);
+//!
+//! tonic::transport::Server::builder()
+//!     .add_service(service.into_server())
+//!     .serve("0.0.0.0:50051".parse()?)
+//!     .await?;
+//! ```
+//!
+//! Protocol messages travel as opaque `bytes`, in the encoding produced by
+//! the corresponding `to_bytes()` method.
+//!
+//! The `world-gate-grpc` binary, also behind the `server` feature, serves an
+//! issuer saved with `Issuer::to_bytes()`.
+
+/// The types and service traits generated from `proto/worldgate.proto`.
+pub mod proto {
+    tonic::include_proto!("worldgate.v1");
+}
+
+#[cfg(feature = "server")]
+pub mod server;
+
+#[cfg(feature = "server")]
+pub use server::IssuerService;
@@ -0,0 +1,223 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A tonic service which serves the `CredentialIssuer` definitions from an
+//! aeonflux `Issuer`.
+//!
+//! This module is only available with the `server` feature.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use aeonflux::credential::CredentialBlindRequest;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::errors::Error;
+use aeonflux::issuer::Issuer;
+use aeonflux::rng::default_rng;
+
+use futures_core::Stream;
+
+use tokio::sync::broadcast;
+
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::BroadcastStream;
+
+use tonic::Request;
+use tonic::Response;
+use tonic::Status;
+
+use crate::proto;
+use crate::proto::credential_issuer_server::CredentialIssuer;
+use crate::proto::credential_issuer_server::CredentialIssuerServer;
+
+/// The number of revocation updates buffered for each slow subscriber
+/// before they start missing updates.
+pub const REVOCATION_CHANNEL_CAPACITY: usize = 64;
+
+/// Map an aeonflux error to a gRPC `Status`.
+pub fn status_from_error<E: Into<Error>>(error: E) -> Status {
+    let error: Error = error.into();
+
+    match error {
+        Error::Proof(_) |
+        Error::Expired  => Status::permission_denied(error.to_string()),
+        Error::Misuse   => Status::internal(error.to_string()),
+        _               => Status::invalid_argument(error.to_string()),
+    }
+}
+
+/// A tonic implementation of the `CredentialIssuer` service.
+#[derive(Clone)]
+pub struct IssuerService {
+    issuer: Arc<Issuer>,
+    revocations: broadcast::Sender<proto::RevocationUpdate>,
+}
+
+impl IssuerService {
+    pub fn new(issuer: Issuer) -> IssuerService {
+        let (revocations, _) = broadcast::channel(REVOCATION_CHANNEL_CAPACITY);
+
+        IssuerService { issuer: Arc::new(issuer), revocations }
+    }
+
+    /// Publish a revocation update to every current subscriber.
+    ///
+    /// # Returns
+    ///
+    /// The number of subscribers the update was sent to.
+    pub fn publish_revocation(&self, update: proto::RevocationUpdate) -> usize {
+        self.revocations.send(update).unwrap_or(0)
+    }
+
+    /// Wrap this service for adding to a `tonic::transport::Server`.
+    pub fn into_server(self) -> CredentialIssuerServer<IssuerService> {
+        CredentialIssuerServer::new(self)
+    }
+}
+
+type RevocationStream = Pin<Box<dyn Stream<Item = Result<proto::RevocationUpdate, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl CredentialIssuer for IssuerService {
+    async fn get_parameters(
+        &self,
+        _request: Request<proto::GetParametersRequest>,
+    ) -> Result<Response<proto::GetParametersResponse>, Status>
+    {
+        Ok(Response::new(proto::GetParametersResponse {
+            system_parameters: self.issuer.system_parameters.to_bytes(),
+            issuer_parameters: self.issuer.get_issuer_parameters().to_bytes(),
+        }))
+    }
+
+    async fn issue(
+        &self,
+        request: Request<proto::IssueRequest>,
+    ) -> Result<Response<proto::IssueResponse>, Status>
+    {
+        let request = CredentialRequest::from_bytes(&request.get_ref().request)
+            .map_err(status_from_error)?;
+        let issuance = self.issuer.issue(&request, &mut default_rng())
+            .map_err(status_from_error)?;
+
+        Ok(Response::new(proto::IssueResponse { issuance: issuance.to_bytes() }))
+    }
+
+    async fn blind_issue(
+        &self,
+        request: Request<proto::BlindIssueRequest>,
+    ) -> Result<Response<proto::BlindIssueResponse>, Status>
+    {
+        let request = CredentialBlindRequest::from_bytes(&request.get_ref().request)
+            .map_err(status_from_error)?;
+        let issuance = self.issuer.blind_issue(&request, &mut default_rng())
+            .map_err(status_from_error)?;
+
+        Ok(Response::new(proto::BlindIssueResponse { issuance: issuance.to_bytes() }))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<proto::VerifyRequest>,
+    ) -> Result<Response<proto::VerifyResponse>, Status>
+    {
+        let presentation = CredentialPresentation::from_bytes(&request.get_ref().presentation)
+            .map_err(status_from_error)?;
+
+        match self.issuer.verify(&presentation) {
+            Ok(_)                => Ok(Response::new(proto::VerifyResponse { valid: true })),
+            Err(Error::Proof(_)) => Ok(Response::new(proto::VerifyResponse { valid: false })),
+            Err(e)               => Err(status_from_error(e)),
+        }
+    }
+
+    type SubscribeRevocationsStream = RevocationStream;
+
+    async fn subscribe_revocations(
+        &self,
+        _request: Request<proto::SubscribeRevocationsRequest>,
+    ) -> Result<Response<Self::SubscribeRevocationsStream>, Status>
+    {
+        let stream = BroadcastStream::new(self.revocations.subscribe())
+            .map(|update| update.map_err(|_| Status::data_loss("missed revocation updates; resubscribe")));
+
+        Ok(Response::new(Box::pin(stream) as RevocationStream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use aeonflux::nonces::Nonces;
+    use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
+    use aeonflux::parameters::SystemParameters;
+    use aeonflux::user::User;
+
+    use rand::thread_rng;
+
+    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+    fn service() -> IssuerService {
+        IssuerService::new(Issuer::create(SystemParameters::from(H), &mut thread_rng()))
+    }
+
+    #[tokio::test]
+    async fn issue_and_verify() {
+        let service = service();
+        let parameters = service.get_parameters(Request::new(proto::GetParametersRequest {}))
+            .await.unwrap().into_inner();
+        let system_parameters = SystemParameters::from_bytes(&parameters.system_parameters).unwrap();
+        let issuer_parameters = aeonflux::issuer::IssuerParameters::from_bytes(&parameters.issuer_parameters)
+            .unwrap();
+        let mut user = User::new(system_parameters, issuer_parameters, None);
+
+        let request = user.obtain(vec![Scalar::random(&mut thread_rng())]).to_bytes();
+        let issuance = service.issue(Request::new(proto::IssueRequest { request }))
+            .await.unwrap().into_inner().issuance;
+
+        user.obtain_finish(Some(&aeonflux::credential::CredentialIssuance::from_bytes(&issuance).unwrap()))
+            .unwrap();
+
+        let presentation = {
+            let mut rng = thread_rng();
+            let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+
+            user.show(&nonces, &mut rng).unwrap().to_bytes()
+        };
+        let response = service.verify(Request::new(proto::VerifyRequest { presentation }))
+            .await.unwrap().into_inner();
+
+        assert!(response.valid);
+    }
+
+    #[tokio::test]
+    async fn malformed_request_is_invalid_argument() {
+        let status = service().issue(Request::new(proto::IssueRequest { request: vec![1, 2, 3] }))
+            .await.unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn revocation_updates_are_streamed() {
+        let service = service();
+        let mut stream = service.subscribe_revocations(Request::new(proto::SubscribeRevocationsRequest {}))
+            .await.unwrap().into_inner();
+        let update = proto::RevocationUpdate { epoch: 7, revoked: vec![vec![0u8; 32]] };
+
+        assert_eq!(service.publish_revocation(update.clone()), 1);
+        assert_eq!(stream.next().await.unwrap().unwrap(), update);
+    }
+}
@@ -0,0 +1,26 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary bytes as group states, updates, and requests.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate signal_credential;
+
+use signal_credential::group::GroupRequest;
+use signal_credential::group::GroupState;
+use signal_credential::group::GroupStateUpdate;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = GroupState::from_bytes(data);
+    let _ = GroupStateUpdate::from_bytes(data);
+    let _ = GroupRequest::from_bytes(data);
+});
@@ -0,0 +1,31 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary bytes as rosters, roster deltas, and roster entries,
+//! checking that whatever decodes re-encodes to the same value.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate signal_credential;
+
+use signal_credential::phone_number::RosterEntryCommitment;
+use signal_credential::roster::GroupMembershipRoster;
+use signal_credential::roster::RosterDelta;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(roster) = GroupMembershipRoster::from_bytes(data) {
+        assert!(GroupMembershipRoster::from_bytes(&roster.to_bytes()).unwrap() == roster);
+    }
+    if let Ok(delta) = RosterDelta::from_bytes(data) {
+        assert!(RosterDelta::from_bytes(&delta.to_bytes()).unwrap() == delta);
+    }
+    let _ = RosterEntryCommitment::from_bytes(data);
+});
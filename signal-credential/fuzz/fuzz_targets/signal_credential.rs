@@ -0,0 +1,33 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Decode arbitrary bytes as Signal credential presentations, phone
+//! numbers, and the issuer and user state loaded from storage.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate signal_credential;
+
+use signal_credential::credential::SignalCredentialPresentation;
+use signal_credential::credential::VerifiedSignalCredential;
+use signal_credential::issuer::SignalIssuer;
+use signal_credential::phone_number::CommittedPhoneNumber;
+use signal_credential::phone_number::PhoneNumber;
+use signal_credential::user::SignalUser;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SignalCredentialPresentation::from_bytes(data);
+    let _ = VerifiedSignalCredential::from_bytes(data);
+    let _ = PhoneNumber::from_bytes(data);
+    let _ = CommittedPhoneNumber::from_bytes(data);
+    let _ = SignalIssuer::from_bytes(data);
+    let _ = SignalUser::from_bytes(data);
+});
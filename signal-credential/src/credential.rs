@@ -16,12 +16,14 @@ use std::vec::Vec;
 
 use aeonflux::credential::SIZEOF_CREDENTIAL_PRESENTATION;
 use aeonflux::credential::Credential;
+use aeonflux::credential::CredentialBlindIssuance;
+use aeonflux::credential::CredentialBlindRequest;
 use aeonflux::credential::CredentialIssuance;
 use aeonflux::credential::CredentialPresentation;
 use aeonflux::errors::CredentialError;
 use aeonflux::proofs::committed_values_equal;
 
-use bincode::{deserialize, serialize};
+use bincode::deserialize;
 
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
@@ -48,6 +50,12 @@ pub const NUMBER_OF_ATTRIBUTES: usize =
 
 pub type SignalCredentialIssuance = CredentialIssuance;
 
+/// A request for a `SignalCredential` over an encrypted phone number.
+pub type SignalCredentialBlindRequest = CredentialBlindRequest;
+
+/// A `SignalCredential` issued over an encrypted phone number.
+pub type SignalCredentialBlindIssuance = CredentialBlindIssuance;
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SignalCredentialPresentation {
     /// The user's corresponding committed phone number in a roster entry.
@@ -94,22 +102,15 @@ impl SignalCredentialPresentation {
         v.extend(self.roster_entry_commitment.to_bytes());
         v.extend(self.presentation.to_bytes());
 
-        let serialized = match serialize(&self.roster_membership_proof) {
-            Ok(x)   => x,
-            Err(_x) => {
-                #[cfg(feature = "std")]
-                println!("Error while serializing SignalCredentialPresentation: {}", _x);
-                panic!();  // XXX clean this up
-            },
-        };
-
-        v.extend(serialized);
+        v.extend(self.roster_membership_proof.to_bytes().iter());
         v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(SignalCredentialPresentation,
                                          "A valid byte sequence representing a SignalCredentialPresentation");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SignalCredentialPresentation);
 
 /// An anonymous credential belonging to a `SignalUser` and issued and verified
 /// by a `SignalIssuer`.
@@ -137,6 +138,8 @@ impl VerifiedSignalCredential {
 
 impl_serde_with_to_bytes_and_from_bytes!(VerifiedSignalCredential,
                                          "A valid byte sequence representing a VerifiedSignalCredential");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(VerifiedSignalCredential);
 
 #[cfg(test)]
 mod test {
@@ -20,20 +20,45 @@ use core::option::NoneError;
 use std::option::NoneError;
 
 use aeonflux::errors::CredentialError;
+use aeonflux::errors::Error;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum PhoneNumberError {
     LengthExceeded,
     InvalidPhoneNumber,
+    /// A phone number contained a character which was not a decimal digit.
+    InvalidDigit,
+    /// An E.164 phone number did not begin with a `+`.
+    MissingE164Prefix,
+    /// An E.164 phone number had fewer digits than any valid number.
+    TooShort,
+    /// An E.164 phone number's country code began with a zero.
+    InvalidCountryCode,
+    /// A scalar was not canonically encoded.
+    ScalarFormatError,
+    /// A point was not the valid encoding of a group element.
+    PointDecompressionError,
 }
 
 impl fmt::Display for PhoneNumberError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             PhoneNumberError::LengthExceeded
-                => write!(f, "A canonicalised phone number cannot be more than 32 bytes"),
+                => write!(f, "The phone number has too many digits"),
             PhoneNumberError::InvalidPhoneNumber
                 => write!(f, "The user's proof of roster membership could not be verified"),
+            PhoneNumberError::InvalidDigit
+                => write!(f, "A phone number may only contain the digits 0 through 9"),
+            PhoneNumberError::MissingE164Prefix
+                => write!(f, "An E.164 phone number must begin with a '+'"),
+            PhoneNumberError::TooShort
+                => write!(f, "The phone number has too few digits"),
+            PhoneNumberError::InvalidCountryCode
+                => write!(f, "A country code cannot begin with a zero"),
+            PhoneNumberError::ScalarFormatError
+                => write!(f, "A scalar was not canonically encoded"),
+            PhoneNumberError::PointDecompressionError
+                => write!(f, "A point was not the valid encoding of a group element"),
         }
     }
 }
@@ -47,13 +72,168 @@ impl From<NoneError> for PhoneNumberError {
 }
 
 impl From<PhoneNumberError> for CredentialError {
-    fn from(_source: PhoneNumberError) -> CredentialError {
-        NoneError.into()
+    fn from(source: PhoneNumberError) -> CredentialError {
+        match source {
+            PhoneNumberError::ScalarFormatError       => CredentialError::ScalarFormatError,
+            PhoneNumberError::PointDecompressionError => CredentialError::PointDecompressionError,
+            _                                         => NoneError.into(),
+        }
+    }
+}
+
+impl From<PhoneNumberError> for Error {
+    fn from(source: PhoneNumberError) -> Error {
+        CredentialError::from(source).into()
     }
 }
 
 impl From<CredentialError> for PhoneNumberError {
-    fn from(_source: CredentialError) -> PhoneNumberError {
-        NoneError.into()
+    fn from(source: CredentialError) -> PhoneNumberError {
+        match source {
+            CredentialError::ScalarFormatError       => PhoneNumberError::ScalarFormatError,
+            CredentialError::PointDecompressionError => PhoneNumberError::PointDecompressionError,
+            _                                        => NoneError.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RosterError {
+    BaseDigestMismatch,
+    DuplicateEntry,
+    MissingEntry,
+    NonCanonicalEncoding,
+    PointDecompressionError,
+    ResultDigestMismatch,
+    UnknownMembershipLevel,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for RosterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RosterError::BaseDigestMismatch
+                => write!(f, "The roster delta does not apply to this roster"),
+            RosterError::DuplicateEntry
+                => write!(f, "The entry is already in the roster"),
+            RosterError::MissingEntry
+                => write!(f, "The entry is not in the roster"),
+            RosterError::NonCanonicalEncoding
+                => write!(f, "The roster entries were not strictly sorted"),
+            RosterError::PointDecompressionError
+                => write!(f, "Cannot decompress Ristretto point"),
+            RosterError::ResultDigestMismatch
+                => write!(f, "Applying the roster delta did not produce the expected roster"),
+            RosterError::UnknownMembershipLevel
+                => write!(f, "The group membership level was not recognised"),
+            RosterError::WrongNumberOfBytes
+                => write!(f, "The roster could not be deserialised because its length was incorrect"),
+        }
+    }
+}
+
+impl ::failure::Fail for RosterError { }
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GroupError {
+    Credential(Error),
+    /// The member's permissions level does not allow the requested action.
+    InsufficientPermissions,
+    /// The presented roster entry is not in the group's roster.
+    NotAMember,
+    Roster(RosterError),
+    /// The request or update was made for a different epoch of the group.
+    StaleEpoch,
+    UnknownAction,
+    /// The request or update was for a different group.
+    WrongGroup,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GroupError::Credential(ref e)
+                => write!(f, "Credential protocol error: {}", e),
+            GroupError::InsufficientPermissions
+                => write!(f, "The member is not permitted to perform this action"),
+            GroupError::NotAMember
+                => write!(f, "The roster entry is not a member of the group"),
+            GroupError::Roster(ref e)
+                => write!(f, "Group roster error: {}", e),
+            GroupError::StaleEpoch
+                => write!(f, "The group state has changed since this was created"),
+            GroupError::UnknownAction
+                => write!(f, "The group action was not recognised"),
+            GroupError::WrongGroup
+                => write!(f, "This was created for a different group"),
+            GroupError::WrongNumberOfBytes
+                => write!(f, "The group data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for GroupError { }
+
+impl From<CredentialError> for GroupError {
+    fn from(source: CredentialError) -> GroupError {
+        GroupError::Credential(source.into())
+    }
+}
+
+impl From<Error> for GroupError {
+    fn from(source: Error) -> GroupError {
+        GroupError::Credential(source)
+    }
+}
+
+impl From<RosterError> for GroupError {
+    fn from(source: RosterError) -> GroupError {
+        GroupError::Roster(source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ZkGroupError {
+    /// A group's epoch was too large for the protobuf `revision` field.
+    EpochOutOfRange,
+    /// A value did not begin with zkgroup's reserved version byte.
+    InvalidReservedByte,
+    /// A `userId` was not a framed `CommittedPhoneNumber`.
+    InvalidUserId,
+    /// A protobuf message was truncated or had a field of the wrong type.
+    MalformedProtobuf,
+    Roster(RosterError),
+    /// A member's role was neither `DEFAULT` nor `ADMINISTRATOR`.
+    UnknownRole,
+    WrongNumberOfBytes,
+}
+
+impl fmt::Display for ZkGroupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ZkGroupError::EpochOutOfRange
+                => write!(f, "The group epoch does not fit in a zkgroup revision"),
+            ZkGroupError::InvalidReservedByte
+                => write!(f, "The zkgroup version byte was not recognised"),
+            ZkGroupError::InvalidUserId
+                => write!(f, "The member's user ID was not a committed phone number"),
+            ZkGroupError::MalformedProtobuf
+                => write!(f, "The protobuf message was malformed"),
+            ZkGroupError::Roster(ref e)
+                => write!(f, "Group roster error: {}", e),
+            ZkGroupError::UnknownRole
+                => write!(f, "The member's role was not recognised"),
+            ZkGroupError::WrongNumberOfBytes
+                => write!(f, "The zkgroup data was not the correct length"),
+        }
+    }
+}
+
+impl ::failure::Fail for ZkGroupError { }
+
+impl From<RosterError> for ZkGroupError {
+    fn from(source: RosterError) -> ZkGroupError {
+        ZkGroupError::Roster(source)
     }
 }
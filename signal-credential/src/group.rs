@@ -0,0 +1,553 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Gating access to a Signal group.
+//!
+//! The server keeps a `SignalGroup`, i.e. the current `GroupState` of the
+//! group: its ID, an epoch which is incremented on every change, and a
+//! `GroupMembershipRoster` of committed phone numbers.  Each member keeps a
+//! `GroupMembership`, holding their own copy of the `GroupState` and the
+//! opening of their `RosterEntryCommitment`, along with a `SignalUser` whose
+//! credential is over their phone number.
+//!
+//! To fetch or post to the group, a member makes a `GroupRequest` containing a
+//! `SignalCredentialPresentation`, which proves that their roster entry is a
+//! commitment to the same phone number as their credential, without revealing
+//! which entry it is to anyone who cannot open the roster.  Changing the
+//! roster requires a request from an admin, or from an owner if any owners
+//! are being added or removed, and results in a `GroupStateUpdate` which the
+//! other members apply to their own copies of the state.
+//!
+//! # Warning
+//!
+//! The credential presentation within a `GroupRequest` is not bound to the
+//! request's action or epoch, so requests MUST be sent over a channel which
+//! prevents them from being replayed.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use rand_core::RngCore;
+use rand_core::CryptoRng;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use credential::SignalCredentialPresentation;
+use errors::GroupError;
+use issuer::SignalIssuer;
+use phone_number::CommittedPhoneNumber;
+use phone_number::RosterEntryCommitment;
+use roster::GroupMembershipLevel;
+use roster::GroupMembershipRoster;
+use roster::RosterDelta;
+use user::SignalUser;
+
+pub const SIZEOF_GROUP_ID: usize = 32;
+
+/// The size of an encoded group ID and epoch.
+const SIZEOF_GROUP_HEADER: usize = SIZEOF_GROUP_ID + 8;
+
+/// A random, unique identifier for a group.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct GroupId(pub [u8; SIZEOF_GROUP_ID]);
+
+impl GroupId {
+    /// Choose a new random `GroupId`.
+    pub fn random<R>(csprng: &mut R) -> GroupId
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut id = [0u8; SIZEOF_GROUP_ID];
+
+        csprng.fill_bytes(&mut id);
+
+        GroupId(id)
+    }
+}
+
+fn write_header(v: &mut Vec<u8>, id: &GroupId, epoch: u64) {
+    v.extend(id.0.iter());
+    v.extend(epoch.to_le_bytes().iter());
+}
+
+fn read_header(bytes: &[u8]) -> Result<(GroupId, u64), GroupError> {
+    if bytes.len() < SIZEOF_GROUP_HEADER {
+        return Err(GroupError::WrongNumberOfBytes);
+    }
+    let mut id = [0u8; SIZEOF_GROUP_ID];
+    let mut epoch = [0u8; 8];
+
+    id.copy_from_slice(&bytes[..SIZEOF_GROUP_ID]);
+    epoch.copy_from_slice(&bytes[SIZEOF_GROUP_ID..SIZEOF_GROUP_HEADER]);
+
+    Ok((GroupId(id), u64::from_le_bytes(epoch)))
+}
+
+/// Something a member may ask to do within a group.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum GroupAction {
+    /// Fetch the group's messages or state.
+    Fetch = 0,
+    /// Post a message to the group.
+    Post = 1,
+    /// Change the group's roster.
+    Modify = 2,
+}
+
+impl GroupAction {
+    pub fn from_byte(byte: u8) -> Result<GroupAction, GroupError> {
+        match byte {
+            0 => Ok(GroupAction::Fetch),
+            1 => Ok(GroupAction::Post),
+            2 => Ok(GroupAction::Modify),
+            _ => Err(GroupError::UnknownAction),
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The least privileged `GroupMembershipLevel` allowed to take this action.
+    pub fn required_level(&self) -> GroupMembershipLevel {
+        match *self {
+            GroupAction::Fetch  => GroupMembershipLevel::User,
+            GroupAction::Post   => GroupMembershipLevel::User,
+            GroupAction::Modify => GroupMembershipLevel::Admin,
+        }
+    }
+}
+
+/// Check that a member at `level` is at least as privileged as `required`.
+fn check_level(level: GroupMembershipLevel, required: GroupMembershipLevel) -> Result<(), GroupError> {
+    // Lower levels are more privileged.
+    if level <= required {
+        Ok(())
+    } else {
+        Err(GroupError::InsufficientPermissions)
+    }
+}
+
+/// The state of a group, as agreed upon by the server and its members.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GroupState {
+    pub id: GroupId,
+    /// The number of changes which have been made to the group.
+    pub epoch: u64,
+    pub roster: GroupMembershipRoster,
+}
+
+impl GroupState {
+    pub fn from_bytes(bytes: &[u8]) -> Result<GroupState, GroupError> {
+        let (id, epoch) = read_header(bytes)?;
+        let roster = GroupMembershipRoster::from_bytes(&bytes[SIZEOF_GROUP_HEADER..])?;
+
+        Ok(GroupState { id, epoch, roster })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        write_header(&mut v, &self.id, self.epoch);
+        v.extend(self.roster.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(GroupState, "A valid byte sequence representing a GroupState");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(GroupState);
+
+impl GroupState {
+    /// Apply an `update` to this state.
+    ///
+    /// # Errors
+    ///
+    /// * `GroupError::WrongGroup` if the `update` is for another group.
+    /// * `GroupError::StaleEpoch` if the `update` is not for the next epoch.
+    /// * `GroupError::Roster` if the `update`'s delta could not be applied to
+    ///   our roster.
+    pub fn apply(&mut self, update: &GroupStateUpdate) -> Result<(), GroupError> {
+        if update.id != self.id {
+            return Err(GroupError::WrongGroup);
+        }
+        if Some(update.epoch) != self.epoch.checked_add(1) {
+            return Err(GroupError::StaleEpoch);
+        }
+        self.roster.apply(&update.delta)?;
+        self.epoch = update.epoch;
+
+        Ok(())
+    }
+}
+
+/// A change from one epoch of a `GroupState` to the next.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GroupStateUpdate {
+    pub id: GroupId,
+    /// The epoch of the group after this update is applied.
+    pub epoch: u64,
+    pub delta: RosterDelta,
+}
+
+impl GroupStateUpdate {
+    pub fn from_bytes(bytes: &[u8]) -> Result<GroupStateUpdate, GroupError> {
+        let (id, epoch) = read_header(bytes)?;
+        let delta = RosterDelta::from_bytes(&bytes[SIZEOF_GROUP_HEADER..])?;
+
+        Ok(GroupStateUpdate { id, epoch, delta })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        write_header(&mut v, &self.id, self.epoch);
+        v.extend(self.delta.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(GroupStateUpdate,
+                                         "A valid byte sequence representing a GroupStateUpdate");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(GroupStateUpdate);
+
+/// A member's request to take some action within a group.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GroupRequest {
+    pub id: GroupId,
+    /// The epoch of the group state which the member made this request in.
+    pub epoch: u64,
+    pub action: GroupAction,
+    pub presentation: SignalCredentialPresentation,
+}
+
+impl GroupRequest {
+    pub fn from_bytes(bytes: &[u8]) -> Result<GroupRequest, GroupError> {
+        let (id, epoch) = read_header(bytes)?;
+
+        if bytes.len() < SIZEOF_GROUP_HEADER + 1 {
+            return Err(GroupError::WrongNumberOfBytes);
+        }
+        let action = GroupAction::from_byte(bytes[SIZEOF_GROUP_HEADER])?;
+        let presentation = SignalCredentialPresentation::from_bytes(&bytes[SIZEOF_GROUP_HEADER + 1..])?;
+
+        Ok(GroupRequest { id, epoch, action, presentation })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::new();
+
+        write_header(&mut v, &self.id, self.epoch);
+        v.push(self.action.to_byte());
+        v.extend(self.presentation.to_bytes());
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(GroupRequest, "A valid byte sequence representing a GroupRequest");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(GroupRequest);
+
+/// The server's view of a group.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignalGroup {
+    state: GroupState,
+}
+
+impl SignalGroup {
+    /// Create a new group whose only member is its `owner`.
+    pub fn create(id: GroupId, owner: CommittedPhoneNumber) -> SignalGroup {
+        let mut roster = GroupMembershipRoster::new();
+
+        // The roster is empty, so this cannot be a duplicate.
+        let _ = roster.insert(GroupMembershipLevel::Owner, owner);
+
+        SignalGroup { state: GroupState { id, epoch: 0, roster } }
+    }
+
+    /// Restore a group from its saved `state`.
+    pub fn from_state(state: GroupState) -> SignalGroup {
+        SignalGroup { state }
+    }
+
+    /// The current state of this group, for sending to new members.
+    pub fn state(&self) -> &GroupState {
+        &self.state
+    }
+
+    /// Check that a `request` is from a member of this group who is permitted
+    /// to take the requested action.
+    ///
+    /// # Errors
+    ///
+    /// * `GroupError::WrongGroup` or `GroupError::StaleEpoch` if the
+    ///   `request` was made for another group or epoch.
+    /// * `GroupError::Credential` if the credential presentation or the proof
+    ///   of roster membership did not verify.
+    /// * `GroupError::NotAMember` if the presented roster entry is not in the
+    ///   roster.
+    /// * `GroupError::InsufficientPermissions` if the member is not
+    ///   privileged enough to take the action.
+    ///
+    /// # Returns
+    ///
+    /// The member's `GroupMembershipLevel`.
+    pub fn authorize(
+        &self,
+        issuer: &SignalIssuer,
+        request: &GroupRequest,
+    ) -> Result<GroupMembershipLevel, GroupError>
+    {
+        if request.id != self.state.id {
+            return Err(GroupError::WrongGroup);
+        }
+        if request.epoch != self.state.epoch {
+            return Err(GroupError::StaleEpoch);
+        }
+
+        let verified = issuer.verify(request.presentation.clone())?;
+        let entry: CommittedPhoneNumber = issuer.verify_roster_membership(&verified)?;
+        let level: GroupMembershipLevel = match self.state.roster.level_of(&entry) {
+            Some(x) => x,
+            None    => return Err(GroupError::NotAMember),
+        };
+
+        check_level(level, request.action.required_level())?;
+
+        Ok(level)
+    }
+
+    /// Replace this group's roster with `roster`, if the `request` is from a
+    /// member permitted to make the change.
+    ///
+    /// Adding or removing an owner requires the request to come from an
+    /// owner; any other change requires at least an admin.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from `SignalGroup::authorize()`.
+    /// * `GroupError::InsufficientPermissions` if the `request` was not for
+    ///   `GroupAction::Modify`.
+    ///
+    /// # Returns
+    ///
+    /// A `GroupStateUpdate` for distributing to the group's members.
+    pub fn modify(
+        &mut self,
+        issuer: &SignalIssuer,
+        request: &GroupRequest,
+        roster: GroupMembershipRoster,
+    ) -> Result<GroupStateUpdate, GroupError>
+    {
+        if request.action != GroupAction::Modify {
+            return Err(GroupError::InsufficientPermissions);
+        }
+
+        let level: GroupMembershipLevel = self.authorize(issuer, request)?;
+        let delta: RosterDelta = self.state.roster.diff(&roster);
+
+        let removes_owner: bool = delta.removals.iter()
+            .any(|x| self.state.roster.level_of(x) == Some(GroupMembershipLevel::Owner));
+        let adds_owner: bool = delta.additions.iter()
+            .any(|x| x.0 == GroupMembershipLevel::Owner);
+
+        if removes_owner || adds_owner {
+            check_level(level, GroupMembershipLevel::Owner)?;
+        }
+
+        self.rotate(roster)
+    }
+
+    /// Move this group to the next epoch with a new `roster`, without any
+    /// access control.
+    pub fn rotate(&mut self, roster: GroupMembershipRoster) -> Result<GroupStateUpdate, GroupError> {
+        let epoch: u64 = match self.state.epoch.checked_add(1) {
+            Some(x) => x,
+            None    => return Err(GroupError::StaleEpoch),
+        };
+        let update = GroupStateUpdate {
+            id: self.state.id,
+            epoch: epoch,
+            delta: self.state.roster.diff(&roster),
+        };
+
+        self.state.apply(&update)?;
+
+        Ok(update)
+    }
+}
+
+/// A member's view of a group.
+pub struct GroupMembership {
+    pub state: GroupState,
+    /// The opening of this member's entry within the roster.
+    pub entry: RosterEntryCommitment,
+}
+
+impl GroupMembership {
+    /// Join a group, given its current `state` and our `entry` in its roster.
+    ///
+    /// # Errors
+    ///
+    /// * `GroupError::NotAMember` if our `entry` is not in the roster.
+    pub fn new(state: GroupState, entry: RosterEntryCommitment) -> Result<GroupMembership, GroupError> {
+        if state.roster.level_of(&entry.commitment).is_none() {
+            return Err(GroupError::NotAMember);
+        }
+
+        Ok(GroupMembership { state, entry })
+    }
+
+    /// Our current permissions level, if we are still a member.
+    pub fn level(&self) -> Option<GroupMembershipLevel> {
+        self.state.roster.level_of(&self.entry.commitment)
+    }
+
+    /// Create a request to take some `action` within the group, using the
+    /// credential held by `user`.
+    ///
+    /// # Errors
+    ///
+    /// * `GroupError::NotAMember` if we have been removed from the group.
+    /// * `GroupError::InsufficientPermissions` if we are not privileged
+    ///   enough to take the `action`.
+    /// * `GroupError::Credential` if the `user` has no credential.
+    pub fn request<R>(
+        &self,
+        user: &SignalUser,
+        action: GroupAction,
+        rng: &mut R,
+    ) -> Result<GroupRequest, GroupError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let level: GroupMembershipLevel = match self.level() {
+            Some(x) => x,
+            None    => return Err(GroupError::NotAMember),
+        };
+
+        check_level(level, action.required_level())?;
+
+        let presentation: SignalCredentialPresentation = user.show(rng, &self.entry)?;
+
+        Ok(GroupRequest {
+            id: self.state.id,
+            epoch: self.state.epoch,
+            action: action,
+            presentation: presentation,
+        })
+    }
+
+    /// Apply an `update` from the server to our view of the group.
+    pub fn update(&mut self, update: &GroupStateUpdate) -> Result<(), GroupError> {
+        self.state.apply(update)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use parameters::SystemParameters;
+
+    use rand::thread_rng;
+
+    const ALICE: &'static [u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    const BOB: &'static [u8] = &[1, 4, 1, 5, 5, 5, 5, 4, 3, 2, 1];
+
+    fn member(issuer: &SignalIssuer, phone_number: &[u8]) -> (SignalUser, RosterEntryCommitment) {
+        let mut rng = thread_rng();
+        let mut user = SignalUser::new(issuer.issuer.system_parameters, issuer.get_issuer_parameters(),
+                                       None, phone_number).unwrap();
+        let issuance = issuer.issue(phone_number, &mut rng).unwrap();
+
+        user.obtain_finish(Some(&issuance)).unwrap();
+
+        let entry = RosterEntryCommitment::create(phone_number, &issuer.issuer.system_parameters, &mut rng)
+            .unwrap();
+
+        (user, entry)
+    }
+
+    fn setup() -> (SignalIssuer, SignalGroup, SignalUser, GroupMembership, SignalUser, RosterEntryCommitment) {
+        let mut rng = thread_rng();
+        let issuer = SignalIssuer::create(SystemParameters::hunt_and_peck(&mut rng), &mut rng);
+        let (alice, alice_entry) = member(&issuer, ALICE);
+        let (bob, bob_entry) = member(&issuer, BOB);
+        let group = SignalGroup::create(GroupId::random(&mut rng), alice_entry.commitment);
+        let alice_membership = GroupMembership::new(group.state().clone(), alice_entry).unwrap();
+
+        (issuer, group, alice, alice_membership, bob, bob_entry)
+    }
+
+    #[test]
+    fn owner_adds_member_who_can_post() {
+        let mut rng = thread_rng();
+        let (issuer, mut group, alice, mut alice_membership, bob, bob_entry) = setup();
+
+        assert!(GroupMembership::new(group.state().clone(), RosterEntryCommitment::create(
+            BOB, &issuer.issuer.system_parameters, &mut rng).unwrap()).is_err());
+
+        let mut roster = group.state().roster.clone();
+
+        roster.insert(GroupMembershipLevel::User, bob_entry.commitment).unwrap();
+
+        let request = alice_membership.request(&alice, GroupAction::Modify, &mut rng).unwrap();
+        let request = GroupRequest::from_bytes(&request.to_bytes()).unwrap();
+        let update = group.modify(&issuer, &request, roster).unwrap();
+        let update = GroupStateUpdate::from_bytes(&update.to_bytes()).unwrap();
+
+        alice_membership.update(&update).unwrap();
+
+        assert!(alice_membership.state == *group.state());
+
+        let bob_membership = GroupMembership::new(GroupState::from_bytes(&group.state().to_bytes()).unwrap(),
+                                                  bob_entry).unwrap();
+        let post = bob_membership.request(&bob, GroupAction::Post, &mut rng).unwrap();
+
+        assert!(group.authorize(&issuer, &post) == Ok(GroupMembershipLevel::User));
+        assert!(bob_membership.request(&bob, GroupAction::Modify, &mut rng).err() ==
+                Some(GroupError::InsufficientPermissions));
+    }
+
+    #[test]
+    fn stale_requests_are_rejected() {
+        let mut rng = thread_rng();
+        let (issuer, mut group, alice, alice_membership, _bob, bob_entry) = setup();
+        let fetch = alice_membership.request(&alice, GroupAction::Fetch, &mut rng).unwrap();
+
+        let mut roster = group.state().roster.clone();
+
+        roster.insert(GroupMembershipLevel::User, bob_entry.commitment).unwrap();
+        group.rotate(roster).unwrap();
+
+        assert!(group.authorize(&issuer, &fetch) == Err(GroupError::StaleEpoch));
+    }
+
+    #[test]
+    fn non_members_are_rejected() {
+        let mut rng = thread_rng();
+        let (issuer, group, _alice, _alice_membership, bob, bob_entry) = setup();
+
+        // Bob pretends to be a member using a view of the group which includes him.
+        let mut state = group.state().clone();
+
+        state.roster.insert(GroupMembershipLevel::Owner, bob_entry.commitment).unwrap();
+
+        let bob_membership = GroupMembership::new(state, bob_entry).unwrap();
+        let request = bob_membership.request(&bob, GroupAction::Fetch, &mut rng).unwrap();
+
+        assert!(group.authorize(&issuer, &request) == Err(GroupError::NotAMember));
+    }
+}
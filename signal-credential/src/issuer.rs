@@ -16,6 +16,7 @@ use aeonflux::amacs::{self};
 use aeonflux::credential::CredentialRequest;
 use aeonflux::credential::RevealedAttribute;
 use aeonflux::errors::CredentialError;
+use aeonflux::errors::Error;
 use aeonflux::issuer::Issuer;
 pub use aeonflux::issuer::IssuerParameters;
 pub use aeonflux::issuer::IssuerSecretKey;
@@ -31,6 +32,8 @@ use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
 use credential::NUMBER_OF_ATTRIBUTES;
+use credential::SignalCredentialBlindIssuance;
+use credential::SignalCredentialBlindRequest;
 use credential::SignalCredentialIssuance;
 use credential::SignalCredentialPresentation;
 use credential::VerifiedSignalCredential;
@@ -57,6 +60,8 @@ impl SignalIssuer {
 
 impl_serde_with_to_bytes_and_from_bytes!(SignalIssuer,
                                          "A valid byte sequence representing a SignalIssuer");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SignalIssuer);
 
 impl SignalIssuer {
     /// Create a new `SignalIssuer` with new key material.
@@ -131,7 +136,7 @@ impl SignalIssuer {
         &self,
         phone_number: &[u8],
         rng: &mut R,
-    ) -> Result<SignalCredentialIssuance, CredentialError>
+    ) -> Result<SignalCredentialIssuance, Error>
     where
         R: RngCore + CryptoRng,
     {
@@ -147,7 +152,7 @@ impl SignalIssuer {
     }
 
     pub fn verify(&self, signal_presentation: SignalCredentialPresentation)
-        -> Result<VerifiedSignalCredential, CredentialError>
+        -> Result<VerifiedSignalCredential, Error>
     {
         self.issuer.verify(&signal_presentation.presentation)?;
 
@@ -162,7 +167,7 @@ impl SignalIssuer {
     pub fn verify_roster_membership(
         &self,
         credential: &VerifiedSignalCredential,
-    ) -> Result<CommittedPhoneNumber, CredentialError>
+    ) -> Result<CommittedPhoneNumber, Error>
     {
         let publics = committed_values_equal::Publics {
             B: &self.issuer.system_parameters.g,
@@ -176,186 +181,46 @@ impl SignalIssuer {
         if credential.0.roster_membership_proof.verify(&mut transcript, publics).is_ok() {
             Ok(credential.0.roster_entry_commitment)
         } else {
-            Err(CredentialError::VerificationFailure)
+            Err(CredentialError::VerificationFailure.into())
         }
     }
 }
 
 impl SignalIssuer {
-    // /// DOCDOC
-    // pub fn blind_issue(&self, request: SignalCredentialBlindRequest)
-    //     -> Result<SignalCredentialBlindIssuance, CredentialError>
-    // {
-    //     // Create a transcript and feed the context into it
-    //     let mut transcript = Transcript::new(b"SIGNAL BLIND ISSUANCE");
-    //     // XXX Put the revealed_attributes and encrypted_attributes into the transcript here via a
-    //     //     transcript extension.
-    // 
-    //     let blind_attributes_proof: blind_attributes::Proof;
-    //     let encrypted_attributes: Vec<EncryptedAttribute> = match request.encrypted_attributes {
-    //         Some(x) => x,
-    //         None    => vec![],
-    //     };
-    //     let revealed_attributes: Vec<RevealedAttribute> = match request.revealed_attributes {
-    //         Some(x) => x,
-    //         None    => vec![],
-    //     };
-    // 
-    //     // Determine if we should verify the proof of correct blind attribute formation first
-    //     if encrypted_attributes.len() > 0 {
-    //         // Return an error if there were encrypted attributes without a corresponding proof.
-    //         blind_attributes_proof = request.blind_attributes_proof?;
-    // 
-    //         if encrypted_attributes.len() != ISSUANCE_NUMBER_OF_HIDDEN_ATTRIBUTES * 2 {
-    //             return Err(CredentialError::WrongNumberOfAttributes);
-    //         }
-    // 
-    //         let publics: blind_attributes::Publics = blind_attributes::Publics {
-    //             B: &self.issuer.system_parameters.g,
-    //             A: &self.issuer.system_parameters.h,
-    //             D: &request.public_key.into(),
-    //             // The first two "attributes" are the commitment and encryption for the elGamal
-    //             // encryption of the user's phone number.  These form one actual attribute, but
-    //             // the zkp macro doesn't currently understand tuples, so we have to break up the
-    //             // pieces of the proof.
-    //             encrypted_attribute_0_0: &encrypted_attributes[0].commitment,
-    //             encrypted_attribute_0_1: &encrypted_attributes[1].encryption,
-    //             // The second two are the elGamal commitment and encryption of the length of the
-    //             // user's phone number, which is used to ensure that the mapping of scalars to
-    //             // users is injective.
-    //             encrypted_attribute_1_0: &encrypted_attributes[2].commitment,
-    //             encrypted_attribute_1_1: &encrypted_attributes[3].encryption,
-    //             roster_entry: &request.roster_entry.into(),
-    //         };
-    // 
-    //         // Check that the ciphertexts were correctly formed and made with the user's public key
-    //         if blind_attributes_proof.verify(&mut transcript, publics).is_err() {
-    //             return Err(CredentialError::MacVerification);
-    //         }
-    //     }
-    // 
-    //     // Choose a blinding factor, \\( b \in ZZ \mod \ell \\).
-    //     let mut csprng = transcript.fork_transcript().reseed_from_rng(&mut thread_rng());
-    //     let b: Scalar = Scalar::random(&mut csprng);
-    // 
-    //     // Compute P = b * B (labelled "u" in CMZ'13, but "P" in LdV'17).
-    //     let P: RistrettoPoint = &self.issuer.system_parameters.g * &b; // XXX use basepoint table
-    // 
-    //     // Compute a partial aMAC on the revealed attribute, if any exist.
-    //     //
-    //     //      Q_{H^{c}} ← ( x0 + \sigma{i \in H}{mi xi}) P
-    //     //
-    //     // This part isn't ever defined in CMZ'13, but is explicitly detailed in §4.2 of LdV'17,
-    //     // so we default to the latter's notation.  (It's vaguely labelled "u'" in CMZ'13 but it's
-    //     // never mentioned how to compute a partial aMAC over the revealed attributes before
-    //     // moving on to the encrypted attributes.)
-    //     let mut QHc: RistrettoPoint = RistrettoPoint::identity();
-    // 
-    //     // XXX Benchmark to see if the computation speeds up if we keep Q as a scalar until the
-    //     //     final basepoint multiplication.  Also, again, tables.
-    //     if revealed_attributes.len() > 0 {
-    //         if revealed_attributes.len() != ISSUANCE_NUMBER_OF_REVEALED_ATTRIBUTES {
-    //             return Err(CredentialError::WrongNumberOfAttributes);
-    //         }
-    // 
-    //         QHc = &self.key.x0 * &P;
-    // 
-    //         for (index, attribute) in revealed_attributes.iter().enumerate() {
-    //             let key: Scalar = self.keypair.secret.xn[index];
-    //             QHc += attribute * &(&P * &key);
-    //         }
-    //     }
-    // 
-    //     let mut csprng = transcript.fork_transcript().reseed_from_rng(&mut thread_rng());
-    //     // XXX Feed the revealed attributes if there were any into this transcript before forking.
-    // 
-    //     // Encrypt the partial aMAC on the revealed attributes to the user's public key.
-    //     let s: Scalar = Scalar::random(&mut csprng);
-    //     let pk: RistrettoPoint = request.public_key.into();
-    //     let EQHc: elgamal::Encryption = elgamal::Encryption { commitment: QHc,
-    //                                                           encryption: &s * &pk };
-    //     // XXX By encrypting regardless of whether a the blind_attributes_proof was present, we're
-    //     //     missing the ZKPoK that the user does in fact control the corresponding secret key.
-    //     //     Does this matter?
-    // 
-    //     // Compute the remainder of the aMAC over the encrypted attributes, if any, using the
-    //     // randomness used to produce P=b*g.
-    //     //
-    //     //     Enc_D(QH) ← \sigma{i \in H}{xi b Enc_D(mi B)}
-    //     let mut EQH: elgamal::Encryption = elgamal::Encryption {
-    //         commitment: P,
-    //         encryption: RistrettoPoint::identity(),
-    //     };
-    // 
-    //     // XXX Again, benchmarks needed here, for the same reason as above.
-    //     if encrypted_attributes.len() > 0 {
-    //         for (index, attribute) in encrypted_attributes.iter().enumerate() {
-    //             EQH.encryption += attribute.encryption * &(&self.keypair.secret.xn[index] * &b);
-    //         }
-    //     }
-    // 
-    //     // Use the additive homomorphism in elGamal encryption to produce the final aMAC. This is:
-    //     //
-    //     //     Enc_D(Q) ← Enc_D(QHc) + Enc_D(QH)
-    //     //
-    //     // where
-    //     //
-    //     //     Q = (x0 + \sigma(i \in H) xi mi) P
-    //     let EQ: elgamal::Encryption = &EQHc + &EQH;
-    // 
-    //     // Pick some blinding factors for the zero-knowledge proofs:
-    //     let x0_tilde: Scalar = Scalar::random(&mut csprng);
-    // 
-    //     // Form some auxiliary commitments to hide secret products in the proofs:
-    //     let t0: Scalar = &b * &self.keypair.secret.xn[0];
-    //     let t1: Scalar = &b * &self.keypair.secret.xn[1];
-    //     let T0: RistrettoPoint = &t0 * &self.issuer.system_parameters.h;
-    //     let T1: RistrettoPoint = &t1 * &self.issuer.system_parameters.h;
-    // 
-    //     // Form a NIPK showing that we issued the credential correctly, the encrypted aMAC was
-    //     // encrypted to the user's public key, the decryption of the encrypted aMAC was produced
-    //     // with the secret key corresponding to the issuer's known public key, and the system
-    //     // parameters are as expected.
-    //     let secrets = blind_issuance::Secrets {
-    //         x0_tilde: &x0_tilde,
-    //         x0: &self.key.x0,
-    //         x1: &self.keypair.secret.xn[0],
-    //         x2: &self.keypair.secret.xn[1],
-    //         s: &s,
-    //         b: &b,
-    //         t0: &t0,
-    //         t1: &t1,
-    //     };
-    // 
-    //     let publics = blind_issuance::Publics {
-    //         B: &self.issuer.system_parameters.g,
-    //         A: &self.issuer.system_parameters.h,
-    //         X0: &self.issuer.issuer_parameters.Xn[0],
-    //         X1: &self.issuer.issuer_parameters.Xn[1],
-    //         X2: &self.issuer.issuer_parameters.Xn[2],
-    //         D: &request.public_key.into(),
-    //         P: &P,
-    //         T0_0: &T0,
-    //         T0_1: &T0,
-    //         T1_0: &T1,
-    //         T1_1: &T1,
-    //         EQ_commitment: &EQ.commitment,
-    //         EQ_encryption: &EQ.encryption,
-    //         encrypted_attribute_0_0: &encrypted_attributes[0].commitment,
-    //         encrypted_attribute_0_1: &encrypted_attributes[1].encryption,
-    //         encrypted_attribute_1_0: &encrypted_attributes[2].commitment,
-    //         encrypted_attribute_1_1: &encrypted_attributes[3].encryption,
-    //     };
-    // 
-    //     let proof = blind_issuance::Proof::create(&mut transcript, publics, secrets);
-    // 
-    //     Ok(SignalCredentialBlindIssuance {
-    //         proof: proof,
-    //         blinding_commitment: P,
-    //         auxiliary_commitments: vec![T0, T1],
-    //         encrypted_mac: EQ,
-    //         revealed_attributes: revealed_attributes.clone(),
-    //         encrypted_attributes: encrypted_attributes.clone(),
-    //     })
-    // }
+    /// Blinded credential issuance.
+    ///
+    /// The issuer learns only an elGamal encryption of the user's phone
+    /// number, never the number itself.
+    ///
+    /// # Note
+    ///
+    /// Since this method cannot see the phone number, it cannot check that the
+    /// user actually controls it.  The caller MUST have verified control of
+    /// the number out-of-band (e.g. via a registration code) before calling
+    /// this method, and is responsible for binding that verification to this
+    /// `request`.
+    ///
+    /// # Inputs
+    ///
+    /// * `request` is a `SignalCredentialBlindRequest` made with
+    ///   `SignalUser::blind_obtain()`.
+    /// * `rng` is an implementation of `rand::RngCore + rand::CryptoRng`.
+    ///
+    /// # Errors
+    ///
+    /// See `aeonflux::issuer::Issuer::blind_issue()`.
+    ///
+    /// # Returns
+    ///
+    /// A `SignalCredentialBlindIssuance` upon successful issuance.
+    pub fn blind_issue<R>(
+        &self,
+        request: &SignalCredentialBlindRequest,
+        rng: &mut R,
+    ) -> Result<SignalCredentialBlindIssuance, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.issuer.blind_issue(request, rng)
+    }
 }
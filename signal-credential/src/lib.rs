@@ -36,15 +36,21 @@ extern crate serde;
 
 pub mod credential;
 pub mod errors;
+pub mod group;
 pub mod issuer;
 pub mod phone_number;
+pub mod roster;
 pub mod user;
+pub mod zkgroup;
 
 pub use credential::*;
 pub use errors::*;
+pub use group::*;
 pub use issuer::*;
 pub use phone_number::*;
+pub use roster::*;
 pub use user::*;
+pub use zkgroup::*;
 
 // Re-export common externally-used types from aeonflux.
 pub use aeonflux::prelude::*;
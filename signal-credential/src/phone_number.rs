@@ -16,9 +16,17 @@ use std::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::string::String;
 
+#[cfg(feature = "std")]
+use std::cmp;
+#[cfg(feature = "std")]
+use std::fmt;
 #[cfg(feature = "std")]
 use std::ops::{Index};
 #[cfg(not(feature = "std"))]
+use core::cmp;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(not(feature = "std"))]
 use core::ops::{Index};
 
 use aeonflux::amacs::{self};
@@ -27,6 +35,7 @@ use aeonflux::elgamal::{self};
 use aeonflux::nonces::Ephemeral;
 use aeonflux::parameters::SystemParameters;
 use aeonflux::pedersen::{self};
+use aeonflux::secret::REDACTED;
 
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
@@ -40,6 +49,20 @@ use rand_core::CryptoRng;
 use errors::PhoneNumberError;
 
 pub const SIZEOF_PHONE_NUMBER: usize = 32;
+
+/// The maximum number of digits in an E.164 phone number, including the
+/// country code.
+pub const E164_MAXIMUM_DIGITS: usize = 15;
+
+/// The minimum number of digits we accept in an E.164 phone number, including
+/// the country code.
+pub const E164_MINIMUM_DIGITS: usize = 7;
+
+/// The maximum number of digits which fit into the encoding of a
+/// `PhoneNumber`, which uses five bytes of the scalar for delimiters and the
+/// length, and leaves the high byte zero so that the encoding is below the
+/// group order and never reduced.
+pub const MAXIMUM_DIGITS: usize = SIZEOF_PHONE_NUMBER - 6;
 pub const SIZEOF_COMMITTED_PHONE_NUMBER: usize = pedersen::SIZEOF_COMMITMENT;
 pub const SIZEOF_ENCRYPTED_PHONE_NUMBER: usize = elgamal::SIZEOF_ENCRYPTION;
 
@@ -51,10 +74,17 @@ pub const SIZEOF_ENCRYPTED_PHONE_NUMBER: usize = elgamal::SIZEOF_ENCRYPTION;
 /// To disambiguate numbers which may have significant leading zeros in some
 /// countries and/or regions, we prefix the bytes of the scalar with
 /// `0x15`s. These `0x15`s are not part of the `number`.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 #[repr(C)]
 pub struct PhoneNumber(pub Scalar);
 
+/// A user's phone number is never written to logs.
+impl fmt::Debug for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PhoneNumber({})", REDACTED)
+    }
+}
+
 impl Index<usize> for PhoneNumber {
     type Output = u8;
 
@@ -74,7 +104,10 @@ impl PhoneNumber {
 
         tmp.copy_from_slice(&bytes[00..32]);
 
-        Ok(PhoneNumber(Scalar::from_bits(tmp)))
+        match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => Ok(PhoneNumber(x)),
+            None    => Err(PhoneNumberError::ScalarFormatError),
+        }
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -84,6 +117,8 @@ impl PhoneNumber {
 
 impl_serde_with_to_bytes_and_from_bytes!(PhoneNumber,
                                          "A valid byte sequence representing a PhoneNumber");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(PhoneNumber);
 
 impl PhoneNumber {
     /// Convert a `String` containing a canonicalised (as if it were to be
@@ -132,10 +167,37 @@ impl PhoneNumber {
     //     }
     // }
     pub fn try_from_string(source: &String) -> Result<Self, PhoneNumberError> {
-        let bytes: &[u8] = source.as_bytes();
+        let mut digits: Vec<u8> = Vec::with_capacity(source.len());
+
+        for byte in source.as_bytes().iter() {
+            match *byte {
+                // Undo UTF-8 encoding:
+                b'0'...b'9' => digits.push(byte - b'0'),
+                _           => return Err(PhoneNumberError::InvalidDigit),
+            }
+        }
+
+        PhoneNumber::try_from_bytes(&digits)
+    }
+
+    /// Convert a slice of digits (each in the range `0..=9`) into a
+    /// `PhoneNumber`.
+    ///
+    /// # Errors
+    ///
+    /// * `PhoneNumberError::LengthExceeded` if there are more than
+    ///   `MAXIMUM_DIGITS` digits.
+    /// * `PhoneNumberError::InvalidDigit` if any byte is not a decimal digit,
+    ///   since the digits `10..=15` are used to delimit the encoding and
+    ///   allowing them would make it non-injective.
+    ///
+    /// The number of digits is treated as public, since it is already
+    /// revealed by the length of `bytes`, but the digits themselves never
+    /// influence control flow.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, PhoneNumberError> {
         let length: usize = bytes.len();
 
-        if length > 32 {
+        if length > MAXIMUM_DIGITS {
             return Err(PhoneNumberError::LengthExceeded);
         }
         let mut bits: [u8; 32] = [0u8; 32];
@@ -145,95 +207,178 @@ impl PhoneNumber {
         bits[0] = 15;
         bits[1] = 15;
 
-        // Write the phone number into the bytes:
+        // Write the phone number into the bytes, noting whether any digit
+        // was greater than nine without branching on it:
+        let mut invalid: u16 = 0;
+
         for i in 2..length+2 {
-            bits[i] = match bytes[i-2] {
-                // Undo UTF-8 encoding:
-                48 => 0,
-                49 => 1,
-                50 => 2,
-                51 => 3,
-                52 => 4,
-                53 => 5,
-                54 => 6,
-                55 => 7,
-                56 => 8,
-                57 => 9,
-                _  => 15,
-            };
+            invalid |= 9u16.wrapping_sub(bytes[i-2] as u16) >> 15;
+            bits[i] = bytes[i-2];
+        }
+        if invalid != 0 {
+            return Err(PhoneNumberError::InvalidDigit);
         }
         // Suffix some bogus digits as well and then finally add the length:
         bits[length+2] = 15;
         bits[length+3] = 15;
         bits[length+4] = length as u8;
 
+        // At most MAXIMUM_DIGITS digits leave the high byte zero, so this is
+        // always a canonical scalar.
         let number: Scalar = Scalar::from_bytes_mod_order(bits);
 
         Ok(PhoneNumber(number))
     }
 
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, PhoneNumberError> {
-        let length: usize = bytes.len();
+    /// Parse and canonicalise an E.164 phone number, such as
+    /// `"+1 (415) 555-1234"`, into a `PhoneNumber`.
+    ///
+    /// Spaces, hyphens, dots, and parentheses are stripped.  What remains must
+    /// be a `+` followed by between `E164_MINIMUM_DIGITS` and
+    /// `E164_MAXIMUM_DIGITS` decimal digits, the first of which (the start of
+    /// the country code) must not be zero.
+    ///
+    /// Every E.164 number fits within a single `Scalar` attribute, and two
+    /// inputs map to the same `PhoneNumber` if and only if they have the same
+    /// canonical form as given by `PhoneNumber::to_e164()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate signal_credential;
+    /// #
+    /// # use signal_credential::errors::PhoneNumberError;
+    /// use signal_credential::phone_number::PhoneNumber;
+    ///
+    /// # fn do_test() -> Result<(), PhoneNumberError> {
+    /// let a: PhoneNumber = PhoneNumber::try_from_e164("+1 (415) 555-1234")?;
+    /// let b: PhoneNumber = PhoneNumber::try_from_e164("+14155551234")?;
+    ///
+    /// assert!(a == b);
+    /// assert!(a.to_e164()? == "+14155551234");
+    /// # Ok(())
+    /// # }
+    /// # fn main() { do_test().unwrap(); }
+    /// ```
+    pub fn try_from_e164(source: &str) -> Result<Self, PhoneNumberError> {
+        let mut bytes = source.bytes().filter(|c| match *c {
+            b' ' | b'-' | b'.' | b'(' | b')' => false,
+            _                                => true,
+        });
+
+        if bytes.next() != Some(b'+') {
+            return Err(PhoneNumberError::MissingE164Prefix);
+        }
+        let mut digits: Vec<u8> = Vec::with_capacity(E164_MAXIMUM_DIGITS);
 
-        if length > 32 {
-            return Err(PhoneNumberError::LengthExceeded);
+        for byte in bytes {
+            match byte {
+                b'0'...b'9' => digits.push(byte - b'0'),
+                _           => return Err(PhoneNumberError::InvalidDigit),
+            }
+            if digits.len() > E164_MAXIMUM_DIGITS {
+                return Err(PhoneNumberError::LengthExceeded);
+            }
+        }
+        if digits.len() < E164_MINIMUM_DIGITS {
+            return Err(PhoneNumberError::TooShort);
+        }
+        if digits[0] == 0 {
+            return Err(PhoneNumberError::InvalidCountryCode);
         }
-        let mut bits: [u8; 32] = [0u8; 32];
 
-        // Prefix it with some bogus digits to prevent phone numbers with
-        // significant leading zeroes getting mapped to the same scalar:
-        bits[0] = 15;
-        bits[1] = 15;
+        PhoneNumber::try_from_bytes(&digits)
+    }
 
-        // Write the phone number into the bytes:
-        for i in 2..length+2 {
-            bits[i] = bytes[i-2];
+    /// Decode the digits of this `PhoneNumber`.
+    ///
+    /// # Errors
+    ///
+    /// `PhoneNumberError::InvalidPhoneNumber` if this scalar is not exactly
+    /// the encoding of some sequence of digits, e.g. if it came from an
+    /// attribute which was not a phone number.
+    pub fn to_digits(&self) -> Result<Vec<u8>, PhoneNumberError> {
+        let bits: &[u8; 32] = self.0.as_bytes();
+
+        // Work backwards to find the length, which is the last non-zero byte:
+        let end: usize = match bits.iter().rposition(|x| *x != 0) {
+            Some(i) => i,
+            None    => return Err(PhoneNumberError::InvalidPhoneNumber),
+        };
+        let length: usize = bits[end] as usize;
+
+        if length > MAXIMUM_DIGITS || end != length + 4 {
+            return Err(PhoneNumberError::InvalidPhoneNumber);
         }
-        // Suffix some bogus digits as well and then finally add the length:
-        bits[length+2] = 15;
-        bits[length+3] = 15;
-        bits[length+4] = length as u8;
+        if bits[0] != 15 || bits[1] != 15 || bits[length+2] != 15 || bits[length+3] != 15 {
+            return Err(PhoneNumberError::InvalidPhoneNumber);
+        }
+        let digits: &[u8] = &bits[2..length+2];
 
-        let number: Scalar = Scalar::from_bytes_mod_order(bits);
+        if digits.iter().any(|x| *x > 9) {
+            return Err(PhoneNumberError::InvalidPhoneNumber);
+        }
 
-        Ok(PhoneNumber(number))
+        Ok(digits.to_vec())
+    }
+
+    /// Produce the canonical E.164 form of this `PhoneNumber`,
+    /// e.g. `"+14155551234"`.
+    ///
+    /// # Errors
+    ///
+    /// * `PhoneNumberError::InvalidPhoneNumber` if this is not the encoding of
+    ///   a sequence of digits.
+    /// * `PhoneNumberError::InvalidCountryCode`, `PhoneNumberError::TooShort`,
+    ///   or `PhoneNumberError::LengthExceeded` if the digits do not form a
+    ///   valid E.164 number.
+    pub fn to_e164(&self) -> Result<String, PhoneNumberError> {
+        let digits: Vec<u8> = self.to_digits()?;
+
+        if digits.len() > E164_MAXIMUM_DIGITS {
+            return Err(PhoneNumberError::LengthExceeded);
+        }
+        if digits.len() < E164_MINIMUM_DIGITS {
+            return Err(PhoneNumberError::TooShort);
+        }
+        if digits[0] == 0 {
+            return Err(PhoneNumberError::InvalidCountryCode);
+        }
+        let mut s: String = String::with_capacity(digits.len() + 1);
+
+        s.push('+');
+
+        for digit in digits.iter() {
+            s.push((b'0' + digit) as char);
+        }
+
+        Ok(s)
     }
 }
 
 impl From<PhoneNumber> for String {
     fn from(source: PhoneNumber) -> String {
-        let mut length: usize = 0;
+        let mut length: u8 = 0;
 
-        // Work backwards to find the length:
-        for i in (0..32).rev() {
-            if source.0[i] == 0 {
-                continue;
-            } else {
-                length = source[i] as usize;
-                break;
-            }
+        // The length is the last non-zero byte.  Scan every byte, rather than
+        // stopping at it, so that where it lies isn't revealed:
+        for i in 0..32 {
+            let byte: u8 = source[i];
+            let nonzero: u8 = 0u8.wrapping_sub((byte | byte.wrapping_neg()) >> 7);
+
+            length = (length & !nonzero) | (byte & nonzero);
         }
 
+        let length: usize = cmp::min(length as usize, MAXIMUM_DIGITS);
         let mut s: String = String::with_capacity(length);
 
         for i in 2..length+2 {
-            match source[i] {
-                0 => s.push_str("0"),
-                1 => s.push_str("1"),
-                2 => s.push_str("2"),
-                3 => s.push_str("3"),
-                4 => s.push_str("4"),
-                5 => s.push_str("5"),
-                6 => s.push_str("6"),
-                7 => s.push_str("7"),
-                8 => s.push_str("8"),
-                9 => s.push_str("9"),
-                _ => {
-                    #[cfg(feature = "std")]
-                    println!("Got weird digit in phone number {:?}", source[i]);
-                    continue;
-                },
+            if source[i] > 9 {
+                #[cfg(feature = "std")]
+                println!("Got weird digit in phone number");
+                continue;
             }
+            s.push((b'0' + source[i]) as char);
         }
 
         s
@@ -287,6 +432,8 @@ impl CommittedPhoneNumber {
 
 impl_serde_with_to_bytes_and_from_bytes!(CommittedPhoneNumber,
                                          "A valid byte sequence representing a CommittedPhoneNumber");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(CommittedPhoneNumber);
 
 impl CommittedPhoneNumber {
     pub fn from_phone_number(
@@ -323,13 +470,20 @@ pub struct RosterEntryCommitment {
 
 impl RosterEntryCommitment {
     pub fn from_bytes(bytes: &[u8]) -> Result<RosterEntryCommitment, PhoneNumberError> {
+        if bytes.len() != SIZEOF_COMMITTED_PHONE_NUMBER + 32 {
+            return Err(PhoneNumberError::LengthExceeded);
+        }
+
         let commitment = CommittedPhoneNumber::from_bytes(&bytes[..SIZEOF_COMMITTED_PHONE_NUMBER])?;
 
         let mut tmp = [0u8; 32];
 
         tmp.copy_from_slice(&bytes[SIZEOF_COMMITTED_PHONE_NUMBER..]);
 
-        let opening: Ephemeral = Scalar::from_bits(tmp).into();
+        let opening: Ephemeral = match Scalar::from_canonical_bytes(tmp) {
+            Some(x) => x.into(),
+            None    => return Err(PhoneNumberError::ScalarFormatError),
+        };
 
         Ok(RosterEntryCommitment { commitment, opening })
     }
@@ -346,6 +500,8 @@ impl RosterEntryCommitment {
 
 impl_serde_with_to_bytes_and_from_bytes!(RosterEntryCommitment,
                                          "A valid byte sequence representing a RosterEntryCommitment");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RosterEntryCommitment);
 
 impl RosterEntryCommitment {
     pub fn create<R>(
@@ -402,4 +558,75 @@ mod test {
 
         assert!(a != b);
     }
+
+    #[test]
+    fn e164_parse_and_canonicalise() {
+        let a: PhoneNumber = PhoneNumber::try_from_e164("+1 (415) 555-1234").unwrap();
+        let b: PhoneNumber = PhoneNumber::try_from_e164("+1.415.555.1234").unwrap();
+        let c: PhoneNumber = PhoneNumber::try_from_bytes(&[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4]).unwrap();
+
+        assert!(a == b);
+        assert!(a == c);
+        assert!(a.to_e164().unwrap() == "+14155551234");
+        assert!(a.to_digits().unwrap() == vec![1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn e164_maximum_length_roundtrip() {
+        let input: &str = "+123456789012345";
+        let number: PhoneNumber = PhoneNumber::try_from_e164(input).unwrap();
+
+        assert!(number.to_e164().unwrap() == input);
+    }
+
+    #[test]
+    fn e164_rejects_malformed() {
+        assert!(PhoneNumber::try_from_e164("14155551234") == Err(PhoneNumberError::MissingE164Prefix));
+        assert!(PhoneNumber::try_from_e164("+1415555123x") == Err(PhoneNumberError::InvalidDigit));
+        assert!(PhoneNumber::try_from_e164("+1+4155551234") == Err(PhoneNumberError::InvalidDigit));
+        assert!(PhoneNumber::try_from_e164("+0415555123") == Err(PhoneNumberError::InvalidCountryCode));
+        assert!(PhoneNumber::try_from_e164("+123456") == Err(PhoneNumberError::TooShort));
+        assert!(PhoneNumber::try_from_e164("+1234567890123456") == Err(PhoneNumberError::LengthExceeded));
+        assert!(PhoneNumber::try_from_e164("") == Err(PhoneNumberError::MissingE164Prefix));
+    }
+
+    #[test]
+    fn bytes_reject_delimiters_and_overlong_input() {
+        assert!(PhoneNumber::try_from_bytes(&[1, 4, 15, 5]) == Err(PhoneNumberError::InvalidDigit));
+
+        // The longest encoding must still decode to exactly its digits.
+        let longest: Vec<u8> = (0..MAXIMUM_DIGITS).map(|i| (i % 10) as u8).collect();
+        let number = PhoneNumber::try_from_bytes(&longest).unwrap();
+
+        assert!(number.0.as_bytes()[SIZEOF_PHONE_NUMBER - 1] == 0);
+        assert!(number.to_digits().unwrap() == longest);
+
+        assert!(PhoneNumber::try_from_bytes(&[1u8; MAXIMUM_DIGITS + 1]) == Err(PhoneNumberError::LengthExceeded));
+        assert!(PhoneNumber::try_from_bytes(&[1u8; 32]) == Err(PhoneNumberError::LengthExceeded));
+    }
+
+    #[test]
+    fn decoding_rejects_non_phone_numbers() {
+        let mut bits = [0u8; 32];
+
+        bits[0] = 1;
+
+        assert!(PhoneNumber(Scalar::from_bits(bits)).to_digits() == Err(PhoneNumberError::InvalidPhoneNumber));
+        assert!(PhoneNumber(Scalar::zero()).to_digits() == Err(PhoneNumberError::InvalidPhoneNumber));
+    }
+
+    #[test]
+    fn from_bytes_rejects_malleated_inputs() {
+        let number: PhoneNumber = PhoneNumber::try_from_e164("+14155551234").unwrap();
+        let mut bytes: Vec<u8> = number.to_bytes();
+
+        assert!(PhoneNumber::from_bytes(&bytes) == Ok(number));
+
+        // Scalar::from_bits would have accepted this and silently changed
+        // the number modulo the group order.
+        bytes[31] |= 0x80;
+
+        assert!(PhoneNumber::from_bytes(&bytes) == Err(PhoneNumberError::ScalarFormatError));
+        assert!(PhoneNumber::from_bytes(&bytes[..31]) == Err(PhoneNumberError::LengthExceeded));
+    }
 }
@@ -0,0 +1,809 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Group membership rosters, their canonical serialisation, and a compact
+//! delta format for incrementally syncing them.
+//!
+//! A roster is canonically encoded as three length-prefixed lists (owners,
+//! admins, users) of `CommittedPhoneNumber`s, where each list is strictly
+//! sorted by the compressed bytes of its commitments.  Since the encoding is
+//! canonical, two parties holding the same roster always compute the same
+//! `RosterDigest`, which lets a client apply a `RosterDelta` and check that it
+//! arrived at the same roster as the server without re-downloading it.
+//!
+//! For groups too large to hold in memory, a `RosterMembershipStream` finds
+//! the level of a single entry while reading the canonical encoding in
+//! chunks, holding no more than one entry at a time.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::cmp::{self, Ordering};
+#[cfg(not(feature = "std"))]
+use core::cmp::{self, Ordering};
+
+use merlin::Transcript;
+
+use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Visitor;
+
+use errors::RosterError;
+use phone_number::SIZEOF_COMMITTED_PHONE_NUMBER;
+use phone_number::CommittedPhoneNumber;
+
+pub const SIZEOF_ROSTER_DIGEST: usize = 32;
+
+/// The size of the `u32` length prefixes in roster and delta encodings.
+const SIZEOF_LENGTH: usize = 4;
+
+/// The size of an encoded `(GroupMembershipLevel, CommittedPhoneNumber)` pair.
+const SIZEOF_LEVELLED_ENTRY: usize = 1 + SIZEOF_COMMITTED_PHONE_NUMBER;
+
+/// The permissions level of an entry within a `GroupMembershipRoster`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[repr(u8)]
+pub enum GroupMembershipLevel {
+    Owner = 0,
+    Admin = 1,
+    User = 2,
+}
+
+impl GroupMembershipLevel {
+    pub fn from_byte(byte: u8) -> Result<GroupMembershipLevel, RosterError> {
+        match byte {
+            0 => Ok(GroupMembershipLevel::Owner),
+            1 => Ok(GroupMembershipLevel::Admin),
+            2 => Ok(GroupMembershipLevel::User),
+            _ => Err(RosterError::UnknownMembershipLevel),
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A digest over the canonical encoding of a `GroupMembershipRoster`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct RosterDigest(pub [u8; SIZEOF_ROSTER_DIGEST]);
+
+fn compare_entries(a: &CommittedPhoneNumber, b: &CommittedPhoneNumber) -> Ordering {
+    a.to_bytes().cmp(&b.to_bytes())
+}
+
+fn read_length(bytes: &[u8], offset: usize) -> Result<usize, RosterError> {
+    if bytes.len() < offset + SIZEOF_LENGTH {
+        return Err(RosterError::WrongNumberOfBytes);
+    }
+    let mut tmp = [0u8; SIZEOF_LENGTH];
+
+    tmp.copy_from_slice(&bytes[offset..offset + SIZEOF_LENGTH]);
+
+    Ok(u32::from_le_bytes(tmp) as usize)
+}
+
+fn write_length(v: &mut Vec<u8>, length: usize) {
+    v.extend((length as u32).to_le_bytes().iter());
+}
+
+/// Decode `count` commitments from `bytes` at `offset`, requiring them to be
+/// strictly increasing (and therefore free of duplicates).
+fn read_sorted_entries(
+    bytes: &[u8],
+    offset: usize,
+    count: usize,
+) -> Result<Vec<CommittedPhoneNumber>, RosterError>
+{
+    let end: usize = offset + count * SIZEOF_COMMITTED_PHONE_NUMBER;
+
+    if bytes.len() < end {
+        return Err(RosterError::WrongNumberOfBytes);
+    }
+    let mut entries: Vec<CommittedPhoneNumber> = Vec::with_capacity(count);
+
+    for chunk in bytes[offset..end].chunks(SIZEOF_COMMITTED_PHONE_NUMBER) {
+        let entry = CommittedPhoneNumber::from_bytes(chunk).or(Err(RosterError::PointDecompressionError))?;
+
+        if let Some(previous) = entries.last() {
+            if compare_entries(previous, &entry) != Ordering::Less {
+                return Err(RosterError::NonCanonicalEncoding);
+            }
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Decode a length-prefixed list of sorted commitments at `offset`, advancing
+/// the `offset` past it.
+fn read_list(bytes: &[u8], offset: &mut usize) -> Result<Vec<CommittedPhoneNumber>, RosterError> {
+    let count: usize = read_length(bytes, *offset)?;
+    let entries = read_sorted_entries(bytes, *offset + SIZEOF_LENGTH, count)?;
+
+    *offset += SIZEOF_LENGTH + count * SIZEOF_COMMITTED_PHONE_NUMBER;
+
+    Ok(entries)
+}
+
+/// A roster of the committed phone numbers of the members of a Signal group,
+/// organised by their permissions level.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GroupMembershipRoster {
+    pub owners: Vec<CommittedPhoneNumber>,
+    pub admins: Vec<CommittedPhoneNumber>,
+    pub users: Vec<CommittedPhoneNumber>,
+}
+
+impl GroupMembershipRoster {
+    /// Decode a roster from its canonical encoding.
+    ///
+    /// # Errors
+    ///
+    /// * `RosterError::WrongNumberOfBytes` if the length prefixes do not match
+    ///   the number of bytes given.
+    /// * `RosterError::NonCanonicalEncoding` if any of the lists are not
+    ///   strictly sorted, or if an entry appears at more than one level.
+    /// * `RosterError::PointDecompressionError` if an entry is not a valid
+    ///   commitment.
+    pub fn from_bytes(bytes: &[u8]) -> Result<GroupMembershipRoster, RosterError> {
+        let mut offset: usize = 0;
+
+        let owners = read_list(bytes, &mut offset)?;
+        let admins = read_list(bytes, &mut offset)?;
+        let users = read_list(bytes, &mut offset)?;
+
+        if offset != bytes.len() {
+            return Err(RosterError::WrongNumberOfBytes);
+        }
+        let roster = GroupMembershipRoster { owners, admins, users };
+
+        // An entry may only hold one permissions level at a time.
+        for entry in roster.owners.iter().chain(roster.admins.iter()) {
+            if roster.level_count(entry) > 1 {
+                return Err(RosterError::NonCanonicalEncoding);
+            }
+        }
+
+        Ok(roster)
+    }
+
+    /// Produce the canonical encoding of this roster.
+    ///
+    /// The lists are sorted here rather than relying upon callers to have
+    /// kept them sorted, so that mutating the public fields directly can never
+    /// produce a non-canonical encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(3 * SIZEOF_LENGTH + self.len() * SIZEOF_COMMITTED_PHONE_NUMBER);
+
+        for list in [&self.owners, &self.admins, &self.users].iter() {
+            let mut sorted: Vec<CommittedPhoneNumber> = (*list).clone();
+
+            sorted.sort_by(compare_entries);
+            sorted.dedup();
+
+            write_length(&mut v, sorted.len());
+
+            for entry in sorted.iter() {
+                v.extend(entry.to_bytes());
+            }
+        }
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(GroupMembershipRoster,
+                                         "A valid byte sequence representing a GroupMembershipRoster");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(GroupMembershipRoster);
+
+impl GroupMembershipRoster {
+    /// Create an empty roster.
+    pub fn new() -> GroupMembershipRoster {
+        GroupMembershipRoster::default()
+    }
+
+    /// The total number of entries in this roster.
+    pub fn len(&self) -> usize {
+        self.owners.len() + self.admins.len() + self.users.len()
+    }
+
+    fn level_count(&self, entry: &CommittedPhoneNumber) -> usize {
+        self.owners.iter()
+            .chain(self.admins.iter())
+            .chain(self.users.iter())
+            .filter(|x| *x == entry)
+            .count()
+    }
+
+    fn list(&self, level: GroupMembershipLevel) -> &Vec<CommittedPhoneNumber> {
+        match level {
+            GroupMembershipLevel::Owner => &self.owners,
+            GroupMembershipLevel::Admin => &self.admins,
+            GroupMembershipLevel::User  => &self.users,
+        }
+    }
+
+    fn list_mut(&mut self, level: GroupMembershipLevel) -> &mut Vec<CommittedPhoneNumber> {
+        match level {
+            GroupMembershipLevel::Owner => &mut self.owners,
+            GroupMembershipLevel::Admin => &mut self.admins,
+            GroupMembershipLevel::User  => &mut self.users,
+        }
+    }
+
+    /// Get the permissions level of an `entry`, if it is in this roster.
+    pub fn level_of(&self, entry: &CommittedPhoneNumber) -> Option<GroupMembershipLevel> {
+        for level in [GroupMembershipLevel::Owner,
+                      GroupMembershipLevel::Admin,
+                      GroupMembershipLevel::User].iter()
+        {
+            if self.list(*level).contains(entry) {
+                return Some(*level);
+            }
+        }
+        None
+    }
+
+    /// Add an `entry` to this roster at the given `level`.
+    ///
+    /// # Errors
+    ///
+    /// `RosterError::DuplicateEntry` if the `entry` is already in the roster,
+    /// at any level.
+    pub fn insert(
+        &mut self,
+        level: GroupMembershipLevel,
+        entry: CommittedPhoneNumber,
+    ) -> Result<(), RosterError>
+    {
+        if self.level_of(&entry).is_some() {
+            return Err(RosterError::DuplicateEntry);
+        }
+        let list = self.list_mut(level);
+        let index = match list.binary_search_by(|x| compare_entries(x, &entry)) {
+            Ok(i)  => i,
+            Err(i) => i,
+        };
+        list.insert(index, entry);
+
+        Ok(())
+    }
+
+    /// Remove an `entry` from this roster, returning the level it was at.
+    ///
+    /// # Errors
+    ///
+    /// `RosterError::MissingEntry` if the `entry` was not in the roster.
+    pub fn remove(&mut self, entry: &CommittedPhoneNumber) -> Result<GroupMembershipLevel, RosterError> {
+        let level = self.level_of(entry).ok_or(RosterError::MissingEntry)?;
+
+        self.list_mut(level).retain(|x| x != entry);
+
+        Ok(level)
+    }
+
+    /// Compute the `RosterDigest` of the canonical encoding of this roster.
+    pub fn digest(&self) -> RosterDigest {
+        let mut transcript = Transcript::new(b"SIGNAL GROUP ROSTER");
+        let mut digest = [0u8; SIZEOF_ROSTER_DIGEST];
+
+        transcript.commit_bytes(b"roster", &self.to_bytes());
+        transcript.challenge_bytes(b"digest", &mut digest);
+
+        RosterDigest(digest)
+    }
+
+    /// Compute the `RosterDelta` which transforms this roster into `other`.
+    ///
+    /// Entries whose level changed are encoded as a removal followed by an
+    /// addition at the new level.
+    pub fn diff(&self, other: &GroupMembershipRoster) -> RosterDelta {
+        let mut removals: Vec<CommittedPhoneNumber> = Vec::new();
+        let mut additions: Vec<(GroupMembershipLevel, CommittedPhoneNumber)> = Vec::new();
+
+        for level in [GroupMembershipLevel::Owner,
+                      GroupMembershipLevel::Admin,
+                      GroupMembershipLevel::User].iter()
+        {
+            for entry in self.list(*level).iter() {
+                if other.level_of(entry) != Some(*level) {
+                    removals.push(*entry);
+                }
+            }
+            for entry in other.list(*level).iter() {
+                if self.level_of(entry) != Some(*level) {
+                    additions.push((*level, *entry));
+                }
+            }
+        }
+
+        removals.sort_by(compare_entries);
+        additions.sort_by(|a, b| a.0.cmp(&b.0).then(compare_entries(&a.1, &b.1)));
+
+        RosterDelta {
+            base: self.digest(),
+            result: other.digest(),
+            removals,
+            additions,
+        }
+    }
+
+    /// Apply a `delta` to this roster.
+    ///
+    /// The delta is first checked with `RosterDelta::verify()`, and then
+    /// applied to a copy of this roster, so that if the resulting roster does
+    /// not match the delta's `result` digest this roster is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// * Any error from `RosterDelta::verify()`.
+    /// * `RosterError::ResultDigestMismatch` if applying the delta did not
+    ///   produce the roster the delta was created for.
+    pub fn apply(&mut self, delta: &RosterDelta) -> Result<(), RosterError> {
+        delta.verify(self)?;
+
+        let mut updated: GroupMembershipRoster = self.clone();
+
+        for entry in delta.removals.iter() {
+            updated.remove(entry)?;
+        }
+        for &(level, entry) in delta.additions.iter() {
+            updated.insert(level, entry)?;
+        }
+
+        if updated.digest() != delta.result {
+            return Err(RosterError::ResultDigestMismatch);
+        }
+        *self = updated;
+
+        Ok(())
+    }
+}
+
+/// A compact set of changes between two versions of a `GroupMembershipRoster`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RosterDelta {
+    /// The digest of the roster this delta applies to.
+    pub base: RosterDigest,
+    /// The digest of the roster after this delta has been applied.
+    pub result: RosterDigest,
+    /// Entries to remove, strictly sorted.
+    pub removals: Vec<CommittedPhoneNumber>,
+    /// Entries to add, strictly sorted by level and then entry.
+    pub additions: Vec<(GroupMembershipLevel, CommittedPhoneNumber)>,
+}
+
+impl RosterDelta {
+    pub fn from_bytes(bytes: &[u8]) -> Result<RosterDelta, RosterError> {
+        if bytes.len() < 2 * SIZEOF_ROSTER_DIGEST {
+            return Err(RosterError::WrongNumberOfBytes);
+        }
+        let mut base = [0u8; SIZEOF_ROSTER_DIGEST];
+        let mut result = [0u8; SIZEOF_ROSTER_DIGEST];
+
+        base.copy_from_slice(&bytes[00..32]);
+        result.copy_from_slice(&bytes[32..64]);
+
+        let mut offset: usize = 2 * SIZEOF_ROSTER_DIGEST;
+        let removals_count: usize = read_length(bytes, offset)?;
+
+        offset += SIZEOF_LENGTH;
+
+        let removals = read_sorted_entries(bytes, offset, removals_count)?;
+
+        offset += removals_count * SIZEOF_COMMITTED_PHONE_NUMBER;
+
+        let additions_count: usize = read_length(bytes, offset)?;
+
+        offset += SIZEOF_LENGTH;
+
+        if bytes.len() != offset + additions_count * SIZEOF_LEVELLED_ENTRY {
+            return Err(RosterError::WrongNumberOfBytes);
+        }
+        let mut additions: Vec<(GroupMembershipLevel, CommittedPhoneNumber)> = Vec::with_capacity(additions_count);
+
+        for chunk in bytes[offset..].chunks(SIZEOF_LEVELLED_ENTRY) {
+            let level = GroupMembershipLevel::from_byte(chunk[0])?;
+            let entry = CommittedPhoneNumber::from_bytes(&chunk[1..])
+                .or(Err(RosterError::PointDecompressionError))?;
+
+            if let Some(previous) = additions.last() {
+                let ordering = previous.0.cmp(&level).then(compare_entries(&previous.1, &entry));
+
+                if ordering != Ordering::Less {
+                    return Err(RosterError::NonCanonicalEncoding);
+                }
+            }
+            additions.push((level, entry));
+        }
+
+        Ok(RosterDelta {
+            base: RosterDigest(base),
+            result: RosterDigest(result),
+            removals,
+            additions,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Vec::with_capacity(2 * SIZEOF_ROSTER_DIGEST + 2 * SIZEOF_LENGTH +
+                                                self.removals.len() * SIZEOF_COMMITTED_PHONE_NUMBER +
+                                                self.additions.len() * SIZEOF_LEVELLED_ENTRY);
+
+        v.extend(self.base.0.iter());
+        v.extend(self.result.0.iter());
+
+        write_length(&mut v, self.removals.len());
+
+        for entry in self.removals.iter() {
+            v.extend(entry.to_bytes());
+        }
+
+        write_length(&mut v, self.additions.len());
+
+        for &(level, entry) in self.additions.iter() {
+            v.push(level.to_byte());
+            v.extend(entry.to_bytes());
+        }
+
+        v
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(RosterDelta,
+                                         "A valid byte sequence representing a RosterDelta");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(RosterDelta);
+
+impl RosterDelta {
+    /// Check that this delta is applicable to the `roster`.
+    ///
+    /// # Errors
+    ///
+    /// * `RosterError::BaseDigestMismatch` if the `roster` is not the one this
+    ///   delta was computed against.
+    /// * `RosterError::MissingEntry` if a removal is not in the `roster`.
+    /// * `RosterError::DuplicateEntry` if an addition is already in the
+    ///   `roster` and is not also being removed.
+    pub fn verify(&self, roster: &GroupMembershipRoster) -> Result<(), RosterError> {
+        if roster.digest() != self.base {
+            return Err(RosterError::BaseDigestMismatch);
+        }
+        for entry in self.removals.iter() {
+            if roster.level_of(entry).is_none() {
+                return Err(RosterError::MissingEntry);
+            }
+        }
+        for &(_, ref entry) in self.additions.iter() {
+            if roster.level_of(entry).is_some() && !self.removals.contains(entry) {
+                return Err(RosterError::DuplicateEntry);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The number of lists in a roster encoding: owners, admins, and users.
+const NUMBER_OF_LISTS: usize = 3;
+
+/// Find the level of a single entry in a roster, while reading the roster's
+/// canonical encoding in arbitrarily-sized chunks with bounded memory.
+///
+/// The encoding is checked to be canonical as it is read, except that, since
+/// no more than one previous entry is kept, only the sought `entry` is
+/// checked not to appear at more than one level.  Entries are compared by
+/// their encodings and are never decompressed: whether or not the other
+/// entries are valid commitments cannot change the level of a valid `entry`.
+///
+/// # Example
+///
+/// ```ignore
+/// let entry = issuer.verify_roster_membership(&credential)?;
+/// let mut stream = RosterMembershipStream::new(&entry);
+///
+/// for chunk in roster_chunks {
+///     stream.update(&chunk)?;
+/// }
+/// let level: Option<GroupMembershipLevel> = stream.finish()?;
+/// ```
+pub struct RosterMembershipStream {
+    entry: [u8; SIZEOF_COMMITTED_PHONE_NUMBER],
+    /// The partially read length prefix or entry.
+    buffer: [u8; SIZEOF_COMMITTED_PHONE_NUMBER],
+    buffered: usize,
+    /// The index of the list being read.
+    list: usize,
+    /// The number of entries left to read in the current list, or `None` if
+    /// its length prefix is still being read.
+    remaining: Option<usize>,
+    previous: Option<[u8; SIZEOF_COMMITTED_PHONE_NUMBER]>,
+    level: Option<GroupMembershipLevel>,
+}
+
+impl RosterMembershipStream {
+    /// Begin looking for `entry` in a roster.
+    pub fn new(entry: &CommittedPhoneNumber) -> RosterMembershipStream {
+        let mut bytes = [0u8; SIZEOF_COMMITTED_PHONE_NUMBER];
+
+        bytes.copy_from_slice(&entry.to_bytes());
+
+        RosterMembershipStream {
+            entry: bytes,
+            buffer: [0u8; SIZEOF_COMMITTED_PHONE_NUMBER],
+            buffered: 0,
+            list: 0,
+            remaining: None,
+            previous: None,
+            level: None,
+        }
+    }
+
+    /// Read the next `chunk` of the roster's encoding.
+    ///
+    /// # Errors
+    ///
+    /// * `RosterError::NonCanonicalEncoding` if a list is not strictly sorted,
+    ///   or if the sought entry appears at more than one level.
+    /// * `RosterError::WrongNumberOfBytes` if there are bytes after the end of
+    ///   the last list.
+    pub fn update(&mut self, chunk: &[u8]) -> Result<(), RosterError> {
+        let mut chunk: &[u8] = chunk;
+
+        while chunk.len() > 0 {
+            if self.list == NUMBER_OF_LISTS {
+                return Err(RosterError::WrongNumberOfBytes);
+            }
+            let wanted: usize = match self.remaining {
+                None    => SIZEOF_LENGTH,
+                Some(_) => SIZEOF_COMMITTED_PHONE_NUMBER,
+            };
+            let take: usize = cmp::min(wanted - self.buffered, chunk.len());
+
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&chunk[..take]);
+            self.buffered += take;
+            chunk = &chunk[take..];
+
+            if self.buffered < wanted {
+                break;
+            }
+            self.buffered = 0;
+
+            match self.remaining {
+                None            => self.remaining = Some(read_length(&self.buffer, 0)?),
+                Some(remaining) => {
+                    self.read_entry()?;
+                    self.remaining = Some(remaining - 1);
+                },
+            }
+
+            // Move on to the next list once this one is exhausted.
+            if self.remaining == Some(0) {
+                self.list += 1;
+                self.remaining = None;
+                self.previous = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_entry(&mut self) -> Result<(), RosterError> {
+        if let Some(ref previous) = self.previous {
+            if previous[..].cmp(&self.buffer[..]) != Ordering::Less {
+                return Err(RosterError::NonCanonicalEncoding);
+            }
+        }
+        if self.buffer == self.entry {
+            if self.level.is_some() {
+                return Err(RosterError::NonCanonicalEncoding);
+            }
+            self.level = Some(match self.list {
+                0 => GroupMembershipLevel::Owner,
+                1 => GroupMembershipLevel::Admin,
+                _ => GroupMembershipLevel::User,
+            });
+        }
+        self.previous = Some(self.buffer);
+
+        Ok(())
+    }
+
+    /// Finish reading the roster, returning the level of the sought entry, if
+    /// it was in the roster.
+    ///
+    /// # Errors
+    ///
+    /// * `RosterError::WrongNumberOfBytes` if the encoding ended part way
+    ///   through a list.
+    pub fn finish(self) -> Result<Option<GroupMembershipLevel>, RosterError> {
+        if self.list != NUMBER_OF_LISTS || self.buffered != 0 {
+            return Err(RosterError::WrongNumberOfBytes);
+        }
+
+        Ok(self.level)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use aeonflux::parameters::SystemParameters;
+
+    use phone_number::RosterEntryCommitment;
+
+    use rand::thread_rng;
+
+    fn entry(number: &[u8], system_parameters: &SystemParameters) -> CommittedPhoneNumber {
+        let mut csprng = thread_rng();
+
+        RosterEntryCommitment::create(number, system_parameters, &mut csprng).unwrap().commitment
+    }
+
+    fn roster(system_parameters: &SystemParameters) -> GroupMembershipRoster {
+        let mut roster = GroupMembershipRoster::new();
+
+        roster.insert(GroupMembershipLevel::Owner, entry(&[1, 4, 1, 5, 5, 5, 5, 6, 6, 6, 6], system_parameters)).unwrap();
+        roster.insert(GroupMembershipLevel::Admin, entry(&[1, 4, 1, 5, 5, 5, 5, 7, 7, 7, 7], system_parameters)).unwrap();
+        roster.insert(GroupMembershipLevel::User, entry(&[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4], system_parameters)).unwrap();
+        roster.insert(GroupMembershipLevel::User, entry(&[1, 4, 1, 5, 5, 5, 5, 4, 3, 2, 1], system_parameters)).unwrap();
+        roster
+    }
+
+    #[test]
+    fn roster_serialize_deserialize() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let roster = roster(&system_parameters);
+
+        let serialized = roster.to_bytes();
+        let deserialized = GroupMembershipRoster::from_bytes(&serialized).unwrap();
+
+        assert!(deserialized == roster);
+        assert!(deserialized.digest() == roster.digest());
+    }
+
+    #[test]
+    fn roster_encoding_is_canonical() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let mut roster = roster(&system_parameters);
+        let digest = roster.digest();
+
+        // Out of order members are still encoded canonically.
+        roster.users.reverse();
+
+        assert!(roster.digest() == digest);
+    }
+
+    #[test]
+    fn roster_deserialize_rejects_unsorted() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let roster = roster(&system_parameters);
+        let mut serialized = roster.to_bytes();
+
+        // Swap the two users in the encoding.
+        let users = serialized.len() - 2 * SIZEOF_COMMITTED_PHONE_NUMBER;
+        let mut first = [0u8; 32];
+
+        first.copy_from_slice(&serialized[users..users+32]);
+
+        for i in 0..32 {
+            serialized[users+i] = serialized[users+32+i];
+            serialized[users+32+i] = first[i];
+        }
+
+        assert!(GroupMembershipRoster::from_bytes(&serialized) == Err(RosterError::NonCanonicalEncoding));
+    }
+
+    #[test]
+    fn roster_deserialize_rejects_duplicate_levels() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let mut roster = roster(&system_parameters);
+        let owner = roster.owners[0];
+
+        roster.users.push(owner);
+
+        assert!(GroupMembershipRoster::from_bytes(&roster.to_bytes()) == Err(RosterError::NonCanonicalEncoding));
+    }
+
+    #[test]
+    fn roster_delta_roundtrip() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let old = roster(&system_parameters);
+        let mut new = old.clone();
+
+        // Promote a user, remove another, and add someone new.
+        let promoted = new.users[0];
+        let removed = new.users[1];
+
+        new.remove(&promoted).unwrap();
+        new.insert(GroupMembershipLevel::Admin, promoted).unwrap();
+        new.remove(&removed).unwrap();
+        new.insert(GroupMembershipLevel::User, entry(&[1, 4, 1, 5, 5, 5, 5, 0, 0, 0, 0], &system_parameters)).unwrap();
+
+        let delta = old.diff(&new);
+        let serialized = delta.to_bytes();
+        let deserialized = RosterDelta::from_bytes(&serialized).unwrap();
+
+        assert!(deserialized == delta);
+
+        let mut synced = old.clone();
+
+        synced.apply(&deserialized).unwrap();
+
+        assert!(synced == new);
+        assert!(synced.level_of(&promoted) == Some(GroupMembershipLevel::Admin));
+        assert!(synced.level_of(&removed).is_none());
+    }
+
+    #[test]
+    fn roster_delta_wrong_base() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let old = roster(&system_parameters);
+        let mut new = old.clone();
+
+        new.insert(GroupMembershipLevel::User, entry(&[1, 4, 1, 5, 5, 5, 5, 0, 0, 0, 0], &system_parameters)).unwrap();
+
+        let delta = old.diff(&new);
+        let mut stale = new.clone();
+
+        assert!(stale.apply(&delta) == Err(RosterError::BaseDigestMismatch));
+        assert!(stale == new);
+    }
+
+    fn stream(roster: &[u8], entry: &CommittedPhoneNumber, chunk_size: usize)
+        -> Result<Option<GroupMembershipLevel>, RosterError>
+    {
+        let mut stream = RosterMembershipStream::new(entry);
+
+        for chunk in roster.chunks(chunk_size) {
+            stream.update(chunk)?;
+        }
+        stream.finish()
+    }
+
+    #[test]
+    fn roster_membership_stream() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let roster = roster(&system_parameters);
+        let serialized = roster.to_bytes();
+        let outsider = entry(&[1, 4, 1, 5, 5, 5, 5, 0, 0, 0, 0], &system_parameters);
+
+        for chunk_size in [1, 7, 32, serialized.len()].iter() {
+            for member in roster.owners.iter().chain(roster.admins.iter()).chain(roster.users.iter()) {
+                assert!(stream(&serialized, member, *chunk_size).unwrap() == roster.level_of(member));
+            }
+            assert!(stream(&serialized, &outsider, *chunk_size).unwrap().is_none());
+        }
+
+        let truncated = &serialized[..serialized.len() - 1];
+        let mut trailing = serialized.clone();
+
+        trailing.push(0);
+
+        assert!(stream(truncated, &outsider, 7) == Err(RosterError::WrongNumberOfBytes));
+        assert!(stream(&trailing, &outsider, 7) == Err(RosterError::WrongNumberOfBytes));
+    }
+
+    #[test]
+    fn roster_membership_stream_rejects_duplicate_levels() {
+        let system_parameters = SystemParameters::hunt_and_peck(&mut thread_rng());
+        let mut roster = roster(&system_parameters);
+        let owner = roster.owners[0];
+
+        roster.users.push(owner);
+
+        assert!(stream(&roster.to_bytes(), &owner, 7) == Err(RosterError::NonCanonicalEncoding));
+    }
+}
@@ -12,8 +12,10 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+use aeonflux::credential::RevealedAttribute;
 use aeonflux::elgamal::{self};
 use aeonflux::errors::CredentialError;
+use aeonflux::errors::Error;
 use aeonflux::issuer::IssuerParameters;
 use aeonflux::nonces::Nonces;
 use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
@@ -29,6 +31,8 @@ use merlin::Transcript;
 use rand_core::RngCore;
 use rand_core::CryptoRng;
 
+use credential::SignalCredentialBlindIssuance;
+use credential::SignalCredentialBlindRequest;
 use credential::SignalCredentialIssuance;
 use credential::SignalCredentialPresentation;
 use credential::SignalCredential;
@@ -62,6 +66,8 @@ impl SignalUser {
 
 impl_serde_with_to_bytes_and_from_bytes!(SignalUser,
                                          "A valid byte sequence representing a SignalUser");
+#[cfg(feature = "cbor")]
+impl_cbor_with_to_bytes_and_from_bytes!(SignalUser);
 
 impl SignalUser {
     /// DOCDOC
@@ -70,7 +76,7 @@ impl SignalUser {
         issuer_parameters: IssuerParameters,
         key: Option<elgamal::Keypair>,
         phone_number: &[u8],
-    ) -> Result<SignalUser, CredentialError>
+    ) -> Result<SignalUser, Error>
     {
         let user = User::new(system_parameters, issuer_parameters, key);
 
@@ -87,7 +93,7 @@ impl SignalUser {
     pub fn obtain_finish(
         &mut self,
         issuance: Option<&SignalCredentialIssuance>,
-    ) -> Result<(), CredentialError>
+    ) -> Result<(), Error>
     {
         self.user.obtain_finish(issuance)
     }
@@ -100,13 +106,13 @@ impl SignalUser {
         &self,
         rng: &mut R,
         roster_entry_commitment: &RosterEntryCommitment,
-    ) -> Result<SignalCredentialPresentation, CredentialError>
+    ) -> Result<SignalCredentialPresentation, Error>
     where
         R: RngCore + CryptoRng,
     {
         let credential: &SignalCredential = match self.user.credential {
             Some(ref x) => x,
-            None        => return Err(CredentialError::MissingData),
+            None        => return Err(CredentialError::MissingData.into()),
         };
         let nonces = Nonces::new(rng, NUMBER_OF_ATTRIBUTES);
         let presentation = self.user.show(&nonces, rng)?;
@@ -118,8 +124,8 @@ impl SignalUser {
         let mut roster_membership_transcript = Transcript::new(b"SIGNAL GROUP MEMBERSHIP");
         let roster_membership_secrets = committed_values_equal::Secrets {
             m0: &credential.attributes[0],
-            z0: (&nonces[0]).into(),
-            z1: (&roster_entry_commitment.opening).into(),
+            z0: nonces[0].as_witness(),
+            z1: roster_entry_commitment.opening.as_witness(),
         };
         let roster_membership_publics = committed_values_equal::Publics {
             B: &self.user.system_parameters.g,
@@ -141,117 +147,39 @@ impl SignalUser {
 }
 
 impl SignalUser {
-    // /// Create a credential with some encrypted attributes and a non-interactive
-    // /// zero-knowledge proof of the correctness of the encrypted attributes, and
-    // /// send both along with the `SignalUser`'s `key` to the `SignalIssuer`.
-    // fn blind_obtain(
-    //     &self
-    // ) -> Result<SignalCredentialBlindRequest, CredentialError>
-    // {
-    //     let mut transcript = Transcript::new(b"SIGNAL BLIND ISSUANCE");
-    //     let mut csprng = transcript.fork_transcript().reseed_from_rng(&mut thread_rng());
-    // 
-    //     let key: elgamal::Keypair = self.key?;
-    //     let e0: elgamal::Ephemeral = Scalar::random(&mut csprng).into();
-    //     let e1: elgamal::Ephemeral = Scalar::random(&mut csprng).into();
-    // 
-    //     let phone_number: PhoneNumber = PhoneNumber::try_from_string(&self.phone_number)?;
-    //     let encrypted_phone_number: EncryptedPhoneNumber = phone_number.encrypt(&key, &e0, &e1);
-    //     let encrypted_attributes: Vec<EncryptedAttribute> = encrypted_phone_number.clone().into();
-    // 
-    //     // Create our roster entry.
-    //     let opening = Scalar::random(&mut csprng);
-    //     let commitment = CommittedPhoneNumber::from_phone_number(&phone_number,
-    //                                                              &opening,
-    //                                                              &self.user.system_parameters.g,
-    //                                                              &self.user.system_parameters.h);
-    //     let roster_key = GroupRosterKey([1u8; 32]); // XXX
-    //     self.roster_entry_opening = Some(roster_entry_opening);
-    //     self.roster_entry = Some(RosterEntry::new(&committed_phone_number,
-    //                                               &phone_number,
-    //                                               &self.roster_entry_opening,
-    //                                               &roster_key));
-    // 
-    //     let secrets: blind_attributes::Secrets = blind_attributes::Secrets {
-    //         d: &key.secret.clone().into(),
-    //         e0: &e0.0,
-    //         e1: &e1.0,
-    //         m0: &phone_number.number,
-    //         m1: &Scalar::from(phone_number.length as u64),
-    //         nonce: &self.roster_entry_opening?,
-    //     };
-    //     let publics: blind_attributes::Publics = blind_attributes::Publics {
-    //         B: &self.user.system_parameters.g,
-    //         A: &self.user.system_parameters.h,
-    //         D: &key.public.into(),
-    //         encrypted_attribute_0_0: &encrypted_phone_number.number.commitment,
-    //         encrypted_attribute_0_1: &encrypted_phone_number.number.encryption,
-    //         encrypted_attribute_1_0: &encrypted_phone_number.length.commitment,
-    //         encrypted_attribute_1_1: &encrypted_phone_number.length.encryption,
-    //         roster_entry: &roster_entry.into(),
-    //     };
-    // 
-    //     let proof = blind_attributes::Proof::create(&mut transcript, publics, secrets);
-    // 
-    //     Ok(SignalCredentialBlindRequest {
-    //         revealed_attributes: None,
-    //         encrypted_attributes: Some(encrypted_attributes),
-    //         blind_attributes_proof: Some(proof),
-    //         public_key: key.public,
-    //         roster_entry: roster_entry,
-    //     })
-    // }
-    // 
-    // /// DOCDOC
-    // fn blind_obtain_finish(&mut self, issuance: Option<SignalCredentialBlindIssuance>)
-    //     -> Result<(), CredentialError>
-    // {
-    //     let issue: SignalCredentialBlindIssuance = match issuance {
-    //         Some(i) => i,
-    //         None    => return Err(CredentialError::CredentialIssuance),
-    //     };
-    //     let key: elgamal::Keypair = self.key?;
-    // 
-    //     // Create a transcript.
-    //     let mut transcript = Transcript::new(b"SIGNAL BLIND ISSUE");
-    // 
-    //     let publics = blind_issuance::Publics {
-    //         B: &self.user.system_parameters.g,
-    //         A: &self.user.system_parameters.h,
-    //         X0: &self.user.issuer_parameters.Xn[0],
-    //         X1: &self.user.issuer_parameters.Xn[1],
-    //         X2: &self.user.issuer_parameters.Xn[2],
-    //         D: &key.public.into(),
-    //         P: &issue.blinding_commitment,
-    //         T0_0: &issue.auxiliary_commitments[0],
-    //         T0_1: &issue.auxiliary_commitments[0],
-    //         T1_0: &issue.auxiliary_commitments[1],
-    //         T1_1: &issue.auxiliary_commitments[1],
-    //         EQ_commitment: &issue.encrypted_mac.commitment,
-    //         EQ_encryption: &issue.encrypted_mac.encryption,
-    //         encrypted_attribute_0_0: &issue.encrypted_attributes[0].commitment,
-    //         encrypted_attribute_0_1: &issue.encrypted_attributes[1].encryption,
-    //         encrypted_attribute_1_0: &issue.encrypted_attributes[2].commitment,
-    //         encrypted_attribute_1_1: &issue.encrypted_attributes[3].encryption,
-    //     };
-    // 
-    //     // Verify that the credential was issued correctly, the encrypted aMAC was
-    //     // encrypted to the our's public key, the decryption of the encrypted aMAC was produced
-    //     // with the secret key corresponding to the issuer's known public key, and the system
-    //     // parameters are as expected.
-    //     if issue.proof.verify(&mut transcript, publics).is_err() {
-    //         return Err(CredentialError::CredentialIssuance);
-    //     }
-    // 
-    //     // Decrypt the Q part of the encrypted tag (P, Q') to produce the tag.
-    //     let decryption: RistrettoPoint = key.secret.decrypt(&issue.encrypted_mac);
-    //     let tag: amacs::Tag = amacs::Tag {
-    //         nonce: issue.blinding_commitment,
-    //         mac: decryption,
-    //     };
-    // 
-    //     unimplemented!()
-    // }
+    /// Create a request for a credential over an elGamal encryption of our
+    /// phone number, along with a non-interactive zero-knowledge proof of the
+    /// correctness of the encryption.
+    ///
+    /// If the `SignalUser` does not yet have an elGamal `key`, one is
+    /// generated.
+    pub fn blind_obtain<R>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<SignalCredentialBlindRequest, Error>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        attributes.push(self.phone_number.0);
+
+        self.user.blind_obtain(&attributes, rng)
+    }
+
+    /// Verify a blinded issuance and, if valid, decrypt and store the
+    /// resulting credential on our phone number.
+    pub fn blind_obtain_finish(
+        &mut self,
+        issuance: Option<&SignalCredentialBlindIssuance>,
+    ) -> Result<(), Error>
+    {
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        attributes.push(self.phone_number.0);
+
+        self.user.blind_obtain_finish(issuance, &attributes)
+    }
 }
 
 #[cfg(test)]
@@ -285,4 +213,30 @@ mod test {
 
         assert!(deserialized == alice);
     }
+
+    #[test]
+    fn signal_user_blind_obtain() {
+        let mut issuer_rng = thread_rng();
+        let mut alice_rng = thread_rng();
+
+        let system_parameters: SystemParameters = SystemParameters::from(H);
+        let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+        let issuer_parameters: IssuerParameters = issuer.get_issuer_parameters();
+        let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+        let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                    issuer_parameters,
+                                                    None,
+                                                    alice_phone_number_input).unwrap();
+
+        let request: SignalCredentialBlindRequest = alice.blind_obtain(&mut alice_rng).unwrap();
+
+        // The issuer only ever sees the encrypted phone number.
+        assert!(request.attributes_revealed.is_none());
+
+        let issuance: SignalCredentialBlindIssuance = issuer.blind_issue(&request, &mut issuer_rng).unwrap();
+
+        alice.blind_obtain_finish(Some(&issuance)).unwrap();
+
+        assert!(alice.user.credential.as_ref().unwrap().attributes[0] == alice.phone_number.0);
+    }
 }
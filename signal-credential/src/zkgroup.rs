@@ -0,0 +1,428 @@
+// -*- mode: rust; -*-
+//
+// This file is part of groupzk.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Encodings compatible with the wire formats of libsignal's zkgroup and the
+//! Groups protobufs which carry its values.
+//!
+//! The credentials here are not zkgroup's: a zkgroup verifier cannot check a
+//! `SignalCredentialPresentation`, nor can a member's `CommittedPhoneNumber`
+//! be decrypted as one of zkgroup's `UuidCiphertext`s.  What does carry over
+//! is the framing, so that tooling which stores, forwards, or diffs groups
+//! need not know which kind it holds:
+//!
+//! * zkgroup serialises each value as a reserved version byte, which must be
+//!   zero, followed by its fixed-size fields without length prefixes.  A
+//!   `CommittedPhoneNumber` is framed the same way by `frame`.
+//!
+//! * A `GroupId` is a 32-byte group identifier, exactly as in zkgroup, where
+//!   it also begins the group's public parameters.
+//!
+//! * A `GroupState` encodes as a `Group` message, with its epoch in the
+//!   `revision` field and each roster entry as a `Member` whose `userId` is
+//!   the framed commitment.
+//!
+//! The Groups protobufs have no owners, so owners are encoded as
+//! administrators, and decoding a group gives a roster without owners.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use errors::ZkGroupError;
+use group::GroupId;
+use group::GroupState;
+use group::SIZEOF_GROUP_ID;
+use phone_number::CommittedPhoneNumber;
+use phone_number::SIZEOF_COMMITTED_PHONE_NUMBER;
+use roster::GroupMembershipLevel;
+use roster::GroupMembershipRoster;
+
+/// The version byte with which zkgroup begins every serialised value.
+pub const ZKGROUP_RESERVED_BYTE: u8 = 0;
+
+/// The size of a framed `CommittedPhoneNumber`.
+pub const SIZEOF_ZKGROUP_USER_ID: usize = 1 + SIZEOF_COMMITTED_PHONE_NUMBER;
+
+/// The size of a framed `GroupId`.
+pub const SIZEOF_ZKGROUP_GROUP_KEY: usize = 1 + SIZEOF_GROUP_ID;
+
+/// The `Member.Role` of an ordinary member.
+pub const ROLE_DEFAULT: u64 = 1;
+
+/// The `Member.Role` of an administrator.
+pub const ROLE_ADMINISTRATOR: u64 = 2;
+
+// Field numbers from Groups.proto.
+const GROUP_PUBLIC_KEY: u64 = 1;
+const GROUP_REVISION: u64 = 6;
+const GROUP_MEMBERS: u64 = 7;
+const MEMBER_USER_ID: u64 = 1;
+const MEMBER_ROLE: u64 = 2;
+const MEMBER_JOINED_AT_REVISION: u64 = 5;
+
+// Protobuf wire types.
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LENGTH_DELIMITED: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+/// Prepend zkgroup's reserved version byte to some `bytes`.
+pub fn frame(bytes: &[u8]) -> Vec<u8> {
+    let mut v: Vec<u8> = Vec::with_capacity(1 + bytes.len());
+
+    v.push(ZKGROUP_RESERVED_BYTE);
+    v.extend(bytes.iter());
+    v
+}
+
+/// Strip zkgroup's reserved version byte from some `bytes`.
+///
+/// # Errors
+///
+/// `ZkGroupError::InvalidReservedByte` if `bytes` is empty or the version byte
+/// is not zero.
+pub fn unframe(bytes: &[u8]) -> Result<&[u8], ZkGroupError> {
+    match bytes.first() {
+        Some(&ZKGROUP_RESERVED_BYTE) => Ok(&bytes[1..]),
+        _                            => Err(ZkGroupError::InvalidReservedByte),
+    }
+}
+
+impl CommittedPhoneNumber {
+    /// Encode this commitment as a zkgroup-framed user ID.
+    pub fn to_zkgroup_bytes(&self) -> Vec<u8> {
+        frame(&self.to_bytes())
+    }
+
+    /// Decode a zkgroup-framed user ID.
+    pub fn from_zkgroup_bytes(bytes: &[u8]) -> Result<CommittedPhoneNumber, ZkGroupError> {
+        if bytes.len() != SIZEOF_ZKGROUP_USER_ID {
+            return Err(ZkGroupError::InvalidUserId);
+        }
+        CommittedPhoneNumber::from_bytes(unframe(bytes)?).or(Err(ZkGroupError::InvalidUserId))
+    }
+}
+
+impl GroupId {
+    /// Encode this ID as zkgroup encodes a group identifier.
+    pub fn to_zkgroup_bytes(&self) -> [u8; SIZEOF_GROUP_ID] {
+        self.0
+    }
+
+    /// Decode a zkgroup group identifier.
+    pub fn from_zkgroup_bytes(bytes: &[u8]) -> Result<GroupId, ZkGroupError> {
+        if bytes.len() != SIZEOF_GROUP_ID {
+            return Err(ZkGroupError::WrongNumberOfBytes);
+        }
+        let mut id = [0u8; SIZEOF_GROUP_ID];
+
+        id.copy_from_slice(bytes);
+
+        Ok(GroupId(id))
+    }
+}
+
+fn write_varint(v: &mut Vec<u8>, mut x: u64) {
+    while x >= 0x80 {
+        v.push((x as u8) | 0x80);
+        x >>= 7;
+    }
+    v.push(x as u8);
+}
+
+fn write_varint_field(v: &mut Vec<u8>, field: u64, x: u64) {
+    write_varint(v, (field << 3) | WIRE_VARINT as u64);
+    write_varint(v, x);
+}
+
+fn write_bytes_field(v: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(v, (field << 3) | WIRE_LENGTH_DELIMITED as u64);
+    write_varint(v, bytes.len() as u64);
+    v.extend(bytes.iter());
+}
+
+/// A single field of a protobuf message.
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    /// A fixed-width field, which no message here uses.
+    Fixed,
+}
+
+/// A reader of the fields of a protobuf message.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ZkGroupError> {
+        if self.bytes.len() - self.offset < length {
+            return Err(ZkGroupError::MalformedProtobuf);
+        }
+        let taken: &'a [u8] = &self.bytes[self.offset..self.offset + length];
+
+        self.offset += length;
+        Ok(taken)
+    }
+
+    fn varint(&mut self) -> Result<u64, ZkGroupError> {
+        let mut x: u64 = 0;
+
+        for i in 0..10 {
+            let byte: u8 = self.take(1)?[0];
+
+            x |= ((byte & 0x7f) as u64) << (7 * i);
+
+            if byte & 0x80 == 0 {
+                return Ok(x);
+            }
+        }
+        Err(ZkGroupError::MalformedProtobuf)
+    }
+
+    /// Read the next field number and its value, or `None` at the end of
+    /// the message.
+    fn next(&mut self) -> Result<Option<(u64, Field<'a>)>, ZkGroupError> {
+        if self.offset == self.bytes.len() {
+            return Ok(None);
+        }
+        let key: u64 = self.varint()?;
+        let field: u64 = key >> 3;
+
+        let value: Field<'a> = match (key & 7) as u8 {
+            WIRE_VARINT           => Field::Varint(self.varint()?),
+            WIRE_FIXED64          => { self.take(8)?; Field::Fixed },
+            WIRE_LENGTH_DELIMITED => {
+                let length: u64 = self.varint()?;
+
+                if length > (self.bytes.len() - self.offset) as u64 {
+                    return Err(ZkGroupError::MalformedProtobuf);
+                }
+                Field::Bytes(self.take(length as usize)?)
+            },
+            WIRE_FIXED32          => { self.take(4)?; Field::Fixed },
+            _                     => return Err(ZkGroupError::MalformedProtobuf),
+        };
+        Ok(Some((field, value)))
+    }
+}
+
+/// A member of a group, as in a `Member` message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ZkGroupMember {
+    pub entry: CommittedPhoneNumber,
+    pub level: GroupMembershipLevel,
+    /// The revision of the group in which this member joined.
+    pub joined_at_revision: u32,
+}
+
+impl ZkGroupMember {
+    /// Encode this member as a `Member` message.
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        let role: u64 = match self.level {
+            GroupMembershipLevel::Owner => ROLE_ADMINISTRATOR,
+            GroupMembershipLevel::Admin => ROLE_ADMINISTRATOR,
+            GroupMembershipLevel::User  => ROLE_DEFAULT,
+        };
+        let mut v: Vec<u8> = Vec::with_capacity(SIZEOF_ZKGROUP_USER_ID + 10);
+
+        write_bytes_field(&mut v, MEMBER_USER_ID, &self.entry.to_zkgroup_bytes());
+        write_varint_field(&mut v, MEMBER_ROLE, role);
+        if self.joined_at_revision != 0 {
+            write_varint_field(&mut v, MEMBER_JOINED_AT_REVISION, self.joined_at_revision as u64);
+        }
+        v
+    }
+
+    /// Decode a `Member` message, ignoring any fields not used here.
+    ///
+    /// # Errors
+    ///
+    /// * `ZkGroupError::InvalidUserId` if the `userId` is missing or is not a
+    ///   framed `CommittedPhoneNumber`, e.g. because it is a zkgroup
+    ///   `UuidCiphertext`.
+    /// * `ZkGroupError::UnknownRole` if the `role` is neither `DEFAULT` nor
+    ///   `ADMINISTRATOR`.
+    pub fn from_protobuf(bytes: &[u8]) -> Result<ZkGroupMember, ZkGroupError> {
+        let mut reader: Reader = Reader::new(bytes);
+        let mut entry: Option<CommittedPhoneNumber> = None;
+        let mut role: u64 = 0;
+        let mut joined_at_revision: u64 = 0;
+
+        while let Some((field, value)) = reader.next()? {
+            match (field, value) {
+                (MEMBER_USER_ID, Field::Bytes(x))             => entry = Some(CommittedPhoneNumber::from_zkgroup_bytes(x)?),
+                (MEMBER_ROLE, Field::Varint(x))               => role = x,
+                (MEMBER_JOINED_AT_REVISION, Field::Varint(x)) => joined_at_revision = x,
+                (MEMBER_USER_ID, _)
+                | (MEMBER_ROLE, _)
+                | (MEMBER_JOINED_AT_REVISION, _)              => return Err(ZkGroupError::MalformedProtobuf),
+                _                                             => (),
+            }
+        }
+
+        let level: GroupMembershipLevel = match role {
+            ROLE_DEFAULT       => GroupMembershipLevel::User,
+            ROLE_ADMINISTRATOR => GroupMembershipLevel::Admin,
+            _                  => return Err(ZkGroupError::UnknownRole),
+        };
+
+        Ok(ZkGroupMember {
+            entry: entry.ok_or(ZkGroupError::InvalidUserId)?,
+            level: level,
+            // A uint32 field is truncated from whatever varint was sent.
+            joined_at_revision: joined_at_revision as u32,
+        })
+    }
+}
+
+impl GroupState {
+    /// Encode this state as a `Group` message.
+    ///
+    /// # Errors
+    ///
+    /// `ZkGroupError::EpochOutOfRange` if the epoch does not fit in the
+    /// 32-bit `revision` field.
+    pub fn to_zkgroup_protobuf(&self) -> Result<Vec<u8>, ZkGroupError> {
+        if self.epoch > u32::max_value() as u64 {
+            return Err(ZkGroupError::EpochOutOfRange);
+        }
+        let mut v: Vec<u8> = Vec::new();
+
+        write_bytes_field(&mut v, GROUP_PUBLIC_KEY, &frame(&self.id.to_zkgroup_bytes()));
+        if self.epoch != 0 {
+            write_varint_field(&mut v, GROUP_REVISION, self.epoch);
+        }
+
+        for (level, list) in [(GroupMembershipLevel::Owner, &self.roster.owners),
+                              (GroupMembershipLevel::Admin, &self.roster.admins),
+                              (GroupMembershipLevel::User,  &self.roster.users)].iter()
+        {
+            for entry in list.iter() {
+                let member = ZkGroupMember { entry: *entry, level: *level, joined_at_revision: 0 };
+
+                write_bytes_field(&mut v, GROUP_MEMBERS, &member.to_protobuf());
+            }
+        }
+        Ok(v)
+    }
+
+    /// Decode a `Group` message, ignoring any fields not used here.
+    ///
+    /// # Errors
+    ///
+    /// * `ZkGroupError::WrongNumberOfBytes` if the `publicKey` is not a framed
+    ///   `GroupId`.
+    /// * `ZkGroupError::Roster` if any member appears twice.
+    /// * Any error from decoding a `Member`.
+    pub fn from_zkgroup_protobuf(bytes: &[u8]) -> Result<GroupState, ZkGroupError> {
+        let mut reader: Reader = Reader::new(bytes);
+        let mut id: Option<GroupId> = None;
+        let mut epoch: u64 = 0;
+        let mut roster: GroupMembershipRoster = GroupMembershipRoster::new();
+
+        while let Some((field, value)) = reader.next()? {
+            match (field, value) {
+                (GROUP_PUBLIC_KEY, Field::Bytes(x)) => {
+                    if x.len() != SIZEOF_ZKGROUP_GROUP_KEY {
+                        return Err(ZkGroupError::WrongNumberOfBytes);
+                    }
+                    id = Some(GroupId::from_zkgroup_bytes(unframe(x)?)?);
+                },
+                (GROUP_REVISION, Field::Varint(x))  => epoch = x as u32 as u64,
+                (GROUP_MEMBERS, Field::Bytes(x))    => {
+                    let member: ZkGroupMember = ZkGroupMember::from_protobuf(x)?;
+
+                    roster.insert(member.level, member.entry)?;
+                },
+                (GROUP_PUBLIC_KEY, _)
+                | (GROUP_REVISION, _)
+                | (GROUP_MEMBERS, _)                => return Err(ZkGroupError::MalformedProtobuf),
+                _                                   => (),
+            }
+        }
+
+        Ok(GroupState { id: id.ok_or(ZkGroupError::WrongNumberOfBytes)?, epoch, roster })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    fn entry(multiple: u64) -> CommittedPhoneNumber {
+        let point = RISTRETTO_BASEPOINT_POINT * Scalar::from(multiple);
+
+        CommittedPhoneNumber::from_bytes(point.compress().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn member_matches_groups_proto() {
+        let member = ZkGroupMember { entry: entry(1), level: GroupMembershipLevel::Admin, joined_at_revision: 300 };
+        let encoded: Vec<u8> = member.to_protobuf();
+
+        // Field 1 (userId), length 33, the reserved byte, and the commitment.
+        assert!(encoded[..3] == [0x0a, 33, 0x00]);
+        assert!(encoded[3..36] == entry(1).to_bytes()[..]);
+        // Field 2 (role) is ADMINISTRATOR, field 5 (joinedAtRevision) is 300.
+        assert!(encoded[36..] == [0x10, 0x02, 0x28, 0xac, 0x02]);
+        assert!(ZkGroupMember::from_protobuf(&encoded) == Ok(member));
+
+        // Unknown fields, such as field 3 (profileKey), are skipped.
+        let mut extended: Vec<u8> = encoded.clone();
+
+        extended.extend([0x1a, 0x02, 0xff, 0xff].iter());
+        assert!(ZkGroupMember::from_protobuf(&extended) == Ok(member));
+
+        // A zkgroup UuidCiphertext is 65 bytes, and is not mistaken for ours.
+        let mut uuid_ciphertext: Vec<u8> = Vec::new();
+
+        write_bytes_field(&mut uuid_ciphertext, MEMBER_USER_ID, &[0u8; 65]);
+        write_varint_field(&mut uuid_ciphertext, MEMBER_ROLE, ROLE_DEFAULT);
+        assert!(ZkGroupMember::from_protobuf(&uuid_ciphertext) == Err(ZkGroupError::InvalidUserId));
+        assert!(unframe(&[1, 0]) == Err(ZkGroupError::InvalidReservedByte));
+    }
+
+    #[test]
+    fn group_state_round_trips() {
+        let mut rng = thread_rng();
+        let mut roster = GroupMembershipRoster::new();
+
+        roster.insert(GroupMembershipLevel::Admin, entry(2)).unwrap();
+        roster.insert(GroupMembershipLevel::User, entry(3)).unwrap();
+
+        let state = GroupState { id: GroupId::random(&mut rng), epoch: 7, roster };
+        let encoded: Vec<u8> = state.to_zkgroup_protobuf().unwrap();
+
+        assert!(encoded[..3] == [0x0a, 33, 0x00]);
+        assert!(GroupState::from_zkgroup_protobuf(&encoded) == Ok(state.clone()));
+
+        let mut owned = state.clone();
+
+        owned.roster.insert(GroupMembershipLevel::Owner, entry(4)).unwrap();
+
+        let decoded = GroupState::from_zkgroup_protobuf(&owned.to_zkgroup_protobuf().unwrap()).unwrap();
+
+        assert!(decoded.roster.level_of(&entry(4)) == Some(GroupMembershipLevel::Admin));
+
+        owned.epoch = 1 << 32;
+        assert!(owned.to_zkgroup_protobuf() == Err(ZkGroupError::EpochOutOfRange));
+    }
+}
@@ -91,3 +91,43 @@ fn credential_issuance_and_presentation() {
     assert!(! roster_admins.contains(&server_copy_alice_roster_entry_commitment));
     assert!(roster_users.contains(&server_copy_alice_roster_entry_commitment));
 }
+
+#[test]
+fn blind_credential_issuance_and_presentation() {
+    let mut issuer_rng = thread_rng();
+    let mut alice_rng = thread_rng();
+
+    let system_parameters: SystemParameters = SystemParameters::hunt_and_peck(&mut issuer_rng);
+    let issuer: SignalIssuer = SignalIssuer::create(system_parameters, &mut issuer_rng);
+    let issuer_parameters: IssuerParameters = issuer.issuer.keypair.public.clone();
+
+    let alice_phone_number_input: &[u8] = &[1, 4, 1, 5, 5, 5, 5, 1, 2, 3, 4];
+    let mut alice: SignalUser = SignalUser::new(system_parameters,
+                                                issuer_parameters.clone(),
+                                                None, // a key is generated by blind_obtain()
+                                                alice_phone_number_input.clone()).unwrap();
+
+    // Alice asks for a credential without revealing her phone number.  (The
+    // issuer is assumed to have already checked out-of-band that she controls
+    // the number.)
+    let alice_request: SignalCredentialBlindRequest = alice.blind_obtain(&mut alice_rng).unwrap();
+    let alice_issuance: SignalCredentialBlindIssuance = issuer.blind_issue(&alice_request,
+                                                                           &mut issuer_rng).unwrap();
+
+    alice.blind_obtain_finish(Some(&alice_issuance)).unwrap();
+
+    let alice_entry: RosterEntryCommitment = RosterEntryCommitment::create(&alice_phone_number_input,
+                                                                           &system_parameters,
+                                                                           &mut alice_rng).unwrap();
+    let mut roster_users: Vec<CommittedPhoneNumber> = Vec::new();
+
+    roster_users.push(alice_entry.commitment);
+
+    let alice_presentation: SignalCredentialPresentation = alice.show(&mut alice_rng,
+                                                                      &alice_entry).unwrap();
+    let verified_credential: VerifiedSignalCredential = issuer.verify(alice_presentation).unwrap();
+    let user_proof = issuer.verify_roster_membership(&verified_credential);
+
+    assert!(user_proof.is_ok());
+    assert!(roster_users.contains(&user_proof.unwrap()));
+}
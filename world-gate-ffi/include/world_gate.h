@@ -0,0 +1,139 @@
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+#ifndef WORLD_GATE_H
+#define WORLD_GATE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define WG_LENGTH_SEED 32
+#define WG_LENGTH_ATTRIBUTE 32
+
+/**
+ * The result of every function in this API.  Outputs are only written when
+ * the result is `WG_OK`.
+ */
+// RUST_C_COUPLED: world-gate-ffi/src/lib.rs wg_status_t
+typedef int32_t wg_status_t;
+
+#define WG_OK                   0 /**< The call succeeded. */
+#define WG_ERROR_NULL_POINTER   1 /**< A required pointer was NULL. */
+#define WG_ERROR_INVALID_LENGTH 2 /**< A seed or attribute was not the correct length. */
+#define WG_ERROR_DECODE         3 /**< Some input bytes could not be decoded. */
+#define WG_ERROR_ISSUANCE       4 /**< Credential issuance failed. */
+#define WG_ERROR_PRESENTATION   5 /**< The user could not produce a presentation. */
+#define WG_ERROR_VERIFICATION   6 /**< A presentation did not verify. */
+#define WG_ERROR_PANIC          7 /**< An internal error.  This is a bug. */
+
+/**
+ * A buffer of bytes allocated by this library, which must be released with
+ * `wg_buffer_free()`.
+ */
+// RUST_C_COUPLED: world-gate-ffi/src/lib.rs wg_buffer_t
+typedef struct wg_buffer_s {
+  uint8_t* ptr; /**< The data. */
+  size_t len;   /**< The length of the data stored in `ptr`. */
+} wg_buffer_t;
+
+/** An opaque handle to a credential issuer. */
+typedef struct wg_issuer_s wg_issuer_t;
+
+/** An opaque handle to a credential user. */
+typedef struct wg_user_s wg_user_t;
+
+/** Release a buffer.  Passing a buffer with a NULL `ptr` does nothing. */
+void wg_buffer_free(wg_buffer_t buffer);
+
+/** A static, NUL-terminated description of a `status`. */
+const char* wg_status_message(wg_status_t status);
+
+/**
+ * Create some system parameters from a `WG_LENGTH_SEED`-byte `seed`.
+ */
+wg_status_t wg_system_parameters_create(const uint8_t* seed, size_t seed_length,
+                                        wg_buffer_t* out);
+
+/**
+ * Generate a new issuer keypair for the given `system_parameters`, and write
+ * a handle to it to `out`.  The handle must be destroyed with
+ * `wg_issuer_free()`.
+ */
+wg_status_t wg_issuer_create(const uint8_t* system_parameters, size_t system_parameters_length,
+                             const uint8_t* seed, size_t seed_length,
+                             wg_issuer_t** out);
+
+/** Load an issuer previously saved with `wg_issuer_to_bytes()`. */
+wg_status_t wg_issuer_from_bytes(const uint8_t* bytes, size_t length, wg_issuer_t** out);
+
+/** Save an issuer.  The output contains the issuer's secret key. */
+wg_status_t wg_issuer_to_bytes(const wg_issuer_t* issuer, wg_buffer_t* out);
+
+/** Get the issuer's public parameters, for distributing to users. */
+wg_status_t wg_issuer_parameters(const wg_issuer_t* issuer, wg_buffer_t* out);
+
+/** Issue a credential in response to a request from `wg_user_request()`. */
+wg_status_t wg_issuer_issue(const wg_issuer_t* issuer,
+                            const uint8_t* request, size_t request_length,
+                            const uint8_t* seed, size_t seed_length,
+                            wg_buffer_t* out);
+
+/**
+ * Verify a presentation from `wg_user_present()`.  On success, the verified
+ * credential is written to `out`.
+ */
+wg_status_t wg_issuer_verify(const wg_issuer_t* issuer,
+                             const uint8_t* presentation, size_t presentation_length,
+                             wg_buffer_t* out);
+
+/** Destroy an issuer.  Passing NULL does nothing. */
+void wg_issuer_free(wg_issuer_t* issuer);
+
+/**
+ * Create a user of the issuer with the given `issuer_parameters`, and write
+ * a handle to it to `out`.  The handle must be destroyed with
+ * `wg_user_free()`.
+ */
+wg_status_t wg_user_create(const uint8_t* system_parameters, size_t system_parameters_length,
+                           const uint8_t* issuer_parameters, size_t issuer_parameters_length,
+                           wg_user_t** out);
+
+/** Load a user previously saved with `wg_user_to_bytes()`. */
+wg_status_t wg_user_from_bytes(const uint8_t* bytes, size_t length, wg_user_t** out);
+
+/** Save a user, including any credential it holds. */
+wg_status_t wg_user_to_bytes(const wg_user_t* user, wg_buffer_t* out);
+
+/**
+ * Request a credential on a `WG_LENGTH_ATTRIBUTE`-byte attribute, which must
+ * be a canonically-encoded scalar.
+ */
+wg_status_t wg_user_request(const wg_user_t* user,
+                            const uint8_t* attribute, size_t attribute_length,
+                            wg_buffer_t* out);
+
+/** Check and store an issuance from `wg_issuer_issue()`. */
+wg_status_t wg_user_finish_issuance(wg_user_t* user,
+                                    const uint8_t* issuance, size_t issuance_length);
+
+/** Produce a fresh, unlinkable presentation of the user's credential. */
+wg_status_t wg_user_present(const wg_user_t* user,
+                            const uint8_t* seed, size_t seed_length,
+                            wg_buffer_t* out);
+
+/** Destroy a user.  Passing NULL does nothing. */
+void wg_user_free(wg_user_t* user);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // WORLD_GATE_H
@@ -0,0 +1,489 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A flat C API for aeonflux credentials.
+//!
+//! Long-lived state, i.e. issuers and users, lives behind opaque handles
+//! which are created and destroyed only by this library.  Everything else
+//! crosses the boundary as bytes: inputs are a pointer and a length, and
+//! outputs are written to a caller-provided `wg_buffer_t`, which the caller
+//! must later release with `wg_buffer_free()`.
+//!
+//! Every function returns a `wg_status_t`, which is `WG_OK` on success.  On
+//! failure, no output is written.  Panics are caught at the boundary and
+//! reported as `WG_ERROR_PANIC`.
+//!
+//! The header for this API is in `include/world_gate.h`.
+
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+extern crate aeonflux;
+extern crate curve25519_dalek;
+extern crate libc;
+extern crate rand;
+
+use std::convert::TryFrom;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::issuer::Issuer;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::nonces::Nonces;
+use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::user::User;
+
+use curve25519_dalek::scalar::Scalar;
+
+use libc::c_char;
+use libc::int32_t;
+use libc::size_t;
+use libc::uint8_t;
+
+use rand::ChaChaRng;
+use rand::SeedableRng;
+
+pub const LENGTH_SEED: usize = 32;
+pub const LENGTH_ATTRIBUTE: usize = 32;
+
+/// The result of every function in this API.
+pub type wg_status_t = int32_t;
+
+/// The call succeeded.
+pub const WG_OK: wg_status_t = 0;
+/// A required pointer was NULL.
+pub const WG_ERROR_NULL_POINTER: wg_status_t = 1;
+/// A seed or attribute was not the correct length.
+pub const WG_ERROR_INVALID_LENGTH: wg_status_t = 2;
+/// Some input bytes could not be decoded.
+pub const WG_ERROR_DECODE: wg_status_t = 3;
+/// The issuer could not issue, or the user could not accept, a credential.
+pub const WG_ERROR_ISSUANCE: wg_status_t = 4;
+/// The user could not produce a presentation, e.g. because it holds no
+/// credential.
+pub const WG_ERROR_PRESENTATION: wg_status_t = 5;
+/// A presentation did not verify.
+pub const WG_ERROR_VERIFICATION: wg_status_t = 6;
+/// The library panicked.  This is a bug.
+pub const WG_ERROR_PANIC: wg_status_t = 7;
+
+/// A buffer of bytes allocated by this library.
+#[repr(C)]
+pub struct wg_buffer_t {
+    pub ptr: *mut uint8_t,
+    pub len: size_t,
+}
+
+/// An opaque handle to a credential issuer.
+pub struct wg_issuer_t(Issuer);
+
+/// An opaque handle to a credential user.
+pub struct wg_user_t(User);
+
+/// Run `f`, converting its result, or any panic, into a status code.
+fn guard<F>(f: F) -> wg_status_t
+where
+    F: FnOnce() -> Result<(), wg_status_t>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(()))  => WG_OK,
+        Ok(Err(e))  => e,
+        Err(_)      => WG_ERROR_PANIC,
+    }
+}
+
+/// Borrow `len` bytes from `ptr`, which may only be NULL if `len` is zero.
+fn input<'a>(ptr: *const uint8_t, len: size_t) -> Result<&'a [u8], wg_status_t> {
+    if ptr.is_null() {
+        if len == 0 {
+            return Ok(&[]);
+        }
+        return Err(WG_ERROR_NULL_POINTER);
+    }
+    Ok(unsafe { slice::from_raw_parts(ptr, len) })
+}
+
+/// Hand ownership of `bytes` to the caller through `out`.
+fn output(bytes: Vec<u8>, out: *mut wg_buffer_t) -> Result<(), wg_status_t> {
+    if out.is_null() {
+        return Err(WG_ERROR_NULL_POINTER);
+    }
+
+    let boxed: Box<[u8]> = bytes.into_boxed_slice();
+    let len: size_t = boxed.len();
+    let ptr: *mut uint8_t = Box::into_raw(boxed) as *mut uint8_t;
+
+    unsafe { *out = wg_buffer_t { ptr, len }; }
+
+    Ok(())
+}
+
+/// Hand ownership of a new handle to the caller through `out`.
+fn output_handle<T>(handle: T, out: *mut *mut T) -> Result<(), wg_status_t> {
+    if out.is_null() {
+        return Err(WG_ERROR_NULL_POINTER);
+    }
+    unsafe { *out = Box::into_raw(Box::new(handle)); }
+
+    Ok(())
+}
+
+fn handle<'a, T>(ptr: *const T) -> Result<&'a T, wg_status_t> {
+    if ptr.is_null() {
+        return Err(WG_ERROR_NULL_POINTER);
+    }
+    Ok(unsafe { &*ptr })
+}
+
+fn handle_mut<'a, T>(ptr: *mut T) -> Result<&'a mut T, wg_status_t> {
+    if ptr.is_null() {
+        return Err(WG_ERROR_NULL_POINTER);
+    }
+    Ok(unsafe { &mut *ptr })
+}
+
+fn csprng(seed: *const uint8_t, seed_length: size_t) -> Result<ChaChaRng, wg_status_t> {
+    let bytes: &[u8] = input(seed, seed_length)?;
+
+    if bytes.len() != LENGTH_SEED {
+        return Err(WG_ERROR_INVALID_LENGTH);
+    }
+
+    let mut array = [0u8; LENGTH_SEED];
+
+    array.copy_from_slice(bytes);
+
+    Ok(ChaChaRng::from_seed(array))
+}
+
+macro_rules! decode {
+    ($t:tt, $ptr:expr, $len:expr) => {
+        $t::try_from(input($ptr, $len)?).map_err(|_| WG_ERROR_DECODE)
+    }
+}
+
+/// Release a buffer previously returned by this library.  Passing a buffer
+/// with a NULL `ptr` does nothing.
+#[no_mangle]
+pub extern "C" fn wg_buffer_free(buffer: wg_buffer_t) {
+    if buffer.ptr.is_null() {
+        return;
+    }
+    unsafe {
+        let _ = Box::from_raw(slice::from_raw_parts_mut(buffer.ptr, buffer.len));
+    }
+}
+
+/// A static, NUL-terminated description of a `status`.
+#[no_mangle]
+pub extern "C" fn wg_status_message(status: wg_status_t) -> *const c_char {
+    let message: &'static [u8] = match status {
+        WG_OK                   => b"ok\0",
+        WG_ERROR_NULL_POINTER   => b"a required pointer was NULL\0",
+        WG_ERROR_INVALID_LENGTH => b"an input was not the correct length\0",
+        WG_ERROR_DECODE         => b"an input could not be decoded\0",
+        WG_ERROR_ISSUANCE       => b"credential issuance failed\0",
+        WG_ERROR_PRESENTATION   => b"credential presentation failed\0",
+        WG_ERROR_VERIFICATION   => b"the presentation did not verify\0",
+        WG_ERROR_PANIC          => b"internal error\0",
+        _                       => b"unknown status\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// Create some `SystemParameters` from a `LENGTH_SEED`-byte `seed`.
+#[no_mangle]
+pub extern "C" fn wg_system_parameters_create(
+    seed: *const uint8_t,
+    seed_length: size_t,
+    out: *mut wg_buffer_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let mut rng = csprng(seed, seed_length)?;
+        let system_parameters = SystemParameters::hunt_and_peck(&mut rng);
+
+        output(system_parameters.to_bytes(), out)
+    })
+}
+
+/// Generate a new issuer keypair.
+#[no_mangle]
+pub extern "C" fn wg_issuer_create(
+    system_parameters: *const uint8_t,
+    system_parameters_length: size_t,
+    seed: *const uint8_t,
+    seed_length: size_t,
+    out: *mut *mut wg_issuer_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let system_parameters = decode!(SystemParameters, system_parameters, system_parameters_length)?;
+        let mut rng = csprng(seed, seed_length)?;
+
+        output_handle(wg_issuer_t(Issuer::create(system_parameters, &mut rng)), out)
+    })
+}
+
+/// Load an issuer previously saved with `wg_issuer_to_bytes()`.
+#[no_mangle]
+pub extern "C" fn wg_issuer_from_bytes(
+    bytes: *const uint8_t,
+    length: size_t,
+    out: *mut *mut wg_issuer_t,
+) -> wg_status_t
+{
+    guard(|| output_handle(wg_issuer_t(decode!(Issuer, bytes, length)?), out))
+}
+
+/// Save an issuer, including its secret key.
+#[no_mangle]
+pub extern "C" fn wg_issuer_to_bytes(issuer: *const wg_issuer_t, out: *mut wg_buffer_t) -> wg_status_t {
+    guard(|| output(handle(issuer)?.0.to_bytes(), out))
+}
+
+/// Get the issuer's public parameters, for distributing to users.
+#[no_mangle]
+pub extern "C" fn wg_issuer_parameters(issuer: *const wg_issuer_t, out: *mut wg_buffer_t) -> wg_status_t {
+    guard(|| output(handle(issuer)?.0.get_issuer_parameters().to_bytes(), out))
+}
+
+/// Issue a credential in response to a user's encoded `CredentialRequest`.
+#[no_mangle]
+pub extern "C" fn wg_issuer_issue(
+    issuer: *const wg_issuer_t,
+    request: *const uint8_t,
+    request_length: size_t,
+    seed: *const uint8_t,
+    seed_length: size_t,
+    out: *mut wg_buffer_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let issuer = handle(issuer)?;
+        let request = decode!(CredentialRequest, request, request_length)?;
+        let mut rng = csprng(seed, seed_length)?;
+        let issuance = issuer.0.issue(&request, &mut rng).map_err(|_| WG_ERROR_ISSUANCE)?;
+
+        output(issuance.to_bytes(), out)
+    })
+}
+
+/// Verify an encoded `CredentialPresentation`, writing the encoded
+/// `VerifiedCredential` to `out`.
+#[no_mangle]
+pub extern "C" fn wg_issuer_verify(
+    issuer: *const wg_issuer_t,
+    presentation: *const uint8_t,
+    presentation_length: size_t,
+    out: *mut wg_buffer_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let issuer = handle(issuer)?;
+        let presentation = decode!(CredentialPresentation, presentation, presentation_length)?;
+        let verified = issuer.0.verify(&presentation).map_err(|_| WG_ERROR_VERIFICATION)?;
+
+        output(verified.to_bytes(), out)
+    })
+}
+
+/// Destroy an issuer.  Passing NULL does nothing.
+#[no_mangle]
+pub extern "C" fn wg_issuer_free(issuer: *mut wg_issuer_t) {
+    if !issuer.is_null() {
+        unsafe { let _ = Box::from_raw(issuer); }
+    }
+}
+
+/// Create a user of the issuer with the given parameters.
+#[no_mangle]
+pub extern "C" fn wg_user_create(
+    system_parameters: *const uint8_t,
+    system_parameters_length: size_t,
+    issuer_parameters: *const uint8_t,
+    issuer_parameters_length: size_t,
+    out: *mut *mut wg_user_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let system_parameters = decode!(SystemParameters, system_parameters, system_parameters_length)?;
+        let issuer_parameters = decode!(IssuerParameters, issuer_parameters, issuer_parameters_length)?;
+
+        output_handle(wg_user_t(User::new(system_parameters, issuer_parameters, None)), out)
+    })
+}
+
+/// Load a user previously saved with `wg_user_to_bytes()`.
+#[no_mangle]
+pub extern "C" fn wg_user_from_bytes(
+    bytes: *const uint8_t,
+    length: size_t,
+    out: *mut *mut wg_user_t,
+) -> wg_status_t
+{
+    guard(|| output_handle(wg_user_t(decode!(User, bytes, length)?), out))
+}
+
+/// Save a user, including any credential it holds.
+#[no_mangle]
+pub extern "C" fn wg_user_to_bytes(user: *const wg_user_t, out: *mut wg_buffer_t) -> wg_status_t {
+    guard(|| output(handle(user)?.0.to_bytes(), out))
+}
+
+/// Request a credential on a `LENGTH_ATTRIBUTE`-byte, canonically-encoded
+/// scalar `attribute`.
+#[no_mangle]
+pub extern "C" fn wg_user_request(
+    user: *const wg_user_t,
+    attribute: *const uint8_t,
+    attribute_length: size_t,
+    out: *mut wg_buffer_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let user = handle(user)?;
+        let bytes: &[u8] = input(attribute, attribute_length)?;
+
+        if bytes.len() != LENGTH_ATTRIBUTE {
+            return Err(WG_ERROR_INVALID_LENGTH);
+        }
+
+        let mut array = [0u8; LENGTH_ATTRIBUTE];
+
+        array.copy_from_slice(bytes);
+
+        let scalar: RevealedAttribute = Scalar::from_canonical_bytes(array).ok_or(WG_ERROR_DECODE)?;
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(1);
+
+        attributes.push(scalar);
+
+        output(user.0.obtain(attributes).to_bytes(), out)
+    })
+}
+
+/// Check and store an encoded `CredentialIssuance` from the issuer.
+#[no_mangle]
+pub extern "C" fn wg_user_finish_issuance(
+    user: *mut wg_user_t,
+    issuance: *const uint8_t,
+    issuance_length: size_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let user = handle_mut(user)?;
+        let issuance = decode!(CredentialIssuance, issuance, issuance_length)?;
+
+        user.0.obtain_finish(Some(&issuance)).map_err(|_| WG_ERROR_ISSUANCE)
+    })
+}
+
+/// Produce a fresh, unlinkable `CredentialPresentation` of the user's
+/// credential.
+#[no_mangle]
+pub extern "C" fn wg_user_present(
+    user: *const wg_user_t,
+    seed: *const uint8_t,
+    seed_length: size_t,
+    out: *mut wg_buffer_t,
+) -> wg_status_t
+{
+    guard(|| {
+        let user = handle(user)?;
+        let mut rng = csprng(seed, seed_length)?;
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = user.0.show(&nonces, &mut rng).map_err(|_| WG_ERROR_PRESENTATION)?;
+
+        output(presentation.to_bytes(), out)
+    })
+}
+
+/// Destroy a user.  Passing NULL does nothing.
+#[no_mangle]
+pub extern "C" fn wg_user_free(user: *mut wg_user_t) {
+    if !user.is_null() {
+        unsafe { let _ = Box::from_raw(user); }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::ptr;
+
+    const SEED: [uint8_t; 32] = [  0,  1,  2,  3,  4,  5,  6,  7,
+                                   8,  9, 10, 11, 12, 13, 14, 15,
+                                  16, 17, 18, 19, 20, 21, 22, 23,
+                                  24, 25, 26, 27, 28, 29, 30, 31, ];
+
+    fn empty() -> wg_buffer_t {
+        wg_buffer_t { ptr: ptr::null_mut(), len: 0 }
+    }
+
+    fn bytes(buffer: &wg_buffer_t) -> &[u8] {
+        unsafe { slice::from_raw_parts(buffer.ptr, buffer.len) }
+    }
+
+    #[test]
+    fn issuance_presentation_and_verification() {
+        let mut system_parameters = empty();
+        let mut issuer: *mut wg_issuer_t = ptr::null_mut();
+        let mut issuer_parameters = empty();
+        let mut user: *mut wg_user_t = ptr::null_mut();
+        let mut request = empty();
+        let mut issuance = empty();
+        let mut presentation = empty();
+        let mut verified = empty();
+        let attribute = [7u8; LENGTH_ATTRIBUTE];
+
+        assert!(wg_system_parameters_create(SEED.as_ptr(), SEED.len(), &mut system_parameters) == WG_OK);
+        assert!(wg_issuer_create(system_parameters.ptr, system_parameters.len,
+                                 SEED.as_ptr(), SEED.len(), &mut issuer) == WG_OK);
+        assert!(wg_issuer_parameters(issuer, &mut issuer_parameters) == WG_OK);
+        assert!(wg_user_create(system_parameters.ptr, system_parameters.len,
+                               issuer_parameters.ptr, issuer_parameters.len, &mut user) == WG_OK);
+
+        // The user has no credential yet.
+        assert!(wg_user_present(user, SEED.as_ptr(), SEED.len(), &mut presentation) == WG_ERROR_PRESENTATION);
+
+        assert!(wg_user_request(user, attribute.as_ptr(), attribute.len(), &mut request) == WG_OK);
+        assert!(wg_issuer_issue(issuer, request.ptr, request.len, SEED.as_ptr(), SEED.len(), &mut issuance) == WG_OK);
+        assert!(wg_user_finish_issuance(user, issuance.ptr, issuance.len) == WG_OK);
+        assert!(wg_user_present(user, SEED.as_ptr(), SEED.len(), &mut presentation) == WG_OK);
+        assert!(wg_issuer_verify(issuer, presentation.ptr, presentation.len, &mut verified) == WG_OK);
+        assert!(bytes(&verified) == bytes(&presentation));
+
+        wg_buffer_free(system_parameters);
+        wg_buffer_free(issuer_parameters);
+        wg_buffer_free(request);
+        wg_buffer_free(issuance);
+        wg_buffer_free(presentation);
+        wg_buffer_free(verified);
+        wg_issuer_free(issuer);
+        wg_user_free(user);
+    }
+
+    #[test]
+    fn bad_inputs_are_reported() {
+        let mut out = empty();
+        let mut issuer: *mut wg_issuer_t = ptr::null_mut();
+
+        assert!(wg_system_parameters_create(SEED.as_ptr(), 31, &mut out) == WG_ERROR_INVALID_LENGTH);
+        assert!(wg_system_parameters_create(ptr::null(), 32, &mut out) == WG_ERROR_NULL_POINTER);
+        assert!(wg_issuer_from_bytes(SEED.as_ptr(), SEED.len(), &mut issuer) == WG_ERROR_DECODE);
+        assert!(wg_issuer_parameters(ptr::null(), &mut out) == WG_ERROR_NULL_POINTER);
+        assert!(out.ptr.is_null() && issuer.is_null());
+    }
+}
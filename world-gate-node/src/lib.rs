@@ -0,0 +1,288 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Node.js bindings for verifying aeonflux presentations and tickets, and for
+//! tracking and synchronising nullifiers between gateway processes.
+
+#![allow(non_snake_case)]
+
+#[macro_use]
+extern crate napi_derive;
+
+use std::fmt::Display;
+
+use napi::bindgen_prelude::Buffer;
+use napi::Error;
+use napi::Result;
+
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::issuer::Issuer;
+use aeonflux::nullifier_sync::GateId;
+use aeonflux::nullifier_sync::NullifierLedger;
+use aeonflux::nullifier_sync::SyncMessage;
+use aeonflux::parameter_chain::ParameterBundle;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::rate_limit::Nullifier;
+use aeonflux::rate_limit::NullifierStore as AeonfluxNullifierStore;
+use aeonflux::rng::default_rng;
+use aeonflux::self_check::Outcome;
+use aeonflux::ticket::CompactTicket;
+use aeonflux::ticket::TicketIssuer;
+use aeonflux::ticket::TicketVerifier as AeonfluxTicketVerifier;
+
+fn error<E: Display>(context: &'static str) -> impl FnOnce(E) -> Error {
+    move |e| Error::from_reason(format!("{}: {}", context, e))
+}
+
+/// JavaScript has no `u64`, so times, epochs, and identifiers arrive as
+/// `i64`s, which must not be negative.
+fn unsigned(x: i64) -> Result<u64> {
+    if x < 0 {
+        return Err(Error::from_reason("expected a non-negative integer"));
+    }
+    Ok(x as u64)
+}
+
+/// The buffers of any double-spent nullifiers found by merging `message`.
+fn merge(ledger: &mut NullifierLedger, message: &[u8]) -> Result<Vec<Buffer>> {
+    let message = SyncMessage::from_bytes(message).map_err(error("invalid sync message"))?;
+
+    Ok(ledger.merge(&message).iter().map(|d| Buffer::from(d.nullifier.to_bytes())).collect())
+}
+
+/// One stage of a verifier's self-check.
+#[napi(object)]
+pub struct SelfCheckStage {
+    pub stage: String,
+    /// One of `"passed"`, `"failed"`, or `"skipped"`.
+    pub outcome: String,
+    /// Why the stage failed, if it did.
+    pub error: Option<String>,
+}
+
+/// The outcome of a verifier's self-check.
+#[napi(object)]
+pub struct SelfCheckReport {
+    /// Whether every stage passed.
+    pub ok: bool,
+    pub stages: Vec<SelfCheckStage>,
+}
+
+/// Verifies credential presentations for a single issuer.
+#[napi]
+pub struct Verifier {
+    issuer: Issuer,
+}
+
+#[napi]
+impl Verifier {
+    /// Create a verifier from an encoded issuer, including its secret key.
+    #[napi(constructor)]
+    pub fn new(issuer: Buffer) -> Result<Verifier> {
+        Ok(Verifier { issuer: Issuer::from_bytes(&issuer).map_err(error("invalid issuer"))? })
+    }
+
+    /// Verify an encoded presentation, returning the encoded verified
+    /// credential.
+    #[napi]
+    pub fn verify(&self, presentation: Buffer) -> Result<Buffer> {
+        let presentation = CredentialPresentation::from_bytes(&presentation)
+            .map_err(error("invalid presentation"))?;
+        let verified = self.issuer.verify(&presentation).map_err(error("verification failed"))?;
+
+        Ok(Buffer::from(verified.to_bytes()))
+    }
+
+    /// Issue, present, and verify a credential with the loaded issuer, and,
+    /// given the encoded `ParameterBundle` holders fetch, check that it is
+    /// the issuer's and valid at time `now`.  Run this at startup, and refuse
+    /// traffic unless the report is `ok`.
+    #[napi]
+    pub fn self_check(&self, bundle: Option<Buffer>, now: Option<i64>) -> Result<SelfCheckReport> {
+        let mut rng = default_rng();
+        let report = match bundle {
+            Some(bundle) => {
+                let bundle = ParameterBundle::from_bytes(&bundle).map_err(error("invalid parameter bundle"))?;
+                let now = match now {
+                    Some(now) => unsigned(now)?,
+                    None      => return Err(Error::from_reason("checking a bundle requires the time")),
+                };
+
+                self.issuer.self_check_against(&bundle, now, &mut rng)
+            },
+            None => self.issuer.self_check(&mut rng),
+        };
+
+        Ok(SelfCheckReport {
+            ok: report.is_ok(),
+            stages: report.stages.iter().map(|&(stage, outcome)| {
+                let (outcome, error) = match outcome {
+                    Outcome::Passed    => ("passed", None),
+                    Outcome::Failed(e) => ("failed", Some(e.to_string())),
+                    Outcome::Skipped   => ("skipped", None),
+                };
+
+                SelfCheckStage { stage: stage.to_string(), outcome: outcome.to_string(), error }
+            }).collect(),
+        })
+    }
+}
+
+/// Adapts a `NullifierLedger` to the `NullifierStore` expected by aeonflux's
+/// `TicketVerifier`, by recording every insertion in a chosen epoch.
+struct LedgerStore {
+    ledger: NullifierLedger,
+    epoch: u64,
+}
+
+impl AeonfluxNullifierStore for LedgerStore {
+    /// Fails, and so rejects the ticket, if the nullifier was already spent
+    /// or the epoch has been closed.
+    fn insert(&mut self, nullifier: &Nullifier) -> bool {
+        self.ledger.spend(self.epoch, nullifier).is_ok()
+    }
+
+    fn contains(&self, nullifier: &Nullifier) -> bool {
+        self.ledger.contains(self.epoch, nullifier)
+    }
+
+    fn clear(&mut self) {
+        let epoch = self.epoch;
+
+        self.ledger.close(epoch);
+    }
+}
+
+/// Verifies one-show tickets for a single venue.
+#[napi]
+pub struct TicketVerifier {
+    verifier: AeonfluxTicketVerifier<LedgerStore>,
+}
+
+#[napi]
+impl TicketVerifier {
+    /// Create a ticket verifier from the ticket issuer's `system_parameters`
+    /// and 32-byte `master` secret, identifying this process to its peers as
+    /// `gate_id`.
+    #[napi(constructor)]
+    pub fn new(system_parameters: Buffer, master: Buffer, venue_id: Buffer, gate_id: i64) -> Result<TicketVerifier> {
+        let system_parameters = SystemParameters::from_bytes(&system_parameters)
+            .map_err(error("invalid system parameters"))?;
+
+        if master.len() != 32 {
+            return Err(Error::from_reason("the master secret must be 32 bytes"));
+        }
+
+        let mut secret = [0u8; 32];
+
+        secret.copy_from_slice(&master);
+
+        let store = LedgerStore { ledger: NullifierLedger::new(GateId(unsigned(gate_id)?)), epoch: 0 };
+        let issuer = TicketIssuer::new(system_parameters, secret);
+
+        Ok(TicketVerifier { verifier: AeonfluxTicketVerifier::new(issuer, &venue_id, store) })
+    }
+
+    /// Verify an encoded ticket presented at time `now`, recording it as used
+    /// in `epoch`.  Returns the ticket's nullifier.
+    #[napi]
+    pub fn verify(&mut self, ticket: Buffer, now: i64, epoch: i64) -> Result<Buffer> {
+        let ticket = CompactTicket::from_bytes(&ticket).map_err(error("invalid ticket"))?;
+        let epoch = unsigned(epoch)?;
+
+        if self.verifier.nullifiers().ledger.is_closed(epoch) {
+            return Err(Error::from_reason("the epoch has been closed"));
+        }
+        self.verifier.nullifiers_mut().epoch = epoch;
+        self.verifier.verify(&ticket, unsigned(now)?).map_err(error("ticket rejected"))?;
+
+        Ok(Buffer::from(ticket.nullifier().to_bytes()))
+    }
+
+    /// The current sequence number of the nullifier ledger.
+    #[napi]
+    pub fn sequence(&self) -> i64 {
+        self.verifier.nullifiers().ledger.sequence() as i64
+    }
+
+    /// An encoded sync message containing everything learned after `since`.
+    #[napi]
+    pub fn delta(&self, since: i64) -> Result<Buffer> {
+        Ok(Buffer::from(self.verifier.nullifiers().ledger.delta(unsigned(since)?).to_bytes()))
+    }
+
+    /// Merge a peer's encoded sync message, returning any tickets which were
+    /// used at more than one gate.
+    #[napi]
+    pub fn merge(&mut self, message: Buffer) -> Result<Vec<Buffer>> {
+        merge(&mut self.verifier.nullifiers_mut().ledger, &message)
+    }
+
+    /// Close an epoch, forgetting its used tickets.
+    #[napi]
+    pub fn close(&mut self, epoch: i64) -> Result<()> {
+        self.verifier.nullifiers_mut().ledger.close(unsigned(epoch)?);
+
+        Ok(())
+    }
+}
+
+/// An epoch-scoped store of spent nullifiers, which may be synchronised with
+/// other processes.
+#[napi]
+pub struct NullifierStore {
+    ledger: NullifierLedger,
+}
+
+#[napi]
+impl NullifierStore {
+    #[napi(constructor)]
+    pub fn new(gate_id: i64) -> Result<NullifierStore> {
+        Ok(NullifierStore { ledger: NullifierLedger::new(GateId(unsigned(gate_id)?)) })
+    }
+
+    /// Record an encoded nullifier as spent in `epoch`, throwing if it was
+    /// already spent or the epoch has been closed.
+    #[napi]
+    pub fn spend(&mut self, epoch: i64, nullifier: Buffer) -> Result<()> {
+        let nullifier = Nullifier::from_bytes(&nullifier).map_err(error("invalid nullifier"))?;
+
+        self.ledger.spend(unsigned(epoch)?, &nullifier).map_err(error("nullifier rejected"))
+    }
+
+    /// Whether an encoded nullifier was spent in `epoch`.
+    #[napi]
+    pub fn contains(&self, epoch: i64, nullifier: Buffer) -> Result<bool> {
+        let nullifier = Nullifier::from_bytes(&nullifier).map_err(error("invalid nullifier"))?;
+
+        Ok(self.ledger.contains(unsigned(epoch)?, &nullifier))
+    }
+
+    #[napi]
+    pub fn sequence(&self) -> i64 {
+        self.ledger.sequence() as i64
+    }
+
+    #[napi]
+    pub fn delta(&self, since: i64) -> Result<Buffer> {
+        Ok(Buffer::from(self.ledger.delta(unsigned(since)?).to_bytes()))
+    }
+
+    #[napi]
+    pub fn merge(&mut self, message: Buffer) -> Result<Vec<Buffer>> {
+        merge(&mut self.ledger, &message)
+    }
+
+    #[napi]
+    pub fn close(&mut self, epoch: i64) -> Result<()> {
+        self.ledger.close(unsigned(epoch)?);
+
+        Ok(())
+    }
+}
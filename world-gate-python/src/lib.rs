@@ -0,0 +1,172 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Python bindings for aeonflux credential issuers and verifiers.
+//!
+//! Every byte string crossing the boundary is the canonical encoding of the
+//! corresponding aeonflux type, and every failure is raised as a
+//! `WorldGateError`.
+
+#![allow(non_snake_case)]
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::CredentialRequest;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::issuer::Issuer;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::nonces::Nonces;
+use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::rng::default_rng;
+use aeonflux::user::User;
+
+use curve25519_dalek::scalar::Scalar;
+
+use sha2::Sha512;
+
+create_exception!(world_gate, WorldGateError, PyException);
+
+fn error<E: std::fmt::Debug>(context: &'static str) -> impl FnOnce(E) -> PyErr {
+    move |e| WorldGateError::new_err(format!("{}: {:?}", context, e))
+}
+
+fn bytes<'py>(py: Python<'py>, data: Vec<u8>) -> Bound<'py, PyBytes> {
+    PyBytes::new_bound(py, &data)
+}
+
+/// Generate new system parameters.
+#[pyfunction]
+fn system_parameters_create(py: Python<'_>) -> Bound<'_, PyBytes> {
+    bytes(py, SystemParameters::hunt_and_peck(&mut default_rng()).to_bytes())
+}
+
+/// Hash arbitrary bytes, such as an account identifier, to an attribute.
+#[pyfunction]
+fn hash_to_attribute<'py>(py: Python<'py>, input: &[u8]) -> Bound<'py, PyBytes> {
+    PyBytes::new_bound(py, Scalar::hash_from_bytes::<Sha512>(input).as_bytes())
+}
+
+/// A credential issuer, which also verifies presentations of its credentials.
+#[pyclass(name = "Issuer", module = "world_gate")]
+struct PyIssuer {
+    issuer: Issuer,
+}
+
+#[pymethods]
+impl PyIssuer {
+    /// Generate a new issuer keypair for the given system parameters.
+    #[staticmethod]
+    fn create(system_parameters: &[u8]) -> PyResult<PyIssuer> {
+        let system_parameters = SystemParameters::from_bytes(system_parameters)
+            .map_err(error("invalid system parameters"))?;
+
+        Ok(PyIssuer { issuer: Issuer::create(system_parameters, &mut default_rng()) })
+    }
+
+    /// Load an issuer saved with `to_bytes()`.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<PyIssuer> {
+        Ok(PyIssuer { issuer: Issuer::from_bytes(data).map_err(error("invalid issuer"))? })
+    }
+
+    /// Save this issuer, including its secret key.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        bytes(py, self.issuer.to_bytes())
+    }
+
+    /// The issuer's public parameters, for distributing to users.
+    fn parameters<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        bytes(py, self.issuer.get_issuer_parameters().to_bytes())
+    }
+
+    /// Issue a credential in response to an encoded credential request.
+    fn issue<'py>(&self, py: Python<'py>, request: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let request = CredentialRequest::from_bytes(request).map_err(error("invalid request"))?;
+        let issuance = self.issuer.issue(&request, &mut default_rng()).map_err(error("issuance failed"))?;
+
+        Ok(bytes(py, issuance.to_bytes()))
+    }
+
+    /// Verify an encoded presentation, returning the encoded verified
+    /// credential.
+    fn verify<'py>(&self, py: Python<'py>, presentation: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let presentation = CredentialPresentation::from_bytes(presentation)
+            .map_err(error("invalid presentation"))?;
+        let verified = self.issuer.verify(&presentation).map_err(error("verification failed"))?;
+
+        Ok(bytes(py, verified.to_bytes()))
+    }
+}
+
+/// A credential user, for playing the client side of the protocol in tests.
+#[pyclass(name = "User", module = "world_gate")]
+struct PyUser {
+    user: User,
+}
+
+#[pymethods]
+impl PyUser {
+    #[new]
+    fn new(system_parameters: &[u8], issuer_parameters: &[u8]) -> PyResult<PyUser> {
+        let system_parameters = SystemParameters::from_bytes(system_parameters)
+            .map_err(error("invalid system parameters"))?;
+        let issuer_parameters = IssuerParameters::from_bytes(issuer_parameters)
+            .map_err(error("invalid issuer parameters"))?;
+
+        Ok(PyUser { user: User::new(system_parameters, issuer_parameters, None) })
+    }
+
+    /// Request a credential on a canonically-encoded, 32-byte attribute.
+    fn request<'py>(&self, py: Python<'py>, attribute: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        let mut array = [0u8; 32];
+
+        if attribute.len() != array.len() {
+            return Err(WorldGateError::new_err("the attribute must be 32 bytes"));
+        }
+        array.copy_from_slice(attribute);
+
+        let scalar: RevealedAttribute = Scalar::from_canonical_bytes(array)
+            .ok_or_else(|| WorldGateError::new_err("the attribute is not a canonical scalar"))?;
+
+        Ok(bytes(py, self.user.obtain(vec![scalar]).to_bytes()))
+    }
+
+    /// Check and store an encoded issuance.
+    fn store(&mut self, issuance: &[u8]) -> PyResult<()> {
+        let issuance = CredentialIssuance::from_bytes(issuance).map_err(error("invalid issuance"))?;
+
+        self.user.obtain_finish(Some(&issuance)).map_err(error("the issuance did not verify"))
+    }
+
+    /// Produce a fresh presentation of the stored credential.
+    fn present<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let mut rng = default_rng();
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = self.user.show(&nonces, &mut rng).map_err(error("presentation failed"))?;
+
+        Ok(bytes(py, presentation.to_bytes()))
+    }
+}
+
+#[pymodule]
+fn world_gate(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("WorldGateError", m.py().get_type_bound::<WorldGateError>())?;
+    m.add_class::<PyIssuer>()?;
+    m.add_class::<PyUser>()?;
+    m.add_function(wrap_pyfunction!(system_parameters_create, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_to_attribute, m)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+import pytest
+
+import world_gate
+
+
+@pytest.fixture
+def issuer_and_user():
+    system_parameters = world_gate.system_parameters_create()
+    issuer = world_gate.Issuer.create(system_parameters)
+    user = world_gate.User(system_parameters, issuer.parameters())
+
+    return issuer, user
+
+
+def test_issuance_and_verification(issuer_and_user):
+    issuer, user = issuer_and_user
+
+    user.store(issuer.issue(user.request(world_gate.hash_to_attribute(b"alice"))))
+
+    first = user.present()
+    second = user.present()
+
+    assert first != second
+    assert issuer.verify(first) == first
+    assert issuer.verify(second) == second
+
+
+def test_issuer_round_trip(issuer_and_user):
+    issuer, user = issuer_and_user
+    restored = world_gate.Issuer.from_bytes(issuer.to_bytes())
+
+    user.store(restored.issue(user.request(world_gate.hash_to_attribute(b"bob"))))
+
+    assert issuer.verify(user.present())
+
+
+def test_errors_are_raised(issuer_and_user):
+    issuer, user = issuer_and_user
+
+    with pytest.raises(world_gate.WorldGateError):
+        user.present()
+    with pytest.raises(world_gate.WorldGateError):
+        issuer.verify(b"\x00" * 256)
+    with pytest.raises(world_gate.WorldGateError):
+        user.request(b"\xff" * 32)
@@ -0,0 +1,202 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! UniFFI bindings, for Swift and Kotlin, for the user side of aeonflux
+//! credentials.
+//!
+//! Everything which crosses the boundary is either an object defined here or
+//! the canonical byte encoding of an aeonflux type, so that mobile clients
+//! never need to know the wire formats.
+
+#![allow(non_snake_case)]
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::CredentialPresentation;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::nonces::Nonces;
+use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::rng::default_rng;
+use aeonflux::user::User;
+
+use curve25519_dalek::scalar::Scalar;
+
+use sha2::Sha512;
+
+uniffi::setup_scaffolding!();
+
+/// The length of an encoded attribute.
+pub const LENGTH_ATTRIBUTE: usize = 32;
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum WalletError {
+    #[error("the attribute was not a canonical 32-byte scalar")]
+    InvalidAttribute,
+    #[error("the issuance could not be decoded")]
+    InvalidIssuance,
+    #[error("the issuer parameters could not be decoded")]
+    InvalidIssuerParameters,
+    #[error("the presentation could not be decoded")]
+    InvalidPresentation,
+    #[error("the system parameters could not be decoded")]
+    InvalidSystemParameters,
+    #[error("the saved wallet could not be decoded")]
+    InvalidWallet,
+    #[error("the issuance did not verify")]
+    IssuanceFailed,
+    #[error("the wallet holds no credential")]
+    NoCredential,
+}
+
+/// Hash arbitrary `input`, such as an account identifier, to an attribute
+/// which may be passed to `Wallet::request()`.
+#[uniffi::export]
+pub fn hash_to_attribute(input: Vec<u8>) -> Vec<u8> {
+    Scalar::hash_from_bytes::<Sha512>(&input).to_bytes().to_vec()
+}
+
+/// A decoded credential presentation.
+#[derive(uniffi::Object)]
+pub struct Presentation {
+    presentation: CredentialPresentation,
+}
+
+#[uniffi::export]
+impl Presentation {
+    /// Decode, and check the encoding of, a presentation.
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Presentation>, WalletError> {
+        let presentation = CredentialPresentation::from_bytes(&bytes)
+            .map_err(|_| WalletError::InvalidPresentation)?;
+
+        Ok(Arc::new(Presentation { presentation }))
+    }
+
+    /// The canonical encoding of this presentation, for sending to a gate.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.presentation.to_bytes()
+    }
+}
+
+/// A user's credential, and the parameters of the issuer which issued it.
+#[derive(uniffi::Object)]
+pub struct Wallet {
+    user: Mutex<User>,
+}
+
+#[uniffi::export]
+impl Wallet {
+    /// Create an empty wallet for credentials from the issuer with the given
+    /// `issuer_parameters`.
+    #[uniffi::constructor]
+    pub fn new(system_parameters: Vec<u8>, issuer_parameters: Vec<u8>) -> Result<Arc<Wallet>, WalletError> {
+        let system_parameters = SystemParameters::from_bytes(&system_parameters)
+            .map_err(|_| WalletError::InvalidSystemParameters)?;
+        let issuer_parameters = IssuerParameters::from_bytes(&issuer_parameters)
+            .map_err(|_| WalletError::InvalidIssuerParameters)?;
+
+        Ok(Arc::new(Wallet { user: Mutex::new(User::new(system_parameters, issuer_parameters, None)) }))
+    }
+
+    /// Load a wallet previously saved with `to_bytes()`.
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Wallet>, WalletError> {
+        let user = User::from_bytes(&bytes).map_err(|_| WalletError::InvalidWallet)?;
+
+        Ok(Arc::new(Wallet { user: Mutex::new(user) }))
+    }
+
+    /// Save this wallet, including its credential.  The result should be kept
+    /// in the platform's secure storage.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.user.lock().unwrap().to_bytes()
+    }
+
+    /// Whether this wallet holds a credential.
+    pub fn has_credential(&self) -> bool {
+        self.user.lock().unwrap().credential.is_some()
+    }
+
+    /// Request a credential on an `attribute`, producing a request to send to
+    /// the issuer.
+    pub fn request(&self, attribute: Vec<u8>) -> Result<Vec<u8>, WalletError> {
+        if attribute.len() != LENGTH_ATTRIBUTE {
+            return Err(WalletError::InvalidAttribute);
+        }
+
+        let mut array = [0u8; LENGTH_ATTRIBUTE];
+
+        array.copy_from_slice(&attribute);
+
+        let scalar: RevealedAttribute = Scalar::from_canonical_bytes(array)
+            .ok_or(WalletError::InvalidAttribute)?;
+
+        Ok(self.user.lock().unwrap().obtain(vec![scalar]).to_bytes())
+    }
+
+    /// Check the issuer's response to a `request()`, and store the credential
+    /// it contains.
+    pub fn store(&self, issuance: Vec<u8>) -> Result<(), WalletError> {
+        let issuance = CredentialIssuance::from_bytes(&issuance).map_err(|_| WalletError::InvalidIssuance)?;
+
+        self.user.lock().unwrap().obtain_finish(Some(&issuance)).map_err(|_| WalletError::IssuanceFailed)
+    }
+
+    /// Produce a fresh, unlinkable presentation of the stored credential.
+    pub fn present(&self) -> Result<Arc<Presentation>, WalletError> {
+        let mut rng = default_rng();
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = self.user.lock().unwrap().show(&nonces, &mut rng)
+            .map_err(|_| WalletError::NoCredential)?;
+
+        Ok(Arc::new(Presentation { presentation }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use aeonflux::credential::CredentialRequest;
+    use aeonflux::issuer::Issuer;
+
+    use rand::thread_rng;
+
+    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+    #[test]
+    fn request_store_and_present() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let wallet = Wallet::new(system_parameters.to_bytes(),
+                                 issuer.get_issuer_parameters().to_bytes()).unwrap();
+
+        assert!(matches!(wallet.present(), Err(WalletError::NoCredential)));
+
+        let attribute = hash_to_attribute(b"+14155551234".to_vec());
+        let request = CredentialRequest::from_bytes(&wallet.request(attribute).unwrap()).unwrap();
+        let issuance = issuer.issue(&request, &mut rng).unwrap();
+
+        wallet.store(issuance.to_bytes()).unwrap();
+
+        let wallet = Wallet::from_bytes(wallet.to_bytes()).unwrap();
+        let bytes = wallet.present().unwrap().to_bytes();
+        let presentation = Presentation::from_bytes(bytes).unwrap();
+
+        assert!(issuer.verify(&presentation.presentation).is_ok());
+    }
+}
@@ -0,0 +1,171 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2018 Signal Foundation
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! WebAssembly bindings for the user side of aeonflux credentials.
+//!
+//! A `Wallet` holds a user's credential for a single issuer.  Every input and
+//! output is a `Uint8Array`, and every error is thrown as a string.  The
+//! wallet gathers its own randomness, via `crypto.getRandomValues()`.
+
+#![allow(non_snake_case)]
+
+extern crate aeonflux;
+extern crate curve25519_dalek;
+extern crate rand;
+extern crate wasm_bindgen;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsValue;
+
+use aeonflux::credential::CredentialIssuance;
+use aeonflux::credential::RevealedAttribute;
+use aeonflux::issuer::IssuerParameters;
+use aeonflux::nonces::Nonces;
+use aeonflux::parameters::NUMBER_OF_ATTRIBUTES;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::rng::default_rng;
+use aeonflux::user::User;
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand::ChaChaRng;
+use rand::RngCore;
+use rand::SeedableRng;
+
+pub const LENGTH_ATTRIBUTE: usize = 32;
+
+fn error(message: &'static str) -> JsValue {
+    JsValue::from_str(message)
+}
+
+/// Seed a CSPRNG from the platform, through aeonflux's default generator.
+fn csprng() -> Result<ChaChaRng, JsValue> {
+    let mut seed = [0u8; 32];
+
+    default_rng().try_fill_bytes(&mut seed).map_err(|_| error("no source of randomness is available"))?;
+
+    Ok(ChaChaRng::from_seed(seed))
+}
+
+/// A user's credential, and the parameters of the issuer which issued it.
+#[wasm_bindgen]
+pub struct Wallet {
+    user: User,
+}
+
+#[wasm_bindgen]
+impl Wallet {
+    /// Create an empty wallet for credentials from the issuer with the given
+    /// `issuerParameters`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(system_parameters: &[u8], issuer_parameters: &[u8]) -> Result<Wallet, JsValue> {
+        let system_parameters = SystemParameters::from_bytes(system_parameters)
+            .map_err(|_| error("invalid system parameters"))?;
+        let issuer_parameters = IssuerParameters::from_bytes(issuer_parameters)
+            .map_err(|_| error("invalid issuer parameters"))?;
+
+        Ok(Wallet { user: User::new(system_parameters, issuer_parameters, None) })
+    }
+
+    /// Load a wallet previously saved with `toBytes()`.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Wallet, JsValue> {
+        let user = User::from_bytes(bytes).map_err(|_| error("invalid wallet"))?;
+
+        Ok(Wallet { user })
+    }
+
+    /// Save this wallet, including its credential, for local storage.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.user.to_bytes()
+    }
+
+    /// Whether this wallet holds a credential.
+    #[wasm_bindgen(js_name = hasCredential)]
+    pub fn has_credential(&self) -> bool {
+        self.user.credential.is_some()
+    }
+
+    /// Request a credential on a 32-byte, canonically-encoded scalar
+    /// `attribute`, producing a request to send to the issuer.
+    pub fn request(&self, attribute: &[u8]) -> Result<Vec<u8>, JsValue> {
+        if attribute.len() != LENGTH_ATTRIBUTE {
+            return Err(error("the attribute must be 32 bytes"));
+        }
+
+        let mut array = [0u8; LENGTH_ATTRIBUTE];
+
+        array.copy_from_slice(attribute);
+
+        let scalar: RevealedAttribute = Scalar::from_canonical_bytes(array)
+            .ok_or_else(|| error("the attribute is not a canonical scalar"))?;
+        let mut attributes: Vec<RevealedAttribute> = Vec::with_capacity(1);
+
+        attributes.push(scalar);
+
+        Ok(self.user.obtain(attributes).to_bytes())
+    }
+
+    /// Check the issuer's response to a `request()`, and store the credential
+    /// it contains.
+    pub fn store(&mut self, issuance: &[u8]) -> Result<(), JsValue> {
+        let issuance = CredentialIssuance::from_bytes(issuance).map_err(|_| error("invalid issuance"))?;
+
+        self.user.obtain_finish(Some(&issuance)).map_err(|_| error("the issuance did not verify"))
+    }
+
+    /// Produce a fresh, unlinkable presentation of the stored credential.
+    pub fn present(&self) -> Result<Vec<u8>, JsValue> {
+        let mut rng = csprng()?;
+        let nonces = Nonces::new(&mut rng, NUMBER_OF_ATTRIBUTES);
+        let presentation = self.user.show(&nonces, &mut rng).map_err(|_| error("the wallet has no credential"))?;
+
+        Ok(presentation.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use aeonflux::credential::CredentialPresentation;
+    use aeonflux::credential::CredentialRequest;
+    use aeonflux::issuer::Issuer;
+
+    const H: [u8; 32] = [ 154, 189, 169, 176, 131,  12,  78, 199,
+                          127,   4, 178,  70, 212, 141, 119, 112,
+                          153, 154, 135,  11, 227, 132, 247,  47,
+                           68, 192,  72, 200,  23,  88,  51,  82, ];
+
+    #[test]
+    fn request_store_and_present() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::from(H);
+        let issuer = Issuer::create(system_parameters, &mut rng);
+        let mut wallet = Wallet::new(&system_parameters.to_bytes(),
+                                     &issuer.get_issuer_parameters().to_bytes()).unwrap();
+
+        assert!(!wallet.has_credential());
+
+        let request = CredentialRequest::from_bytes(&wallet.request(&[7u8; 32]).unwrap()).unwrap();
+        let issuance = issuer.issue(&request, &mut rng).unwrap();
+
+        wallet.store(&issuance.to_bytes()).unwrap();
+
+        // The wallet survives a round trip through local storage.
+        let wallet = Wallet::from_bytes(&wallet.to_bytes()).unwrap();
+        let presentation = CredentialPresentation::from_bytes(&wallet.present().unwrap()).unwrap();
+
+        assert!(wallet.has_credential());
+        assert!(issuer.verify(&presentation).is_ok());
+    }
+}